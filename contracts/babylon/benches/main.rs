@@ -41,20 +41,12 @@ pub fn get_main_msg_test_headers() -> Vec<BtcHeader> {
 #[track_caller]
 pub fn setup_instance() -> Instance<MockApi, MockStorage, MockQuerier> {
     let mut deps = mock_instance_with_gas_limit(WASM, 10_000_000_000_000);
-    let msg = InstantiateMsg {
-        network: babylon_bitcoin::chain_params::Network::Regtest,
-        babylon_tag: "01020304".to_string(),
-        consumer_name: None,
-        consumer_description: None,
-        btc_confirmation_depth: 10,
-        checkpoint_finalization_timeout: 1,
-        notify_cosmos_zone: false,
-        btc_staking_code_id: None,
-        btc_staking_msg: None,
-        btc_finality_code_id: None,
-        btc_finality_msg: None,
-        admin: None,
-    };
+    let msg = InstantiateMsg::new(
+        babylon_bitcoin::chain_params::Network::Regtest,
+        "01020304",
+        10,
+        100,
+    );
     let info = mock_info(CREATOR, &[]);
     let res: Response = instantiate(&mut deps, mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
@@ -1,19 +1,29 @@
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, QueryResponse, Reply,
-    Response, SubMsg, SubMsgResponse, WasmMsg,
+    to_json_binary, Addr, Binary, Deps, DepsMut, Env, Event, IbcMsg, MessageInfo, QueryResponse,
+    Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResponse, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw_utils::ParseReplyError;
 
+use babylon_apis::btc_pk::BtcPkHex;
+use babylon_apis::contract_version::ensure_upgrade;
+use babylon_apis::error::{ErrorCodesResponse, StakingApiError};
+use babylon_apis::state_hash::init_section_hashes;
 use babylon_apis::{btc_staking_api, finality_api};
 use babylon_bindings::BabylonMsg;
 
-use crate::error::ContractError;
-use crate::ibc::{ibc_packet, IBC_CHANNEL};
-use crate::msg::contract::{ContractMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::error::{ContractError, ERROR_CODES};
+use crate::ibc::{self, ibc_packet, DEAD_LETTERS, IBC_CHANNEL};
+use crate::msg::btc_header::BtcHeaderResponse;
+use crate::msg::contract::{
+    ContractMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, MigrationSummary, QueryMsg,
+};
 use crate::queries;
-use crate::state::btc_light_client;
 use crate::state::config::{Config, CONFIG};
+use crate::state::migration::{self, MIGRATIONS};
+use crate::state::state_hash::{self, SECTION_BTC_LIGHT_CLIENT};
+use crate::state::{btc_light_client, cz_header_chain, staking_router};
+use crate::utils::dry_run::DryRunStorage;
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -37,6 +47,8 @@ pub fn instantiate(
     let mut cfg = Config {
         network: msg.network.clone(),
         babylon_tag: msg.babylon_tag_to_bytes()?,
+        babylon_tag_end_height: None,
+        babylon_tags: msg.babylon_tags()?,
         btc_confirmation_depth: msg.btc_confirmation_depth,
         checkpoint_finalization_timeout: msg.checkpoint_finalization_timeout,
         notify_cosmos_zone: msg.notify_cosmos_zone,
@@ -44,11 +56,19 @@ pub fn instantiate(
         btc_finality: None, // Will be set in `reply` if `btc_finality_code_id` is provided
         consumer_name: None,
         consumer_description: None,
+        cz_header_storage: msg.cz_header_storage.unwrap_or_default(),
+        babylon_counterparty: msg.babylon_counterparty,
+        packet_timeout: msg.packet_timeout.unwrap_or_default(),
+        max_stored_headers: msg.max_stored_headers.unwrap_or_default(),
+        on_item_error: msg.on_item_error.unwrap_or_default(),
     };
 
     let mut res = Response::new().add_attribute("action", "instantiate");
 
-    if let Some(btc_staking_code_id) = msg.btc_staking_code_id {
+    // `validate` ensures the BTC staking code id and instantiation message are set together
+    if let (Some(btc_staking_code_id), Some(btc_staking_msg)) =
+        (msg.btc_staking_code_id, msg.btc_staking_msg)
+    {
         // Update config with consumer information
         cfg.consumer_name = msg.consumer_name;
         cfg.consumer_description = msg.consumer_description;
@@ -57,7 +77,7 @@ pub fn instantiate(
         let init_msg = WasmMsg::Instantiate {
             admin: msg.admin.clone(),
             code_id: btc_staking_code_id,
-            msg: msg.btc_staking_msg.unwrap_or(Binary::from(b"{}")),
+            msg: btc_staking_msg,
             funds: vec![],
             label: "BTC Staking".into(),
         };
@@ -94,18 +114,30 @@ pub fn instantiate(
     // Save the config after potentially updating it
     CONFIG.save(deps.storage, &cfg)?;
 
+    // Bootstrap the BTC light client from the trusted base header, if any
+    if let Some(base_header) = &msg.btc_base_header {
+        let headers = msg.btc_headers.unwrap_or_default();
+        btc_light_client::init_from_base(deps.storage, base_header, &headers)?;
+        res = res
+            .add_attribute("btc_base_height", base_header.height.to_string())
+            .add_attribute(
+                "btc_tip_height",
+                (base_header.height + headers.len() as u64).to_string(),
+            );
+    }
+
+    init_section_hashes(deps.storage)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(res)
 }
 
-pub fn reply(
-    deps: DepsMut,
-    _env: Env,
-    reply: Reply,
-) -> Result<Response<BabylonMsg>, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response<BabylonMsg>, ContractError> {
     match reply.id {
         REPLY_ID_INSTANTIATE_STAKING => reply_init_callback_staking(deps, reply.result.unwrap()),
         REPLY_ID_INSTANTIATE_FINALITY => reply_init_finality_callback(deps, reply.result.unwrap()),
+        ibc::REPLY_ID_BTC_STAKING => ibc_packet::reply_btc_staking(deps, reply),
+        ibc::REPLY_ID_IBC_HOOK => ibc_packet::reply_ibc_hook(deps, env, reply),
+        ibc::REPLY_ID_SEND_PACKET => ibc::reply_send_packet(deps, reply),
         _ => Err(ContractError::InvalidReplyId(reply.id)),
     }
 }
@@ -172,6 +204,10 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
         QueryMsg::Config {} => Ok(to_json_binary(&queries::config(deps)?)?),
         QueryMsg::BtcBaseHeader {} => Ok(to_json_binary(&queries::btc_base_header(deps)?)?),
         QueryMsg::BtcTipHeader {} => Ok(to_json_binary(&queries::btc_tip_header(deps)?)?),
+        QueryMsg::BtcHeaderRetention {} => {
+            Ok(to_json_binary(&queries::btc_header_retention(deps)?)?)
+        }
+        QueryMsg::BtcHeaderRelayers {} => Ok(to_json_binary(&queries::btc_header_relayers(deps)?)?),
         QueryMsg::BtcHeader { height } => Ok(to_json_binary(&queries::btc_header(deps, height)?)?),
         QueryMsg::BtcHeaderByHash { hash } => {
             Ok(to_json_binary(&queries::btc_header_by_hash(deps, &hash)?)?)
@@ -195,18 +231,156 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
         QueryMsg::BabylonCheckpoint { epoch_number } => Ok(to_json_binary(
             &queries::babylon_checkpoint(deps, epoch_number)?,
         )?),
+        QueryMsg::PendingEpochs {} => Ok(to_json_binary(&queries::pending_epochs(deps)?)?),
+        QueryMsg::ParseCheckpoint { tx_bytes } => Ok(to_json_binary(&queries::parse_checkpoint(
+            deps, &tx_bytes,
+        )?)?),
         QueryMsg::CzLastHeader {} => Ok(to_json_binary(&queries::cz_last_header(deps)?)?),
         QueryMsg::CzHeader { height } => Ok(to_json_binary(&queries::cz_header(deps, height)?)?),
+        QueryMsg::CzNextHeader { height } => {
+            Ok(to_json_binary(&queries::cz_next_header(deps, height)?)?)
+        }
+        QueryMsg::ErrorCodes {} => Ok(to_json_binary(&ErrorCodesResponse::from(ERROR_CODES))?),
+        QueryMsg::StateHash {} => Ok(to_json_binary(&queries::state_hash(deps)?)?),
+        QueryMsg::StakingRoutes {} => Ok(to_json_binary(&queries::staking_routes(deps)?)?),
+        QueryMsg::IbcChannel {} => Ok(to_json_binary(&IBC_CHANNEL.may_load(deps.storage)?)?),
+        QueryMsg::Channels {} => Ok(to_json_binary(&queries::channels(deps)?)?),
+        QueryMsg::ClosedChannels {} => Ok(to_json_binary(&queries::closed_channels(deps)?)?),
+        QueryMsg::PendingPackets {} => Ok(to_json_binary(&queries::pending_packets(deps)?)?),
+        QueryMsg::DeadLetter {
+            channel_id,
+            sequence,
+        } => Ok(to_json_binary(&queries::dead_letter(
+            deps, channel_id, sequence,
+        )?)?),
+        QueryMsg::PacketStats {} => Ok(to_json_binary(&queries::packet_stats(deps)?)?),
+        QueryMsg::MigrationProgress {} => Ok(to_json_binary(&queries::migration_progress(deps)?)?),
+        QueryMsg::PendingWork {} => Ok(to_json_binary(&queries::pending_work(deps)?)?),
+        QueryMsg::DeadLetters {
+            channel_id,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&queries::dead_letters(
+            deps,
+            channel_id,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::IbcHookFailures {
+            channel_id,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&queries::ibc_hook_failures(
+            deps,
+            channel_id,
+            start_after,
+            limit,
+        )?)?),
+    }
+}
+
+/// apply_migration applies the given migration to the storage.
+/// It returns the migration response, along with the names of the scheduled storage migrations
+fn apply_migration(
+    storage: &mut dyn Storage,
+    msg: &MigrateMsg,
+) -> Result<(Response<BabylonMsg>, Vec<&'static str>), ContractError> {
+    let from_version = ensure_upgrade(storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let mut res = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.unwrap_or_default())
+        .add_attribute("to_version", CONTRACT_VERSION);
+    if let Some(mode) = msg.cz_header_storage {
+        let pruned = cz_header_chain::set_cz_header_storage(storage, mode)?;
+        res = res
+            .add_attribute("cz_header_storage", format!("{mode:?}"))
+            .add_attribute("pruned_cz_headers", pruned.to_string());
+    }
+    if msg.babylon_tags.is_some()
+        || msg.babylon_counterparty.is_some()
+        || msg.packet_timeout.is_some()
+        || msg.max_stored_headers.is_some()
+        || msg.on_item_error.is_some()
+    {
+        let mut cfg = CONFIG.load(storage)?;
+        if let Some(tags) = &msg.babylon_tags {
+            cfg.babylon_tag_end_height = tags.babylon_tag_end_height;
+            cfg.babylon_tags = tags.to_babylon_tags()?;
+            res = res
+                .add_attribute(
+                    "babylon_tag_end_height",
+                    tags.babylon_tag_end_height
+                        .map_or("none".to_string(), |h| h.to_string()),
+                )
+                .add_attribute("babylon_tags", cfg.babylon_tags.len().to_string());
+        }
+        if let Some(counterparty) = &msg.babylon_counterparty {
+            res = res
+                .add_attribute("babylon_connection_id", &counterparty.connection_id)
+                .add_attribute("babylon_port_id", &counterparty.port_id);
+            cfg.babylon_counterparty = Some(counterparty.clone());
+        }
+        if let Some(packet_timeout) = &msg.packet_timeout {
+            res = res
+                .add_attribute(
+                    "packet_timeout_seconds",
+                    packet_timeout.timeout_seconds.to_string(),
+                )
+                .add_attribute(
+                    "packet_max_resubmissions",
+                    packet_timeout.max_resubmissions.to_string(),
+                );
+            cfg.packet_timeout = packet_timeout.clone();
+        }
+        if let Some(max_stored_headers) = msg.max_stored_headers {
+            res = res.add_attribute("max_stored_headers", max_stored_headers.to_string());
+            cfg.max_stored_headers = max_stored_headers;
+        }
+        if let Some(on_item_error) = msg.on_item_error {
+            res = res.add_attribute("on_item_error", format!("{on_item_error:?}"));
+            cfg.on_item_error = on_item_error;
+        }
+        CONFIG.save(storage, &cfg)?;
+    }
+    if let Some(connection_id) = ibc::migrate_reopen_lock(storage)? {
+        res = res.add_attribute("reopen_locked_connection", connection_id);
     }
+    state_hash::remove_legacy_section_hashes(storage);
+    let scheduled = migration::schedule_migrations(storage, MIGRATIONS)?;
+    if !scheduled.is_empty() {
+        res = res.add_attribute("scheduled_migrations", scheduled.join(","));
+    }
+    Ok((res, scheduled))
 }
 
-/// this is a no-op just to test how this integrates with wasmd
+/// migrate migrates the contract to this version of its code, refusing downgrades.
+/// It optionally switches the storage mode of CZ headers, and sets the babylon tags, the expected
+/// counterparty of the zoneconcierge channel, the timeout policy of outbound packets, the BTC
+/// header retention and the handling of failed BTC staking operations.
+/// Upon `dry_run`, the migration is only rehearsed, without changing any state
 pub fn migrate(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
-    _msg: Empty,
+    msg: MigrateMsg,
 ) -> Result<Response<BabylonMsg>, ContractError> {
-    Ok(Response::default())
+    if !msg.dry_run {
+        return Ok(apply_migration(deps.storage, &msg)?.0);
+    }
+
+    // Rehearse the migration over a storage overlay, whose writes are discarded
+    let mut storage = DryRunStorage::new(deps.storage);
+    let (res, scheduled) = apply_migration(&mut storage, &msg)?;
+    let (written_keys, removed_keys) = storage.changes();
+    let summary = MigrationSummary {
+        stored_version: get_contract_version(deps.storage).ok().map(|v| v.version),
+        code_version: CONTRACT_VERSION.to_string(),
+        written_keys,
+        removed_keys,
+        scheduled_migrations: scheduled.iter().map(|name| name.to_string()).collect(),
+    };
+    Ok(res
+        .add_attribute("dry_run", "true")
+        .set_data(to_json_binary(&summary)?))
 }
 
 pub fn execute(
@@ -219,47 +393,43 @@ pub fn execute(
         ExecuteMsg::BtcHeaders {
             headers: btc_headers,
         } => {
-            if btc_light_client::is_initialized(deps.storage) {
-                btc_light_client::handle_btc_headers_from_user(deps.storage, &btc_headers)?;
-            } else {
-                btc_light_client::init_from_user(deps.storage, &btc_headers)?;
+            if !btc_light_client::is_relayer(deps.storage, &info.sender) {
+                return Err(ContractError::Unauthorized {});
             }
-            // TODO: Add events
-            Ok(Response::new())
+            migration::ensure_sections_active(deps.storage, &[SECTION_BTC_LIGHT_CLIENT])?;
+            let inserted = if btc_light_client::is_initialized(deps.storage) {
+                btc_light_client::handle_btc_headers_from_user(deps.storage, &btc_headers)?
+            } else {
+                btc_light_client::init_from_user(deps.storage, &btc_headers)?
+            };
+            let events = inserted
+                .iter()
+                .map(|header| {
+                    Ok(Event::new("btc_header_inserted")
+                        .add_attribute("height", header.height.to_string())
+                        .add_attribute("hash", BtcHeaderResponse::try_from(header)?.hash)
+                        .add_attribute("relayer", info.sender.as_str()))
+                })
+                .collect::<Result<Vec<_>, ContractError>>()?;
+            Ok(Response::new().add_events(events))
         }
         ExecuteMsg::Slashing { evidence } => {
             // This is an internal routing message from the `btc_finality` contract
             let cfg = CONFIG.load(deps.storage)?;
-            // Check sender
-            let btc_finality = cfg
-                .btc_finality
-                .ok_or(ContractError::BtcFinalityNotSet {})?;
-            if info.sender != btc_finality {
-                return Err(ContractError::Unauthorized {});
-            }
-            // Send to the staking contract for processing
+            ensure_btc_finality(&cfg, &info)?;
+            // Send to the staking contract of the finality provider's consumer for processing
             let mut res = Response::new();
-            let btc_staking = cfg.btc_staking.ok_or(ContractError::BtcStakingNotSet {})?;
-            // Slashes this finality provider, i.e., sets its slashing height to the block height
-            // and its power to zero
-            let msg = btc_staking_api::ExecuteMsg::Slash {
-                fp_btc_pk_hex: hex::encode(evidence.fp_btc_pk.clone()),
-            };
-            let wasm_msg = WasmMsg::Execute {
-                contract_addr: btc_staking.to_string(),
-                msg: to_json_binary(&msg)?,
-                funds: vec![],
-            };
+            let wasm_msg = slash_fp_msg(deps.storage, &cfg, &hex::encode(&evidence.fp_btc_pk))?;
             res = res.add_message(wasm_msg);
 
             // Send over IBC to the Provider (Babylon)
             let channel = IBC_CHANNEL.load(deps.storage)?;
-            let ibc_msg = ibc_packet::slashing_msg(&env, &channel, &evidence)?;
+            let ibc_msg = ibc_packet::slashing_msg(deps.storage, &env, &channel, &evidence)?;
             // Send packet only if we are IBC enabled
             // TODO: send in test code when multi-test can handle it
             #[cfg(not(any(test, feature = "library")))]
             {
-                res = res.add_message(ibc_msg);
+                res = res.add_submessage(ibc_msg);
             }
             #[cfg(any(test, feature = "library"))]
             {
@@ -269,18 +439,256 @@ pub fn execute(
             // TODO: Add events
             Ok(res)
         }
+        ExecuteMsg::SlashFinalityProvider { fp_btc_pk_hex } => {
+            // This is an internal routing message from the `btc_finality` contract
+            let cfg = CONFIG.load(deps.storage)?;
+            ensure_btc_finality(&cfg, &info)?;
+            let wasm_msg = slash_fp_msg(deps.storage, &cfg, &fp_btc_pk_hex)?;
+            Ok(Response::new().add_message(wasm_msg))
+        }
+        ExecuteMsg::JailFinalityProvider { fp_btc_pk_hex } => {
+            // This is an internal routing message from the `btc_finality` contract
+            let cfg = CONFIG.load(deps.storage)?;
+            ensure_btc_finality(&cfg, &info)?;
+            let wasm_msg = jail_fp_msg(deps.storage, &cfg, &fp_btc_pk_hex)?;
+            Ok(Response::new().add_message(wasm_msg))
+        }
+        ExecuteMsg::ResetBaseHeader { header_info } => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            migration::ensure_sections_active(deps.storage, &[SECTION_BTC_LIGHT_CLIENT])?;
+            let prev = btc_light_client::reset_base_header(deps.storage, &header_info)?;
+            let base_header = btc_light_client::get_base_header(deps.storage)?;
+            let mut event = Event::new("btc_base_header_reset")
+                .add_attribute("base_height", header_info.height.to_string())
+                .add_attribute("base_hash", BtcHeaderResponse::try_from(&base_header)?.hash);
+            if let Some((prev_base, prev_tip)) = prev {
+                event = event
+                    .add_attribute("prev_base_height", prev_base.height.to_string())
+                    .add_attribute("prev_tip_height", prev_tip.height.to_string());
+            }
+            Ok(Response::new().add_event(event))
+        }
+        ExecuteMsg::SetStakingRoute {
+            consumer_id,
+            contract,
+        } => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            let contract = contract
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?;
+            staking_router::set_staking_route(deps.storage, &consumer_id, contract.as_ref())?;
+            Ok(Response::new()
+                .add_attribute("action", "set_staking_route")
+                .add_attribute("consumer_id", consumer_id)
+                .add_attribute(
+                    "contract",
+                    contract.map(|c| c.to_string()).unwrap_or_default(),
+                ))
+        }
+        ExecuteMsg::ReprocessPacket {
+            channel_id,
+            sequence,
+        } => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            let dead_letter = DEAD_LETTERS.load(deps.storage, (&channel_id, sequence))?;
+            DEAD_LETTERS.remove(deps.storage, (&channel_id, sequence));
+            // Processing failing again reverts the whole execution, and keeps the dead letter.
+            // In `Abort` mode, a failed btc-staking operation reverts it through its reply
+            let resp = ibc::process_packet(deps, channel_id.clone(), sequence, &dead_letter.data)?;
+            let event = Event::new("ibc_packet_reprocessed")
+                .add_attribute("channel_id", channel_id)
+                .add_attribute("sequence", sequence.to_string())
+                .add_attribute("packet_hash", hex::encode(&dead_letter.packet_hash));
+            Ok(Response::new()
+                .add_submessages(resp.messages)
+                .add_attributes(resp.attributes)
+                .add_events(resp.events)
+                .add_event(event))
+        }
+        ExecuteMsg::CheckChannels {} => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            let stale = ibc::check_channels(deps.storage, &env)?;
+            let mut res = Response::new().add_attribute("action", "check_channels");
+            for channel in stale {
+                let event = Event::new("ibc_channel_stale")
+                    .add_attribute("channel_id", &channel.channel.endpoint.channel_id)
+                    .add_attribute(
+                        "last_active_time",
+                        channel
+                            .last_active_time
+                            .unwrap_or_default()
+                            .seconds()
+                            .to_string(),
+                    );
+                res = res.add_event(event).add_message(IbcMsg::CloseChannel {
+                    channel_id: channel.channel.endpoint.channel_id,
+                });
+            }
+            Ok(res)
+        }
+        ExecuteMsg::AllowChannelReopen { connection_id } => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            let locked = ibc::allow_channel_reopen(deps.storage, &connection_id)?;
+            Ok(Response::new()
+                .add_attribute("action", "allow_channel_reopen")
+                .add_attribute("connection_id", connection_id)
+                .add_attribute("was_locked", locked.to_string()))
+        }
+        ExecuteMsg::UpdateChannel {
+            channel_id,
+            counterparty_chain_id,
+            consumer_id,
+        } => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            let channel = ibc::update_channel(
+                deps.storage,
+                &channel_id,
+                counterparty_chain_id,
+                consumer_id,
+            )?;
+            Ok(Response::new()
+                .add_attribute("action", "update_channel")
+                .add_attribute("channel_id", channel_id)
+                .add_attribute("consumer_id", channel.consumer_id.unwrap_or_default()))
+        }
+        ExecuteMsg::ContinueMigration { limit } => {
+            let progress = migration::continue_migration(deps.storage, MIGRATIONS, limit as usize)?;
+            let mut event = Event::new("migration_progress");
+            event = match progress {
+                Some(progress) => event
+                    .add_attribute("name", progress.name)
+                    .add_attribute("section", progress.section)
+                    .add_attribute("migrated", progress.migrated.to_string())
+                    .add_attribute("complete", progress.complete.to_string()),
+                None => event.add_attribute("pending", "false"),
+            };
+            Ok(Response::new().add_event(event))
+        }
+        ExecuteMsg::PruneHeaders { retain, limit } => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            migration::ensure_sections_active(deps.storage, &[SECTION_BTC_LIGHT_CLIENT])?;
+            let retain = retain.unwrap_or(CONFIG.load(deps.storage)?.max_stored_headers);
+            let pruned = btc_light_client::prune_headers(deps.storage, retain, limit as usize)?;
+            let event = Event::new("btc_headers_pruned")
+                .add_attribute("pruned", pruned.to_string())
+                .add_attribute(
+                    "earliest_height",
+                    btc_light_client::get_earliest_height(deps.storage)?.to_string(),
+                );
+            Ok(Response::new().add_event(event))
+        }
+        ExecuteMsg::UpdateBtcHeaderRelayers { add, remove } => {
+            ensure_contract_admin(deps.as_ref(), &env, &info)?;
+            let add = add
+                .iter()
+                .map(|addr| deps.api.addr_validate(addr))
+                .collect::<StdResult<Vec<_>>>()?;
+            let remove = remove
+                .iter()
+                .map(|addr| deps.api.addr_validate(addr))
+                .collect::<StdResult<Vec<_>>>()?;
+            btc_light_client::update_relayers(deps.storage, &add, &remove)?;
+            let join =
+                |addrs: &[Addr]| addrs.iter().map(Addr::as_str).collect::<Vec<_>>().join(",");
+            Ok(Response::new()
+                .add_attribute("action", "update_btc_header_relayers")
+                .add_attribute("added", join(&add))
+                .add_attribute("removed", join(&remove)))
+        }
     }
 }
 
+/// ensure_contract_admin ensures the sender is the Wasm admin of the contract
+fn ensure_contract_admin(deps: Deps, env: &Env, info: &MessageInfo) -> Result<(), ContractError> {
+    let contract_admin = deps
+        .querier
+        .query_wasm_contract_info(&env.contract.address)?
+        .admin;
+    if contract_admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// ensure_btc_finality ensures the sender is the `btc_finality` contract
+fn ensure_btc_finality(cfg: &Config, info: &MessageInfo) -> Result<(), ContractError> {
+    let btc_finality = cfg
+        .btc_finality
+        .as_ref()
+        .ok_or(ContractError::BtcFinalityNotSet {})?;
+    if info.sender != btc_finality {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// slash_fp_msg builds the message slashing the given finality provider, i.e. setting its slashing
+/// height to the block height and its power to zero, in the staking contract of its consumer
+fn slash_fp_msg(
+    storage: &dyn Storage,
+    cfg: &Config,
+    fp_btc_pk_hex: &str,
+) -> Result<WasmMsg, ContractError> {
+    staking_fp_msg(storage, cfg, fp_btc_pk_hex, |fp_btc_pk_hex| {
+        btc_staking_api::ExecuteMsg::Slash { fp_btc_pk_hex }
+    })
+}
+
+/// jail_fp_msg builds the message jailing the given finality provider, i.e. excluding it from the
+/// voting power table until it is unjailed, in the staking contract of its consumer
+fn jail_fp_msg(
+    storage: &dyn Storage,
+    cfg: &Config,
+    fp_btc_pk_hex: &str,
+) -> Result<WasmMsg, ContractError> {
+    staking_fp_msg(storage, cfg, fp_btc_pk_hex, |fp_btc_pk_hex| {
+        btc_staking_api::ExecuteMsg::Jail { fp_btc_pk_hex }
+    })
+}
+
+/// staking_fp_msg builds the given message about a finality provider, for the staking contract
+/// of its consumer
+fn staking_fp_msg(
+    storage: &dyn Storage,
+    cfg: &Config,
+    fp_btc_pk_hex: &str,
+    msg: impl FnOnce(BtcPkHex) -> btc_staking_api::ExecuteMsg,
+) -> Result<WasmMsg, ContractError> {
+    let btc_staking = staking_router::route_fp(storage, cfg, fp_btc_pk_hex)?;
+    let msg = msg(fp_btc_pk_hex
+        .parse()
+        .map_err(|e: StakingApiError| StdError::generic_err(e.to_string()))?);
+    Ok(WasmMsg::Execute {
+        contract_addr: btc_staking.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    })
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::msg::btc_header::{BtcBaseHeader, BtcHeader, BtcHeaderResponse};
+    use crate::msg::contract::{BabylonTagMsg, BabylonTagsMsg};
+    use crate::state::config::PacketTimeoutConfig;
     use babylon_bitcoin::BlockHeader;
+    use cosmwasm_std::from_json;
     use cosmwasm_std::testing::message_info;
     use cosmwasm_std::testing::{mock_dependencies, mock_env};
 
     const CREATOR: &str = "creator";
 
+    /// base_instantiate_msg returns the instantiation message of a regtest contract, without any
+    /// optional settings
+    pub(crate) fn base_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg::new(
+            babylon_bitcoin::chain_params::Network::Regtest,
+            "01020304",
+            10,
+            100,
+        )
+    }
+
     #[test]
     fn test_deserialize_btc_header() {
         // https://babylon.explorers.guru/transaction/8CEC6D605A39378F560C2134ABC931AE7DED0D055A6655B82CC5A31D5DA0BE26
@@ -292,22 +700,257 @@ mod tests {
     #[test]
     fn instantiate_works() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {
-            network: babylon_bitcoin::chain_params::Network::Regtest,
-            babylon_tag: "01020304".to_string(),
-            btc_confirmation_depth: 10,
-            checkpoint_finalization_timeout: 100,
-            notify_cosmos_zone: false,
-            btc_staking_code_id: None,
-            btc_staking_msg: None,
-            btc_finality_code_id: None,
-            btc_finality_msg: None,
-            admin: None,
-            consumer_name: None,
-            consumer_description: None,
-        };
+        let msg = base_instantiate_msg();
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
         let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
     }
+
+    #[test]
+    fn instantiate_with_babylon_tags_works() {
+        let mut deps = mock_dependencies();
+        let mut msg = InstantiateMsg {
+            babylon_tags: Some(vec![BabylonTagMsg {
+                tag: "05060708".to_string(),
+                start_height: 100,
+                end_height: Some(99),
+            }]),
+            ..base_instantiate_msg()
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        // Invalid height range
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+
+        // Invalid tag
+        msg.babylon_tags = Some(vec![BabylonTagMsg {
+            tag: "0506".to_string(),
+            start_height: 100,
+            end_height: None,
+        }]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+
+        msg.babylon_tags = Some(vec![BabylonTagMsg {
+            tag: "05060708".to_string(),
+            start_height: 100,
+            end_height: None,
+        }]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(
+            cfg.babylon_tags,
+            vec![crate::state::config::BabylonTag {
+                tag: vec![0x5, 0x6, 0x7, 0x8],
+                start_height: 100,
+                end_height: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn migrate_babylon_tags_works() {
+        let mut deps = mock_dependencies();
+        let msg = base_instantiate_msg();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Expiring the main tag requires an open-ended tag to take over
+        let mut tags = BabylonTagsMsg {
+            babylon_tag_end_height: Some(199),
+            babylon_tags: vec![BabylonTagMsg {
+                tag: "05060708".to_string(),
+                start_height: 100,
+                end_height: Some(300),
+            }],
+        };
+        let msg = |tags: &BabylonTagsMsg| MigrateMsg {
+            babylon_tags: Some(tags.clone()),
+            ..MigrateMsg::default()
+        };
+        let err = migrate(deps.as_mut(), mock_env(), msg(&tags)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+        // Rotate the tag
+        tags.babylon_tags[0].end_height = None;
+        migrate(deps.as_mut(), mock_env(), msg(&tags)).unwrap();
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(cfg.babylon_tag_end_height, Some(199));
+        let main_tag: &[u8] = &[0x1, 0x2, 0x3, 0x4];
+        let new_tag: &[u8] = &[0x5, 0x6, 0x7, 0x8];
+        assert_eq!(cfg.accepted_babylon_tags(150), vec![main_tag, new_tag]);
+        assert_eq!(cfg.accepted_babylon_tags(200), vec![new_tag]);
+
+        // The tags are replaced, and the main tag no longer expires
+        tags.babylon_tag_end_height = None;
+        tags.babylon_tags = vec![];
+        migrate(deps.as_mut(), mock_env(), msg(&tags)).unwrap();
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(cfg.babylon_tag_end_height, None);
+        assert_eq!(cfg.accepted_babylon_tags(200), vec![main_tag]);
+    }
+
+    #[test]
+    fn instantiate_validation_works() {
+        let mut deps = mock_dependencies();
+        let valid_msg = base_instantiate_msg();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        // The babylon tag must be exactly 4 hex-encoded bytes
+        for tag in ["010203", "0102030405", "0102030g"] {
+            let msg = InstantiateMsg {
+                babylon_tag: tag.to_string(),
+                ..valid_msg.clone()
+            };
+            let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::InvalidBabylonTag {
+                    tag: tag.to_string()
+                }
+            );
+        }
+
+        let msg = InstantiateMsg {
+            btc_confirmation_depth: 0,
+            ..valid_msg.clone()
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidBtcConfirmationDepth {});
+
+        let msg = InstantiateMsg {
+            checkpoint_finalization_timeout: 10,
+            ..valid_msg.clone()
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidCheckpointFinalizationTimeout {
+                timeout: 10,
+                btc_confirmation_depth: 10,
+            }
+        );
+
+        // The BTC staking code id and instantiation message go together
+        let msg = InstantiateMsg {
+            btc_staking_msg: Some(Binary::from(b"{}")),
+            ..valid_msg.clone()
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::BtcStakingMsgMismatch {});
+        let msg = InstantiateMsg {
+            btc_staking_code_id: Some(1),
+            consumer_name: Some("consumer".to_string()),
+            consumer_description: Some("description".to_string()),
+            ..valid_msg.clone()
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::BtcStakingMsgMismatch {});
+
+        instantiate(deps.as_mut(), mock_env(), info, valid_msg).unwrap();
+    }
+
+    #[test]
+    fn instantiate_with_btc_base_header_works() {
+        let mut deps = mock_dependencies();
+        let headers = test_utils::get_btc_lc_headers();
+        let base = BtcHeaderResponse::try_from(&headers[0]).unwrap();
+        let mut msg = InstantiateMsg {
+            btc_headers: Some(vec![]),
+            ..base_instantiate_msg()
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        // Headers without a base header are rejected
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+
+        // Headers not extending the base header are rejected
+        msg.btc_base_header = Some(BtcBaseHeader {
+            header: base.header.clone(),
+            height: base.height,
+            cum_work: base.cum_work,
+        });
+        msg.btc_headers = Some(vec![BtcHeader::try_from(&headers[2]).unwrap()]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+
+        // The light client starts from the base header, with fewer headers than a w-deep proof
+        let mut deps = mock_dependencies();
+        msg.btc_headers = Some(
+            headers[1..3]
+                .iter()
+                .map(|header| BtcHeader::try_from(header).unwrap())
+                .collect(),
+        );
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let base_header = btc_light_client::get_base_header(deps.as_ref().storage).unwrap();
+        assert_eq!(base_header, headers[0]);
+        let tip = btc_light_client::get_tip(deps.as_ref().storage).unwrap();
+        assert_eq!(tip, headers[2]);
+    }
+
+    #[test]
+    fn instantiate_with_known_network_works() {
+        let mut deps = mock_dependencies();
+        let mut msg = InstantiateMsg {
+            network: babylon_bitcoin::chain_params::Network::Signet,
+            babylon_tag: "62627434".to_string(),
+            babylon_network: Some("bbn-devnet".to_string()),
+            ..base_instantiate_msg()
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        // Unknown network
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+
+        // Parameters not matching the network
+        msg.babylon_network = Some("mainnet".to_string());
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert!(err.to_string().contains("network mismatch"));
+
+        msg.babylon_network = Some("bbn-test-5".to_string());
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn migrate_dry_run_works() {
+        let mut deps = mock_dependencies();
+        let msg = base_instantiate_msg();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+
+        let packet_timeout = PacketTimeoutConfig {
+            timeout_seconds: 60,
+            max_resubmissions: 1,
+            keepalive_seconds: 0,
+        };
+        let mut msg = MigrateMsg {
+            packet_timeout: Some(packet_timeout.clone()),
+            dry_run: true,
+            ..MigrateMsg::default()
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        let summary: MigrationSummary = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(
+            summary,
+            MigrationSummary {
+                stored_version: Some(CONTRACT_VERSION.to_string()),
+                code_version: CONTRACT_VERSION.to_string(),
+                written_keys: 1,
+                removed_keys: 0,
+                scheduled_migrations: vec![],
+            }
+        );
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "packet_timeout_seconds" && a.value == "60"));
+        // Nothing has changed
+        assert_eq!(CONFIG.load(deps.as_ref().storage).unwrap(), cfg);
+
+        msg.dry_run = false;
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.data.is_none());
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(cfg.packet_timeout, packet_timeout);
+    }
 }
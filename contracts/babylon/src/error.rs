@@ -1,3 +1,4 @@
+use babylon_apis::error::{ErrorCategory, ErrorCode};
 use babylon_bitcoin::Work;
 use cosmwasm_std::StdError;
 use cw_utils::ParseReplyError;
@@ -20,7 +21,7 @@ pub enum ContractError {
     BabylonEpochError(#[from] BabylonEpochChainError),
     #[error("{0}")]
     CzHeaderError(#[from] CZHeaderChainError),
-    #[error("Contract already has an open IBC channel")]
+    #[error("Contract already has an open IBC channel over the connection")]
     IbcChannelAlreadyOpen {},
     #[error("The contract only supports ordered channels")]
     IbcUnorderedChannel {},
@@ -30,6 +31,21 @@ pub enum ContractError {
     IbcUnsupportedMethod {},
     #[error("IBC send timed out: dest: channel {0}, port {1}")]
     IbcTimeout(String, String),
+    #[error("Unexpected IBC counterparty: connection {connection_id}, port {port_id}")]
+    IbcUnexpectedCounterparty {
+        connection_id: String,
+        port_id: String,
+    },
+    #[error("The IBC channel has been closed, re-opening it requires the approval of the admin")]
+    IbcChannelReopenLocked {},
+    #[error("Packet received over stale IBC channel {channel_id}")]
+    IbcStaleChannel { channel_id: String },
+    #[error("IBC channel {channel_id} not found")]
+    IbcChannelNotFound { channel_id: String },
+    #[error("BTC staking packet aborted, as operations failed in btc-staking contract {contract}: {reason}")]
+    BtcStakingAborted { contract: String, reason: String },
+    #[error("Unsupported packet memo version {0}")]
+    UnsupportedMemoVersion(u32),
     #[error("Unauthorized")]
     Unauthorized {},
     #[error("The BTC staking contract is not set")]
@@ -38,6 +54,19 @@ pub enum ContractError {
     BtcFinalityNotSet {},
     #[error("Invalid configuration: {msg}")]
     InvalidConfig { msg: String },
+    #[error("State section {section} is paused until its migration is complete")]
+    SectionMigrating { section: String },
+    #[error("Invalid babylon tag {tag}: it must be exactly 4 hex-encoded bytes")]
+    InvalidBabylonTag { tag: String },
+    #[error("The BTC confirmation depth must be greater than zero")]
+    InvalidBtcConfirmationDepth {},
+    #[error("The checkpoint finalization timeout ({timeout}) must be greater than the BTC confirmation depth ({btc_confirmation_depth})")]
+    InvalidCheckpointFinalizationTimeout {
+        timeout: u64,
+        btc_confirmation_depth: u64,
+    },
+    #[error("btc_staking_code_id and btc_staking_msg must be set together")]
+    BtcStakingMsgMismatch {},
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -125,6 +154,8 @@ pub enum BabylonEpochChainError {
     BTCHeaderNotDeepEnough { w: u64 },
     #[error("The checkpoint is not in the given BTC headers: {err_msg}")]
     CheckpointNotSubmitted { err_msg: String },
+    #[error("The checkpoint cannot be parsed from the given BTC txs: {err_msg}")]
+    CheckpointParseError { err_msg: String },
     #[error("The epoch is not sealed by the epoch's validator set: {err_msg}")]
     EpochNotSealed { err_msg: String },
     #[error("Transaction key is empty")]
@@ -132,3 +163,444 @@ pub enum BabylonEpochChainError {
     #[error("The BTC header cannot be decoded")]
     BTCHeaderDecodeError {},
 }
+
+impl ContractError {
+    /// code returns the stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            ContractError::StdError(..) => 101,
+            ContractError::ParseReply(..) => 102,
+            ContractError::InvalidReplyId(..) => 103,
+            ContractError::BtcError(e) => e.code(),
+            ContractError::BabylonEpochError(e) => e.code(),
+            ContractError::CzHeaderError(e) => e.code(),
+            ContractError::IbcChannelAlreadyOpen { .. } => 301,
+            ContractError::IbcUnorderedChannel { .. } => 302,
+            ContractError::IbcInvalidCounterPartyVersion { .. } => 303,
+            ContractError::IbcUnsupportedMethod { .. } => 304,
+            ContractError::IbcTimeout(..) => 305,
+            ContractError::IbcUnexpectedCounterparty { .. } => 306,
+            ContractError::IbcChannelReopenLocked { .. } => 307,
+            ContractError::IbcStaleChannel { .. } => 308,
+            ContractError::IbcChannelNotFound { .. } => 309,
+            ContractError::BtcStakingAborted { .. } => 310,
+            ContractError::UnsupportedMemoVersion(..) => 311,
+            ContractError::Unauthorized { .. } => 201,
+            ContractError::BtcStakingNotSet { .. } => 401,
+            ContractError::BtcFinalityNotSet { .. } => 402,
+            ContractError::InvalidConfig { .. } => 403,
+            ContractError::SectionMigrating { .. } => 404,
+            ContractError::InvalidBabylonTag { .. } => 405,
+            ContractError::InvalidBtcConfirmationDepth { .. } => 406,
+            ContractError::InvalidCheckpointFinalizationTimeout { .. } => 407,
+            ContractError::BtcStakingMsgMismatch { .. } => 408,
+        }
+    }
+}
+
+impl CZHeaderChainError {
+    /// code returns the stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            CZHeaderChainError::StdError(..) => 701,
+            CZHeaderChainError::InitError { .. } => 702,
+            CZHeaderChainError::DecodeError(..) => 703,
+            CZHeaderChainError::EpochNumberError { .. } => 704,
+            CZHeaderChainError::ProofError { .. } => 705,
+            CZHeaderChainError::CZHeaderDecodeError { .. } => 706,
+            CZHeaderChainError::CZHeaderNotFoundError { .. } => 707,
+            CZHeaderChainError::NoCZHeader { .. } => 708,
+        }
+    }
+}
+
+impl BTCLightclientError {
+    /// code returns the stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            BTCLightclientError::StdError(..) => 501,
+            BTCLightclientError::InitError { .. } => 502,
+            BTCLightclientError::InitErrorLength(..) => 503,
+            BTCLightclientError::DecodeError(..) => 504,
+            BTCLightclientError::HashError(..) => 505,
+            BTCLightclientError::DecodeHexError(..) => 506,
+            BTCLightclientError::DecodeUtf8Error(..) => 507,
+            BTCLightclientError::BTCHeaderDecodeError { .. } => 508,
+            BTCLightclientError::BTCHeaderEncodeError { .. } => 509,
+            BTCLightclientError::BTCHeaderEmpty { .. } => 510,
+            BTCLightclientError::BTCHeaderError { .. } => 511,
+            BTCLightclientError::BTCHeaderNotFoundError { .. } => 512,
+            BTCLightclientError::BTCHeightNotFoundError { .. } => 513,
+            BTCLightclientError::BTCWrongCumulativeWorkEncoding { .. } => 514,
+            BTCLightclientError::BTCWrongCumulativeWork(..) => 515,
+            BTCLightclientError::BTCWrongHeight(..) => 516,
+            BTCLightclientError::BTCChainWithNotEnoughWork(..) => 517,
+        }
+    }
+}
+
+impl BabylonEpochChainError {
+    /// code returns the stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            BabylonEpochChainError::StdError(..) => 601,
+            BabylonEpochChainError::DecodeError(..) => 602,
+            BabylonEpochChainError::BTCLightClientError(e) => e.code(),
+            BabylonEpochChainError::EpochNotFoundError { .. } => 603,
+            BabylonEpochChainError::NoFinalizedEpoch { .. } => 604,
+            BabylonEpochChainError::CheckpointNotMatchError { .. } => 605,
+            BabylonEpochChainError::CheckpointNotFoundError { .. } => 606,
+            BabylonEpochChainError::BTCHeaderNotFoundError { .. } => 607,
+            BabylonEpochChainError::BTCHeaderNotDeepEnough { .. } => 608,
+            BabylonEpochChainError::CheckpointNotSubmitted { .. } => 609,
+            BabylonEpochChainError::CheckpointParseError { .. } => 610,
+            BabylonEpochChainError::EpochNotSealed { .. } => 611,
+            BabylonEpochChainError::EmptyTxKey { .. } => 612,
+            BabylonEpochChainError::BTCHeaderDecodeError { .. } => 613,
+        }
+    }
+}
+
+/// ERROR_CODES is the table of error codes of the contract
+pub const ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode {
+        code: 101,
+        category: ErrorCategory::Std,
+        description: "Generic error",
+    },
+    ErrorCode {
+        code: 102,
+        category: ErrorCategory::Std,
+        description: "The submessage reply cannot be parsed",
+    },
+    ErrorCode {
+        code: 103,
+        category: ErrorCategory::Std,
+        description: "Invalid reply id",
+    },
+    ErrorCode {
+        code: 201,
+        category: ErrorCategory::Auth,
+        description: "Unauthorized",
+    },
+    ErrorCode {
+        code: 301,
+        category: ErrorCategory::Ibc,
+        description: "Contract already has an open IBC channel over the connection",
+    },
+    ErrorCode {
+        code: 302,
+        category: ErrorCategory::Ibc,
+        description: "The contract only supports ordered channels",
+    },
+    ErrorCode {
+        code: 303,
+        category: ErrorCategory::Ibc,
+        description: "Counterparty version must be the zoneconcierge version",
+    },
+    ErrorCode {
+        code: 304,
+        category: ErrorCategory::Ibc,
+        description: "IBC method is not supported",
+    },
+    ErrorCode {
+        code: 305,
+        category: ErrorCategory::Ibc,
+        description: "IBC send timed out",
+    },
+    ErrorCode {
+        code: 306,
+        category: ErrorCategory::Ibc,
+        description: "Unexpected IBC counterparty",
+    },
+    ErrorCode {
+        code: 307,
+        category: ErrorCategory::Ibc,
+        description:
+            "The IBC channel has been closed, re-opening it requires the approval of the admin",
+    },
+    ErrorCode {
+        code: 308,
+        category: ErrorCategory::Ibc,
+        description: "Packet received over stale IBC channel",
+    },
+    ErrorCode {
+        code: 309,
+        category: ErrorCategory::Ibc,
+        description: "IBC channel not found",
+    },
+    ErrorCode {
+        code: 310,
+        category: ErrorCategory::Ibc,
+        description: "BTC staking packet aborted, as operations failed in btc-staking contract",
+    },
+    ErrorCode {
+        code: 311,
+        category: ErrorCategory::Ibc,
+        description: "Unsupported packet memo version",
+    },
+    ErrorCode {
+        code: 401,
+        category: ErrorCategory::Config,
+        description: "The BTC staking contract is not set",
+    },
+    ErrorCode {
+        code: 402,
+        category: ErrorCategory::Config,
+        description: "The BTC finality contract is not set",
+    },
+    ErrorCode {
+        code: 403,
+        category: ErrorCategory::Config,
+        description: "Invalid configuration",
+    },
+    ErrorCode {
+        code: 404,
+        category: ErrorCategory::Config,
+        description: "State section is paused until its migration is complete",
+    },
+    ErrorCode {
+        code: 405,
+        category: ErrorCategory::Config,
+        description: "Invalid babylon tag",
+    },
+    ErrorCode {
+        code: 406,
+        category: ErrorCategory::Config,
+        description: "The BTC confirmation depth must be greater than zero",
+    },
+    ErrorCode {
+        code: 407,
+        category: ErrorCategory::Config,
+        description:
+            "The checkpoint finalization timeout must be greater than the BTC confirmation depth",
+    },
+    ErrorCode {
+        code: 408,
+        category: ErrorCategory::Config,
+        description: "btc_staking_code_id and btc_staking_msg must be set together",
+    },
+    ErrorCode {
+        code: 501,
+        category: ErrorCategory::BtcLightClient,
+        description: "Generic error",
+    },
+    ErrorCode {
+        code: 502,
+        category: ErrorCategory::BtcLightClient,
+        description: "The given headers during initialization cannot be verified",
+    },
+    ErrorCode {
+        code: 503,
+        category: ErrorCategory::BtcLightClient,
+        description: "The given headers during initialization cannot be verified",
+    },
+    ErrorCode {
+        code: 504,
+        category: ErrorCategory::BtcLightClient,
+        description: "The bytes cannot be decoded",
+    },
+    ErrorCode {
+        code: 505,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC hash cannot be decoded",
+    },
+    ErrorCode {
+        code: 506,
+        category: ErrorCategory::BtcLightClient,
+        description: "The hex cannot be decoded",
+    },
+    ErrorCode {
+        code: 507,
+        category: ErrorCategory::BtcLightClient,
+        description: "The bytes cannot be decoded as string",
+    },
+    ErrorCode {
+        code: 508,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC header cannot be decoded",
+    },
+    ErrorCode {
+        code: 509,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC header cannot be encoded",
+    },
+    ErrorCode {
+        code: 510,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC header is not being sent",
+    },
+    ErrorCode {
+        code: 511,
+        category: ErrorCategory::BtcLightClient,
+        description:
+            "The BTC header does not satisfy the difficulty requirement or is not consecutive",
+    },
+    ErrorCode {
+        code: 512,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC header with height is not found in the storage",
+    },
+    ErrorCode {
+        code: 513,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC height with hash is not found in the storage",
+    },
+    ErrorCode {
+        code: 514,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC header info cumulative work encoding is wrong",
+    },
+    ErrorCode {
+        code: 515,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC header info cumulative work is wrong",
+    },
+    ErrorCode {
+        code: 516,
+        category: ErrorCategory::BtcLightClient,
+        description: "The BTC header info height is wrong",
+    },
+    ErrorCode {
+        code: 517,
+        category: ErrorCategory::BtcLightClient,
+        description: "The new chain's work is not better than the current chain's work",
+    },
+    ErrorCode {
+        code: 601,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "Generic error",
+    },
+    ErrorCode {
+        code: 602,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The bytes cannot be decoded",
+    },
+    ErrorCode {
+        code: 603,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The epoch is not found in the storage",
+    },
+    ErrorCode {
+        code: 604,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "There is no finalized epoch yet",
+    },
+    ErrorCode {
+        code: 605,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The checkpoint is for epoch rather than the given epoch",
+    },
+    ErrorCode {
+        code: 606,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The checkpoint of epoch is not found in the storage",
+    },
+    ErrorCode {
+        code: 607,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The BTC header with hash is not found in the storage",
+    },
+    ErrorCode {
+        code: 608,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The BTC headers are not deep enough",
+    },
+    ErrorCode {
+        code: 609,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The checkpoint is not in the given BTC headers",
+    },
+    ErrorCode {
+        code: 610,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The checkpoint cannot be parsed from the given BTC txs",
+    },
+    ErrorCode {
+        code: 611,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The epoch is not sealed by the epoch's validator set",
+    },
+    ErrorCode {
+        code: 612,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "Transaction key is empty",
+    },
+    ErrorCode {
+        code: 613,
+        category: ErrorCategory::BabylonEpochChain,
+        description: "The BTC header cannot be decoded",
+    },
+    ErrorCode {
+        code: 701,
+        category: ErrorCategory::CzHeaderChain,
+        description: "Generic error",
+    },
+    ErrorCode {
+        code: 702,
+        category: ErrorCategory::CzHeaderChain,
+        description: "The given headers during initialization cannot be verified",
+    },
+    ErrorCode {
+        code: 703,
+        category: ErrorCategory::CzHeaderChain,
+        description: "The bytes cannot be decoded",
+    },
+    ErrorCode {
+        code: 704,
+        category: ErrorCategory::CzHeaderChain,
+        description: "The header is not checkpointed in the given epoch",
+    },
+    ErrorCode {
+        code: 705,
+        category: ErrorCategory::CzHeaderChain,
+        description: "The Proof cannot be verified",
+    },
+    ErrorCode {
+        code: 706,
+        category: ErrorCategory::CzHeaderChain,
+        description: "The CZ header cannot be decoded",
+    },
+    ErrorCode {
+        code: 707,
+        category: ErrorCategory::CzHeaderChain,
+        description: "The CZ header with height is not found in the storage",
+    },
+    ErrorCode {
+        code: 708,
+        category: ErrorCategory::CzHeaderChain,
+        description: "There is no finalized CZ header yet",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use babylon_apis::error::{check_error_codes, validate_error_codes};
+
+    #[test]
+    fn error_codes_are_valid() {
+        validate_error_codes(ERROR_CODES).unwrap();
+
+        // Every variant has a code in the table, described after its message
+        check_error_codes(
+            include_str!("error.rs"),
+            &[
+                "ContractError",
+                "CZHeaderChainError",
+                "BTCLightclientError",
+                "BabylonEpochChainError",
+            ],
+            ERROR_CODES,
+        )
+        .unwrap();
+        // Wrapped errors keep their code
+        assert_eq!(
+            ContractError::BtcError(BTCLightclientError::BTCHeaderEmpty {}).code(),
+            ContractError::BabylonEpochError(BabylonEpochChainError::BTCLightClientError(
+                BTCLightclientError::BTCHeaderEmpty {}
+            ))
+            .code()
+        );
+    }
+}
@@ -1,25 +1,191 @@
 use crate::error::ContractError;
+use babylon_apis::pagination::{self, page_limit};
+use babylon_apis::zoneconcierge_api::error_ack;
 use babylon_bindings::BabylonMsg;
 use babylon_proto::babylon::zoneconcierge::v1::{
     zoneconcierge_packet_data::Packet, BtcTimestamp, ConsumerRegisterIbcPacket,
     ZoneconciergePacketData,
 };
 
-use crate::state::config::CONFIG;
+use crate::msg::ibc::{BtcStakingResponse, PacketMemo, PacketMemoData};
+use crate::state::config::{Config, CONFIG};
+use crate::state::packet_stats;
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    Binary, DepsMut, Env, Event, Ibc3ChannelOpenResponse, IbcBasicResponse, IbcChannel,
-    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcMsg,
-    IbcOrder, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse,
-    IbcTimeout, Never, StdAck, StdError, StdResult,
+    from_json, to_json_binary, Binary, DepsMut, Env, Event, Ibc3ChannelOpenResponse,
+    IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
+    IbcChannelOpenResponse, IbcMsg, IbcOrder, IbcPacketAckMsg, IbcPacketReceiveMsg,
+    IbcPacketTimeoutMsg, IbcReceiveResponse, IbcTimeout, Never, Order, Reply, Response, StdAck,
+    StdError, StdResult, Storage, SubMsg, SubMsgResponse, Timestamp,
 };
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
 use prost::Message;
+use sha2::{Digest, Sha256};
 
 pub const IBC_VERSION: &str = "zoneconcierge-1";
 pub const IBC_ORDERING: IbcOrder = IbcOrder::Ordered;
 
 // IBC specific state
+/// The primary zoneconcierge channel, i.e. the one outbound packets are sent over.
+/// It is the first connected channel, and is replaced by another open channel upon closing
 pub const IBC_CHANNEL: Item<IbcChannel> = Item::new("ibc_channel");
+/// Open zoneconcierge channels, by channel id
+pub const CHANNELS: Map<&str, ChannelInfo> = Map::new("ibc_channels");
+/// Outbound packets pending acknowledgement, by (channel id, sequence)
+pub const PENDING_PACKETS: Map<(&str, u64), PendingPacket> = Map::new("ibc_pending_packets");
+/// Timed out outbound packets pending resubmission, by (channel id, sequence) of their last
+/// submission
+pub const PACKET_RESUBMISSIONS: Map<(&str, u64), PacketResubmission> =
+    Map::new("packet_resubmissions");
+/// Reply id of the outbound packets sent, recording them as pending acknowledgement under the
+/// sequence they are assigned upon sending
+pub const REPLY_ID_SEND_PACKET: u64 = 6;
+
+/// Inbound packets that failed processing, by (channel id, sequence), pending reprocessing
+pub const DEAD_LETTERS: Map<(&str, u64), DeadLetter> = Map::new("ibc_dead_letters");
+/// Acknowledgement of the BTC staking packet being processed, pending the replies of the
+/// btc-staking contracts
+pub const PENDING_STAKING_ACK: Item<PendingStakingAck> = Item::new("ibc_pending_staking_ack");
+/// Reply id of the BTC staking operations sent to the btc-staking contracts
+pub const REPLY_ID_BTC_STAKING: u64 = 4;
+/// Reply id of the ibc-hooks calls into the destination contracts of packet memos
+pub const REPLY_ID_IBC_HOOK: u64 = 5;
+/// ibc-hooks calls that failed, by (channel id, sequence) of their inbound packet
+pub const IBC_HOOK_FAILURES: Map<(&str, u64), IbcHookFailure> = Map::new("ibc_hook_failures");
+/// Closed zoneconcierge channels, by channel id
+pub const CLOSED_CHANNELS: Map<&str, ClosedChannel> = Map::new("ibc_closed_channels");
+/// Connections over which opening a new channel is locked until the admin approves it, after
+/// their channel has been closed through a channel close handshake, by connection id
+pub const IBC_CHANNEL_REOPEN_LOCKS: Map<&str, ()> = Map::new("ibc_channel_reopen_locks");
+/// Legacy contract-wide reopen lock, superseded by `IBC_CHANNEL_REOPEN_LOCKS`
+pub(crate) const IBC_CHANNEL_REOPEN_LOCKED: Item<bool> = Item::new("ibc_channel_reopen_locked");
+
+/// PendingPacket is an outbound packet pending acknowledgement
+#[cw_serde]
+pub struct PendingPacket {
+    /// packet_hash is the SHA-256 hash of the packet data
+    pub packet_hash: Binary,
+    /// packet_type is the type of the packet, e.g. `consumer_slashing`
+    pub packet_type: String,
+    pub channel_id: String,
+    /// sequence is the sequence of the packet over its channel, assigned upon sending
+    pub sequence: u64,
+    /// height is the block height at which the packet was (last) sent
+    pub height: u64,
+    /// attempts is the number of times the packet timed out before being sent over this channel
+    pub attempts: u32,
+}
+
+/// PacketResubmission is an outbound packet that timed out, to be resubmitted over the next
+/// channel
+#[cw_serde]
+pub struct PacketResubmission {
+    pub data: Binary,
+    /// attempts is the number of times the packet timed out
+    pub attempts: u32,
+}
+
+/// DeadLetter is an inbound packet that failed processing, and was acknowledged with an error.
+/// It is kept until the admin reprocesses it, e.g. after a fix or migration
+#[cw_serde]
+pub struct DeadLetter {
+    pub channel_id: String,
+    pub sequence: u64,
+    /// error_code is the stable code of the processing error
+    pub error_code: u32,
+    pub error: String,
+    /// packet_hash is the SHA-256 hash of the packet data
+    pub packet_hash: Binary,
+    pub data: Binary,
+    /// height is the block height at which the packet failed processing
+    pub height: u64,
+}
+
+/// IbcHookFailure is an ibc-hooks call into the destination contract of a packet memo that
+/// failed. The packet itself was handled and acknowledged, only the call was reverted
+#[cw_serde]
+pub struct IbcHookFailure {
+    pub channel_id: String,
+    pub sequence: u64,
+    /// contract is the address of the destination contract
+    pub contract: String,
+    pub error: String,
+    /// height is the block height at which the call failed
+    pub height: u64,
+}
+
+/// IbcHook identifies an ibc-hooks call into the destination contract of a packet memo.
+/// It is the payload of its submessage
+#[cw_serde]
+struct IbcHook {
+    channel_id: String,
+    sequence: u64,
+    contract: String,
+}
+
+/// PendingStakingAck is the acknowledgement of the BTC staking packet being processed, pending
+/// the replies of the btc-staking contracts
+#[cw_serde]
+pub struct PendingStakingAck {
+    pub ack: BtcStakingResponse,
+    /// pending is the number of btc-staking contracts yet to reply
+    pub pending: u32,
+}
+
+/// StakingBatch identifies the operations of a BTC staking packet routed to a btc-staking
+/// contract. It is the payload of their submessage
+#[cw_serde]
+struct StakingBatch {
+    contract: String,
+    fps: Vec<String>,
+    dels: Vec<String>,
+}
+
+/// MsgExecuteContractResponse is the response of a wasm execute message
+#[derive(Clone, PartialEq, prost::Message)]
+struct MsgExecuteContractResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    data: Vec<u8>,
+}
+
+/// MsgIbcSendResponse is the response of an IBC send packet message
+#[derive(Clone, PartialEq, prost::Message)]
+struct MsgIbcSendResponse {
+    #[prost(uint64, tag = "1")]
+    sequence: u64,
+}
+
+/// ChannelInfo is an open zoneconcierge channel, along with its metadata
+#[cw_serde]
+pub struct ChannelInfo {
+    pub channel: IbcChannel,
+    /// counterparty_chain_id is the chain id of the Babylon chain at the other end of the
+    /// channel, if set by the admin
+    pub counterparty_chain_id: Option<String>,
+    /// consumer_id is the consumer id of the channel, if set by the admin.
+    /// BTC staking operations received over the channel without a consumer of their own are
+    /// routed to the btc-staking contract of this consumer
+    pub consumer_id: Option<String>,
+    /// created_height is the block height at which the channel was connected
+    pub created_height: u64,
+    /// last_active_time is the block time of the last inbound packet or acknowledgement over the
+    /// channel, or of its connection. `None` until checked, for channels connected before
+    /// activity was recorded
+    #[serde(default)]
+    pub last_active_time: Option<Timestamp>,
+}
+
+/// ClosedChannel is a zoneconcierge channel that has been closed.
+/// Packets received over it are rejected, while its dead letters are kept for reprocessing
+#[cw_serde]
+pub struct ClosedChannel {
+    pub channel_id: String,
+    /// height is the block height at which the channel was closed
+    pub height: u64,
+    /// timed_out is whether the channel was closed by a packet timeout, rather than by a channel
+    /// close handshake
+    pub timed_out: bool,
+}
 
 /// This is executed during the ChannelOpenInit and ChannelOpenTry
 /// of the IBC 4-step channel protocol
@@ -31,12 +197,9 @@ pub fn ibc_channel_open(
     _env: Env,
     msg: IbcChannelOpenMsg,
 ) -> Result<IbcChannelOpenResponse, ContractError> {
-    // Ensure we have no channel yet
-    if IBC_CHANNEL.may_load(deps.storage)?.is_some() {
-        return Err(ContractError::IbcChannelAlreadyOpen {});
-    }
     // The IBC channel has to be ordered
     let channel = msg.channel();
+    ensure_channel_openable(deps.storage, channel)?;
     if channel.order != IBC_ORDERING {
         return Err(ContractError::IbcUnorderedChannel {});
     }
@@ -51,6 +214,9 @@ pub fn ibc_channel_open(
         }
     }
 
+    // The channel has to terminate on the expected Babylon chain, if any
+    ensure_counterparty(&CONFIG.load(deps.storage)?, channel)?;
+
     // We return the version we need (which could be different from the counterparty version)
     Ok(Some(Ibc3ChannelOpenResponse {
         version: IBC_VERSION.to_string(),
@@ -63,22 +229,33 @@ pub fn ibc_channel_connect(
     env: Env,
     msg: IbcChannelConnectMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    // Ensure we have no channel yet
-    if IBC_CHANNEL.may_load(deps.storage)?.is_some() {
-        return Err(ContractError::IbcChannelAlreadyOpen {});
-    }
     let channel = msg.channel();
-
-    // Store the channel
-    IBC_CHANNEL.save(deps.storage, channel)?;
+    ensure_channel_openable(deps.storage, channel)?;
 
     // Load the config
     let cfg = CONFIG.load(deps.storage)?;
+    ensure_counterparty(&cfg, channel)?;
+
+    // Store the channel, along with its connection and counterparty endpoint.
+    // The admin approval of a re-open, if any, is used up
+    register_channel(deps.storage, &env, channel)?;
+    IBC_CHANNEL_REOPEN_LOCKS.remove(deps.storage, &channel.connection_id);
 
     let chan_id = &channel.endpoint.channel_id;
+    let (resubmissions, resubmitted) = resubmit_packets(deps.storage, &env, &cfg, channel)?;
     let mut response = IbcBasicResponse::new()
+        .add_submessages(resubmissions)
         .add_attribute("action", "ibc_connect")
         .add_attribute("channel_id", chan_id)
+        .add_attribute("connection_id", &channel.connection_id)
+        .add_attribute(
+            "counterparty_port_id",
+            &channel.counterparty_endpoint.port_id,
+        )
+        .add_attribute(
+            "counterparty_channel_id",
+            &channel.counterparty_endpoint.channel_id,
+        )
         .add_event(Event::new("ibc").add_attribute("channel", "connect"));
 
     // If the consumer name and description are set, create and send a ConsumerRegister packet
@@ -92,14 +269,20 @@ pub fn ibc_channel_connect(
             packet: Some(Packet::ConsumerRegister(consumer_register_packet)),
         };
 
-        let ibc_msg = IbcMsg::SendPacket {
-            channel_id: channel.endpoint.channel_id.clone(),
-            data: Binary::new(packet_data.encode_to_vec()),
-            timeout: packet_timeout(&env),
-        };
+        // Unless it is being resubmitted already
+        let data = packet_data.encode_to_vec();
+        if !resubmitted.iter().any(|packet| packet.as_slice() == data) {
+            let ibc_msg = send_packet(
+                &env,
+                &channel.endpoint.channel_id,
+                data,
+                packet_timeout(&env, &cfg),
+                0,
+            )?;
+            response = response.add_submessage(ibc_msg);
+        }
 
         response = response
-            .add_message(ibc_msg)
             .add_attribute("consumer_name", name)
             .add_attribute("consumer_description", description);
     }
@@ -107,22 +290,264 @@ pub fn ibc_channel_connect(
     Ok(response)
 }
 
+/// ensure_counterparty checks the channel terminates on the expected Babylon chain, i.e. goes
+/// over the expected connection to the expected port, if an expected counterparty is configured
+fn ensure_counterparty(cfg: &Config, channel: &IbcChannel) -> Result<(), ContractError> {
+    match &cfg.babylon_counterparty {
+        Some(expected)
+            if expected.connection_id != channel.connection_id
+                || expected.port_id != channel.counterparty_endpoint.port_id =>
+        {
+            Err(ContractError::IbcUnexpectedCounterparty {
+                connection_id: channel.connection_id.clone(),
+                port_id: channel.counterparty_endpoint.port_id.clone(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// ensure_channel_openable ensures we have no open channel over the connection of the given
+/// channel yet, and that opening a new channel over it is not locked after the closing of its
+/// previous one
+fn ensure_channel_openable(
+    storage: &dyn Storage,
+    channel: &IbcChannel,
+) -> Result<(), ContractError> {
+    if get_channels(storage)?
+        .iter()
+        .any(|info| info.channel.connection_id == channel.connection_id)
+    {
+        return Err(ContractError::IbcChannelAlreadyOpen {});
+    }
+    if IBC_CHANNEL_REOPEN_LOCKS.has(storage, &channel.connection_id) {
+        return Err(ContractError::IbcChannelReopenLocked {});
+    }
+    Ok(())
+}
+
+/// register_channel records the given connected channel, which becomes the primary channel if
+/// there is none yet
+pub(crate) fn register_channel(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &IbcChannel,
+) -> StdResult<()> {
+    let info = ChannelInfo {
+        channel: channel.clone(),
+        counterparty_chain_id: None,
+        consumer_id: None,
+        created_height: env.block.height,
+        last_active_time: Some(env.block.time),
+    };
+    CHANNELS.save(storage, &channel.endpoint.channel_id, &info)?;
+    if IBC_CHANNEL.may_load(storage)?.is_none() {
+        IBC_CHANNEL.save(storage, channel)?;
+    }
+    Ok(())
+}
+
+/// close_channel records the closing of the given channel, unless recorded already, and removes
+/// it from the open channels. If it is the primary channel, another open channel (if any)
+/// becomes the primary one
+fn close_channel(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel_id: &str,
+    timed_out: bool,
+) -> StdResult<()> {
+    CHANNELS.remove(storage, channel_id);
+    if let Some(channel) = IBC_CHANNEL.may_load(storage)? {
+        if channel.endpoint.channel_id == channel_id {
+            let next = CHANNELS
+                .range(storage, None, None, Order::Ascending)
+                .next()
+                .transpose()?;
+            match next {
+                Some((_, info)) => IBC_CHANNEL.save(storage, &info.channel)?,
+                None => IBC_CHANNEL.remove(storage),
+            }
+        }
+    }
+    if CLOSED_CHANNELS.has(storage, channel_id) {
+        return Ok(());
+    }
+    let closed = ClosedChannel {
+        channel_id: channel_id.to_string(),
+        height: env.block.height,
+        timed_out,
+    };
+    CLOSED_CHANNELS.save(storage, channel_id, &closed)
+}
+
 /// This is invoked on the IBC Channel Close message
-/// We perform any cleanup related to the channel
+/// The channel is marked as closed, and packets received over it are rejected afterwards.
+/// Its channel-scoped state (i.e. dead letters) is kept for reprocessing.
+/// As the closing of the channel is deliberate, opening a new channel over its connection is
+/// locked until the admin approves it (see `ExecuteMsg::AllowChannelReopen`), unless the contract
+/// closed the channel itself as stale (see `ExecuteMsg::CheckChannels`)
 pub fn ibc_channel_close(
-    _deps: DepsMut,
-    _env: Env,
+    deps: DepsMut,
+    env: Env,
     msg: IbcChannelCloseMsg,
 ) -> StdResult<IbcBasicResponse> {
     let channel = msg.channel();
-    // Get contract address and remove lookup
     let channel_id = channel.endpoint.channel_id.as_str();
 
+    // A stale channel is recorded as closed already, when closing it
+    let stale = CLOSED_CHANNELS.has(deps.storage, channel_id);
+    close_channel(deps.storage, &env, channel_id, false)?;
+    if !stale {
+        IBC_CHANNEL_REOPEN_LOCKS.save(deps.storage, &channel.connection_id, &())?;
+    }
+
     Ok(IbcBasicResponse::new()
         .add_attribute("action", "ibc_close")
-        .add_attribute("channel_id", channel_id))
+        .add_attribute("channel_id", channel_id)
+        .add_event(Event::new("ibc").add_attribute("channel", "close")))
+}
+
+/// ensure_active_channel ensures the given channel is not stale, i.e. it has not been closed, and
+/// is one of the open channels if any
+fn ensure_active_channel(storage: &dyn Storage, channel_id: &str) -> Result<(), ContractError> {
+    let channels = get_channels(storage)?;
+    let stale = CLOSED_CHANNELS.has(storage, channel_id)
+        || (!channels.is_empty()
+            && !channels
+                .iter()
+                .any(|info| info.channel.endpoint.channel_id == channel_id));
+    if stale {
+        return Err(ContractError::IbcStaleChannel {
+            channel_id: channel_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// record_channel_activity records an inbound packet or acknowledgement over the given open
+/// channel, for the channel keepalive
+fn record_channel_activity(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel_id: &str,
+) -> StdResult<()> {
+    if let Some(mut info) = CHANNELS.may_load(storage, channel_id)? {
+        info.last_active_time = Some(env.block.time);
+        CHANNELS.save(storage, channel_id, &info)?;
+    }
+    Ok(())
+}
+
+/// check_channels closes the open channels that have been inactive for longer than the
+/// keepalive, and returns them. The activity of the channels connected before activity was
+/// recorded starts being recorded instead.
+/// The channels are closed right away, and their channel close handshake is left to the caller
+pub fn check_channels(storage: &mut dyn Storage, env: &Env) -> StdResult<Vec<ChannelInfo>> {
+    let keepalive = CONFIG.load(storage)?.packet_timeout.keepalive_seconds;
+    if keepalive == 0 {
+        return Ok(vec![]);
+    }
+    let mut stale = vec![];
+    for mut info in get_channels(storage)? {
+        let channel_id = info.channel.endpoint.channel_id.clone();
+        match info.last_active_time {
+            None => {
+                info.last_active_time = Some(env.block.time);
+                CHANNELS.save(storage, &channel_id, &info)?;
+            }
+            Some(time) if time.plus_seconds(keepalive) < env.block.time => {
+                close_channel(storage, env, &channel_id, false)?;
+                stale.push(info);
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(stale)
+}
+
+/// allow_channel_reopen unlocks the opening of a new channel over the given connection, after
+/// its previous one has been closed. It returns whether opening was locked
+pub fn allow_channel_reopen(storage: &mut dyn Storage, connection_id: &str) -> StdResult<bool> {
+    let locked = IBC_CHANNEL_REOPEN_LOCKS.has(storage, connection_id);
+    IBC_CHANNEL_REOPEN_LOCKS.remove(storage, connection_id);
+    Ok(locked)
+}
+
+/// get_reopen_locks returns the connections over which opening a new channel is locked, by
+/// connection id
+pub fn get_reopen_locks(storage: &dyn Storage) -> StdResult<Vec<String>> {
+    IBC_CHANNEL_REOPEN_LOCKS
+        .keys(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+/// migrate_reopen_lock moves the legacy contract-wide reopen lock, if set, onto the connection of
+/// the expected counterparty, if any. It returns the connection locked, if any
+pub(crate) fn migrate_reopen_lock(storage: &mut dyn Storage) -> StdResult<Option<String>> {
+    let locked = IBC_CHANNEL_REOPEN_LOCKED
+        .may_load(storage)?
+        .unwrap_or_default();
+    IBC_CHANNEL_REOPEN_LOCKED.remove(storage);
+    if !locked {
+        return Ok(None);
+    }
+    let Some(counterparty) = CONFIG.load(storage)?.babylon_counterparty else {
+        return Ok(None);
+    };
+    IBC_CHANNEL_REOPEN_LOCKS.save(storage, &counterparty.connection_id, &())?;
+    Ok(Some(counterparty.connection_id))
+}
+
+/// get_channels returns the open zoneconcierge channels, by channel id.
+/// A primary channel connected before channels were recorded is included, with a zero
+/// `created_height`
+pub fn get_channels(storage: &dyn Storage) -> StdResult<Vec<ChannelInfo>> {
+    let mut channels = CHANNELS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, info)| info))
+        .collect::<StdResult<Vec<_>>>()?;
+    if let Some(channel) = IBC_CHANNEL.may_load(storage)? {
+        if !CHANNELS.has(storage, &channel.endpoint.channel_id) {
+            channels.insert(
+                0,
+                ChannelInfo {
+                    channel,
+                    counterparty_chain_id: None,
+                    consumer_id: None,
+                    created_height: 0,
+                    last_active_time: None,
+                },
+            );
+        }
+    }
+    Ok(channels)
+}
 
-    // TODO: erase all contract state upon closing the channel
+/// update_channel sets the metadata of the given open channel
+pub fn update_channel(
+    storage: &mut dyn Storage,
+    channel_id: &str,
+    counterparty_chain_id: Option<String>,
+    consumer_id: Option<String>,
+) -> Result<ChannelInfo, ContractError> {
+    let mut info = get_channels(storage)?
+        .into_iter()
+        .find(|info| info.channel.endpoint.channel_id == channel_id)
+        .ok_or_else(|| ContractError::IbcChannelNotFound {
+            channel_id: channel_id.to_string(),
+        })?;
+    info.counterparty_chain_id = counterparty_chain_id;
+    info.consumer_id = consumer_id;
+    CHANNELS.save(storage, channel_id, &info)?;
+    Ok(info)
+}
+
+/// get_closed_channels returns the closed zoneconcierge channels, by channel id
+pub fn get_closed_channels(storage: &dyn Storage) -> StdResult<Vec<ClosedChannel>> {
+    CLOSED_CHANNELS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, closed)| closed))
+        .collect()
 }
 
 /// Invoked when an IBC packet is received
@@ -133,78 +558,199 @@ pub fn ibc_channel_close(
 /// https://github.com/CosmWasm/cosmwasm/blob/main/IBC.md#acknowledging-errors
 /// That's because we want to send an ACK for the packet regardless if there's an error or not,
 /// but in the case of an error, we do not want the state to be committed.
+/// Packets failing processing are recorded as dead letters, for reprocessing by the admin
 pub fn ibc_packet_receive(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse<BabylonMsg>, Never> {
-    // put this in a closure so we can convert all error responses into acknowledgements
-    (|| {
-        let packet = msg.packet;
-        // which local channel did this packet come on
-        let caller = packet.dest.channel_id;
-        let zc_packet_data =
-            ZoneconciergePacketData::decode(packet.data.as_slice()).map_err(|e| {
-                StdError::generic_err(format!("failed to decode ZoneconciergePacketData: {e}"))
-            })?;
-        let zc_packet = zc_packet_data
-            .packet
-            .ok_or(StdError::generic_err("empty IBC packet"))?;
-        match zc_packet {
-            Packet::BtcTimestamp(btc_ts) => ibc_packet::handle_btc_timestamp(deps, caller, &btc_ts),
-            Packet::BtcStaking(btc_staking) => {
-                ibc_packet::handle_btc_staking(deps, caller, &btc_staking)
-            }
-            Packet::ConsumerRegister(_) => Err(StdError::generic_err(
-                "ConsumerRegister packet should not be received",
-            )),
-            Packet::ConsumerSlashing(_) => Err(StdError::generic_err(
-                "ConsumerSlashing packet should not be received",
-            )),
-        }
-    })()
-    .or_else(|e| {
+    let packet = msg.packet;
+    // which local channel did this packet come on
+    let caller = packet.dest.channel_id;
+    let zc_packet = decode_packet(packet.data.as_slice());
+    let packet_type = zc_packet
+        .as_ref()
+        .map_or(PACKET_TYPE_INVALID, |(zc_packet, _)| packet_type(zc_packet));
+    let res = zc_packet.and_then(|(zc_packet, memo)| {
+        ensure_active_channel(deps.storage, &caller)?;
+        handle_packet(
+            deps.branch(),
+            caller.clone(),
+            packet.sequence,
+            zc_packet,
+            memo,
+        )
+    });
+    // Packet stats and channel activity are best effort, and never fail the packet
+    let _ = packet_stats::record_packet(deps.storage, packet_type, packet.data.len(), res.is_ok());
+    let _ = record_channel_activity(deps.storage, &env, &caller);
+    res.or_else(|e| {
         // we try to capture all app-level errors and convert them into
         // acknowledgement packets that contain an error code.
+        let event =
+            record_dead_letter(deps.storage, &env, caller, packet.sequence, packet.data, &e);
         Ok(
-            IbcReceiveResponse::new(StdAck::error(format!("invalid packet: {e}"))) // TODO: design error ack format
-                .add_event(Event::new("ibc").add_attribute("packet", "receive")),
+            IbcReceiveResponse::new(error_ack(e.code(), format!("invalid packet: {e}")))
+                .add_event(Event::new("ibc").add_attribute("packet", "receive"))
+                .add_event(event),
         )
     })
 }
 
+/// record_dead_letter records the given inbound packet as a dead letter, for reprocessing by the
+/// admin. Recording is best effort, and the returned event tells whether it succeeded
+fn record_dead_letter(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel_id: String,
+    sequence: u64,
+    data: Binary,
+    err: &ContractError,
+) -> Event {
+    let dead_letter = DeadLetter {
+        channel_id,
+        sequence,
+        error_code: err.code(),
+        error: err.to_string(),
+        packet_hash: packet_hash(data.as_slice()).into(),
+        data,
+        height: env.block.height,
+    };
+    let recorded = DEAD_LETTERS
+        .save(
+            storage,
+            (&dead_letter.channel_id, dead_letter.sequence),
+            &dead_letter,
+        )
+        .is_ok();
+    Event::new("ibc_packet_dead_letter")
+        .add_attribute("channel_id", &dead_letter.channel_id)
+        .add_attribute("sequence", dead_letter.sequence.to_string())
+        .add_attribute("error_code", dead_letter.error_code.to_string())
+        .add_attribute("packet_hash", hex::encode(&dead_letter.packet_hash))
+        .add_attribute("recorded", recorded.to_string())
+}
+
+/// process_packet decodes and handles the given inbound packet data, received over the given
+/// channel with the given sequence
+pub fn process_packet(
+    deps: DepsMut,
+    caller: String,
+    sequence: u64,
+    data: &[u8],
+) -> Result<IbcReceiveResponse<BabylonMsg>, ContractError> {
+    let (zc_packet, memo) = decode_packet(data)?;
+    handle_packet(deps, caller, sequence, zc_packet, memo)
+}
+
+/// Packet type of the packets that cannot be decoded
+pub const PACKET_TYPE_INVALID: &str = "invalid";
+
+/// decode_packet decodes the given (inbound or outbound) packet data into the packet and its
+/// memo, if any
+fn decode_packet(data: &[u8]) -> Result<(Packet, Option<PacketMemo>), ContractError> {
+    let zc_packet_data = ZoneconciergePacketData::decode(data).map_err(|e| {
+        StdError::generic_err(format!("failed to decode ZoneconciergePacketData: {e}"))
+    })?;
+    let zc_packet = zc_packet_data
+        .packet
+        .ok_or(StdError::generic_err("empty IBC packet"))?;
+    let memo = PacketMemoData::decode(data)
+        .map_err(|e| StdError::generic_err(format!("failed to decode packet memo: {e}")))?
+        .memo;
+    Ok((zc_packet, memo))
+}
+
+/// packet_type returns the name of the type of the given packet, for packet stats
+fn packet_type(zc_packet: &Packet) -> &'static str {
+    match zc_packet {
+        Packet::BtcTimestamp(_) => "btc_timestamp",
+        Packet::BtcStaking(_) => "btc_staking",
+        Packet::ConsumerRegister(_) => "consumer_register",
+        Packet::ConsumerSlashing(_) => "consumer_slashing",
+    }
+}
+
+/// handle_packet handles the given decoded inbound packet, received over the given channel with
+/// the given sequence, along with its memo
+fn handle_packet(
+    deps: DepsMut,
+    caller: String,
+    sequence: u64,
+    zc_packet: Packet,
+    memo: Option<PacketMemo>,
+) -> Result<IbcReceiveResponse<BabylonMsg>, ContractError> {
+    // The memo is validated before the packet is handled, so that an invalid memo fails the
+    // packet before any state is touched
+    let hook_msg = match memo {
+        Some(memo) => ibc_packet::ibc_hook_msg(deps.api, &caller, sequence, &memo)?,
+        None => None,
+    };
+    let mut resp = match zc_packet {
+        Packet::BtcTimestamp(btc_ts) => ibc_packet::handle_btc_timestamp(deps, caller, &btc_ts),
+        Packet::BtcStaking(btc_staking) => {
+            ibc_packet::handle_btc_staking(deps, caller, &btc_staking)
+        }
+        Packet::ConsumerRegister(_) => Err(ContractError::IbcUnsupportedMethod {}),
+        Packet::ConsumerSlashing(_) => Err(ContractError::IbcUnsupportedMethod {}),
+    }?;
+
+    // Forward to the ibc-hooks destination contract, if any, once the packet is handled.
+    // A failing call is reverted and recorded, without failing the packet
+    if let Some(hook_msg) = hook_msg {
+        resp = resp
+            .add_submessage(hook_msg)
+            .add_attribute("ibc_hooks", "wasm");
+    }
+
+    Ok(resp)
+}
+
 // Methods to handle PacketMsg variants
 pub(crate) mod ibc_packet {
     use super::*;
-    use crate::state::config::CONFIG;
-    use babylon_apis::btc_staking_api::SlashedBtcDelegation;
+    use crate::msg::btc_header::BtcHeaderResponse;
+    use crate::msg::ibc::{
+        BtcTimestampResponse, IbcHooksMemo, RejectedBtcStaking, ZoneconciergeAck,
+        IBC_HOOKS_MEMO_VERSION,
+    };
+    use crate::state::btc_light_client::get_tip;
+    use crate::state::config::{OnItemError, CONFIG};
+    use crate::state::staking_router;
     use babylon_apis::btc_staking_api::{
         ActiveBtcDelegation, BtcUndelegationInfo, CovenantAdaptorSignatures,
         FinalityProviderDescription, NewFinalityProvider, ProofOfPossessionBtc, SignatureInfo,
         UnbondedBtcDelegation,
     };
+    use babylon_apis::btc_staking_api::{BtcStakingResult, SlashedBtcDelegation};
     use babylon_apis::finality_api::Evidence;
+    use babylon_apis::legacy_dec_from_str;
     use babylon_proto::babylon::btcstaking::v1::BtcStakingIbcPacket;
     use babylon_proto::babylon::zoneconcierge::v1::zoneconcierge_packet_data::Packet::ConsumerSlashing;
     use babylon_proto::babylon::zoneconcierge::v1::ConsumerSlashingIbcPacket;
-    use cosmwasm_std::{to_json_binary, Decimal, IbcChannel, IbcMsg, WasmMsg};
-    use std::str::FromStr;
+    use cosmwasm_std::{
+        from_json, to_json_binary, Api, IbcChannel, Reply, Response, SubMsg, SubMsgResponse,
+        SubMsgResult, WasmMsg,
+    };
 
     pub fn handle_btc_timestamp(
         deps: DepsMut,
         _caller: String,
         btc_ts: &BtcTimestamp,
-    ) -> StdResult<IbcReceiveResponse<BabylonMsg>> {
+    ) -> Result<IbcReceiveResponse<BabylonMsg>, ContractError> {
         let storage = deps.storage;
         let cfg = CONFIG.load(storage)?;
 
         // handle the BTC timestamp, i.e., verify the BTC timestamp and update the contract state
         let msg_option = crate::state::handle_btc_timestamp(storage, btc_ts)?;
 
-        // construct response
-        let mut resp: IbcReceiveResponse<BabylonMsg> =
-            IbcReceiveResponse::new(StdAck::success(vec![])); // TODO: design response format
-                                                              // add attribute to response
+        // construct response, acknowledging the resulting BTC tip
+        let tip = get_tip(storage)?;
+        let ack = ZoneconciergeAck::BtcTimestamp(BtcTimestampResponse::from(
+            BtcHeaderResponse::try_from(&tip)?,
+        ));
+        let mut resp: IbcReceiveResponse<BabylonMsg> = IbcReceiveResponse::new(ack.to_std_ack()?);
+        // add attribute to response
         resp = resp.add_attribute("action", "receive_btc_timestamp");
 
         // if the BTC timestamp carries a Babylon message for the Cosmos zone, and
@@ -221,147 +767,310 @@ pub(crate) mod ibc_packet {
 
     pub fn handle_btc_staking(
         deps: DepsMut,
-        _caller: String,
+        caller: String,
         btc_staking: &BtcStakingIbcPacket,
-    ) -> StdResult<IbcReceiveResponse<BabylonMsg>> {
+    ) -> Result<IbcReceiveResponse<BabylonMsg>, ContractError> {
         let storage = deps.storage;
         let cfg = CONFIG.load(storage)?;
+        // Operations without a consumer of their own are routed along the channel's consumer
+        let channel_consumer = CHANNELS
+            .may_load(storage, &caller)?
+            .and_then(|info| info.consumer_id);
 
-        // Route the packet to the btc-staking contract
-        let btc_staking_addr = cfg
-            .btc_staking
-            .ok_or(StdError::generic_err("btc_staking contract not set"))?;
-
-        // Build the message to send to the BTC staking contract
-        let msg = babylon_apis::btc_staking_api::ExecuteMsg::BtcStaking {
-            new_fp: btc_staking
-                .new_fp
-                .iter()
-                .map(|fp| {
-                    Ok(NewFinalityProvider {
-                        description: fp
-                            .description
-                            .as_ref()
-                            .map(|d| FinalityProviderDescription {
-                                moniker: d.moniker.clone(),
-                                identity: d.identity.clone(),
-                                website: d.website.clone(),
-                                security_contact: d.security_contact.clone(),
-                                details: d.details.clone(),
-                            }),
-                        commission: Decimal::from_str(&fp.commission)?,
-                        addr: fp.addr.clone(),
-                        btc_pk_hex: fp.btc_pk_hex.clone(),
-                        pop: fp.pop.as_ref().map(|pop| ProofOfPossessionBtc {
-                            btc_sig_type: pop.btc_sig_type,
-                            btc_sig: pop.btc_sig.to_vec().into(),
+        // Convert the packet into BTC staking operations
+        let new_fp = btc_staking
+            .new_fp
+            .iter()
+            .map(|fp| {
+                Ok(NewFinalityProvider {
+                    description: fp
+                        .description
+                        .as_ref()
+                        .map(|d| FinalityProviderDescription {
+                            moniker: d.moniker.clone(),
+                            identity: d.identity.clone(),
+                            website: d.website.clone(),
+                            security_contact: d.security_contact.clone(),
+                            details: d.details.clone(),
                         }),
-                        consumer_id: fp.consumer_id.clone(),
-                    })
-                })
-                .collect::<StdResult<_>>()?,
-            active_del: btc_staking
-                .active_del
-                .iter()
-                .map(|d| {
-                    Ok(ActiveBtcDelegation {
-                        staker_addr: d.staker_addr.clone(),
-                        btc_pk_hex: d.btc_pk_hex.clone(),
-                        fp_btc_pk_list: d.fp_btc_pk_list.clone(),
-                        start_height: d.start_height,
-                        end_height: d.end_height,
-                        total_sat: d.total_sat,
-                        staking_tx: d.staking_tx.to_vec().into(),
-                        slashing_tx: d.slashing_tx.to_vec().into(),
-                        delegator_slashing_sig: d.delegator_slashing_sig.to_vec().into(),
-                        covenant_sigs: d
-                            .covenant_sigs
-                            .iter()
-                            .map(|s| CovenantAdaptorSignatures {
-                                cov_pk: s.cov_pk.to_vec().into(),
-                                adaptor_sigs: s
-                                    .adaptor_sigs
-                                    .iter()
-                                    .map(|a| a.to_vec().into())
-                                    .collect(),
-                            })
-                            .collect(),
-                        staking_output_idx: d.staking_output_idx,
-                        unbonding_time: d.unbonding_time,
-                        undelegation_info: d
-                            .undelegation_info
-                            .as_ref()
-                            .map(|ui| BtcUndelegationInfo {
-                                unbonding_tx: ui.unbonding_tx.to_vec().into(),
-                                delegator_unbonding_sig: ui.delegator_unbonding_sig.to_vec().into(),
-                                covenant_unbonding_sig_list: ui
-                                    .covenant_unbonding_sig_list
-                                    .iter()
-                                    .map(|s| SignatureInfo {
-                                        pk: s.pk.to_vec().into(),
-                                        sig: s.sig.to_vec().into(),
-                                    })
-                                    .collect(),
-                                slashing_tx: ui.slashing_tx.to_vec().into(),
-                                delegator_slashing_sig: ui.delegator_slashing_sig.to_vec().into(),
-                                covenant_slashing_sigs: ui
-                                    .covenant_slashing_sigs
-                                    .iter()
-                                    .map(|s| CovenantAdaptorSignatures {
-                                        cov_pk: s.cov_pk.to_vec().into(),
-                                        adaptor_sigs: s
-                                            .adaptor_sigs
-                                            .iter()
-                                            .map(|a| a.to_vec().into())
-                                            .collect(),
-                                    })
-                                    .collect(),
-                            })
-                            .ok_or(StdError::generic_err("undelegation info not set"))?,
-                        params_version: d.params_version,
-                    })
+                    commission: legacy_dec_from_str(&fp.commission)?,
+                    addr: fp.addr.clone(),
+                    btc_pk_hex: fp.btc_pk_hex.clone(),
+                    pop: fp.pop.as_ref().map(|pop| ProofOfPossessionBtc {
+                        btc_sig_type: pop.btc_sig_type,
+                        btc_sig: pop.btc_sig.to_vec().into(),
+                    }),
+                    consumer_id: fp.consumer_id.clone(),
                 })
-                .collect::<StdResult<_>>()?,
-            slashed_del: btc_staking
-                .slashed_del
-                .iter()
-                .map(|d| SlashedBtcDelegation {
-                    staking_tx_hash: d.staking_tx_hash.clone(),
-                    recovered_fp_btc_sk: d.recovered_fp_btc_sk.clone(),
+            })
+            .collect::<StdResult<_>>()?;
+        let active_del = btc_staking
+            .active_del
+            .iter()
+            .map(|d| {
+                Ok(ActiveBtcDelegation {
+                    staker_addr: d.staker_addr.clone(),
+                    btc_pk_hex: d.btc_pk_hex.clone(),
+                    fp_btc_pk_list: d.fp_btc_pk_list.clone(),
+                    start_height: d.start_height,
+                    end_height: d.end_height,
+                    total_sat: d.total_sat,
+                    staking_tx: d.staking_tx.to_vec().into(),
+                    slashing_tx: d.slashing_tx.to_vec().into(),
+                    delegator_slashing_sig: d.delegator_slashing_sig.to_vec().into(),
+                    covenant_sigs: d
+                        .covenant_sigs
+                        .iter()
+                        .map(|s| CovenantAdaptorSignatures {
+                            cov_pk: s.cov_pk.to_vec().into(),
+                            adaptor_sigs: s
+                                .adaptor_sigs
+                                .iter()
+                                .map(|a| a.to_vec().into())
+                                .collect(),
+                        })
+                        .collect(),
+                    staking_output_idx: d.staking_output_idx,
+                    unbonding_time: d.unbonding_time,
+                    undelegation_info: d
+                        .undelegation_info
+                        .as_ref()
+                        .map(|ui| BtcUndelegationInfo {
+                            unbonding_tx: ui.unbonding_tx.to_vec().into(),
+                            delegator_unbonding_sig: ui.delegator_unbonding_sig.to_vec().into(),
+                            covenant_unbonding_sig_list: ui
+                                .covenant_unbonding_sig_list
+                                .iter()
+                                .map(|s| SignatureInfo {
+                                    pk: s.pk.to_vec().into(),
+                                    sig: s.sig.to_vec().into(),
+                                })
+                                .collect(),
+                            slashing_tx: ui.slashing_tx.to_vec().into(),
+                            delegator_slashing_sig: ui.delegator_slashing_sig.to_vec().into(),
+                            covenant_slashing_sigs: ui
+                                .covenant_slashing_sigs
+                                .iter()
+                                .map(|s| CovenantAdaptorSignatures {
+                                    cov_pk: s.cov_pk.to_vec().into(),
+                                    adaptor_sigs: s
+                                        .adaptor_sigs
+                                        .iter()
+                                        .map(|a| a.to_vec().into())
+                                        .collect(),
+                                })
+                                .collect(),
+                        })
+                        .ok_or(StdError::generic_err("undelegation info not set"))?,
+                    params_version: d.params_version,
                 })
-                .collect(),
-            unbonded_del: btc_staking
-                .unbonded_del
-                .iter()
-                .map(|u| UnbondedBtcDelegation {
-                    staking_tx_hash: u.staking_tx_hash.clone(),
-                    unbonding_tx_sig: u.unbonding_tx_sig.to_vec().into(),
-                })
-                .collect(),
-        };
+            })
+            .collect::<StdResult<_>>()?;
+        let slashed_del = btc_staking
+            .slashed_del
+            .iter()
+            .map(|d| SlashedBtcDelegation {
+                staking_tx_hash: d.staking_tx_hash.clone(),
+                recovered_fp_btc_sk: d.recovered_fp_btc_sk.clone(),
+            })
+            .collect();
+        let unbonded_del = btc_staking
+            .unbonded_del
+            .iter()
+            .map(|u| UnbondedBtcDelegation {
+                staking_tx_hash: u.staking_tx_hash.clone(),
+                unbonding_tx_sig: u.unbonding_tx_sig.to_vec().into(),
+            })
+            .collect();
 
-        let wasm_msg = WasmMsg::Execute {
-            contract_addr: btc_staking_addr.to_string(),
-            msg: to_json_binary(&msg)?,
-            funds: vec![],
-        };
+        // Route the operations to the btc-staking contracts of their consumers
+        let msgs = staking_router::split_btc_staking(
+            storage,
+            &cfg,
+            channel_consumer.as_deref(),
+            new_fp,
+            active_del,
+            slashed_del,
+            unbonded_del,
+        )?;
 
-        // construct response
-        let mut resp: IbcReceiveResponse<BabylonMsg> =
-            IbcReceiveResponse::new(StdAck::success(vec![])); // TODO: design response format
-                                                              // add wasm message to response
-        resp = resp.add_message(wasm_msg);
+        // construct response.
+        // The acknowledgement is completed by the replies of the btc-staking contracts, with the
+        // operations they accept or reject
+        let ack = ZoneconciergeAck::BtcStaking(BtcStakingResponse::default());
+        let mut resp: IbcReceiveResponse<BabylonMsg> = IbcReceiveResponse::new(ack.to_std_ack()?);
+        // Without any routed operation, the acknowledgement is complete as is
+        if !msgs.is_empty() {
+            PENDING_STAKING_ACK.save(
+                storage,
+                &PendingStakingAck {
+                    ack: BtcStakingResponse::default(),
+                    pending: msgs.len() as u32,
+                },
+            )?;
+        }
+        // add wasm messages to response
+        for (addr, msg) in msgs {
+            let (fps, dels) = staking_router::batch_ops(&msg)?;
+            let batch = StakingBatch {
+                contract: addr.to_string(),
+                fps,
+                dels,
+            };
+            let wasm_msg = WasmMsg::Execute {
+                contract_addr: addr.to_string(),
+                msg: to_json_binary(&msg)?,
+                funds: vec![],
+            };
+            resp = resp.add_submessage(
+                SubMsg::reply_always(wasm_msg, REPLY_ID_BTC_STAKING)
+                    .with_payload(to_json_binary(&batch)?),
+            );
+        }
         // add attribute to response
         resp = resp.add_attribute("action", "receive_btc_staking");
 
         Ok(resp)
     }
 
+    /// reply_btc_staking accounts for the operations of a BTC staking packet accepted or rejected
+    /// by a btc-staking contract. The response data overrides the acknowledgement of the packet,
+    /// so that the last reply acknowledges the operations of all the btc-staking contracts.
+    /// Rejected operations are reverted, without failing the packet, unless `on_item_error` is
+    /// `Abort`, in which case any rejected or quarantined operation fails the reply, so that the
+    /// whole packet is reverted, along with the operations of the other btc-staking contracts
+    pub fn reply_btc_staking(
+        deps: DepsMut,
+        reply: Reply,
+    ) -> Result<Response<BabylonMsg>, ContractError> {
+        let batch: StakingBatch = from_json(&reply.payload)?;
+        let on_item_error = CONFIG.load(deps.storage)?.on_item_error;
+        let mut pending = PENDING_STAKING_ACK.load(deps.storage)?;
+        match reply.result {
+            SubMsgResult::Ok(resp) => {
+                let result: BtcStakingResult = match execute_response_data(resp)? {
+                    Some(data) => from_json(data)?,
+                    None => BtcStakingResult::default(),
+                };
+                if on_item_error == OnItemError::Abort && !result.quarantined.is_empty() {
+                    return Err(ContractError::BtcStakingAborted {
+                        contract: batch.contract,
+                        reason: format!("quarantined {}", result.quarantined.join(",")),
+                    });
+                }
+                pending.ack.add_accepted(&result);
+            }
+            SubMsgResult::Err(reason) if on_item_error == OnItemError::Abort => {
+                return Err(ContractError::BtcStakingAborted {
+                    contract: batch.contract,
+                    reason,
+                });
+            }
+            SubMsgResult::Err(reason) => {
+                pending.ack.rejected.push(RejectedBtcStaking {
+                    contract: batch.contract,
+                    fps: batch.fps,
+                    dels: batch.dels,
+                    reason,
+                });
+            }
+        }
+        pending.pending = pending.pending.saturating_sub(1);
+        if pending.pending == 0 {
+            PENDING_STAKING_ACK.remove(deps.storage);
+        } else {
+            PENDING_STAKING_ACK.save(deps.storage, &pending)?;
+        }
+        let ack = ZoneconciergeAck::BtcStaking(pending.ack).to_std_ack()?;
+        Ok(Response::new().set_data(ack))
+    }
+
+    /// execute_response_data returns the response data of an execute submessage
+    fn execute_response_data(resp: SubMsgResponse) -> StdResult<Option<Binary>> {
+        if let Some(msg_resp) = resp.msg_responses.first() {
+            let data = MsgExecuteContractResponse::decode(msg_resp.value.as_slice())
+                .map_err(|e| StdError::generic_err(format!("failed to decode response: {e}")))?
+                .data;
+            return Ok(Some(Binary::new(data)).filter(|data| !data.is_empty()));
+        }
+        // Chains running CosmWasm 1.x only fill the deprecated data field
+        #[allow(deprecated)]
+        Ok(resp.data)
+    }
+
+    /// ibc_hook_msg validates the memo of the inbound packet with the given channel and
+    /// sequence, and builds the execute message to its ibc-hooks destination contract, if any.
+    /// A failing execution is reverted, and recorded by `reply_ibc_hook`
+    pub fn ibc_hook_msg(
+        api: &dyn Api,
+        channel_id: &str,
+        sequence: u64,
+        memo: &PacketMemo,
+    ) -> Result<Option<SubMsg<BabylonMsg>>, ContractError> {
+        if memo.version != IBC_HOOKS_MEMO_VERSION {
+            return Err(ContractError::UnsupportedMemoVersion(memo.version));
+        }
+        if memo.memo.is_empty() {
+            return Ok(None);
+        }
+        let hook = match from_json::<IbcHooksMemo>(memo.memo.as_bytes())?.wasm {
+            Some(hook) => hook,
+            None => return Ok(None),
+        };
+        let contract_addr = api.addr_validate(&hook.contract)?;
+        let msg = WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_json_binary(&hook.msg)?,
+            funds: vec![],
+        };
+        let hook = IbcHook {
+            channel_id: channel_id.to_string(),
+            sequence,
+            contract: contract_addr.into(),
+        };
+        Ok(Some(
+            SubMsg::reply_on_error(msg, REPLY_ID_IBC_HOOK).with_payload(to_json_binary(&hook)?),
+        ))
+    }
+
+    /// reply_ibc_hook records a failed ibc-hooks call. The call is reverted, but the packet
+    /// stays handled and acknowledged
+    pub fn reply_ibc_hook(
+        deps: DepsMut,
+        env: Env,
+        reply: Reply,
+    ) -> Result<Response<BabylonMsg>, ContractError> {
+        let hook: IbcHook = from_json(&reply.payload)?;
+        let error = match reply.result {
+            SubMsgResult::Err(error) => error,
+            SubMsgResult::Ok(_) => return Ok(Response::new()),
+        };
+        let failure = IbcHookFailure {
+            channel_id: hook.channel_id,
+            sequence: hook.sequence,
+            contract: hook.contract,
+            error,
+            height: env.block.height,
+        };
+        IBC_HOOK_FAILURES.save(
+            deps.storage,
+            (&failure.channel_id, failure.sequence),
+            &failure,
+        )?;
+        let event = Event::new("ibc_hook_failed")
+            .add_attribute("channel_id", failure.channel_id)
+            .add_attribute("sequence", failure.sequence.to_string())
+            .add_attribute("contract", failure.contract)
+            .add_attribute("error", failure.error);
+        Ok(Response::new().add_event(event))
+    }
+
     pub fn slashing_msg(
+        storage: &dyn Storage,
         env: &Env,
         channel: &IbcChannel,
         evidence: &Evidence,
-    ) -> Result<IbcMsg, ContractError> {
+    ) -> Result<SubMsg<BabylonMsg>, ContractError> {
         let packet = ZoneconciergePacketData {
             packet: Some(ConsumerSlashing(ConsumerSlashingIbcPacket {
                 evidence: Some(babylon_proto::babylon::finality::v1::Evidence {
@@ -375,71 +1084,302 @@ pub(crate) mod ibc_packet {
                 }),
             })),
         };
-        let msg = IbcMsg::SendPacket {
-            channel_id: channel.endpoint.channel_id.clone(),
-            data: Binary::new(packet.encode_to_vec()),
-            timeout: packet_timeout(env),
-        };
+        let timeout = packet_timeout(env, &CONFIG.load(storage)?);
+        let msg = send_packet(
+            env,
+            &channel.endpoint.channel_id,
+            packet.encode_to_vec(),
+            timeout,
+            0,
+        )?;
         Ok(msg)
     }
 }
 
-const DEFAULT_TIMEOUT: u64 = 10 * 60;
-
-pub fn packet_timeout(env: &Env) -> IbcTimeout {
-    let timeout = env.block.time.plus_seconds(DEFAULT_TIMEOUT);
+/// packet_timeout returns the timeout of a new outbound packet
+pub fn packet_timeout(env: &Env, cfg: &Config) -> IbcTimeout {
+    let timeout = env
+        .block
+        .time
+        .plus_seconds(cfg.packet_timeout.timeout_seconds);
     IbcTimeout::with_timestamp(timeout)
 }
 
+fn packet_hash(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+/// send_packet returns the submessage sending the given outbound packet over the given channel.
+/// `attempts` is the number of times the packet timed out before.
+/// The packet is recorded as pending acknowledgement upon the reply, as its sequence is only
+/// assigned upon sending
+fn send_packet<T>(
+    env: &Env,
+    channel_id: &str,
+    data: Vec<u8>,
+    timeout: IbcTimeout,
+    attempts: u32,
+) -> StdResult<SubMsg<T>> {
+    let pending = PendingPacket {
+        packet_hash: packet_hash(&data).into(),
+        packet_type: outbound_packet_type(&data).to_string(),
+        channel_id: channel_id.to_string(),
+        sequence: 0,
+        height: env.block.height,
+        attempts,
+    };
+    let msg = IbcMsg::SendPacket {
+        channel_id: channel_id.to_string(),
+        data: Binary::new(data),
+        timeout,
+    };
+    Ok(SubMsg::reply_on_success(msg, REPLY_ID_SEND_PACKET).with_payload(to_json_binary(&pending)?))
+}
+
+/// reply_send_packet records the sent outbound packet as pending acknowledgement, under the
+/// sequence it was assigned
+pub fn reply_send_packet(
+    deps: DepsMut,
+    reply: Reply,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut pending: PendingPacket = from_json(&reply.payload)?;
+    let resp = reply.result.into_result().map_err(StdError::generic_err)?;
+    pending.sequence = send_packet_sequence(resp)?;
+    PENDING_PACKETS.save(
+        deps.storage,
+        (pending.channel_id.as_str(), pending.sequence),
+        &pending,
+    )?;
+    Ok(Response::new())
+}
+
+/// send_packet_sequence returns the sequence assigned to the packet of a send packet submessage
+fn send_packet_sequence(resp: SubMsgResponse) -> StdResult<u64> {
+    let data = match resp.msg_responses.first() {
+        Some(msg_resp) => msg_resp.value.clone(),
+        // Chains running CosmWasm 1.x only fill the deprecated data field
+        #[allow(deprecated)]
+        None => resp.data.unwrap_or_default(),
+    };
+    let sequence = MsgIbcSendResponse::decode(data.as_slice())
+        .map_err(|e| StdError::generic_err(format!("failed to decode response: {e}")))?
+        .sequence;
+    // Sequences start at 1
+    if sequence == 0 {
+        return Err(StdError::generic_err("missing packet sequence"));
+    }
+    Ok(sequence)
+}
+
+/// outbound_packet_type returns the name of the type of the given outbound packet data
+fn outbound_packet_type(data: &[u8]) -> &'static str {
+    decode_packet(data)
+        .as_ref()
+        .map_or(PACKET_TYPE_INVALID, |(zc_packet, _)| packet_type(zc_packet))
+}
+
+/// get_pending_packets returns the outbound packets pending acknowledgement, by channel and
+/// sequence
+pub fn get_pending_packets(storage: &dyn Storage) -> StdResult<Vec<PendingPacket>> {
+    PENDING_PACKETS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, pending)| pending))
+        .collect()
+}
+
+/// resubmit_packets resubmits the timed out packets over the given channel, with a timeout
+/// doubling upon every attempt. It returns the messages, along with the data of the packets.
+/// The resubmitted packets are pending acknowledgement again, along with their attempts
+fn resubmit_packets(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    channel: &IbcChannel,
+) -> StdResult<(Vec<SubMsg>, Vec<Binary>)> {
+    let resubmissions = PACKET_RESUBMISSIONS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    PACKET_RESUBMISSIONS.clear(storage);
+    resubmissions
+        .into_iter()
+        .map(|(_, resubmission)| {
+            let timeout_seconds = cfg.packet_timeout.timeout_seconds_at(resubmission.attempts);
+            let msg = send_packet(
+                env,
+                &channel.endpoint.channel_id,
+                resubmission.data.to_vec(),
+                IbcTimeout::with_timestamp(env.block.time.plus_seconds(timeout_seconds)),
+                resubmission.attempts,
+            )?;
+            Ok((msg, resubmission.data))
+        })
+        .collect::<StdResult<Vec<_>>>()
+        .map(|items| items.into_iter().unzip())
+}
+
+/// get_dead_letters returns the dead letters of the given channel, by sequence.
+/// `start_after` is the sequence of the dead letter to start after, or `None` to start from the
+/// beginning
+pub fn get_dead_letters(
+    storage: &dyn Storage,
+    channel_id: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<DeadLetter>> {
+    DEAD_LETTERS
+        .prefix(channel_id)
+        .range(
+            storage,
+            pagination::start_after(start_after),
+            None,
+            Order::Ascending,
+        )
+        .take(page_limit(limit))
+        .map(|item| item.map(|(_, dead_letter)| dead_letter))
+        .collect()
+}
+
+/// get_ibc_hook_failures returns the failed ibc-hooks calls of the given channel, by sequence of
+/// their inbound packet.
+/// `start_after` is the sequence to start after, or `None` to start from the beginning
+pub fn get_ibc_hook_failures(
+    storage: &dyn Storage,
+    channel_id: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<IbcHookFailure>> {
+    IBC_HOOK_FAILURES
+        .prefix(channel_id)
+        .range(
+            storage,
+            pagination::start_after(start_after),
+            None,
+            Order::Ascending,
+        )
+        .take(page_limit(limit))
+        .map(|item| item.map(|(_, failure)| failure))
+        .collect()
+}
+
+/// Invoked when an outbound packet is acknowledged.
+/// The packet is delivered, so it is not pending acknowledgement anymore.
+/// Error acknowledgements are final, i.e. the packet is not resubmitted, and are reported
+/// through the `ibc_packet_ack` event
 pub fn ibc_packet_ack(
-    _deps: DepsMut,
-    _env: Env,
-    _msg: IbcPacketAckMsg,
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketAckMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    Ok(IbcBasicResponse::default())
+    let packet = msg.original_packet;
+    record_channel_activity(deps.storage, &env, &packet.src.channel_id)?;
+    let hash = packet_hash(&packet.data);
+    PENDING_PACKETS.remove(deps.storage, (&packet.src.channel_id, packet.sequence));
+
+    let mut event = Event::new("ibc_packet_ack")
+        .add_attribute("channel_id", &packet.src.channel_id)
+        .add_attribute("sequence", packet.sequence.to_string())
+        .add_attribute("packet_hash", hex::encode(&hash))
+        .add_attribute("packet_type", outbound_packet_type(&packet.data));
+    event = match from_json::<StdAck>(&msg.acknowledgement.data) {
+        Ok(StdAck::Success(_)) => event.add_attribute("success", "true"),
+        Ok(StdAck::Error(err)) => event
+            .add_attribute("success", "false")
+            .add_attribute("error", err),
+        Err(_) => event
+            .add_attribute("success", "false")
+            .add_attribute("error", "invalid acknowledgement"),
+    };
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_packet_ack")
+        .add_event(event))
 }
 
+/// Invoked when an outbound packet times out.
+/// The timeout closes the (ordered) channel, so the packet is recorded for resubmission over the
+/// next channel, unless it has been resubmitted too many times already, in which case it is
+/// dropped
 pub fn ibc_packet_timeout(
-    _deps: DepsMut,
-    _env: Env,
+    deps: DepsMut,
+    env: Env,
     msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    // TODO: handle the timeout / error
-    Err(ContractError::IbcTimeout(
-        msg.packet.dest.channel_id,
-        msg.packet.dest.port_id,
-    ))
+    let cfg = CONFIG.load(deps.storage)?;
+    let packet = msg.packet;
+
+    // The channel is closed, so that a new one can be opened
+    close_channel(deps.storage, &env, &packet.src.channel_id, true)?;
+
+    let key = (packet.src.channel_id.as_str(), packet.sequence);
+    let attempts = PENDING_PACKETS
+        .may_load(deps.storage, key)?
+        .map_or(0, |pending| pending.attempts);
+    PENDING_PACKETS.remove(deps.storage, key);
+    let mut event = Event::new("ibc_packet_timeout")
+        .add_attribute("channel_id", &packet.src.channel_id)
+        .add_attribute("sequence", packet.sequence.to_string())
+        .add_attribute("packet_hash", hex::encode(packet_hash(&packet.data)));
+    if attempts >= cfg.packet_timeout.max_resubmissions {
+        event = event.add_attribute("dropped", "true");
+    } else {
+        let resubmission = PacketResubmission {
+            data: packet.data,
+            attempts: attempts + 1,
+        };
+        PACKET_RESUBMISSIONS.save(deps.storage, key, &resubmission)?;
+        event = event.add_attribute("attempts", resubmission.attempts.to_string());
+    }
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_packet_timeout")
+        .add_event(event))
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
-    use crate::contract::instantiate;
-    use crate::msg::contract::InstantiateMsg;
+    use crate::contract::tests::base_instantiate_msg;
+    use crate::contract::{instantiate, migrate};
+    use crate::msg::contract::MigrateMsg;
+    use crate::msg::ibc::{RejectedBtcStaking, ZoneconciergeAck};
+    use crate::state::config::{IbcCounterparty, OnItemError};
     use cosmwasm_std::testing::message_info;
     use cosmwasm_std::testing::{
-        mock_dependencies, mock_env, mock_ibc_channel_open_try, MockApi, MockQuerier, MockStorage,
+        mock_dependencies, mock_env, mock_ibc_channel, mock_ibc_channel_close_init,
+        mock_ibc_channel_connect_ack, mock_ibc_channel_open_try, mock_ibc_packet_ack,
+        mock_ibc_packet_recv, mock_ibc_packet_timeout, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        from_json, to_json_binary, Addr, ContractInfoResponse, ContractResult, CosmosMsg,
+        IbcAcknowledgement, OwnedDeps, StdAck, SystemResult, WasmMsg,
     };
-    use cosmwasm_std::OwnedDeps;
 
     const CREATOR: &str = "creator";
 
+    /// send_packet_reply returns the reply to the given send packet submessage, whose packet was
+    /// assigned the given sequence
+    pub(crate) fn send_packet_reply<T>(msg: &SubMsg<T>, sequence: u64) -> Reply {
+        let resp = MsgIbcSendResponse { sequence };
+        #[allow(deprecated)]
+        let result = cosmwasm_std::SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+            msg_responses: vec![cosmwasm_std::MsgResponse {
+                type_url: "/cosmwasm.wasm.v1.MsgIBCSendResponse".to_string(),
+                value: Binary::new(resp.encode_to_vec()),
+            }],
+        });
+        Reply {
+            id: msg.id,
+            payload: msg.payload.clone(),
+            gas_used: 0,
+            result,
+        }
+    }
+
     fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {
-            network: babylon_bitcoin::chain_params::Network::Regtest,
-            babylon_tag: "01020304".to_string(),
-            btc_confirmation_depth: 10,
-            checkpoint_finalization_timeout: 100,
-            notify_cosmos_zone: false,
-            btc_staking_code_id: None,
-            btc_staking_msg: None,
-            btc_finality_code_id: None,
-            btc_finality_msg: None,
-            admin: None,
-            consumer_name: None,
-            consumer_description: None,
-        };
+        let msg = base_instantiate_msg();
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
         let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
@@ -459,4 +1399,809 @@ mod tests {
         let valid_handshake = mock_ibc_channel_open_try("channel-12", IBC_ORDERING, IBC_VERSION);
         ibc_channel_open(deps.as_mut(), mock_env(), valid_handshake).unwrap();
     }
+
+    #[test]
+    fn enforce_counterparty_in_handshake() {
+        let mut deps = setup();
+
+        // Any counterparty is accepted by default
+        let handshake = mock_ibc_channel_open_try("channel-12", IBC_ORDERING, IBC_VERSION);
+        ibc_channel_open(deps.as_mut(), mock_env(), handshake.clone()).unwrap();
+        let channel = handshake.channel().clone();
+
+        // Unexpected connection
+        let mut counterparty = IbcCounterparty {
+            connection_id: "connection-7".to_string(),
+            port_id: channel.counterparty_endpoint.port_id.clone(),
+        };
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                babylon_counterparty: Some(counterparty.clone()),
+                ..MigrateMsg::default()
+            },
+        )
+        .unwrap();
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), handshake.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::IbcUnexpectedCounterparty {
+                connection_id: channel.connection_id.clone(),
+                port_id: channel.counterparty_endpoint.port_id.clone(),
+            }
+        );
+        let connect = mock_ibc_channel_connect_ack("channel-12", IBC_ORDERING, IBC_VERSION);
+        ibc_channel_connect(deps.as_mut(), mock_env(), connect.clone()).unwrap_err();
+
+        // Expected connection and port
+        counterparty.connection_id = channel.connection_id.clone();
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.babylon_counterparty = Some(counterparty);
+                Ok(cfg)
+            })
+            .unwrap();
+        ibc_channel_open(deps.as_mut(), mock_env(), handshake).unwrap();
+        let res = ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "connection_id" && a.value == channel.connection_id));
+        assert_eq!(IBC_CHANNEL.load(deps.as_ref().storage).unwrap(), channel);
+    }
+
+    #[test]
+    fn btc_staking_ack_reconciles_replies() {
+        use babylon_apis::btc_staking_api::BtcStakingResult;
+        use babylon_proto::babylon::btcstaking::v1::{BtcStakingIbcPacket, NewFinalityProvider};
+        use cosmwasm_std::{MsgResponse, Reply, SubMsgResponse, SubMsgResult};
+
+        let mut deps = setup();
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.btc_staking = Some(Addr::unchecked("btc_staking"));
+                Ok(cfg)
+            })
+            .unwrap();
+        let other = Addr::unchecked("other_btc_staking");
+        crate::state::staking_router::set_staking_route(
+            deps.as_mut().storage,
+            "consumer-2",
+            Some(&other),
+        )
+        .unwrap();
+
+        let new_fp = |btc_pk_hex: &str, consumer_id: &str| NewFinalityProvider {
+            commission: "50000000000000000".to_string(),
+            btc_pk_hex: btc_pk_hex.to_string(),
+            consumer_id: consumer_id.to_string(),
+            ..NewFinalityProvider::default()
+        };
+        let packet_data = ZoneconciergePacketData {
+            packet: Some(Packet::BtcStaking(BtcStakingIbcPacket {
+                new_fp: vec![new_fp("fp1", "consumer-1"), new_fp("fp2", "consumer-2")],
+                ..BtcStakingIbcPacket::default()
+            })),
+        }
+        .encode_to_vec();
+        let mut msg = mock_ibc_packet_recv("channel-1", &()).unwrap();
+        msg.packet.data = Binary::new(packet_data);
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        // The default contract accepts its operations, the other one rejects them
+        let result = BtcStakingResult {
+            new_fps: vec!["fp1".to_string()],
+            ..BtcStakingResult::default()
+        };
+        let data = MsgExecuteContractResponse {
+            data: to_json_binary(&result).unwrap().to_vec(),
+        };
+        #[allow(deprecated)]
+        let ok = SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+            msg_responses: vec![MsgResponse {
+                type_url: "/cosmwasm.wasm.v1.MsgExecuteContractResponse".to_string(),
+                value: Binary::new(data.encode_to_vec()),
+            }],
+        });
+        let replies = [ok, SubMsgResult::Err("code 801: invalid fp".to_string())];
+        let mut acks = vec![];
+        for (sub_msg, result) in res.messages.iter().zip(replies) {
+            let reply = Reply {
+                id: sub_msg.id,
+                payload: sub_msg.payload.clone(),
+                gas_used: 0,
+                result,
+            };
+            let res = crate::contract::reply(deps.as_mut(), mock_env(), reply).unwrap();
+            let ack: StdAck = from_json(res.data.unwrap()).unwrap();
+            acks.push(ZoneconciergeAck::from_std_ack(&ack).unwrap());
+        }
+
+        // The last reply acknowledges the operations of both contracts
+        assert_eq!(
+            acks.pop().unwrap(),
+            ZoneconciergeAck::BtcStaking(BtcStakingResponse {
+                fps_added: 1,
+                rejected: vec![RejectedBtcStaking {
+                    contract: other.to_string(),
+                    fps: vec!["fp2".to_string()],
+                    dels: vec![],
+                    reason: "code 801: invalid fp".to_string(),
+                }],
+                ..BtcStakingResponse::default()
+            })
+        );
+        assert!(PENDING_STAKING_ACK
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+
+        // In abort mode, rejected or quarantined operations fail the whole packet instead
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.on_item_error = OnItemError::Abort;
+                Ok(cfg)
+            })
+            .unwrap();
+        let quarantined = BtcStakingResult {
+            quarantined: vec!["fp1".to_string()],
+            ..BtcStakingResult::default()
+        };
+        let data = MsgExecuteContractResponse {
+            data: to_json_binary(&quarantined).unwrap().to_vec(),
+        };
+        #[allow(deprecated)]
+        let quarantined = SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+            msg_responses: vec![MsgResponse {
+                type_url: "/cosmwasm.wasm.v1.MsgExecuteContractResponse".to_string(),
+                value: Binary::new(data.encode_to_vec()),
+            }],
+        });
+        let failures = [
+            (
+                quarantined,
+                "btc_staking".to_string(),
+                "quarantined fp1".to_string(),
+            ),
+            (
+                SubMsgResult::Err("code 801: invalid fp".to_string()),
+                other.to_string(),
+                "code 801: invalid fp".to_string(),
+            ),
+        ];
+        for (idx, (result, contract, reason)) in failures.into_iter().enumerate() {
+            let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+            // The failing reply reverts the whole packet, along with the other contract's calls
+            let sub_msg = &res.messages[idx];
+            let reply = Reply {
+                id: sub_msg.id,
+                payload: sub_msg.payload.clone(),
+                gas_used: 0,
+                result,
+            };
+            let err = crate::contract::reply(deps.as_mut(), mock_env(), reply).unwrap_err();
+            assert_eq!(err, ContractError::BtcStakingAborted { contract, reason });
+        }
+    }
+
+    #[test]
+    fn channel_close_and_reopen_works() {
+        let mut deps = setup();
+        let env = mock_env();
+        let connect = mock_ibc_channel_connect_ack("channel-12", IBC_ORDERING, IBC_VERSION);
+        ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+
+        // Closing the channel removes it, and locks opening a new one
+        let close = mock_ibc_channel_close_init("channel-12", IBC_ORDERING, IBC_VERSION);
+        ibc_channel_close(deps.as_mut(), env.clone(), close).unwrap();
+        assert!(IBC_CHANNEL.may_load(&deps.storage).unwrap().is_none());
+        let closed = crate::queries::closed_channels(deps.as_ref()).unwrap();
+        assert_eq!(
+            closed.channels,
+            vec![ClosedChannel {
+                channel_id: "channel-12".to_string(),
+                height: env.block.height,
+                timed_out: false,
+            }]
+        );
+        assert_eq!(closed.reopen_locked, vec!["connection-2".to_string()]);
+
+        let handshake = mock_ibc_channel_open_try("channel-13", IBC_ORDERING, IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), env.clone(), handshake.clone()).unwrap_err();
+        assert_eq!(err, ContractError::IbcChannelReopenLocked {});
+        let connect = mock_ibc_channel_connect_ack("channel-13", IBC_ORDERING, IBC_VERSION);
+        let err = ibc_channel_connect(deps.as_mut(), env.clone(), connect.clone()).unwrap_err();
+        assert_eq!(err, ContractError::IbcChannelReopenLocked {});
+
+        // Packets received over the closed channel are rejected
+        let packet_data = ZoneconciergePacketData {
+            packet: Some(Packet::BtcTimestamp(BtcTimestamp::default())),
+        }
+        .encode_to_vec();
+        let mut msg = mock_ibc_packet_recv("channel-12", &()).unwrap();
+        msg.packet.data = Binary::new(packet_data);
+        let res = ibc_packet_receive(deps.as_mut(), env.clone(), msg).unwrap();
+        let ack: StdAck = from_json(res.acknowledgement.unwrap()).unwrap();
+        let ZoneconciergeAck::Error { code, .. } = ZoneconciergeAck::from_std_ack(&ack).unwrap()
+        else {
+            panic!("unexpected acknowledgement");
+        };
+        assert_eq!(code, 308);
+
+        // Once the admin approves it, a new channel can be opened
+        assert!(allow_channel_reopen(deps.as_mut().storage, "connection-2").unwrap());
+        ibc_channel_open(deps.as_mut(), env.clone(), handshake).unwrap();
+        ibc_channel_connect(deps.as_mut(), env, connect).unwrap();
+        let closed = crate::queries::closed_channels(deps.as_ref()).unwrap();
+        assert_eq!(closed.channels.len(), 1);
+        assert!(closed.reopen_locked.is_empty());
+    }
+
+    #[test]
+    fn migrate_reopen_lock_works() {
+        let mut deps = setup();
+        IBC_CHANNEL_REOPEN_LOCKED
+            .save(deps.as_mut().storage, &true)
+            .unwrap();
+
+        // Without an expected counterparty, the legacy lock is dropped
+        assert_eq!(migrate_reopen_lock(deps.as_mut().storage).unwrap(), None);
+        assert!(get_reopen_locks(&deps.storage).unwrap().is_empty());
+
+        // Otherwise, it moves onto the connection of the counterparty
+        IBC_CHANNEL_REOPEN_LOCKED
+            .save(deps.as_mut().storage, &true)
+            .unwrap();
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.babylon_counterparty = Some(IbcCounterparty {
+                    connection_id: "connection-7".to_string(),
+                    port_id: "zoneconcierge".to_string(),
+                });
+                Ok(cfg)
+            })
+            .unwrap();
+        assert_eq!(
+            migrate_reopen_lock(deps.as_mut().storage).unwrap(),
+            Some("connection-7".to_string())
+        );
+        assert_eq!(
+            get_reopen_locks(&deps.storage).unwrap(),
+            vec!["connection-7".to_string()]
+        );
+        assert!(IBC_CHANNEL_REOPEN_LOCKED
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+        assert_eq!(migrate_reopen_lock(deps.as_mut().storage).unwrap(), None);
+    }
+
+    #[test]
+    fn multiple_channels_work() {
+        let mut deps = setup();
+        let env = mock_env();
+        let channel = |channel_id: &str, connection_id: &str| {
+            let mut channel = mock_ibc_channel(channel_id, IBC_ORDERING, IBC_VERSION);
+            channel.connection_id = connection_id.to_string();
+            channel
+        };
+        for (channel_id, connection_id) in
+            [("channel-1", "connection-1"), ("channel-2", "connection-2")]
+        {
+            let connect =
+                IbcChannelConnectMsg::new_ack(channel(channel_id, connection_id), IBC_VERSION);
+            ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+        }
+
+        // One channel per connection
+        let open = IbcChannelOpenMsg::new_try(channel("channel-3", "connection-1"), IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), env.clone(), open).unwrap_err();
+        assert_eq!(err, ContractError::IbcChannelAlreadyOpen {});
+
+        // The first channel is the primary one
+        let channels = crate::queries::channels(deps.as_ref()).unwrap().channels;
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[1].channel, channel("channel-2", "connection-2"));
+        assert_eq!(channels[1].created_height, env.block.height);
+        assert_eq!(
+            IBC_CHANNEL.load(&deps.storage).unwrap(),
+            channel("channel-1", "connection-1")
+        );
+        ensure_active_channel(&deps.storage, "channel-2").unwrap();
+        let err = ensure_active_channel(&deps.storage, "channel-9").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::IbcStaleChannel {
+                channel_id: "channel-9".to_string()
+            }
+        );
+
+        // BTC staking operations are routed along the consumer of their channel
+        let err = update_channel(deps.as_mut().storage, "channel-9", None, None).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::IbcChannelNotFound {
+                channel_id: "channel-9".to_string()
+            }
+        );
+        let info = update_channel(
+            deps.as_mut().storage,
+            "channel-2",
+            Some("bbn-test-5".to_string()),
+            Some("consumer-2".to_string()),
+        )
+        .unwrap();
+        assert_eq!(info.consumer_id.as_deref(), Some("consumer-2"));
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.btc_staking = Some(Addr::unchecked("btc_staking"));
+                Ok(cfg)
+            })
+            .unwrap();
+        crate::state::staking_router::set_staking_route(
+            deps.as_mut().storage,
+            "consumer-2",
+            Some(&Addr::unchecked("other_btc_staking")),
+        )
+        .unwrap();
+        let packet_data = ZoneconciergePacketData {
+            packet: Some(Packet::BtcStaking(Default::default())),
+        }
+        .encode_to_vec();
+        for (channel_id, contract) in [
+            ("channel-1", "btc_staking"),
+            ("channel-2", "other_btc_staking"),
+        ] {
+            let mut msg = mock_ibc_packet_recv(channel_id, &()).unwrap();
+            msg.packet.data = Binary::new(packet_data.clone());
+            let res = ibc_packet_receive(deps.as_mut(), env.clone(), msg).unwrap();
+            let CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) = &res.messages[0].msg
+            else {
+                panic!("unexpected message");
+            };
+            assert_eq!(contract_addr, contract);
+        }
+
+        // Closing the primary channel promotes another one
+        let close = IbcChannelCloseMsg::new_init(channel("channel-1", "connection-1"));
+        ibc_channel_close(deps.as_mut(), env.clone(), close).unwrap();
+        let channels = crate::queries::channels(deps.as_ref()).unwrap().channels;
+        assert_eq!(channels, vec![info]);
+        assert_eq!(
+            IBC_CHANNEL.load(&deps.storage).unwrap(),
+            channel("channel-2", "connection-2")
+        );
+
+        // Only reopening over the connection of the closed channel is locked
+        let open = IbcChannelOpenMsg::new_try(channel("channel-3", "connection-1"), IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), env.clone(), open).unwrap_err();
+        assert_eq!(err, ContractError::IbcChannelReopenLocked {});
+        let connect =
+            IbcChannelConnectMsg::new_ack(channel("channel-4", "connection-3"), IBC_VERSION);
+        ibc_channel_connect(deps.as_mut(), env, connect).unwrap();
+        update_channel(deps.as_mut().storage, "channel-2", None, None).unwrap();
+        let closed = crate::queries::closed_channels(deps.as_ref()).unwrap();
+        assert_eq!(closed.reopen_locked, vec!["connection-1".to_string()]);
+    }
+
+    #[test]
+    fn channel_keepalive_works() {
+        let mut deps = setup();
+        let mut env = mock_env();
+        let admin = deps.api.addr_make(CREATOR);
+        let info = message_info(&admin, &[]);
+        deps.querier.update_wasm(move |_| {
+            let contract_info =
+                ContractInfoResponse::new(1, admin.clone(), Some(admin.clone()), false, None);
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&contract_info).unwrap()))
+        });
+        let check = |deps: DepsMut, env: &Env| {
+            crate::contract::execute(
+                deps,
+                env.clone(),
+                info.clone(),
+                crate::msg::contract::ExecuteMsg::CheckChannels {},
+            )
+            .unwrap()
+        };
+        for channel_id in ["channel-1", "channel-2"] {
+            let mut channel = mock_ibc_channel(channel_id, IBC_ORDERING, IBC_VERSION);
+            channel.connection_id = format!("connection-{channel_id}");
+            let connect = IbcChannelConnectMsg::new_ack(channel, IBC_VERSION);
+            ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+        }
+
+        // The keepalive is disabled by default
+        env.block.time = env.block.time.plus_days(365);
+        let res = check(deps.as_mut(), &env);
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            crate::queries::channels(deps.as_ref())
+                .unwrap()
+                .channels
+                .len(),
+            2
+        );
+
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.packet_timeout.keepalive_seconds = 60;
+                Ok(cfg)
+            })
+            .unwrap();
+
+        // Inbound packets keep a channel alive
+        let packet_data = ZoneconciergePacketData {
+            packet: Some(Packet::BtcStaking(Default::default())),
+        }
+        .encode_to_vec();
+        let mut msg = mock_ibc_packet_recv("channel-2", &()).unwrap();
+        msg.packet.data = Binary::new(packet_data);
+        ibc_packet_receive(deps.as_mut(), env.clone(), msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(60);
+        let res = check(deps.as_mut(), &env);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Ibc(IbcMsg::CloseChannel {
+                channel_id: "channel-1".to_string()
+            })
+        );
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.events[0].ty, "ibc_channel_stale");
+
+        // Only the admin can check channels
+        let anyone = message_info(&deps.api.addr_make("anyone"), &[]);
+        let err = crate::contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone,
+            crate::msg::contract::ExecuteMsg::CheckChannels {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }), "{err}");
+
+        // The stale channel is closed right away
+        let channels = crate::queries::channels(deps.as_ref()).unwrap().channels;
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].channel.endpoint.channel_id, "channel-2");
+        assert_eq!(
+            IBC_CHANNEL.load(&deps.storage).unwrap(),
+            channels[0].channel
+        );
+        let err = ensure_active_channel(&deps.storage, "channel-1").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::IbcStaleChannel {
+                channel_id: "channel-1".to_string()
+            }
+        );
+
+        // Its channel close handshake does not lock opening a new channel over its connection
+        let mut channel = mock_ibc_channel("channel-1", IBC_ORDERING, IBC_VERSION);
+        channel.connection_id = "connection-channel-1".to_string();
+        ibc_channel_close(
+            deps.as_mut(),
+            env.clone(),
+            IbcChannelCloseMsg::new_init(channel),
+        )
+        .unwrap();
+        assert!(get_reopen_locks(&deps.storage).unwrap().is_empty());
+
+        // Acknowledgements keep a channel alive too
+        let ack = mock_ibc_packet_ack(
+            "channel-2",
+            &"packet",
+            IbcAcknowledgement::new(StdAck::success(b"ok")),
+        )
+        .unwrap();
+        env.block.time = env.block.time.plus_seconds(60);
+        ibc_packet_ack(deps.as_mut(), env.clone(), ack).unwrap();
+        env.block.time = env.block.time.plus_seconds(60);
+        assert!(check(deps.as_mut(), &env).messages.is_empty());
+        env.block.time = env.block.time.plus_seconds(1);
+        assert_eq!(check(deps.as_mut(), &env).messages.len(), 1);
+        assert!(crate::queries::channels(deps.as_ref())
+            .unwrap()
+            .channels
+            .is_empty());
+    }
+
+    #[test]
+    fn pending_packets_work() {
+        let mut deps = setup();
+        let env = mock_env();
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.consumer_name = Some("consumer".to_string());
+                cfg.consumer_description = Some("a consumer".to_string());
+                Ok(cfg)
+            })
+            .unwrap();
+        let connect = mock_ibc_channel_connect_ack("channel-12", IBC_ORDERING, IBC_VERSION);
+        let res = ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+        let CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) = &res.messages[0].msg else {
+            panic!("unexpected message: {:?}", res.messages[0].msg);
+        };
+
+        // The consumer register packet is pending acknowledgement once sent
+        assert!(get_pending_packets(&deps.storage).unwrap().is_empty());
+        let reply = send_packet_reply(&res.messages[0], 1);
+        crate::contract::reply(deps.as_mut(), env.clone(), reply).unwrap();
+        let pending = get_pending_packets(&deps.storage).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].packet_hash, Binary::new(packet_hash(data)));
+        assert_eq!(pending[0].packet_type, "consumer_register");
+        assert_eq!(pending[0].channel_id, "channel-12");
+        assert_eq!(pending[0].sequence, 1);
+        assert_eq!(pending[0].height, env.block.height);
+        assert_eq!(pending[0].attempts, 0);
+
+        // An identical packet in flight is pending on its own
+        let timeout = packet_timeout(&env, &CONFIG.load(&deps.storage).unwrap());
+        let msg: SubMsg = send_packet(&env, "channel-12", data.to_vec(), timeout, 0).unwrap();
+        crate::contract::reply(deps.as_mut(), env.clone(), send_packet_reply(&msg, 2)).unwrap();
+        assert_eq!(get_pending_packets(&deps.storage).unwrap().len(), 2);
+
+        // An error ack removes it, and is reported
+        let mut ack = mock_ibc_packet_ack(
+            "channel-12",
+            &"packet",
+            IbcAcknowledgement::new(StdAck::error("consumer already registered")),
+        )
+        .unwrap();
+        ack.original_packet.data = data.clone();
+        ack.original_packet.sequence = 1;
+        let res = ibc_packet_ack(deps.as_mut(), env, ack).unwrap();
+        let pending = get_pending_packets(&deps.storage).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].sequence, 2);
+        let attrs = &res.events[0].attributes;
+        assert!(attrs
+            .iter()
+            .any(|a| a.key == "packet_type" && a.value == "consumer_register"));
+        assert!(attrs
+            .iter()
+            .any(|a| a.key == "success" && a.value == "false"));
+        assert!(attrs
+            .iter()
+            .any(|a| a.key == "error" && a.value == "consumer already registered"));
+    }
+
+    #[test]
+    fn packet_timeout_resubmission_works() {
+        let mut deps = setup();
+        let env = mock_env();
+        let connect = mock_ibc_channel_connect_ack("channel-12", IBC_ORDERING, IBC_VERSION);
+        ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+
+        // The timeout closes the channel, and records the packet for resubmission
+        let mut timeout = mock_ibc_packet_timeout("channel-12", &"packet").unwrap();
+        let data = timeout.packet.data.clone();
+        ibc_packet_timeout(deps.as_mut(), env.clone(), timeout.clone()).unwrap();
+        assert!(IBC_CHANNEL.may_load(&deps.storage).unwrap().is_none());
+        let resubmission = PACKET_RESUBMISSIONS
+            .load(&deps.storage, ("channel-12", timeout.packet.sequence))
+            .unwrap();
+        assert_eq!(resubmission.attempts, 1);
+
+        // The packet is resubmitted over the next channel, with a doubled timeout, and is pending
+        // acknowledgement again, along with its attempts
+        let connect = mock_ibc_channel_connect_ack("channel-13", IBC_ORDERING, IBC_VERSION);
+        let res = ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id: "channel-13".to_string(),
+                data: data.clone(),
+                timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(2 * 10 * 60)),
+            })
+        );
+        let reply = send_packet_reply(&res.messages[0], 1);
+        crate::contract::reply(deps.as_mut(), env.clone(), reply).unwrap();
+        assert!(PACKET_RESUBMISSIONS.is_empty(&deps.storage));
+        let pending = PENDING_PACKETS
+            .load(&deps.storage, ("channel-13", 1))
+            .unwrap();
+        assert_eq!(pending.attempts, 1);
+
+        // The packet is dropped after the maximum number of resubmissions
+        for attempts in 2..=3 {
+            let channel_id = format!("channel-{}", 11 + attempts);
+            timeout.packet.src.channel_id = channel_id.clone();
+            timeout.packet.sequence = 1;
+            ibc_packet_timeout(deps.as_mut(), env.clone(), timeout.clone()).unwrap();
+            let resubmission = PACKET_RESUBMISSIONS
+                .load(&deps.storage, (&channel_id, 1))
+                .unwrap();
+            assert_eq!(resubmission.attempts, attempts);
+
+            let next_channel_id = format!("channel-{}", 12 + attempts);
+            let connect = mock_ibc_channel_connect_ack(&next_channel_id, IBC_ORDERING, IBC_VERSION);
+            let res = ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+            let reply = send_packet_reply(&res.messages[0], 1);
+            crate::contract::reply(deps.as_mut(), env.clone(), reply).unwrap();
+        }
+        timeout.packet.src.channel_id = "channel-15".to_string();
+        let res = ibc_packet_timeout(deps.as_mut(), env.clone(), timeout).unwrap();
+        assert!(res.events[0]
+            .attributes
+            .iter()
+            .any(|a| a.key == "dropped" && a.value == "true"));
+        assert!(PACKET_RESUBMISSIONS.is_empty(&deps.storage));
+        assert!(PENDING_PACKETS.is_empty(&deps.storage));
+    }
+
+    #[test]
+    fn ibc_hooks_memo_works() {
+        let deps = mock_dependencies();
+        let memo = |version, memo: &str| PacketMemo {
+            version,
+            memo: memo.to_string(),
+        };
+
+        // Empty memo, or memo without a wasm hook
+        let msg = ibc_packet::ibc_hook_msg(&deps.api, "channel-1", 1, &memo(1, "")).unwrap();
+        assert!(msg.is_none());
+        let msg = ibc_packet::ibc_hook_msg(&deps.api, "channel-1", 1, &memo(1, "{}")).unwrap();
+        assert!(msg.is_none());
+
+        // Memo with a wasm hook
+        let hub = deps.api.addr_make("hub");
+        let hook_memo = format!(r#"{{"wasm":{{"contract":"{hub}","msg":{{"notify":{{}}}}}}}}"#);
+        let msg = ibc_packet::ibc_hook_msg(&deps.api, "channel-1", 1, &memo(1, &hook_memo))
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg.id, REPLY_ID_IBC_HOOK);
+        assert_eq!(msg.reply_on, cosmwasm_std::ReplyOn::Error);
+        assert_eq!(
+            msg.msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: hub.to_string(),
+                msg: Binary::from(br#"{"notify":{}}"#),
+                funds: vec![],
+            })
+        );
+
+        // Unsupported memo version
+        let err =
+            ibc_packet::ibc_hook_msg(&deps.api, "channel-1", 1, &memo(2, &hook_memo)).unwrap_err();
+        assert_eq!(err, ContractError::UnsupportedMemoVersion(2));
+
+        // Invalid destination contract
+        let invalid = r#"{"wasm":{"contract":"invalid","msg":{}}}"#;
+        ibc_packet::ibc_hook_msg(&deps.api, "channel-1", 1, &memo(1, invalid)).unwrap_err();
+    }
+
+    #[test]
+    fn invalid_memo_fails_packet_before_handling() {
+        let mut deps = setup();
+
+        // The memo is appended to the packet data, as its own field
+        let mut packet_data = ZoneconciergePacketData {
+            packet: Some(Packet::BtcStaking(Default::default())),
+        }
+        .encode_to_vec();
+        let memo = PacketMemoData {
+            memo: Some(PacketMemo {
+                version: 1,
+                memo: "not json".to_string(),
+            }),
+        };
+        packet_data.extend(memo.encode_to_vec());
+        let mut msg = mock_ibc_packet_recv("channel-1", &()).unwrap();
+        msg.packet.data = Binary::new(packet_data);
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack: StdAck = from_json(res.acknowledgement.unwrap()).unwrap();
+        assert!(!ack.is_success());
+        assert!(res.messages.is_empty());
+
+        // The packet was not handled
+        assert!(PENDING_STAKING_ACK
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn failed_ibc_hooks_are_recorded() {
+        let mut deps = setup();
+
+        let hook = IbcHook {
+            channel_id: "channel-1".to_string(),
+            sequence: 27,
+            contract: deps.api.addr_make("hub").to_string(),
+        };
+        #[allow(deprecated)]
+        let reply = cosmwasm_std::Reply {
+            id: REPLY_ID_IBC_HOOK,
+            payload: to_json_binary(&hook).unwrap(),
+            gas_used: 0,
+            result: cosmwasm_std::SubMsgResult::Err("hook failed".to_string()),
+        };
+        ibc_packet::reply_ibc_hook(deps.as_mut(), mock_env(), reply).unwrap();
+
+        let failures =
+            crate::queries::ibc_hook_failures(deps.as_ref(), "channel-1".to_string(), None, None)
+                .unwrap()
+                .failures;
+        assert_eq!(
+            failures,
+            vec![IbcHookFailure {
+                channel_id: "channel-1".to_string(),
+                sequence: 27,
+                contract: hook.contract,
+                error: "hook failed".to_string(),
+                height: mock_env().block.height,
+            }]
+        );
+    }
+
+    #[test]
+    fn failed_packets_are_dead_lettered() {
+        let mut deps = setup();
+
+        // Consumer registration packets are not supported on the consumer side
+        let packet_data = ZoneconciergePacketData {
+            packet: Some(Packet::ConsumerRegister(
+                ConsumerRegisterIbcPacket::default(),
+            )),
+        }
+        .encode_to_vec();
+        let mut msg = mock_ibc_packet_recv("channel-1", &()).unwrap();
+        msg.packet.data = Binary::new(packet_data.clone());
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack: StdAck = from_json(res.acknowledgement.unwrap()).unwrap();
+        assert_eq!(
+            ZoneconciergeAck::from_std_ack(&ack).unwrap(),
+            ZoneconciergeAck::error(
+                ContractError::IbcUnsupportedMethod {}.code(),
+                format!("invalid packet: {}", ContractError::IbcUnsupportedMethod {})
+            )
+        );
+
+        // The failure is accounted for in the packet stats
+        let stats = crate::queries::packet_stats(deps.as_ref()).unwrap().stats;
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].packet_type, "consumer_register");
+        assert_eq!(stats[0].stats.failures, 1);
+        assert_eq!(stats[0].stats.last_sizes, vec![packet_data.len() as u64]);
+
+        let sequence = 27; // sequence of mock packets
+        let dead_letter =
+            crate::queries::dead_letter(deps.as_ref(), "channel-1".to_string(), sequence).unwrap();
+        assert_eq!(
+            dead_letter.error_code,
+            ContractError::IbcUnsupportedMethod {}.code()
+        );
+        assert_eq!(dead_letter.data, Binary::new(packet_data.clone()));
+        assert_eq!(
+            dead_letter.packet_hash,
+            Binary::new(packet_hash(&packet_data))
+        );
+        assert_eq!(dead_letter.height, mock_env().block.height);
+
+        let dead_letters =
+            crate::queries::dead_letters(deps.as_ref(), "channel-1".to_string(), None, None)
+                .unwrap();
+        assert_eq!(dead_letters.dead_letters, vec![dead_letter]);
+        let dead_letters = crate::queries::dead_letters(
+            deps.as_ref(),
+            "channel-1".to_string(),
+            Some(sequence),
+            None,
+        )
+        .unwrap();
+        assert!(dead_letters.dead_letters.is_empty());
+        let dead_letters =
+            crate::queries::dead_letters(deps.as_ref(), "channel-2".to_string(), None, None)
+                .unwrap();
+        assert!(dead_letters.dead_letters.is_empty());
+    }
 }
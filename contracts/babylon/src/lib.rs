@@ -1,16 +1,18 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    Binary, Deps, DepsMut, Empty, Env, IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg,
+    Binary, Deps, DepsMut, Env, IbcBasicResponse, IbcChannelCloseMsg, IbcChannelConnectMsg,
     IbcChannelOpenMsg, IbcChannelOpenResponse, IbcPacketAckMsg, IbcPacketReceiveMsg,
     IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo, Never, Reply, Response, StdResult,
 };
 
+use babylon_apis::state_hash::with_state_hash;
 use babylon_bindings::BabylonMsg;
 
 use crate::error::ContractError;
 pub use crate::msg::contract::ExecuteMsg;
 use crate::msg::contract::InstantiateMsg;
+use crate::state::state_hash::SECTIONS;
 
 mod bindings;
 pub mod contract;
@@ -20,6 +22,8 @@ pub mod msg;
 #[cfg(test)]
 mod multitest;
 mod queries;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
 pub mod state;
 mod utils;
 
@@ -30,12 +34,14 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response<BabylonMsg>, ContractError> {
-    contract::instantiate(deps, env, info, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        contract::instantiate(deps, env, info, msg)
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response<BabylonMsg>, ContractError> {
-    contract::reply(deps, env, reply)
+    with_state_hash(deps, SECTIONS, |deps| contract::reply(deps, env, reply))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -44,8 +50,12 @@ pub fn query(deps: Deps, env: Env, msg: msg::contract::QueryMsg) -> Result<Binar
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, env: Env, msg: Empty) -> Result<Response<BabylonMsg>, ContractError> {
-    contract::migrate(deps, env, msg)
+pub fn migrate(
+    deps: DepsMut,
+    env: Env,
+    msg: msg::contract::MigrateMsg,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    with_state_hash(deps, SECTIONS, |deps| contract::migrate(deps, env, msg))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -55,7 +65,9 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<BabylonMsg>, ContractError> {
-    contract::execute(deps, env, info, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        contract::execute(deps, env, info, msg)
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -64,7 +76,7 @@ pub fn ibc_channel_open(
     env: Env,
     msg: IbcChannelOpenMsg,
 ) -> Result<IbcChannelOpenResponse, error::ContractError> {
-    ibc::ibc_channel_open(deps, env, msg)
+    with_state_hash(deps, SECTIONS, |deps| ibc::ibc_channel_open(deps, env, msg))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -73,7 +85,9 @@ pub fn ibc_channel_connect(
     env: Env,
     msg: IbcChannelConnectMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    ibc::ibc_channel_connect(deps, env, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        ibc::ibc_channel_connect(deps, env, msg)
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -82,7 +96,9 @@ pub fn ibc_channel_close(
     env: Env,
     msg: IbcChannelCloseMsg,
 ) -> StdResult<IbcBasicResponse> {
-    ibc::ibc_channel_close(deps, env, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        ibc::ibc_channel_close(deps, env, msg)
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -91,7 +107,9 @@ pub fn ibc_packet_receive(
     env: Env,
     msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse<BabylonMsg>, Never> {
-    ibc::ibc_packet_receive(deps, env, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        ibc::ibc_packet_receive(deps, env, msg)
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -100,7 +118,7 @@ pub fn ibc_packet_ack(
     env: Env,
     msg: IbcPacketAckMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    ibc::ibc_packet_ack(deps, env, msg)
+    with_state_hash(deps, SECTIONS, |deps| ibc::ibc_packet_ack(deps, env, msg))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -109,5 +127,7 @@ pub fn ibc_packet_timeout(
     env: Env,
     msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    ibc::ibc_packet_timeout(deps, env, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        ibc::ibc_packet_timeout(deps, env, msg)
+    })
 }
@@ -178,6 +178,31 @@ pub struct BtcHeaderResponse {
     pub cum_work: cosmwasm_std::Uint256,
 }
 
+/// Trusted Bitcoin header, to bootstrap the BTC light client from.
+#[cw_serde]
+pub struct BtcBaseHeader {
+    /// The Bitcoin header.
+    pub header: BtcHeader,
+    /// The height of the block in the BTC blockchain.
+    pub height: u64,
+    /// The cumulative total work of this block and all of its ancestors.
+    pub cum_work: cosmwasm_std::Uint256,
+}
+
+impl BtcBaseHeader {
+    pub fn to_btc_header_info(&self) -> Result<BtcHeaderInfo, BTCLightclientError> {
+        let block_header: BlockHeader = (&self.header).try_into()?;
+        Ok(BtcHeaderInfo {
+            header: ::prost::bytes::Bytes::from(babylon_bitcoin::serialize(&block_header)),
+            hash: ::prost::bytes::Bytes::from(babylon_bitcoin::serialize(
+                &block_header.block_hash(),
+            )),
+            height: self.height,
+            work: prost::bytes::Bytes::from(self.cum_work.to_string()),
+        })
+    }
+}
+
 /// Bitcoin header responses.
 ///
 /// Vector of `BtcHeaderResponse`.
@@ -432,3 +457,26 @@ mod tests {
         );
     }
 }
+
+/// BtcHeaderRetentionResponse is the retention of the BTC headers stored in the contract
+#[cw_serde]
+pub struct BtcHeaderRetentionResponse {
+    /// earliest_height is the height of the earliest BTC header retained
+    pub earliest_height: u64,
+    /// tip_height is the height of the BTC tip
+    pub tip_height: u64,
+    /// max_stored_headers is the number of headers retained below (and including) the tip, or
+    /// zero if all headers are retained
+    pub max_stored_headers: u64,
+    /// min_retained_headers is the minimum number of headers retained below (and including) the
+    /// tip, whatever `max_stored_headers`
+    pub min_retained_headers: u64,
+}
+
+/// BtcHeaderRelayersResponse is the list of relayers allowed to submit BTC headers directly
+#[cw_serde]
+pub struct BtcHeaderRelayersResponse {
+    /// relayers are the relayer addresses, in address order.
+    /// Anyone can submit BTC headers if empty
+    pub relayers: Vec<cosmwasm_std::Addr>,
+}
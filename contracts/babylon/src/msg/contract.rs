@@ -1,22 +1,41 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Binary, StdError, StdResult};
 
+use babylon_apis::chain_registry::known_network;
 use babylon_apis::finality_api::Evidence;
 
-use crate::msg::btc_header::BtcHeader;
+use crate::error::ContractError;
+use crate::msg::btc_header::{BtcBaseHeader, BtcHeader};
+use crate::state::config::{
+    BabylonTag, CzHeaderStorage, IbcCounterparty, OnItemError, PacketTimeoutConfig,
+};
 #[cfg(not(target_arch = "wasm32"))]
 use {
-    crate::msg::btc_header::{BtcHeaderResponse, BtcHeadersResponse},
+    crate::ibc::DeadLetter,
+    crate::msg::btc_header::{
+        BtcHeaderRelayersResponse, BtcHeaderResponse, BtcHeaderRetentionResponse,
+        BtcHeadersResponse,
+    },
     crate::msg::cz_header::CzHeaderResponse,
-    crate::msg::epoch::EpochResponse,
+    crate::msg::epoch::{CheckpointResponse, EpochResponse, PendingEpochsResponse},
+    crate::msg::ibc::{
+        ChannelsResponse, ClosedChannelsResponse, DeadLettersResponse, IbcHookFailuresResponse,
+        PacketStatsResponse, PendingPacketsResponse,
+    },
+    crate::msg::migration::MigrationProgressResponse,
+    crate::msg::staking::StakingRoutesResponse,
+    crate::msg::state_hash::StateHashResponse,
+    crate::msg::work::PendingWorkResponse,
     crate::state::config::Config,
+    babylon_apis::error::ErrorCodesResponse,
+    cosmwasm_std::IbcChannel,
 };
 
 const BABYLON_TAG_BYTES: usize = 4;
 
 // Common functions for contract messages
 pub trait ContractMsg {
-    fn validate(&self) -> StdResult<()>;
+    fn validate(&self) -> Result<(), ContractError>;
     fn babylon_tag_to_bytes(&self) -> StdResult<Vec<u8>>;
 }
 
@@ -26,6 +45,10 @@ pub struct InstantiateMsg {
     /// babylon_tag is a string encoding four bytes used for identification / tagging of the Babylon zone.
     /// NOTE: this is a hex string, not raw bytes
     pub babylon_tag: String,
+    /// babylon_tags are additional babylon tags accepted within a BTC height range, e.g. to
+    /// support a tag rotation on Babylon.
+    /// The main `babylon_tag` is always accepted
+    pub babylon_tags: Option<Vec<BabylonTagMsg>>,
     pub btc_confirmation_depth: u64,
     pub checkpoint_finalization_timeout: u64,
     /// notify_cosmos_zone indicates whether to send Cosmos zone messages notifying BTC-finalised
@@ -33,9 +56,11 @@ pub struct InstantiateMsg {
     /// NOTE: If set to true, then the Cosmos zone needs to integrate the corresponding message handler
     /// as well
     pub notify_cosmos_zone: bool,
-    /// If set, this will instantiate a BTC staking contract for BTC re-staking
+    /// If set, this will instantiate a BTC staking contract for BTC re-staking.
+    /// Requires `btc_staking_msg`
     pub btc_staking_code_id: Option<u64>,
-    /// If set, this will define the instantiation message for the BTC staking contract.
+    /// The instantiation message for the BTC staking contract, set if and only if
+    /// `btc_staking_code_id` is.
     /// This message is opaque to the Babylon contract, and depends on the specific staking contract
     /// being instantiated
     pub btc_staking_msg: Option<Binary>,
@@ -52,34 +77,238 @@ pub struct InstantiateMsg {
     pub consumer_name: Option<String>,
     /// Description of the consumer
     pub consumer_description: Option<String>,
+    /// cz_header_storage is the storage mode of finalised CZ headers.
+    /// Defaults to `Full`
+    pub cz_header_storage: Option<CzHeaderStorage>,
+    /// babylon_counterparty is the expected counterparty of the zoneconcierge channel.
+    /// If set, channels from other counterparties are refused
+    pub babylon_counterparty: Option<IbcCounterparty>,
+    /// packet_timeout is the timeout and resubmission policy of outbound IBC packets.
+    /// Defaults to a 10 minutes timeout, and up to 3 resubmissions
+    pub packet_timeout: Option<PacketTimeoutConfig>,
+    /// max_stored_headers is the number of BTC headers retained below (and including) the tip,
+    /// deeper ones being pruned. Defaults to zero, i.e. all headers are retained
+    pub max_stored_headers: Option<u64>,
+    /// on_item_error is how the failures of BTC staking operations of inbound packets are
+    /// handled. Defaults to `SkipAndReport`
+    pub on_item_error: Option<OnItemError>,
+    /// btc_base_header is an optional trusted BTC header to bootstrap the BTC light client from,
+    /// instead of the first BTC headers sent by Babylon
+    pub btc_base_header: Option<BtcBaseHeader>,
+    /// btc_headers are optional BTC headers extending `btc_base_header`, verified at instantiation
+    pub btc_headers: Option<Vec<BtcHeader>>,
+    /// babylon_network is the name or chain id of a known Babylon network (see
+    /// `babylon_apis::chain_registry`), e.g. `mainnet`.
+    /// If set, the network, babylon tag and checkpoint parameters are validated against it
+    pub babylon_network: Option<String>,
 }
 
-impl ContractMsg for InstantiateMsg {
-    fn validate(&self) -> StdResult<()> {
-        if self.babylon_tag.len() != BABYLON_TAG_BYTES * 2 {
+#[cw_serde]
+#[derive(Default)]
+pub struct MigrateMsg {
+    /// If set, this replaces the additional babylon tags and the expiry of the main
+    /// `babylon_tag`, e.g. to rotate the tag on Babylon
+    pub babylon_tags: Option<BabylonTagsMsg>,
+    /// If set, this switches the storage mode of finalised CZ headers.
+    /// Switching to `HashOnly` prunes the CZ headers already stored, while switching to `Full`
+    /// only affects the CZ headers finalised afterwards
+    pub cz_header_storage: Option<CzHeaderStorage>,
+    /// If set, this sets the expected counterparty of the zoneconcierge channel
+    pub babylon_counterparty: Option<IbcCounterparty>,
+    /// If set, this sets the timeout and resubmission policy of outbound IBC packets
+    pub packet_timeout: Option<PacketTimeoutConfig>,
+    /// If set, this sets the number of BTC headers retained below (and including) the tip (see
+    /// `InstantiateMsg::max_stored_headers`)
+    pub max_stored_headers: Option<u64>,
+    /// If set, this sets how the failures of BTC staking operations of inbound packets are
+    /// handled
+    pub on_item_error: Option<OnItemError>,
+    /// If set, the migration is only rehearsed: it is checked against the current state, and its
+    /// summary (`MigrationSummary`) is returned as the response data, but no state is changed
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// MigrationSummary is the summary of a rehearsed migration (see `MigrateMsg::dry_run`)
+#[cw_serde]
+pub struct MigrationSummary {
+    /// stored_version is the contract version stored before the migration, if any
+    pub stored_version: Option<String>,
+    /// code_version is the contract version of the code migrated to
+    pub code_version: String,
+    /// written_keys is the number of state entries the migration would add or update
+    pub written_keys: u64,
+    /// removed_keys is the number of state entries the migration would remove
+    pub removed_keys: u64,
+    /// scheduled_migrations are the storage migrations the migration would schedule (see
+    /// `ExecuteMsg::ContinueMigration`)
+    pub scheduled_migrations: Vec<String>,
+}
+
+/// BabylonTagMsg is a babylon tag accepted for checkpoints submitted within a BTC height range
+#[cw_serde]
+pub struct BabylonTagMsg {
+    /// tag is a string encoding four bytes. Same format as `babylon_tag`
+    pub tag: String,
+    /// start_height is the first BTC height the tag is accepted at
+    pub start_height: u64,
+    /// end_height is the last BTC height the tag is accepted at, or `None` if open-ended
+    pub end_height: Option<u64>,
+}
+
+impl BabylonTagMsg {
+    pub fn to_babylon_tag(&self) -> StdResult<BabylonTag> {
+        if self.tag.len() != BABYLON_TAG_BYTES * 2 {
             return Err(StdError::invalid_data_size(
                 BABYLON_TAG_BYTES * 2,
-                self.babylon_tag.len(),
+                self.tag.len(),
             ));
         }
-        let _ = self.babylon_tag_to_bytes()?;
+        let tag = hex::decode(&self.tag).map_err(|_| {
+            StdError::generic_err(format!(
+                "babylon tag is not a valid hex string: {}",
+                self.tag
+            ))
+        })?;
+        if let Some(end_height) = self.end_height {
+            if end_height < self.start_height {
+                return Err(StdError::generic_err(format!(
+                    "Invalid babylon tag height range: [{}, {}]",
+                    self.start_height, end_height
+                )));
+            }
+        }
+        Ok(BabylonTag {
+            tag,
+            start_height: self.start_height,
+            end_height: self.end_height,
+        })
+    }
+}
+
+/// BabylonTagsMsg sets the babylon tags accepted along with the main `babylon_tag`, and the
+/// expiry of the main tag
+#[cw_serde]
+pub struct BabylonTagsMsg {
+    /// babylon_tag_end_height is the last BTC height the main `babylon_tag` is accepted at, or
+    /// `None` if it never expires.
+    /// Expiring the main tag requires an open-ended babylon tag to take over
+    pub babylon_tag_end_height: Option<u64>,
+    /// babylon_tags are the additional babylon tags, replacing the configured ones
+    pub babylon_tags: Vec<BabylonTagMsg>,
+}
+
+impl BabylonTagsMsg {
+    pub fn to_babylon_tags(&self) -> Result<Vec<BabylonTag>, ContractError> {
+        let tags = self
+            .babylon_tags
+            .iter()
+            .map(BabylonTagMsg::to_babylon_tag)
+            .collect::<StdResult<Vec<_>>>()?;
+        if self.babylon_tag_end_height.is_some() && !tags.iter().any(|t| t.end_height.is_none()) {
+            return Err(ContractError::InvalidConfig {
+                msg: "expiring the main babylon tag requires an open-ended babylon tag".to_string(),
+            });
+        }
+        Ok(tags)
+    }
+}
+
+impl InstantiateMsg {
+    /// new returns the instantiation message of a contract with the given network, babylon tag
+    /// and checkpoint parameters, leaving all the optional settings unset
+    pub fn new(
+        network: babylon_bitcoin::chain_params::Network,
+        babylon_tag: &str,
+        btc_confirmation_depth: u64,
+        checkpoint_finalization_timeout: u64,
+    ) -> Self {
+        InstantiateMsg {
+            network,
+            babylon_tag: babylon_tag.to_string(),
+            babylon_tags: None,
+            btc_confirmation_depth,
+            checkpoint_finalization_timeout,
+            notify_cosmos_zone: false,
+            btc_staking_code_id: None,
+            btc_staking_msg: None,
+            btc_finality_code_id: None,
+            btc_finality_msg: None,
+            admin: None,
+            consumer_name: None,
+            consumer_description: None,
+            cz_header_storage: None,
+            babylon_counterparty: None,
+            packet_timeout: None,
+            max_stored_headers: None,
+            on_item_error: None,
+            btc_base_header: None,
+            btc_headers: None,
+            babylon_network: None,
+        }
+    }
+
+    pub fn babylon_tags(&self) -> StdResult<Vec<BabylonTag>> {
+        self.babylon_tags
+            .iter()
+            .flatten()
+            .map(BabylonTagMsg::to_babylon_tag)
+            .collect()
+    }
+}
+
+impl ContractMsg for InstantiateMsg {
+    fn validate(&self) -> Result<(), ContractError> {
+        if self.babylon_tag.len() != BABYLON_TAG_BYTES * 2 || self.babylon_tag_to_bytes().is_err() {
+            return Err(ContractError::InvalidBabylonTag {
+                tag: self.babylon_tag.clone(),
+            });
+        }
+        let _ = self.babylon_tags()?;
+
+        if self.btc_confirmation_depth == 0 {
+            return Err(ContractError::InvalidBtcConfirmationDepth {});
+        }
+        if self.checkpoint_finalization_timeout <= self.btc_confirmation_depth {
+            return Err(ContractError::InvalidCheckpointFinalizationTimeout {
+                timeout: self.checkpoint_finalization_timeout,
+                btc_confirmation_depth: self.btc_confirmation_depth,
+            });
+        }
+        if self.btc_staking_code_id.is_some() != self.btc_staking_msg.is_some() {
+            return Err(ContractError::BtcStakingMsgMismatch {});
+        }
+
+        if let Some(babylon_network) = &self.babylon_network {
+            known_network(babylon_network)?.validate(
+                &self.network,
+                &self.babylon_tag,
+                self.btc_confirmation_depth,
+                self.checkpoint_finalization_timeout,
+            )?;
+        }
+
+        if self.btc_base_header.is_none() && self.btc_headers.is_some() {
+            return Err(StdError::generic_err("BTC headers require a BTC base header").into());
+        }
 
         if self.btc_staking_code_id.is_some() {
             if let (Some(consumer_name), Some(consumer_description)) =
                 (&self.consumer_name, &self.consumer_description)
             {
                 if consumer_name.trim().is_empty() {
-                    return Err(StdError::generic_err("Consumer name cannot be empty"));
+                    return Err(StdError::generic_err("Consumer name cannot be empty").into());
                 }
                 if consumer_description.trim().is_empty() {
-                    return Err(StdError::generic_err(
-                        "Consumer description cannot be empty",
-                    ));
+                    return Err(
+                        StdError::generic_err("Consumer description cannot be empty").into(),
+                    );
                 }
             } else {
                 return Err(StdError::generic_err(
                     "Consumer name and description are required when btc_staking_code_id is set",
-                ));
+                )
+                .into());
             }
         }
 
@@ -98,6 +327,12 @@ impl ContractMsg for InstantiateMsg {
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// `btc_headers` submits BTC headers directly, instead of through Babylon's IBC packets.
+    /// The headers are verified against the stored header chain (including their proof of work
+    /// and difficulty), and may switch the main chain to a fork with more work.
+    ///
+    /// Only the BTC header relayers can submit headers, or anyone if there are none (see the
+    /// `BtcHeaderRelayers` query)
     BtcHeaders {
         /// `headers` is a list of BTC headers. Typically:
         /// - A given delta of headers a user wants to add to the tip or fork of the BTC chain.
@@ -108,6 +343,82 @@ pub enum ExecuteMsg {
     /// This will be forwarded over IBC to the Babylon side for propagation to other Consumers, and
     /// Babylon itself
     Slashing { evidence: Evidence },
+    /// `slash_finality_provider` slashes a finality provider on the Consumer only, for
+    /// misbehaviour that Babylon's slashing evidence does not cover (e.g. public randomness reuse
+    /// across heights).
+    ///
+    /// Only the `btc_finality` contract can send it
+    SlashFinalityProvider { fp_btc_pk_hex: String },
+    /// `jail_finality_provider` jails a finality provider on the Consumer, e.g. for missing too
+    /// many consecutive blocks.
+    ///
+    /// Only the `btc_finality` contract can send it
+    JailFinalityProvider { fp_btc_pk_hex: String },
+    /// `reset_base_header` re-anchors the BTC light client at the given trusted base header,
+    /// removing all the current BTC headers, e.g. to catch up after a prolonged downtime.
+    ///
+    /// Only the Wasm admin of the contract can reset the base header
+    ResetBaseHeader { header_info: BtcBaseHeader },
+    /// `set_staking_route` routes the BTC staking operations of the given consumer to the given
+    /// btc-staking contract, or removes the route if `contract` is not set, so that they go to
+    /// the default `btc_staking` contract.
+    ///
+    /// Only the Wasm admin of the contract can set routes
+    SetStakingRoute {
+        consumer_id: String,
+        contract: Option<String>,
+    },
+    /// `reprocess_packet` processes again an inbound packet that failed processing (see the
+    /// `DeadLetters` query), e.g. after a fix or migration.
+    /// The packet is removed from the dead letters upon success.
+    ///
+    /// Only the Wasm admin of the contract can reprocess packets
+    ReprocessPacket { channel_id: String, sequence: u64 },
+    /// `allow_channel_reopen` approves the opening of a new zoneconcierge channel over the given
+    /// connection, after its previous one has been closed through a channel close handshake (see
+    /// the `ClosedChannels` query).
+    ///
+    /// Only the Wasm admin of the contract can approve it
+    AllowChannelReopen { connection_id: String },
+    /// `check_channels` closes the open zoneconcierge channels without any inbound packet or
+    /// acknowledgement for longer than the keepalive (see `PacketTimeoutConfig::keepalive_seconds`),
+    /// e.g. as the relayer or Babylon went away. It is meant to be called periodically.
+    /// Closing a stale channel does not lock opening a new one over its connection.
+    ///
+    /// Only the Wasm admin of the contract can check channels
+    CheckChannels {},
+    /// `update_channel` sets the metadata of an open zoneconcierge channel (see the `Channels`
+    /// query), i.e. the chain id of its Babylon counterparty, and its consumer id.
+    ///
+    /// Only the Wasm admin of the contract can update channels
+    UpdateChannel {
+        channel_id: String,
+        counterparty_chain_id: Option<String>,
+        consumer_id: Option<String>,
+    },
+    /// `continue_migration` migrates up to `limit` entries of the first pending storage migration
+    /// (see the `MigrationProgress` query). The state section of a migration is paused until the
+    /// migration is complete.
+    ///
+    /// Anyone can continue a migration
+    ContinueMigration { limit: u32 },
+    /// `prune_headers` prunes up to `limit` of the earliest BTC headers, retaining the `retain`
+    /// headers below (and including) the tip, e.g. to clean up after enabling header retention.
+    /// `retain` defaults to the configured `max_stored_headers` (if set), and is never less than
+    /// `checkpoint_finalization_timeout` plus a safety margin (see the `BtcHeaderRetention` query).
+    ///
+    /// Only the Wasm admin of the contract can prune headers
+    PruneHeaders { retain: Option<u64>, limit: u32 },
+    /// `update_btc_header_relayers` adds and removes relayers allowed to submit BTC headers
+    /// directly through `btc_headers`.
+    ///
+    /// Only the Wasm admin of the contract can update the relayers
+    UpdateBtcHeaderRelayers {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
 }
 
 #[cw_serde]
@@ -130,15 +441,24 @@ pub enum QueryMsg {
     /// `hash` is the (byte-reversed) hex-encoded hash of the BTC header
     #[returns(BtcHeaderResponse)]
     BtcHeaderByHash { hash: String },
-    /// BtcHeaders returns the canonical BTC chain stored in the contract.
+    /// BtcHeaders returns the canonical BTC chain stored in the contract, i.e. the main chain
+    /// from the base header to the tip (as `QueryMainChain` of Babylon's `btclightclient` module).
     ///
     /// `start_after` is the height of the header to start after, or `None` to start from the base
+    /// (from the tip, if `reverse` is set)
     #[returns(BtcHeadersResponse)]
     BtcHeaders {
         start_after: Option<u64>,
         limit: Option<u32>,
         reverse: Option<bool>,
     },
+    /// BtcHeaderRetention returns the retention of the BTC headers stored in the contract,
+    /// including the height of the earliest header retained
+    #[returns(BtcHeaderRetentionResponse)]
+    BtcHeaderRetention {},
+    /// BtcHeaderRelayers returns the relayers allowed to submit BTC headers directly
+    #[returns(BtcHeaderRelayersResponse)]
+    BtcHeaderRelayers {},
     /// BabylonBaseEpoch returns the base Babylon epoch stored in the contract
     #[returns(EpochResponse)]
     BabylonBaseEpoch {},
@@ -151,10 +471,95 @@ pub enum QueryMsg {
     /// BabylonCheckpoint returns the Babylon checkpoint stored in the contract, by epoch number.
     #[returns(EpochResponse)]
     BabylonCheckpoint { epoch_number: u64 },
+    /// PendingEpochs returns the epochs whose checkpoints have been seen on BTC, but are not yet
+    /// `btc_confirmation_depth` deep, together with the BTC height at which they will be confirmed
+    #[returns(PendingEpochsResponse)]
+    PendingEpochs {},
+    /// ParseCheckpoint extracts and decodes a Babylon checkpoint from the OP_RETURN outputs of raw
+    /// BTC transactions, using the configured babylon tag(s).
+    ///
+    /// `tx_bytes` are the two serialized BTC txs carrying the first and second part of the
+    /// checkpoint, in that order
+    #[returns(CheckpointResponse)]
+    ParseCheckpoint { tx_bytes: Vec<Binary> },
     /// CzLastHeader returns the last CZ epoch stored in the contract
     #[returns(CzHeaderResponse)]
     CzLastHeader {},
     /// CzHeader returns the CZ header stored in the contract, by CZ height.
     #[returns(CzHeaderResponse)]
     CzHeader { height: u64 },
+    /// CzNextHeader returns the first CZ header stored in the contract at or above the given CZ
+    /// height, if any.
+    /// As CZ headers are only stored once BTC-finalised, it is the header BTC-finalising the CZ
+    /// ledger at that height, and its `babylon_epoch` is the Babylon epoch timestamping it
+    #[returns(Option<CzHeaderResponse>)]
+    CzNextHeader { height: u64 },
+    /// ErrorCodes returns the table of error codes of the contract, together with their
+    /// descriptions.
+    ///
+    /// Error acknowledgements are prefixed by the error code, i.e. `code <code>: <message>`, and
+    /// decode into `ZoneconciergeAck::Error` (see `babylon_apis::zoneconcierge_api`)
+    #[returns(ErrorCodesResponse)]
+    ErrorCodes {},
+    /// StateHash returns a deterministic digest over the contract's state, by state section
+    /// (see `babylon_apis::state_hash`).
+    ///
+    /// It can be used to compare the state of two deployments, e.g. after a migration
+    #[returns(StateHashResponse)]
+    StateHash {},
+    /// StakingRoutes returns the btc-staking contract of each consumer with a dedicated route
+    #[returns(StakingRoutesResponse)]
+    StakingRoutes {},
+    /// IbcChannel returns the primary zoneconcierge channel (if connected), i.e. the one outbound
+    /// packets are sent over, including its connection and counterparty endpoint
+    #[returns(Option<IbcChannel>)]
+    IbcChannel {},
+    /// Channels returns the open zoneconcierge channels, along with their metadata
+    #[returns(ChannelsResponse)]
+    Channels {},
+    /// ClosedChannels returns the closed zoneconcierge channels, and whether opening a new channel
+    /// is locked until the admin approves it
+    #[returns(ClosedChannelsResponse)]
+    ClosedChannels {},
+    /// PendingPackets returns the outbound packets sent and pending acknowledgement
+    #[returns(PendingPacketsResponse)]
+    PendingPackets {},
+    /// DeadLetter returns an inbound packet that failed processing, by channel id and sequence
+    #[returns(DeadLetter)]
+    DeadLetter { channel_id: String, sequence: u64 },
+    /// DeadLetters returns the inbound packets of the given channel that failed processing, and
+    /// are pending reprocessing.
+    ///
+    /// `start_after` is the sequence of the packet to start after, or `None` to start from the
+    /// beginning
+    #[returns(DeadLettersResponse)]
+    DeadLetters {
+        channel_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// IbcHookFailures returns the ibc-hooks calls into the destination contracts of the memos
+    /// of the packets of the given channel that failed, and were reverted.
+    ///
+    /// `start_after` is the sequence of the packet to start after, or `None` to start from the
+    /// beginning
+    #[returns(IbcHookFailuresResponse)]
+    IbcHookFailures {
+        channel_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// PacketStats returns the processing stats of the inbound packets, by packet type: the
+    /// number of packets and failures, their cumulative size, and the sizes of the most recent
+    /// ones
+    #[returns(PacketStatsResponse)]
+    PacketStats {},
+    /// MigrationProgress returns the progress of the scheduled storage migrations
+    #[returns(MigrationProgressResponse)]
+    MigrationProgress {},
+    /// PendingWork summarises the actionable items for off-chain actors: the pending storage
+    /// migrations, the outbound packets awaiting relay or resubmission, the inbound packets
+    /// pending reprocessing, and the epochs awaiting BTC confirmation
+    #[returns(PendingWorkResponse)]
+    PendingWork {},
 }
@@ -90,6 +90,24 @@ impl From<RawCheckpoint> for CheckpointResponse {
     }
 }
 
+/// PendingEpochResponse is an epoch whose checkpoint has been seen on BTC, but is not
+/// `btc_confirmation_depth` deep yet
+#[cw_serde]
+pub struct PendingEpochResponse {
+    pub epoch_number: u64,
+    /// btc_height is the BTC height of the checkpoint
+    pub btc_height: u64,
+    /// confirmation_height is the BTC tip height at which the checkpoint will be confirmed
+    pub confirmation_height: u64,
+}
+
+#[cw_serde]
+pub struct PendingEpochsResponse {
+    pub epochs: Vec<PendingEpochResponse>,
+    /// btc_tip_height is the current BTC tip height of the BTC light client
+    pub btc_tip_height: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
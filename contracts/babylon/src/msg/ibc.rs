@@ -1,6 +1,14 @@
 use cosmos_sdk_proto::ibc::core::channel::v1::{acknowledgement::Response, Acknowledgement};
 use cosmwasm_schema::cw_serde;
 
+pub use babylon_apis::zoneconcierge_api::{
+    BtcStakingResponse, BtcTimestampResponse, RejectedBtcStaking, ZoneconciergeAck,
+};
+
+use crate::ibc::{ChannelInfo, ClosedChannel, DeadLetter, IbcHookFailure, PendingPacket};
+use crate::msg::btc_header::BtcHeaderResponse;
+use crate::state::packet_stats::PacketStats;
+
 pub fn new_ack_res() -> Acknowledgement {
     let resp = Response::Result(vec![]);
 
@@ -17,7 +25,102 @@ pub fn new_ack_err(emsg: String) -> Acknowledgement {
     }
 }
 
+impl From<BtcHeaderResponse> for BtcTimestampResponse {
+    fn from(tip: BtcHeaderResponse) -> Self {
+        BtcTimestampResponse {
+            btc_tip_height: tip.height,
+            btc_tip_hash: tip.hash,
+            btc_tip_work: tip.cum_work,
+        }
+    }
+}
+
+/// Version of the packet memos following the ibc-hooks wasm memo convention
+pub const IBC_HOOKS_MEMO_VERSION: u32 = 1;
+
+/// PacketMemoData is the optional memo of an inbound packet, carried next to the packet in its
+/// `ZoneconciergePacketData` (field 5). It is decoded from the packet data on its own, as the
+/// zoneconcierge packet data skips it
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PacketMemoData {
+    #[prost(message, optional, tag = "5")]
+    pub memo: Option<PacketMemo>,
+}
+
+/// PacketMemo is a versioned packet memo
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PacketMemo {
+    /// version is the version of the memo format. Version 1 is the ibc-hooks wasm memo format
+    /// (see `IbcHooksMemo`)
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    /// memo is the memo, in the format of its version
+    #[prost(string, tag = "2")]
+    pub memo: String,
+}
+
+/// IbcHooksMemo follows the ibc-hooks wasm memo convention, i.e.
+/// `{"wasm": {"contract": "<addr>", "msg": {...}}}`
+#[cw_serde]
+pub struct IbcHooksMemo {
+    pub wasm: Option<WasmHook>,
+}
+
+/// WasmHook is a post-processing call into a destination contract, executed by the babylon
+/// contract after the packet has been successfully handled
+#[cw_serde]
+pub struct WasmHook {
+    /// contract is the address of the destination contract
+    pub contract: String,
+    /// msg is the JSON execute message to send to the destination contract
+    pub msg: serde_json::Value,
+}
+
+/// IbcHookFailuresResponse is the list of failed ibc-hooks calls of a channel, by sequence of
+/// their inbound packet
+#[cw_serde]
+pub struct IbcHookFailuresResponse {
+    pub failures: Vec<IbcHookFailure>,
+}
+
+/// DeadLettersResponse is the list of inbound packets of a channel that failed processing, by
+/// sequence
+#[cw_serde]
+pub struct DeadLettersResponse {
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+/// ChannelsResponse is the list of open zoneconcierge channels, by channel id
+#[cw_serde]
+pub struct ChannelsResponse {
+    pub channels: Vec<ChannelInfo>,
+}
+
+/// ClosedChannelsResponse is the list of closed zoneconcierge channels, by channel id
+#[cw_serde]
+pub struct ClosedChannelsResponse {
+    pub channels: Vec<ClosedChannel>,
+    /// reopen_locked are the connections over which opening a new channel is locked until the
+    /// admin approves it, by connection id
+    pub reopen_locked: Vec<String>,
+}
+
+/// PendingPacketsResponse is the list of outbound packets pending acknowledgement, by channel and
+/// sequence
+#[cw_serde]
+pub struct PendingPacketsResponse {
+    pub packets: Vec<PendingPacket>,
+}
+
+/// PacketStatsResponse is the processing stats of the inbound packets, by packet type
+#[cw_serde]
+pub struct PacketStatsResponse {
+    pub stats: Vec<PacketTypeStats>,
+}
+
+/// PacketTypeStats is the processing stats of the inbound packets of a given type
 #[cw_serde]
-pub struct BtcTimestampResponse {
-    pub placeholder: String,
+pub struct PacketTypeStats {
+    pub packet_type: String,
+    pub stats: PacketStats,
 }
@@ -0,0 +1 @@
+pub use babylon_apis::migration::MigrationProgressResponse;
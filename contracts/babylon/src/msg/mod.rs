@@ -3,3 +3,7 @@ pub mod contract;
 pub mod cz_header;
 pub mod epoch;
 pub mod ibc;
+pub mod migration;
+pub mod staking;
+pub mod state_hash;
+pub mod work;
@@ -0,0 +1,31 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Addr;
+
+/// StakingRouteResponse is the btc-staking contract the BTC staking operations of a consumer are
+/// routed to
+#[cw_serde]
+pub struct StakingRouteResponse {
+    pub consumer_id: String,
+    pub contract: Addr,
+}
+
+/// StakingRoutesResponse is the table of BTC staking routes.
+/// Operations of consumers without a route are sent to the default `btc_staking` contract
+#[cw_serde]
+pub struct StakingRoutesResponse {
+    pub routes: Vec<StakingRouteResponse>,
+}
+
+impl From<Vec<(String, Addr)>> for StakingRoutesResponse {
+    fn from(routes: Vec<(String, Addr)>) -> Self {
+        StakingRoutesResponse {
+            routes: routes
+                .into_iter()
+                .map(|(consumer_id, contract)| StakingRouteResponse {
+                    consumer_id,
+                    contract,
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1 @@
+pub use babylon_apis::state_hash::{SectionHashResponse, StateHashResponse};
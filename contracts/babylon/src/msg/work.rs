@@ -0,0 +1,22 @@
+use cosmwasm_schema::cw_serde;
+
+/// PendingWorkResponse summarises the actionable items for off-chain actors (relayers, keepers
+/// and the admin), so that a single generic worker can service any deployment
+#[cw_serde]
+pub struct PendingWorkResponse {
+    /// migrations are the names of the storage migrations pending completion, in name order.
+    /// Anyone can continue them through `ExecuteMsg::ContinueMigration`
+    pub migrations: Vec<String>,
+    /// pending_packets is the number of outbound packets sent and awaiting relay and
+    /// acknowledgement (see the `PendingPackets` query)
+    pub pending_packets: u64,
+    /// packet_resubmissions is the number of outbound packets that timed out, queued for
+    /// resubmission over the next zoneconcierge channel
+    pub packet_resubmissions: u64,
+    /// dead_letters is the number of inbound packets that failed processing, pending
+    /// reprocessing by the admin through `ExecuteMsg::ReprocessPacket`
+    pub dead_letters: u64,
+    /// pending_epochs is the number of Babylon epochs whose checkpoint is pending finalisation
+    /// through further BTC headers (see the `PendingEpochs` query)
+    pub pending_epochs: u64,
+}
@@ -1,3 +1,4 @@
+mod relayer;
 mod suite;
 
 use cosmwasm_std::Addr;
@@ -62,3 +63,304 @@ mod migration {
         suite.migrate(&admin, Empty {}).unwrap();
     }
 }
+
+mod staking {
+    use super::*;
+    use babylon_apis::btc_staking_api::ExecuteMsg;
+    use cw_multi_test::Executor;
+
+    #[test]
+    fn mismatched_config_is_rejected() {
+        let mut suite = SuiteBuilder::new().build();
+
+        // The babylon contract runs on Testnet, while the btc-staking contract uses the default
+        // Regtest params
+        let err = suite
+            .app
+            .execute_contract(
+                suite.contract.clone(),
+                Addr::unchecked(CONTRACT1_ADDR),
+                &ExecuteMsg::BtcStaking {
+                    new_fp: vec![],
+                    active_del: vec![],
+                    slashed_del: vec![],
+                    unbonded_del: vec![],
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast().unwrap(),
+            btc_staking::error::ContractError::BabylonConfigMismatch(_)
+        ));
+    }
+
+    #[test]
+    fn set_staking_route_works() {
+        let mut suite = SuiteBuilder::new().build();
+        let admin = Addr::unchecked(suite.admin());
+        let set_route =
+            |contract: Option<&str>| crate::msg::contract::ExecuteMsg::SetStakingRoute {
+                consumer_id: "consumer-2".to_string(),
+                contract: contract.map(str::to_string),
+            };
+
+        // Only the admin can set routes
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked("other"),
+                suite.contract.clone(),
+                &set_route(Some(CONTRACT2_ADDR)),
+                &[],
+            )
+            .unwrap_err();
+
+        suite
+            .app
+            .execute_contract(
+                admin.clone(),
+                suite.contract.clone(),
+                &set_route(Some(CONTRACT2_ADDR)),
+                &[],
+            )
+            .unwrap();
+        let routes = suite.get_staking_routes();
+        assert_eq!(routes.routes.len(), 1);
+        assert_eq!(routes.routes[0].consumer_id, "consumer-2");
+        assert_eq!(routes.routes[0].contract, Addr::unchecked(CONTRACT2_ADDR));
+
+        // Remove the route
+        suite
+            .app
+            .execute_contract(admin, suite.contract.clone(), &set_route(None), &[])
+            .unwrap();
+        assert!(suite.get_staking_routes().routes.is_empty());
+    }
+}
+
+mod ibc_faults {
+    use super::relayer::{Fault, Relayer};
+    use crate::ibc::PACKET_RESUBMISSIONS;
+    use crate::msg::ibc::{BtcStakingResponse, ZoneconciergeAck};
+    use crate::state::config::CONFIG;
+    use babylon_proto::babylon::btcstaking::v1::BtcStakingIbcPacket;
+    use babylon_proto::babylon::zoneconcierge::v1::zoneconcierge_packet_data::Packet;
+    use babylon_proto::babylon::zoneconcierge::v1::{
+        ConsumerRegisterIbcPacket, ZoneconciergePacketData,
+    };
+    use cosmwasm_std::{Addr, StdResult};
+    use prost::Message;
+
+    /// A packet that fails processing on the consumer side
+    fn unsupported_packet(name: &str) -> Vec<u8> {
+        ZoneconciergePacketData {
+            packet: Some(Packet::ConsumerRegister(ConsumerRegisterIbcPacket {
+                consumer_name: name.to_string(),
+                ..Default::default()
+            })),
+        }
+        .encode_to_vec()
+    }
+
+    /// A packet that is processed successfully, once a btc-staking contract is set
+    fn btc_staking_packet() -> Vec<u8> {
+        ZoneconciergePacketData {
+            packet: Some(Packet::BtcStaking(BtcStakingIbcPacket::default())),
+        }
+        .encode_to_vec()
+    }
+
+    fn dead_letter_sequences(relayer: &Relayer) -> Vec<u64> {
+        crate::queries::dead_letters(relayer.deps.as_ref(), "channel-1".to_string(), None, None)
+            .unwrap()
+            .dead_letters
+            .iter()
+            .map(|dead_letter| dead_letter.sequence)
+            .collect()
+    }
+
+    #[test]
+    fn duplicated_packets_are_acknowledged_consistently() {
+        let mut relayer = Relayer::new("channel-1");
+        CONFIG
+            .update(relayer.deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.btc_staking = Some(Addr::unchecked("btc_staking"));
+                Ok(cfg)
+            })
+            .unwrap();
+
+        let ok = relayer.send(btc_staking_packet());
+        let failed = relayer.send(unsupported_packet("consumer"));
+        let acks = relayer.relay(Some(Fault::Duplicate));
+
+        // Both deliveries of a packet get the same acknowledgement
+        assert_eq!(
+            acks.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(),
+            vec![ok, ok, failed, failed]
+        );
+        assert!(acks[0].1.is_success() && acks[1].1.is_success());
+        assert!(!acks[2].1.is_success());
+        assert_eq!(acks[2].1, acks[3].1);
+        assert_eq!(
+            ZoneconciergeAck::from_std_ack(&acks[0].1).unwrap(),
+            ZoneconciergeAck::BtcStaking(BtcStakingResponse::default())
+        );
+
+        // A duplicated failed packet is dead lettered once, but both deliveries are accounted for
+        assert_eq!(dead_letter_sequences(&relayer), vec![failed]);
+        let stats = crate::queries::packet_stats(relayer.deps.as_ref())
+            .unwrap()
+            .stats;
+        let stats = |packet_type: &str| {
+            stats
+                .iter()
+                .find(|s| s.packet_type == packet_type)
+                .unwrap()
+                .stats
+                .clone()
+        };
+        assert_eq!(stats("btc_staking").count, 2);
+        assert_eq!(stats("consumer_register").failures, 2);
+    }
+
+    #[test]
+    fn reordered_packets_are_dead_lettered_by_sequence() {
+        let mut relayer = Relayer::new("channel-1");
+
+        let sequences: Vec<u64> = (0..3)
+            .map(|i| relayer.send(unsupported_packet(&format!("consumer-{i}"))))
+            .collect();
+        let acks = relayer.relay(Some(Fault::Reorder));
+        assert_eq!(
+            acks.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(),
+            sequences.iter().rev().copied().collect::<Vec<_>>()
+        );
+        assert!(acks.iter().all(|(_, ack)| !ack.is_success()));
+
+        // Dead letters are listed by sequence, whatever the delivery order
+        assert_eq!(dead_letter_sequences(&relayer), sequences);
+    }
+
+    #[test]
+    fn timed_out_packets_are_resubmitted_once() {
+        // The consumer registration packet is sent upon connection
+        let mut relayer = Relayer::new("channel-1");
+        assert_eq!(relayer.outbound().len(), 1);
+        let data = relayer.outbound()[0].data.clone();
+
+        // Timed out inbound packets never reach the contract
+        relayer.send(unsupported_packet("consumer"));
+        assert!(relayer.relay(Some(Fault::Timeout)).is_empty());
+        assert!(dead_letter_sequences(&relayer).is_empty());
+
+        // The timed out registration packet is resubmitted over the next channel, and not sent
+        // twice
+        assert_eq!(relayer.relay_outbound(Some(Fault::Timeout)).len(), 1);
+        assert!(relayer.outbound().is_empty());
+        relayer.connect("channel-2");
+        assert_eq!(relayer.outbound().len(), 1);
+        assert_eq!(relayer.outbound()[0].data, data);
+
+        // A duplicated acknowledgement is harmless, and the packet is not resubmitted anymore
+        assert_eq!(relayer.relay_outbound(Some(Fault::Duplicate)).len(), 2);
+        assert!(PACKET_RESUBMISSIONS.is_empty(relayer.deps.as_ref().storage));
+    }
+}
+
+mod btc_light_client {
+    use super::*;
+    use crate::error::ContractError;
+    use crate::msg::btc_header::{BtcHeader, BtcHeaderRelayersResponse, BtcHeaderResponse};
+    use crate::msg::contract::{ExecuteMsg, QueryMsg};
+    use cosmwasm_std::Event;
+    use cw_multi_test::Executor;
+    use test_utils::get_btc_lc_headers;
+
+    #[test]
+    fn btc_header_relayers_work() {
+        // The test headers are regtest headers
+        let mut suite = SuiteBuilder::new()
+            .with_network(babylon_bitcoin::chain_params::Network::Regtest)
+            .build();
+        let admin = Addr::unchecked(suite.admin());
+        let test_headers = get_btc_lc_headers();
+        let headers: Vec<BtcHeader> = test_headers[..12]
+            .iter()
+            .map(TryInto::try_into)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let relayer = suite.app.api().addr_make("relayer");
+        let update_relayers = ExecuteMsg::UpdateBtcHeaderRelayers {
+            add: vec![relayer.to_string()],
+            remove: vec![],
+        };
+
+        // Only the admin can update the relayers
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked("other"),
+                suite.contract.clone(),
+                &update_relayers,
+                &[],
+            )
+            .unwrap_err();
+        suite
+            .app
+            .execute_contract(admin, suite.contract.clone(), &update_relayers, &[])
+            .unwrap();
+        let res: BtcHeaderRelayersResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(suite.contract.clone(), &QueryMsg::BtcHeaderRelayers {})
+            .unwrap();
+        assert_eq!(res.relayers, vec![relayer.clone()]);
+
+        // Only relayers can submit headers
+        let submit_headers = ExecuteMsg::BtcHeaders {
+            headers: headers.clone(),
+        };
+        let err = suite
+            .app
+            .execute_contract(
+                Addr::unchecked("other"),
+                suite.contract.clone(),
+                &submit_headers,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized {}
+        );
+
+        // An event is emitted for each inserted header
+        let res = suite
+            .app
+            .execute_contract(
+                relayer.clone(),
+                suite.contract.clone(),
+                &submit_headers,
+                &[],
+            )
+            .unwrap();
+        let inserted: Vec<_> = res
+            .events
+            .iter()
+            .filter(|ev| ev.ty == "wasm-btc_header_inserted")
+            .collect();
+        assert_eq!(inserted.len(), headers.len());
+        assert_eq!(
+            *inserted[0],
+            Event::new("wasm-btc_header_inserted")
+                .add_attribute("_contract_address", CONTRACT0_ADDR)
+                .add_attribute("height", "1")
+                .add_attribute(
+                    "hash",
+                    BtcHeaderResponse::try_from(&test_headers[0]).unwrap().hash
+                )
+                .add_attribute("relayer", relayer)
+        );
+    }
+}
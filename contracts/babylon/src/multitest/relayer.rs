@@ -0,0 +1,205 @@
+//! relayer simulates an IBC relayer between the babylon contract and its Babylon counterparty.
+//! Multi-test does not relay IBC packets, so the relayer calls the contract's IBC entry points
+//! directly, optionally simulating relayer faults
+use cosmwasm_std::testing::{
+    message_info, mock_dependencies, mock_env, mock_ibc_channel_connect_ack,
+    mock_ibc_channel_open_try, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    from_json, Binary, CosmosMsg, Env, IbcAcknowledgement, IbcChannel, IbcMsg, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcTimeout, OwnedDeps, StdAck,
+    StdResult, SubMsg,
+};
+
+use crate::contract::tests::base_instantiate_msg;
+use crate::contract::{instantiate, reply};
+use crate::ibc::tests::send_packet_reply;
+use crate::ibc::{
+    ibc_channel_connect, ibc_channel_open, ibc_packet_ack, ibc_packet_receive, ibc_packet_timeout,
+    IBC_ORDERING, IBC_VERSION,
+};
+use crate::state::config::CONFIG;
+
+/// Fault is a relayer fault to simulate when relaying packets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Every packet is delivered twice
+    Duplicate,
+    /// Packets are delivered in reverse order. Only possible over unordered channels
+    Reorder,
+    /// Packets are not delivered before their timeout
+    Timeout,
+}
+
+/// Relayer relays packets over a channel between the babylon contract and its counterparty.
+/// Packets are queued as they are sent, and delivered upon `relay` / `relay_outbound`
+pub struct Relayer {
+    pub deps: OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    pub env: Env,
+    channel: IbcChannel,
+    next_recv_sequence: u64,
+    next_send_sequence: u64,
+    /// Packets sent by the counterparty, pending delivery to the contract
+    inbound: Vec<IbcPacket>,
+    /// Packets sent by the contract, pending acknowledgement or timeout
+    outbound: Vec<IbcPacket>,
+}
+
+impl Relayer {
+    /// new instantiates the babylon contract, and opens the given channel to it
+    #[track_caller]
+    pub fn new(channel_id: &str) -> Self {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = message_info(&deps.api.addr_make("creator"), &[]);
+        instantiate(deps.as_mut(), env.clone(), info, base_instantiate_msg()).unwrap();
+        // Consumer info is only kept along with a btc-staking contract, which is not instantiated
+        // here. It is set so that the consumer registers upon connection
+        CONFIG
+            .update(deps.as_mut().storage, |mut cfg| -> StdResult<_> {
+                cfg.consumer_name = Some("TestConsumer".to_string());
+                cfg.consumer_description = Some("Test Consumer Description".to_string());
+                Ok(cfg)
+            })
+            .unwrap();
+
+        let channel = mock_ibc_channel_connect_ack(channel_id, IBC_ORDERING, IBC_VERSION)
+            .channel()
+            .clone();
+        let mut relayer = Relayer {
+            deps,
+            env,
+            channel,
+            next_recv_sequence: 1,
+            next_send_sequence: 1,
+            inbound: vec![],
+            outbound: vec![],
+        };
+        relayer.connect(channel_id);
+        relayer
+    }
+
+    /// connect opens the given channel to the contract, queueing the packets the contract sends
+    /// upon connection
+    #[track_caller]
+    pub fn connect(&mut self, channel_id: &str) {
+        let open = mock_ibc_channel_open_try(channel_id, IBC_ORDERING, IBC_VERSION);
+        ibc_channel_open(self.deps.as_mut(), self.env.clone(), open).unwrap();
+        let connect = mock_ibc_channel_connect_ack(channel_id, IBC_ORDERING, IBC_VERSION);
+        self.channel = connect.channel().clone();
+        // Sequences are per channel
+        self.next_send_sequence = 1;
+        let res = ibc_channel_connect(self.deps.as_mut(), self.env.clone(), connect).unwrap();
+        self.capture(&res.messages);
+    }
+
+    /// capture queues the packets sent by the contract in the given messages, replying to the
+    /// contract with the sequence of every packet
+    #[track_caller]
+    pub fn capture<T>(&mut self, msgs: &[SubMsg<T>]) {
+        for msg in msgs {
+            if let CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id,
+                data,
+                timeout,
+            }) = &msg.msg
+            {
+                assert_eq!(channel_id, &self.channel.endpoint.channel_id);
+                let packet = IbcPacket::new(
+                    data.clone(),
+                    self.channel.endpoint.clone(),
+                    self.channel.counterparty_endpoint.clone(),
+                    self.next_send_sequence,
+                    timeout.clone(),
+                );
+                let send_reply = send_packet_reply(msg, self.next_send_sequence);
+                reply(self.deps.as_mut(), self.env.clone(), send_reply).unwrap();
+                self.next_send_sequence += 1;
+                self.outbound.push(packet);
+            }
+        }
+    }
+
+    /// send queues a packet sent by the counterparty. Returns the packet's sequence
+    pub fn send(&mut self, data: impl Into<Binary>) -> u64 {
+        let sequence = self.next_recv_sequence;
+        self.next_recv_sequence += 1;
+        let timeout = IbcTimeout::with_timestamp(self.env.block.time.plus_seconds(600));
+        self.inbound.push(IbcPacket::new(
+            data,
+            self.channel.counterparty_endpoint.clone(),
+            self.channel.endpoint.clone(),
+            sequence,
+            timeout,
+        ));
+        sequence
+    }
+
+    /// relay delivers the queued inbound packets to the contract, simulating the given fault.
+    /// Returns the sequence and the acknowledgement of every delivery, in delivery order.
+    /// Timed out packets are not delivered at all
+    #[track_caller]
+    pub fn relay(&mut self, fault: Option<Fault>) -> Vec<(u64, StdAck)> {
+        let packets = faulty(std::mem::take(&mut self.inbound), fault);
+        let relayer = self.deps.api.addr_make("relayer");
+        packets
+            .into_iter()
+            .map(|packet| {
+                let sequence = packet.sequence;
+                let msg = IbcPacketReceiveMsg::new(packet, relayer.clone());
+                let res = ibc_packet_receive(self.deps.as_mut(), self.env.clone(), msg).unwrap();
+                self.capture(&res.messages);
+                let ack = from_json(res.acknowledgement.unwrap()).unwrap();
+                (sequence, ack)
+            })
+            .collect()
+    }
+
+    /// relay_outbound acknowledges the queued outbound packets, or times them out upon
+    /// `Fault::Timeout`, simulating the given fault.
+    /// Returns the sequence of every relayed packet, in relay order
+    #[track_caller]
+    pub fn relay_outbound(&mut self, fault: Option<Fault>) -> Vec<u64> {
+        let packets = std::mem::take(&mut self.outbound);
+        let relayer = self.deps.api.addr_make("relayer");
+        if fault == Some(Fault::Timeout) {
+            return packets
+                .into_iter()
+                .map(|packet| {
+                    let sequence = packet.sequence;
+                    let msg = IbcPacketTimeoutMsg::new(packet, relayer.clone());
+                    ibc_packet_timeout(self.deps.as_mut(), self.env.clone(), msg).unwrap();
+                    sequence
+                })
+                .collect();
+        }
+        faulty(packets, fault)
+            .into_iter()
+            .map(|packet| {
+                let sequence = packet.sequence;
+                let ack = IbcAcknowledgement::new(StdAck::success(b"\x01"));
+                let msg = IbcPacketAckMsg::new(ack, packet, relayer.clone());
+                ibc_packet_ack(self.deps.as_mut(), self.env.clone(), msg).unwrap();
+                sequence
+            })
+            .collect()
+    }
+
+    /// outbound returns the outbound packets pending acknowledgement or timeout
+    pub fn outbound(&self) -> &[IbcPacket] {
+        &self.outbound
+    }
+}
+
+/// faulty returns the packets to deliver upon the given fault, in delivery order
+fn faulty(mut packets: Vec<IbcPacket>, fault: Option<Fault>) -> Vec<IbcPacket> {
+    match fault {
+        None => packets,
+        Some(Fault::Duplicate) => packets.into_iter().flat_map(|p| [p.clone(), p]).collect(),
+        Some(Fault::Reorder) => {
+            packets.reverse();
+            packets
+        }
+        Some(Fault::Timeout) => vec![],
+    }
+}
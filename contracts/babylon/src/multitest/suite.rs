@@ -1,11 +1,12 @@
 use crate::msg::contract::{InstantiateMsg, QueryMsg};
+use crate::msg::staking::StakingRoutesResponse;
 use crate::multitest::{CONTRACT1_ADDR, CONTRACT2_ADDR};
 use crate::state::config::Config;
 use anyhow::Result as AnyResult;
 use babylon_bindings::BabylonMsg;
 use babylon_bindings_test::BabylonApp;
 use babylon_bitcoin::chain_params::Network;
-use cosmwasm_std::{Addr, Empty};
+use cosmwasm_std::{Addr, Binary, Empty};
 use cw_multi_test::{AppResponse, Contract, ContractWrapper, Executor};
 use derivative::Derivative;
 
@@ -38,6 +39,7 @@ fn contract_babylon() -> Box<dyn Contract<BabylonMsg>> {
 #[derivative(Default = "new")]
 pub struct SuiteBuilder {
     funds: Vec<(Addr, u128)>,
+    network: Option<Network>,
 }
 
 impl SuiteBuilder {
@@ -48,6 +50,12 @@ impl SuiteBuilder {
         self
     }
 
+    /// Sets the BTC network of the babylon contract. Defaults to testnet
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
     #[track_caller]
     pub fn build(self) -> Suite {
         let _funds = self.funds;
@@ -71,18 +79,18 @@ impl SuiteBuilder {
                 contract_code_id,
                 owner.clone(),
                 &InstantiateMsg {
-                    network: Network::Testnet,
-                    babylon_tag: "01020304".to_string(),
-                    btc_confirmation_depth: 1,
-                    checkpoint_finalization_timeout: 10,
-                    notify_cosmos_zone: false,
                     btc_staking_code_id: Some(btc_staking_code_id),
-                    btc_staking_msg: None,
+                    btc_staking_msg: Some(Binary::from(b"{}")),
                     btc_finality_code_id: Some(btc_finality_code_id),
-                    btc_finality_msg: None,
                     admin: Some(owner.to_string()),
                     consumer_name: Some("TestConsumer".to_string()),
                     consumer_description: Some("Test Consumer Description".to_string()),
+                    ..InstantiateMsg::new(
+                        self.network.unwrap_or(Network::Testnet),
+                        "01020304",
+                        1,
+                        10,
+                    )
                 },
                 &[],
                 "babylon",
@@ -141,6 +149,14 @@ impl Suite {
             .unwrap()
     }
 
+    #[track_caller]
+    pub fn get_staking_routes(&self) -> StakingRoutesResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.contract.clone(), &QueryMsg::StakingRoutes {})
+            .unwrap()
+    }
+
     pub fn migrate(&mut self, addr: &str, msg: Empty) -> AnyResult<AppResponse> {
         self.app.migrate_contract(
             Addr::unchecked(addr),
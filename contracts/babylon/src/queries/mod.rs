@@ -1,17 +1,42 @@
 use crate::error::{BTCLightclientError, BabylonEpochChainError, CZHeaderChainError};
-use crate::msg::btc_header::{BtcHeaderResponse, BtcHeadersResponse};
+use crate::ibc::{
+    get_channels, get_closed_channels, get_dead_letters, get_ibc_hook_failures,
+    get_pending_packets, get_reopen_locks, DeadLetter, DEAD_LETTERS, PACKET_RESUBMISSIONS,
+    PENDING_PACKETS,
+};
+use crate::msg::btc_header::{
+    BtcHeaderRelayersResponse, BtcHeaderResponse, BtcHeaderRetentionResponse, BtcHeadersResponse,
+};
 use crate::msg::cz_header::CzHeaderResponse;
-use crate::msg::epoch::{CheckpointResponse, EpochResponse};
+use crate::msg::epoch::{
+    CheckpointResponse, EpochResponse, PendingEpochResponse, PendingEpochsResponse,
+};
+use crate::msg::ibc::{
+    ChannelsResponse, ClosedChannelsResponse, DeadLettersResponse, IbcHookFailuresResponse,
+    PacketStatsResponse, PacketTypeStats, PendingPacketsResponse,
+};
+use crate::msg::migration::MigrationProgressResponse;
+use crate::msg::staking::StakingRoutesResponse;
+use crate::msg::state_hash::StateHashResponse;
+use crate::msg::work::PendingWorkResponse;
 use crate::state::babylon_epoch_chain::{
-    get_base_epoch, get_checkpoint, get_epoch, get_last_finalized_epoch,
+    get_base_epoch, get_checkpoint, get_epoch, get_last_finalized_epoch, get_pending_epochs,
+    BABYLON_PENDING_EPOCHS,
 };
 use crate::state::btc_light_client::{
-    get_base_header, get_header, get_header_by_hash, get_headers, get_tip,
+    get_base_header, get_earliest_height, get_header, get_header_by_hash, get_headers,
+    get_relayers, get_tip, min_retained_headers,
 };
 use crate::state::config::{Config, CONFIG};
-use crate::state::cz_header_chain::{get_cz_header, get_last_cz_header};
-use babylon_bitcoin::BlockHash;
-use cosmwasm_std::{Deps, StdResult};
+use crate::state::cz_header_chain::{get_cz_header, get_last_cz_header, get_next_cz_header};
+use crate::state::migration::get_migration_progress;
+use crate::state::packet_stats::get_packet_stats;
+use crate::state::staking_router::get_staking_routes;
+use crate::state::state_hash::get_state_hash;
+use crate::utils;
+use crate::utils::babylon_epoch_chain::NUM_BTC_TXS;
+use babylon_bitcoin::{BlockHash, Transaction};
+use cosmwasm_std::{Binary, Deps, Order, StdResult};
 use std::str::FromStr;
 
 pub fn config(deps: Deps) -> StdResult<Config> {
@@ -28,6 +53,22 @@ pub fn btc_tip_header(_deps: Deps) -> Result<BtcHeaderResponse, BTCLightclientEr
     BtcHeaderResponse::try_from(&btc_header_info)
 }
 
+pub fn btc_header_retention(deps: Deps) -> Result<BtcHeaderRetentionResponse, BTCLightclientError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(BtcHeaderRetentionResponse {
+        earliest_height: get_earliest_height(deps.storage)?,
+        tip_height: get_tip(deps.storage)?.height,
+        max_stored_headers: cfg.max_stored_headers,
+        min_retained_headers: min_retained_headers(&cfg),
+    })
+}
+
+pub fn btc_header_relayers(deps: Deps) -> StdResult<BtcHeaderRelayersResponse> {
+    Ok(BtcHeaderRelayersResponse {
+        relayers: get_relayers(deps.storage)?,
+    })
+}
+
 pub fn btc_header(deps: Deps, height: u64) -> Result<BtcHeaderResponse, BTCLightclientError> {
     let btc_header_info = get_header(deps.storage, height)?;
     BtcHeaderResponse::try_from(&btc_header_info)
@@ -84,6 +125,57 @@ pub fn babylon_checkpoint(
     Ok(CheckpointResponse::from(&raw_checkpoint))
 }
 
+pub fn pending_epochs(deps: Deps) -> Result<PendingEpochsResponse, BabylonEpochChainError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let btc_tip_height = get_tip(deps.storage)?.height;
+    let epochs = get_pending_epochs(deps.storage)?
+        .into_iter()
+        .map(|(epoch_number, btc_height)| PendingEpochResponse {
+            epoch_number,
+            btc_height,
+            confirmation_height: btc_height + cfg.btc_confirmation_depth,
+        })
+        .filter(|epoch| epoch.confirmation_height > btc_tip_height)
+        .collect();
+    Ok(PendingEpochsResponse {
+        epochs,
+        btc_tip_height,
+    })
+}
+
+pub fn parse_checkpoint(
+    deps: Deps,
+    tx_bytes: &[Binary],
+) -> Result<CheckpointResponse, BabylonEpochChainError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let btc_txs: [Transaction; NUM_BTC_TXS] = tx_bytes
+        .iter()
+        .map(|tx| {
+            babylon_bitcoin::deserialize(tx).map_err(|e| {
+                BabylonEpochChainError::CheckpointParseError {
+                    err_msg: format!("failed to decode BTC tx: {e}"),
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .try_into()
+        .map_err(|_| BabylonEpochChainError::CheckpointParseError {
+            err_msg: format!("expected {NUM_BTC_TXS} BTC txs, got {}", tx_bytes.len()),
+        })?;
+
+    // try all the configured babylon tags
+    let mut parsed = Err(String::new());
+    for babylon_tag in cfg.all_babylon_tags() {
+        parsed = utils::babylon_epoch_chain::parse_checkpoint(&btc_txs, babylon_tag);
+        if parsed.is_ok() {
+            break;
+        }
+    }
+    let raw_checkpoint =
+        parsed.map_err(|e| BabylonEpochChainError::CheckpointParseError { err_msg: e })?;
+    Ok(CheckpointResponse::from(&raw_checkpoint))
+}
+
 pub fn cz_last_header(deps: Deps) -> Result<CzHeaderResponse, CZHeaderChainError> {
     let header = get_last_cz_header(deps.storage)?;
     Ok(CzHeaderResponse::from(&header))
@@ -94,12 +186,110 @@ pub(crate) fn cz_header(deps: Deps, height: u64) -> Result<CzHeaderResponse, CZH
     Ok(CzHeaderResponse::from(&header))
 }
 
+pub(crate) fn cz_next_header(
+    deps: Deps,
+    height: u64,
+) -> Result<Option<CzHeaderResponse>, CZHeaderChainError> {
+    let header = get_next_cz_header(deps.storage, height)?;
+    Ok(header.as_ref().map(CzHeaderResponse::from))
+}
+
+pub(crate) fn state_hash(deps: Deps) -> StdResult<StateHashResponse> {
+    get_state_hash(deps.storage)
+}
+
+pub(crate) fn staking_routes(deps: Deps) -> StdResult<StakingRoutesResponse> {
+    get_staking_routes(deps.storage).map(StakingRoutesResponse::from)
+}
+
+pub fn dead_letter(deps: Deps, channel_id: String, sequence: u64) -> StdResult<DeadLetter> {
+    DEAD_LETTERS.load(deps.storage, (&channel_id, sequence))
+}
+
+pub fn dead_letters(
+    deps: Deps,
+    channel_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DeadLettersResponse> {
+    let dead_letters = get_dead_letters(deps.storage, &channel_id, start_after, limit)?;
+    Ok(DeadLettersResponse { dead_letters })
+}
+
+pub fn ibc_hook_failures(
+    deps: Deps,
+    channel_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<IbcHookFailuresResponse> {
+    let failures = get_ibc_hook_failures(deps.storage, &channel_id, start_after, limit)?;
+    Ok(IbcHookFailuresResponse { failures })
+}
+
+pub fn channels(deps: Deps) -> StdResult<ChannelsResponse> {
+    Ok(ChannelsResponse {
+        channels: get_channels(deps.storage)?,
+    })
+}
+
+pub fn closed_channels(deps: Deps) -> StdResult<ClosedChannelsResponse> {
+    Ok(ClosedChannelsResponse {
+        channels: get_closed_channels(deps.storage)?,
+        reopen_locked: get_reopen_locks(deps.storage)?,
+    })
+}
+
+pub fn pending_packets(deps: Deps) -> StdResult<PendingPacketsResponse> {
+    Ok(PendingPacketsResponse {
+        packets: get_pending_packets(deps.storage)?,
+    })
+}
+
+pub fn packet_stats(deps: Deps) -> StdResult<PacketStatsResponse> {
+    let stats = get_packet_stats(deps.storage)?
+        .into_iter()
+        .map(|(packet_type, stats)| PacketTypeStats { packet_type, stats })
+        .collect();
+    Ok(PacketStatsResponse { stats })
+}
+
+pub fn migration_progress(deps: Deps) -> StdResult<MigrationProgressResponse> {
+    Ok(MigrationProgressResponse {
+        migrations: get_migration_progress(deps.storage)?,
+    })
+}
+
+pub fn pending_work(deps: Deps) -> StdResult<PendingWorkResponse> {
+    let migrations = get_migration_progress(deps.storage)?
+        .into_iter()
+        .filter(|progress| !progress.complete)
+        .map(|progress| progress.name)
+        .collect();
+    Ok(PendingWorkResponse {
+        migrations,
+        pending_packets: PENDING_PACKETS
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .count() as u64,
+        packet_resubmissions: PACKET_RESUBMISSIONS
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .count() as u64,
+        dead_letters: DEAD_LETTERS
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .count() as u64,
+        pending_epochs: BABYLON_PENDING_EPOCHS
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .count() as u64,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ibc::{PacketResubmission, PendingPacket};
     use crate::state::btc_light_client::{init, tests::setup};
+    use crate::state::migration::{MigrationProgress, MIGRATION_PROGRESS};
     use cosmwasm_std::testing::mock_dependencies;
-    use test_utils::get_btc_lc_headers;
+    use test_utils::{get_btc_lc_headers, get_btc_timestamp_and_headers};
 
     #[test]
     fn btc_headers_work() {
@@ -232,4 +422,129 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_checkpoint_works() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut().storage);
+
+        let (btc_ts, _) = get_btc_timestamp_and_headers();
+        let raw_ckpt = btc_ts.raw_checkpoint.unwrap();
+        let txs_info = btc_ts.proof.unwrap().proof_epoch_submitted;
+        let tx_bytes: Vec<Binary> = txs_info
+            .iter()
+            .map(|tx_info| Binary::from(tx_info.transaction.to_vec()))
+            .collect();
+
+        let res = parse_checkpoint(deps.as_ref(), &tx_bytes).unwrap();
+        assert_eq!(res, CheckpointResponse::from(&raw_ckpt));
+
+        // Missing second part
+        let err = parse_checkpoint(deps.as_ref(), &tx_bytes[0..1]).unwrap_err();
+        assert!(matches!(
+            err,
+            BabylonEpochChainError::CheckpointParseError { .. }
+        ));
+    }
+
+    #[test]
+    fn pending_epochs_works() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut().storage);
+
+        let test_headers = get_btc_lc_headers();
+        init(deps.as_mut().storage, &test_headers).unwrap();
+        let tip_height = test_headers.last().unwrap().height;
+        // btc_confirmation_depth is 1
+        let cfg = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(cfg.btc_confirmation_depth, 1);
+
+        let res = pending_epochs(deps.as_ref()).unwrap();
+        assert_eq!(res.epochs, vec![]);
+        assert_eq!(res.btc_tip_height, tip_height);
+
+        // Confirmed epoch is not reported
+        BABYLON_PENDING_EPOCHS
+            .save(deps.as_mut().storage, 1, &(tip_height - 1))
+            .unwrap();
+        // Unconfirmed epoch is reported
+        BABYLON_PENDING_EPOCHS
+            .save(deps.as_mut().storage, 2, &tip_height)
+            .unwrap();
+
+        let res = pending_epochs(deps.as_ref()).unwrap();
+        assert_eq!(
+            res.epochs,
+            vec![PendingEpochResponse {
+                epoch_number: 2,
+                btc_height: tip_height,
+                confirmation_height: tip_height + 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn pending_work_works() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut().storage);
+
+        let res = pending_work(deps.as_ref()).unwrap();
+        assert_eq!(
+            res,
+            PendingWorkResponse {
+                migrations: vec![],
+                pending_packets: 0,
+                packet_resubmissions: 0,
+                dead_letters: 0,
+                pending_epochs: 0,
+            }
+        );
+
+        let storage = deps.as_mut().storage;
+        for (name, complete) in [("done", true), ("pending", false)] {
+            let progress = MigrationProgress {
+                name: name.to_string(),
+                section: "section".to_string(),
+                migrated: 0,
+                complete,
+                cursor: None,
+            };
+            MIGRATION_PROGRESS.save(storage, name, &progress).unwrap();
+        }
+        for sequence in [1, 2] {
+            let packet = PendingPacket {
+                packet_hash: Binary::from(b"hash"),
+                packet_type: "consumer_slashing".to_string(),
+                channel_id: "channel-0".to_string(),
+                sequence,
+                height: 1,
+                attempts: 0,
+            };
+            PENDING_PACKETS
+                .save(storage, ("channel-0", sequence), &packet)
+                .unwrap();
+        }
+        let resubmission = PacketResubmission {
+            data: Binary::from(b"data"),
+            attempts: 1,
+        };
+        PACKET_RESUBMISSIONS
+            .save(storage, ("channel-1", 1), &resubmission)
+            .unwrap();
+        BABYLON_PENDING_EPOCHS.save(storage, 1, &10).unwrap();
+        BABYLON_PENDING_EPOCHS.save(storage, 2, &11).unwrap();
+        BABYLON_PENDING_EPOCHS.save(storage, 3, &12).unwrap();
+
+        let res = pending_work(deps.as_ref()).unwrap();
+        assert_eq!(
+            res,
+            PendingWorkResponse {
+                migrations: vec!["pending".to_string()],
+                pending_packets: 2,
+                packet_resubmissions: 1,
+                dead_letters: 0,
+                pending_epochs: 3,
+            }
+        );
+    }
 }
@@ -5,8 +5,8 @@ use babylon_bitcoin::BlockHeader;
 use prost::Message;
 use std::cmp::min;
 
-use cosmwasm_std::{StdError, StdResult, Storage};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, Item, Map};
 
 use babylon_proto::babylon::btccheckpoint::v1::TransactionInfo;
 use babylon_proto::babylon::checkpointing::v1::RawCheckpoint;
@@ -24,6 +24,12 @@ pub const BABYLON_EPOCHS: Map<u64, Vec<u8>> = Map::new("babylon_epochs");
 pub const BABYLON_EPOCH_BASE: Item<Vec<u8>> = Item::new("babylon_epoch_base");
 pub const BABYLON_EPOCH_EPOCH_LAST_FINALIZED: Item<Vec<u8>> = Item::new("babylon_epoch_last");
 pub const BABYLON_CHECKPOINTS: Map<u64, Vec<u8>> = Map::new("babylon_checkpoints");
+/// Epochs whose checkpoint has been seen on BTC, but is not deep enough yet,
+/// by epoch number -> BTC height of the checkpoint
+pub const BABYLON_PENDING_EPOCHS: Map<u64, u64> = Map::new("babylon_pending_epochs");
+/// Finalised epochs, by epoch number -> BTC height of the checkpoint.
+/// Used for rolling back the epochs whose checkpoint is reorged out of the BTC header chain
+pub const BABYLON_CHECKPOINT_BTC_HEIGHTS: Map<u64, u64> = Map::new("babylon_ckpt_btc_heights");
 
 // is_initialized checks if the BTC light client has been initialised or not
 // the check is done by checking existence of base epoch
@@ -96,6 +102,8 @@ pub fn get_checkpoint(
 struct VerifiedEpochAndCheckpoint {
     pub epoch: Epoch,
     pub raw_ckpt: RawCheckpoint,
+    /// btc_height is the BTC height of the checkpoint, i.e. of its earliest BTC header
+    pub btc_height: u64,
 }
 
 /// verify_epoch_and_checkpoint verifies an epoch metadata and a raw checkpoint
@@ -148,26 +156,36 @@ fn verify_epoch_and_checkpoint(
         min_height = min(min_height, header.height);
     }
 
-    // ensure at least 1 given btc headers are finalised, i.e., w-deep
+    // verify the checkpoint is submitted, i.e., committed to the 2 BTC headers, under any of the
+    // babylon tags accepted at the height of the checkpoint
+    let mut submitted = Ok(());
+    for babylon_tag in cfg.accepted_babylon_tags(min_height) {
+        submitted = verify_checkpoint_submitted(raw_ckpt, txs_info, &btc_headers, babylon_tag);
+        if submitted.is_ok() {
+            break;
+        }
+    }
+    submitted.map_err(|e| BabylonEpochChainError::CheckpointNotSubmitted { err_msg: e })?;
+
+    // verify the epoch is sealed by its validator set
+    verify_epoch_sealed(epoch, raw_ckpt, proof_epoch_sealed)
+        .map_err(|e| BabylonEpochChainError::EpochNotSealed { err_msg: e })?;
+
+    // ensure at least 1 given btc headers are finalised, i.e., w-deep.
+    // Otherwise, keep track of the epoch as pending, for monitoring
     let tip_height = super::btc_light_client::get_tip(storage)?.height;
     if min_height + cfg.checkpoint_finalization_timeout > tip_height {
+        BABYLON_PENDING_EPOCHS.save(storage, epoch.epoch_number, &min_height)?;
         return Err(BabylonEpochChainError::BTCHeaderNotDeepEnough {
             w: cfg.checkpoint_finalization_timeout,
         });
     }
 
-    // verify the checkpoint is submitted, i.e., committed to the 2 BTC headers
-    verify_checkpoint_submitted(raw_ckpt, txs_info, &btc_headers, &cfg.babylon_tag)
-        .map_err(|e| BabylonEpochChainError::CheckpointNotSubmitted { err_msg: e })?;
-
-    // verify the epoch is sealed by its validator set
-    verify_epoch_sealed(epoch, raw_ckpt, proof_epoch_sealed)
-        .map_err(|e| BabylonEpochChainError::EpochNotSealed { err_msg: e })?;
-
     // all good
     Ok(VerifiedEpochAndCheckpoint {
         epoch: epoch.clone(),
         raw_ckpt: raw_ckpt.clone(),
+        btc_height: min_height,
     })
 }
 
@@ -186,11 +204,105 @@ fn insert_epoch_and_checkpoint(
     // insert raw ckpt
     let raw_ckpt_bytes = verified_tuple.raw_ckpt.encode_to_vec();
     BABYLON_CHECKPOINTS.save(storage, epoch_number, &raw_ckpt_bytes)?;
+    BABYLON_CHECKPOINT_BTC_HEIGHTS.save(storage, epoch_number, &verified_tuple.btc_height)?;
+
+    // the epoch, and all the previous ones, are no longer pending
+    remove_pending_epochs(storage, epoch_number)?;
 
     // update last finalised epoch
     set_last_finalized_epoch(storage, &verified_tuple.epoch)
 }
 
+/// get_pending_epochs returns the epochs whose checkpoint has been seen on BTC but is not deep
+/// enough yet, as (epoch number, BTC height of the checkpoint) pairs, by epoch number
+pub fn get_pending_epochs(storage: &dyn Storage) -> StdResult<Vec<(u64, u64)>> {
+    BABYLON_PENDING_EPOCHS
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+/// remove_pending_epochs removes all the pending epochs up to (and including) the given epoch
+fn remove_pending_epochs(storage: &mut dyn Storage, epoch_number: u64) -> StdResult<()> {
+    let pending_epochs = BABYLON_PENDING_EPOCHS
+        .keys(
+            storage,
+            None,
+            Some(Bound::inclusive(epoch_number)),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+    for pending_epoch in pending_epochs {
+        BABYLON_PENDING_EPOCHS.remove(storage, pending_epoch);
+    }
+    Ok(())
+}
+
+/// rollback_to_btc_height rolls back the epochs whose checkpoint is above the given BTC height,
+/// i.e. has been reorged out of the BTC header chain. This includes
+/// - the pending epochs above that height, and
+/// - the finalised epochs above that height, along with their checkpoints. The last finalised
+///   epoch is set to the latest remaining one.
+///
+/// The base epoch is never rolled back, and neither are the finalised epochs with an unknown BTC
+/// height (i.e. finalised before BTC heights were tracked).
+/// It returns the numbers of the rolled back finalised epochs, in descending order
+pub fn rollback_to_btc_height(storage: &mut dyn Storage, btc_height: u64) -> StdResult<Vec<u64>> {
+    let pending_epochs = BABYLON_PENDING_EPOCHS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, height)| *height > btc_height)
+        })
+        .map(|item| item.map(|(epoch_number, _)| epoch_number))
+        .collect::<StdResult<Vec<_>>>()?;
+    for epoch_number in pending_epochs {
+        BABYLON_PENDING_EPOCHS.remove(storage, epoch_number);
+    }
+
+    // Nothing to roll back if the epoch chain is not initialised
+    let Some(base_epoch_bytes) = BABYLON_EPOCH_BASE.may_load(storage)? else {
+        return Ok(vec![]);
+    };
+    let base_epoch_number = Epoch::decode(base_epoch_bytes.as_slice())
+        .map_err(|e| StdError::generic_err(e.to_string()))?
+        .epoch_number;
+
+    // Finalised epochs are checkpointed in order, so roll back from the latest one
+    let mut rolled_back = vec![];
+    let finalized_epochs = BABYLON_EPOCHS
+        .keys(
+            storage,
+            Some(Bound::exclusive(base_epoch_number)),
+            None,
+            Order::Descending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+    for epoch_number in finalized_epochs {
+        match BABYLON_CHECKPOINT_BTC_HEIGHTS.may_load(storage, epoch_number)? {
+            Some(height) if height > btc_height => {}
+            _ => break,
+        }
+        BABYLON_EPOCHS.remove(storage, epoch_number);
+        BABYLON_CHECKPOINTS.remove(storage, epoch_number);
+        BABYLON_CHECKPOINT_BTC_HEIGHTS.remove(storage, epoch_number);
+        rolled_back.push(epoch_number);
+    }
+    if rolled_back.is_empty() {
+        return Ok(rolled_back);
+    }
+
+    // The latest remaining epoch is the last finalised one
+    let last_finalized = BABYLON_EPOCHS
+        .range(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map(|(_, epoch_bytes)| epoch_bytes)
+        .unwrap_or(base_epoch_bytes);
+    BABYLON_EPOCH_EPOCH_LAST_FINALIZED.save(storage, &last_finalized)?;
+
+    Ok(rolled_back)
+}
+
 /// extract_data_from_btc_ts extracts data needed for verifying Babylon epoch chain
 /// from a given BTC timestamp
 pub fn extract_data_from_btc_ts(
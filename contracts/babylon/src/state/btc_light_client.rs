@@ -3,16 +3,17 @@ use babylon_bitcoin::{BlockHash, BlockHeader};
 use prost::Message;
 use std::str::FromStr;
 
-use cosmwasm_std::Order::{Ascending, Descending};
-use cosmwasm_std::{StdResult, Storage};
-use cw_storage_plus::{Bound, Item, Map};
+use cosmwasm_std::{Addr, Empty, Order, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
 use hex::ToHex;
 
+use babylon_apis::pagination::{page_limit, page_range};
 use babylon_proto::babylon::btclightclient::v1::BtcHeaderInfo;
 
 use crate::error::BTCLightclientError;
-use crate::msg::btc_header::BtcHeader;
-use crate::state::config::CONFIG;
+use crate::msg::btc_header::{BtcBaseHeader, BtcHeader};
+use crate::state::babylon_epoch_chain::rollback_to_btc_height;
+use crate::state::config::{Config, CONFIG};
 use crate::utils::btc_light_client::{total_work, verify_headers, zero_work};
 
 pub const BTC_TIP_KEY: &str = "btc_lc_tip";
@@ -21,6 +22,17 @@ pub const BTC_HEADERS: Map<u64, Vec<u8>> = Map::new("btc_lc_headers");
 pub const BTC_HEADER_BASE: Item<Vec<u8>> = Item::new("btc_lc_header_base");
 pub const BTC_HEIGHTS: Map<&[u8], u64> = Map::new("btc_lc_heights");
 pub const BTC_TIP: Item<Vec<u8>> = Item::new(BTC_TIP_KEY);
+/// Height of the earliest BTC header retained, if headers have been pruned
+pub const BTC_EARLIEST_HEIGHT: Item<u64> = Item::new("btc_lc_earliest_height");
+/// Relayers allowed to submit BTC headers directly (see `ExecuteMsg::BtcHeaders`).
+/// Anyone can submit headers while there are none
+pub const BTC_HEADER_RELAYERS: Map<&Addr, Empty> = Map::new("btc_lc_relayers");
+
+/// Number of headers retained on top of `checkpoint_finalization_timeout` when pruning, so that
+/// checkpoints about to be finalised and shallow forks can still be verified
+pub const PRUNING_MARGIN: u64 = 10;
+/// Maximum number of headers pruned automatically upon inserting headers, to bound the gas cost
+pub const MAX_AUTO_PRUNED_HEADERS: usize = 100;
 
 // getters for storages
 
@@ -71,6 +83,8 @@ fn insert_headers(storage: &mut dyn Storage, new_headers: &[BtcHeaderInfo]) -> S
 }
 
 // remove_headers removes BTC headers from the header chain storages, including
+// - remove all headers, so that headers of the old fork above the new tip are not left on the
+//   main chain
 // - remove all hash-to-height indices
 fn remove_headers(
     storage: &mut dyn Storage,
@@ -81,6 +95,7 @@ fn remove_headers(
     let mut rem_header = tip_header.clone();
     while rem_header.hash != parent_header.hash {
         // Remove header from storage
+        BTC_HEADERS.remove(storage, rem_header.height);
         BTC_HEIGHTS.remove(storage, rem_header.hash.as_ref());
         // Obtain the previous header
         rem_header = get_header(storage, rem_header.height - 1)?;
@@ -124,24 +139,16 @@ pub fn get_header_height(storage: &dyn Storage, hash: &[u8]) -> Result<u64, BTCL
     Ok(height)
 }
 
-// Settings for pagination
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
-
-// get_headers retrieves the BTC headers after a given height, up to limit headers
+// get_headers retrieves the BTC headers of the main chain after a given height (before it, in
+// reverse order), up to limit headers
 pub fn get_headers(
     storage: &dyn Storage,
     start_after: Option<u64>,
     limit: Option<u32>,
     reverse: Option<bool>,
 ) -> Result<Vec<BtcHeaderInfo>, BTCLightclientError> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start_after = start_after.map(Bound::exclusive);
-    let (start, end, order) = if reverse.unwrap_or(false) {
-        (None, start_after, Descending)
-    } else {
-        (start_after, None, Ascending)
-    };
+    let limit = page_limit(limit);
+    let (start, end, order) = page_range(start_after, reverse);
 
     BTC_HEADERS
         .range_raw(storage, start, end, order)
@@ -172,6 +179,59 @@ pub fn init(
         ));
     }
 
+    insert_initial_headers(storage, &btc_network, headers)
+}
+
+/// `init_from_base` initialises the BTC header chain storage from a trusted base header, and
+/// the (possibly empty) headers extending it.
+/// Alternative to `init`, used to bootstrap the BTC light client at instantiation
+pub fn init_from_base(
+    storage: &mut dyn Storage,
+    base_header: &BtcBaseHeader,
+    headers: &[BtcHeader],
+) -> Result<(), BTCLightclientError> {
+    let cfg = CONFIG.load(storage)?;
+    let btc_network = babylon_bitcoin::chain_params::get_chain_params(cfg.network);
+
+    let base_header = base_header.to_btc_header_info()?;
+    let mut prev_height = base_header.height;
+    let mut prev_work = total_work(&base_header)?;
+    let mut all_headers = vec![base_header];
+    for header in headers {
+        let header_info = header.to_btc_header_info(prev_height, prev_work)?;
+        prev_height = header_info.height;
+        prev_work = total_work(&header_info)?;
+        all_headers.push(header_info);
+    }
+    insert_initial_headers(storage, &btc_network, &all_headers)
+}
+
+/// `reset_base_header` re-anchors the BTC header chain storage at the given trusted base header,
+/// removing all the current headers, e.g. to catch up after a prolonged downtime.
+/// Returns the previous base and tip headers, if the light client was initialised
+pub fn reset_base_header(
+    storage: &mut dyn Storage,
+    base_header: &BtcBaseHeader,
+) -> Result<Option<(BtcHeaderInfo, BtcHeaderInfo)>, BTCLightclientError> {
+    let prev = if is_initialized(storage) {
+        Some((get_base_header(storage)?, get_tip(storage)?))
+    } else {
+        None
+    };
+    BTC_HEADERS.clear(storage);
+    BTC_HEIGHTS.clear(storage);
+    BTC_EARLIEST_HEIGHT.remove(storage);
+    init_from_base(storage, base_header, &[])?;
+    Ok(prev)
+}
+
+/// `insert_initial_headers` verifies the given base header and the headers extending it, and
+/// inserts them as the initial BTC header chain
+fn insert_initial_headers(
+    storage: &mut dyn Storage,
+    btc_network: &babylon_bitcoin::chain_params::Params,
+    headers: &[BtcHeaderInfo],
+) -> Result<(), BTCLightclientError> {
     // base header is the first header in the list
     let base_header = headers.first().ok_or(BTCLightclientError::InitError {})?;
 
@@ -180,13 +240,13 @@ pub fn init(
         .map_err(|_| BTCLightclientError::BTCHeaderDecodeError {})?;
 
     // verify the base header's pow
-    if babylon_bitcoin::pow::verify_header_pow(&btc_network, &base_btc_header).is_err() {
+    if babylon_bitcoin::pow::verify_header_pow(btc_network, &base_btc_header).is_err() {
         return Err(BTCLightclientError::BTCHeaderError {});
     }
 
     // verify subsequent headers
     let new_headers = &headers[1..headers.len()];
-    verify_headers(&btc_network, base_header, new_headers)?;
+    verify_headers(btc_network, base_header, new_headers)?;
 
     // all good, set base header, insert all headers, and set tip
 
@@ -207,10 +267,11 @@ pub fn init(
 /// Alternative to `init`, in which a user sends the initial batch of headers, instead of Babylon.
 ///
 /// Starts from zero work and heights. Mostly useful for integration tests.
+/// It returns the inserted headers
 pub fn init_from_user(
     storage: &mut dyn Storage,
     headers: &[BtcHeader],
-) -> Result<(), BTCLightclientError> {
+) -> Result<Vec<BtcHeaderInfo>, BTCLightclientError> {
     let mut prev_height = 0;
     let mut prev_work = zero_work();
     let headers = headers
@@ -222,13 +283,18 @@ pub fn init_from_user(
             Ok(btc_header)
         })
         .collect::<Result<Vec<BtcHeaderInfo>, BTCLightclientError>>()?;
-    init(storage, &headers)
+    init(storage, &headers)?;
+    Ok(headers)
 }
 
 /// handle_btc_headers_from_babylon verifies and inserts a number of
 /// finalised BTC headers to the header chain storage, and update
 /// the chain tip.
 ///
+/// Headers extending a fork (i.e. not the tip) switch the main chain to that fork if it has more
+/// cumulative work than the current one. The epochs whose checkpoint is in the abandoned headers
+/// are then rolled back (see `babylon_epoch_chain::rollback_to_btc_height`).
+///
 /// NOTE: upon each finalised epoch e, Babylon will send BTC headers between
 /// - the common ancestor of
 ///   - BTC tip upon finalising epoch e-1
@@ -258,7 +324,7 @@ pub fn handle_btc_headers_from_babylon(
         // Most common case: extending the current tip
 
         // Verify each new header after `current_tip` iteratively
-        verify_headers(&btc_network, &cur_tip.clone(), new_headers)?;
+        verify_headers(&btc_network, &cur_tip, new_headers)?;
 
         // All good, add all the headers to the BTC light client store
         insert_headers(storage, new_headers)?;
@@ -292,15 +358,68 @@ pub fn handle_btc_headers_from_babylon(
         // Remove all headers from the old fork first
         remove_headers(storage, &cur_tip, &fork_parent)?;
 
+        // Roll back the epochs whose checkpoint was in the old fork
+        rollback_to_btc_height(storage, fork_parent.height)?;
+
         // All good, add all the headers to the BTC light client store
         insert_headers(storage, new_headers)?;
 
         // Update tip
         set_tip(storage, new_tip)?;
     }
+
+    // Prune the headers beyond the retention depth, if any
+    if cfg.max_stored_headers > 0 {
+        prune_headers(storage, cfg.max_stored_headers, MAX_AUTO_PRUNED_HEADERS)?;
+    }
     Ok(())
 }
 
+/// get_earliest_height returns the height of the earliest BTC header retained, i.e. the base
+/// height unless headers have been pruned
+pub fn get_earliest_height(storage: &dyn Storage) -> Result<u64, BTCLightclientError> {
+    match BTC_EARLIEST_HEIGHT.may_load(storage)? {
+        Some(height) => Ok(height),
+        None => Ok(get_base_header(storage)?.height),
+    }
+}
+
+/// min_retained_headers returns the minimum number of headers retained below (and including) the
+/// tip when pruning
+pub fn min_retained_headers(cfg: &Config) -> u64 {
+    cfg.checkpoint_finalization_timeout + PRUNING_MARGIN
+}
+
+/// prune_headers prunes up to `limit` of the earliest BTC headers, retaining (at least) the
+/// `retain` headers below (and including) the tip, and never less than `min_retained_headers`.
+/// It returns the number of pruned headers
+pub fn prune_headers(
+    storage: &mut dyn Storage,
+    retain: u64,
+    limit: usize,
+) -> Result<u64, BTCLightclientError> {
+    let cfg = CONFIG.load(storage)?;
+    let retain = retain.max(min_retained_headers(&cfg));
+    let tip_height = get_tip(storage)?.height;
+    let earliest = get_earliest_height(storage)?;
+    let cutoff = (tip_height + 1)
+        .saturating_sub(retain)
+        .min(earliest.saturating_add(limit as u64));
+    if cutoff <= earliest {
+        return Ok(0);
+    }
+
+    for height in earliest..cutoff {
+        if let Some(header_bytes) = BTC_HEADERS.may_load(storage, height)? {
+            let header = BtcHeaderInfo::decode(header_bytes.as_slice())?;
+            BTC_HEIGHTS.remove(storage, header.hash.as_ref());
+            BTC_HEADERS.remove(storage, height);
+        }
+    }
+    BTC_EARLIEST_HEIGHT.save(storage, &cutoff)?;
+    Ok(cutoff - earliest)
+}
+
 /// handle_btc_headers_from_user verifies and inserts a number of finalised BTC headers to the
 /// header chain storage, and updates the chain's tip.
 ///
@@ -308,10 +427,11 @@ pub fn handle_btc_headers_from_babylon(
 /// Babylon itself is unavailable / unresponsive.
 /// The user wants to submit BTC headers directly, such that the Babylon contract maintains the same
 /// canonical BTC header chain as Babylon.
+/// It returns the inserted headers
 pub fn handle_btc_headers_from_user(
     storage: &mut dyn Storage,
     new_btc_headers: &[BtcHeader],
-) -> Result<(), BTCLightclientError> {
+) -> Result<Vec<BtcHeaderInfo>, BTCLightclientError> {
     let first_new_btc_header = new_btc_headers
         .first()
         .ok_or(BTCLightclientError::BTCHeaderEmpty {})?;
@@ -334,14 +454,45 @@ pub fn handle_btc_headers_from_user(
     }
 
     // Call `handle_btc_headers_from_babylon`
-    handle_btc_headers_from_babylon(storage, &new_headers_info)
+    handle_btc_headers_from_babylon(storage, &new_headers_info)?;
+    Ok(new_headers_info)
+}
+
+/// update_relayers adds and removes relayers from the BTC header relayers
+pub fn update_relayers(storage: &mut dyn Storage, add: &[Addr], remove: &[Addr]) -> StdResult<()> {
+    for relayer in add {
+        BTC_HEADER_RELAYERS.save(storage, relayer, &Empty {})?;
+    }
+    for relayer in remove {
+        BTC_HEADER_RELAYERS.remove(storage, relayer);
+    }
+    Ok(())
+}
+
+/// get_relayers returns the BTC header relayers, in address order
+pub fn get_relayers(storage: &dyn Storage) -> StdResult<Vec<Addr>> {
+    BTC_HEADER_RELAYERS
+        .keys(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+/// is_relayer returns whether `sender` can submit BTC headers directly, i.e. whether it is a
+/// relayer, or there are no relayers
+pub fn is_relayer(storage: &dyn Storage, sender: &Addr) -> bool {
+    BTC_HEADER_RELAYERS.has(storage, sender)
+        || BTC_HEADER_RELAYERS
+            .keys_raw(storage, None, None, Order::Ascending)
+            .next()
+            .is_none()
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
     use crate::msg::contract::ExecuteMsg;
-    use crate::state::config::Config;
+    use crate::state::config::{Config, CzHeaderStorage};
+    use crate::state::state_hash::{get_state_hash, SECTIONS};
+    use babylon_apis::state_hash::HashedStorage;
     use babylon_proto::babylon::btclightclient::v1::BtcHeaderInfo;
     use cosmwasm_std::from_json;
     use cosmwasm_std::testing::mock_dependencies;
@@ -353,6 +504,8 @@ pub(crate) mod tests {
         let cfg = Config {
             network: babylon_bitcoin::chain_params::Network::Regtest,
             babylon_tag: vec![0x1, 0x2, 0x3, 0x4],
+            babylon_tag_end_height: None,
+            babylon_tags: vec![],
             btc_confirmation_depth: 1,
             checkpoint_finalization_timeout: w as u64,
             notify_cosmos_zone: false,
@@ -360,6 +513,11 @@ pub(crate) mod tests {
             btc_finality: None,
             consumer_name: None,
             consumer_description: None,
+            cz_header_storage: CzHeaderStorage::Full,
+            babylon_counterparty: None,
+            packet_timeout: Default::default(),
+            max_stored_headers: 0,
+            on_item_error: Default::default(),
         };
         CONFIG.save(storage, &cfg).unwrap();
         w
@@ -371,6 +529,23 @@ pub(crate) mod tests {
         match resp {
             ExecuteMsg::BtcHeaders { headers } => headers,
             ExecuteMsg::Slashing { .. } => unreachable!("unexpected slashing message"),
+            ExecuteMsg::SlashFinalityProvider { .. } => {
+                unreachable!("unexpected slashing message")
+            }
+            ExecuteMsg::JailFinalityProvider { .. } => unreachable!("unexpected jailing message"),
+            ExecuteMsg::ResetBaseHeader { .. } => unreachable!("unexpected base header message"),
+            ExecuteMsg::SetStakingRoute { .. } => unreachable!("unexpected staking route message"),
+            ExecuteMsg::ReprocessPacket { .. } => unreachable!("unexpected reprocess message"),
+            ExecuteMsg::AllowChannelReopen { .. }
+            | ExecuteMsg::UpdateChannel { .. }
+            | ExecuteMsg::CheckChannels { .. } => {
+                unreachable!("unexpected channel message")
+            }
+            ExecuteMsg::ContinueMigration { .. } => unreachable!("unexpected migration message"),
+            ExecuteMsg::PruneHeaders { .. } => unreachable!("unexpected pruning message"),
+            ExecuteMsg::UpdateBtcHeaderRelayers { .. } => {
+                unreachable!("unexpected relayers message")
+            }
         }
     }
 
@@ -435,6 +610,86 @@ pub(crate) mod tests {
         ensure_headers(&storage, test_new_headers);
     }
 
+    // btc_lc_batch_works checks that handling all the new headers in a single batch leads to the
+    // same state as handling them one by one
+    #[test]
+    fn btc_lc_batch_works() {
+        let test_headers = get_btc_lc_headers();
+
+        let mut batch_deps = mock_dependencies();
+        let mut batch_storage = HashedStorage::new(&mut batch_deps.storage, SECTIONS);
+        let w = setup(&mut batch_storage);
+        init(&mut batch_storage, &test_headers[0..w + 1]).unwrap();
+        handle_btc_headers_from_babylon(&mut batch_storage, &test_headers[w + 1..]).unwrap();
+
+        let mut single_deps = mock_dependencies();
+        let mut single_storage = HashedStorage::new(&mut single_deps.storage, SECTIONS);
+        setup(&mut single_storage);
+        init(&mut single_storage, &test_headers[0..w + 1]).unwrap();
+        for header in &test_headers[w + 1..] {
+            handle_btc_headers_from_babylon(&mut single_storage, std::slice::from_ref(header))
+                .unwrap();
+        }
+
+        ensure_base_and_tip(&batch_storage, &test_headers);
+        ensure_headers(&batch_storage, &test_headers);
+        assert_eq!(
+            get_state_hash(&batch_storage).unwrap(),
+            get_state_hash(&single_storage).unwrap()
+        );
+    }
+
+    #[test]
+    fn btc_lc_pruning_works() {
+        let deps = mock_dependencies();
+        let mut storage = deps.storage;
+        let w = setup(&mut storage) as u64;
+        let test_headers = get_btc_lc_headers();
+        init(&mut storage, &test_headers).unwrap();
+        let base_height = test_headers.first().unwrap().height;
+        let tip_height = test_headers.last().unwrap().height;
+        assert_eq!(get_earliest_height(&storage).unwrap(), base_height);
+
+        // Pruning is bounded by the limit
+        assert_eq!(prune_headers(&mut storage, 50, 20).unwrap(), 20);
+        let earliest = get_earliest_height(&storage).unwrap();
+        assert_eq!(earliest, base_height + 20);
+        get_header(&storage, earliest - 1).unwrap_err();
+        get_header_height(&storage, test_headers[19].hash.as_ref()).unwrap_err();
+        let headers = get_headers(&storage, None, Some(1), None).unwrap();
+        assert_eq!(headers[0].height, earliest);
+
+        // Up to the retained headers
+        assert_eq!(prune_headers(&mut storage, 50, 100).unwrap(), 30);
+        assert_eq!(get_earliest_height(&storage).unwrap(), tip_height + 1 - 50);
+        assert_eq!(prune_headers(&mut storage, 50, 100).unwrap(), 0);
+
+        // And never below the minimum
+        prune_headers(&mut storage, 1, 100).unwrap();
+        assert_eq!(
+            get_earliest_height(&storage).unwrap(),
+            tip_height + 1 - (w + PRUNING_MARGIN)
+        );
+        get_header(&storage, tip_height + 1 - (w + PRUNING_MARGIN)).unwrap();
+    }
+
+    #[test]
+    fn btc_lc_auto_pruning_works() {
+        let deps = mock_dependencies();
+        let mut storage = deps.storage;
+        setup(&mut storage);
+        let mut cfg = CONFIG.load(&storage).unwrap();
+        cfg.max_stored_headers = 30;
+        CONFIG.save(&mut storage, &cfg).unwrap();
+
+        // Headers beyond the retention depth are pruned upon inserting new ones
+        let test_headers = get_btc_lc_headers();
+        init(&mut storage, &test_headers[..50]).unwrap();
+        handle_btc_headers_from_babylon(&mut storage, &test_headers[50..]).unwrap();
+        let tip_height = test_headers.last().unwrap().height;
+        assert_eq!(get_earliest_height(&storage).unwrap(), tip_height + 1 - 30);
+    }
+
     // Must match `forkHeaderHeight` in datagen/main.go
     const FORK_HEADER_HEIGHT: u64 = 90;
 
@@ -482,6 +737,77 @@ pub(crate) mod tests {
         for header_expected in test_headers[FORK_HEADER_HEIGHT as usize..].iter() {
             assert!(get_header_height(&storage, header_expected.hash.as_ref()).is_err());
         }
+
+        // the main chain ends at the new tip
+        let headers = get_headers(&storage, None, Some(1), Some(true)).unwrap();
+        assert_eq!(headers, vec![tip_expected.clone()]);
+    }
+
+    // btc_lc_fork_rolls_back_epochs checks that an accepted fork rolls back the epochs whose
+    // checkpoint is in the abandoned headers
+    #[test]
+    fn btc_lc_fork_rolls_back_epochs() {
+        use crate::state::babylon_epoch_chain::{
+            get_last_finalized_epoch, get_pending_epochs, BABYLON_CHECKPOINTS,
+            BABYLON_CHECKPOINT_BTC_HEIGHTS, BABYLON_EPOCHS, BABYLON_EPOCH_BASE,
+            BABYLON_EPOCH_EPOCH_LAST_FINALIZED, BABYLON_PENDING_EPOCHS,
+        };
+        use babylon_proto::babylon::epoching::v1::Epoch;
+
+        let deps = mock_dependencies();
+        let mut storage = deps.storage;
+        setup(&mut storage);
+        let test_headers = get_btc_lc_headers();
+        init(&mut storage, &test_headers).unwrap();
+        let fork_height = test_headers[FORK_HEADER_HEIGHT as usize - 1].height;
+
+        // Base epoch 1, epoch 2 checkpointed before the fork, epoch 3 after it, and pending
+        // epochs 4 and 5, checkpointed before and after the fork respectively
+        let epoch = |epoch_number| {
+            Epoch {
+                epoch_number,
+                ..Default::default()
+            }
+            .encode_to_vec()
+        };
+        BABYLON_EPOCH_BASE.save(&mut storage, &epoch(1)).unwrap();
+        for (epoch_number, btc_height) in [
+            (1, fork_height - 20),
+            (2, fork_height),
+            (3, fork_height + 1),
+        ] {
+            BABYLON_EPOCHS
+                .save(&mut storage, epoch_number, &epoch(epoch_number))
+                .unwrap();
+            BABYLON_CHECKPOINTS
+                .save(&mut storage, epoch_number, &vec![])
+                .unwrap();
+            BABYLON_CHECKPOINT_BTC_HEIGHTS
+                .save(&mut storage, epoch_number, &btc_height)
+                .unwrap();
+        }
+        BABYLON_EPOCH_EPOCH_LAST_FINALIZED
+            .save(&mut storage, &epoch(3))
+            .unwrap();
+        BABYLON_PENDING_EPOCHS
+            .save(&mut storage, 4, &fork_height)
+            .unwrap();
+        BABYLON_PENDING_EPOCHS
+            .save(&mut storage, 5, &(fork_height + 2))
+            .unwrap();
+
+        handle_btc_headers_from_babylon(&mut storage, &get_btc_lc_fork_headers()).unwrap();
+
+        // Epoch 3 is rolled back, and epoch 2 is the last finalised one
+        assert!(BABYLON_EPOCHS.may_load(&storage, 3).unwrap().is_none());
+        assert!(BABYLON_CHECKPOINTS.may_load(&storage, 3).unwrap().is_none());
+        assert!(BABYLON_EPOCHS.has(&storage, 2));
+        assert_eq!(get_last_finalized_epoch(&storage).unwrap().epoch_number, 2);
+        // Only the pending epoch checkpointed before the fork is left
+        assert_eq!(
+            get_pending_epochs(&storage).unwrap(),
+            vec![(4, fork_height)]
+        );
     }
 
     // btc_lc_fork_rejected simulates initialization of BTC light client storage,
@@ -710,4 +1036,44 @@ pub(crate) mod tests {
             assert!(get_header_height(&storage, header_expected.hash.as_ref()).is_err());
         }
     }
+
+    // btc_lc_reset_base_header_works checks that resetting the base header drops all the current
+    // headers, and re-anchors the header chain at the new base header
+    #[test]
+    fn btc_lc_reset_base_header_works() {
+        let deps = mock_dependencies();
+        let mut storage = deps.storage;
+        setup(&mut storage);
+
+        let test_headers = get_btc_lc_headers();
+        init(&mut storage, &test_headers[..5]).unwrap();
+
+        let new_base = &test_headers[7];
+        let base_header = BtcBaseHeader {
+            header: new_base.try_into().unwrap(),
+            height: new_base.height,
+            cum_work: cosmwasm_std::Uint256::from_str(
+                std::str::from_utf8(new_base.work.as_ref()).unwrap(),
+            )
+            .unwrap(),
+        };
+        let (prev_base, prev_tip) = reset_base_header(&mut storage, &base_header)
+            .unwrap()
+            .unwrap();
+        assert_eq!(prev_base, test_headers[0]);
+        assert_eq!(prev_tip, test_headers[4]);
+
+        // the new base header is both the base and the tip
+        ensure_base_and_tip(&storage, &test_headers[7..8]);
+        ensure_headers(&storage, &test_headers[7..8]);
+        // all the previous headers have been removed
+        for header in &test_headers[..5] {
+            assert!(get_header_height(&storage, header.hash.as_ref()).is_err());
+        }
+
+        // the header chain can be extended from the new base header
+        handle_btc_headers_from_babylon(&mut storage, &test_headers[8..]).unwrap();
+        ensure_base_and_tip(&storage, &test_headers[7..]);
+        ensure_headers(&storage, &test_headers[8..]);
+    }
 }
@@ -9,6 +9,14 @@ pub(crate) const CONFIG: Item<Config> = Item::new("config");
 pub struct Config {
     pub network: babylon_bitcoin::chain_params::Network,
     pub babylon_tag: Vec<u8>,
+    /// babylon_tag_end_height is the last BTC height the main `babylon_tag` is accepted at, or
+    /// `None` if it never expires, e.g. once Babylon has rotated its tag
+    #[serde(default)]
+    pub babylon_tag_end_height: Option<u64>,
+    /// babylon_tags are additional babylon tags accepted within a BTC height range, e.g. during
+    /// a tag rotation on Babylon
+    #[serde(default)]
+    pub babylon_tags: Vec<BabylonTag>,
     pub btc_confirmation_depth: u64,
     pub checkpoint_finalization_timeout: u64,
     /// notify_cosmos_zone indicates whether to send Cosmos zone messages notifying BTC-finalised headers.
@@ -23,4 +31,199 @@ pub struct Config {
     pub consumer_name: Option<String>,
     /// Consumer description
     pub consumer_description: Option<String>,
+    /// cz_header_storage is the way finalised CZ headers are stored
+    #[serde(default)]
+    pub cz_header_storage: CzHeaderStorage,
+    /// babylon_counterparty is the expected counterparty of the zoneconcierge channel.
+    /// If set, channels from other counterparties are refused
+    #[serde(default)]
+    pub babylon_counterparty: Option<IbcCounterparty>,
+    /// packet_timeout is the timeout and resubmission policy of outbound IBC packets
+    #[serde(default)]
+    pub packet_timeout: PacketTimeoutConfig,
+    /// max_stored_headers is the number of BTC headers retained below (and including) the tip.
+    /// Deeper headers are pruned automatically, but never within
+    /// `checkpoint_finalization_timeout` (plus a safety margin) of the tip.
+    /// Zero retains all headers
+    #[serde(default)]
+    pub max_stored_headers: u64,
+    /// on_item_error is how the failures of BTC staking operations of inbound packets are
+    /// handled
+    #[serde(default)]
+    pub on_item_error: OnItemError,
+}
+
+/// PacketTimeoutConfig is the timeout and resubmission policy of outbound IBC packets.
+///
+/// Packets time out on the block time only, as the contract does not track the Babylon height.
+/// A timed out packet closes the (ordered) zoneconcierge channel, so it is resubmitted over the
+/// next channel, with a timeout doubling upon every resubmission
+#[cw_serde]
+pub struct PacketTimeoutConfig {
+    /// timeout_seconds is the timeout of outbound packets, relative to the block time
+    pub timeout_seconds: u64,
+    /// max_resubmissions is the maximum number of times a timed out packet is resubmitted, before
+    /// being dropped
+    pub max_resubmissions: u32,
+    /// keepalive_seconds is the time after which an open channel without any inbound packet or
+    /// acknowledgement is stale, and closed by `ExecuteMsg::CheckChannels`.
+    /// Zero disables the keepalive
+    #[serde(default)]
+    pub keepalive_seconds: u64,
+}
+
+impl Default for PacketTimeoutConfig {
+    fn default() -> Self {
+        PacketTimeoutConfig {
+            timeout_seconds: 10 * 60,
+            max_resubmissions: 3,
+            keepalive_seconds: 0,
+        }
+    }
+}
+
+impl PacketTimeoutConfig {
+    /// timeout_seconds_at returns the timeout of a packet resubmitted `attempts` times, i.e.
+    /// `timeout_seconds * 2^attempts`
+    pub fn timeout_seconds_at(&self, attempts: u32) -> u64 {
+        self.timeout_seconds
+            .saturating_mul(1u64.checked_shl(attempts).unwrap_or(u64::MAX))
+    }
+}
+
+/// IbcCounterparty identifies the Babylon end of the zoneconcierge channel.
+///
+/// The counterparty chain id is not available to contracts during the channel handshake, but the
+/// connection is bound to the IBC light client of the counterparty chain, and thus identifies it
+#[cw_serde]
+pub struct IbcCounterparty {
+    /// connection_id is the id of the IBC connection to the Babylon chain, on the consumer side
+    pub connection_id: String,
+    /// port_id is the zoneconcierge port id on the Babylon chain
+    pub port_id: String,
+}
+
+/// CzHeaderStorage is the storage mode of finalised CZ headers
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum CzHeaderStorage {
+    /// Full stores the full CZ headers, enabling rich proofs and queries
+    #[default]
+    Full,
+    /// HashOnly stores only the (height, hash, babylon_epoch) of CZ headers, for minimal storage.
+    /// The last finalised CZ header is always stored in full
+    HashOnly,
+}
+
+/// OnItemError is how the failures of BTC staking operations of an inbound packet are handled,
+/// i.e. the operations rejected by a btc-staking contract, or quarantined by it as invalid
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum OnItemError {
+    /// SkipAndReport skips the failed operations, and reports them in the acknowledgement
+    /// (`BtcStakingResponse::rejected` and `BtcStakingResponse::quarantined`)
+    #[default]
+    SkipAndReport,
+    /// Abort fails the whole packet upon any failed operation, so that it is acknowledged with an
+    /// error and none of its operations are applied
+    Abort,
+}
+
+/// BabylonTag is a babylon tag accepted for checkpoints submitted within the
+/// `[start_height, end_height]` BTC height range
+#[cw_serde]
+pub struct BabylonTag {
+    pub tag: Vec<u8>,
+    pub start_height: u64,
+    /// end_height is the last BTC height the tag is accepted at, or `None` if open-ended
+    pub end_height: Option<u64>,
+}
+
+impl BabylonTag {
+    /// is_effective_at checks if the tag is accepted at the given BTC height
+    pub fn is_effective_at(&self, btc_height: u64) -> bool {
+        self.start_height <= btc_height && btc_height <= self.end_height.unwrap_or(u64::MAX)
+    }
+}
+
+impl Config {
+    /// accepted_babylon_tags returns the babylon tags accepted at the given BTC height.
+    /// The main `babylon_tag` is accepted up to its end height, if any, and comes first
+    pub fn accepted_babylon_tags(&self, btc_height: u64) -> Vec<&[u8]> {
+        std::iter::once(self.babylon_tag.as_slice())
+            .filter(|_| btc_height <= self.babylon_tag_end_height.unwrap_or(u64::MAX))
+            .chain(
+                self.babylon_tags
+                    .iter()
+                    .filter(|t| t.is_effective_at(btc_height))
+                    .map(|t| t.tag.as_slice()),
+            )
+            .collect()
+    }
+
+    /// all_babylon_tags returns all the configured babylon tags, regardless of their height range.
+    /// The main `babylon_tag` comes first
+    pub fn all_babylon_tags(&self) -> Vec<&[u8]> {
+        std::iter::once(self.babylon_tag.as_slice())
+            .chain(self.babylon_tags.iter().map(|t| t.tag.as_slice()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepted_babylon_tags_works() {
+        let mut cfg = Config {
+            network: babylon_bitcoin::chain_params::Network::Regtest,
+            babylon_tag: vec![0x1, 0x2, 0x3, 0x4],
+            babylon_tag_end_height: None,
+            babylon_tags: vec![
+                BabylonTag {
+                    tag: vec![0x5, 0x6, 0x7, 0x8],
+                    start_height: 10,
+                    end_height: Some(20),
+                },
+                BabylonTag {
+                    tag: vec![0x9, 0xa, 0xb, 0xc],
+                    start_height: 15,
+                    end_height: None,
+                },
+            ],
+            btc_confirmation_depth: 1,
+            checkpoint_finalization_timeout: 2,
+            notify_cosmos_zone: false,
+            btc_staking: None,
+            btc_finality: None,
+            consumer_name: None,
+            consumer_description: None,
+            cz_header_storage: CzHeaderStorage::Full,
+            babylon_counterparty: None,
+            packet_timeout: PacketTimeoutConfig::default(),
+            max_stored_headers: 0,
+            on_item_error: OnItemError::SkipAndReport,
+        };
+
+        let main_tag: &[u8] = &[0x1, 0x2, 0x3, 0x4];
+        let rotated_tag: &[u8] = &[0x5, 0x6, 0x7, 0x8];
+        let new_tag: &[u8] = &[0x9, 0xa, 0xb, 0xc];
+        assert_eq!(cfg.accepted_babylon_tags(9), vec![main_tag]);
+        assert_eq!(cfg.accepted_babylon_tags(10), vec![main_tag, rotated_tag]);
+        assert_eq!(
+            cfg.accepted_babylon_tags(20),
+            vec![main_tag, rotated_tag, new_tag]
+        );
+        assert_eq!(cfg.accepted_babylon_tags(21), vec![main_tag, new_tag]);
+
+        // The main tag expires past its end height
+        cfg.babylon_tag_end_height = Some(15);
+        assert_eq!(
+            cfg.accepted_babylon_tags(15),
+            vec![main_tag, rotated_tag, new_tag]
+        );
+        assert_eq!(cfg.accepted_babylon_tags(16), vec![rotated_tag, new_tag]);
+        assert_eq!(cfg.accepted_babylon_tags(21), vec![new_tag]);
+    }
 }
@@ -1,16 +1,18 @@
 //! cz_header_chain is the storage for the chain of **finalised** CZ headers.
 //! It maintains a chain of finalised CZ headers.
 //! NOTE: the CZ header chain is always finalised, i.e., w-deep on BTC.
+//! Depending on the `cz_header_storage` config, either full CZ headers or only their
+//! (height, hash, babylon_epoch) are kept.
 use prost::Message;
 use tendermint_proto::crypto::ProofOps;
 
-use cosmwasm_std::{StdResult, Storage};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw_storage_plus::{Bound, Item, Map};
 
 use babylon_proto::babylon::epoching::v1::Epoch;
 use babylon_proto::babylon::zoneconcierge::v1::IndexedHeader;
 
-use crate::state::config::CONFIG;
+use crate::state::config::{CzHeaderStorage, CONFIG};
 use crate::{error, utils};
 
 pub const CZ_HEADERS: Map<u64, Vec<u8>> = Map::new("cz_headers");
@@ -48,6 +50,28 @@ pub fn get_cz_header(
     Ok(indexed_header)
 }
 
+/// get_next_cz_header gets the first CZ header at or above a given height, if any.
+/// As CZ headers are only stored once BTC-finalised, it is the header BTC-finalising the CZ ledger
+/// at that height
+pub fn get_next_cz_header(
+    storage: &dyn Storage,
+    height: u64,
+) -> Result<Option<IndexedHeader>, error::CZHeaderChainError> {
+    let Some(item) = CZ_HEADERS
+        .range_raw(
+            storage,
+            Some(Bound::inclusive(height)),
+            None,
+            Order::Ascending,
+        )
+        .next()
+    else {
+        return Ok(None);
+    };
+    let (_, cz_header_bytes) = item?;
+    Ok(Some(IndexedHeader::decode(cz_header_bytes.as_slice())?))
+}
+
 /// verify_cz_header verifies whether a CZ header is committed to a Babylon epoch, including
 /// - The Babylon tx carrying this header is included in a Babylon block
 /// - The Babylon block's AppHash is committed to the AppHashRoot of the epoch
@@ -69,16 +93,62 @@ fn verify_cz_header(
     Ok(())
 }
 
+/// minimal_cz_header returns the (height, hash, babylon_epoch) of a CZ header, i.e. what is kept
+/// in `HashOnly` storage mode
+fn minimal_cz_header(cz_header: &IndexedHeader) -> IndexedHeader {
+    IndexedHeader {
+        hash: cz_header.hash.clone(),
+        height: cz_header.height,
+        babylon_epoch: cz_header.babylon_epoch,
+        ..Default::default()
+    }
+}
+
+fn encode_cz_header(cz_header: &IndexedHeader, mode: CzHeaderStorage) -> Vec<u8> {
+    match mode {
+        CzHeaderStorage::Full => cz_header.encode_to_vec(),
+        CzHeaderStorage::HashOnly => minimal_cz_header(cz_header).encode_to_vec(),
+    }
+}
+
 fn insert_cz_header(storage: &mut dyn Storage, cz_header: &IndexedHeader) -> StdResult<()> {
+    let cfg = CONFIG.load(storage)?;
+
     // insert indexed header
-    let cz_header_bytes = cz_header.encode_to_vec();
+    let cz_header_bytes = encode_cz_header(cz_header, cfg.cz_header_storage);
     CZ_HEADERS.save(storage, cz_header.height, &cz_header_bytes)?;
 
     // update last finalised header
     set_last_cz_header(storage, cz_header)
 }
 
-// TODO: unit test
+/// set_cz_header_storage switches the storage mode of CZ headers.
+/// Switching from `Full` to `HashOnly` prunes the CZ headers already stored, except for the last
+/// finalised one. Returns the number of pruned CZ headers
+pub fn set_cz_header_storage(
+    storage: &mut dyn Storage,
+    mode: CzHeaderStorage,
+) -> Result<u64, error::CZHeaderChainError> {
+    let mut cfg = CONFIG.load(storage)?;
+    let prune = cfg.cz_header_storage == CzHeaderStorage::Full && mode == CzHeaderStorage::HashOnly;
+    cfg.cz_header_storage = mode;
+    CONFIG.save(storage, &cfg)?;
+    if !prune {
+        return Ok(0);
+    }
+
+    let cz_headers = CZ_HEADERS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut pruned = 0;
+    for (height, cz_header_bytes) in cz_headers {
+        let cz_header = IndexedHeader::decode(cz_header_bytes.as_slice())?;
+        CZ_HEADERS.save(storage, height, &encode_cz_header(&cz_header, mode))?;
+        pruned += 1;
+    }
+    Ok(pruned)
+}
+
 pub fn handle_cz_header(
     storage: &mut dyn Storage,
     cz_header: &IndexedHeader,
@@ -90,3 +160,72 @@ pub fn handle_cz_header(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::btc_light_client::tests::setup;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    fn cz_header(height: u64) -> IndexedHeader {
+        IndexedHeader {
+            chain_id: "chain_id".to_string(),
+            hash: prost::bytes::Bytes::from(vec![height as u8; 32]),
+            height,
+            babylon_header_height: 100 + height,
+            babylon_epoch: 1,
+            babylon_tx_hash: prost::bytes::Bytes::from(vec![0xab; 32]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cz_header_storage_works() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        setup(storage);
+
+        // Full mode stores full headers
+        insert_cz_header(storage, &cz_header(1)).unwrap();
+        assert_eq!(get_cz_header(storage, 1).unwrap(), cz_header(1));
+
+        // Switching to hash-only mode prunes the stored headers
+        assert_eq!(
+            set_cz_header_storage(storage, CzHeaderStorage::HashOnly).unwrap(),
+            1
+        );
+        assert_eq!(
+            get_cz_header(storage, 1).unwrap(),
+            minimal_cz_header(&cz_header(1))
+        );
+
+        // Hash-only mode stores minimal headers, but keeps the last header in full
+        insert_cz_header(storage, &cz_header(2)).unwrap();
+        let stored = get_cz_header(storage, 2).unwrap();
+        assert_eq!(stored.height, 2);
+        assert_eq!(stored.hash, cz_header(2).hash);
+        assert_eq!(stored.babylon_epoch, 1);
+        assert!(stored.chain_id.is_empty());
+        assert_eq!(get_last_cz_header(storage).unwrap(), cz_header(2));
+
+        // Switching back to full mode only affects new headers
+        assert_eq!(
+            set_cz_header_storage(storage, CzHeaderStorage::Full).unwrap(),
+            0
+        );
+        insert_cz_header(storage, &cz_header(3)).unwrap();
+        assert_eq!(
+            get_cz_header(storage, 2).unwrap(),
+            minimal_cz_header(&cz_header(2))
+        );
+        assert_eq!(get_cz_header(storage, 3).unwrap(), cz_header(3));
+
+        // The next header of a height is the first one at or above it
+        assert_eq!(get_next_cz_header(storage, 3).unwrap(), Some(cz_header(3)));
+        assert_eq!(
+            get_next_cz_header(storage, 0).unwrap(),
+            Some(minimal_cz_header(&cz_header(1)))
+        );
+        assert_eq!(get_next_cz_header(storage, 4).unwrap(), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! migration runs the storage migrations of the contract in chunks (see
+//! `babylon_apis::migration`), advanced by anyone through `ContinueMigration`.
+//! The state section of a migration is paused until the migration is complete
+pub use babylon_apis::migration::{
+    continue_migration, get_migration_progress, schedule_migrations, Migration, MigrationProgress,
+    MIGRATION_PROGRESS,
+};
+use cosmwasm_std::Storage;
+
+use crate::error::ContractError;
+use crate::state::state_hash::SECTION_HASHES_REBUILD;
+
+/// The migrations of the current contract version, in order
+pub const MIGRATIONS: &[Migration] = &[SECTION_HASHES_REBUILD];
+
+/// ensure_sections_active ensures none of the given state sections is being migrated
+pub fn ensure_sections_active(
+    storage: &dyn Storage,
+    sections: &[&str],
+) -> Result<(), ContractError> {
+    match babylon_apis::migration::migrating_section(storage, sections)? {
+        Some(section) => Err(ContractError::SectionMigrating { section }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::state_hash::{SECTION_BTC_LIGHT_CLIENT, SECTION_CZ_HEADER_CHAIN};
+    use babylon_apis::storage_layout::VersionedMap;
+    use babylon_apis::versioned_namespace;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cw_storage_plus::Map;
+
+    const LEGACY: Map<u64, u32> = Map::new("test_values");
+    const VALUES: VersionedMap<u64, u64, u32> = VersionedMap::new(
+        versioned_namespace!("test_values", 1),
+        versioned_namespace!("test_values", 2),
+        |v| Ok(v.into()),
+    );
+
+    const TEST_MIGRATIONS: &[Migration] = &[Migration {
+        name: "test_values_v2",
+        section: SECTION_BTC_LIGHT_CLIENT,
+        step: |storage, _, limit| Ok((VALUES.migrate(storage, limit)?, None)),
+    }];
+
+    #[test]
+    fn chunked_migration_works() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        for i in 0..5 {
+            LEGACY.save(storage, i, &(i as u32)).unwrap();
+        }
+
+        // Nothing to do until the migration is scheduled
+        assert!(continue_migration(storage, TEST_MIGRATIONS, 2)
+            .unwrap()
+            .is_none());
+        ensure_sections_active(storage, &[SECTION_BTC_LIGHT_CLIENT]).unwrap();
+
+        assert_eq!(
+            schedule_migrations(storage, TEST_MIGRATIONS).unwrap(),
+            vec!["test_values_v2"]
+        );
+        // Scheduling is idempotent
+        assert!(schedule_migrations(storage, TEST_MIGRATIONS)
+            .unwrap()
+            .is_empty());
+
+        // The section is paused until the migration is complete, other sections are not
+        let err = ensure_sections_active(storage, &[SECTION_BTC_LIGHT_CLIENT]).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SectionMigrating {
+                section: SECTION_BTC_LIGHT_CLIENT.to_string()
+            }
+        );
+        ensure_sections_active(storage, &[SECTION_CZ_HEADER_CHAIN]).unwrap();
+
+        for (migrated, complete) in [(2, false), (4, false), (5, true)] {
+            let progress = continue_migration(storage, TEST_MIGRATIONS, 2)
+                .unwrap()
+                .unwrap();
+            assert_eq!(progress.migrated, migrated);
+            assert_eq!(progress.complete, complete);
+        }
+        assert!(VALUES.is_migrated(storage));
+        assert_eq!(VALUES.load(storage, 4).unwrap(), 4);
+        ensure_sections_active(storage, &[SECTION_BTC_LIGHT_CLIENT]).unwrap();
+        assert!(continue_migration(storage, TEST_MIGRATIONS, 2)
+            .unwrap()
+            .is_none());
+        assert_eq!(get_migration_progress(storage).unwrap().len(), 1);
+    }
+}
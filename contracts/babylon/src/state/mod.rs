@@ -4,12 +4,20 @@ use cosmwasm_std::{StdError, Storage};
 use babylon_proto::babylon::zoneconcierge::v1::BtcTimestamp;
 
 use crate::bindings::msg_btc_finalized_header;
+use crate::error::ContractError;
+use crate::state::state_hash::{
+    SECTION_BABYLON_EPOCH_CHAIN, SECTION_BTC_LIGHT_CLIENT, SECTION_CZ_HEADER_CHAIN,
+};
 use babylon_bindings::BabylonMsg;
 
 pub mod babylon_epoch_chain;
 pub mod btc_light_client;
 pub mod config;
 pub mod cz_header_chain;
+pub mod migration;
+pub mod packet_stats;
+pub mod staking_router;
+pub mod state_hash;
 
 /// handle_btc_timestamp handles a BTC timestamp
 /// It returns an option if the BTC timestamp is verified, otherwise an error.
@@ -19,16 +27,22 @@ pub mod cz_header_chain;
 pub fn handle_btc_timestamp(
     storage: &mut dyn Storage,
     btc_ts: &BtcTimestamp,
-) -> Result<Option<BabylonMsg>, StdError> {
+) -> Result<Option<BabylonMsg>, ContractError> {
+    migration::ensure_sections_active(
+        storage,
+        &[
+            SECTION_BTC_LIGHT_CLIENT,
+            SECTION_BABYLON_EPOCH_CHAIN,
+            SECTION_CZ_HEADER_CHAIN,
+        ],
+    )?;
+
     // extract and init/handle BTC headers
     let btc_headers = &btc_ts.btc_headers;
     if btc_light_client::is_initialized(storage) {
-        btc_light_client::handle_btc_headers_from_babylon(storage, btc_headers).map_err(|e| {
-            StdError::generic_err(format!("failed to handle BTC headers from Babylon: {e}"))
-        })?;
+        btc_light_client::handle_btc_headers_from_babylon(storage, btc_headers)?;
     } else {
-        btc_light_client::init(storage, btc_headers)
-            .map_err(|e| StdError::generic_err(format!("failed to initialize BTC headers: {e}")))?;
+        btc_light_client::init(storage, btc_headers)?;
     }
 
     // extract and init/handle Babylon epoch chain
@@ -41,15 +55,9 @@ pub fn handle_btc_timestamp(
             raw_ckpt,
             proof_epoch_sealed,
             &txs_info,
-        )
-        .map_err(|e| {
-            StdError::generic_err(format!("failed to handle Babylon epoch from Babylon: {e}"))
-        })?;
+        )?;
     } else {
-        babylon_epoch_chain::init(storage, epoch, raw_ckpt, proof_epoch_sealed, &txs_info)
-            .map_err(|e| {
-                StdError::generic_err(format!("failed to initialize Babylon epoch: {e}"))
-            })?;
+        babylon_epoch_chain::init(storage, epoch, raw_ckpt, proof_epoch_sealed, &txs_info)?;
     }
 
     // try to extract and handle CZ header
@@ -63,10 +71,7 @@ pub fn handle_btc_timestamp(
             .proof_cz_header_in_epoch
             .as_ref()
             .ok_or(StdError::generic_err("empty proof_cz_header_in_epoch"))?;
-        cz_header_chain::handle_cz_header(storage, cz_header, epoch, proof_cz_header_in_epoch)
-            .map_err(|e| {
-                StdError::generic_err(format!("failed to handle CZ header from Babylon: {e}"))
-            })?;
+        cz_header_chain::handle_cz_header(storage, cz_header, epoch, proof_cz_header_in_epoch)?;
 
         // Finalised CZ header verified, notify Cosmos zone about the newly finalised CZ header
         // Cosmos zone that deploys corresponding CosmWasm plugin will handle this message
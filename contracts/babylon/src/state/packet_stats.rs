@@ -0,0 +1,88 @@
+//! packet_stats keeps lightweight processing stats of the inbound IBC packets, by packet type,
+//! to inform the tuning of parameters such as the maximum number of headers per packet.
+//! Contracts cannot observe the gas they consume, so packets are profiled by size only
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw_storage_plus::Map;
+
+/// Number of most recent packet sizes kept per packet type
+pub const PACKET_SIZES_LEN: usize = 10;
+
+/// Processing stats of the inbound packets, by packet type
+pub const PACKET_STATS: Map<&str, PacketStats> = Map::new("packet_stats");
+
+/// PacketStats are the processing stats of the inbound packets of a given type
+#[cw_serde]
+#[derive(Default)]
+pub struct PacketStats {
+    /// count is the number of packets received
+    pub count: u64,
+    /// failures is the number of packets acknowledged with an error
+    pub failures: u64,
+    /// total_bytes is the cumulative size of the packets, in bytes
+    pub total_bytes: u64,
+    /// last_sizes are the sizes (in bytes) of the `PACKET_SIZES_LEN` most recent packets, the
+    /// most recent last
+    pub last_sizes: Vec<u64>,
+}
+
+/// record_packet records a packet of the given type and size (in bytes), along with whether it
+/// was processed successfully
+pub fn record_packet(
+    storage: &mut dyn Storage,
+    packet_type: &str,
+    size: usize,
+    success: bool,
+) -> StdResult<()> {
+    let mut stats = PACKET_STATS
+        .may_load(storage, packet_type)?
+        .unwrap_or_default();
+    let size = size as u64;
+    stats.count += 1;
+    if !success {
+        stats.failures += 1;
+    }
+    stats.total_bytes = stats.total_bytes.saturating_add(size);
+    if stats.last_sizes.len() >= PACKET_SIZES_LEN {
+        stats.last_sizes.remove(0);
+    }
+    stats.last_sizes.push(size);
+    PACKET_STATS.save(storage, packet_type, &stats)
+}
+
+/// get_packet_stats returns the (packet type, stats) of all the packet types received so far
+pub fn get_packet_stats(storage: &dyn Storage) -> StdResult<Vec<(String, PacketStats)>> {
+    PACKET_STATS
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    #[test]
+    fn record_packet_works() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+
+        for size in 1..=PACKET_SIZES_LEN + 2 {
+            record_packet(storage, "btc_timestamp", size, size != 1).unwrap();
+        }
+        record_packet(storage, "btc_staking", 100, true).unwrap();
+
+        let stats = get_packet_stats(storage).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].0, "btc_staking");
+        assert_eq!(stats[0].1.last_sizes, vec![100]);
+
+        let (_, ts_stats) = &stats[1];
+        let n = PACKET_SIZES_LEN as u64 + 2;
+        assert_eq!(ts_stats.count, n);
+        assert_eq!(ts_stats.failures, 1);
+        assert_eq!(ts_stats.total_bytes, n * (n + 1) / 2);
+        // Only the most recent sizes are kept
+        assert_eq!(ts_stats.last_sizes, (3..=n).collect::<Vec<_>>());
+    }
+}
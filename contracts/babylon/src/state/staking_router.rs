@@ -0,0 +1,299 @@
+//! staking_router routes BTC staking operations to btc-staking contracts, by consumer id.
+//! Finality providers carry their consumer id; delegations are routed along with the first
+//! finality provider they delegate to, and later delegation updates follow the delegation.
+//! Operations of consumers without a route go to the default `btc_staking` contract.
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, StdError, StdResult, Storage};
+use cw_storage_plus::Map;
+
+use babylon_apis::btc_staking_api::{
+    ActiveBtcDelegation, ExecuteMsg, NewFinalityProvider, SlashedBtcDelegation, TxHash,
+    UnbondedBtcDelegation,
+};
+use babylon_bitcoin::Transaction;
+
+use crate::error::ContractError;
+use crate::state::config::Config;
+
+/// btc-staking contract of each consumer id
+pub const STAKING_ROUTES: Map<&str, Addr> = Map::new("staking_routes");
+/// Consumer id of each finality provider routed so far, by BTC PK (hex)
+pub const FP_CONSUMERS: Map<&str, String> = Map::new("staking_fp_consumers");
+/// Consumer id of each delegation routed so far, by staking tx hash (canonical hex)
+pub const DEL_CONSUMERS: Map<&str, String> = Map::new("staking_del_consumers");
+
+/// set_staking_route sets, or removes if `contract` is `None`, the btc-staking contract of the
+/// given consumer
+pub fn set_staking_route(
+    storage: &mut dyn Storage,
+    consumer_id: &str,
+    contract: Option<&Addr>,
+) -> StdResult<()> {
+    match contract {
+        Some(contract) => STAKING_ROUTES.save(storage, consumer_id, contract),
+        None => {
+            STAKING_ROUTES.remove(storage, consumer_id);
+            Ok(())
+        }
+    }
+}
+
+/// get_staking_routes returns all the (consumer id, btc-staking contract) routes
+pub fn get_staking_routes(storage: &dyn Storage) -> StdResult<Vec<(String, Addr)>> {
+    STAKING_ROUTES
+        .range(storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect()
+}
+
+/// route returns the btc-staking contract of the given consumer, falling back to the default one
+pub fn route(
+    storage: &dyn Storage,
+    cfg: &Config,
+    consumer_id: Option<&str>,
+) -> Result<Addr, ContractError> {
+    if let Some(consumer_id) = consumer_id {
+        if let Some(addr) = STAKING_ROUTES.may_load(storage, consumer_id)? {
+            return Ok(addr);
+        }
+    }
+    cfg.btc_staking
+        .clone()
+        .ok_or(ContractError::BtcStakingNotSet {})
+}
+
+/// route_fp returns the btc-staking contract of the consumer of the given finality provider
+pub fn route_fp(
+    storage: &dyn Storage,
+    cfg: &Config,
+    fp_btc_pk_hex: &str,
+) -> Result<Addr, ContractError> {
+    let consumer_id = FP_CONSUMERS.may_load(storage, fp_btc_pk_hex)?;
+    route(storage, cfg, consumer_id.as_deref())
+}
+
+fn staking_tx_hash(del: &ActiveBtcDelegation) -> StdResult<String> {
+    let staking_tx: Transaction = babylon_bitcoin::deserialize(&del.staking_tx)
+        .map_err(|_| StdError::generic_err("Failed to decode staking tx"))?;
+    Ok(TxHash::from(staking_tx.txid()).to_hex())
+}
+
+/// batch_ops returns the BTC public keys of the new finality providers, and the staking tx hashes
+/// of the delegation operations, of the given `BtcStaking` message
+pub fn batch_ops(msg: &ExecuteMsg) -> StdResult<(Vec<String>, Vec<String>)> {
+    let ExecuteMsg::BtcStaking {
+        new_fp,
+        active_del,
+        slashed_del,
+        unbonded_del,
+    } = msg
+    else {
+        return Ok((vec![], vec![]));
+    };
+    let fps = new_fp.iter().map(|fp| fp.btc_pk_hex.clone()).collect();
+    let mut dels = active_del
+        .iter()
+        .map(staking_tx_hash)
+        .collect::<StdResult<Vec<_>>>()?;
+    dels.extend(slashed_del.iter().map(|d| d.staking_tx_hash.clone()));
+    dels.extend(unbonded_del.iter().map(|u| u.staking_tx_hash.clone()));
+    Ok((fps, dels))
+}
+
+/// del_consumer returns the consumer id of the delegation with the given staking tx hash.
+/// Malformed hashes have no consumer, and are left to the btc-staking contract to reject
+fn del_consumer(storage: &dyn Storage, staking_tx_hash: &str) -> StdResult<Option<String>> {
+    match TxHash::from_hex(staking_tx_hash) {
+        Ok(hash) => DEL_CONSUMERS.may_load(storage, &hash.to_hex()),
+        Err(_) => Ok(None),
+    }
+}
+
+#[derive(Default)]
+struct BtcStakingBatch {
+    new_fp: Vec<NewFinalityProvider>,
+    active_del: Vec<ActiveBtcDelegation>,
+    slashed_del: Vec<SlashedBtcDelegation>,
+    unbonded_del: Vec<UnbondedBtcDelegation>,
+}
+
+/// split_btc_staking splits the given BTC staking operations into one `BtcStaking` message per
+/// btc-staking contract, recording the consumers of new finality providers and delegations.
+/// Delegations without a known consumer are routed along `default_consumer` (i.e. the consumer
+/// of the channel the operations were received over), if any.
+/// An empty set of operations is sent to the btc-staking contract of `default_consumer`
+pub fn split_btc_staking(
+    storage: &mut dyn Storage,
+    cfg: &Config,
+    default_consumer: Option<&str>,
+    new_fp: Vec<NewFinalityProvider>,
+    active_del: Vec<ActiveBtcDelegation>,
+    slashed_del: Vec<SlashedBtcDelegation>,
+    unbonded_del: Vec<UnbondedBtcDelegation>,
+) -> Result<Vec<(Addr, ExecuteMsg)>, ContractError> {
+    let mut batches: BTreeMap<Addr, BtcStakingBatch> = BTreeMap::new();
+
+    for fp in new_fp {
+        FP_CONSUMERS.save(storage, &fp.btc_pk_hex, &fp.consumer_id)?;
+        let addr = route(storage, cfg, Some(&fp.consumer_id))?;
+        batches.entry(addr).or_default().new_fp.push(fp);
+    }
+    for del in active_del {
+        let consumer_id = match del.fp_btc_pk_list.first() {
+            Some(fp_btc_pk_hex) => FP_CONSUMERS.may_load(storage, fp_btc_pk_hex)?,
+            None => None,
+        };
+        if let Some(consumer_id) = &consumer_id {
+            DEL_CONSUMERS.save(storage, &staking_tx_hash(&del)?, consumer_id)?;
+        }
+        let addr = route(storage, cfg, consumer_id.as_deref().or(default_consumer))?;
+        batches.entry(addr).or_default().active_del.push(del);
+    }
+    for del in slashed_del {
+        let consumer_id = del_consumer(storage, &del.staking_tx_hash)?;
+        let addr = route(storage, cfg, consumer_id.as_deref().or(default_consumer))?;
+        batches.entry(addr).or_default().slashed_del.push(del);
+    }
+    for del in unbonded_del {
+        let consumer_id = del_consumer(storage, &del.staking_tx_hash)?;
+        let addr = route(storage, cfg, consumer_id.as_deref().or(default_consumer))?;
+        batches.entry(addr).or_default().unbonded_del.push(del);
+    }
+
+    if batches.is_empty() {
+        batches.insert(
+            route(storage, cfg, default_consumer)?,
+            BtcStakingBatch::default(),
+        );
+    }
+    Ok(batches
+        .into_iter()
+        .map(|(addr, batch)| {
+            let msg = ExecuteMsg::BtcStaking {
+                new_fp: batch.new_fp,
+                active_del: batch.active_del,
+                slashed_del: batch.slashed_del,
+                unbonded_del: batch.unbonded_del,
+            };
+            (addr, msg)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::btc_light_client::tests::setup;
+    use crate::state::config::CONFIG;
+    use cosmwasm_std::testing::mock_dependencies;
+    use test_utils::get_active_btc_delegation;
+
+    fn new_fp(btc_pk_hex: &str, consumer_id: &str) -> NewFinalityProvider {
+        NewFinalityProvider {
+            description: None,
+            commission: Default::default(),
+            addr: "fp".to_string(),
+            btc_pk_hex: btc_pk_hex.to_string(),
+            pop: None,
+            consumer_id: consumer_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn split_btc_staking_works() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        setup(storage);
+        let mut cfg = CONFIG.load(storage).unwrap();
+
+        // No default contract, and no route
+        split_btc_staking(storage, &cfg, None, vec![], vec![], vec![], vec![]).unwrap_err();
+
+        let default = Addr::unchecked("default");
+        let other = Addr::unchecked("other");
+        cfg.btc_staking = Some(default.clone());
+        set_staking_route(storage, "consumer-2", Some(&other)).unwrap();
+
+        let mut del = get_active_btc_delegation();
+        let del_hash = staking_tx_hash(&del).unwrap();
+        del.fp_btc_pk_list = vec!["fp2".to_string()];
+        let msgs = split_btc_staking(
+            storage,
+            &cfg,
+            None,
+            vec![new_fp("fp1", "consumer-1"), new_fp("fp2", "consumer-2")],
+            vec![del],
+            vec![],
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].0, default);
+        assert_eq!(msgs[1].0, other);
+        let ExecuteMsg::BtcStaking {
+            new_fp, active_del, ..
+        } = &msgs[1].1
+        else {
+            panic!("unexpected message");
+        };
+        assert_eq!(new_fp.len(), 1);
+        assert_eq!(new_fp[0].btc_pk_hex, "fp2");
+        assert_eq!(active_del.len(), 1);
+
+        // Delegation updates follow the delegation, whatever the case of their staking tx hash
+        let msgs = split_btc_staking(
+            storage,
+            &cfg,
+            None,
+            vec![],
+            vec![],
+            vec![SlashedBtcDelegation {
+                staking_tx_hash: del_hash.clone(),
+                recovered_fp_btc_sk: "sk".to_string(),
+            }],
+            vec![UnbondedBtcDelegation {
+                staking_tx_hash: del_hash.to_uppercase(),
+                unbonding_tx_sig: Default::default(),
+            }],
+        )
+        .unwrap();
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].0, other);
+        let ExecuteMsg::BtcStaking {
+            slashed_del,
+            unbonded_del,
+            ..
+        } = &msgs[0].1
+        else {
+            panic!("unexpected message");
+        };
+        assert_eq!(slashed_del.len(), 1);
+        assert_eq!(slashed_del[0].staking_tx_hash, del_hash);
+        assert_eq!(unbonded_del.len(), 1);
+        assert_eq!(route_fp(storage, &cfg, "fp1").unwrap(), default);
+
+        // Delegations without a known consumer follow the consumer of the channel, if any
+        let unknown = || SlashedBtcDelegation {
+            staking_tx_hash: "00".repeat(32),
+            recovered_fp_btc_sk: "sk".to_string(),
+        };
+        let msgs = split_btc_staking(storage, &cfg, None, vec![], vec![], vec![unknown()], vec![])
+            .unwrap();
+        assert_eq!(msgs[0].0, default);
+        let msgs = split_btc_staking(
+            storage,
+            &cfg,
+            Some("consumer-2"),
+            vec![],
+            vec![],
+            vec![unknown()],
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(msgs[0].0, other);
+
+        // Removing the route falls back to the default contract
+        set_staking_route(storage, "consumer-2", None).unwrap();
+        assert_eq!(route_fp(storage, &cfg, "fp2").unwrap(), default);
+    }
+}
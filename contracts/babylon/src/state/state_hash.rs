@@ -0,0 +1,115 @@
+//! state_hash defines the state sections of the contract, which the state hash is computed over
+//! (see `babylon_apis::state_hash`). The sections are also the unit of storage migrations (see
+//! `migration`)
+use cosmwasm_std::{StdResult, Storage};
+use cw_storage_plus::Map;
+
+use babylon_apis::migration::Migration;
+use babylon_apis::state_hash::{
+    rebuild_section_hashes, StateHashResponse, StateSection, STATE_HASH_REBUILD,
+};
+
+pub const SECTION_CONFIG: &str = "config";
+pub const SECTION_BTC_LIGHT_CLIENT: &str = "btc_light_client";
+pub const SECTION_BABYLON_EPOCH_CHAIN: &str = "babylon_epoch_chain";
+pub const SECTION_CZ_HEADER_CHAIN: &str = "cz_header_chain";
+
+/// All state sections, in the order they are folded into the state hash
+pub const SECTIONS: &[StateSection] = &[
+    StateSection {
+        name: SECTION_CONFIG,
+        namespaces: &["config", "contract_info"],
+    },
+    StateSection {
+        name: SECTION_BTC_LIGHT_CLIENT,
+        namespaces: &[
+            "btc_lc_headers",
+            "btc_lc_header_base",
+            "btc_lc_heights",
+            "btc_lc_tip",
+            "btc_lc_earliest_height",
+            "btc_lc_relayers",
+        ],
+    },
+    StateSection {
+        name: SECTION_BABYLON_EPOCH_CHAIN,
+        namespaces: &[
+            "babylon_epochs",
+            "babylon_epoch_base",
+            "babylon_epoch_last",
+            "babylon_checkpoints",
+            "babylon_pending_epochs",
+            "babylon_ckpt_btc_heights",
+        ],
+    },
+    StateSection {
+        name: SECTION_CZ_HEADER_CHAIN,
+        namespaces: &["cz_headers", "cz_header_last"],
+    },
+];
+
+/// The migration rebuilding the section hashes, for contracts migrating from a version not keeping
+/// them
+pub const SECTION_HASHES_REBUILD: Migration = Migration {
+    name: STATE_HASH_REBUILD,
+    section: STATE_HASH_REBUILD,
+    step: |storage, cursor, limit| rebuild_section_hashes(storage, SECTIONS, cursor, limit),
+};
+
+/// Running section hashes kept by the contract versions predating the multiset state hash
+const LEGACY_SECTION_HASHES: Map<&str, Vec<u8>> = Map::new("state_hash_sections");
+
+/// get_state_hash returns the hashes of all sections, together with the overall state hash
+pub fn get_state_hash(storage: &dyn Storage) -> StdResult<StateHashResponse> {
+    babylon_apis::state_hash::get_state_hash(storage, SECTIONS)
+}
+
+/// remove_legacy_section_hashes removes the running section hashes of older contract versions,
+/// so that they do not weigh in the state hash
+pub fn remove_legacy_section_hashes(storage: &mut dyn Storage) {
+    LEGACY_SECTION_HASHES.clear(storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::btc_light_client::tests::setup;
+    use crate::state::btc_light_client::{BTC_HEADERS, BTC_TIP};
+    use babylon_apis::state_hash::HashedStorage;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn state_hash_is_deterministic() {
+        let (mut storage1, mut storage2) = (MockStorage::new(), MockStorage::new());
+        let mut storage1 = HashedStorage::new(&mut storage1, SECTIONS);
+        let mut storage2 = HashedStorage::new(&mut storage2, SECTIONS);
+        setup(&mut storage1);
+        setup(&mut storage2);
+        let empty = get_state_hash(&storage1).unwrap();
+
+        // Entries are hashed under their section
+        BTC_HEADERS.save(&mut storage1, 1, &b"a".to_vec()).unwrap();
+        BTC_TIP.save(&mut storage1, &b"a".to_vec()).unwrap();
+        let hash1 = get_state_hash(&storage1).unwrap();
+        assert_eq!(hash1.sections[0], empty.sections[0]);
+        assert_ne!(hash1.sections[1], empty.sections[1]);
+        assert_ne!(hash1.hash, empty.hash);
+
+        // The same contents hash the same, however they were written and pruned
+        BTC_TIP.save(&mut storage2, &b"b".to_vec()).unwrap();
+        BTC_HEADERS.save(&mut storage2, 2, &b"b".to_vec()).unwrap();
+        BTC_HEADERS.save(&mut storage2, 1, &b"a".to_vec()).unwrap();
+        assert_ne!(get_state_hash(&storage2).unwrap(), hash1);
+        BTC_HEADERS.remove(&mut storage2, 2);
+        BTC_TIP.save(&mut storage2, &b"a".to_vec()).unwrap();
+        assert_eq!(get_state_hash(&storage2).unwrap(), hash1);
+
+        // The running hashes of older versions are removed
+        LEGACY_SECTION_HASHES
+            .save(&mut storage2, SECTION_BTC_LIGHT_CLIENT, &vec![1])
+            .unwrap();
+        assert_ne!(get_state_hash(&storage2).unwrap(), hash1);
+        remove_legacy_section_hashes(&mut storage2);
+        assert_eq!(get_state_hash(&storage2).unwrap(), hash1);
+    }
+}
@@ -1,4 +1,4 @@
-use babylon_bitcoin::BlockHeader;
+use babylon_bitcoin::{BlockHeader, Transaction};
 use babylon_proto::babylon::btccheckpoint::v1::TransactionInfo;
 use babylon_proto::babylon::checkpointing::v1::{RawCheckpoint, CURRENT_VERSION};
 use babylon_proto::babylon::epoching::v1::Epoch;
@@ -105,26 +105,17 @@ pub fn verify_checkpoint_submitted(
     btc_headers: &[BlockHeader; NUM_BTC_TXS],
     babylon_tag: &[u8],
 ) -> Result<(), String> {
-    // decoded checkpoint data
-    let mut checkpoint_data_arr: Vec<Vec<u8>> = vec![];
-
-    // for each tx info, verify the Merkle proof and extract checkpoint data
-    for i in 0..NUM_BTC_TXS {
-        let tx_info = &txs_info[i];
-        let btc_header = &btc_headers[i];
-        // verify Merkle proof and extract BTC tx
-        let btc_tx = super::bitcoin::parse_tx_info(tx_info, btc_header)?;
-        // extract OP_RETURN data
-        let checkpoint_data = super::bitcoin::extract_checkpoint_data(&btc_tx, babylon_tag, i)?;
-        checkpoint_data_arr.push(checkpoint_data);
-    }
-
-    // decode checkpoint_data array to raw checkpoint
-    let decode_raw_ckpt = RawCheckpoint::from_checkpoint_data(
-        CURRENT_VERSION,
-        checkpoint_data_arr[0].clone(),
-        checkpoint_data_arr[1].clone(),
-    )?;
+    // for each tx info, verify the Merkle proof and extract BTC tx
+    let btc_txs: [Transaction; NUM_BTC_TXS] = txs_info
+        .iter()
+        .zip(btc_headers)
+        .map(|(tx_info, btc_header)| super::bitcoin::parse_tx_info(tx_info, btc_header))
+        .collect::<Result<Vec<_>, _>>()?
+        .try_into()
+        .map_err(|_| "wrong number of BTC txs".to_string())?;
+
+    // extract the raw checkpoint from the BTC txs
+    let decode_raw_ckpt = parse_checkpoint(&btc_txs, babylon_tag)?;
 
     // check if the decoded raw checkpoint is same as the given one
     if decode_raw_ckpt.ne(raw_ckpt) {
@@ -136,6 +127,27 @@ pub fn verify_checkpoint_submitted(
     Ok(())
 }
 
+/// parse_checkpoint extracts and decodes a raw checkpoint from the OP_RETURN data of the
+/// given BTC txs, which carry the first and second part of the checkpoint, respectively
+pub fn parse_checkpoint(
+    btc_txs: &[Transaction; NUM_BTC_TXS],
+    babylon_tag: &[u8],
+) -> Result<RawCheckpoint, String> {
+    // extract OP_RETURN data
+    let checkpoint_data_arr = btc_txs
+        .iter()
+        .enumerate()
+        .map(|(i, btc_tx)| super::bitcoin::extract_checkpoint_data(btc_tx, babylon_tag, i))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // decode checkpoint_data array to raw checkpoint
+    RawCheckpoint::from_checkpoint_data(
+        CURRENT_VERSION,
+        checkpoint_data_arr[0].clone(),
+        checkpoint_data_arr[1].clone(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,5 +180,28 @@ mod tests {
         verify_checkpoint_submitted(&raw_ckpt, txs_info_arr, btc_headers, &babylon_tag).unwrap();
     }
 
+    #[test]
+    fn parse_checkpoint_works() {
+        let (btc_ts, _) = get_btc_timestamp_and_headers();
+        let raw_ckpt = btc_ts.raw_checkpoint.unwrap();
+        let txs_info = btc_ts.proof.unwrap().proof_epoch_submitted;
+        let btc_txs: [Transaction; NUM_BTC_TXS] = [
+            babylon_bitcoin::deserialize(&txs_info[0].transaction).unwrap(),
+            babylon_bitcoin::deserialize(&txs_info[1].transaction).unwrap(),
+        ];
+
+        let babylon_tag = vec![0x1, 0x2, 0x3, 0x4];
+        let parsed_ckpt = parse_checkpoint(&btc_txs, &babylon_tag).unwrap();
+        assert_eq!(parsed_ckpt, raw_ckpt);
+
+        // Wrong tag
+        let babylon_tag = vec![0x5, 0x6, 0x7, 0x8];
+        parse_checkpoint(&btc_txs, &babylon_tag).unwrap_err();
+
+        // Wrong order
+        let btc_txs = [btc_txs[1].clone(), btc_txs[0].clone()];
+        parse_checkpoint(&btc_txs, &babylon_tag).unwrap_err();
+    }
+
     // TODO: more tests on different scenarios
 }
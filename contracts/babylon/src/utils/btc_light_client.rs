@@ -6,20 +6,22 @@ use cosmwasm_std::{StdResult, Uint256};
 use std::str::{from_utf8, FromStr};
 
 /// verify_headers verifies whether `new_headers` are valid consecutive headers
-/// after the given `first_header`
+/// after the given `first_header`.
+/// Each header is decoded only once, and the whole batch is verified in memory before anything
+/// is written to storage
 pub fn verify_headers(
     btc_network: &babylon_bitcoin::chain_params::Params,
     first_header: &BtcHeaderInfo,
     new_headers: &[BtcHeaderInfo],
 ) -> Result<(), error::BTCLightclientError> {
+    // decode first header to rust-bitcoin's type
+    let mut last_header = first_header;
+    let mut last_btc_header: BlockHeader =
+        babylon_bitcoin::deserialize(last_header.header.as_ref())
+            .map_err(|_| error::BTCLightclientError::BTCHeaderDecodeError {})?;
+    let mut cum_work_old = total_work(last_header)?;
     // verify each new header iteratively
-    let mut last_header = first_header.clone();
-    let mut cum_work_old = total_work(&last_header)?;
     for (i, new_header) in new_headers.iter().enumerate() {
-        // decode last header to rust-bitcoin's type
-        let last_btc_header: BlockHeader =
-            babylon_bitcoin::deserialize(last_header.header.as_ref())
-                .map_err(|_| error::BTCLightclientError::BTCHeaderDecodeError {})?;
         // decode this header to rust-bitcoin's type
         let btc_header: BlockHeader = babylon_bitcoin::deserialize(new_header.header.as_ref())
             .map_err(|_| error::BTCLightclientError::BTCHeaderDecodeError {})?;
@@ -50,7 +52,8 @@ pub fn verify_headers(
         }
 
         // this header is good, verify the next one
-        last_header = new_header.clone();
+        last_header = new_header;
+        last_btc_header = btc_header;
     }
     Ok(())
 }
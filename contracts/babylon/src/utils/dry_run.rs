@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use cosmwasm_std::{Order, Record, Storage};
+
+/// DryRunStorage is a storage overlay buffering the writes to an underlying storage, which is
+/// never written to. Reads see the buffered writes, so that a state transition can be rehearsed
+/// without persisting it
+pub struct DryRunStorage<'a> {
+    base: &'a dyn Storage,
+    /// Buffered writes by key, `None` being a removal
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> DryRunStorage<'a> {
+    pub fn new(base: &'a dyn Storage) -> Self {
+        DryRunStorage {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// changes returns the number of entries written (i.e. added or updated) and removed, as
+    /// compared to the underlying storage
+    pub fn changes(&self) -> (u64, u64) {
+        let (mut written, mut removed) = (0, 0);
+        for (key, value) in &self.writes {
+            let prev = self.base.get(key);
+            match value {
+                Some(value) if prev.as_ref() != Some(value) => written += 1,
+                None if prev.is_some() => removed += 1,
+                _ => {}
+            }
+        }
+        (written, removed)
+    }
+}
+
+impl Storage for DryRunStorage<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let mut records: BTreeMap<Vec<u8>, Vec<u8>> =
+            self.base.range(start, end, Order::Ascending).collect();
+        let bounds = (
+            start.map_or(Bound::Unbounded, |s| Bound::Included(s.to_vec())),
+            end.map_or(Bound::Unbounded, |e| Bound::Excluded(e.to_vec())),
+        );
+        for (key, value) in self.writes.range(bounds) {
+            match value {
+                Some(value) => records.insert(key.clone(), value.clone()),
+                None => records.remove(key),
+            };
+        }
+        match order {
+            Order::Ascending => Box::new(records.into_iter()),
+            Order::Descending => Box::new(records.into_iter().rev()),
+        }
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn dry_run_storage_works() {
+        let mut base = MockStorage::new();
+        for key in [b"a", b"b", b"c"] {
+            base.set(key, b"1");
+        }
+
+        let mut storage = DryRunStorage::new(&base);
+        storage.set(b"b", b"2");
+        storage.set(b"c", b"1"); // unchanged
+        storage.set(b"d", b"1");
+        storage.remove(b"a");
+        storage.remove(b"e"); // not found
+
+        assert_eq!(storage.get(b"a"), None);
+        assert_eq!(storage.get(b"b"), Some(b"2".to_vec()));
+        let keys = |order| {
+            storage
+                .range(Some(b"a"), Some(b"d"), order)
+                .map(|(k, _)| k)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(Order::Ascending), vec![b"b".to_vec(), b"c".to_vec()]);
+        assert_eq!(keys(Order::Descending), vec![b"c".to_vec(), b"b".to_vec()]);
+        assert_eq!(storage.changes(), (2, 1));
+
+        // The underlying storage is untouched
+        assert_eq!(base.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(base.get(b"d"), None);
+    }
+}
@@ -6,3 +6,4 @@ mod ics23_commitment;
 pub mod babylon_epoch_chain;
 pub mod btc_light_client;
 pub mod cz_header_chain;
+pub mod dry_run;
@@ -39,20 +39,12 @@ const CREATOR: &str = "creator";
 #[track_caller]
 fn setup() -> Instance<MockApi, MockStorage, MockQuerier> {
     let mut deps = mock_instance_with_gas_limit(BABYLON_CONTRACT_WASM, 2_250_000_000_000);
-    let msg = InstantiateMsg {
-        network: babylon_bitcoin::chain_params::Network::Regtest,
-        babylon_tag: "01020304".to_string(),
-        consumer_name: None,
-        consumer_description: None,
-        btc_confirmation_depth: 10,
-        checkpoint_finalization_timeout: 99,
-        notify_cosmos_zone: false,
-        btc_staking_code_id: None,
-        btc_staking_msg: None,
-        btc_finality_code_id: None,
-        btc_finality_msg: None,
-        admin: None,
-    };
+    let msg = InstantiateMsg::new(
+        babylon_bitcoin::chain_params::Network::Regtest,
+        "01020304",
+        10,
+        99,
+    );
     let info = message_info(&Addr::unchecked(CREATOR), &[]);
     let res: Response = instantiate(&mut deps, mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
@@ -76,6 +68,19 @@ fn get_fork_msg_test_headers() -> Vec<BtcHeader> {
     match resp {
         ExecuteMsg::BtcHeaders { headers } => headers,
         ExecuteMsg::Slashing { .. } => unreachable!("unexpected slashing message"),
+        ExecuteMsg::SlashFinalityProvider { .. } => {
+            unreachable!("unexpected slashing message")
+        }
+        ExecuteMsg::ResetBaseHeader { .. } => unreachable!("unexpected base header message"),
+        ExecuteMsg::SetStakingRoute { .. } => unreachable!("unexpected staking route message"),
+        ExecuteMsg::ReprocessPacket { .. } => unreachable!("unexpected reprocess message"),
+        ExecuteMsg::ContinueMigration { .. } => unreachable!("unexpected migration message"),
+        ExecuteMsg::AllowChannelReopen { .. } => unreachable!("unexpected channel message"),
+        ExecuteMsg::UpdateChannel { .. } => unreachable!("unexpected channel message"),
+        ExecuteMsg::PruneHeaders { .. } => unreachable!("unexpected pruning message"),
+        ExecuteMsg::UpdateBtcHeaderRelayers { .. } => unreachable!("unexpected relayer message"),
+        ExecuteMsg::JailFinalityProvider { .. } => unreachable!("unexpected jailing message"),
+        ExecuteMsg::CheckChannels {} => unreachable!("unexpected channel message"),
     }
 }
 
@@ -93,20 +98,12 @@ fn wasm_size_limit_check() {
 fn instantiate_works() {
     let mut deps = mock_instance(BABYLON_CONTRACT_WASM, &[]);
 
-    let msg = InstantiateMsg {
-        network: babylon_bitcoin::chain_params::Network::Regtest,
-        babylon_tag: "01020304".to_string(),
-        consumer_name: None,
-        consumer_description: None,
-        btc_confirmation_depth: 10,
-        checkpoint_finalization_timeout: 100,
-        notify_cosmos_zone: false,
-        btc_staking_code_id: None,
-        btc_staking_msg: None,
-        btc_finality_code_id: None,
-        btc_finality_msg: None,
-        admin: None,
-    };
+    let msg = InstantiateMsg::new(
+        babylon_bitcoin::chain_params::Network::Regtest,
+        "01020304",
+        10,
+        100,
+    );
     let info = message_info(&Addr::unchecked(CREATOR), &[]);
     let res: ContractResult<Response> = instantiate(&mut deps, mock_env(), info, msg);
     let msgs = res.unwrap().messages;
@@ -1,9 +1,14 @@
-use babylon_apis::finality_api::SudoMsg;
+use babylon_apis::contract_version::ensure_upgrade;
+use babylon_apis::error::ErrorCodesResponse;
+use babylon_apis::finality_api::{FinalitySudoMsg, SigningContext, SudoMsg};
+use babylon_apis::migration::{self, Migration, MigrationProgressResponse};
+use babylon_apis::state_hash::{get_state_hash, init_section_hashes, HashedStorage};
+use babylon_apis::sudo_api::SudoMsg as BlockSudoMsg;
 use babylon_bindings::BabylonMsg;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_json_binary, Addr, CustomQuery, Deps, DepsMut, Empty, Env, MessageInfo,
+    attr, to_json_binary, Addr, CustomQuery, Deps, DepsMut, Env, Event, MessageInfo,
     QuerierWrapper, QueryRequest, QueryResponse, Reply, Response, StdResult, WasmQuery,
 };
 use cw2::set_contract_version;
@@ -11,24 +16,34 @@ use cw_utils::{maybe_addr, nonpayable};
 
 use btc_staking::msg::ActivatedHeightResponse;
 
-use crate::error::ContractError;
+use crate::error::{ContractError, ERROR_CODES};
 use crate::finality::{
     compute_active_finality_providers, handle_finality_signature, handle_public_randomness_commit,
+    handle_public_randomness_commit_batch,
 };
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RevealedRandomnessResponse};
 use crate::state::config::{Config, ADMIN, CONFIG, PARAMS};
+use crate::state::state_hash::{SECTIONS, SECTION_HASHES_REBUILD};
 use crate::{finality, queries, state};
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The storage migrations of the current contract version, in order
+const MIGRATIONS: &[Migration] = &[SECTION_HASHES_REBUILD];
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    mut deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let mut deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
     nonpayable(&info)?;
     let config = Config {
         babylon: info.sender,
@@ -40,9 +55,20 @@ pub fn instantiate(
     ADMIN.set(deps.branch(), maybe_addr(api, msg.admin.clone())?)?;
 
     let params = msg.params.unwrap_or_default();
+    SigningContext::validate_version(params.signing_context_version)
+        .map_err(ContractError::InvalidSigningContext)?;
+    if let Some(native_quorum) = &params.native_quorum {
+        native_quorum
+            .validate()
+            .map_err(ContractError::InvalidNativeQuorum)?;
+    }
     PARAMS.save(deps.storage, &params)?;
+    if params.tally_start_height > 0 {
+        state::finality::NEXT_HEIGHT.save(deps.storage, &params.tally_start_height)?;
+    }
     // initialize storage, so no issue when reading for the first time
 
+    init_section_hashes(deps.storage)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new().add_attribute("action", "instantiate"))
 }
@@ -53,7 +79,7 @@ pub fn reply(_deps: DepsMut, _env: Env, _reply: Reply) -> StdResult<Response> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
     match msg {
         QueryMsg::Config {} => Ok(to_json_binary(&queries::config(deps)?)?),
         QueryMsg::Params {} => Ok(to_json_binary(&queries::params(deps)?)?),
@@ -81,6 +107,13 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
         QueryMsg::LastPubRandCommit { btc_pk_hex } => Ok(to_json_binary(
             &state::public_randomness::get_last_pub_rand_commit(deps.storage, &btc_pk_hex)?,
         )?),
+        QueryMsg::PubRandCommitTimestamp {
+            btc_pk_hex,
+            start_height,
+        } => Ok(to_json_binary(
+            &state::public_randomness::PUB_RAND_COMMIT_TIMESTAMPS
+                .may_load(deps.storage, (&btc_pk_hex, start_height))?,
+        )?),
         QueryMsg::Block { height } => Ok(to_json_binary(&queries::block(deps, height)?)?),
         QueryMsg::Blocks {
             start_after,
@@ -97,13 +130,73 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
         QueryMsg::Evidence { btc_pk_hex, height } => Ok(to_json_binary(&queries::evidence(
             deps, btc_pk_hex, height,
         )?)?),
+        QueryMsg::VoteProof { height, btc_pk_hex } => Ok(to_json_binary(&queries::vote_proof(
+            deps, &env, height, btc_pk_hex,
+        )?)?),
+        QueryMsg::PubRandReuseEvidence { btc_pk_hex, height } => Ok(to_json_binary(
+            &queries::pub_rand_reuse_evidence(deps, btc_pk_hex, height)?,
+        )?),
+        QueryMsg::RevealedRandomness { btc_pk_hex, height } => {
+            Ok(to_json_binary(&RevealedRandomnessResponse {
+                pub_rand: state::public_randomness::get_pub_rand_value(
+                    deps.storage,
+                    &btc_pk_hex,
+                    height,
+                )?,
+            })?)
+        }
+        QueryMsg::NativeTally { height } => Ok(to_json_binary(
+            &state::finality::NATIVE_TALLIES.may_load(deps.storage, height)?,
+        )?),
+        QueryMsg::SigFailures { btc_pk_hex } => {
+            Ok(to_json_binary(&queries::sig_failures(deps, btc_pk_hex)?)?)
+        }
+        QueryMsg::VoteInvalidations { start_after, limit } => Ok(to_json_binary(
+            &queries::vote_invalidations(deps, start_after, limit)?,
+        )?),
+        QueryMsg::VotingContext { btc_pk_hex, height } => Ok(to_json_binary(
+            &queries::voting_context(deps, btc_pk_hex, height)?,
+        )?),
+        QueryMsg::RandomnessCoverage { btc_pk_hex } => Ok(to_json_binary(
+            &queries::randomness_coverage(deps, &env, &btc_pk_hex)?,
+        )?),
+        QueryMsg::FinalityLatency { last_n } => {
+            Ok(to_json_binary(&queries::finality_latency(deps, last_n)?)?)
+        }
+        QueryMsg::RewardAccrual { btc_pk_hex } => Ok(to_json_binary(&queries::reward_accrual(
+            deps,
+            &btc_pk_hex,
+        )?)?),
+        QueryMsg::TallyStatus {} => Ok(to_json_binary(&queries::tally_status(deps)?)?),
+        QueryMsg::ErrorCodes {} => Ok(to_json_binary(&ErrorCodesResponse::from(ERROR_CODES))?),
+        QueryMsg::StateHash {} => Ok(to_json_binary(&get_state_hash(deps.storage, SECTIONS)?)?),
+        QueryMsg::MigrationProgress {} => Ok(to_json_binary(&MigrationProgressResponse {
+            migrations: migration::get_migration_progress(deps.storage)?,
+        })?),
     }
 }
 
-/// This is a no-op just to test how this integrates with wasmd
+/// migrate migrates the contract to this version of its code, refusing downgrades, and schedules
+/// the rebuild of the section hashes, run in chunks through `ContinueMigration`.
+/// Params added since are filled in with their defaults when loaded
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: Empty) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(
+    deps: DepsMut,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
+    let from_version = ensure_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let scheduled = migration::schedule_migrations(deps.storage, MIGRATIONS)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.unwrap_or_default())
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("scheduled_migrations", scheduled.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -113,6 +206,11 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
     let api = deps.api;
     match msg {
         ExecuteMsg::UpdateAdmin { admin } => ADMIN
@@ -126,6 +224,7 @@ pub fn execute(
             proof,
             block_hash,
             signature,
+            payload_version,
         } => handle_finality_signature(
             deps,
             env,
@@ -135,6 +234,7 @@ pub fn execute(
             &proof,
             &block_hash,
             &signature,
+            payload_version.unwrap_or_default(),
         ),
         ExecuteMsg::CommitPublicRandomness {
             fp_pubkey_hex,
@@ -144,27 +244,71 @@ pub fn execute(
             signature,
         } => handle_public_randomness_commit(
             deps,
+            &env,
             &fp_pubkey_hex,
             start_height,
             num_pub_rand,
             &commitment,
             &signature,
         ),
+        ExecuteMsg::CommitPublicRandomnessBatch { commits } => {
+            handle_public_randomness_commit_batch(deps, &env, &commits)
+        }
+        ExecuteMsg::ContinueMigration { limit } => {
+            let progress = migration::continue_migration(deps.storage, MIGRATIONS, limit as usize)?;
+            let mut event = Event::new("migration_progress");
+            event = match progress {
+                Some(progress) => event
+                    .add_attribute("name", progress.name)
+                    .add_attribute("section", progress.section)
+                    .add_attribute("migrated", progress.migrated.to_string())
+                    .add_attribute("complete", progress.complete.to_string()),
+                None => event.add_attribute("pending", "false"),
+            };
+            Ok(Response::new().add_event(event))
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn sudo(
-    mut deps: DepsMut,
-    env: Env,
-    msg: SudoMsg,
-) -> Result<Response<BabylonMsg>, ContractError> {
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let mut deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
     match msg {
-        SudoMsg::BeginBlock { .. } => handle_begin_block(&mut deps, env),
-        SudoMsg::EndBlock {
+        SudoMsg::Block(BlockSudoMsg::BeginBlock { .. }) => handle_begin_block(&mut deps, env),
+        SudoMsg::Block(BlockSudoMsg::EndBlock {
             hash_hex,
             app_hash_hex,
-        } => handle_end_block(&mut deps, env, &hash_hex, &app_hash_hex),
+        }) => handle_end_block(&mut deps, env, &hash_hex, &app_hash_hex),
+        SudoMsg::Finality(msg) => handle_finality_sudo(deps, env, msg),
+    }
+}
+
+fn handle_finality_sudo(
+    deps: DepsMut,
+    env: Env,
+    msg: FinalitySudoMsg,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    match msg {
+        FinalitySudoMsg::NativeVotes { height, validators } => {
+            let ev = finality::handle_native_votes(deps.storage, height, &validators)?;
+            Ok(Response::new().add_event(ev))
+        }
+        FinalitySudoMsg::InvalidateVotes { height, fp_pks } => {
+            let ev = finality::handle_invalidate_votes(deps.storage, &env, height, &fp_pks)?;
+            Ok(Response::new().add_event(ev))
+        }
+        FinalitySudoMsg::SetTallyStartHeight { height } => {
+            let ev = finality::handle_set_tally_start_height(deps.storage, height)?;
+            Ok(Response::new().add_event(ev))
+        }
+        FinalitySudoMsg::SetMinVotePayloadVersion { version } => {
+            let ev = finality::handle_set_min_vote_payload_version(deps.storage, version)?;
+            Ok(Response::new().add_event(ev))
+        }
     }
 }
 
@@ -209,11 +353,11 @@ fn handle_end_block(
     let activated_height = get_activated_height(&cfg.staking, &deps.querier)?;
     if activated_height > 0 {
         // Index the current block
-        let ev = finality::index_block(deps, env.block.height, &hex::decode(app_hash_hex)?)?;
+        let ev = finality::index_block(deps, &env.block, &hex::decode(app_hash_hex)?)?;
         res = res.add_event(ev);
         // Tally all non-finalised blocks
-        let events = finality::tally_blocks(deps, activated_height, env.block.height)?;
-        res = res.add_events(events);
+        let (events, msgs) = finality::tally_blocks(deps, activated_height, &env.block)?;
+        res = res.add_events(events).add_messages(msgs);
     }
     Ok(res)
 }
@@ -243,9 +387,12 @@ pub(crate) fn encode_smart_query<Q: CustomQuery>(
 pub(crate) mod tests {
     use super::*;
 
+    use babylon_apis::migration::MIGRATION_PROGRESS;
+    use babylon_apis::state_hash::{StateHashResponse, STATE_HASH_REBUILD};
     use cosmwasm_std::{
         from_json,
         testing::{message_info, mock_dependencies, mock_env},
+        OwnedDeps,
     };
     use cw_controllers::AdminResponse;
 
@@ -357,4 +504,178 @@ pub(crate) mod tests {
         // Use assert_admin to verify that the admin was updated correctly
         ADMIN.assert_admin(deps.as_ref(), &new_admin).unwrap();
     }
+
+    #[test]
+    fn tally_start_height_works() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            params: Some(crate::state::config::Params {
+                tally_start_height: 100,
+                ..Default::default()
+            }),
+            admin: None,
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Tallying starts from the configured height
+        let next_height = state::finality::NEXT_HEIGHT.load(&deps.storage).unwrap();
+        assert_eq!(next_height, 100);
+
+        // The tally start height cannot go backwards
+        let err = sudo(
+            deps.as_mut(),
+            mock_env(),
+            FinalitySudoMsg::SetTallyStartHeight { height: 99 }.into(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidTallyStartHeight(99, 100));
+
+        // It can be moved forward
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            FinalitySudoMsg::SetTallyStartHeight { height: 150 }.into(),
+        )
+        .unwrap();
+        let next_height = state::finality::NEXT_HEIGHT.load(&deps.storage).unwrap();
+        assert_eq!(next_height, 150);
+        let params = PARAMS.load(&deps.storage).unwrap();
+        assert_eq!(params.tally_start_height, 150);
+    }
+
+    #[test]
+    fn state_hash_works() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            params: None,
+            admin: None,
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let state_hash = |deps: &OwnedDeps<_, _, _>| -> StateHashResponse {
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::StateHash {}).unwrap()).unwrap()
+        };
+        let before = state_hash(&deps);
+        let sections: Vec<_> = before.sections.iter().map(|s| s.section.as_str()).collect();
+        assert_eq!(
+            sections,
+            [
+                "config",
+                "finality",
+                "public_randomness",
+                "rewards",
+                "other"
+            ]
+        );
+
+        // Only the sections of the updated state change
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            FinalitySudoMsg::SetTallyStartHeight { height: 10 }.into(),
+        )
+        .unwrap();
+        let after = state_hash(&deps);
+        assert_ne!(after.hash, before.hash);
+        for i in 0..before.sections.len() {
+            assert_eq!(after.sections[i] == before.sections[i], i > 1);
+        }
+
+        // Writes around the section hashes, as by the versions not keeping them, leave them stale
+        state::finality::NEXT_HEIGHT
+            .save(&mut deps.storage, &11)
+            .unwrap();
+        let mut storage = HashedStorage::new(&mut deps.storage, SECTIONS);
+        state::finality::NEXT_HEIGHT
+            .save(&mut storage, &10)
+            .unwrap();
+        assert_ne!(state_hash(&deps).sections[1], after.sections[1]);
+
+        // The section hashes are rebuilt in chunks upon migration from such versions
+        MIGRATION_PROGRESS.remove(&mut deps.storage, STATE_HASH_REBUILD);
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("scheduled_migrations", STATE_HASH_REBUILD)));
+        query(deps.as_ref(), mock_env(), QueryMsg::StateHash {}).unwrap_err();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let continue_msg = ExecuteMsg::ContinueMigration { limit: 2 };
+        while execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            continue_msg.clone(),
+        )
+        .unwrap()
+        .events[0]
+            .attributes
+            .contains(&attr("complete", "false"))
+        {}
+        let rebuilt = state_hash(&deps);
+        assert_eq!(rebuilt.sections[..4], after.sections[..4]);
+    }
+
+    #[test]
+    fn min_vote_payload_version_works() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            params: None,
+            admin: None,
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        sudo(
+            deps.as_mut(),
+            mock_env(),
+            FinalitySudoMsg::SetMinVotePayloadVersion { version: 2 }.into(),
+        )
+        .unwrap();
+        let params = PARAMS.load(&deps.storage).unwrap();
+        assert_eq!(params.min_vote_payload_version, 2);
+
+        let submit = |payload_version| ExecuteMsg::SubmitFinalitySignature {
+            fp_pubkey_hex: "02".repeat(32),
+            height: 1,
+            pub_rand: vec![1u8; 32].into(),
+            proof: babylon_merkle::Proof {
+                total: 1,
+                index: 0,
+                leaf_hash: vec![2u8; 32].into(),
+                aunts: vec![],
+            },
+            block_hash: vec![3u8; 32].into(),
+            signature: vec![4u8; 32].into(),
+            payload_version,
+        };
+
+        // Daemons predating the handshake, or on an outdated version, are told what to upgrade to
+        for (payload_version, version) in [(None, 0), (Some(1), 1)] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                submit(payload_version),
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::OutdatedVotePayloadVersion {
+                    version,
+                    min_version: 2
+                }
+            );
+        }
+
+        // Up-to-date submissions go through to the regular processing
+        let err = execute(deps.as_mut(), mock_env(), info, submit(Some(2))).unwrap_err();
+        assert!(!matches!(
+            err,
+            ContractError::OutdatedVotePayloadVersion { .. }
+        ));
+    }
 }
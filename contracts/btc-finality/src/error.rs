@@ -9,7 +9,7 @@ use cosmwasm_std::StdError;
 use cw_controllers::AdminError;
 use cw_utils::PaymentError;
 
-use babylon_apis::error::StakingApiError;
+use babylon_apis::error::{ErrorCategory, ErrorCode, StakingApiError};
 use babylon_merkle::error::MerkleError;
 
 #[derive(Error, Debug, PartialEq)]
@@ -40,6 +40,10 @@ pub enum ContractError {
     SecP256K1Error(String), // TODO: inherit errors from k256
     #[error("Unauthorized")]
     Unauthorized,
+    #[error("Invalid signing context: {0}")]
+    InvalidSigningContext(String),
+    #[error("Invalid native quorum: {0}")]
+    InvalidNativeQuorum(String),
     #[error("Failed to verify the finality provider registration request: {0}")]
     FinalityProviderVerificationError(String),
     #[error("Finality provider already exists: {0}")]
@@ -86,12 +90,322 @@ pub enum ContractError {
     FinalisedBlockWithoutFinalityProviderSet(u64),
     #[error("Block {0} is not found: {1}")]
     BlockNotFound(u64, String),
+    #[error("The vote of finality provider {0} at height {1} has been invalidated")]
+    VoteInvalidated(String, u64),
+    #[error("The public randomness commitment of finality provider {0} at height {1} is not BTC-finalised yet")]
+    PubRandCommitNotFinalized(String, u64),
     #[error("The finality provider {0} has already been slashed")]
     FinalityProviderAlreadySlashed(String),
     #[error("Failed to slash finality provider: {0}")]
     FailedToSlashFinalityProvider(String),
+    #[error("Tally start height {0} is below the next height to be tallied {1}")]
+    InvalidTallyStartHeight(u64, u64),
+    #[error("Vote payload version {version} is no longer supported: upgrade the finality provider to vote payload version {min_version} or later")]
+    OutdatedVotePayloadVersion { version: u32, min_version: u32 },
     #[error("Failed to extract secret key: {0}")]
     SecretKeyExtractionError(String),
     #[error("Hash length error: {0}")]
     WrongHashLength(String),
 }
+
+impl ContractError {
+    /// code returns the stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            ContractError::Admin(..) => 201,
+            ContractError::Std(..) => 101,
+            ContractError::Payment(..) => 102,
+            ContractError::BTCStaking(..) => 801,
+            ContractError::HexArrayError(..) => 103,
+            ContractError::SliceError(..) => 104,
+            ContractError::StakingError(..) => 802,
+            ContractError::MerkleError(..) => 1001,
+            ContractError::ProtoError(..) => 105,
+            ContractError::HexError(..) => 106,
+            ContractError::EotsError(..) => 1002,
+            ContractError::SecP256K1Error(..) => 1003,
+            ContractError::Unauthorized => 202,
+            ContractError::InvalidSigningContext(..) => 401,
+            ContractError::InvalidNativeQuorum(..) => 402,
+            ContractError::FinalityProviderVerificationError(..) => 803,
+            ContractError::FinalityProviderAlreadyExists(..) => 804,
+            ContractError::FinalityProviderNotRegistered => 805,
+            ContractError::FinalityProviderNotFound(..) => 806,
+            ContractError::DelegationAlreadyExists(..) => 807,
+            ContractError::InvalidBtcTx(..) => 808,
+            ContractError::EmptySignature => 1004,
+            ContractError::ErrInvalidLockType => 809,
+            ContractError::ErrInvalidLockTime(..) => 810,
+            ContractError::NoVotingPower(..) => 901,
+            ContractError::HeightTooHigh => 902,
+            ContractError::DuplicateFinalityVote(..) => 903,
+            ContractError::TooFewPubRand(..) => 904,
+            ContractError::InvalidPubRandHeight(..) => 905,
+            ContractError::InvalidPubRandSignature => 1005,
+            ContractError::MissingPubRandCommit(..) => 906,
+            ContractError::InvalidFinalitySigHeight(..) => 907,
+            ContractError::InvalidFinalitySigAmount(..) => 908,
+            ContractError::InvalidSignature(..) => 1006,
+            ContractError::FailedSignatureVerification(..) => 1007,
+            ContractError::FinalisedBlockWithFinalityProviderSet(..) => 909,
+            ContractError::FinalisedBlockWithoutFinalityProviderSet(..) => 910,
+            ContractError::BlockNotFound(..) => 911,
+            ContractError::VoteInvalidated(..) => 912,
+            ContractError::PubRandCommitNotFinalized(..) => 913,
+            ContractError::InvalidTallyStartHeight(..) => 914,
+            ContractError::OutdatedVotePayloadVersion { .. } => 915,
+            ContractError::FinalityProviderAlreadySlashed(..) => 811,
+            ContractError::FailedToSlashFinalityProvider(..) => 812,
+            ContractError::SecretKeyExtractionError(..) => 1008,
+            ContractError::WrongHashLength(..) => 107,
+        }
+    }
+}
+
+/// ERROR_CODES is the table of error codes of the contract
+pub const ERROR_CODES: &[ErrorCode] = &[
+        ErrorCode {
+            code: 101,
+            category: ErrorCategory::Std,
+            description: "Generic error",
+        },
+        ErrorCode {
+            code: 102,
+            category: ErrorCategory::Std,
+            description: "Invalid funds",
+        },
+        ErrorCode {
+            code: 103,
+            category: ErrorCategory::Std,
+            description: "Error converting from hex to array",
+        },
+        ErrorCode {
+            code: 104,
+            category: ErrorCategory::Std,
+            description: "Invalid slice length",
+        },
+        ErrorCode {
+            code: 105,
+            category: ErrorCategory::Std,
+            description: "The bytes cannot be decoded",
+        },
+        ErrorCode {
+            code: 106,
+            category: ErrorCategory::Std,
+            description: "The hex cannot be decoded",
+        },
+        ErrorCode {
+            code: 107,
+            category: ErrorCategory::Std,
+            description: "Hash length error",
+        },
+        ErrorCode {
+            code: 201,
+            category: ErrorCategory::Auth,
+            description: "Admin error",
+        },
+        ErrorCode {
+            code: 202,
+            category: ErrorCategory::Auth,
+            description: "Unauthorized",
+        },
+        ErrorCode {
+            code: 401,
+            category: ErrorCategory::Config,
+            description: "Invalid signing context",
+        },
+        ErrorCode {
+            code: 402,
+            category: ErrorCategory::Config,
+            description: "Invalid native quorum",
+        },
+        ErrorCode {
+            code: 801,
+            category: ErrorCategory::Staking,
+            description: "BTC staking error",
+        },
+        ErrorCode {
+            code: 802,
+            category: ErrorCategory::Staking,
+            description: "Staking API error",
+        },
+        ErrorCode {
+            code: 803,
+            category: ErrorCategory::Staking,
+            description: "Failed to verify the finality provider registration request",
+        },
+        ErrorCode {
+            code: 804,
+            category: ErrorCategory::Staking,
+            description: "Finality provider already exists",
+        },
+        ErrorCode {
+            code: 805,
+            category: ErrorCategory::Staking,
+            description: "No finality providers are registered in this Consumer",
+        },
+        ErrorCode {
+            code: 806,
+            category: ErrorCategory::Staking,
+            description: "Finality provider not found",
+        },
+        ErrorCode {
+            code: 807,
+            category: ErrorCategory::Staking,
+            description: "Staking tx hash already exists",
+        },
+        ErrorCode {
+            code: 808,
+            category: ErrorCategory::Staking,
+            description: "Invalid Btc tx",
+        },
+        ErrorCode {
+            code: 809,
+            category: ErrorCategory::Staking,
+            description: "Invalid lock type: seconds",
+        },
+        ErrorCode {
+            code: 810,
+            category: ErrorCategory::Staking,
+            description: "Invalid lock time blocks",
+        },
+        ErrorCode {
+            code: 811,
+            category: ErrorCategory::Staking,
+            description: "The finality provider has already been slashed",
+        },
+        ErrorCode {
+            code: 812,
+            category: ErrorCategory::Staking,
+            description: "Failed to slash finality provider",
+        },
+        ErrorCode {
+            code: 901,
+            category: ErrorCategory::Finality,
+            description: "The finality provider does not have voting power at the height",
+        },
+        ErrorCode {
+            code: 902,
+            category: ErrorCategory::Finality,
+            description: "The chain has not reached the given height yet",
+        },
+        ErrorCode {
+            code: 903,
+            category: ErrorCategory::Finality,
+            description: "The finality provider signed two different blocks at the same height",
+        },
+        ErrorCode {
+            code: 904,
+            category: ErrorCategory::Finality,
+            description: "The request contains too few public randomness",
+        },
+        ErrorCode {
+            code: 905,
+            category: ErrorCategory::Finality,
+            description: "The start height has overlap with the height of the highest public randomness committed",
+        },
+        ErrorCode {
+            code: 906,
+            category: ErrorCategory::Finality,
+            description: "Public randomness not found for finality provider at the height",
+        },
+        ErrorCode {
+            code: 907,
+            category: ErrorCategory::Finality,
+            description: "The inclusion proof for height does not correspond to the given height",
+        },
+        ErrorCode {
+            code: 908,
+            category: ErrorCategory::Finality,
+            description: "The total amount of public randomnesses in the proof does not match the amount of public committed randomness",
+        },
+        ErrorCode {
+            code: 909,
+            category: ErrorCategory::Finality,
+            description: "Block is finalized, but last finalized height does not reach here",
+        },
+        ErrorCode {
+            code: 910,
+            category: ErrorCategory::Finality,
+            description: "Block is finalized, but does not have a finality provider set",
+        },
+        ErrorCode {
+            code: 911,
+            category: ErrorCategory::Finality,
+            description: "Block is not found",
+        },
+        ErrorCode {
+            code: 912,
+            category: ErrorCategory::Finality,
+            description: "The vote of finality provider at the height has been invalidated",
+        },
+        ErrorCode {
+            code: 913,
+            category: ErrorCategory::Finality,
+            description: "The public randomness commitment of finality provider is not BTC-finalised yet",
+        },
+        ErrorCode {
+            code: 914,
+            category: ErrorCategory::Finality,
+            description: "Tally start height is below the next height to be tallied",
+        },
+        ErrorCode {
+            code: 915,
+            category: ErrorCategory::Finality,
+            description: "Vote payload version is no longer supported",
+        },
+        ErrorCode {
+            code: 1001,
+            category: ErrorCategory::Crypto,
+            description: "Merkle proof error",
+        },
+        ErrorCode {
+            code: 1002,
+            category: ErrorCategory::Crypto,
+            description: "EOTS error",
+        },
+        ErrorCode {
+            code: 1003,
+            category: ErrorCategory::Crypto,
+            description: "Invalid secp256k1 key or signature",
+        },
+        ErrorCode {
+            code: 1004,
+            category: ErrorCategory::Crypto,
+            description: "Empty signature from the delegator",
+        },
+        ErrorCode {
+            code: 1005,
+            category: ErrorCategory::Crypto,
+            description: "Invalid signature over the public randomness list",
+        },
+        ErrorCode {
+            code: 1006,
+            category: ErrorCategory::Crypto,
+            description: "Invalid finality signature",
+        },
+        ErrorCode {
+            code: 1007,
+            category: ErrorCategory::Crypto,
+            description: "Failed to verify signature",
+        },
+        ErrorCode {
+            code: 1008,
+            category: ErrorCategory::Crypto,
+            description: "Failed to extract secret key",
+        },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use babylon_apis::error::{check_error_codes, validate_error_codes};
+
+    #[test]
+    fn error_codes_are_valid() {
+        validate_error_codes(ERROR_CODES).unwrap();
+
+        // Every variant has a code in the table, described after its message
+        check_error_codes(include_str!("error.rs"), &["ContractError"], ERROR_CODES).unwrap();
+    }
+}
@@ -6,30 +6,113 @@ use std::collections::HashSet;
 
 use crate::contract::encode_smart_query;
 use crate::error::ContractError;
-use crate::state::config::{CONFIG, PARAMS};
-use crate::state::finality::{BLOCKS, EVIDENCES, FP_SET, NEXT_HEIGHT, SIGNATURES, TOTAL_POWER};
+use crate::msg::FinalitySignatureResult;
+use crate::state::config::{Params, ZeroPowerVotes, CONFIG, PARAMS};
+use crate::state::finality::{
+    FinalityLatency, NativeTally, PubRandReuseEvidence, SigFailureReason, VoteInvalidation, BLOCKS,
+    BLOCK_TIMES, EVIDENCES, FINALITY_LATENCIES, FP_SET, INVALIDATED_VOTES, MISSED_BLOCKS,
+    NATIVE_TALLIES, NEXT_HEIGHT, PUB_RAND_REUSE_EVIDENCES, SIGNATURES, SIG_FAILURES,
+    SIG_FAILURE_TOTALS, TOTAL_POWER, VOTE_INVALIDATIONS,
+};
 use crate::state::public_randomness::{
-    get_last_pub_rand_commit, get_pub_rand_commit_for_height, PUB_RAND_COMMITS, PUB_RAND_VALUES,
+    get_last_pub_rand_commit, get_pub_rand_commit_for_height, save_pub_rand_value,
+    PubRandCommitTimestamp, PUB_RAND_COMMITS, PUB_RAND_COMMIT_TIMESTAMPS,
 };
+use crate::state::rewards::accrue;
+use babylon_apis::btc_pk::{canonical_btc_pk_hex, BtcPkHex};
 use babylon_apis::btc_staking_api::FinalityProvider;
-use babylon_apis::finality_api::{Evidence, IndexedBlock, PubRandCommit};
+use babylon_apis::error::format_error;
+use babylon_apis::finality_api::{
+    Evidence, IndexedBlock, NativeValidatorVote, PubRandCommit, PublicRandomnessCommit,
+    SigningContext,
+};
+use babylon_apis::math::{add_sat, exceeds_fraction, exceeds_share};
+use babylon_apis::pagination::MAX_LIMIT;
 use babylon_bindings::BabylonMsg;
+use babylon_contract::msg::cz_header::CzHeaderResponse;
 use babylon_merkle::Proof;
 use btc_staking::msg::{FinalityProviderInfo, FinalityProvidersByPowerResponse};
-use cosmwasm_std::Order::Ascending;
+use cosmwasm_std::Order::{Ascending, Descending};
 use cosmwasm_std::{
-    to_json_binary, Addr, DepsMut, Env, Event, QuerierWrapper, Response, StdResult, Storage,
-    WasmMsg,
+    to_json_binary, Addr, BlockInfo, DepsMut, Env, Event, QuerierWrapper, Response, StdError,
+    StdResult, Storage, WasmMsg,
 };
 
 pub fn handle_public_randomness_commit(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: &Env,
     fp_pubkey_hex: &str,
     start_height: u64,
     num_pub_rand: u64,
     commitment: &[u8],
     signature: &[u8],
 ) -> Result<Response<BabylonMsg>, ContractError> {
+    commit_public_randomness(
+        &mut deps,
+        env,
+        fp_pubkey_hex,
+        start_height,
+        num_pub_rand,
+        commitment,
+        signature,
+    )?;
+    // TODO: Add events
+    Ok(Response::new())
+}
+
+/// `handle_public_randomness_commit_batch` commits the given public randomness commitments
+/// independently. The commitments that fail are reported in the event, along with their error,
+/// while the others are stored
+pub fn handle_public_randomness_commit_batch(
+    mut deps: DepsMut,
+    env: &Env,
+    commits: &[PublicRandomnessCommit],
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut ev = Event::new("commit_public_randomness_batch").add_attribute("module", "finality");
+    let mut committed = 0;
+    for (i, commit) in commits.iter().enumerate() {
+        let res = commit_public_randomness(
+            &mut deps,
+            env,
+            &commit.fp_pubkey_hex,
+            commit.start_height,
+            commit.num_pub_rand,
+            &commit.commitment,
+            &commit.signature,
+        );
+        match res {
+            Ok(()) => committed += 1,
+            Err(err) => {
+                ev = ev.add_attribute(
+                    format!("failed.{i}"),
+                    format!(
+                        "{}/{}: {}",
+                        commit.fp_pubkey_hex,
+                        commit.start_height,
+                        format_error(err.code(), err)
+                    ),
+                )
+            }
+        }
+    }
+    ev = ev
+        .add_attribute("committed", committed.to_string())
+        .add_attribute("failed", (commits.len() - committed).to_string());
+    Ok(Response::new().add_event(ev))
+}
+
+/// `commit_public_randomness` verifies and stores a public randomness commitment.
+/// The commitment is fully verified before anything is stored, so that nothing is stored upon
+/// error
+fn commit_public_randomness(
+    deps: &mut DepsMut,
+    env: &Env,
+    fp_pubkey_hex: &str,
+    start_height: u64,
+    num_pub_rand: u64,
+    commitment: &[u8],
+    signature: &[u8],
+) -> Result<(), ContractError> {
     // Ensure the request contains enough amounts of public randomness
     let min_pub_rand = PARAMS.load(deps.storage)?.min_pub_rand;
     if num_pub_rand < min_pub_rand {
@@ -37,6 +120,9 @@ pub fn handle_public_randomness_commit(
     }
     // TODO: ensure log_2(num_pub_rand) is an integer?
 
+    // Accept the FP key in compressed encoding as well, under its canonical (x-only) encoding
+    let fp_pubkey_hex = &canonical_btc_pk_hex(fp_pubkey_hex)?;
+
     // Ensure the finality provider is registered
     // TODO: Use a raw query for performance and cost
     let _fp: FinalityProvider = deps
@@ -44,12 +130,14 @@ pub fn handle_public_randomness_commit(
         .query_wasm_smart(
             CONFIG.load(deps.storage)?.staking,
             &btc_staking::msg::QueryMsg::FinalityProvider {
-                btc_pk_hex: fp_pubkey_hex.to_string(),
+                btc_pk_hex: fp_pubkey_hex.parse()?,
             },
         )
         .map_err(|_| ContractError::FinalityProviderNotFound(fp_pubkey_hex.to_string()))?;
     // Verify signature over the list
+    let ctx = signing_context(deps.storage, env)?;
     verify_commitment_signature(
+        &ctx,
         fp_pubkey_hex,
         start_height,
         num_pub_rand,
@@ -85,12 +173,33 @@ pub fn handle_public_randomness_commit(
         (fp_pubkey_hex, pr_commit.start_height),
         &pr_commit,
     )?;
+    PUB_RAND_COMMIT_TIMESTAMPS.save(
+        deps.storage,
+        (fp_pubkey_hex, pr_commit.start_height),
+        &PubRandCommitTimestamp {
+            committed_at: env.block.height,
+            babylon_epoch: None,
+        },
+    )?;
+    Ok(())
+}
 
-    // TODO: Add events
-    Ok(Response::new())
+/// `signing_context` returns the signing context of finality provider signatures submitted to
+/// this contract. The contract runs on the Consumer chain, so the consumer id is the chain id
+pub(crate) fn signing_context(
+    storage: &dyn Storage,
+    env: &Env,
+) -> Result<SigningContext, ContractError> {
+    Ok(SigningContext {
+        version: PARAMS.load(storage)?.signing_context_version,
+        chain_id: env.block.chain_id.clone(),
+        contract_address: env.contract.address.to_string(),
+        consumer_id: env.block.chain_id.clone(),
+    })
 }
 
 fn verify_commitment_signature(
+    ctx: &SigningContext,
     fp_btc_pk_hex: &str,
     start_height: u64,
     num_pub_rand: u64,
@@ -110,10 +219,7 @@ fn verify_commitment_signature(
         Signature::try_from(signature).map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
 
     // get signed message
-    let mut msg: Vec<u8> = vec![];
-    msg.extend_from_slice(&start_height.to_be_bytes());
-    msg.extend_from_slice(&num_pub_rand.to_be_bytes());
-    msg.extend_from_slice(commitment);
+    let msg = ctx.commit_pub_rand_msg(start_height, num_pub_rand, commitment);
 
     // Verify the signature
     btc_pk
@@ -121,6 +227,11 @@ fn verify_commitment_signature(
         .map_err(|e| ContractError::SecP256K1Error(e.to_string()))
 }
 
+/// `handle_finality_signature` handles a finality signature submission.
+/// If `Params::record_sig_failures` is set, signatures failing verification are counted by reason
+/// and reported through an event and the response data, instead of being rejected with an error.
+/// Submissions declaring a vote payload version below `Params::min_vote_payload_version` are
+/// rejected upfront
 #[allow(clippy::too_many_arguments)]
 pub fn handle_finality_signature(
     mut deps: DepsMut,
@@ -131,15 +242,114 @@ pub fn handle_finality_signature(
     proof: &Proof,
     block_app_hash: &[u8],
     signature: &[u8],
+    payload_version: u32,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let min_version = PARAMS.load(deps.storage)?.min_vote_payload_version;
+    if payload_version < min_version {
+        return Err(ContractError::OutdatedVotePayloadVersion {
+            version: payload_version,
+            min_version,
+        });
+    }
+    // Accept the FP key in compressed encoding as well, under its canonical (x-only) encoding
+    let fp_btc_pk_hex = &canonical_btc_pk_hex(fp_btc_pk_hex)?;
+    let res = process_finality_signature(
+        deps.branch(),
+        env,
+        fp_btc_pk_hex,
+        height,
+        pub_rand,
+        proof,
+        block_app_hash,
+        signature,
+    );
+    let err = match res {
+        Ok(res) => return Ok(res),
+        Err(err) => err,
+    };
+    let reason = match sig_failure_reason(&err) {
+        Some(reason) if PARAMS.load(deps.storage)?.record_sig_failures => reason,
+        _ => return Err(err),
+    };
+
+    record_sig_failure(deps.storage, reason, fp_btc_pk_hex)?;
+    let ev = Event::new("finality_sig_rejected")
+        .add_attribute("fp_btc_pk_hex", fp_btc_pk_hex)
+        .add_attribute("height", height.to_string())
+        .add_attribute("reason", reason.as_str())
+        .add_attribute("error", err.to_string());
+    let result = FinalitySignatureResult {
+        fp_btc_pk_hex: fp_btc_pk_hex.to_string(),
+        height,
+        duplicate: false,
+        fork: false,
+        slashed: false,
+        finalized: BLOCKS
+            .may_load(deps.storage, height)?
+            .is_some_and(|b| b.finalized),
+        rejected: Some(reason),
+        zero_power: false,
+    };
+    Ok(Response::new()
+        .add_event(ev)
+        .set_data(to_json_binary(&result)?))
+}
+
+/// `sig_failure_reason` classifies a finality signature verification error for the failure
+/// metrics. Other errors (e.g. malformed requests, slashed FPs) are not counted
+fn sig_failure_reason(err: &ContractError) -> Option<SigFailureReason> {
+    match err {
+        ContractError::InvalidFinalitySigAmount(..) | ContractError::MerkleError(..) => {
+            Some(SigFailureReason::BadProof)
+        }
+        ContractError::FailedSignatureVerification(..) | ContractError::EotsError(..) => {
+            Some(SigFailureReason::RandomnessMismatch)
+        }
+        ContractError::FinalityProviderNotFound(..) | ContractError::NoVotingPower(..) => {
+            Some(SigFailureReason::UnknownFp)
+        }
+        ContractError::HeightTooHigh
+        | ContractError::MissingPubRandCommit(..)
+        | ContractError::InvalidFinalitySigHeight(..)
+        | ContractError::BlockNotFound(..) => Some(SigFailureReason::HeightOutOfRange),
+        _ => None,
+    }
+}
+
+fn record_sig_failure(
+    storage: &mut dyn Storage,
+    reason: SigFailureReason,
+    fp_btc_pk_hex: &str,
+) -> StdResult<()> {
+    let increment = |count: Option<u64>| -> StdResult<u64> { Ok(count.unwrap_or_default() + 1) };
+    SIG_FAILURES.update(storage, (reason.as_str(), fp_btc_pk_hex), increment)?;
+    SIG_FAILURE_TOTALS.update(storage, reason.as_str(), increment)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_finality_signature(
+    mut deps: DepsMut,
+    env: Env,
+    fp_btc_pk_hex: &str,
+    height: u64,
+    pub_rand: &[u8],
+    proof: &Proof,
+    block_app_hash: &[u8],
+    signature: &[u8],
 ) -> Result<Response<BabylonMsg>, ContractError> {
     // Ensure the finality provider exists
+    let btc_pk: BtcPkHex = fp_btc_pk_hex.parse()?;
     let staking_addr = CONFIG.load(deps.storage)?.staking;
-    let fp: FinalityProvider = deps.querier.query_wasm_smart(
-        staking_addr.clone(),
-        &btc_staking::msg::QueryMsg::FinalityProvider {
-            btc_pk_hex: fp_btc_pk_hex.to_string(),
-        },
-    )?;
+    let fp: FinalityProvider = deps
+        .querier
+        .query_wasm_smart(
+            staking_addr.clone(),
+            &btc_staking::msg::QueryMsg::FinalityProvider {
+                btc_pk_hex: btc_pk.clone(),
+            },
+        )
+        .map_err(|_| ContractError::FinalityProviderNotFound(fp_btc_pk_hex.to_string()))?;
 
     // Ensure the finality provider is not slashed at this time point
     // NOTE: It's possible that the finality provider equivocates for height h, and the signature is
@@ -165,18 +375,20 @@ pub fn handle_finality_signature(
         ));
     }
 
-    // Ensure the finality provider has voting power at this height
-    let fp: FinalityProviderInfo = deps
+    // Ensure the finality provider has voting power at this height, unless zero-power votes are
+    // recorded. A finality provider without state at this height has no voting power
+    let power = deps
         .querier
-        .query_wasm_smart(
+        .query_wasm_smart::<FinalityProviderInfo>(
             staking_addr.clone(),
             &btc_staking::msg::QueryMsg::FinalityProviderInfo {
-                btc_pk_hex: fp_btc_pk_hex.to_string(),
+                btc_pk_hex: btc_pk,
                 height: Some(height),
             },
         )
-        .map_err(|_| ContractError::NoVotingPower(fp_btc_pk_hex.to_string(), height))?;
-    if fp.power == 0 {
+        .map_or(0, |fp| fp.power);
+    let params = PARAMS.load(deps.storage)?;
+    if power == 0 && params.zero_power_votes == ZeroPowerVotes::Reject {
         return Err(ContractError::NoVotingPower(
             fp_btc_pk_hex.to_string(),
             height,
@@ -191,22 +403,47 @@ pub fn handle_finality_signature(
     if env.block.height < height {
         return Err(ContractError::HeightTooHigh);
     }
+    // Ensure the vote has not been invalidated by governance
+    if INVALIDATED_VOTES.has(deps.storage, (height, fp_btc_pk_hex)) {
+        return Err(ContractError::VoteInvalidated(
+            fp_btc_pk_hex.to_string(),
+            height,
+        ));
+    }
     // Ensure the finality provider has not cast the same vote yet
     let existing_sig = SIGNATURES.may_load(deps.storage, (height, fp_btc_pk_hex))?;
     match existing_sig {
         Some(existing_sig) if existing_sig == signature => {
             deps.api.debug(&format!("Received duplicated finality vote. Height: {height}, Finality Provider: {fp_btc_pk_hex}"));
             // Exactly the same vote already exists, return success to the provider
-            return Ok(Response::new());
+            let result = FinalitySignatureResult {
+                fp_btc_pk_hex: fp_btc_pk_hex.to_string(),
+                height,
+                duplicate: true,
+                fork: false,
+                slashed: false,
+                finalized: BLOCKS
+                    .may_load(deps.storage, height)?
+                    .is_some_and(|b| b.finalized),
+                rejected: None,
+                zero_power: power == 0,
+            };
+            return Ok(Response::new().set_data(to_json_binary(&result)?));
         }
         _ => {}
     }
 
     // Find the public randomness commitment for this height from this finality provider
     let pr_commit = get_pub_rand_commit_for_height(deps.storage, fp_btc_pk_hex, height)?;
+    // In strict mode, the commitment has to be timestamped in a BTC-finalised Babylon epoch
+    if params.strict_pub_rand_timestamping {
+        ensure_pub_rand_commit_finalized(&mut deps, fp_btc_pk_hex, &pr_commit)?;
+    }
 
     // Verify the finality signature message
+    let ctx = signing_context(deps.storage, &env)?;
     verify_finality_signature(
+        &ctx,
         fp_btc_pk_hex,
         height,
         pub_rand,
@@ -216,19 +453,54 @@ pub fn handle_finality_signature(
         signature,
     )?;
 
-    // The public randomness value is good, save it.
-    // TODO?: Don't save public randomness values, to save storage space
-    PUB_RAND_VALUES.save(deps.storage, (fp_btc_pk_hex, height), &pub_rand.to_vec())?;
-
     // Verify whether the voted block is a fork or not
     // TODO?: Do not rely on 'canonical' (i.e. BFT-consensus provided) blocks info
     let indexed_block = BLOCKS
         .load(deps.storage, height)
         .map_err(|err| ContractError::BlockNotFound(height, err.to_string()))?;
 
+    // The public randomness value is good, archive it (with bounded retention)
+    let reused_at = save_pub_rand_value(
+        deps.storage,
+        fp_btc_pk_hex,
+        height,
+        pub_rand,
+        params.pub_rand_retention,
+    )?;
+
+    // If the finality provider revealed the same public randomness at an earlier height, record
+    // the evidence. The finality provider is slashed for it below, unless it's slashed for
+    // equivocation already
+    let reuse_evidence = match reused_at {
+        Some(first_height) => pub_rand_reuse_evidence(
+            deps.storage,
+            fp_btc_pk_hex,
+            pub_rand,
+            first_height,
+            height,
+            block_app_hash,
+            signature,
+        )?,
+        None => None,
+    };
+    if let Some(evidence) = &reuse_evidence {
+        PUB_RAND_REUSE_EVIDENCES.save(deps.storage, (fp_btc_pk_hex, height), evidence)?;
+    }
+
     let mut res = Response::new();
+    let mut result = FinalitySignatureResult {
+        fp_btc_pk_hex: fp_btc_pk_hex.to_string(),
+        height,
+        duplicate: false,
+        fork: false,
+        slashed: false,
+        finalized: indexed_block.finalized,
+        rejected: None,
+        zero_power: power == 0,
+    };
     if indexed_block.app_hash != block_app_hash {
         // The finality provider votes for a fork!
+        result.fork = true;
 
         // Construct evidence
         let mut evidence = Evidence {
@@ -251,15 +523,23 @@ pub fn handle_finality_signature(
             let (msg, ev) = slash_finality_provider(&mut deps, fp_btc_pk_hex, &evidence)?;
             res = res.add_message(msg);
             res = res.add_event(ev);
+            result.slashed = true;
         }
         // TODO?: Also slash if this finality provider has signed another fork before
 
         // Save evidence
         EVIDENCES.save(deps.storage, (fp_btc_pk_hex, height), &evidence)?;
 
+        if let (Some(reuse_evidence), false) = (&reuse_evidence, result.slashed) {
+            let (msg, ev) = slash_pub_rand_reuse(&mut deps, &ctx, fp_btc_pk_hex, reuse_evidence)?;
+            res = res.add_message(msg);
+            res = res.add_event(ev);
+            result.slashed = true;
+        }
+
         // NOTE: We should NOT return error here, otherwise the state change triggered in this tx
         // (including the evidence) will be rolled back
-        return Ok(res);
+        return Ok(res.set_data(to_json_binary(&result)?));
     }
 
     // This signature is good, save the vote to the store
@@ -280,9 +560,136 @@ pub fn handle_finality_signature(
         let (msg, ev) = slash_finality_provider(&mut deps, fp_btc_pk_hex, &evidence)?;
         res = res.add_message(msg);
         res = res.add_event(ev);
+        result.slashed = true;
+    }
+
+    if let (Some(reuse_evidence), false) = (&reuse_evidence, result.slashed) {
+        let (msg, ev) = slash_pub_rand_reuse(&mut deps, &ctx, fp_btc_pk_hex, reuse_evidence)?;
+        res = res.add_message(msg);
+        res = res.add_event(ev);
+        result.slashed = true;
+    }
+
+    Ok(res.set_data(to_json_binary(&result)?))
+}
+
+/// `ensure_pub_rand_commit_finalized` ensures the given public randomness commitment has been
+/// timestamped in a BTC-finalised Babylon epoch, recording that epoch. The epoch is the one of the
+/// first BTC-finalised CZ header at or above the height the commitment was made at.
+/// Commitments made before their timestamp was recorded are taken as made at their start height
+fn ensure_pub_rand_commit_finalized(
+    deps: &mut DepsMut,
+    fp_btc_pk_hex: &str,
+    pr_commit: &PubRandCommit,
+) -> Result<u64, ContractError> {
+    let key = (fp_btc_pk_hex, pr_commit.start_height);
+    let mut timestamp = PUB_RAND_COMMIT_TIMESTAMPS
+        .may_load(deps.storage, key)?
+        .unwrap_or(PubRandCommitTimestamp {
+            committed_at: pr_commit.start_height,
+            babylon_epoch: None,
+        });
+    if let Some(babylon_epoch) = timestamp.babylon_epoch {
+        return Ok(babylon_epoch);
     }
 
-    Ok(res)
+    let babylon_addr = CONFIG.load(deps.storage)?.babylon;
+    let cz_header: Option<CzHeaderResponse> = deps.querier.query_wasm_smart(
+        babylon_addr,
+        &babylon_contract::msg::contract::QueryMsg::CzNextHeader {
+            height: timestamp.committed_at,
+        },
+    )?;
+    let Some(cz_header) = cz_header else {
+        return Err(ContractError::PubRandCommitNotFinalized(
+            fp_btc_pk_hex.to_string(),
+            pr_commit.start_height,
+        ));
+    };
+    timestamp.babylon_epoch = Some(cz_header.babylon_epoch);
+    PUB_RAND_COMMIT_TIMESTAMPS.save(deps.storage, key, &timestamp)?;
+    Ok(cz_header.babylon_epoch)
+}
+
+/// `pub_rand_reuse_evidence` builds the evidence of a finality provider revealing the same public
+/// randomness at `first_height` and `height`, from its (canonical or fork) vote at `first_height`.
+/// Returns `None` if that vote is not around anymore
+#[allow(clippy::too_many_arguments)]
+fn pub_rand_reuse_evidence(
+    storage: &dyn Storage,
+    fp_btc_pk_hex: &str,
+    pub_rand: &[u8],
+    first_height: u64,
+    height: u64,
+    app_hash: &[u8],
+    finality_sig: &[u8],
+) -> Result<Option<PubRandReuseEvidence>, ContractError> {
+    let first_vote = match SIGNATURES.may_load(storage, (first_height, fp_btc_pk_hex))? {
+        Some(sig) => BLOCKS
+            .may_load(storage, first_height)?
+            .map(|block| (block.app_hash, sig)),
+        None => EVIDENCES
+            .may_load(storage, (fp_btc_pk_hex, first_height))?
+            .map(|evidence| (evidence.fork_app_hash, evidence.fork_finality_sig)),
+    };
+    let fp_btc_pk = hex::decode(fp_btc_pk_hex)?;
+    Ok(first_vote.map(
+        |(first_app_hash, first_finality_sig)| PubRandReuseEvidence {
+            fp_btc_pk,
+            pub_rand: pub_rand.to_vec(),
+            first_height,
+            first_app_hash,
+            first_finality_sig,
+            height,
+            app_hash: app_hash.to_vec(),
+            finality_sig: finality_sig.to_vec(),
+        },
+    ))
+}
+
+/// `slash_pub_rand_reuse` slashes a finality provider that reused public randomness across
+/// heights, extracting its BTC SK, and emitting an event.
+/// Babylon's evidence only covers double signing at a single height, so the finality provider is
+/// slashed on the Consumer only
+fn slash_pub_rand_reuse(
+    deps: &mut DepsMut,
+    ctx: &SigningContext,
+    fp_btc_pk_hex: &str,
+    evidence: &PubRandReuseEvidence,
+) -> Result<(WasmMsg, Event), ContractError> {
+    let pk = eots::PublicKey::from_hex(fp_btc_pk_hex)?;
+    let first_msg_hash =
+        Sha256::digest(ctx.finality_sig_msg(evidence.first_height, &evidence.first_app_hash));
+    let msg_hash = Sha256::digest(ctx.finality_sig_msg(evidence.height, &evidence.app_hash));
+    let btc_sk = pk
+        .extract_secret_key(
+            &evidence.pub_rand,
+            &first_msg_hash,
+            &evidence.first_finality_sig,
+            &msg_hash,
+            &evidence.finality_sig,
+        )
+        .map_err(|err| ContractError::SecretKeyExtractionError(err.to_string()))?;
+
+    // Send to babylon-contract for routing to the staking contract
+    let msg = babylon_contract::ExecuteMsg::SlashFinalityProvider {
+        fp_btc_pk_hex: fp_btc_pk_hex.to_string(),
+    };
+    let babylon_addr = CONFIG.load(deps.storage)?.babylon;
+    let wasm_msg = WasmMsg::Execute {
+        contract_addr: babylon_addr.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    };
+
+    let ev = Event::new("slashed_finality_provider_pub_rand_reuse")
+        .add_attribute("module", "finality")
+        .add_attribute("finality_provider", fp_btc_pk_hex)
+        .add_attribute("pub_rand", hex::encode(&evidence.pub_rand))
+        .add_attribute("first_block_height", evidence.first_height.to_string())
+        .add_attribute("block_height", evidence.height.to_string())
+        .add_attribute("secret_key", hex::encode(btc_sk.to_bytes()));
+    Ok((wasm_msg, ev))
 }
 
 /// `slash_finality_provider` slashes a finality provider with the given evidence including setting
@@ -342,7 +749,9 @@ fn slash_finality_provider(
 /// Verifies the finality signature message w.r.t. the public randomness commitment:
 /// - Public randomness inclusion proof.
 /// - Finality signature
+#[allow(clippy::too_many_arguments)]
 fn verify_finality_signature(
+    ctx: &SigningContext,
     fp_btc_pk_hex: &str,
     block_height: u64,
     pub_rand: &[u8],
@@ -371,7 +780,7 @@ fn verify_finality_signature(
 
     // Public randomness is good, verify finality signature
     let pubkey = eots::PublicKey::from_hex(fp_btc_pk_hex)?;
-    let msg = msg_to_sign(block_height, app_hash);
+    let msg = ctx.finality_sig_msg(block_height, app_hash);
     let msg_hash = Sha256::digest(msg);
 
     if !pubkey.verify(pub_rand, &msg_hash, signature)? {
@@ -380,26 +789,20 @@ fn verify_finality_signature(
     Ok(())
 }
 
-/// `msg_to_sign` returns the message for an EOTS signature.
-///
-/// The EOTS signature on a block will be (block_height || block_hash)
-fn msg_to_sign(height: u64, block_hash: &[u8]) -> Vec<u8> {
-    let mut msg: Vec<u8> = height.to_be_bytes().to_vec();
-    msg.extend_from_slice(block_hash);
-    msg
-}
-
 pub fn index_block(
     deps: &mut DepsMut,
-    height: u64,
+    block: &BlockInfo,
     app_hash: &[u8],
 ) -> Result<Event, ContractError> {
+    let height = block.height;
     let indexed_block = IndexedBlock {
         height,
         app_hash: app_hash.into(),
         finalized: false,
     };
     BLOCKS.save(deps.storage, height, &indexed_block)?;
+    // Record the block time, to measure its finality latency
+    BLOCK_TIMES.save(deps.storage, height, &block.time)?;
 
     // Register the indexed block height
     let ev = Event::new("index_block")
@@ -418,11 +821,13 @@ pub fn index_block(
 /// but no blocks that have a finality provider set and do not receive a QC
 ///
 /// It must be invoked only after the BTC staking protocol is activated.
+/// Returns the events, and the messages jailing the finality providers that missed too many blocks
 pub fn tally_blocks(
     deps: &mut DepsMut,
     activated_height: u64,
-    height: u64,
-) -> Result<Vec<Event>, ContractError> {
+    block: &BlockInfo,
+) -> Result<(Vec<Event>, Vec<WasmMsg>), ContractError> {
+    let height = block.height;
     // Start finalising blocks since max(activated_height, next_height)
     let next_height = NEXT_HEIGHT.may_load(deps.storage)?.unwrap_or(0);
     let start_height = max(activated_height, next_height);
@@ -436,7 +841,9 @@ pub fn tally_blocks(
     // - Does not have finality providers, finalised: Impossible, panic.
     // After this for loop, the blocks since the earliest activated height are either finalised or
     // non-finalisable
+    let params = PARAMS.load(deps.storage)?;
     let mut events = vec![];
+    let mut msgs = vec![];
     for h in start_height..=height {
         let mut indexed_block = BLOCKS.load(deps.storage, h)?;
         // Get the finality provider set of this block
@@ -449,10 +856,24 @@ pub fn tally_blocks(
                     .prefix(indexed_block.height)
                     .keys(deps.storage, None, None, Ascending)
                     .collect::<StdResult<Vec<_>>>()?;
-                if tally(&fp_set, &voter_btc_pks) {
-                    // If this block gets >2/3 votes, finalise it
-                    let ev = finalize_block(deps.storage, &mut indexed_block, &voter_btc_pks)?;
+                if reaches_quorum(deps.storage, &params, h, &fp_set, &voter_btc_pks)? {
+                    // If this block reaches the quorum(s), finalise it
+                    let ev = finalize_block(
+                        deps.storage,
+                        &params,
+                        block,
+                        &mut indexed_block,
+                        &fp_set,
+                        &voter_btc_pks,
+                    )?;
                     events.push(ev);
+                    // Jail the finality providers that missed too many blocks in a row
+                    for (wasm_msg, ev) in
+                        jail_missed_blocks(deps.storage, &params, h, &fp_set, &voter_btc_pks)?
+                    {
+                        msgs.push(wasm_msg);
+                        events.push(ev);
+                    }
                 } else {
                     // If not, then this block and all subsequent blocks should not be finalised.
                     // Thus, we need to break here
@@ -480,30 +901,197 @@ pub fn tally_blocks(
             }
         }
     }
-    Ok(events)
+    Ok((events, msgs))
+}
+
+/// `reaches_quorum` checks whether a block with the given finality provider set and votes reaches
+/// a quorum or not. If a native quorum is configured, the native validator votes reported for
+/// the block have to reach it as well
+fn reaches_quorum(
+    storage: &dyn Storage,
+    params: &Params,
+    height: u64,
+    fp_set: &[FinalityProviderInfo],
+    voters: &[String],
+) -> StdResult<bool> {
+    let (voted_power, total_power) = tally(fp_set, voters)?;
+    let Some(native_quorum) = &params.native_quorum else {
+        return Ok(exceeds_fraction(voted_power, total_power, 2, 3));
+    };
+    // Blocks cannot be finalised until the native votes are reported
+    let Some(native) = NATIVE_TALLIES.may_load(storage, height)? else {
+        return Ok(false);
+    };
+    Ok(
+        exceeds_share(voted_power, total_power, native_quorum.btc_threshold)
+            && exceeds_share(
+                native.voted_power,
+                native.total_power,
+                native_quorum.native_threshold,
+            ),
+    )
 }
 
-/// `tally` checks whether a block with the given finality provider set and votes reaches a quorum
-/// or not
-fn tally(fp_set: &[FinalityProviderInfo], voters: &[String]) -> bool {
+/// `tally` returns the voted and total power of the given finality provider set
+pub(crate) fn tally(fp_set: &[FinalityProviderInfo], voters: &[String]) -> StdResult<(u64, u64)> {
     let voters: HashSet<String> = voters.iter().cloned().collect();
     let mut total_power = 0;
     let mut voted_power = 0;
     for fp_info in fp_set {
-        total_power += fp_info.power;
+        total_power = add_sat(total_power, fp_info.power)?;
         if voters.contains(&fp_info.btc_pk_hex) {
-            voted_power += fp_info.power;
+            voted_power = add_sat(voted_power, fp_info.power)?;
         }
     }
-    voted_power * 3 > total_power * 2
+    Ok((voted_power, total_power))
+}
+
+/// `handle_native_votes` records the voting power of the native-staked validators of the consumer
+/// chain at the given height
+pub fn handle_native_votes(
+    storage: &mut dyn Storage,
+    height: u64,
+    validators: &[NativeValidatorVote],
+) -> Result<Event, ContractError> {
+    let tally =
+        validators
+            .iter()
+            .try_fold(NativeTally::default(), |mut tally, val| -> StdResult<_> {
+                tally.total_power = add_sat(tally.total_power, val.power)?;
+                if val.voted {
+                    tally.voted_power = add_sat(tally.voted_power, val.power)?;
+                }
+                Ok(tally)
+            })?;
+    NATIVE_TALLIES.save(storage, height, &tally)?;
+
+    Ok(Event::new("native_votes")
+        .add_attribute("module", "finality")
+        .add_attribute("height", height.to_string())
+        .add_attribute("total_power", tally.total_power.to_string())
+        .add_attribute("voted_power", tally.voted_power.to_string()))
+}
+
+/// `handle_invalidate_votes` strikes the votes of the given finality providers at the given
+/// height, and tallies the block again without them.
+/// If the block was finalized and no longer reaches the quorum, it loses its finality along with
+/// its finalized descendants, which are tallied again from the next `EndBlock` on.
+/// The intervention is recorded for auditing
+pub fn handle_invalidate_votes(
+    storage: &mut dyn Storage,
+    env: &Env,
+    height: u64,
+    fp_pks: &[String],
+) -> Result<Event, ContractError> {
+    let id = VOTE_INVALIDATIONS
+        .keys(storage, None, None, Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |id| id + 1);
+    for fp_btc_pk_hex in fp_pks {
+        SIGNATURES.remove(storage, (height, fp_btc_pk_hex));
+        INVALIDATED_VOTES.save(storage, (height, fp_btc_pk_hex), &id)?;
+    }
+
+    // Tally the block again, without the struck votes
+    let params = PARAMS.load(storage)?;
+    let fp_set = FP_SET.may_load(storage, height)?.unwrap_or_default();
+    let voters = SIGNATURES
+        .prefix(height)
+        .keys(storage, None, None, Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let (voted_power, total_power) = tally(&fp_set, &voters)?;
+    let mut last_unfinalized_height = None;
+    let finalized = BLOCKS
+        .may_load(storage, height)?
+        .is_some_and(|b| b.finalized);
+    if finalized && !reaches_quorum(storage, &params, height, &fp_set, &voters)? {
+        let next_height = NEXT_HEIGHT.load(storage)?;
+        for h in height..next_height {
+            if let Some(mut block) = BLOCKS.may_load(storage, h)? {
+                block.finalized = false;
+                BLOCKS.save(storage, h, &block)?;
+            }
+        }
+        NEXT_HEIGHT.save(storage, &height)?;
+        last_unfinalized_height = Some(next_height - 1);
+    }
+
+    let invalidation = VoteInvalidation {
+        id,
+        height,
+        fp_pks: fp_pks.to_vec(),
+        invalidated_at: env.block.height,
+        voted_power,
+        total_power,
+        last_unfinalized_height,
+    };
+    VOTE_INVALIDATIONS.save(storage, id, &invalidation)?;
+
+    let mut ev = Event::new("invalidate_votes")
+        .add_attribute("module", "finality")
+        .add_attribute("id", id.to_string())
+        .add_attribute("height", height.to_string())
+        .add_attribute("fp_pks", fp_pks.join(","))
+        .add_attribute("voted_power", voted_power.to_string())
+        .add_attribute("total_power", total_power.to_string());
+    if let Some(last_unfinalized_height) = last_unfinalized_height {
+        ev = ev.add_attribute(
+            "last_unfinalized_height",
+            last_unfinalized_height.to_string(),
+        );
+    }
+    Ok(ev)
+}
+
+/// `handle_set_tally_start_height` moves the next height to be tallied forward to `height`, so
+/// that the heights below it are skipped. It cannot move it backwards, over already tallied blocks
+pub fn handle_set_tally_start_height(
+    storage: &mut dyn Storage,
+    height: u64,
+) -> Result<Event, ContractError> {
+    let next_height = NEXT_HEIGHT.may_load(storage)?.unwrap_or(0);
+    if height < next_height {
+        return Err(ContractError::InvalidTallyStartHeight(height, next_height));
+    }
+    NEXT_HEIGHT.save(storage, &height)?;
+    PARAMS.update(storage, |mut params| -> StdResult<_> {
+        params.tally_start_height = height;
+        Ok(params)
+    })?;
+
+    Ok(Event::new("set_tally_start_height")
+        .add_attribute("module", "finality")
+        .add_attribute("previous_height", next_height.to_string())
+        .add_attribute("height", height.to_string()))
+}
+
+/// `handle_set_min_vote_payload_version` sets the minimum vote payload version finality signature
+/// submissions must declare
+pub fn handle_set_min_vote_payload_version(
+    storage: &mut dyn Storage,
+    version: u32,
+) -> Result<Event, ContractError> {
+    let mut params = PARAMS.load(storage)?;
+    let previous_version = params.min_vote_payload_version;
+    params.min_vote_payload_version = version;
+    PARAMS.save(storage, &params)?;
+
+    Ok(Event::new("set_min_vote_payload_version")
+        .add_attribute("module", "finality")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("version", version.to_string()))
 }
 
-/// `finalize_block` sets a block to be finalised, and distributes rewards to finality providers
-/// and delegators
+/// `finalize_block` sets a block to be finalised at the `current` block, records its finality
+/// latency, and accounts for it in the reward accrual of the active finality providers
 fn finalize_block(
     store: &mut dyn Storage,
+    params: &Params,
+    current: &BlockInfo,
     block: &mut IndexedBlock,
-    _voters: &[String],
+    fp_set: &[FinalityProviderInfo],
+    voters: &[String],
 ) -> Result<Event, ContractError> {
     // Set block to be finalised
     block.finalized = true;
@@ -512,6 +1100,23 @@ fn finalize_block(
     // Set the next height to finalise as height+1
     NEXT_HEIGHT.save(store, &(block.height + 1))?;
 
+    // Record the finality latency, if the block time is known
+    if let Some(time) = BLOCK_TIMES.may_load(store, block.height)? {
+        let latency = FinalityLatency {
+            blocks: current.height.saturating_sub(block.height),
+            seconds: current.time.seconds().saturating_sub(time.seconds()),
+        };
+        FINALITY_LATENCIES.save(store, block.height, &latency)?;
+    }
+
+    // Accrue rewards to the finality providers by their participation
+    accrue(
+        store,
+        params.reward_accrual_period,
+        block.height,
+        fp_set,
+        voters,
+    )?;
     // TODO: Distribute rewards to BTC staking delegators
 
     // Record the last finalized height metric
@@ -521,7 +1126,56 @@ fn finalize_block(
     Ok(ev)
 }
 
-const QUERY_LIMIT: Option<u32> = Some(30);
+/// `jail_missed_blocks` updates the consecutive blocks missed by the finality providers of the
+/// finalised block at `height`, and jails (through the babylon contract) the ones reaching
+/// `Params::max_missed_blocks`
+fn jail_missed_blocks(
+    storage: &mut dyn Storage,
+    params: &Params,
+    height: u64,
+    fp_set: &[FinalityProviderInfo],
+    voters: &[String],
+) -> Result<Vec<(WasmMsg, Event)>, ContractError> {
+    if params.max_missed_blocks == 0 {
+        return Ok(vec![]);
+    }
+    let mut jailed = vec![];
+    for fp in fp_set {
+        if voters.contains(&fp.btc_pk_hex) {
+            MISSED_BLOCKS.remove(storage, &fp.btc_pk_hex);
+            continue;
+        }
+        let missed = MISSED_BLOCKS
+            .may_load(storage, &fp.btc_pk_hex)?
+            .unwrap_or_default()
+            + 1;
+        if missed < params.max_missed_blocks {
+            MISSED_BLOCKS.save(storage, &fp.btc_pk_hex, &missed)?;
+            continue;
+        }
+        MISSED_BLOCKS.remove(storage, &fp.btc_pk_hex);
+
+        // Send to babylon-contract for routing to the staking contract
+        let msg = babylon_contract::ExecuteMsg::JailFinalityProvider {
+            fp_btc_pk_hex: fp.btc_pk_hex.clone(),
+        };
+        let babylon_addr = CONFIG.load(storage)?.babylon;
+        let wasm_msg = WasmMsg::Execute {
+            contract_addr: babylon_addr.to_string(),
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+        };
+        let ev = Event::new("jailed_finality_provider")
+            .add_attribute("module", "finality")
+            .add_attribute("finality_provider", &fp.btc_pk_hex)
+            .add_attribute("block_height", height.to_string())
+            .add_attribute("missed_blocks", missed.to_string());
+        jailed.push((wasm_msg, ev));
+    }
+    Ok(jailed)
+}
+
+const QUERY_LIMIT: Option<u32> = Some(MAX_LIMIT);
 
 /// `compute_active_finality_providers` sorts all finality providers, counts the total voting
 /// power of top finality providers, and records them in the contract state
@@ -531,6 +1185,7 @@ pub fn compute_active_finality_providers(
     max_active_fps: usize,
 ) -> Result<(), ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
+    let min_self_stake = get_min_fp_self_stake(&cfg.staking, &deps.querier)?;
     // Get all finality providers from the staking contract, filtered
     let mut batch = list_fps_by_power(&cfg.staking, &deps.querier, None, QUERY_LIMIT)?;
 
@@ -539,26 +1194,21 @@ pub fn compute_active_finality_providers(
     while !batch.is_empty() && finality_providers.len() < max_active_fps {
         let last = batch.last().cloned();
 
-        let (filtered, running_total): (Vec<_>, Vec<_>) = batch
+        // Filter out FPs with no voting power, or not enough self-stake
+        for fp in batch
             .into_iter()
-            .filter(|fp| {
-                // Filter out FPs with no voting power
-                fp.power > 0
-            })
-            .scan(total_power, |acc, fp| {
-                *acc += fp.power;
-                Some((fp, *acc))
-            })
-            .unzip();
-        finality_providers.extend_from_slice(&filtered);
-        total_power = running_total.last().copied().unwrap_or_default();
+            .filter(|fp| fp.power > 0 && fp.self_stake >= min_self_stake)
+        {
+            total_power = add_sat(total_power, fp.power).map_err(StdError::from)?;
+            finality_providers.push(fp);
+        }
 
         // and get the next page
         batch = list_fps_by_power(&cfg.staking, &deps.querier, last, QUERY_LIMIT)?;
     }
 
     // TODO: Online FPs verification
-    // TODO: Filter out slashed / offline / jailed FPs
+    // TODO: Filter out slashed / offline FPs (jailed FPs have no voting power)
     // Save the new set of active finality providers
     // TODO: Purge old (height - finality depth) FP_SET entries to avoid bloating the storage
     FP_SET.save(deps.storage, env.block.height, &finality_providers)?;
@@ -568,6 +1218,14 @@ pub fn compute_active_finality_providers(
     Ok(())
 }
 
+/// `get_min_fp_self_stake` returns the minimum self-stake a finality provider needs to be part of
+/// the active set, according to the staking contract's params
+fn get_min_fp_self_stake(staking_addr: &Addr, querier: &QuerierWrapper) -> StdResult<u64> {
+    let query = encode_smart_query(staking_addr, &btc_staking::msg::QueryMsg::Params {})?;
+    let params: btc_staking::state::config::Params = querier.query(&query)?;
+    Ok(params.min_fp_self_stake_sat)
+}
+
 pub fn list_fps_by_power(
     staking_addr: &Addr,
     querier: &QuerierWrapper,
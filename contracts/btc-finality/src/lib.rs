@@ -4,6 +4,8 @@ pub mod contract;
 pub mod error;
 pub mod msg;
 pub mod queries;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
 pub mod state;
 
 #[cfg(test)]
@@ -1,13 +1,27 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Decimal;
 #[cfg(not(target_arch = "wasm32"))]
 use {
-    crate::state::config::Config, babylon_apis::finality_api::PubRandCommit,
-    cw_controllers::AdminResponse,
+    crate::state::config::Config, babylon_apis::error::ErrorCodesResponse,
+    babylon_apis::finality_api::PubRandCommit, babylon_apis::migration::MigrationProgressResponse,
+    babylon_apis::state_hash::StateHashResponse, cw_controllers::AdminResponse,
 };
 
 use babylon_apis::finality_api::{Evidence, IndexedBlock};
+use btc_staking::msg::FinalityProviderInfo;
 
 use crate::state::config::Params;
+use crate::state::finality::{
+    NativeTally, PubRandReuseEvidence, SigFailureReason, VoteInvalidation,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::state::public_randomness::PubRandCommitTimestamp;
+use crate::state::rewards::{RewardAccrual, SigningInfo};
+
+/// MigrateMsg is the message of a migration to a newer version of the contract
+#[cw_serde]
+#[derive(Default)]
+pub struct MigrateMsg {}
 
 #[cw_serde]
 #[derive(Default)]
@@ -65,6 +79,16 @@ pub enum QueryMsg {
     /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
     #[returns(Option<PubRandCommit>)]
     LastPubRandCommit { btc_pk_hex: String },
+    /// `PubRandCommitTimestamp` returns the block height a public randomness commitment was made
+    /// at, along with the Babylon epoch that timestamped it on BTC, once known.
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    /// `start_height` is the start height of the commitment
+    #[returns(Option<PubRandCommitTimestamp>)]
+    PubRandCommitTimestamp {
+        btc_pk_hex: String,
+        start_height: u64,
+    },
     /// `Block` returns the indexed block information at height
     ///
     #[returns(IndexedBlock)]
@@ -86,6 +110,113 @@ pub enum QueryMsg {
     /// `Evidence` returns the evidence for a given FP and block height
     #[returns(EvidenceResponse)]
     Evidence { btc_pk_hex: String, height: u64 },
+    /// `VoteProof` returns the vote of a finality provider for the block at the given height,
+    /// packaged for an external verifier to check its participation independently: the EOTS
+    /// signature, the public randomness it was produced with, the exact signed payload, the
+    /// block, and the voting power of the finality provider in the set of the block.
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    #[returns(VoteProofResponse)]
+    VoteProof { height: u64, btc_pk_hex: String },
+    /// `PubRandReuseEvidence` returns the evidence for a given FP having reused, at the given
+    /// block height, public randomness revealed at an earlier height
+    #[returns(PubRandReuseEvidenceResponse)]
+    PubRandReuseEvidence { btc_pk_hex: String, height: u64 },
+    /// `RevealedRandomness` returns the public randomness value revealed by a finality provider
+    /// for its vote at the given height, if it is still archived (see `Params::pub_rand_retention`)
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    #[returns(RevealedRandomnessResponse)]
+    RevealedRandomness { btc_pk_hex: String, height: u64 },
+    /// `NativeTally` returns the voting power of the native-staked validators of the consumer
+    /// chain at the given height (if reported)
+    #[returns(Option<NativeTally>)]
+    NativeTally { height: u64 },
+    /// `SigFailures` returns the counts of finality signatures that failed verification, by reason.
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format, or `None` for
+    /// the totals over all finality providers.
+    /// Failures are only recorded if `Params::record_sig_failures` is set
+    #[returns(SigFailuresResponse)]
+    SigFailures { btc_pk_hex: Option<String> },
+    /// `VoteInvalidations` returns the audit records of the governance interventions striking
+    /// finality votes (see `SudoMsg::InvalidateVotes`), in order.
+    ///
+    /// `start_after` is the id of the record to start after, or `None` to start from the first one.
+    /// `limit` is the maximum number of records to return.
+    #[returns(VoteInvalidationsResponse)]
+    VoteInvalidations {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// `VotingContext` returns everything a finality provider needs to know to vote at a given
+    /// block height: whether BTC staking is activated, whether it committed public randomness for
+    /// the height and already voted, its voting power, and whether the block is already finalized.
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    #[returns(VotingContextResponse)]
+    VotingContext { btc_pk_hex: String, height: u64 },
+    /// `RandomnessCoverage` returns the highest block height covered by the public randomness
+    /// commitments of a finality provider, and whether its public randomness runs out within the
+    /// next `Params::pub_rand_coverage_alert` blocks, i.e. whether it will soon be unable to vote.
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    #[returns(RandomnessCoverageResponse)]
+    RandomnessCoverage { btc_pk_hex: String },
+    /// `FinalityLatency` returns the min / avg / max finality latency, in blocks and in seconds,
+    /// over the last finalised blocks, e.g. for SLA monitoring.
+    ///
+    /// `last_n` is the number of last finalised blocks to compute the statistics over (100 by
+    /// default, 1000 at most)
+    #[returns(FinalityLatencyResponse)]
+    FinalityLatency { last_n: Option<u32> },
+    /// `RewardAccrual` returns the reward accrual of a finality provider over its completed
+    /// accrual periods, i.e. its voting power weighted by the fraction of the finalised blocks it
+    /// actually signed, along with its signing info over the current period.
+    /// Accrual is only tracked if `Params::reward_accrual_period` is set.
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    #[returns(RewardAccrualResponse)]
+    RewardAccrual { btc_pk_hex: String },
+    /// `TallyStatus` returns the progress of the tallying of the blocks: the next height to be
+    /// finalised, the latest indexed height, the gap between them, and the oldest non-finalised
+    /// block along with the voting power it is missing, e.g. to diagnose a stalled finalization
+    #[returns(TallyStatusResponse)]
+    TallyStatus {},
+    /// `ErrorCodes` returns the table of stable error codes of the contract
+    #[returns(ErrorCodesResponse)]
+    ErrorCodes {},
+    /// `StateHash` returns a deterministic digest over the contract's state, by state section
+    /// (see `babylon_apis::state_hash`), e.g. to compare the state of two deployments after a
+    /// migration
+    #[returns(StateHashResponse)]
+    StateHash {},
+    /// `MigrationProgress` returns the progress of the scheduled storage migrations
+    #[returns(MigrationProgressResponse)]
+    MigrationProgress {},
+}
+
+/// `FinalitySignatureResult` is the result of an accepted finality signature, set as the response
+/// data of `SubmitFinalitySignature`
+#[cw_serde]
+pub struct FinalitySignatureResult {
+    pub fp_btc_pk_hex: String,
+    pub height: u64,
+    /// `duplicate` is whether exactly the same vote had already been accepted
+    pub duplicate: bool,
+    /// `fork` is whether the vote is for a block other than the canonical one at `height`
+    pub fork: bool,
+    /// `slashed` is whether the finality provider has been slashed because of this vote
+    pub slashed: bool,
+    /// `finalized` is whether the canonical block at `height` is already finalized
+    pub finalized: bool,
+    /// `rejected` is the reason the signature failed verification, if it was recorded in the
+    /// failure metrics instead of being rejected with an error (see `Params::record_sig_failures`)
+    pub rejected: Option<SigFailureReason>,
+    /// `zero_power` is whether the finality provider has no voting power at `height`, so that the
+    /// vote is recorded but not counted (see `Params::zero_power_votes`)
+    #[serde(default)]
+    pub zero_power: bool,
 }
 
 #[cw_serde]
@@ -93,12 +224,153 @@ pub struct FinalitySignatureResponse {
     pub signature: Vec<u8>,
 }
 
+#[cw_serde]
+pub struct VoteInvalidationsResponse {
+    pub invalidations: Vec<VoteInvalidation>,
+}
+
+#[cw_serde]
+pub struct VotingContextResponse {
+    /// `activated` is whether the BTC staking protocol is activated at the height
+    pub activated: bool,
+    /// `pub_rand_committed` is whether the finality provider committed public randomness covering
+    /// the height
+    pub pub_rand_committed: bool,
+    /// `voted` is whether the finality provider already voted at the height
+    pub voted: bool,
+    /// `power` is the voting power of the finality provider at the height
+    pub power: u64,
+    /// `finalized` is whether the block at the height is already finalized
+    pub finalized: bool,
+}
+
+#[cw_serde]
+pub struct RandomnessCoverageResponse {
+    /// `covered_height` is the highest block height covered by the public randomness commitments
+    /// of the finality provider, if it committed any
+    pub covered_height: Option<u64>,
+    /// `running_out` is whether the public randomness of the finality provider does not cover the
+    /// next `alert_blocks` blocks
+    pub running_out: bool,
+    /// `alert_blocks` is the number of upcoming blocks checked (see
+    /// `Params::pub_rand_coverage_alert`)
+    pub alert_blocks: u64,
+}
+
+#[cw_serde]
+pub struct FinalityLatencyResponse {
+    /// `count` is the number of finalised blocks the statistics are computed over
+    pub count: u32,
+    /// `blocks` is the latency in blocks, i.e. the number of blocks between a block and the one
+    /// it was finalised at
+    pub blocks: LatencyStats,
+    /// `seconds` is the latency in seconds, i.e. the time elapsed between a block and the one it
+    /// was finalised at
+    pub seconds: LatencyStats,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct LatencyStats {
+    pub min: u64,
+    pub avg: Decimal,
+    pub max: u64,
+}
+
+#[cw_serde]
+pub struct RewardAccrualResponse {
+    /// `accrual` is the reward accrual over the completed accrual periods
+    pub accrual: RewardAccrual,
+    /// `current` is the signing info of the finality provider over the current accrual period, if
+    /// it was active in any block finalised during it
+    pub current: Option<SigningInfo>,
+    /// `participation` is the fraction of the blocks finalised during the current accrual period
+    /// the finality provider signed
+    pub participation: Decimal,
+}
+
+#[cw_serde]
+pub struct TallyStatusResponse {
+    /// `next_height` is the next height to be finalised
+    pub next_height: u64,
+    /// `latest_height` is the height of the latest indexed block, if any
+    pub latest_height: Option<u64>,
+    /// `gap` is the number of indexed blocks from `next_height` on, i.e. not finalised yet
+    pub gap: u64,
+    /// `oldest_unfinalized` is the oldest non-finalised block with a finality provider set, if any
+    pub oldest_unfinalized: Option<UnfinalizedBlock>,
+}
+
+/// `UnfinalizedBlock` is the tally of a non-finalised block
+#[cw_serde]
+pub struct UnfinalizedBlock {
+    pub height: u64,
+    /// `total_power` is the voting power of the finality provider set of the block
+    pub total_power: u64,
+    /// `voted_power` is the voting power of the finality providers that voted for the block
+    pub voted_power: u64,
+    /// `missing` are the finality providers of the set that did not vote for the block
+    pub missing: Vec<FinalityProviderInfo>,
+    /// `native` is the tally of the native-staked validators at the height, if reported (see
+    /// `Params::native_quorum`)
+    pub native: Option<NativeTally>,
+}
+
+#[cw_serde]
+pub struct RevealedRandomnessResponse {
+    pub pub_rand: Option<Vec<u8>>,
+}
+
 #[cw_serde]
 pub struct BlocksResponse {
     pub blocks: Vec<IndexedBlock>,
 }
 
+#[cw_serde]
+pub struct VoteProofResponse {
+    /// `proof` is the proof of the vote, if the finality provider voted for the indexed block
+    pub proof: Option<VoteProof>,
+}
+
+/// `VoteProof` is the proof that a finality provider voted for a block.
+/// The vote is valid if `signature` is a valid EOTS signature by `fp_btc_pk_hex`, with
+/// `pub_rand`, over `sha256(signed_msg)`
+#[cw_serde]
+pub struct VoteProof {
+    pub fp_btc_pk_hex: String,
+    /// `block` is the voted block
+    pub block: IndexedBlock,
+    /// `signature` is the EOTS finality signature
+    pub signature: Vec<u8>,
+    /// `pub_rand` is the public randomness of the signature, if it is still archived (see
+    /// `Params::pub_rand_retention`)
+    pub pub_rand: Option<Vec<u8>>,
+    /// `signed_msg` is the payload signed by the finality provider, under the current signing
+    /// context (see `Params::signing_context_version`)
+    pub signed_msg: Vec<u8>,
+    /// `power` is the voting power of the finality provider in the set of the block
+    pub power: u64,
+    /// `total_power` is the total voting power of the finality provider set of the block
+    pub total_power: u64,
+}
+
 #[cw_serde]
 pub struct EvidenceResponse {
     pub evidence: Option<Evidence>,
 }
+
+#[cw_serde]
+pub struct PubRandReuseEvidenceResponse {
+    pub evidence: Option<PubRandReuseEvidence>,
+}
+
+#[cw_serde]
+pub struct SigFailureCount {
+    pub reason: SigFailureReason,
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct SigFailuresResponse {
+    pub failures: Vec<SigFailureCount>,
+}
@@ -1,3 +1,4 @@
+mod scenario;
 mod suite;
 
 use cosmwasm_std::Addr;
@@ -37,11 +38,20 @@ mod instantiation {
 mod finality {
     use super::*;
 
-    use crate::msg::FinalitySignatureResponse;
-    use babylon_apis::finality_api::IndexedBlock;
+    use crate::error::ContractError;
+    use crate::msg::{
+        FinalitySignatureResponse, FinalitySignatureResult, LatencyStats, SigFailuresResponse,
+        UnfinalizedBlock, VotingContextResponse,
+    };
+    use crate::state::config::{DualQuorum, Params, ZeroPowerVotes};
+    use crate::state::finality::{NativeTally, SigFailureReason, VoteInvalidation};
+    use crate::state::public_randomness::PubRandCommitTimestamp;
+    use crate::state::rewards::{RewardAccrual, SigningInfo};
+    use babylon_apis::finality_api::{IndexedBlock, NativeValidatorVote, PublicRandomnessCommit};
     use test_utils::get_public_randomness_commitment;
 
-    use cosmwasm_std::Event;
+    use cosmwasm_std::{from_json, Decimal, Event};
+    use k256::sha2::{Digest, Sha256};
     use test_utils::{
         create_new_finality_provider, get_add_finality_sig, get_derived_btc_delegation,
         get_pub_rand_value,
@@ -60,209 +70,1178 @@ mod finality {
         let new_fp = create_new_finality_provider(1);
         assert_eq!(new_fp.btc_pk_hex, pk_hex);
 
-        suite.register_finality_providers(&[new_fp]).unwrap();
+        suite.register_finality_providers(&[new_fp]).unwrap();
+
+        // Now commit the public randomness for it
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+    }
+
+    #[test]
+    fn randomness_coverage_works() {
+        let mut suite = SuiteBuilder::new()
+            .with_finality_params(Params {
+                pub_rand_coverage_alert: 10,
+                ..Params::default()
+            })
+            .build();
+
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let new_fp = create_new_finality_provider(1);
+        assert_eq!(new_fp.btc_pk_hex, pk_hex);
+        suite.register_finality_providers(&[new_fp]).unwrap();
+
+        // No public randomness committed yet
+        let coverage = suite.get_randomness_coverage(&pk_hex);
+        assert_eq!(coverage.covered_height, None);
+        assert!(coverage.running_out);
+        assert_eq!(coverage.alert_blocks, 10);
+
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+
+        // Covered for the next 10 blocks
+        suite
+            .app
+            .update_block(|block| block.height = pub_rand.end_height() - 10);
+        let coverage = suite.get_randomness_coverage(&pk_hex);
+        assert_eq!(coverage.covered_height, Some(pub_rand.end_height()));
+        assert!(!coverage.running_out);
+
+        // Runs out within the next 10 blocks
+        suite.app.update_block(|block| block.height += 1);
+        let coverage = suite.get_randomness_coverage(&pk_hex);
+        assert_eq!(coverage.covered_height, Some(pub_rand.end_height()));
+        assert!(coverage.running_out);
+    }
+
+    #[test]
+    fn commit_public_randomness_batch_works() {
+        let mut suite = SuiteBuilder::new().build();
+
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let new_fp = create_new_finality_provider(1);
+        assert_eq!(new_fp.btc_pk_hex, pk_hex);
+        suite.register_finality_providers(&[new_fp]).unwrap();
+
+        let commit = PublicRandomnessCommit {
+            fp_pubkey_hex: pk_hex.clone(),
+            start_height: pub_rand.start_height,
+            num_pub_rand: pub_rand.num_pub_rand,
+            commitment: pub_rand.commitment.clone().into(),
+            signature: pubrand_signature.into(),
+        };
+        // Unregistered finality provider
+        let unknown_fp = PublicRandomnessCommit {
+            fp_pubkey_hex: create_new_finality_provider(2).btc_pk_hex,
+            ..commit.clone()
+        };
+        // The second commit overlaps the first one
+        let res = suite
+            .commit_public_randomness_batch(&[commit.clone(), commit, unknown_fp.clone()])
+            .unwrap();
+
+        let overlap_err =
+            ContractError::InvalidPubRandHeight(pub_rand.start_height, pub_rand.end_height());
+        let unknown_fp_err =
+            ContractError::FinalityProviderNotFound(unknown_fp.fp_pubkey_hex.clone());
+        let ev = res
+            .events
+            .iter()
+            .find(|ev| ev.ty == "wasm-commit_public_randomness_batch")
+            .unwrap();
+        let attr = |key: &str| {
+            ev.attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.clone())
+        };
+        assert_eq!(attr("failed.0"), None);
+        assert_eq!(
+            attr("failed.1").unwrap(),
+            format!(
+                "{pk_hex}/{}: code {}: {overlap_err}",
+                pub_rand.start_height,
+                overlap_err.code()
+            )
+        );
+        assert_eq!(
+            attr("failed.2").unwrap(),
+            format!(
+                "{}/{}: code {}: {unknown_fp_err}",
+                unknown_fp.fp_pubkey_hex,
+                pub_rand.start_height,
+                unknown_fp_err.code()
+            )
+        );
+        assert_eq!(attr("committed").unwrap(), "1");
+        assert_eq!(attr("failed").unwrap(), "2");
+
+        // Only the valid commitment has been stored
+        assert!(suite
+            .get_pub_rand_commit_timestamp(&pk_hex, pub_rand.start_height)
+            .is_some());
+        assert!(suite
+            .get_pub_rand_commit_timestamp(&unknown_fp.fp_pubkey_hex, pub_rand.start_height)
+            .is_none());
+    }
+
+    #[test]
+    fn finality_signature_happy_path() {
+        // Read public randomness commitment test data
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        // Read equivalent / consistent add finality signature test data
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+
+        let initial_height = pub_rand.start_height;
+
+        let mut suite = SuiteBuilder::new().with_height(initial_height).build();
+
+        // Register one FP
+        // NOTE: the test data ensures that pub rand commit / finality sig are
+        // signed by the 1st FP
+        let new_fp = create_new_finality_provider(1);
+
+        suite.register_finality_providers(&[new_fp]).unwrap();
+
+        // Activated height is not set
+        let res = suite.get_activated_height();
+        assert_eq!(res.height, 0);
+        let no_context = VotingContextResponse {
+            activated: false,
+            pub_rand_committed: false,
+            voted: false,
+            power: 0,
+            finalized: false,
+        };
+        assert_eq!(
+            suite.get_voting_context(&pk_hex, initial_height + 1),
+            no_context
+        );
+
+        // Add a delegation, so that the finality provider has some power
+        let mut del1 = get_derived_btc_delegation(1, &[1]);
+        del1.fp_btc_pk_list = vec![pk_hex.clone()];
+
+        suite.add_delegations(&[del1]).unwrap();
+
+        // Activated height is now set
+        let res = suite.get_activated_height();
+        assert_eq!(res.height, initial_height + 1);
+
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+
+        // Call the begin-block sudo handler(s), for completeness
+        let res = suite
+            .call_begin_block(&add_finality_signature.block_app_hash, initial_height + 1)
+            .unwrap();
+        assert_eq!(1, res.events.len());
+        assert_eq!(
+            res.events[0],
+            Event::new("sudo").add_attribute("_contract_address", CONTRACT2_ADDR)
+        );
+
+        // Call the end-block sudo handler(s), so that the block is indexed in the store
+        let res = suite
+            .call_end_block(&add_finality_signature.block_app_hash, initial_height + 1)
+            .unwrap();
+        assert_eq!(2, res.events.len());
+        assert_eq!(
+            res.events[0],
+            Event::new("sudo").add_attribute("_contract_address", CONTRACT2_ADDR)
+        );
+        assert_eq!(
+            res.events[1],
+            Event::new("wasm-index_block")
+                .add_attribute("_contract_address", CONTRACT2_ADDR)
+                .add_attribute("module", "finality")
+                .add_attribute("last_height", (initial_height + 1).to_string())
+        );
+
+        // The finality provider can vote at height initial_height + 1
+        let power = suite
+            .get_finality_provider_info(&pk_hex, Some(initial_height + 1))
+            .power;
+        assert!(power > 0);
+        let context = VotingContextResponse {
+            activated: true,
+            pub_rand_committed: true,
+            voted: false,
+            power,
+            finalized: false,
+        };
+        assert_eq!(
+            suite.get_voting_context(&pk_hex, initial_height + 1),
+            context
+        );
+
+        // Submit a finality signature from that finality provider at height initial_height + 1
+        let finality_sig = add_finality_signature.finality_sig.to_vec();
+        let res = suite
+            .submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &pub_rand_one,
+                &proof,
+                &add_finality_signature.block_app_hash,
+                &finality_sig,
+            )
+            .unwrap();
+
+        // The result is set as the response data
+        let result: FinalitySignatureResult = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(
+            result,
+            FinalitySignatureResult {
+                fp_btc_pk_hex: pk_hex.clone(),
+                height: initial_height + 1,
+                duplicate: false,
+                fork: false,
+                slashed: false,
+                finalized: false,
+                rejected: None,
+                zero_power: false,
+            }
+        );
+        assert_eq!(
+            suite.get_voting_context(&pk_hex, initial_height + 1),
+            VotingContextResponse {
+                voted: true,
+                ..context
+            }
+        );
+
+        // Query finality signature for that exact height
+        let sig = suite.get_finality_signature(&pk_hex, initial_height + 1);
+        assert_eq!(
+            sig,
+            FinalitySignatureResponse {
+                signature: finality_sig
+            }
+        );
+
+        // The revealed public randomness is archived
+        let revealed = suite.get_revealed_randomness(&pk_hex, initial_height + 1);
+        assert_eq!(revealed, Some(pub_rand_one.to_vec()));
+    }
+
+    #[test]
+    fn sig_failures_are_recorded() {
+        // Read public randomness commitment test data
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        // Read equivalent / consistent add finality signature test data
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+        let finality_sig = add_finality_signature.finality_sig.to_vec();
+
+        let initial_height = pub_rand.start_height;
+
+        let mut suite = SuiteBuilder::new()
+            .with_height(initial_height)
+            .with_finality_params(Params {
+                record_sig_failures: true,
+                ..Default::default()
+            })
+            .build();
+
+        let new_fp = create_new_finality_provider(1);
+        suite.register_finality_providers(&[new_fp]).unwrap();
+        let mut del1 = get_derived_btc_delegation(1, &[1]);
+        del1.fp_btc_pk_list = vec![pk_hex.clone()];
+        suite.add_delegations(&[del1]).unwrap();
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+
+        // The block is not indexed yet
+        let res = suite
+            .submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &pub_rand_one,
+                &proof,
+                &add_finality_signature.block_app_hash,
+                &finality_sig,
+            )
+            .unwrap();
+        let result: FinalitySignatureResult = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(result.rejected, Some(SigFailureReason::HeightOutOfRange));
+
+        suite
+            .call_end_block(&add_finality_signature.block_app_hash, initial_height + 1)
+            .unwrap();
+
+        // Wrong public randomness for the proof
+        let mut bad_pub_rand = pub_rand_one.to_vec();
+        bad_pub_rand[0] ^= 1;
+        let res = suite
+            .submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &bad_pub_rand,
+                &proof,
+                &add_finality_signature.block_app_hash,
+                &finality_sig,
+            )
+            .unwrap();
+        let result: FinalitySignatureResult = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(result.rejected, Some(SigFailureReason::BadProof));
+        // The signature is not accepted
+        let sig = suite.get_finality_signature(&pk_hex, initial_height + 1);
+        assert!(sig.signature.is_empty());
+
+        let counts = |res: SigFailuresResponse| -> Vec<u64> {
+            res.failures.into_iter().map(|f| f.count).collect()
+        };
+        // bad proof, randomness mismatch, unknown FP, height out of range
+        assert_eq!(
+            counts(suite.get_sig_failures(Some(&pk_hex))),
+            vec![1, 0, 0, 1]
+        );
+        assert_eq!(counts(suite.get_sig_failures(None)), vec![1, 0, 0, 1]);
+
+        // A valid signature is still accepted
+        let res = suite
+            .submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &pub_rand_one,
+                &proof,
+                &add_finality_signature.block_app_hash,
+                &finality_sig,
+            )
+            .unwrap();
+        let result: FinalitySignatureResult = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(result.rejected, None);
+        let sig = suite.get_finality_signature(&pk_hex, initial_height + 1);
+        assert_eq!(sig.signature, finality_sig);
+    }
+
+    #[test]
+    fn zero_power_votes_work() {
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+        let finality_sig = add_finality_signature.finality_sig.to_vec();
+        let initial_height = pub_rand.start_height;
+
+        let build = |zero_power_votes| {
+            let mut suite = SuiteBuilder::new()
+                .with_height(initial_height)
+                .with_finality_params(Params {
+                    zero_power_votes,
+                    ..Default::default()
+                })
+                .build();
+            // Only the 2nd FP has power, the 1st FP (that signed the test data) has none
+            suite
+                .register_finality_providers(&[
+                    create_new_finality_provider(1),
+                    create_new_finality_provider(2),
+                ])
+                .unwrap();
+            suite
+                .add_delegations(&[get_derived_btc_delegation(2, &[2])])
+                .unwrap();
+            suite
+                .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+                .unwrap();
+            suite
+                .call_end_block(&add_finality_signature.block_app_hash, initial_height + 1)
+                .unwrap();
+            suite
+        };
+        let submit = |suite: &mut suite::Suite| {
+            suite.submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &pub_rand_one,
+                &proof,
+                &add_finality_signature.block_app_hash,
+                &finality_sig,
+            )
+        };
+
+        // Zero-power votes are rejected by default
+        let mut suite = build(ZeroPowerVotes::Reject);
+        let err = submit(&mut suite).unwrap_err();
+        assert_eq!(
+            ContractError::NoVotingPower(pk_hex.clone(), initial_height + 1),
+            err.downcast().unwrap()
+        );
+
+        // Or recorded, but not counted
+        let mut suite = build(ZeroPowerVotes::Record);
+        let res = submit(&mut suite).unwrap();
+        let result: FinalitySignatureResult = from_json(res.data.unwrap()).unwrap();
+        assert!(result.zero_power);
+        let sig = suite.get_finality_signature(&pk_hex, initial_height + 1);
+        assert_eq!(sig.signature, finality_sig);
+
+        suite
+            .call_end_block(&add_finality_signature.block_app_hash, initial_height + 2)
+            .unwrap();
+        let block = suite.get_indexed_block(initial_height + 1);
+        assert!(!block.finalized);
+    }
+
+    #[test]
+    fn strict_pub_rand_timestamping_works() {
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+        let block_app_hash = add_finality_signature.block_app_hash.to_vec();
+        let finality_sig = add_finality_signature.finality_sig.to_vec();
+
+        let initial_height = pub_rand.start_height;
+
+        let mut suite = SuiteBuilder::new()
+            .with_height(initial_height)
+            .with_finality_params(Params {
+                strict_pub_rand_timestamping: true,
+                ..Params::default()
+            })
+            .build();
+
+        let new_fp = create_new_finality_provider(1);
+        suite.register_finality_providers(&[new_fp]).unwrap();
+        let mut del1 = get_derived_btc_delegation(1, &[1]);
+        del1.fp_btc_pk_list = vec![pk_hex.clone()];
+        suite.add_delegations(&[del1]).unwrap();
+
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+        let committed_at = suite.app.block_info().height;
+        assert_eq!(
+            suite.get_pub_rand_commit_timestamp(&pk_hex, pub_rand.start_height),
+            Some(PubRandCommitTimestamp {
+                committed_at,
+                babylon_epoch: None,
+            })
+        );
+
+        suite
+            .call_begin_block(&block_app_hash, initial_height + 1)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, initial_height + 1)
+            .unwrap();
+
+        // The commitment is not BTC-finalised yet, so the signature is rejected
+        let err = suite
+            .submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &pub_rand_one,
+                &proof,
+                &block_app_hash,
+                &finality_sig,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::PubRandCommitNotFinalized(pk_hex.clone(), pub_rand.start_height)
+        );
+
+        // CZ headers below the commitment height do not BTC-finalise it
+        suite.insert_cz_header(committed_at - 1, 4);
+        suite
+            .submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &pub_rand_one,
+                &proof,
+                &block_app_hash,
+                &finality_sig,
+            )
+            .unwrap_err();
+
+        // Once a CZ header at or above the commitment height is BTC-finalised, the signature is
+        // accepted, and the epoch that timestamped the commitment is recorded
+        suite.insert_cz_header(committed_at + 2, 5);
+        suite.insert_cz_header(committed_at + 3, 6);
+        suite
+            .submit_finality_signature(
+                &pk_hex,
+                initial_height + 1,
+                &pub_rand_one,
+                &proof,
+                &block_app_hash,
+                &finality_sig,
+            )
+            .unwrap();
+        assert_eq!(
+            suite.get_pub_rand_commit_timestamp(&pk_hex, pub_rand.start_height),
+            Some(PubRandCommitTimestamp {
+                committed_at,
+                babylon_epoch: Some(5),
+            })
+        );
+    }
+
+    #[test]
+    fn finality_round_works() {
+        // Read public randomness commitment test data
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        // Read equivalent / consistent add finality signature test data
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+
+        let initial_height = pub_rand.start_height;
+
+        let mut suite = SuiteBuilder::new().with_height(initial_height).build();
+
+        // signed by the 1st FP
+        let new_fp = create_new_finality_provider(1);
+        assert_eq!(new_fp.btc_pk_hex, pk_hex);
+
+        suite
+            .register_finality_providers(&[new_fp.clone()])
+            .unwrap();
+
+        // Add a delegation, so that the finality provider has some power
+        let mut del1 = get_derived_btc_delegation(1, &[1]);
+        del1.fp_btc_pk_list = vec![pk_hex.clone()];
+
+        suite.add_delegations(&[del1.clone()]).unwrap();
+
+        // Check that the finality provider power has been updated
+        let fp_info = suite.get_finality_provider_info(&new_fp.btc_pk_hex, None);
+        assert_eq!(fp_info.power, del1.total_sat);
+
+        // Submit public randomness commitment for the FP and the involved heights
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+
+        // Call the begin-block sudo handler, for completeness
+        suite
+            .call_begin_block(&add_finality_signature.block_app_hash, initial_height + 1)
+            .unwrap();
+
+        // Call the end-block sudo handler, so that the block is indexed in the store
+        suite
+            .call_end_block(&add_finality_signature.block_app_hash, initial_height + 1)
+            .unwrap();
+
+        // Submit a finality signature from that finality provider at height initial_height + 1
+        let submit_height = initial_height + 1;
+        let finality_sig = add_finality_signature.finality_sig.to_vec();
+        suite
+            .submit_finality_signature(
+                &pk_hex,
+                submit_height,
+                &pub_rand_one,
+                &proof,
+                &add_finality_signature.block_app_hash,
+                &finality_sig,
+            )
+            .unwrap();
+
+        // Call the begin blocker, to compute the active FP set
+        suite
+            .call_begin_block(&add_finality_signature.block_app_hash, submit_height)
+            .unwrap();
+
+        // Call the end blocker, to process the finality signatures
+        let res = suite
+            .call_end_block(&add_finality_signature.block_app_hash, submit_height)
+            .unwrap();
+        assert_eq!(3, res.events.len());
+        assert_eq!(
+            res.events[0],
+            Event::new("sudo").add_attribute("_contract_address", CONTRACT2_ADDR)
+        );
+        assert_eq!(
+            res.events[1],
+            Event::new("wasm-index_block")
+                .add_attribute("_contract_address", CONTRACT2_ADDR)
+                .add_attribute("module", "finality")
+                .add_attribute("last_height", submit_height.to_string())
+        );
+        assert_eq!(
+            res.events[2],
+            Event::new("wasm-finalize_block")
+                .add_attribute("_contract_address", CONTRACT2_ADDR)
+                .add_attribute("module", "finality")
+                .add_attribute("finalized_height", submit_height.to_string())
+        );
+
+        // Assert the submitted block has been indexed and finalised
+        let indexed_block = suite.get_indexed_block(submit_height);
+        assert_eq!(
+            indexed_block,
+            IndexedBlock {
+                height: submit_height,
+                app_hash: add_finality_signature.block_app_hash.to_vec(),
+                finalized: true,
+            }
+        );
+
+        // Governance strikes the vote, e.g. because the finality provider key was compromised
+        let res = suite
+            .invalidate_votes(submit_height, std::slice::from_ref(&pk_hex))
+            .unwrap();
+        assert_eq!(
+            res.events[1],
+            Event::new("wasm-invalidate_votes")
+                .add_attribute("_contract_address", CONTRACT2_ADDR)
+                .add_attribute("module", "finality")
+                .add_attribute("id", "0")
+                .add_attribute("height", submit_height.to_string())
+                .add_attribute("fp_pks", pk_hex.clone())
+                .add_attribute("voted_power", "0")
+                .add_attribute("total_power", del1.total_sat.to_string())
+                .add_attribute("last_unfinalized_height", submit_height.to_string())
+        );
+
+        // The block lost its finality, and the vote is gone
+        assert!(!suite.get_indexed_block(submit_height).finalized);
+        assert!(suite
+            .get_finality_signature(&pk_hex, submit_height)
+            .signature
+            .is_empty());
+        assert_eq!(
+            suite.get_vote_invalidations(),
+            vec![VoteInvalidation {
+                id: 0,
+                height: submit_height,
+                fp_pks: vec![pk_hex.clone()],
+                invalidated_at: submit_height,
+                voted_power: 0,
+                total_power: del1.total_sat,
+                last_unfinalized_height: Some(submit_height),
+            }]
+        );
+
+        // The struck vote cannot be cast again
+        let err = suite
+            .submit_finality_signature(
+                &pk_hex,
+                submit_height,
+                &pub_rand_one,
+                &proof,
+                &add_finality_signature.block_app_hash,
+                &finality_sig,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::VoteInvalidated(pk_hex.clone(), submit_height)
+        );
+
+        // The block is tallied again, and not finalised without the struck vote
+        let res = suite
+            .call_end_block(&add_finality_signature.block_app_hash, submit_height)
+            .unwrap();
+        assert_eq!(2, res.events.len());
+        assert!(!suite.get_indexed_block(submit_height).finalized);
+    }
+
+    #[test]
+    fn dual_quorum_finalization_works() {
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+        let block_app_hash = add_finality_signature.block_app_hash.to_vec();
+
+        let initial_height = pub_rand.start_height;
+
+        let mut suite = SuiteBuilder::new()
+            .with_height(initial_height)
+            .with_finality_params(Params {
+                native_quorum: Some(DualQuorum {
+                    btc_threshold: Decimal::from_ratio(2u128, 3u128),
+                    native_threshold: Decimal::from_ratio(2u128, 3u128),
+                }),
+                ..Params::default()
+            })
+            .build();
+
+        let new_fp = create_new_finality_provider(1);
+        suite
+            .register_finality_providers(std::slice::from_ref(&new_fp))
+            .unwrap();
+        let mut del1 = get_derived_btc_delegation(1, &[1]);
+        del1.fp_btc_pk_list = vec![pk_hex.clone()];
+        suite.add_delegations(&[del1]).unwrap();
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+
+        let submit_height = initial_height + 1;
+        suite
+            .call_begin_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .submit_finality_signature(
+                &pk_hex,
+                submit_height,
+                &pub_rand_one,
+                &proof,
+                &block_app_hash,
+                &add_finality_signature.finality_sig,
+            )
+            .unwrap();
+
+        // The BTC quorum is reached, but the native votes are not reported yet
+        suite
+            .call_begin_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height)
+            .unwrap();
+        assert!(!suite.get_indexed_block(submit_height).finalized);
+
+        // The native quorum is not reached
+        let native_vote = |address: &str, voted: bool| NativeValidatorVote {
+            address: address.to_string(),
+            power: 10,
+            voted,
+        };
+        suite
+            .report_native_votes(
+                submit_height,
+                &[native_vote("val1", true), native_vote("val2", false)],
+            )
+            .unwrap();
+        assert_eq!(
+            suite.get_native_tally(submit_height),
+            Some(NativeTally {
+                total_power: 20,
+                voted_power: 10,
+            })
+        );
+        suite
+            .call_end_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        assert!(!suite.get_indexed_block(submit_height).finalized);
+
+        // Both quorums are reached
+        suite
+            .report_native_votes(
+                submit_height,
+                &[native_vote("val1", true), native_vote("val2", true)],
+            )
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height + 2)
+            .unwrap();
+        assert!(suite.get_indexed_block(submit_height).finalized);
+    }
+
+    #[test]
+    fn reward_accrual_works() {
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+        let block_app_hash = add_finality_signature.block_app_hash.clone();
+
+        let initial_height = pub_rand.start_height;
+        let submit_height = initial_height + 1;
+
+        let mut suite = SuiteBuilder::new()
+            .with_height(initial_height)
+            .with_finality_params(Params {
+                reward_accrual_period: 10,
+                ..Params::default()
+            })
+            .build();
 
-        // Now commit the public randomness for it
+        suite
+            .register_finality_providers(&[create_new_finality_provider(1)])
+            .unwrap();
+        let del1 = get_derived_btc_delegation(1, &[1]);
+        suite.add_delegations(std::slice::from_ref(&del1)).unwrap();
         suite
             .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
             .unwrap();
+
+        // Nothing accrued yet
+        let res = suite.get_reward_accrual(&pk_hex);
+        assert_eq!(res.accrual, RewardAccrual::default());
+        assert_eq!(res.current, None);
+
+        suite
+            .call_begin_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .submit_finality_signature(
+                &pk_hex,
+                submit_height,
+                &pub_rand_one,
+                &proof,
+                &block_app_hash,
+                &add_finality_signature.finality_sig,
+            )
+            .unwrap();
+        suite
+            .call_begin_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        assert!(suite.get_indexed_block(submit_height).finalized);
+
+        // The finality provider signed the only block finalised in the current period
+        let res = suite.get_reward_accrual(&pk_hex);
+        assert_eq!(res.accrual, RewardAccrual::default());
+        assert_eq!(
+            res.current,
+            Some(SigningInfo {
+                period: submit_height / 10,
+                active_blocks: 1,
+                signed_blocks: 1,
+                nominal_power: del1.total_sat.into(),
+                last_height: submit_height,
+            })
+        );
+        assert_eq!(res.participation, Decimal::one());
     }
 
     #[test]
-    fn finality_signature_happy_path() {
-        // Read public randomness commitment test data
+    fn missed_blocks_jailing_works() {
         let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
         let pub_rand_one = get_pub_rand_value();
-        // Read equivalent / consistent add finality signature test data
         let add_finality_signature = get_add_finality_sig();
         let proof = add_finality_signature.proof.unwrap();
+        let block_app_hash = add_finality_signature.block_app_hash.clone();
 
         let initial_height = pub_rand.start_height;
+        let submit_height = initial_height + 1;
 
-        let mut suite = SuiteBuilder::new().with_height(initial_height).build();
+        let mut suite = SuiteBuilder::new()
+            .with_height(initial_height)
+            .with_finality_params(Params {
+                max_missed_blocks: 1,
+                ..Params::default()
+            })
+            .build();
 
-        // Register one FP
-        // NOTE: the test data ensures that pub rand commit / finality sig are
-        // signed by the 1st FP
-        let new_fp = create_new_finality_provider(1);
+        // The 2nd FP has a minority of the voting power, so that blocks are finalised without it
+        let fp2 = create_new_finality_provider(2);
+        suite
+            .register_finality_providers(&[create_new_finality_provider(1), fp2.clone()])
+            .unwrap();
+        suite
+            .add_delegations(&[
+                get_derived_btc_delegation(1, &[1]),
+                get_derived_btc_delegation(2, &[1]),
+                get_derived_btc_delegation(3, &[1]),
+                get_derived_btc_delegation(1, &[2]),
+            ])
+            .unwrap();
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
 
-        suite.register_finality_providers(&[new_fp]).unwrap();
+        suite
+            .call_begin_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .submit_finality_signature(
+                &pk_hex,
+                submit_height,
+                &pub_rand_one,
+                &proof,
+                &block_app_hash,
+                &add_finality_signature.finality_sig,
+            )
+            .unwrap();
+        suite
+            .call_begin_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        assert!(suite.get_finality_provider_jail(&fp2.btc_pk_hex).is_none());
+        let res = suite
+            .call_end_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        assert!(suite.get_indexed_block(submit_height).finalized);
 
-        // Activated height is not set
-        let res = suite.get_activated_height();
-        assert_eq!(res.height, 0);
+        // The 2nd FP missed the finalised block, and is jailed
+        assert!(res.events.contains(
+            &Event::new("wasm-jailed_finality_provider")
+                .add_attribute("_contract_address", CONTRACT2_ADDR)
+                .add_attribute("module", "finality")
+                .add_attribute("finality_provider", &fp2.btc_pk_hex)
+                .add_attribute("block_height", submit_height.to_string())
+                .add_attribute("missed_blocks", "1")
+        ));
+        let jail = suite.get_finality_provider_jail(&fp2.btc_pk_hex).unwrap();
+        assert_eq!(jail.jailed_height, submit_height + 1);
+        assert_eq!(
+            suite
+                .get_finality_provider_info(&fp2.btc_pk_hex, None)
+                .power,
+            0
+        );
+        // The 1st FP voted, and is not jailed
+        assert!(suite.get_finality_provider_jail(&pk_hex).is_none());
+    }
 
-        // Add a delegation, so that the finality provider has some power
-        let mut del1 = get_derived_btc_delegation(1, &[1]);
-        del1.fp_btc_pk_list = vec![pk_hex.clone()];
+    #[test]
+    fn finality_latency_works() {
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+        let block_app_hash = add_finality_signature.block_app_hash.clone();
 
-        suite.add_delegations(&[del1]).unwrap();
+        let initial_height = pub_rand.start_height;
+        let submit_height = initial_height + 1;
 
-        // Activated height is now set
-        let res = suite.get_activated_height();
-        assert_eq!(res.height, initial_height + 1);
+        let mut suite = SuiteBuilder::new().with_height(initial_height).build();
 
+        suite
+            .register_finality_providers(&[create_new_finality_provider(1)])
+            .unwrap();
+        suite
+            .add_delegations(&[get_derived_btc_delegation(1, &[1])])
+            .unwrap();
         suite
             .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
             .unwrap();
 
-        // Call the begin-block sudo handler(s), for completeness
-        let res = suite
-            .call_begin_block(&add_finality_signature.block_app_hash, initial_height + 1)
-            .unwrap();
-        assert_eq!(1, res.events.len());
-        assert_eq!(
-            res.events[0],
-            Event::new("sudo").add_attribute("_contract_address", CONTRACT2_ADDR)
-        );
+        // No block has been finalised yet
+        let latency = suite.get_finality_latency(None);
+        assert_eq!(latency.count, 0);
+        assert_eq!(latency.blocks, LatencyStats::default());
 
-        // Call the end-block sudo handler(s), so that the block is indexed in the store
-        let res = suite
-            .call_end_block(&add_finality_signature.block_app_hash, initial_height + 1)
+        suite
+            .call_begin_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height)
             .unwrap();
-        assert_eq!(2, res.events.len());
-        assert_eq!(
-            res.events[0],
-            Event::new("sudo").add_attribute("_contract_address", CONTRACT2_ADDR)
-        );
-        assert_eq!(
-            res.events[1],
-            Event::new("wasm-index_block")
-                .add_attribute("_contract_address", CONTRACT2_ADDR)
-                .add_attribute("module", "finality")
-                .add_attribute("last_height", (initial_height + 1).to_string())
-        );
 
-        // Submit a finality signature from that finality provider at height initial_height + 1
-        let finality_sig = add_finality_signature.finality_sig.to_vec();
+        // The vote arrives in the next block, 5 seconds later
+        suite.app.update_block(|block| {
+            block.time = block.time.plus_seconds(5);
+        });
         suite
             .submit_finality_signature(
                 &pk_hex,
-                initial_height + 1,
+                submit_height,
                 &pub_rand_one,
                 &proof,
-                &add_finality_signature.block_app_hash,
-                &finality_sig,
+                &block_app_hash,
+                &add_finality_signature.finality_sig,
             )
             .unwrap();
+        suite
+            .call_begin_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        assert!(suite.get_indexed_block(submit_height).finalized);
 
-        // Query finality signature for that exact height
-        let sig = suite.get_finality_signature(&pk_hex, initial_height + 1);
+        let latency = suite.get_finality_latency(Some(10));
+        assert_eq!(latency.count, 1);
         assert_eq!(
-            sig,
-            FinalitySignatureResponse {
-                signature: finality_sig
+            latency.blocks,
+            LatencyStats {
+                min: 1,
+                avg: Decimal::one(),
+                max: 1,
+            }
+        );
+        assert_eq!(
+            latency.seconds,
+            LatencyStats {
+                min: 5,
+                avg: Decimal::from_atomics(5u128, 0).unwrap(),
+                max: 5,
             }
         );
     }
 
     #[test]
-    fn finality_round_works() {
-        // Read public randomness commitment test data
+    fn tally_status_works() {
         let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
         let pub_rand_one = get_pub_rand_value();
-        // Read equivalent / consistent add finality signature test data
         let add_finality_signature = get_add_finality_sig();
         let proof = add_finality_signature.proof.unwrap();
+        let block_app_hash = add_finality_signature.block_app_hash.clone();
 
         let initial_height = pub_rand.start_height;
+        let submit_height = initial_height + 1;
 
         let mut suite = SuiteBuilder::new().with_height(initial_height).build();
 
-        // signed by the 1st FP
+        // Nothing indexed yet
+        let status = suite.get_tally_status();
+        assert_eq!(status.latest_height, None);
+        assert_eq!(status.gap, 0);
+        assert_eq!(status.oldest_unfinalized, None);
+
         let new_fp = create_new_finality_provider(1);
-        assert_eq!(new_fp.btc_pk_hex, pk_hex);
+        suite
+            .register_finality_providers(std::slice::from_ref(&new_fp))
+            .unwrap();
+        suite
+            .add_delegations(&[get_derived_btc_delegation(1, &[1])])
+            .unwrap();
+        suite
+            .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
+            .unwrap();
+        suite
+            .call_begin_block(&block_app_hash, submit_height)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height)
+            .unwrap();
 
+        // The block is stalled, missing the vote of the only finality provider
+        let status = suite.get_tally_status();
+        assert_eq!(status.latest_height, Some(submit_height));
+        let oldest = status.oldest_unfinalized.unwrap();
+        assert_eq!(oldest.height, submit_height);
+        assert_eq!(oldest.voted_power, 0);
+        assert!(oldest.total_power > 0);
+        assert_eq!(oldest.missing.len(), 1);
+        assert_eq!(oldest.missing[0].btc_pk_hex, new_fp.btc_pk_hex);
+        assert_eq!(oldest.missing[0].power, oldest.total_power);
+        assert_eq!(oldest.native, None);
+
+        // The vote arrives, and the block is finalised in the next one
         suite
-            .register_finality_providers(&[new_fp.clone()])
+            .submit_finality_signature(
+                &pk_hex,
+                submit_height,
+                &pub_rand_one,
+                &proof,
+                &block_app_hash,
+                &add_finality_signature.finality_sig,
+            )
+            .unwrap();
+        suite
+            .call_begin_block(&block_app_hash, submit_height + 1)
+            .unwrap();
+        suite
+            .call_end_block(&block_app_hash, submit_height + 1)
             .unwrap();
 
-        // Add a delegation, so that the finality provider has some power
-        let mut del1 = get_derived_btc_delegation(1, &[1]);
-        del1.fp_btc_pk_list = vec![pk_hex.clone()];
+        let status = suite.get_tally_status();
+        assert_eq!(status.next_height, submit_height + 1);
+        assert_eq!(status.latest_height, Some(submit_height + 1));
+        assert_eq!(status.gap, 1);
+        assert_eq!(
+            status.oldest_unfinalized,
+            Some(UnfinalizedBlock {
+                height: submit_height + 1,
+                total_power: oldest.total_power,
+                voted_power: 0,
+                missing: oldest.missing,
+                native: None,
+            })
+        );
+    }
 
-        suite.add_delegations(&[del1.clone()]).unwrap();
+    #[test]
+    fn vote_proof_works() {
+        let (pk_hex, pub_rand, pubrand_signature) = get_public_randomness_commitment();
+        let pub_rand_one = get_pub_rand_value();
+        let add_finality_signature = get_add_finality_sig();
+        let proof = add_finality_signature.proof.unwrap();
+        let block_app_hash = add_finality_signature.block_app_hash.clone();
 
-        // Check that the finality provider power has been updated
-        let fp_info = suite.get_finality_provider_info(&new_fp.btc_pk_hex, None);
-        assert_eq!(fp_info.power, del1.total_sat);
+        let initial_height = pub_rand.start_height;
+        let submit_height = initial_height + 1;
 
-        // Submit public randomness commitment for the FP and the involved heights
+        let mut suite = SuiteBuilder::new().with_height(initial_height).build();
+
+        suite
+            .register_finality_providers(&[create_new_finality_provider(1)])
+            .unwrap();
+        suite
+            .add_delegations(&[get_derived_btc_delegation(1, &[1])])
+            .unwrap();
         suite
             .commit_public_randomness(&pk_hex, &pub_rand, &pubrand_signature)
             .unwrap();
-
-        // Call the begin-block sudo handler, for completeness
         suite
-            .call_begin_block(&add_finality_signature.block_app_hash, initial_height + 1)
+            .call_begin_block(&block_app_hash, submit_height)
             .unwrap();
-
-        // Call the end-block sudo handler, so that the block is indexed in the store
         suite
-            .call_end_block(&add_finality_signature.block_app_hash, initial_height + 1)
+            .call_end_block(&block_app_hash, submit_height)
             .unwrap();
 
-        // Submit a finality signature from that finality provider at height initial_height + 1
-        let submit_height = initial_height + 1;
-        let finality_sig = add_finality_signature.finality_sig.to_vec();
+        // No vote yet
+        assert_eq!(suite.get_vote_proof(submit_height, &pk_hex).proof, None);
+
         suite
             .submit_finality_signature(
                 &pk_hex,
                 submit_height,
                 &pub_rand_one,
                 &proof,
-                &add_finality_signature.block_app_hash,
-                &finality_sig,
+                &block_app_hash,
+                &add_finality_signature.finality_sig,
             )
             .unwrap();
-
-        // Call the begin blocker, to compute the active FP set
         suite
-            .call_begin_block(&add_finality_signature.block_app_hash, submit_height)
+            .call_begin_block(&block_app_hash, submit_height + 1)
             .unwrap();
-
-        // Call the end blocker, to process the finality signatures
-        let res = suite
-            .call_end_block(&add_finality_signature.block_app_hash, submit_height)
+        suite
+            .call_end_block(&block_app_hash, submit_height + 1)
             .unwrap();
-        assert_eq!(3, res.events.len());
-        assert_eq!(
-            res.events[0],
-            Event::new("sudo").add_attribute("_contract_address", CONTRACT2_ADDR)
-        );
-        assert_eq!(
-            res.events[1],
-            Event::new("wasm-index_block")
-                .add_attribute("_contract_address", CONTRACT2_ADDR)
-                .add_attribute("module", "finality")
-                .add_attribute("last_height", submit_height.to_string())
-        );
-        assert_eq!(
-            res.events[2],
-            Event::new("wasm-finalize_block")
-                .add_attribute("_contract_address", CONTRACT2_ADDR)
-                .add_attribute("module", "finality")
-                .add_attribute("finalized_height", submit_height.to_string())
-        );
 
-        // Assert the submitted block has been indexed and finalised
-        let indexed_block = suite.get_indexed_block(submit_height);
+        let vote = suite.get_vote_proof(submit_height, &pk_hex).proof.unwrap();
+        assert_eq!(vote.fp_btc_pk_hex, pk_hex);
         assert_eq!(
-            indexed_block,
+            vote.block,
             IndexedBlock {
                 height: submit_height,
-                app_hash: add_finality_signature.block_app_hash.to_vec(),
+                app_hash: block_app_hash.to_vec(),
                 finalized: true,
             }
         );
+        assert_eq!(vote.signature, add_finality_signature.finality_sig);
+        assert!(vote.power > 0);
+        assert_eq!(vote.power, vote.total_power);
+
+        // The proof verifies on its own
+        let pub_rand = vote.pub_rand.unwrap();
+        let msg_hash = Sha256::digest(&vote.signed_msg);
+        let pk = eots::PublicKey::from_hex(&vote.fp_btc_pk_hex).unwrap();
+        assert!(pk.verify(&pub_rand, &msg_hash, &vote.signature).unwrap());
     }
 }
 
 mod slashing {
-    use babylon_apis::finality_api::IndexedBlock;
+    use babylon_apis::finality_api::{
+        IndexedBlock, PubRandCommit, SigningContext, SIGNING_CONTEXT_V0,
+    };
+    use k256::elliptic_curve::ops::MulByGenerator;
+    use k256::elliptic_curve::point::AffineCoordinates;
+    use k256::schnorr::signature::Signer;
+    use k256::sha2::{Digest, Sha256};
+    use k256::ProjectivePoint;
     use test_utils::{
-        create_new_finality_provider, get_add_finality_sig, get_add_finality_sig_2,
-        get_derived_btc_delegation, get_pub_rand_value,
+        create_new_finality_provider, create_new_fp_sk, get_add_finality_sig,
+        get_add_finality_sig_2, get_derived_btc_delegation, get_fp_sk_bytes, get_pub_rand_value,
     };
 
     use crate::multitest::suite::SuiteBuilder;
@@ -414,4 +1393,132 @@ mod slashing {
         let fp = suite.get_finality_provider(&pk_hex);
         assert_eq!(fp.slashed_height, next_height);
     }
+
+    #[test]
+    fn pub_rand_reuse_slashing_works() {
+        let initial_height = 100;
+        let mut suite = SuiteBuilder::new().with_height(initial_height).build();
+
+        // Register one FP, with some power
+        let new_fp = create_new_finality_provider(1);
+        let pk_hex = new_fp.btc_pk_hex.clone();
+        suite
+            .register_finality_providers(std::slice::from_ref(&new_fp))
+            .unwrap();
+        let del1 = get_derived_btc_delegation(1, &[1]);
+        suite.add_delegations(&[del1]).unwrap();
+
+        // Commit to the same public randomness for two heights
+        let sec_rand = [7u8; 32];
+        let pub_rand = eots::PubRand::from(ProjectivePoint::mul_by_generator(
+            &*eots::SecRand::new(&sec_rand).unwrap(),
+        ))
+        .to_bytes();
+        let leaf_hash = babylon_merkle::hash::leaf_hash(&pub_rand);
+        let pr_commit = PubRandCommit {
+            start_height: initial_height + 1,
+            num_pub_rand: 2,
+            commitment: babylon_merkle::tree::hash_from_byte_slices(vec![
+                pub_rand.clone(),
+                pub_rand.clone(),
+            ]),
+        };
+        let fp_sk = create_new_fp_sk(1);
+        // The signing context is the legacy one, i.e. with no prefix
+        let ctx = SigningContext {
+            version: SIGNING_CONTEXT_V0,
+            chain_id: String::new(),
+            contract_address: String::new(),
+            consumer_id: String::new(),
+        };
+        let commit_msg = ctx.commit_pub_rand_msg(
+            pr_commit.start_height,
+            pr_commit.num_pub_rand,
+            &pr_commit.commitment,
+        );
+        let commit_sig: k256::schnorr::Signature = fp_sk.sign(&commit_msg);
+        suite
+            .commit_public_randomness(&pk_hex, &pr_commit, &commit_sig.to_bytes())
+            .unwrap();
+
+        // Index two blocks
+        let app_hashes = [b"deadbeef01".to_vec(), b"deadbeef02".to_vec()];
+        for (i, app_hash) in app_hashes.iter().enumerate() {
+            let height = pr_commit.start_height + i as u64;
+            suite.app.advance_blocks(1);
+            suite.call_begin_block(app_hash, height).unwrap();
+            suite.call_end_block(app_hash, height).unwrap();
+        }
+
+        // Vote for both blocks, with the same randomness.
+        // The BTC PK is x-only, so the secret key is negated if its point has an odd y-coordinate
+        let mut x = *k256::SecretKey::from_slice(&get_fp_sk_bytes(1))
+            .unwrap()
+            .to_nonzero_scalar();
+        if ProjectivePoint::mul_by_generator(&x)
+            .to_affine()
+            .y_is_odd()
+            .into()
+        {
+            x = -x;
+        }
+        let fp_sk_bytes = x.to_bytes().to_vec();
+        let eots_sk = eots::SecretKey::from_bytes(&fp_sk_bytes).unwrap();
+        let mut res = None;
+        for (i, app_hash) in app_hashes.iter().enumerate() {
+            let height = pr_commit.start_height + i as u64;
+            let msg_hash = Sha256::digest(ctx.finality_sig_msg(height, app_hash));
+            let sig = eots_sk.sign(&sec_rand, &msg_hash).unwrap().to_bytes();
+            let proof = tendermint_proto::crypto::Proof {
+                total: 2,
+                index: i as i64,
+                leaf_hash: leaf_hash.clone(),
+                aunts: vec![leaf_hash.clone()],
+            };
+            res = Some(
+                suite
+                    .submit_finality_signature(&pk_hex, height, &pub_rand, &proof, app_hash, &sig)
+                    .unwrap(),
+            );
+        }
+
+        // Assert the reuse evidence is proper
+        let reuse_height = pr_commit.start_height + 1;
+        let evidence = suite
+            .get_pub_rand_reuse_evidence(&pk_hex, reuse_height)
+            .evidence
+            .unwrap();
+        assert_eq!(evidence.fp_btc_pk, hex::decode(&pk_hex).unwrap());
+        assert_eq!(evidence.pub_rand, pub_rand);
+        assert_eq!(evidence.first_height, pr_commit.start_height);
+        assert_eq!(evidence.height, reuse_height);
+
+        // Assert the slashing event is there, with the extracted secret key
+        let res = res.unwrap();
+        let ev = res
+            .events
+            .iter()
+            .find(|ev| ev.ty == "wasm-slashed_finality_provider_pub_rand_reuse")
+            .unwrap();
+        let secret_key = ev
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "secret_key")
+            .unwrap();
+        assert_eq!(secret_key.value, hex::encode(fp_sk_bytes));
+
+        // Assert the finality provider has been slashed
+        let fp = suite.get_finality_provider(&pk_hex);
+        assert_eq!(fp.slashed_height, reuse_height + 1);
+    }
+}
+
+mod scenarios {
+    use super::scenario::run;
+    use test_utils::scenario::get_scenario;
+
+    #[test]
+    fn finalization_pipeline_scenario() {
+        run(&get_scenario("finalization_pipeline"));
+    }
 }
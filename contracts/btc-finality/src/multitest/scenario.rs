@@ -0,0 +1,111 @@
+//! Multi-test runner for the finalization scenarios of `test_utils::scenario`
+use anyhow::{bail, ensure};
+
+use babylon_contract::msg::btc_header::BtcHeader;
+use babylon_proto::babylon::finality::v1::MsgAddFinalitySig;
+use test_utils::scenario::{Action, Scenario};
+use test_utils::{
+    create_new_finality_provider, get_add_finality_sig, get_add_finality_sig_2, get_btc_lc_headers,
+    get_derived_btc_delegation, get_public_randomness_commitment,
+};
+
+use super::suite::{Suite, SuiteBuilder};
+
+/// run executes the steps of `scenario` in order against freshly instantiated contracts, and
+/// panics on the first step that does not behave as expected
+#[track_caller]
+pub fn run(scenario: &Scenario) -> Suite {
+    let mut suite = SuiteBuilder::new().with_height(scenario.height).build();
+
+    for (i, step) in scenario.steps.iter().enumerate() {
+        let ctx = format!("{}: step {} ({:?})", scenario.name, i, step.action);
+        match (execute(&mut suite, &step.action), &step.expect_error) {
+            (Ok(()), None) => {}
+            (Ok(()), Some(expected)) => panic!("{ctx}: expected error containing {expected:?}"),
+            (Err(err), None) => panic!("{ctx}: unexpected error: {err:#}"),
+            (Err(err), Some(expected)) => assert!(
+                format!("{err:#}").contains(expected.as_str()),
+                "{ctx}: unexpected error: {err:#}"
+            ),
+        }
+    }
+    suite
+}
+
+fn execute(suite: &mut Suite, action: &Action) -> anyhow::Result<()> {
+    match action {
+        Action::BtcHeaders { start, end } => {
+            let headers = get_btc_lc_headers()[*start..*end]
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<BtcHeader>, _>>()?;
+            suite.submit_btc_headers(&headers)?;
+        }
+        Action::FinalizeEpoch { epoch, height } => suite.insert_cz_header(*height, *epoch),
+        Action::RegisterFinalityProviders { fps } => {
+            let fps: Vec<_> = fps
+                .iter()
+                .map(|id| create_new_finality_provider(*id))
+                .collect();
+            suite.register_finality_providers(&fps)?;
+        }
+        Action::AddDelegation { del, fps } => {
+            suite.add_delegations(&[get_derived_btc_delegation(*del, fps)])?;
+        }
+        Action::CommitPublicRandomness {} => {
+            let (pk_hex, pub_rand, signature) = get_public_randomness_commitment();
+            suite.commit_public_randomness(&pk_hex, &pub_rand, &signature)?;
+        }
+        Action::Block { height } => {
+            let app_hash = get_add_finality_sig().block_app_hash;
+            suite.call_begin_block(&app_hash, *height)?;
+            suite.call_end_block(&app_hash, *height)?;
+        }
+        Action::SubmitFinalitySignature { sig } => {
+            let msg = finality_sig(*sig)?;
+            let Some(proof) = msg.proof else {
+                bail!("finality signature {sig} has no proof");
+            };
+            suite.submit_finality_signature(
+                &hex::encode(&msg.fp_btc_pk),
+                msg.block_height,
+                &msg.pub_rand,
+                &proof,
+                &msg.block_app_hash,
+                &msg.finality_sig,
+            )?;
+        }
+        Action::ExpectBtcTip { height } => {
+            let tip_height = suite.get_btc_tip_height();
+            ensure!(
+                tip_height == *height,
+                "BTC tip at height {tip_height}, expected {height}"
+            );
+        }
+        Action::ExpectPower { fp, power } => {
+            let pk_hex = create_new_finality_provider(*fp).btc_pk_hex;
+            let fp_power = suite.get_finality_provider_info(&pk_hex, None).power;
+            ensure!(
+                fp_power == *power,
+                "finality provider {fp} has power {fp_power}, expected {power}"
+            );
+        }
+        Action::ExpectBlock { height, finalized } => {
+            let block = suite.get_indexed_block(*height);
+            ensure!(
+                block.finalized == *finalized,
+                "block {height} finalized: {}, expected {finalized}",
+                block.finalized
+            );
+        }
+    }
+    Ok(())
+}
+
+fn finality_sig(index: i32) -> anyhow::Result<MsgAddFinalitySig> {
+    match index {
+        1 => Ok(get_add_finality_sig()),
+        2 => Ok(get_add_finality_sig_2()),
+        _ => bail!("unknown finality signature test data {index}"),
+    }
+}
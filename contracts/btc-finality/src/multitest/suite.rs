@@ -7,16 +7,28 @@ use cosmwasm_std::{to_json_binary, Addr};
 use cw_multi_test::{AppResponse, Contract, ContractWrapper, Executor};
 
 use babylon_apis::btc_staking_api::{ActiveBtcDelegation, FinalityProvider, NewFinalityProvider};
-use babylon_apis::finality_api::{IndexedBlock, PubRandCommit};
-use babylon_apis::{btc_staking_api, finality_api};
+use babylon_apis::finality_api::{IndexedBlock, PubRandCommit, PublicRandomnessCommit};
+use babylon_apis::{btc_staking_api, finality_api, sudo_api};
 use babylon_bindings::BabylonMsg;
 use babylon_bindings_test::BabylonApp;
 use babylon_bitcoin::chain_params::Network;
+use babylon_contract::state::cz_header_chain::CZ_HEADERS;
+use babylon_proto::babylon::zoneconcierge::v1::IndexedHeader;
+use prost::Message;
 
 use btc_staking::msg::{ActivatedHeightResponse, FinalityProviderInfo};
-
-use crate::msg::{EvidenceResponse, FinalitySignatureResponse};
+use btc_staking::state::staking::FpJail;
+
+use crate::msg::{
+    EvidenceResponse, FinalityLatencyResponse, FinalitySignatureResponse,
+    PubRandReuseEvidenceResponse, RandomnessCoverageResponse, RevealedRandomnessResponse,
+    RewardAccrualResponse, SigFailuresResponse, TallyStatusResponse, VoteInvalidationsResponse,
+    VoteProofResponse, VotingContextResponse,
+};
 use crate::multitest::{CONTRACT1_ADDR, CONTRACT2_ADDR};
+use crate::state::config::Params;
+use crate::state::finality::{NativeTally, VoteInvalidation};
+use crate::state::public_randomness::PubRandCommitTimestamp;
 
 fn contract_btc_staking() -> Box<dyn Contract<BabylonMsg>> {
     let contract = ContractWrapper::new(
@@ -52,6 +64,7 @@ fn contract_babylon() -> Box<dyn Contract<BabylonMsg>> {
 #[derivative(Default = "new")]
 pub struct SuiteBuilder {
     height: Option<u64>,
+    finality_params: Option<Params>,
 }
 
 impl SuiteBuilder {
@@ -60,6 +73,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_finality_params(mut self, params: Params) -> Self {
+        self.finality_params = Some(params);
+        self
+    }
+
     #[track_caller]
     pub fn build(self) -> Suite {
         let owner = Addr::unchecked("owner");
@@ -82,24 +100,32 @@ impl SuiteBuilder {
                 contract_code_id,
                 owner.clone(),
                 &babylon_contract::msg::contract::InstantiateMsg {
-                    network: Network::Testnet,
-                    babylon_tag: "01020304".to_string(),
-                    btc_confirmation_depth: 1,
-                    checkpoint_finalization_timeout: 10,
-                    notify_cosmos_zone: false,
                     btc_staking_code_id: Some(btc_staking_code_id),
                     btc_staking_msg: Some(
                         to_json_binary(&btc_staking::msg::InstantiateMsg {
                             params: Some(staking_params),
                             admin: None,
+                            genesis: None,
                         })
                         .unwrap(),
                     ),
                     btc_finality_code_id: Some(btc_finality_code_id),
-                    btc_finality_msg: None,
+                    btc_finality_msg: self.finality_params.map(|params| {
+                        to_json_binary(&crate::msg::InstantiateMsg {
+                            params: Some(params),
+                            admin: None,
+                        })
+                        .unwrap()
+                    }),
                     admin: Some(owner.to_string()),
                     consumer_name: Some("TestConsumer".to_string()),
                     consumer_description: Some("Test Consumer Description".to_string()),
+                    ..babylon_contract::msg::contract::InstantiateMsg::new(
+                        Network::Regtest,
+                        "01020304",
+                        1,
+                        10,
+                    )
                 },
                 &[],
                 "babylon",
@@ -152,6 +178,18 @@ impl Suite {
             .unwrap()
     }
 
+    #[track_caller]
+    pub fn get_btc_tip_height(&self) -> u64 {
+        self.app
+            .wrap()
+            .query_wasm_smart::<babylon_contract::msg::btc_header::BtcHeaderResponse>(
+                self.babylon.clone(),
+                &babylon_contract::msg::contract::QueryMsg::BtcTipHeader {},
+            )
+            .unwrap()
+            .height
+    }
+
     #[track_caller]
     pub fn get_btc_staking_config(&self) -> btc_staking::state::config::Config {
         self.app
@@ -186,7 +224,7 @@ impl Suite {
             .query_wasm_smart(
                 self.staking.clone(),
                 &btc_staking::msg::QueryMsg::FinalityProvider {
-                    btc_pk_hex: pk_hex.to_string(),
+                    btc_pk_hex: pk_hex.parse().unwrap(),
                 },
             )
             .unwrap()
@@ -203,7 +241,7 @@ impl Suite {
             .query_wasm_smart(
                 self.staking.clone(),
                 &btc_staking::msg::QueryMsg::FinalityProviderInfo {
-                    btc_pk_hex: pk_hex.to_string(),
+                    btc_pk_hex: pk_hex.parse().unwrap(),
                     height,
                 },
             )
@@ -224,6 +262,166 @@ impl Suite {
             .unwrap()
     }
 
+    #[track_caller]
+    pub fn get_vote_invalidations(&self) -> Vec<VoteInvalidation> {
+        let res: VoteInvalidationsResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::VoteInvalidations {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        res.invalidations
+    }
+
+    #[track_caller]
+    pub fn get_pub_rand_commit_timestamp(
+        &self,
+        pk_hex: &str,
+        start_height: u64,
+    ) -> Option<PubRandCommitTimestamp> {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::PubRandCommitTimestamp {
+                    btc_pk_hex: pk_hex.to_string(),
+                    start_height,
+                },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_voting_context(&self, pk_hex: &str, height: u64) -> VotingContextResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::VotingContext {
+                    btc_pk_hex: pk_hex.to_string(),
+                    height,
+                },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_randomness_coverage(&self, pk_hex: &str) -> RandomnessCoverageResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::RandomnessCoverage {
+                    btc_pk_hex: pk_hex.to_string(),
+                },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_finality_provider_jail(&self, pk_hex: &str) -> Option<FpJail> {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.staking.clone(),
+                &btc_staking::msg::QueryMsg::FinalityProviderJail {
+                    btc_pk_hex: pk_hex.parse().unwrap(),
+                },
+            )
+            .ok()
+    }
+
+    #[track_caller]
+    pub fn get_finality_latency(&self, last_n: Option<u32>) -> FinalityLatencyResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::FinalityLatency { last_n },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_vote_proof(&self, height: u64, pk_hex: &str) -> VoteProofResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::VoteProof {
+                    height,
+                    btc_pk_hex: pk_hex.to_string(),
+                },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_tally_status(&self) -> TallyStatusResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.finality.clone(), &crate::msg::QueryMsg::TallyStatus {})
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_reward_accrual(&self, pk_hex: &str) -> RewardAccrualResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::RewardAccrual {
+                    btc_pk_hex: pk_hex.to_string(),
+                },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_revealed_randomness(&self, pk_hex: &str, height: u64) -> Option<Vec<u8>> {
+        let res: RevealedRandomnessResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::RevealedRandomness {
+                    btc_pk_hex: pk_hex.to_string(),
+                    height,
+                },
+            )
+            .unwrap();
+        res.pub_rand
+    }
+
+    #[track_caller]
+    pub fn get_native_tally(&self, height: u64) -> Option<NativeTally> {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::NativeTally { height },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn get_sig_failures(&self, pk_hex: Option<&str>) -> SigFailuresResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::SigFailures {
+                    btc_pk_hex: pk_hex.map(String::from),
+                },
+            )
+            .unwrap()
+    }
+
     #[track_caller]
     pub fn get_indexed_block(&self, height: u64) -> IndexedBlock {
         self.app
@@ -249,6 +447,39 @@ impl Suite {
             .unwrap()
     }
 
+    #[track_caller]
+    pub fn get_pub_rand_reuse_evidence(
+        &self,
+        pk_hex: &str,
+        height: u64,
+    ) -> PubRandReuseEvidenceResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.finality.clone(),
+                &crate::msg::QueryMsg::PubRandReuseEvidence {
+                    btc_pk_hex: pk_hex.to_string(),
+                    height,
+                },
+            )
+            .unwrap()
+    }
+
+    #[track_caller]
+    pub fn submit_btc_headers(
+        &mut self,
+        headers: &[babylon_contract::msg::btc_header::BtcHeader],
+    ) -> anyhow::Result<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked("anyone"),
+            self.babylon.clone(),
+            &babylon_contract::msg::contract::ExecuteMsg::BtcHeaders {
+                headers: headers.to_vec(),
+            },
+            &[],
+        )
+    }
+
     #[track_caller]
     pub fn register_finality_providers(
         &mut self,
@@ -303,6 +534,21 @@ impl Suite {
         )
     }
 
+    #[track_caller]
+    pub fn commit_public_randomness_batch(
+        &mut self,
+        commits: &[PublicRandomnessCommit],
+    ) -> anyhow::Result<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked("anyone"),
+            self.finality.clone(),
+            &finality_api::ExecuteMsg::CommitPublicRandomnessBatch {
+                commits: commits.to_vec(),
+            },
+            &[],
+        )
+    }
+
     #[track_caller]
     pub fn call_begin_block(
         &mut self,
@@ -320,7 +566,7 @@ impl Suite {
 
         self.app.wasm_sudo(
             self.finality.clone(),
-            &finality_api::SudoMsg::BeginBlock {
+            &sudo_api::SudoMsg::BeginBlock {
                 hash_hex: hash_hex.clone(),
                 app_hash_hex: app_hash_hex.clone(),
             },
@@ -340,13 +586,58 @@ impl Suite {
 
         self.app.wasm_sudo(
             self.finality.clone(),
-            &finality_api::SudoMsg::EndBlock {
+            &sudo_api::SudoMsg::EndBlock {
                 hash_hex: hash_hex.clone(),
                 app_hash_hex: app_hash_hex.clone(),
             },
         )
     }
 
+    #[track_caller]
+    pub fn report_native_votes(
+        &mut self,
+        height: u64,
+        validators: &[finality_api::NativeValidatorVote],
+    ) -> anyhow::Result<AppResponse> {
+        self.app.wasm_sudo(
+            self.finality.clone(),
+            &finality_api::FinalitySudoMsg::NativeVotes {
+                height,
+                validators: validators.to_vec(),
+            },
+        )
+    }
+
+    #[track_caller]
+    pub fn invalidate_votes(
+        &mut self,
+        height: u64,
+        fp_pks: &[String],
+    ) -> anyhow::Result<AppResponse> {
+        self.app.wasm_sudo(
+            self.finality.clone(),
+            &finality_api::FinalitySudoMsg::InvalidateVotes {
+                height,
+                fp_pks: fp_pks.to_vec(),
+            },
+        )
+    }
+
+    /// insert_cz_header stores a CZ header in the babylon contract, as if it was BTC-finalised
+    /// through Babylon timestamping
+    #[track_caller]
+    pub fn insert_cz_header(&mut self, height: u64, babylon_epoch: u64) {
+        let cz_header = IndexedHeader {
+            height,
+            babylon_epoch,
+            ..Default::default()
+        };
+        let mut storage = self.app.contract_storage_mut(&self.babylon);
+        CZ_HEADERS
+            .save(storage.as_mut(), height, &cz_header.encode_to_vec())
+            .unwrap();
+    }
+
     #[track_caller]
     pub fn submit_finality_signature(
         &mut self,
@@ -374,6 +665,7 @@ impl Suite {
                 proof: proof.into(),
                 block_hash: block_hash.into(),
                 signature: finality_sig.into(),
+                payload_version: None,
             },
             &[],
         )
@@ -1,14 +1,31 @@
-use cosmwasm_std::Order::{Ascending, Descending};
-use cosmwasm_std::{Deps, StdResult};
+use cosmwasm_std::{Decimal, Deps, Env, Order, StdResult};
 use cw_storage_plus::Bound;
 
 use babylon_apis::finality_api::IndexedBlock;
+use babylon_apis::pagination::{page_limit, page_range};
 
+use btc_staking::msg::FinalityProviderInfo;
+
+use crate::contract::get_activated_height;
 use crate::error::ContractError;
-use crate::msg::{BlocksResponse, EvidenceResponse, FinalitySignatureResponse};
+use crate::finality::{signing_context, tally};
+use crate::msg::{
+    BlocksResponse, EvidenceResponse, FinalityLatencyResponse, FinalitySignatureResponse,
+    LatencyStats, PubRandReuseEvidenceResponse, RandomnessCoverageResponse, RewardAccrualResponse,
+    SigFailureCount, SigFailuresResponse, TallyStatusResponse, UnfinalizedBlock,
+    VoteInvalidationsResponse, VoteProof, VoteProofResponse, VotingContextResponse,
+};
 use crate::state::config::{Config, Params};
 use crate::state::config::{CONFIG, PARAMS};
-use crate::state::finality::{BLOCKS, EVIDENCES, SIGNATURES};
+use crate::state::finality::{
+    FinalityLatency, SigFailureReason, BLOCKS, EVIDENCES, FINALITY_LATENCIES, FP_SET,
+    NATIVE_TALLIES, NEXT_HEIGHT, PUB_RAND_REUSE_EVIDENCES, SIGNATURES, SIG_FAILURES,
+    SIG_FAILURE_TOTALS, VOTE_INVALIDATIONS,
+};
+use crate::state::public_randomness::{
+    get_last_pub_rand_commit, get_pub_rand_commit_for_height, get_pub_rand_value,
+};
+use crate::state::rewards::{REWARD_ACCRUALS, SIGNING_INFOS};
 
 pub fn config(deps: Deps) -> StdResult<Config> {
     CONFIG.load(deps.storage)
@@ -18,10 +35,6 @@ pub fn params(deps: Deps) -> StdResult<Params> {
     PARAMS.load(deps.storage)
 }
 
-// Settings for pagination
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
-
 pub fn finality_signature(
     deps: Deps,
     btc_pk_hex: String,
@@ -35,6 +48,197 @@ pub fn finality_signature(
     }
 }
 
+/// Everything a finality provider needs to know to vote at the given height
+pub fn voting_context(
+    deps: Deps,
+    btc_pk_hex: String,
+    height: u64,
+) -> Result<VotingContextResponse, ContractError> {
+    let staking_addr = CONFIG.load(deps.storage)?.staking;
+    let activated_height = get_activated_height(&staking_addr, &deps.querier)?;
+    let pub_rand_committed = match get_pub_rand_commit_for_height(deps.storage, &btc_pk_hex, height)
+    {
+        Ok(_) => true,
+        Err(ContractError::MissingPubRandCommit(..)) => false,
+        Err(e) => return Err(e),
+    };
+    // Unknown finality providers have no voting power
+    let power = deps
+        .querier
+        .query_wasm_smart::<FinalityProviderInfo>(
+            staking_addr,
+            &btc_staking::msg::QueryMsg::FinalityProviderInfo {
+                btc_pk_hex: btc_pk_hex.parse()?,
+                height: Some(height),
+            },
+        )
+        .map(|fp| fp.power)
+        .unwrap_or_default();
+    Ok(VotingContextResponse {
+        activated: activated_height > 0 && activated_height <= height,
+        pub_rand_committed,
+        voted: SIGNATURES.has(deps.storage, (height, &btc_pk_hex)),
+        power,
+        finalized: BLOCKS
+            .may_load(deps.storage, height)?
+            .is_some_and(|b| b.finalized),
+    })
+}
+
+/// Public randomness coverage of a finality provider over the upcoming blocks
+pub fn randomness_coverage(
+    deps: Deps,
+    env: &Env,
+    btc_pk_hex: &str,
+) -> Result<RandomnessCoverageResponse, ContractError> {
+    let alert_blocks = PARAMS.load(deps.storage)?.pub_rand_coverage_alert;
+    let covered_height =
+        get_last_pub_rand_commit(deps.storage, btc_pk_hex)?.map(|commit| commit.end_height());
+    // The finality provider can vote up to `covered_height` included
+    let running_out =
+        covered_height.is_none_or(|h| h < env.block.height.saturating_add(alert_blocks));
+    Ok(RandomnessCoverageResponse {
+        covered_height,
+        running_out,
+        alert_blocks,
+    })
+}
+
+/// Default number of last finalised blocks the finality latency statistics are computed over
+const DEFAULT_LATENCY_WINDOW: u32 = 100;
+/// Maximum number of last finalised blocks the finality latency statistics are computed over
+const MAX_LATENCY_WINDOW: u32 = 1000;
+
+/// Finality latency statistics over the last finalised blocks.
+/// `last_n`: The number of last finalised blocks, if provided.
+pub fn finality_latency(deps: Deps, last_n: Option<u32>) -> StdResult<FinalityLatencyResponse> {
+    let last_n = last_n
+        .unwrap_or(DEFAULT_LATENCY_WINDOW)
+        .min(MAX_LATENCY_WINDOW) as usize;
+    let latencies = FINALITY_LATENCIES
+        .range(deps.storage, None, None, Order::Descending)
+        .take(last_n)
+        .map(|item| item.map(|(_, latency)| latency))
+        .collect::<StdResult<Vec<_>>>()?;
+    let stats = |value: fn(&FinalityLatency) -> u64| {
+        let values = latencies.iter().map(value);
+        let (Some(min), Some(max)) = (values.clone().min(), values.clone().max()) else {
+            return LatencyStats::default();
+        };
+        let sum: u128 = values.map(u128::from).sum();
+        LatencyStats {
+            min,
+            avg: Decimal::from_ratio(sum, latencies.len() as u128),
+            max,
+        }
+    };
+    Ok(FinalityLatencyResponse {
+        count: latencies.len() as u32,
+        blocks: stats(|latency| latency.blocks),
+        seconds: stats(|latency| latency.seconds),
+    })
+}
+
+/// Reward accrual of a finality provider.
+/// Its signing info over a period completed since it was last active is folded into the accrual
+pub fn reward_accrual(deps: Deps, btc_pk_hex: &str) -> StdResult<RewardAccrualResponse> {
+    let period_len = PARAMS.load(deps.storage)?.reward_accrual_period;
+    let mut accrual = REWARD_ACCRUALS
+        .may_load(deps.storage, btc_pk_hex)?
+        .unwrap_or_default();
+    let mut current = SIGNING_INFOS.may_load(deps.storage, btc_pk_hex)?;
+    if let Some(info) = &current {
+        // The period of the next block to finalise
+        let next_height = NEXT_HEIGHT.may_load(deps.storage)?.unwrap_or(0);
+        if period_len > 0 && info.period < next_height / period_len {
+            accrual.fold(info);
+            current = None;
+        }
+    }
+    let participation = current
+        .as_ref()
+        .map(|info| info.participation())
+        .unwrap_or_default();
+    Ok(RewardAccrualResponse {
+        accrual,
+        current,
+        participation,
+    })
+}
+
+/// Progress of the tallying of the blocks.
+/// The oldest non-finalised block is the first indexed block from the next height to be finalised
+/// on that has a finality provider set, as blocks without one are skipped by the tallying
+pub fn tally_status(deps: Deps) -> StdResult<TallyStatusResponse> {
+    let next_height = NEXT_HEIGHT.may_load(deps.storage)?.unwrap_or(0);
+    let latest_height = BLOCKS
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    let gap = latest_height.map_or(0, |h| (h + 1).saturating_sub(next_height));
+
+    let mut oldest_unfinalized = None;
+    for block in BLOCKS.range(
+        deps.storage,
+        Some(Bound::inclusive(next_height)),
+        None,
+        Order::Ascending,
+    ) {
+        let (height, block) = block?;
+        if block.finalized {
+            continue;
+        }
+        let Some(fp_set) = FP_SET.may_load(deps.storage, height)? else {
+            continue;
+        };
+        let voters = SIGNATURES
+            .prefix(height)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let (voted_power, total_power) = tally(&fp_set, &voters)?;
+        let missing = fp_set
+            .into_iter()
+            .filter(|fp| !voters.contains(&fp.btc_pk_hex))
+            .collect();
+        oldest_unfinalized = Some(UnfinalizedBlock {
+            height,
+            total_power,
+            voted_power,
+            missing,
+            native: NATIVE_TALLIES.may_load(deps.storage, height)?,
+        });
+        break;
+    }
+
+    Ok(TallyStatusResponse {
+        next_height,
+        latest_height,
+        gap,
+        oldest_unfinalized,
+    })
+}
+
+/// Finality signature verification failure counts, by reason.
+/// `btc_pk_hex`: The BTC public key of the finality provider, or `None` for the totals
+pub fn sig_failures(deps: Deps, btc_pk_hex: Option<String>) -> StdResult<SigFailuresResponse> {
+    let failures = SigFailureReason::ALL
+        .into_iter()
+        .map(|reason| {
+            let count = match &btc_pk_hex {
+                Some(btc_pk_hex) => {
+                    SIG_FAILURES.may_load(deps.storage, (reason.as_str(), btc_pk_hex))?
+                }
+                None => SIG_FAILURE_TOTALS.may_load(deps.storage, reason.as_str())?,
+            };
+            Ok(SigFailureCount {
+                reason,
+                count: count.unwrap_or_default(),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(SigFailuresResponse { failures })
+}
+
 pub fn block(deps: Deps, height: u64) -> StdResult<IndexedBlock> {
     BLOCKS.load(deps.storage, height)
 }
@@ -51,13 +255,8 @@ pub fn blocks(
     reverse: Option<bool>,
 ) -> Result<BlocksResponse, ContractError> {
     let finalised = finalised.unwrap_or_default();
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start_after = start_after.map(Bound::exclusive);
-    let (start, end, order) = if reverse.unwrap_or(false) {
-        (None, start_after, Descending)
-    } else {
-        (start_after, None, Ascending)
-    };
+    let limit = page_limit(limit);
+    let (start, end, order) = page_range(start_after, reverse);
     let blocks = BLOCKS
         .range_raw(deps.storage, start, end, order)
         .filter(|item| {
@@ -77,3 +276,63 @@ pub fn evidence(deps: Deps, btc_pk_hex: String, height: u64) -> StdResult<Eviden
     let evidence = EVIDENCES.may_load(deps.storage, (&btc_pk_hex, height))?;
     Ok(EvidenceResponse { evidence })
 }
+
+/// Proof of the vote of a finality provider for the block at the given height
+pub fn vote_proof(
+    deps: Deps,
+    env: &Env,
+    height: u64,
+    btc_pk_hex: String,
+) -> Result<VoteProofResponse, ContractError> {
+    let Some(signature) = SIGNATURES.may_load(deps.storage, (height, &btc_pk_hex))? else {
+        return Ok(VoteProofResponse { proof: None });
+    };
+    let Some(block) = BLOCKS.may_load(deps.storage, height)? else {
+        return Ok(VoteProofResponse { proof: None });
+    };
+    let fp_set = FP_SET.may_load(deps.storage, height)?.unwrap_or_default();
+    let power = fp_set
+        .iter()
+        .find(|fp| fp.btc_pk_hex == btc_pk_hex)
+        .map_or(0, |fp| fp.power);
+    let total_power = fp_set.iter().map(|fp| fp.power).sum();
+    let signed_msg = signing_context(deps.storage, env)?.finality_sig_msg(height, &block.app_hash);
+    let pub_rand = get_pub_rand_value(deps.storage, &btc_pk_hex, height)?;
+    Ok(VoteProofResponse {
+        proof: Some(VoteProof {
+            fp_btc_pk_hex: btc_pk_hex,
+            block,
+            signature,
+            pub_rand,
+            signed_msg,
+            power,
+            total_power,
+        }),
+    })
+}
+
+pub fn pub_rand_reuse_evidence(
+    deps: Deps,
+    btc_pk_hex: String,
+    height: u64,
+) -> StdResult<PubRandReuseEvidenceResponse> {
+    let evidence = PUB_RAND_REUSE_EVIDENCES.may_load(deps.storage, (&btc_pk_hex, height))?;
+    Ok(PubRandReuseEvidenceResponse { evidence })
+}
+
+/// Get the vote invalidation audit records, in order.
+/// `start_after`: The id of the record to start after, if any.
+pub fn vote_invalidations(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<VoteInvalidationsResponse> {
+    let limit = page_limit(limit);
+    let (start, end, order) = page_range(start_after, None);
+    let invalidations = VOTE_INVALIDATIONS
+        .range(deps.storage, start, end, order)
+        .take(limit)
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<_>>()?;
+    Ok(VoteInvalidationsResponse { invalidations })
+}
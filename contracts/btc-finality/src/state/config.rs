@@ -1,7 +1,7 @@
 use derivative::Derivative;
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Decimal};
 
 use cw_controllers::Admin;
 use cw_storage_plus::Item;
@@ -32,4 +32,106 @@ pub struct Params {
     /// should commit
     #[derivative(Default(value = "1"))]
     pub min_pub_rand: u64,
+    /// `signing_context_version` is the version of the signing context finality providers bind
+    /// their signatures to (see `babylon_apis::finality_api::SigningContext`).
+    /// Defaults to the legacy version 0, i.e. no domain separation
+    #[serde(default)]
+    pub signing_context_version: u32,
+    /// `record_sig_failures` is whether finality signatures failing verification are recorded in
+    /// the failure metrics instead of being rejected with an error.
+    /// A failed signature is still not accepted, but the tx succeeds so that the metrics persist
+    #[serde(default)]
+    pub record_sig_failures: bool,
+    /// `pub_rand_retention` is the number of blocks the revealed public randomness values are kept
+    /// for, per finality provider. Zero keeps them forever
+    #[serde(default)]
+    pub pub_rand_retention: u64,
+    /// `native_quorum` is the optional second quorum, of the native-staked validators of the
+    /// consumer chain (reported through `SudoMsg::NativeVotes`). If set, a block is finalised
+    /// only when both the BTC finality provider and the native validator quorums are reached.
+    /// If unset, a block is finalised upon more than 2/3 of the BTC voting power
+    #[serde(default)]
+    pub native_quorum: Option<DualQuorum>,
+    /// `strict_pub_rand_timestamping` is whether finality signatures are only accepted if their
+    /// public randomness commitment has been timestamped in a BTC-finalised Babylon epoch, i.e.
+    /// the babylon contract holds a (BTC-finalised) CZ header at or above the height the
+    /// commitment was made at
+    #[serde(default)]
+    pub strict_pub_rand_timestamping: bool,
+    /// `pub_rand_coverage_alert` is the number of upcoming blocks the public randomness of a
+    /// finality provider should cover. `RandomnessCoverage` flags the finality providers whose
+    /// committed public randomness runs out within that many blocks
+    #[derivative(Default(value = "DEFAULT_PUB_RAND_COVERAGE_ALERT"))]
+    #[serde(default = "default_pub_rand_coverage_alert")]
+    pub pub_rand_coverage_alert: u64,
+    /// `reward_accrual_period` is the number of blocks of a reward accrual period. The reward
+    /// accrual of each active finality provider is its voting power, weighted by the fraction of
+    /// the finalised blocks of the period it actually signed. Zero disables the accrual
+    #[serde(default)]
+    pub reward_accrual_period: u64,
+    /// `max_missed_blocks` is the number of consecutive finalised blocks a finality provider of
+    /// the active set can miss voting on before it is jailed in the staking contract. Zero
+    /// disables jailing
+    #[serde(default)]
+    pub max_missed_blocks: u64,
+    /// `tally_start_height` is the height the tallying of the blocks starts from, for deployments
+    /// joining an already-running consumer chain, so that historical heights that will never be
+    /// finalised are not tallied. Zero tallies from the BTC staking activation height
+    #[serde(default)]
+    pub tally_start_height: u64,
+    /// `zero_power_votes` is how finality signatures of finality providers without voting power
+    /// at the voted height are handled. Defaults to rejecting them
+    #[serde(default)]
+    pub zero_power_votes: ZeroPowerVotes,
+    /// `min_vote_payload_version` is the minimum vote payload version finality signature
+    /// submissions must declare (see `SubmitFinalitySignature::payload_version`). Defaults to 0,
+    /// i.e. accepting the daemons predating the version handshake
+    #[serde(default)]
+    pub min_vote_payload_version: u32,
+}
+
+const DEFAULT_PUB_RAND_COVERAGE_ALERT: u64 = 1000;
+
+fn default_pub_rand_coverage_alert() -> u64 {
+    DEFAULT_PUB_RAND_COVERAGE_ALERT
+}
+
+/// ZeroPowerVotes is how the finality signatures of finality providers without voting power at
+/// the voted height are handled
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum ZeroPowerVotes {
+    /// `Reject` rejects them with `ContractError::NoVotingPower`
+    #[default]
+    Reject,
+    /// `Record` verifies and stores them like any other vote, but they are not counted in the
+    /// tally, as the finality provider is not in the active set at that height. Their
+    /// equivocations are still slashable
+    Record,
+}
+
+/// DualQuorum are the weights of the BTC and native stake quorums
+#[cw_serde]
+pub struct DualQuorum {
+    /// `btc_threshold` is the share of the BTC voting power that has to be exceeded for a block
+    /// to be finalised
+    pub btc_threshold: Decimal,
+    /// `native_threshold` is the share of the native voting power that has to be exceeded for a
+    /// block to be finalised
+    pub native_threshold: Decimal,
+}
+
+impl DualQuorum {
+    /// validate checks both thresholds are a share in [0, 1), so that a quorum can be reached
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, threshold) in [
+            ("btc_threshold", self.btc_threshold),
+            ("native_threshold", self.native_threshold),
+        ] {
+            if threshold >= Decimal::one() {
+                return Err(format!("{name} must be lower than 1, got {threshold}"));
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,3 +1,5 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Timestamp;
 use cw_storage_plus::{Item, Map};
 
 use babylon_apis::finality_api::{Evidence, IndexedBlock};
@@ -9,15 +11,138 @@ pub const SIGNATURES: Map<(u64, &str), Vec<u8>> = Map::new("fp_sigs");
 /// Map of blocks information by height
 pub const BLOCKS: Map<u64, IndexedBlock> = Map::new("blocks");
 
+/// Map of block times by height, recorded upon indexing
+pub const BLOCK_TIMES: Map<u64, Timestamp> = Map::new("block_times");
+
+/// `FinalityLatency` is how long it took for a block to be finalised
+#[cw_serde]
+pub struct FinalityLatency {
+    /// `blocks` is the number of blocks between the block and the one it was finalised at
+    pub blocks: u64,
+    /// `seconds` is the time elapsed between the block and the one it was finalised at
+    pub seconds: u64,
+}
+
+/// Map of finality latencies by block height
+pub const FINALITY_LATENCIES: Map<u64, FinalityLatency> = Map::new("finality_latencies");
+
 /// Next height to finalise
 pub const NEXT_HEIGHT: Item<u64> = Item::new("next_height");
 
 /// `FP_SET` is the calculated list of the active finality providers by height
 pub const FP_SET: Map<u64, Vec<FinalityProviderInfo>> = Map::new("fp_set");
 
+/// Map of the consecutive finalised blocks each active FP missed voting on, by FP.
+/// FPs that voted on the last finalised block they were active in have no entry
+pub const MISSED_BLOCKS: Map<&str, u64> = Map::new("missed_blocks");
+
 /// `TOTAL_POWER` is the total power of all finality providers
 // FIXME: Store by height? Remove? Not currently being used in the contract
 pub const TOTAL_POWER: Item<u64> = Item::new("total_power");
 
+/// `NativeTally` is the voting power of the native-staked validators of the consumer chain at a
+/// height, as reported through `SudoMsg::NativeVotes`
+#[cw_serde]
+#[derive(Default)]
+pub struct NativeTally {
+    pub total_power: u64,
+    pub voted_power: u64,
+}
+
+/// Map of native validator tallies by block height
+pub const NATIVE_TALLIES: Map<u64, NativeTally> = Map::new("native_tallies");
+
 /// Map of double signing evidence by FP and block height
 pub const EVIDENCES: Map<(&str, u64), Evidence> = Map::new("evidences");
+
+/// `PubRandReuseEvidence` is the evidence that a finality provider revealed the same public
+/// randomness at two different heights, which leaks its secret key
+#[cw_serde]
+pub struct PubRandReuseEvidence {
+    /// `fp_btc_pk` is the BTC PK of the finality provider that reused the randomness
+    pub fp_btc_pk: Vec<u8>,
+    /// `pub_rand` is the reused public randomness
+    pub pub_rand: Vec<u8>,
+    /// `first_height` is the height the randomness was first revealed at
+    pub first_height: u64,
+    /// `first_app_hash` is the AppHash of the block voted for at `first_height`
+    pub first_app_hash: Vec<u8>,
+    /// `first_finality_sig` is the finality signature at `first_height`
+    pub first_finality_sig: Vec<u8>,
+    /// `height` is the height the randomness was revealed again at
+    pub height: u64,
+    /// `app_hash` is the AppHash of the block voted for at `height`
+    pub app_hash: Vec<u8>,
+    /// `finality_sig` is the finality signature at `height`
+    pub finality_sig: Vec<u8>,
+}
+
+/// Map of public randomness reuse evidence by FP and block height (of the reuse)
+pub const PUB_RAND_REUSE_EVIDENCES: Map<(&str, u64), PubRandReuseEvidence> =
+    Map::new("pub_rand_reuse_evidences");
+
+/// Reason a finality signature failed verification, for the failure metrics
+#[cw_serde]
+#[derive(Copy)]
+pub enum SigFailureReason {
+    /// The public randomness inclusion proof is invalid
+    BadProof,
+    /// The EOTS signature does not verify against the public randomness
+    RandomnessMismatch,
+    /// The finality provider is unknown, or has no voting power at the height
+    UnknownFp,
+    /// The height is not indexed yet, or not covered by a public randomness commitment
+    HeightOutOfRange,
+}
+
+impl SigFailureReason {
+    pub const ALL: [SigFailureReason; 4] = [
+        SigFailureReason::BadProof,
+        SigFailureReason::RandomnessMismatch,
+        SigFailureReason::UnknownFp,
+        SigFailureReason::HeightOutOfRange,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SigFailureReason::BadProof => "bad_proof",
+            SigFailureReason::RandomnessMismatch => "randomness_mismatch",
+            SigFailureReason::UnknownFp => "unknown_fp",
+            SigFailureReason::HeightOutOfRange => "height_out_of_range",
+        }
+    }
+}
+
+/// Map of finality signature verification failure counts by reason and FP
+pub const SIG_FAILURES: Map<(&str, &str), u64> = Map::new("sig_failures");
+
+/// Map of total finality signature verification failure counts by reason
+pub const SIG_FAILURE_TOTALS: Map<&str, u64> = Map::new("sig_failure_totals");
+
+/// `VoteInvalidation` is the audit record of a governance intervention striking finality votes,
+/// through `SudoMsg::InvalidateVotes`
+#[cw_serde]
+pub struct VoteInvalidation {
+    /// `id` is the sequence number of the intervention
+    pub id: u64,
+    /// `height` is the height of the struck votes
+    pub height: u64,
+    /// `fp_pks` are the BTC PKs (hex) of the finality providers whose votes were struck
+    pub fp_pks: Vec<String>,
+    /// `invalidated_at` is the block height of the intervention
+    pub invalidated_at: u64,
+    /// `voted_power` is the voted power of the block at `height`, without the struck votes
+    pub voted_power: u64,
+    /// `total_power` is the total power of the finality providers of the block at `height`
+    pub total_power: u64,
+    /// `last_unfinalized_height` is set if the block at `height` was finalized and does not reach
+    /// the quorum without the struck votes. The blocks from `height` up to it lost their finality,
+    /// and are tallied again
+    pub last_unfinalized_height: Option<u64>,
+}
+
+/// Map of vote invalidation records by id. Records are never updated nor removed
+pub const VOTE_INVALIDATIONS: Map<u64, VoteInvalidation> = Map::new("vote_invalidations");
+
+/// Map of invalidated votes by block height and FP, to the id of the invalidation record
+pub const INVALIDATED_VOTES: Map<(u64, &str), u64> = Map::new("invalidated_votes");
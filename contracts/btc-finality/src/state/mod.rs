@@ -1,3 +1,5 @@
 pub mod config;
 pub mod finality;
 pub mod public_randomness;
+pub mod rewards;
+pub mod state_hash;
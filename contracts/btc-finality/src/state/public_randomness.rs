@@ -1,14 +1,86 @@
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Order::{Ascending, Descending};
 use cosmwasm_std::{StdResult, Storage};
 use cw_storage_plus::{Bound, Map};
 
 use crate::error::ContractError;
 use babylon_apis::finality_api::PubRandCommit;
+use babylon_apis::pagination::{page_limit, page_range};
 
 /// Map of public randomness commitments by fp and block height
 pub const PUB_RAND_COMMITS: Map<(&str, u64), PubRandCommit> = Map::new("fp_pub_rand_commit");
+/// `PubRandCommitTimestamp` is the block height a public randomness commitment was made at, along
+/// with the Babylon epoch that timestamped it on BTC, once known
+#[cw_serde]
+pub struct PubRandCommitTimestamp {
+    pub committed_at: u64,
+    pub babylon_epoch: Option<u64>,
+}
+
+/// Map of public randomness commitment timestamps by fp and (start) block height
+pub const PUB_RAND_COMMIT_TIMESTAMPS: Map<(&str, u64), PubRandCommitTimestamp> =
+    Map::new("fp_pub_rand_commit_timestamps");
 /// Map of public randomness values by fp and block height
 pub const PUB_RAND_VALUES: Map<(&str, u64), Vec<u8>> = Map::new("fp_pub_rand");
+/// Map of the first block height each public randomness value was revealed at, by fp and value
+pub const PUB_RAND_HEIGHTS: Map<(&str, &[u8]), u64> = Map::new("fp_pub_rand_heights");
+
+/// Maximum number of expired public randomness values pruned per saved value
+const PUB_RAND_PRUNE_LIMIT: usize = 10;
+
+/// `save_pub_rand_value` archives the public randomness value revealed by a finality provider at
+/// the given height, and prunes (a bounded number of) its values older than `retention` blocks.
+/// A `retention` of zero keeps all the values.
+///
+/// Returns the (archived) height at which the finality provider revealed the same value before,
+/// if any. Reusing public randomness across heights leaks the finality provider's secret key
+pub fn save_pub_rand_value(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    height: u64,
+    pub_rand: &[u8],
+    retention: u64,
+) -> StdResult<Option<u64>> {
+    PUB_RAND_VALUES.save(storage, (fp_btc_pk_hex, height), &pub_rand.to_vec())?;
+    let reused_at = PUB_RAND_HEIGHTS
+        .may_load(storage, (fp_btc_pk_hex, pub_rand))?
+        .filter(|&prev_height| prev_height != height);
+    if reused_at.is_none() {
+        PUB_RAND_HEIGHTS.save(storage, (fp_btc_pk_hex, pub_rand), &height)?;
+    }
+
+    if retention == 0 || height <= retention {
+        return Ok(reused_at);
+    }
+    let expired = PUB_RAND_VALUES
+        .prefix(fp_btc_pk_hex)
+        .range(
+            storage,
+            None,
+            Some(Bound::exclusive(height - retention)),
+            Ascending,
+        )
+        .take(PUB_RAND_PRUNE_LIMIT)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (expired_height, expired_pub_rand) in expired {
+        PUB_RAND_VALUES.remove(storage, (fp_btc_pk_hex, expired_height));
+        let key = (fp_btc_pk_hex, expired_pub_rand.as_slice());
+        if PUB_RAND_HEIGHTS.may_load(storage, key)? == Some(expired_height) {
+            PUB_RAND_HEIGHTS.remove(storage, key);
+        }
+    }
+    Ok(reused_at)
+}
+
+/// `get_pub_rand_value` returns the public randomness value revealed by a finality provider at
+/// the given height, if it is still archived
+pub fn get_pub_rand_value(
+    storage: &dyn Storage,
+    fp_btc_pk_hex: &str,
+    height: u64,
+) -> StdResult<Option<Vec<u8>>> {
+    PUB_RAND_VALUES.may_load(storage, (fp_btc_pk_hex, height))
+}
 
 pub fn get_pub_rand_commit_for_height(
     storage: &dyn Storage,
@@ -57,10 +129,6 @@ pub fn get_last_pub_rand_commit(
     Ok(res.into_iter().next())
 }
 
-// Settings for pagination
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
-
 pub fn get_pub_rand_commit(
     storage: &dyn Storage,
     fp_btc_pk_hex: &str,
@@ -68,13 +136,8 @@ pub fn get_pub_rand_commit(
     limit: Option<u32>,
     reverse: Option<bool>,
 ) -> Result<Vec<PubRandCommit>, ContractError> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start_after = start_after.map(Bound::exclusive);
-    let (start, end, order) = if reverse.unwrap_or(false) {
-        (None, start_after, Descending)
-    } else {
-        (start_after, None, Ascending)
-    };
+    let limit = page_limit(limit);
+    let (start, end, order) = page_range(start_after, reverse);
     let res = PUB_RAND_COMMITS
         .prefix(fp_btc_pk_hex)
         .range_raw(storage, start, end, order)
@@ -88,3 +151,84 @@ pub fn get_pub_rand_commit(
     // Return the results or an empty vector if no results found
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+
+    #[test]
+    fn pub_rand_values_retention_works() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+
+        for height in 1..=5 {
+            save_pub_rand_value(storage, "fp1", height, &[height as u8], 2).unwrap();
+        }
+        save_pub_rand_value(storage, "fp2", 1, &[1], 2).unwrap();
+
+        // Only the values of the last `retention` blocks (and the current one) are kept
+        assert_eq!(get_pub_rand_value(storage, "fp1", 2).unwrap(), None);
+        assert_eq!(
+            get_pub_rand_value(storage, "fp1", 3).unwrap(),
+            Some(vec![3])
+        );
+        assert_eq!(
+            get_pub_rand_value(storage, "fp1", 5).unwrap(),
+            Some(vec![5])
+        );
+        // Other FPs are not affected
+        assert_eq!(
+            get_pub_rand_value(storage, "fp2", 1).unwrap(),
+            Some(vec![1])
+        );
+
+        // Zero retention keeps all the values
+        save_pub_rand_value(storage, "fp2", 100, &[100], 0).unwrap();
+        assert_eq!(
+            get_pub_rand_value(storage, "fp2", 1).unwrap(),
+            Some(vec![1])
+        );
+    }
+
+    #[test]
+    fn pub_rand_reuse_is_detected() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+
+        assert_eq!(
+            save_pub_rand_value(storage, "fp1", 1, &[1], 2).unwrap(),
+            None
+        );
+        // Revealing the same value at the same height again is not a reuse
+        assert_eq!(
+            save_pub_rand_value(storage, "fp1", 1, &[1], 2).unwrap(),
+            None
+        );
+        // Other FPs revealing the same value is not a reuse either
+        assert_eq!(
+            save_pub_rand_value(storage, "fp2", 2, &[1], 2).unwrap(),
+            None
+        );
+
+        // Revealing the same value at another height is
+        assert_eq!(
+            save_pub_rand_value(storage, "fp1", 2, &[1], 2).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            save_pub_rand_value(storage, "fp1", 3, &[1], 2).unwrap(),
+            Some(1)
+        );
+
+        // Values are forgotten once pruned
+        assert_eq!(
+            save_pub_rand_value(storage, "fp1", 5, &[5], 2).unwrap(),
+            None
+        );
+        assert_eq!(
+            save_pub_rand_value(storage, "fp1", 6, &[1], 2).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, StdResult, Storage, Uint128};
+use cw_storage_plus::Map;
+
+use btc_staking::msg::FinalityProviderInfo;
+
+/// Signing info of the finality providers over the current accrual period, by BTC public key
+pub const SIGNING_INFOS: Map<&str, SigningInfo> = Map::new("signing_infos");
+/// Reward accrual of the finality providers over their completed accrual periods, by BTC public key
+pub const REWARD_ACCRUALS: Map<&str, RewardAccrual> = Map::new("reward_accruals");
+
+/// `SigningInfo` is the participation of a finality provider in the blocks finalised during an
+/// accrual period, i.e. its signing-info window
+#[cw_serde]
+#[derive(Default)]
+pub struct SigningInfo {
+    /// `period` is the accrual period, i.e. the height of the finalised blocks divided by
+    /// `Params::reward_accrual_period`
+    pub period: u64,
+    /// `active_blocks` is the number of blocks finalised in the period with the finality provider
+    /// in the active set
+    pub active_blocks: u64,
+    /// `signed_blocks` is the number of those blocks the finality provider signed
+    pub signed_blocks: u64,
+    /// `nominal_power` is the voting power of the finality provider, summed over the active blocks
+    pub nominal_power: Uint128,
+    /// `last_height` is the height of the last finalised block accounted for, so that blocks
+    /// finalised again after a vote invalidation are not accounted twice
+    pub last_height: u64,
+}
+
+impl SigningInfo {
+    /// `participation` is the fraction of the active blocks the finality provider signed
+    pub fn participation(&self) -> Decimal {
+        if self.active_blocks == 0 {
+            return Decimal::zero();
+        }
+        Decimal::from_ratio(self.signed_blocks, self.active_blocks)
+    }
+
+    /// `accrual` is the nominal power of the period weighted by the participation
+    pub fn accrual(&self) -> Uint128 {
+        if self.active_blocks == 0 {
+            return Uint128::zero();
+        }
+        self.nominal_power
+            .multiply_ratio(self.signed_blocks, self.active_blocks)
+    }
+}
+
+/// `RewardAccrual` is the reward accrual of a finality provider over its completed accrual periods
+#[cw_serde]
+#[derive(Default)]
+pub struct RewardAccrual {
+    /// `accrued` is the nominal power of each period, weighted by the participation of the
+    /// finality provider in that period
+    pub accrued: Uint128,
+    /// `nominal` is what would have been accrued by nominal power only
+    pub nominal: Uint128,
+    /// `periods` is the number of completed accrual periods
+    pub periods: u64,
+}
+
+impl RewardAccrual {
+    /// `fold` accounts for the signing info of a completed period
+    pub fn fold(&mut self, info: &SigningInfo) {
+        self.accrued += info.accrual();
+        self.nominal += info.nominal_power;
+        self.periods += 1;
+    }
+}
+
+/// `accrue` accounts for the block finalised at `height` in the signing info of the active
+/// finality providers, folding their signing info into their reward accrual when a new accrual
+/// period starts. A zero `period_len` disables the accrual
+pub fn accrue(
+    storage: &mut dyn Storage,
+    period_len: u64,
+    height: u64,
+    fp_set: &[FinalityProviderInfo],
+    voters: &[String],
+) -> StdResult<()> {
+    if period_len == 0 {
+        return Ok(());
+    }
+    let period = height / period_len;
+    for fp in fp_set {
+        let mut info = SIGNING_INFOS
+            .may_load(storage, &fp.btc_pk_hex)?
+            .unwrap_or_default();
+        if info.active_blocks > 0 && height <= info.last_height {
+            continue;
+        }
+        if info.period != period {
+            if info.active_blocks > 0 {
+                let mut accrual = REWARD_ACCRUALS
+                    .may_load(storage, &fp.btc_pk_hex)?
+                    .unwrap_or_default();
+                accrual.fold(&info);
+                REWARD_ACCRUALS.save(storage, &fp.btc_pk_hex, &accrual)?;
+            }
+            info = SigningInfo {
+                period,
+                ..SigningInfo::default()
+            };
+        }
+        info.active_blocks += 1;
+        if voters.contains(&fp.btc_pk_hex) {
+            info.signed_blocks += 1;
+        }
+        info.nominal_power += Uint128::from(fp.power);
+        info.last_height = height;
+        SIGNING_INFOS.save(storage, &fp.btc_pk_hex, &info)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    fn fp(btc_pk_hex: &str, power: u64) -> FinalityProviderInfo {
+        FinalityProviderInfo {
+            btc_pk_hex: btc_pk_hex.to_string(),
+            power,
+            self_stake: 0,
+        }
+    }
+
+    #[test]
+    fn accrual_is_weighted_by_participation() {
+        let mut storage = MockStorage::new();
+        let fp_set = [fp("a", 100), fp("b", 300)];
+        let both = ["a".to_string(), "b".to_string()];
+        let b_only = ["b".to_string()];
+
+        // Period 1 (heights 10 to 19): `a` signs one block out of two
+        accrue(&mut storage, 10, 10, &fp_set, &both).unwrap();
+        accrue(&mut storage, 10, 11, &fp_set, &b_only).unwrap();
+        // Blocks finalised again are not accounted twice
+        accrue(&mut storage, 10, 11, &fp_set, &both).unwrap();
+        let info = SIGNING_INFOS.load(&storage, "a").unwrap();
+        assert_eq!(info.active_blocks, 2);
+        assert_eq!(info.signed_blocks, 1);
+        assert_eq!(info.participation(), Decimal::percent(50));
+        assert!(REWARD_ACCRUALS.may_load(&storage, "a").unwrap().is_none());
+
+        // Period 2 starts, folding period 1 into the accruals
+        accrue(&mut storage, 10, 20, &fp_set, &both).unwrap();
+        let accrual = REWARD_ACCRUALS.load(&storage, "a").unwrap();
+        assert_eq!(
+            accrual,
+            RewardAccrual {
+                accrued: Uint128::new(100),
+                nominal: Uint128::new(200),
+                periods: 1,
+            }
+        );
+        let accrual = REWARD_ACCRUALS.load(&storage, "b").unwrap();
+        assert_eq!(accrual.accrued, Uint128::new(600));
+        assert_eq!(accrual.nominal, Uint128::new(600));
+
+        let info = SIGNING_INFOS.load(&storage, "a").unwrap();
+        assert_eq!(info.period, 2);
+        assert_eq!(info.active_blocks, 1);
+        assert_eq!(info.signed_blocks, 1);
+    }
+
+    #[test]
+    fn accrual_disabled() {
+        let mut storage = MockStorage::new();
+        accrue(&mut storage, 0, 10, &[fp("a", 100)], &[]).unwrap();
+        assert!(SIGNING_INFOS.may_load(&storage, "a").unwrap().is_none());
+    }
+}
@@ -0,0 +1,53 @@
+//! state_hash defines the state sections of the contract, which the state hash is computed over
+//! (see `babylon_apis::state_hash`)
+use babylon_apis::migration::Migration;
+use babylon_apis::state_hash::{rebuild_section_hashes, StateSection, STATE_HASH_REBUILD};
+
+/// All state sections, in the order they are folded into the state hash
+pub const SECTIONS: &[StateSection] = &[
+    StateSection {
+        name: "config",
+        namespaces: &["config", "params", "admin", "contract_info"],
+    },
+    StateSection {
+        name: "finality",
+        namespaces: &[
+            "fp_sigs",
+            "blocks",
+            "block_times",
+            "finality_latencies",
+            "next_height",
+            "fp_set",
+            "missed_blocks",
+            "total_power",
+            "native_tallies",
+            "evidences",
+            "pub_rand_reuse_evidences",
+            "sig_failures",
+            "sig_failure_totals",
+            "vote_invalidations",
+            "invalidated_votes",
+        ],
+    },
+    StateSection {
+        name: "public_randomness",
+        namespaces: &[
+            "fp_pub_rand_commit",
+            "fp_pub_rand_commit_timestamps",
+            "fp_pub_rand",
+            "fp_pub_rand_heights",
+        ],
+    },
+    StateSection {
+        name: "rewards",
+        namespaces: &["signing_infos", "reward_accruals"],
+    },
+];
+
+/// The migration rebuilding the section hashes, for contracts migrating from a version not keeping
+/// them
+pub const SECTION_HASHES_REBUILD: Migration = Migration {
+    name: STATE_HASH_REBUILD,
+    section: STATE_HASH_REBUILD,
+    step: |storage, cursor, limit| rebuild_section_hashes(storage, SECTIONS, cursor, limit),
+};
@@ -0,0 +1,88 @@
+//! This benchmark measures the storage / gas trade-off of storing the proofs of delegations
+//! compressed.
+//! It reports the stored size of a delegation with and without compression of its proofs, along
+//! with the storage gas it costs, and measures the (native) time spent (de)compressing them.
+//! Run it with `CARGO_PROFILE_RELEASE_PANIC=unwind cargo bench -p btc-staking --features compression`
+//! (btc-staking is both a dependency of babylon-contract and a dependant of it, so benches cannot
+//! mix the release `panic = 'abort'` with the unwinding bench harness)
+use criterion::{criterion_group, criterion_main, Criterion, PlottingBackend};
+
+use std::time::Duration;
+use thousands::Separable;
+
+use cosmwasm_std::{from_json, to_json_vec};
+
+use babylon_apis::compression::Compressed;
+use btc_staking::state::staking::{BtcDelegation, DelegationProofs};
+use test_utils::get_active_btc_delegation;
+
+// From https://github.com/cosmos/cosmos-sdk/blob/v0.50.9/store/types/gas.go#L233
+const WRITE_COST_FLAT: u64 = 2000;
+const WRITE_COST_PER_BYTE: u64 = 30;
+const READ_COST_FLAT: u64 = 1000;
+const READ_COST_PER_BYTE: u64 = 3;
+
+fn delegation() -> BtcDelegation {
+    let mut del = BtcDelegation::from(&get_active_btc_delegation());
+    // Mainnet-like covenant committee
+    let sigs = del.covenant_sigs.clone();
+    while del.covenant_sigs.len() < 9 {
+        del.covenant_sigs.extend(sigs.iter().cloned());
+    }
+    del.covenant_sigs.truncate(9);
+    del
+}
+
+fn report_storage_gas(name: &str, len: usize) {
+    let len = len as u64;
+    println!(
+        "{name:<18} {:>8} bytes, write gas: {:>8}, read gas: {:>8}",
+        len.separate_with_underscores(),
+        (WRITE_COST_FLAT + WRITE_COST_PER_BYTE * len).separate_with_underscores(),
+        (READ_COST_FLAT + READ_COST_PER_BYTE * len).separate_with_underscores(),
+    );
+}
+
+fn bench_compression(c: &mut Criterion) {
+    let del = delegation();
+    let plain = to_json_vec(&del).unwrap();
+    let mut stripped = del.clone();
+    let proofs = DelegationProofs::split(&mut stripped);
+    let plain_proofs = to_json_vec(&proofs).unwrap();
+    let compressed_proofs = to_json_vec(&Compressed(&proofs)).unwrap();
+    let stripped = to_json_vec(&stripped).unwrap();
+    // Compression stores the delegation without its proofs, and its compressed proofs apart
+    report_storage_gas("plain", plain.len());
+    report_storage_gas("stripped", stripped.len());
+    report_storage_gas("proofs", plain_proofs.len());
+    report_storage_gas("compressed proofs", compressed_proofs.len());
+
+    let mut group = c.benchmark_group("Delegation proofs");
+    group.bench_function("Encode plain", |b| b.iter(|| to_json_vec(&proofs).unwrap()));
+    group.bench_function("Encode compressed", |b| {
+        b.iter(|| to_json_vec(&Compressed(&proofs)).unwrap())
+    });
+    group.bench_function("Decode plain", |b| {
+        b.iter(|| from_json::<DelegationProofs>(&plain_proofs).unwrap())
+    });
+    group.bench_function("Decode compressed", |b| {
+        b.iter(|| from_json::<Compressed<DelegationProofs>>(&compressed_proofs).unwrap())
+    });
+    group.finish();
+}
+
+fn make_config() -> Criterion {
+    Criterion::default()
+        .plotting_backend(PlottingBackend::Plotters)
+        .without_plots()
+        .measurement_time(Duration::new(3, 0))
+        .sample_size(50)
+        .configure_from_args()
+}
+
+criterion_group!(
+    name = compression;
+    config = make_config();
+    targets = bench_compression
+);
+criterion_main!(compression);
@@ -1,16 +1,13 @@
-use cosmwasm_schema::write_api;
-use cosmwasm_std::Empty;
+use std::env::current_dir;
 
-use btc_staking::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use babylon_apis::schema::write_schema_files;
 
 fn main() {
     // Clear & write standard API
-    write_api! {
-        instantiate: InstantiateMsg,
-        query: QueryMsg,
-        migrate: Empty,
-        execute: ExecuteMsg,
-    }
+    write_schema_files(
+        btc_staking::schema::api(),
+        &current_dir().unwrap().join("schema"),
+    );
 
     // Schemas for inter-contract communication
     // let mut out_dir = current_dir().unwrap();
@@ -1,7 +1,7 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Deps, DepsMut, Empty, Env, MessageInfo, QueryResponse, Reply, Response,
+    to_json_binary, Deps, DepsMut, Env, Event, MessageInfo, QueryResponse, Reply, Response,
     StdResult,
 };
 use cw2::set_contract_version;
@@ -9,22 +9,41 @@ use cw_utils::{maybe_addr, nonpayable};
 
 use babylon_bindings::BabylonMsg;
 
-use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use babylon_apis::contract_version::ensure_upgrade;
+use babylon_apis::error::ErrorCodesResponse;
+use babylon_apis::migration::{self, MigrationProgressResponse};
+use babylon_apis::state_hash::{get_state_hash, init_section_hashes, HashedStorage};
+
+use crate::error::{ContractError, ERROR_CODES};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg};
 use crate::queries;
-use crate::staking::{handle_btc_staking, handle_slash_fp};
-use crate::state::config::{Config, ADMIN, CONFIG, PARAMS};
+use crate::staking::{
+    handle_begin_block, handle_bind_staker, handle_btc_staking, handle_deregister_fp,
+    handle_discard_quarantined, handle_distribute_rewards, handle_end_block, handle_grant_claim,
+    handle_jail_fp, handle_receive_cw20, handle_refund_fp_deposit, handle_register_fp,
+    handle_remove_fp, handle_reprocess_quarantined, handle_revoke_claim, handle_set_fp_metadata,
+    handle_slash_fp, handle_unbind_staker, handle_unbond, handle_unjail_fp,
+    handle_update_reward_tokens, handle_withdraw_rewards, import_genesis,
+};
+use crate::state::config::{Config, ADMIN, CONFIG, EVENT_VERBOSITY, PARAMS};
+use crate::state::migration::{needed_migrations, MIGRATIONS};
+use crate::state::state_hash::SECTIONS;
 
 pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    mut deps: DepsMut,
-    _env: Env,
+    deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let mut deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
     nonpayable(&info)?;
     let config = Config {
         babylon: info.sender,
@@ -34,12 +53,26 @@ pub fn instantiate(
     let api = deps.api;
     ADMIN.set(deps.branch(), maybe_addr(api, msg.admin.clone())?)?;
 
-    let params = msg.params.unwrap_or_default();
+    let mut params = msg.params.unwrap_or_default();
+    params.normalize_pks()?;
     PARAMS.save(deps.storage, &params)?;
     // initialize storage, so no issue when reading for the first time
 
+    let mut res = Response::new().add_attribute("action", "instantiate");
+    if let Some(genesis) = msg.genesis {
+        let evs = import_genesis(deps.storage, env.block.height, &genesis)?;
+        res = res
+            .add_events(evs)
+            .add_attribute("genesis_fps", genesis.fps.len().to_string())
+            .add_attribute(
+                "genesis_active_delegations",
+                genesis.active_delegations.len().to_string(),
+            );
+    }
+
+    init_section_hashes(deps.storage)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::new().add_attribute("action", "instantiate"))
+    Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -48,7 +81,7 @@ pub fn reply(_deps: DepsMut, _env: Env, _reply: Reply) -> StdResult<Response> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
     match msg {
         QueryMsg::Config {} => Ok(to_json_binary(&queries::config(deps)?)?),
         QueryMsg::Params {} => Ok(to_json_binary(&queries::params(deps)?)?),
@@ -65,6 +98,12 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
             deps,
             staking_tx_hash_hex,
         )?)?),
+        QueryMsg::DelegationStatus {
+            staking_tx_hash_hex,
+        } => Ok(to_json_binary(&queries::delegation_status(
+            deps,
+            staking_tx_hash_hex,
+        )?)?),
         QueryMsg::Delegations {
             start_after,
             limit,
@@ -75,23 +114,145 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
             limit,
             active,
         )?)?),
-        QueryMsg::DelegationsByFP { btc_pk_hex } => Ok(to_json_binary(
-            &queries::delegations_by_fp(deps, btc_pk_hex)?,
-        )?),
+        QueryMsg::DelegationsByFP {
+            btc_pk_hex,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&queries::delegations_by_fp(
+            deps,
+            btc_pk_hex,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::DelegationsByStaker {
+            staker_addr,
+            start_after,
+            limit,
+            active,
+        } => Ok(to_json_binary(&queries::delegations_by_staker(
+            deps,
+            staker_addr,
+            start_after,
+            limit,
+            active,
+        )?)?),
         QueryMsg::FinalityProviderInfo { btc_pk_hex, height } => Ok(to_json_binary(
             &queries::finality_provider_info(deps, btc_pk_hex, height)?,
         )?),
         QueryMsg::FinalityProvidersByPower { start_after, limit } => Ok(to_json_binary(
             &queries::finality_providers_by_power(deps, start_after, limit)?,
         )?),
+        QueryMsg::VotingPowerAtHeight { fp, height } => Ok(to_json_binary(
+            &queries::voting_power_at_height(deps, fp, height)?,
+        )?),
         QueryMsg::ActivatedHeight {} => Ok(to_json_binary(&queries::activated_height(deps)?)?),
+        QueryMsg::UnbondingIntent {
+            staking_tx_hash_hex,
+        } => Ok(to_json_binary(&queries::unbonding_intent(
+            deps,
+            staking_tx_hash_hex,
+        )?)?),
+        QueryMsg::UnbondingIntents {
+            start_after,
+            limit,
+            pending,
+        } => Ok(to_json_binary(&queries::unbonding_intents(
+            deps,
+            start_after,
+            limit,
+            pending,
+        )?)?),
+        QueryMsg::StakerBinding { btc_pk_hex } => {
+            Ok(to_json_binary(&queries::staker_binding(deps, btc_pk_hex)?)?)
+        }
+        QueryMsg::StakerBindingsByAddr {
+            addr,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&queries::staker_bindings_by_addr(
+            deps,
+            addr,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::ClaimGrants {
+            staker,
+            start_after,
+            limit,
+        } => Ok(to_json_binary(&queries::claim_grants(
+            deps,
+            staker,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::FinalityProviderMetadata { btc_pk_hex } => Ok(to_json_binary(
+            &queries::finality_provider_metadata(deps, btc_pk_hex)?,
+        )?),
+        QueryMsg::FinalityProviderExit { btc_pk_hex } => Ok(to_json_binary(
+            &queries::finality_provider_exit(deps, btc_pk_hex)?,
+        )?),
+        QueryMsg::QuarantinedItem { id } => {
+            Ok(to_json_binary(&queries::quarantined_item(deps, id)?)?)
+        }
+        QueryMsg::Quarantine { start_after, limit } => Ok(to_json_binary(&queries::quarantine(
+            deps,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::FinalityProviderJail { btc_pk_hex } => Ok(to_json_binary(
+            &queries::finality_provider_jail(deps, btc_pk_hex)?,
+        )?),
+        QueryMsg::FinalityProviderRemoval { btc_pk_hex } => Ok(to_json_binary(
+            &queries::finality_provider_removal(deps, btc_pk_hex)?,
+        )?),
+        QueryMsg::FinalityProviderDeposit { btc_pk_hex } => Ok(to_json_binary(
+            &queries::finality_provider_deposit(deps, btc_pk_hex)?,
+        )?),
+        QueryMsg::PendingRewards { staker_addr } => Ok(to_json_binary(&queries::pending_rewards(
+            deps,
+            staker_addr,
+        )?)?),
+        QueryMsg::FinalityProviderRewards { btc_pk_hex } => Ok(to_json_binary(
+            &queries::finality_provider_rewards(deps, btc_pk_hex)?,
+        )?),
+        QueryMsg::CommissionVestings { addr } => Ok(to_json_binary(
+            &queries::commission_vestings(deps, env, addr)?,
+        )?),
+        QueryMsg::RewardTokens {} => Ok(to_json_binary(&queries::reward_tokens(deps)?)?),
+        QueryMsg::EventVerbosity {} => Ok(to_json_binary(&queries::event_verbosity(deps)?)?),
+        QueryMsg::NativeValidator { operator_address } => Ok(to_json_binary(
+            &queries::native_validator(deps, operator_address)?,
+        )?),
+        QueryMsg::ErrorCodes {} => Ok(to_json_binary(&ErrorCodesResponse::from(ERROR_CODES))?),
+        QueryMsg::StateHash {} => Ok(to_json_binary(&get_state_hash(deps.storage, SECTIONS)?)?),
+        QueryMsg::MigrationProgress {} => Ok(to_json_binary(&MigrationProgressResponse {
+            migrations: migration::get_migration_progress(deps.storage)?,
+        })?),
     }
 }
 
-/// This is a no-op just to test how this integrates with wasmd
+/// migrate migrates the contract to this version of its code, refusing downgrades, and schedules
+/// the backfills of the delegation state and of the section hashes (see `state::migration`).
+/// The backfills are run in chunks through `ContinueMigration`
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: Empty) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(
+    deps: DepsMut,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
+    let from_version = ensure_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let migrations = needed_migrations(deps.storage)?;
+    let scheduled = migration::schedule_migrations(deps.storage, &migrations)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.unwrap_or_default())
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("scheduled_migrations", scheduled.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -101,6 +262,11 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
     let api = deps.api;
     match msg {
         ExecuteMsg::UpdateAdmin { admin } => ADMIN
@@ -121,6 +287,79 @@ pub fn execute(
             &unbonded_del,
         ),
         ExecuteMsg::Slash { fp_btc_pk_hex } => handle_slash_fp(deps, env, &info, &fp_btc_pk_hex),
+        ExecuteMsg::Jail { fp_btc_pk_hex } => handle_jail_fp(deps, env, &info, &fp_btc_pk_hex),
+        ExecuteMsg::Unjail { fp_btc_pk_hex } => handle_unjail_fp(deps, env, &info, &fp_btc_pk_hex),
+        ExecuteMsg::Unbond { staking_tx_hash } => handle_unbond(deps, env, &info, &staking_tx_hash),
+        ExecuteMsg::BindStaker { btc_pk_hex, sig } => {
+            handle_bind_staker(deps, env, &info, &btc_pk_hex, &sig)
+        }
+        ExecuteMsg::UnbindStaker { btc_pk_hex } => handle_unbind_staker(deps, &info, &btc_pk_hex),
+        ExecuteMsg::RegisterFinalityProvider { fp } => handle_register_fp(deps, env, &info, &fp),
+        ExecuteMsg::RefundFpDeposit { btc_pk_hex } => {
+            handle_refund_fp_deposit(deps, &info, &btc_pk_hex)
+        }
+        ExecuteMsg::DeregisterFinalityProvider { btc_pk_hex } => {
+            handle_deregister_fp(deps, env, &info, &btc_pk_hex)
+        }
+        ExecuteMsg::SetFpMetadata {
+            fp_btc_pk_hex,
+            metadata,
+            sig,
+        } => handle_set_fp_metadata(deps, env, &info, &fp_btc_pk_hex, metadata, sig),
+        ExecuteMsg::SetEventVerbosity { verbosity } => {
+            ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+            EVENT_VERBOSITY.save(deps.storage, &verbosity)?;
+            Ok(Response::new()
+                .add_attribute("action", "set_event_verbosity")
+                .add_attribute("verbosity", format!("{verbosity:?}")))
+        }
+        ExecuteMsg::ReprocessQuarantined { id } => {
+            handle_reprocess_quarantined(deps, env, &info, &id)
+        }
+        ExecuteMsg::DiscardQuarantined { id } => handle_discard_quarantined(deps, &info, &id),
+        ExecuteMsg::RemoveFinalityProvider { btc_pk_hex, reason } => {
+            handle_remove_fp(deps, env, &info, &btc_pk_hex, &reason)
+        }
+        ExecuteMsg::GrantClaim { grantee, expiry } => {
+            handle_grant_claim(deps, env, &info, &grantee, expiry)
+        }
+        ExecuteMsg::RevokeClaim { grantee } => handle_revoke_claim(deps, &info, &grantee),
+        ExecuteMsg::DistributeRewards { fp_distribution } => {
+            handle_distribute_rewards(deps, &info, &fp_distribution)
+        }
+        ExecuteMsg::WithdrawRewards { staker_addr } => {
+            handle_withdraw_rewards(deps, env, &info, staker_addr.as_deref())
+        }
+        ExecuteMsg::Receive(msg) => handle_receive_cw20(deps, &info, msg),
+        ExecuteMsg::UpdateRewardTokens { add, remove } => {
+            handle_update_reward_tokens(deps, &info, &add, &remove)
+        }
+        ExecuteMsg::ContinueMigration { limit } => {
+            let progress = migration::continue_migration(deps.storage, MIGRATIONS, limit as usize)?;
+            let mut event = Event::new("migration_progress");
+            event = match progress {
+                Some(progress) => event
+                    .add_attribute("name", progress.name)
+                    .add_attribute("section", progress.section)
+                    .add_attribute("migrated", progress.migrated.to_string())
+                    .add_attribute("complete", progress.complete.to_string()),
+                None => event.add_attribute("pending", "false"),
+            };
+            Ok(Response::new().add_event(event))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response<BabylonMsg>, ContractError> {
+    let mut storage = HashedStorage::new(deps.storage, SECTIONS);
+    let deps = DepsMut {
+        storage: &mut storage,
+        ..deps
+    };
+    match msg {
+        SudoMsg::BeginBlock { .. } => handle_begin_block(deps, env),
+        SudoMsg::EndBlock { .. } => handle_end_block(deps, env),
     }
 }
 
@@ -146,6 +385,7 @@ pub mod tests {
         let msg = InstantiateMsg {
             params: None,
             admin: None, // No admin provided
+            genesis: None,
         };
 
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
@@ -170,6 +410,7 @@ pub mod tests {
         let msg = InstantiateMsg {
             params: None,
             admin: Some(init_admin.to_string()), // Admin provided
+            genesis: None,
         };
 
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
@@ -200,6 +441,7 @@ pub mod tests {
         let instantiate_msg = InstantiateMsg {
             params: None,
             admin: Some(init_admin.to_string()), // Admin provided
+            genesis: None,
         };
 
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
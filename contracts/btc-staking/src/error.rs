@@ -9,7 +9,7 @@ use cosmwasm_std::StdError;
 use cw_controllers::AdminError;
 use cw_utils::PaymentError;
 
-use babylon_apis::error::StakingApiError;
+use babylon_apis::error::{ErrorCategory, ErrorCode, StakingApiError};
 use babylon_merkle::error::MerkleError;
 
 #[derive(Error, Debug, PartialEq)]
@@ -40,6 +40,8 @@ pub enum ContractError {
     SecP256K1Error(String), // TODO: inherit errors from k256
     #[error("Unauthorized")]
     Unauthorized,
+    #[error("The params do not match the Babylon contract's config: {0}")]
+    BabylonConfigMismatch(String),
     #[error("Failed to verify the finality provider registration request: {0}")]
     FinalityProviderVerificationError(String),
     #[error("Finality provider already exists: {0}")]
@@ -52,6 +54,42 @@ pub enum ContractError {
     DelegationAlreadyExists(String),
     #[error("BTC delegation is not active: {0}")]
     DelegationIsNotActive(String),
+    #[error("BTC delegation already has an unbonding intent: {0}")]
+    UnbondingIntentAlreadyExists(String),
+    #[error("Finality provider is already deregistered: {0}")]
+    FinalityProviderDeregistered(String),
+    #[error("Quarantined BTC staking operation not found: {0}")]
+    QuarantinedItemNotFound(String),
+    #[error("The removal of finality provider {0} is timelocked until height {1}")]
+    FinalityProviderRemovalTimelocked(String, u64),
+    #[error("Finality provider has been removed: {0}")]
+    FinalityProviderRemoved(String),
+    #[error("Invalid reward claim grant: {0}")]
+    InvalidClaimGrant(String),
+    #[error("Reward claim grant of {0} to {1} not found")]
+    ClaimGrantNotFound(String, String),
+    #[error("Finality provider is not jailed: {0}")]
+    FinalityProviderNotJailed(String),
+    #[error("Finality provider {0} is jailed until height {1}")]
+    FinalityProviderJailed(String, u64),
+    #[error("Invalid rewards distribution: {0}")]
+    InvalidRewardDistribution(String),
+    #[error("No pending rewards for {0}")]
+    NoPendingRewards(String),
+    #[error("Native staking queries are not enabled (`native-staking` feature)")]
+    NativeStakingDisabled,
+    #[error("Invalid finality provider metadata: {0}")]
+    InvalidFpMetadata(String),
+    #[error("Staking tx is inconsistent with the delegation: {0}")]
+    StakingTxMismatch(String),
+    #[error("Staker key {0} is not bound to a consumer address")]
+    StakerNotBound(String),
+    #[error("cw20 token {0} is not registered as a reward")]
+    UnregisteredRewardToken(String),
+    #[error("Invalid finality provider registration deposit: {0}")]
+    InvalidFpDeposit(String),
+    #[error("The deposit of finality provider {0} cannot be refunded: {1}")]
+    FpDepositNotRefundable(String, String),
     #[error("Invalid covenant signature: {0}")]
     InvalidCovenantSig(String),
     #[error("Invalid Btc tx: {0}")]
@@ -96,6 +134,414 @@ pub enum ContractError {
     FailedToSlashFinalityProvider(String),
     #[error("Failed to extract secret key: {0}")]
     SecretKeyExtractionError(String),
+    #[error("Invalid staker binding signature: {0}")]
+    InvalidStakerBinding(String),
     #[error("Hash length error: {0}")]
     WrongHashLength(String),
+    #[error("State section {section} is paused until its migration is complete")]
+    SectionMigrating { section: String },
+}
+
+impl ContractError {
+    /// code returns the stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            ContractError::Admin(..) => 201,
+            ContractError::Std(..) => 101,
+            ContractError::Payment(..) => 102,
+            ContractError::BTCStaking(..) => 801,
+            ContractError::HexArrayError(..) => 103,
+            ContractError::SliceError(..) => 104,
+            ContractError::StakingError(..) => 802,
+            ContractError::MerkleError(..) => 1001,
+            ContractError::ProtoError(..) => 105,
+            ContractError::HexError(..) => 106,
+            ContractError::EotsError(..) => 1002,
+            ContractError::SecP256K1Error(..) => 1003,
+            ContractError::Unauthorized => 202,
+            ContractError::BabylonConfigMismatch(..) => 401,
+            ContractError::FinalityProviderVerificationError(..) => 803,
+            ContractError::FinalityProviderAlreadyExists(..) => 804,
+            ContractError::FinalityProviderNotRegistered => 805,
+            ContractError::FinalityProviderNotFound(..) => 806,
+            ContractError::DelegationAlreadyExists(..) => 807,
+            ContractError::DelegationIsNotActive(..) => 808,
+            ContractError::InvalidCovenantSig(..) => 1004,
+            ContractError::InvalidBtcTx(..) => 809,
+            ContractError::EmptySignature => 1005,
+            ContractError::ErrInvalidLockType => 810,
+            ContractError::ErrInvalidLockTime(..) => 811,
+            ContractError::NoVotingPower(..) => 901,
+            ContractError::HeightTooHigh => 902,
+            ContractError::DuplicateFinalityVote(..) => 903,
+            ContractError::TooFewPubRand(..) => 904,
+            ContractError::InvalidPubRandHeight(..) => 905,
+            ContractError::InvalidPubRandSignature => 1006,
+            ContractError::MissingPubRandCommit(..) => 906,
+            ContractError::InvalidFinalitySigHeight(..) => 907,
+            ContractError::InvalidFinalitySigAmount(..) => 908,
+            ContractError::InvalidSignature(..) => 1007,
+            ContractError::FailedSignatureVerification(..) => 1008,
+            ContractError::FinalisedBlockWithFinalityProviderSet(..) => 909,
+            ContractError::FinalisedBlockWithoutFinalityProviderSet(..) => 910,
+            ContractError::BlockNotFound(..) => 911,
+            ContractError::FinalityProviderAlreadySlashed(..) => 812,
+            ContractError::FailedToSlashFinalityProvider(..) => 813,
+            ContractError::UnbondingIntentAlreadyExists(..) => 814,
+            ContractError::FinalityProviderDeregistered(..) => 815,
+            ContractError::QuarantinedItemNotFound(..) => 816,
+            ContractError::FinalityProviderRemovalTimelocked(..) => 817,
+            ContractError::FinalityProviderRemoved(..) => 818,
+            ContractError::InvalidClaimGrant(..) => 819,
+            ContractError::ClaimGrantNotFound(..) => 820,
+            ContractError::FinalityProviderNotJailed(..) => 821,
+            ContractError::FinalityProviderJailed(..) => 822,
+            ContractError::InvalidRewardDistribution(..) => 823,
+            ContractError::NoPendingRewards(..) => 824,
+            ContractError::NativeStakingDisabled => 825,
+            ContractError::InvalidFpMetadata(..) => 826,
+            ContractError::StakingTxMismatch(..) => 827,
+            ContractError::StakerNotBound(..) => 828,
+            ContractError::UnregisteredRewardToken(..) => 829,
+            ContractError::InvalidFpDeposit(..) => 830,
+            ContractError::FpDepositNotRefundable(..) => 831,
+            ContractError::SecretKeyExtractionError(..) => 1009,
+            ContractError::InvalidStakerBinding(..) => 1010,
+            ContractError::WrongHashLength(..) => 107,
+            ContractError::SectionMigrating { .. } => 402,
+        }
+    }
+}
+
+/// ERROR_CODES is the table of error codes of the contract
+pub const ERROR_CODES: &[ErrorCode] = &[
+        ErrorCode {
+            code: 101,
+            category: ErrorCategory::Std,
+            description: "Generic error",
+        },
+        ErrorCode {
+            code: 102,
+            category: ErrorCategory::Std,
+            description: "Invalid funds",
+        },
+        ErrorCode {
+            code: 103,
+            category: ErrorCategory::Std,
+            description: "Error converting from hex to array",
+        },
+        ErrorCode {
+            code: 104,
+            category: ErrorCategory::Std,
+            description: "Invalid slice length",
+        },
+        ErrorCode {
+            code: 105,
+            category: ErrorCategory::Std,
+            description: "The bytes cannot be decoded",
+        },
+        ErrorCode {
+            code: 106,
+            category: ErrorCategory::Std,
+            description: "The hex cannot be decoded",
+        },
+        ErrorCode {
+            code: 107,
+            category: ErrorCategory::Std,
+            description: "Hash length error",
+        },
+        ErrorCode {
+            code: 201,
+            category: ErrorCategory::Auth,
+            description: "Admin error",
+        },
+        ErrorCode {
+            code: 202,
+            category: ErrorCategory::Auth,
+            description: "Unauthorized",
+        },
+        ErrorCode {
+            code: 401,
+            category: ErrorCategory::Config,
+            description: "The params do not match the Babylon contract's config",
+        },
+        ErrorCode {
+            code: 402,
+            category: ErrorCategory::Config,
+            description: "State section is paused until its migration is complete",
+        },
+        ErrorCode {
+            code: 801,
+            category: ErrorCategory::Staking,
+            description: "BTC staking error",
+        },
+        ErrorCode {
+            code: 802,
+            category: ErrorCategory::Staking,
+            description: "Staking API error",
+        },
+        ErrorCode {
+            code: 803,
+            category: ErrorCategory::Staking,
+            description: "Failed to verify the finality provider registration request",
+        },
+        ErrorCode {
+            code: 804,
+            category: ErrorCategory::Staking,
+            description: "Finality provider already exists",
+        },
+        ErrorCode {
+            code: 805,
+            category: ErrorCategory::Staking,
+            description: "No finality providers are registered in this Consumer",
+        },
+        ErrorCode {
+            code: 806,
+            category: ErrorCategory::Staking,
+            description: "Finality provider not found",
+        },
+        ErrorCode {
+            code: 807,
+            category: ErrorCategory::Staking,
+            description: "Staking tx hash already exists",
+        },
+        ErrorCode {
+            code: 808,
+            category: ErrorCategory::Staking,
+            description: "BTC delegation is not active",
+        },
+        ErrorCode {
+            code: 809,
+            category: ErrorCategory::Staking,
+            description: "Invalid Btc tx",
+        },
+        ErrorCode {
+            code: 810,
+            category: ErrorCategory::Staking,
+            description: "Invalid lock type: seconds",
+        },
+        ErrorCode {
+            code: 811,
+            category: ErrorCategory::Staking,
+            description: "Invalid lock time blocks",
+        },
+        ErrorCode {
+            code: 812,
+            category: ErrorCategory::Staking,
+            description: "The finality provider has already been slashed",
+        },
+        ErrorCode {
+            code: 813,
+            category: ErrorCategory::Staking,
+            description: "Failed to slash finality provider",
+        },
+        ErrorCode {
+            code: 814,
+            category: ErrorCategory::Staking,
+            description: "BTC delegation already has an unbonding intent",
+        },
+        ErrorCode {
+            code: 815,
+            category: ErrorCategory::Staking,
+            description: "Finality provider is already deregistered",
+        },
+        ErrorCode {
+            code: 816,
+            category: ErrorCategory::Staking,
+            description: "Quarantined BTC staking operation not found",
+        },
+        ErrorCode {
+            code: 817,
+            category: ErrorCategory::Staking,
+            description: "The removal of finality provider is timelocked",
+        },
+        ErrorCode {
+            code: 818,
+            category: ErrorCategory::Staking,
+            description: "Finality provider has been removed",
+        },
+        ErrorCode {
+            code: 819,
+            category: ErrorCategory::Staking,
+            description: "Invalid reward claim grant",
+        },
+        ErrorCode {
+            code: 820,
+            category: ErrorCategory::Staking,
+            description: "Reward claim grant of the granter not found",
+        },
+        ErrorCode {
+            code: 821,
+            category: ErrorCategory::Staking,
+            description: "Finality provider is not jailed",
+        },
+        ErrorCode {
+            code: 822,
+            category: ErrorCategory::Staking,
+            description: "Finality provider is jailed",
+        },
+        ErrorCode {
+            code: 823,
+            category: ErrorCategory::Staking,
+            description: "Invalid rewards distribution",
+        },
+        ErrorCode {
+            code: 824,
+            category: ErrorCategory::Staking,
+            description: "No pending rewards for the recipient",
+        },
+        ErrorCode {
+            code: 825,
+            category: ErrorCategory::Staking,
+            description: "Native staking queries are not enabled (`native-staking` feature)",
+        },
+        ErrorCode {
+            code: 826,
+            category: ErrorCategory::Staking,
+            description: "Invalid finality provider metadata, or signature over it",
+        },
+        ErrorCode {
+            code: 827,
+            category: ErrorCategory::Staking,
+            description: "Staking tx is inconsistent with the delegation",
+        },
+        ErrorCode {
+            code: 828,
+            category: ErrorCategory::Staking,
+            description: "Staker key is not bound to a consumer address",
+        },
+        ErrorCode {
+            code: 829,
+            category: ErrorCategory::Staking,
+            description: "cw20 token is not registered as a reward",
+        },
+        ErrorCode {
+            code: 830,
+            category: ErrorCategory::Staking,
+            description: "Invalid finality provider registration deposit",
+        },
+        ErrorCode {
+            code: 831,
+            category: ErrorCategory::Staking,
+            description: "The deposit of finality provider cannot be refunded",
+        },
+        ErrorCode {
+            code: 901,
+            category: ErrorCategory::Finality,
+            description: "The finality provider does not have voting power at the height",
+        },
+        ErrorCode {
+            code: 902,
+            category: ErrorCategory::Finality,
+            description: "The chain has not reached the given height yet",
+        },
+        ErrorCode {
+            code: 903,
+            category: ErrorCategory::Finality,
+            description: "The finality provider signed two different blocks at the same height",
+        },
+        ErrorCode {
+            code: 904,
+            category: ErrorCategory::Finality,
+            description: "The request contains too few public randomness",
+        },
+        ErrorCode {
+            code: 905,
+            category: ErrorCategory::Finality,
+            description: "The start height has overlap with the height of the highest public randomness committed",
+        },
+        ErrorCode {
+            code: 906,
+            category: ErrorCategory::Finality,
+            description: "Public randomness not found for finality provider at the height",
+        },
+        ErrorCode {
+            code: 907,
+            category: ErrorCategory::Finality,
+            description: "The inclusion proof for height does not correspond to the given height",
+        },
+        ErrorCode {
+            code: 908,
+            category: ErrorCategory::Finality,
+            description: "The total amount of public randomnesses in the proof does not match the amount of public committed randomness",
+        },
+        ErrorCode {
+            code: 909,
+            category: ErrorCategory::Finality,
+            description: "Block is finalized, but last finalized height does not reach here",
+        },
+        ErrorCode {
+            code: 910,
+            category: ErrorCategory::Finality,
+            description: "Block is finalized, but does not have a finality provider set",
+        },
+        ErrorCode {
+            code: 911,
+            category: ErrorCategory::Finality,
+            description: "Block is not found",
+        },
+        ErrorCode {
+            code: 1001,
+            category: ErrorCategory::Crypto,
+            description: "Merkle proof error",
+        },
+        ErrorCode {
+            code: 1002,
+            category: ErrorCategory::Crypto,
+            description: "EOTS error",
+        },
+        ErrorCode {
+            code: 1003,
+            category: ErrorCategory::Crypto,
+            description: "Invalid secp256k1 key or signature",
+        },
+        ErrorCode {
+            code: 1004,
+            category: ErrorCategory::Crypto,
+            description: "Invalid covenant signature",
+        },
+        ErrorCode {
+            code: 1005,
+            category: ErrorCategory::Crypto,
+            description: "Empty signature from the delegator",
+        },
+        ErrorCode {
+            code: 1006,
+            category: ErrorCategory::Crypto,
+            description: "Invalid signature over the public randomness list",
+        },
+        ErrorCode {
+            code: 1007,
+            category: ErrorCategory::Crypto,
+            description: "Invalid finality signature",
+        },
+        ErrorCode {
+            code: 1008,
+            category: ErrorCategory::Crypto,
+            description: "Failed to verify signature",
+        },
+        ErrorCode {
+            code: 1009,
+            category: ErrorCategory::Crypto,
+            description: "Failed to extract secret key",
+        },
+        ErrorCode {
+            code: 1010,
+            category: ErrorCategory::Crypto,
+            description: "Invalid staker binding signature",
+        },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use babylon_apis::error::{check_error_codes, validate_error_codes};
+
+    #[test]
+    fn error_codes_are_valid() {
+        validate_error_codes(ERROR_CODES).unwrap();
+
+        // Every variant has a code in the table, described after its message
+        check_error_codes(include_str!("error.rs"), &["ContractError"], ERROR_CODES).unwrap();
+    }
 }
@@ -4,7 +4,11 @@ mod validation;
 pub mod contract;
 pub mod error;
 pub mod msg;
+#[cfg(feature = "native-staking")]
+mod native;
 pub mod queries;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
 pub mod state;
 #[cfg(any(test, feature = "library"))]
 pub mod test_utils;
@@ -1,20 +1,52 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 #[cfg(not(target_arch = "wasm32"))]
 use {
-    crate::state::config::Config, babylon_apis::btc_staking_api::ActiveBtcDelegation,
-    cw_controllers::AdminResponse,
+    crate::state::config::Config, babylon_apis::btc_staking_api::EventVerbosity,
+    babylon_apis::error::ErrorCodesResponse, babylon_apis::migration::MigrationProgressResponse,
+    babylon_apis::state_hash::StateHashResponse, cw_controllers::AdminResponse,
 };
 
-use babylon_apis::btc_staking_api::FinalityProvider;
+use babylon_apis::btc_pk::BtcPkHex;
+pub use babylon_apis::btc_staking_api::BtcStakingResult;
+use babylon_apis::btc_staking_api::{ActiveBtcDelegation, FinalityProvider, NewFinalityProvider};
+use babylon_apis::cw20::Cw20Coin;
+use cosmwasm_std::{Addr, Coin, Uint128};
 
 use crate::state::config::Params;
-use crate::state::staking::BtcDelegation;
+use crate::state::quarantine::QuarantinedItem;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::state::rewards::FpRewards;
+use crate::state::staking::{
+    BtcDelegation, ClaimGrant, DelegationStatus, FpDeposit, FpExit, FpJail, FpMetadataRecord,
+    FpRemoval, UnbondingIntent,
+};
+
+/// MigrateMsg is the message of a migration to a newer version of the contract
+#[cw_serde]
+#[derive(Default)]
+pub struct MigrateMsg {}
+
+pub use babylon_apis::sudo_api::SudoMsg;
 
 #[cw_serde]
 #[derive(Default)]
 pub struct InstantiateMsg {
     pub params: Option<Params>,
     pub admin: Option<String>,
+    /// `genesis` is an optional set of finality providers and active delegations to import at
+    /// instantiation, so that consumers launching with existing Babylon stake don't need to
+    /// relay it again
+    #[serde(default)]
+    pub genesis: Option<Genesis>,
+}
+
+/// `Genesis` is the initial BTC staking state of the contract.
+/// It is validated the same as the `BtcStaking` operations
+#[cw_serde]
+#[derive(Default)]
+pub struct Genesis {
+    pub fps: Vec<NewFinalityProvider>,
+    pub active_delegations: Vec<ActiveBtcDelegation>,
 }
 
 pub type ExecuteMsg = babylon_apis::btc_staking_api::ExecuteMsg;
@@ -33,18 +65,23 @@ pub enum QueryMsg {
     Admin {},
     /// `FinalityProvider` returns the finality provider by its BTC public key, in hex format
     #[returns(FinalityProvider)]
-    FinalityProvider { btc_pk_hex: String },
+    FinalityProvider { btc_pk_hex: BtcPkHex },
     /// `FinalityProviders` returns the list of registered finality providers
     ///
     /// `start_after` is the BTC public key of the FP to start after, or `None` to start from the beginning
     #[returns(FinalityProvidersResponse)]
     FinalityProviders {
-        start_after: Option<String>,
+        start_after: Option<BtcPkHex>,
         limit: Option<u32>,
     },
-    /// `Delegation` returns delegation information by its staking tx hash, in hex format
+    /// `Delegation` returns delegation information by its staking tx hash, in hex format.
+    /// The hash can be given either byte-reversed (as displayed by BTC explorers) or in wire order
     #[returns(ActiveBtcDelegation)]
     Delegation { staking_tx_hash_hex: String },
+    /// `DelegationStatus` returns the lifecycle state of a delegation, by its staking tx hash in
+    /// hex format, either byte-reversed or in wire order
+    #[returns(DelegationStatusResponse)]
+    DelegationStatus { staking_tx_hash_hex: String },
     /// `Delegations` return the list of delegations
     ///
     /// `start_after` is the staking tx hash (in hex format) of the delegation to start after,
@@ -58,13 +95,34 @@ pub enum QueryMsg {
         active: Option<bool>,
     },
     /// `DelegationsByFP` returns the list of staking tx hashes (in hex format) corresponding to
-    /// delegations, for a given finality provider.
+    /// delegations, for a given finality provider, in order of registration.
     ///
     /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    /// `start_after` is the staking tx hash (in hex format) of the delegation to start after,
+    /// or `None` to start from the beginning.
+    /// `limit` is the maximum number of hashes to return.
     /// The hashes are returned in hex format
-    //TODO?: Support pagination
     #[returns(DelegationsByFPResponse)]
-    DelegationsByFP { btc_pk_hex: String },
+    DelegationsByFP {
+        btc_pk_hex: BtcPkHex,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// `DelegationsByStaker` returns the list of delegations of a given staker address, by
+    /// staking tx hash.
+    ///
+    /// `staker_addr` is the address receiving the rewards of the delegations.
+    /// `start_after` is the staking tx hash (in hex format) of the delegation to start after,
+    /// or `None` to start from the beginning.
+    /// `limit` is the maximum number of delegations to return.
+    /// `active` is an optional filter to return only active delegations
+    #[returns(BtcDelegationsResponse)]
+    DelegationsByStaker {
+        staker_addr: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        active: Option<bool>,
+    },
     /// `FinalityProviderInfo` returns the finality provider information by its BTC public key,
     /// in hex format
     /// The information includes the aggregated power of the finality provider.
@@ -73,11 +131,13 @@ pub enum QueryMsg {
     /// If `height` is not provided, the latest aggregated power is returned
     #[returns(FinalityProviderInfo)]
     FinalityProviderInfo {
-        btc_pk_hex: String,
+        btc_pk_hex: BtcPkHex,
         height: Option<u64>,
     },
     /// `FinalityProvidersByPower` returns the list of finality provider infos sorted by their
     /// aggregated power, in descending order.
+    /// Only the active set, i.e. the top `Params::max_active_finality_providers` finality
+    /// providers, is listed.
     ///
     /// `start_after` is the BTC public key of the FP to start after, or `None` to start from the top
     #[returns(FinalityProvidersByPowerResponse)]
@@ -85,10 +145,128 @@ pub enum QueryMsg {
         start_after: Option<FinalityProviderInfo>,
         limit: Option<u32>,
     },
+    /// `VotingPowerAtHeight` returns the voting power of a finality provider at the given block
+    /// height, from the voting power table snapshots.
+    /// The power is zero if the finality provider was unknown or jailed at that height.
+    ///
+    /// `fp` is the BTC public key of the finality provider, in hex format.
+    #[returns(VotingPowerResponse)]
+    VotingPowerAtHeight { fp: BtcPkHex, height: u64 },
     /// `ActivatedHeight` returns the height at which the contract gets its first delegation, if any
     ///
     #[returns(ActivatedHeightResponse)]
     ActivatedHeight {},
+    /// `UnbondingIntent` returns the delegator-initiated unbonding intent of a delegation, by its
+    /// staking tx hash in hex format, either byte-reversed or in wire order
+    #[returns(UnbondingIntent)]
+    UnbondingIntent { staking_tx_hash_hex: String },
+    /// `UnbondingIntents` returns the list of delegator-initiated unbonding intents
+    ///
+    /// `start_after` is the staking tx hash (in hex format) of the intent to start after,
+    /// or `None` to start from the beginning.
+    /// `pending` is an optional filter to return only the intents whose voting power is still to
+    /// be removed
+    #[returns(UnbondingIntentsResponse)]
+    UnbondingIntents {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        pending: Option<bool>,
+    },
+    /// `StakerBinding` returns the consumer address bound to a BTC staker public key, in hex format
+    #[returns(StakerBindingResponse)]
+    StakerBinding { btc_pk_hex: BtcPkHex },
+    /// `StakerBindingsByAddr` returns the BTC staker public keys (in hex format) bound to a
+    /// consumer address
+    ///
+    /// `start_after` is the BTC public key to start after, or `None` to start from the beginning
+    #[returns(StakerBindingsResponse)]
+    StakerBindingsByAddr {
+        addr: String,
+        start_after: Option<BtcPkHex>,
+        limit: Option<u32>,
+    },
+    /// `ClaimGrants` returns the reward claim grants recorded by a staker address, expired ones
+    /// included
+    ///
+    /// `start_after` is the grantee address to start after, or `None` to start from the beginning
+    #[returns(ClaimGrantsResponse)]
+    ClaimGrants {
+        staker: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// `FinalityProviderMetadata` returns the operational metadata published by a finality
+    /// provider, by its BTC public key, in hex format
+    #[returns(FpMetadataRecord)]
+    FinalityProviderMetadata { btc_pk_hex: BtcPkHex },
+    /// `FinalityProviderExit` returns the exit status of a deregistered finality provider, by its
+    /// BTC public key, in hex format
+    #[returns(FpExit)]
+    FinalityProviderExit { btc_pk_hex: BtcPkHex },
+    /// `FinalityProviderJail` returns the jailing of a jailed finality provider, by its BTC public
+    /// key, in hex format
+    #[returns(FpJail)]
+    FinalityProviderJail { btc_pk_hex: BtcPkHex },
+    /// `FinalityProviderRemoval` returns the removal of a finality provider by the admin,
+    /// scheduled or executed, by its BTC public key, in hex format
+    #[returns(FpRemoval)]
+    FinalityProviderRemoval { btc_pk_hex: BtcPkHex },
+    /// `FinalityProviderDeposit` returns the registration deposit of a finality provider
+    /// registered directly on the consumer, not refunded yet, by its BTC public key, in hex format
+    #[returns(FpDeposit)]
+    FinalityProviderDeposit { btc_pk_hex: BtcPkHex },
+    /// `QuarantinedItem` returns a quarantined BTC staking operation, by its id, i.e. the BTC
+    /// public key of a new finality provider, or the staking tx hash of an active delegation,
+    /// in hex format
+    #[returns(QuarantinedItem)]
+    QuarantinedItem { id: String },
+    /// `Quarantine` returns the BTC staking operations that failed validation, and are awaiting
+    /// being reprocessed or discarded by the admin
+    ///
+    /// `start_after` is the id to start after, or `None` to start from the beginning
+    #[returns(QuarantineResponse)]
+    Quarantine {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// `EventVerbosity` returns the verbosity of the events emitted while processing BTC staking
+    /// operations
+    #[returns(EventVerbosity)]
+    EventVerbosity {},
+    /// `PendingRewards` returns the rewards a staker address can withdraw, along with the rewards
+    /// pending in each finality provider it stakes in
+    #[returns(PendingRewardsResponse)]
+    PendingRewards { staker_addr: String },
+    /// `FinalityProviderRewards` returns the reward distribution state of a finality provider, by
+    /// its BTC public key, in hex format
+    #[returns(FpRewards)]
+    FinalityProviderRewards { btc_pk_hex: BtcPkHex },
+    /// `CommissionVestings` returns the withdrawn commissions of an address (e.g. of a finality
+    /// provider) vesting, under the commission lockup of the params
+    #[returns(CommissionVestingsResponse)]
+    CommissionVestings { addr: String },
+    /// `RewardTokens` returns the cw20 token contracts registered as rewards
+    #[returns(RewardTokensResponse)]
+    RewardTokens {},
+    /// `NativeValidator` returns a native validator of the consumer chain by its operator
+    /// address, through a gRPC query to the staking module of the chain. It allows
+    /// cross-checking native validators against finality providers, e.g. for hybrid power
+    /// calculations.
+    ///
+    /// Requires the contract to be built with the `native-staking` feature
+    #[returns(NativeValidatorResponse)]
+    NativeValidator { operator_address: String },
+    /// `ErrorCodes` returns the table of stable error codes of the contract
+    #[returns(ErrorCodesResponse)]
+    ErrorCodes {},
+    /// `StateHash` returns a deterministic digest over the contract's state, by state section
+    /// (see `babylon_apis::state_hash`), e.g. to compare the state of two deployments after a
+    /// migration
+    #[returns(StateHashResponse)]
+    StateHash {},
+    /// `MigrationProgress` returns the progress of the scheduled storage migrations
+    #[returns(MigrationProgressResponse)]
+    MigrationProgress {},
 }
 
 #[cw_serde]
@@ -111,6 +289,11 @@ pub struct FinalityProvidersByPowerResponse {
     pub fps: Vec<FinalityProviderInfo>,
 }
 
+#[cw_serde]
+pub struct VotingPowerResponse {
+    pub power: u64,
+}
+
 #[cw_serde]
 pub struct FinalityProviderInfo {
     /// `btc_pk_hex` is the Bitcoin secp256k1 PK of this finality provider.
@@ -119,6 +302,100 @@ pub struct FinalityProviderInfo {
     /// `power` is the aggregated power of this finality provider.
     /// The power is calculated based on the amount of BTC delegated to this finality provider
     pub power: u64,
+    /// `self_stake` is the part of the power delegated by the finality provider's own address
+    #[serde(default)]
+    pub self_stake: u64,
+}
+
+#[cw_serde]
+pub struct UnbondingIntentsResponse {
+    pub intents: Vec<UnbondingIntent>,
+}
+
+#[cw_serde]
+pub struct StakerBindingResponse {
+    pub btc_pk_hex: String,
+    pub addr: Addr,
+}
+
+#[cw_serde]
+pub struct StakerBindingsResponse {
+    pub btc_pk_hexes: Vec<String>,
+}
+
+/// `NativeValidatorResponse` is a native validator of the consumer chain, as reported by its
+/// staking module
+#[cw_serde]
+pub struct NativeValidatorResponse {
+    pub operator_address: String,
+    pub jailed: bool,
+    /// `bonded` is whether the validator is in the active set of the chain
+    pub bonded: bool,
+    /// `tokens` is the native stake delegated to the validator
+    pub tokens: Uint128,
+}
+
+#[cw_serde]
+pub struct PendingRewardsResponse {
+    /// `rewards` is the total the staker can withdraw, i.e. its settled rewards plus the rewards
+    /// pending in each finality provider
+    pub rewards: Coin,
+    pub fp_rewards: Vec<FpPendingRewards>,
+    /// `cw20_rewards` is the total the staker can withdraw in each cw20 token, if any
+    #[serde(default)]
+    pub cw20_rewards: Vec<Cw20Coin>,
+}
+
+#[cw_serde]
+pub struct CommissionVestingsResponse {
+    pub vestings: Vec<CommissionVestingInfo>,
+}
+
+/// `CommissionVestingInfo` is a withdrawn commission vesting linearly from `start_height` to
+/// `end_height`
+#[cw_serde]
+pub struct CommissionVestingInfo {
+    pub start_height: u64,
+    pub end_height: u64,
+    /// `token` is the cw20 token of the commission, or `None` for the reward denom
+    pub token: Option<String>,
+    pub amount: Uint128,
+    /// `claimed` is the part of the amount paid out so far
+    pub claimed: Uint128,
+    /// `vested` is the part of the amount vested at the current height, claimed included
+    pub vested: Uint128,
+}
+
+#[cw_serde]
+pub struct RewardTokensResponse {
+    pub tokens: Vec<String>,
+}
+
+/// `FpPendingRewards` are the rewards of a staker pending in a finality provider
+#[cw_serde]
+pub struct FpPendingRewards {
+    pub fp_btc_pk_hex: String,
+    /// `stake` is the stake of the staker in the finality provider, in satoshi
+    pub stake: u64,
+    /// `pending` is the reward accrued since the stake last changed or was withdrawn from
+    pub pending: Uint128,
+}
+
+#[cw_serde]
+pub struct ClaimGrantsResponse {
+    pub grants: Vec<ClaimGrant>,
+}
+
+#[cw_serde]
+pub struct QuarantineResponse {
+    pub items: Vec<QuarantinedItem>,
+}
+
+#[cw_serde]
+pub struct DelegationStatusResponse {
+    pub status: DelegationStatus,
+    /// btc_tip_height is the last BTC tip height reported by the Babylon contract, if any
+    pub btc_tip_height: Option<u64>,
 }
 
 #[cw_serde]
@@ -0,0 +1,84 @@
+//! native queries the staking module of the consumer chain through gRPC, so that native
+//! validators can be cross-checked against finality providers
+use anybuf::{Anybuf, Bufany};
+use cosmwasm_std::{Binary, Deps, StdError, StdResult, Uint128};
+
+use crate::msg::NativeValidatorResponse;
+
+/// `BOND_STATUS_BONDED` is the status of the validators of the active set, see
+/// https://github.com/cosmos/cosmos-sdk/blob/main/proto/cosmos/staking/v1beta1/staking.proto
+const BOND_STATUS_BONDED: i32 = 3;
+
+/// query_validator returns the native validator with the given operator address
+pub fn query_validator(deps: Deps, operator_address: &str) -> StdResult<NativeValidatorResponse> {
+    // `validator_addr` is field 1 of `QueryValidatorRequest`
+    let query_data = Anybuf::new().append_string(1, operator_address).into_vec();
+    let res_data = deps.querier.query_grpc(
+        "/cosmos.staking.v1beta1.Query/Validator".to_string(),
+        Binary::new(query_data),
+    )?;
+    decode_validator(&res_data)
+}
+
+/// decode_validator decodes a `QueryValidatorResponse`
+fn decode_validator(data: &[u8]) -> StdResult<NativeValidatorResponse> {
+    let invalid = || StdError::generic_err("Invalid Validator response");
+    let res_decoded = Bufany::deserialize(data)
+        .map_err(|e| StdError::generic_err(format!("Decoding Validator response: {e:?}")))?;
+    // `validator` is field 1 of `QueryValidatorResponse`
+    let validator = res_decoded.message(1).ok_or_else(invalid)?;
+    // see `Validator` in https://github.com/cosmos/cosmos-sdk/blob/main/proto/cosmos/staking/v1beta1/staking.proto
+    let tokens = validator.string(5).ok_or_else(invalid)?;
+    Ok(NativeValidatorResponse {
+        operator_address: validator
+            .string(1)
+            .filter(|addr| !addr.is_empty())
+            .ok_or_else(invalid)?,
+        jailed: validator.bool(3).ok_or_else(invalid)?,
+        bonded: validator.int32(4).ok_or_else(invalid)? == BOND_STATUS_BONDED,
+        tokens: if tokens.is_empty() {
+            Uint128::zero()
+        } else {
+            tokens.parse()?
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_validator_works() {
+        let validator = Anybuf::new()
+            .append_string(1, "cosmosvaloper1abc")
+            .append_bool(3, false)
+            .append_int32(4, BOND_STATUS_BONDED)
+            .append_string(5, "1000000")
+            .append_string(6, "1000000.000000000000000000");
+        let data = Anybuf::new().append_message(1, &validator).into_vec();
+        assert_eq!(
+            decode_validator(&data).unwrap(),
+            NativeValidatorResponse {
+                operator_address: "cosmosvaloper1abc".to_string(),
+                jailed: false,
+                bonded: true,
+                tokens: Uint128::new(1_000_000),
+            }
+        );
+
+        // Jailed validators are unbonding, and may have no tokens left
+        let validator = Anybuf::new()
+            .append_string(1, "cosmosvaloper1abc")
+            .append_bool(3, true)
+            .append_int32(4, 2);
+        let data = Anybuf::new().append_message(1, &validator).into_vec();
+        let validator = decode_validator(&data).unwrap();
+        assert!(validator.jailed);
+        assert!(!validator.bonded);
+        assert_eq!(validator.tokens, Uint128::zero());
+
+        // The validator is required
+        decode_validator(&[]).unwrap_err();
+    }
+}
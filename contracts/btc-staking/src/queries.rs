@@ -1,23 +1,30 @@
-use std::str::FromStr;
-
-use bitcoin::hashes::Hash;
-use bitcoin::Txid;
-
 use cosmwasm_std::Order::Descending;
-use cosmwasm_std::{Deps, Order, StdResult};
+use cosmwasm_std::{coin, Deps, Env, Order, StdError, StdResult};
 use cw_storage_plus::Bound;
 
-use babylon_apis::btc_staking_api::FinalityProvider;
+use babylon_apis::btc_pk::BtcPkHex;
+use babylon_apis::btc_staking_api::{EventVerbosity, FinalityProvider, TxHash, HASH_SIZE};
+use babylon_apis::cw20::Cw20Coin;
+use babylon_apis::pagination::{self, page_limit};
 
 use crate::error::ContractError;
 use crate::msg::{
-    ActivatedHeightResponse, BtcDelegationsResponse, DelegationsByFPResponse, FinalityProviderInfo,
-    FinalityProvidersByPowerResponse, FinalityProvidersResponse,
+    ActivatedHeightResponse, BtcDelegationsResponse, ClaimGrantsResponse, CommissionVestingInfo,
+    CommissionVestingsResponse, DelegationStatusResponse, DelegationsByFPResponse,
+    FinalityProviderInfo, FinalityProvidersByPowerResponse, FinalityProvidersResponse,
+    FpPendingRewards, NativeValidatorResponse, PendingRewardsResponse, QuarantineResponse,
+    RewardTokensResponse, StakerBindingResponse, StakerBindingsResponse, UnbondingIntentsResponse,
+    VotingPowerResponse,
 };
 use crate::state::config::{Config, Params};
-use crate::state::config::{CONFIG, PARAMS};
+use crate::state::config::{CONFIG, EVENT_VERBOSITY, PARAMS};
+use crate::state::quarantine::{QuarantinedItem, QUARANTINE};
+use crate::state::rewards::{self, FpRewards, FP_REWARDS, REWARD_BALANCES};
 use crate::state::staking::{
-    fps, BtcDelegation, FinalityProviderState, ACTIVATED_HEIGHT, DELEGATIONS, FPS, FP_DELEGATIONS,
+    fps, BtcDelegation, FpDeposit, FpExit, FpJail, FpMetadataRecord, FpRemoval, UnbondingIntent,
+    ACTIVATED_HEIGHT, ADDR_STAKER_BINDINGS, BTC_TIP_HEIGHT, CLAIM_GRANTS, DELEGATIONS, FPS,
+    FP_DELEGATIONS, FP_DEPOSITS, FP_EXITS, FP_JAILS, FP_METADATA, FP_REMOVALS, STAKER_BINDINGS,
+    STAKER_DELEGATIONS, UNBONDING_INTENTS,
 };
 
 pub fn config(deps: Deps) -> StdResult<Config> {
@@ -28,34 +35,80 @@ pub fn params(deps: Deps) -> StdResult<Params> {
     PARAMS.load(deps.storage)
 }
 
-pub fn finality_provider(deps: Deps, btc_pk_hex: String) -> StdResult<FinalityProvider> {
-    FPS.load(deps.storage, &btc_pk_hex)
+pub fn event_verbosity(deps: Deps) -> StdResult<EventVerbosity> {
+    Ok(EVENT_VERBOSITY.may_load(deps.storage)?.unwrap_or_default())
 }
 
-// Settings for pagination
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
+pub fn finality_provider(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<FinalityProvider> {
+    FPS.load(deps.storage, &btc_pk_hex)
+}
 
 pub fn finality_providers(
     deps: Deps,
-    start_after: Option<String>,
+    start_after: Option<BtcPkHex>,
     limit: Option<u32>,
 ) -> StdResult<FinalityProvidersResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start_after = start_after.as_ref().map(|s| Bound::exclusive(&**s));
+    let limit = page_limit(limit);
     let fps = FPS
-        .range_raw(deps.storage, start_after, None, Order::Ascending)
+        .range_raw(
+            deps.storage,
+            pagination::start_after(start_after.as_deref()),
+            None,
+            Order::Ascending,
+        )
         .take(limit)
         .map(|item| item.map(|(_, v)| v))
         .collect::<StdResult<Vec<FinalityProvider>>>()?;
     Ok(FinalityProvidersResponse { fps })
 }
 
+/// load_by_tx_hash loads the entry keyed by the given staking tx hash, through `may_load`.
+/// `staking_tx_hash_hex`: The staking tx hash in hex, either reversed (as displayed by BTC
+/// explorers) or in wire order. The reversed form takes precedence
+fn load_by_tx_hash<T>(
+    may_load: impl Fn(&[u8; HASH_SIZE]) -> StdResult<Option<T>>,
+    staking_tx_hash_hex: &str,
+) -> Result<T, ContractError> {
+    let staking_tx_hash = TxHash::from_hex(staking_tx_hash_hex)?;
+    for key in [staking_tx_hash, staking_tx_hash.reversed()] {
+        if let Some(value) = may_load(key.as_byte_array())? {
+            return Ok(value);
+        }
+    }
+    Err(StdError::not_found(std::any::type_name::<T>()).into())
+}
+
 /// Get the delegation info by staking tx hash.
-/// `staking_tx_hash_hex`: The (reversed) staking tx hash, in hex
+/// `staking_tx_hash_hex`: The staking tx hash in hex, either reversed or in wire order
 pub fn delegation(deps: Deps, staking_tx_hash_hex: String) -> Result<BtcDelegation, ContractError> {
-    let staking_tx_hash = Txid::from_str(&staking_tx_hash_hex)?;
-    Ok(DELEGATIONS.load(deps.storage, staking_tx_hash.as_ref())?)
+    load_by_tx_hash(
+        |key| DELEGATIONS.may_load(deps.storage, key),
+        &staking_tx_hash_hex,
+    )
+}
+
+/// Get the lifecycle state of a delegation by staking tx hash.
+/// `staking_tx_hash_hex`: The staking tx hash in hex, either reversed or in wire order
+pub fn delegation_status(
+    deps: Deps,
+    staking_tx_hash_hex: String,
+) -> Result<DelegationStatusResponse, ContractError> {
+    let (staking_tx_hash, del) = load_by_tx_hash(
+        |key| {
+            Ok(DELEGATIONS
+                .may_load(deps.storage, key)?
+                .map(|del| (*key, del)))
+        },
+        &staking_tx_hash_hex,
+    )?;
+    let unbonding = UNBONDING_INTENTS
+        .may_load(deps.storage, &staking_tx_hash)?
+        .is_some_and(|intent| intent.processed);
+    let btc_tip_height = BTC_TIP_HEIGHT.may_load(deps.storage)?;
+    Ok(DelegationStatusResponse {
+        status: del.status(btc_tip_height, unbonding),
+        btc_tip_height,
+    })
 }
 
 /// Get list of delegations.
@@ -68,13 +121,12 @@ pub fn delegations(
     active: Option<bool>,
 ) -> Result<BtcDelegationsResponse, ContractError> {
     let active = active.unwrap_or_default();
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let limit = page_limit(limit);
     let start_after = start_after
         .as_ref()
-        .map(|s| Txid::from_str(s))
+        .map(|s| TxHash::from_hex(s))
         .transpose()?;
-    let start_after = start_after.as_ref().map(|s| s.as_ref());
-    let start_after = start_after.map(Bound::exclusive);
+    let start_after = pagination::start_after(start_after.as_ref().map(|s| s.as_ref()));
     let delegations = DELEGATIONS
         .range_raw(deps.storage, start_after, None, Order::Ascending)
         .filter(|item| {
@@ -90,21 +142,303 @@ pub fn delegations(
     Ok(BtcDelegationsResponse { delegations })
 }
 
+/// Get the unbonding intent of a delegation by staking tx hash.
+/// `staking_tx_hash_hex`: The staking tx hash in hex, either reversed or in wire order
+pub fn unbonding_intent(
+    deps: Deps,
+    staking_tx_hash_hex: String,
+) -> Result<UnbondingIntent, ContractError> {
+    load_by_tx_hash(
+        |key| UNBONDING_INTENTS.may_load(deps.storage, key),
+        &staking_tx_hash_hex,
+    )
+}
+
+/// Get list of unbonding intents.
+/// `start_after`: The (reversed) associated staking tx hash of the intent in hex, if provided.
+/// `pending`: List only intents whose voting power is still to be removed if true, otherwise list
+/// all intents.
+pub fn unbonding_intents(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    pending: Option<bool>,
+) -> Result<UnbondingIntentsResponse, ContractError> {
+    let pending = pending.unwrap_or_default();
+    let limit = page_limit(limit);
+    let start_after = start_after
+        .as_ref()
+        .map(|s| TxHash::from_hex(s))
+        .transpose()?;
+    let start_after = pagination::start_after(start_after.as_ref().map(|s| s.as_ref()));
+    let intents = UNBONDING_INTENTS
+        .range_raw(deps.storage, start_after, None, Order::Ascending)
+        .filter(|item| {
+            if let Ok((_, intent)) = item {
+                !pending || !intent.processed
+            } else {
+                true // don't filter errors
+            }
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, v)| v))
+        .collect::<StdResult<Vec<UnbondingIntent>>>()?;
+    Ok(UnbondingIntentsResponse { intents })
+}
+
+/// Get the consumer address bound to a BTC staker public key.
+/// `btc_pk_hex`: The BTC public key of the staker, in hex
+pub fn staker_binding(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<StakerBindingResponse> {
+    let addr = STAKER_BINDINGS.load(deps.storage, &btc_pk_hex)?;
+    Ok(StakerBindingResponse {
+        btc_pk_hex: btc_pk_hex.into(),
+        addr,
+    })
+}
+
+/// Get the operational metadata published by a finality provider.
+/// `btc_pk_hex`: The BTC public key of the finality provider, in hex
+pub fn finality_provider_metadata(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<FpMetadataRecord> {
+    FP_METADATA.load(deps.storage, &btc_pk_hex)
+}
+
+/// Get the exit status of a deregistered finality provider.
+/// `btc_pk_hex`: The BTC public key of the finality provider, in hex
+pub fn finality_provider_exit(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<FpExit> {
+    FP_EXITS.load(deps.storage, &btc_pk_hex)
+}
+
+pub fn finality_provider_jail(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<FpJail> {
+    FP_JAILS.load(deps.storage, &btc_pk_hex)
+}
+
+pub fn finality_provider_removal(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<FpRemoval> {
+    FP_REMOVALS.load(deps.storage, &btc_pk_hex)
+}
+
+pub fn finality_provider_deposit(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<FpDeposit> {
+    FP_DEPOSITS.load(deps.storage, &btc_pk_hex)
+}
+
+/// Get the BTC staker public keys bound to a consumer address.
+/// `start_after`: The BTC public key to start after, in hex, if provided.
+pub fn staker_bindings_by_addr(
+    deps: Deps,
+    addr: String,
+    start_after: Option<BtcPkHex>,
+    limit: Option<u32>,
+) -> StdResult<StakerBindingsResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let limit = page_limit(limit);
+    let start_after = pagination::start_after(start_after.as_deref());
+    let btc_pk_hexes = ADDR_STAKER_BINDINGS
+        .prefix(&addr)
+        .keys(deps.storage, start_after, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(StakerBindingsResponse { btc_pk_hexes })
+}
+
+/// Get the rewards a staker address can withdraw, and the rewards pending in each finality
+/// provider it stakes in
+pub fn pending_rewards(deps: Deps, staker_addr: String) -> StdResult<PendingRewardsResponse> {
+    let staker = deps.api.addr_validate(&staker_addr)?;
+    let mut total = REWARD_BALANCES
+        .may_load(deps.storage, staker.as_str())?
+        .unwrap_or_default();
+    let fp_rewards = rewards::pending_rewards(deps.storage, staker.as_str())?
+        .into_iter()
+        .map(|(fp_btc_pk_hex, rewards, pending)| {
+            total += pending;
+            FpPendingRewards {
+                fp_btc_pk_hex,
+                stake: rewards.stake,
+                pending,
+            }
+        })
+        .collect();
+    let mut cw20_rewards = rewards::pending_cw20(deps.storage, staker.as_str())?;
+    for (asset, commission) in rewards::commissions(deps.storage, staker.as_str())? {
+        match asset.as_str() {
+            rewards::NATIVE_ASSET => total += commission,
+            _ => *cw20_rewards.entry(asset).or_default() += commission,
+        }
+    }
+    let cw20_rewards = cw20_rewards
+        .into_iter()
+        .map(|(address, amount)| Cw20Coin { address, amount })
+        .collect();
+    let denom = PARAMS.load(deps.storage)?.reward_denom;
+    Ok(PendingRewardsResponse {
+        rewards: coin(total.u128(), denom),
+        fp_rewards,
+        cw20_rewards,
+    })
+}
+
+/// Get the withdrawn commissions of an address vesting, with the part vested at the current
+/// height
+pub fn commission_vestings(
+    deps: Deps,
+    env: Env,
+    addr: String,
+) -> StdResult<CommissionVestingsResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let vestings = rewards::commission_vestings(deps.storage, addr.as_str())?
+        .into_iter()
+        .map(|((start_height, asset), vesting)| CommissionVestingInfo {
+            start_height,
+            end_height: vesting.end_height,
+            token: (asset != rewards::NATIVE_ASSET).then_some(asset),
+            amount: vesting.amount,
+            claimed: vesting.claimed,
+            vested: vesting.vested(start_height, env.block.height),
+        })
+        .collect();
+    Ok(CommissionVestingsResponse { vestings })
+}
+
+pub fn reward_tokens(deps: Deps) -> StdResult<RewardTokensResponse> {
+    let tokens = rewards::CW20_REWARD_TOKENS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|token| token.map(String::from))
+        .collect::<StdResult<_>>()?;
+    Ok(RewardTokensResponse { tokens })
+}
+
+pub fn finality_provider_rewards(deps: Deps, btc_pk_hex: BtcPkHex) -> StdResult<FpRewards> {
+    Ok(FP_REWARDS
+        .may_load(deps.storage, &btc_pk_hex)?
+        .unwrap_or_default())
+}
+
+/// Get the reward claim grants recorded by a staker address.
+/// `start_after`: The grantee address to start after, if provided.
+pub fn claim_grants(
+    deps: Deps,
+    staker: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ClaimGrantsResponse> {
+    let staker = deps.api.addr_validate(&staker)?;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let limit = page_limit(limit);
+    let grants = CLAIM_GRANTS
+        .prefix(&staker)
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, grant)| grant))
+        .collect::<StdResult<_>>()?;
+    Ok(ClaimGrantsResponse { grants })
+}
+
+pub fn quarantined_item(deps: Deps, id: String) -> Result<QuarantinedItem, ContractError> {
+    QUARANTINE
+        .may_load(deps.storage, &id)?
+        .ok_or(ContractError::QuarantinedItemNotFound(id))
+}
+
+pub fn quarantine(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<QuarantineResponse> {
+    let limit = page_limit(limit);
+    let start_after = pagination::start_after(start_after.as_deref());
+    let items = QUARANTINE
+        .range(deps.storage, start_after, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, item)| item))
+        .collect::<StdResult<_>>()?;
+    Ok(QuarantineResponse { items })
+}
+
 /// Delegation hashes by FP query.
 ///
 /// `btc_pk_hex`: The BTC public key of the finality provider, in hex
 pub fn delegations_by_fp(
     deps: Deps,
-    btc_pk_hex: String,
+    btc_pk_hex: BtcPkHex,
+    start_after: Option<String>,
+    limit: Option<u32>,
 ) -> Result<DelegationsByFPResponse, ContractError> {
     let tx_hashes = FP_DELEGATIONS.load(deps.storage, &btc_pk_hex)?;
+    // The hashes are kept in order of registration, so the page resumes after the cursor's
+    // position
+    let start = match start_after {
+        Some(start_after) => {
+            let start_after = TxHash::from_hex(&start_after)?;
+            tx_hashes
+                .iter()
+                .position(|h| h.as_slice() == start_after.as_byte_array())
+                .map_or(tx_hashes.len(), |pos| pos + 1)
+        }
+        None => 0,
+    };
     let tx_hashes = tx_hashes
         .iter()
-        .map(|h| Ok(Txid::from_slice(h)?.to_string()))
+        .skip(start)
+        .take(page_limit(limit))
+        .map(|h| Ok(TxHash::from_slice(h)?.to_string()))
         .collect::<Result<_, ContractError>>()?;
     Ok(DelegationsByFPResponse { hashes: tx_hashes })
 }
 
+/// Get a native validator of the consumer chain, through a gRPC query to its staking module
+pub fn native_validator(
+    deps: Deps,
+    operator_address: String,
+) -> Result<NativeValidatorResponse, ContractError> {
+    #[cfg(feature = "native-staking")]
+    {
+        Ok(crate::native::query_validator(deps, &operator_address)?)
+    }
+    #[cfg(not(feature = "native-staking"))]
+    {
+        let _ = (deps, operator_address);
+        Err(ContractError::NativeStakingDisabled)
+    }
+}
+
+/// Get the delegations of a staker address, using the staker index.
+/// `start_after` is the staking tx hash (in hex) of the delegation to start after
+pub fn delegations_by_staker(
+    deps: Deps,
+    staker_addr: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    active: Option<bool>,
+) -> Result<BtcDelegationsResponse, ContractError> {
+    let active = active.unwrap_or_default();
+    let limit = page_limit(limit);
+    let start_after = start_after
+        .as_ref()
+        .map(|s| TxHash::from_hex(s))
+        .transpose()?;
+    let start_after = pagination::start_after(start_after.as_ref().map(|s| s.as_ref()));
+    let delegations = STAKER_DELEGATIONS
+        .prefix(&staker_addr)
+        .keys(deps.storage, start_after, None, Order::Ascending)
+        .map(|key| DELEGATIONS.load(deps.storage, &key?))
+        .filter(|item| {
+            if let Ok(del) = item {
+                !active || del.is_active()
+            } else {
+                true // don't filter errors
+            }
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(BtcDelegationsResponse { delegations })
+}
+
 /// Active / all delegations by FP convenience query.
 ///
 /// This is an alternative to `delegations_by_fp` that returns the actual delegations instead of
@@ -120,7 +454,7 @@ pub fn active_delegations_by_fp(
     let tx_hashes = FP_DELEGATIONS.load(deps.storage, &btc_pk_hex)?;
     let delegations = tx_hashes
         .iter()
-        .map(|h| Ok(DELEGATIONS.load(deps.storage, Txid::from_slice(h)?.as_ref())?))
+        .map(|h| Ok(DELEGATIONS.load(deps.storage, TxHash::from_slice(h)?.as_ref())?))
         .filter(|item| {
             if let Ok(del) = item {
                 !active || del.is_active()
@@ -134,18 +468,19 @@ pub fn active_delegations_by_fp(
 
 pub fn finality_provider_info(
     deps: Deps,
-    btc_pk_hex: String,
+    btc_pk_hex: BtcPkHex,
     height: Option<u64>,
 ) -> Result<FinalityProviderInfo, ContractError> {
     let fp_state = match height {
         Some(h) => fps().may_load_at_height(deps.storage, &btc_pk_hex, h),
         None => fps().may_load(deps.storage, &btc_pk_hex),
     }?
-    .ok_or_else(|| ContractError::FinalityProviderNotFound(btc_pk_hex.clone()))?;
+    .ok_or_else(|| ContractError::FinalityProviderNotFound(btc_pk_hex.to_string()))?;
 
     Ok(FinalityProviderInfo {
-        btc_pk_hex,
-        power: fp_state.power,
+        btc_pk_hex: btc_pk_hex.into(),
+        power: fp_state.voting_power(),
+        self_stake: fp_state.self_stake,
     })
 }
 
@@ -154,20 +489,48 @@ pub fn finality_providers_by_power(
     start_after: Option<FinalityProviderInfo>,
     limit: Option<u32>,
 ) -> StdResult<FinalityProvidersByPowerResponse> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|fpp| Bound::exclusive((fpp.power, fpp.btc_pk_hex.clone())));
-    let fps = fps()
+    let limit = page_limit(limit);
+    let max_active = PARAMS.load(deps.storage)?.max_active_finality_providers as usize;
+    // Walk the active set from the top, as the page cursor does not tell its rank in it
+    let cursor = start_after.map(|fpp| (fpp.power, fpp.btc_pk_hex));
+    let mut active = vec![];
+    for item in fps()
         .idx
         .power
-        .range(deps.storage, None, start, Descending)
-        .take(limit)
-        .map(|item| {
-            let (btc_pk_hex, FinalityProviderState { power }) = item?;
-            Ok(FinalityProviderInfo { btc_pk_hex, power })
-        })
-        .collect::<StdResult<Vec<_>>>()?;
+        .range(deps.storage, None, None, Descending)
+        .take(max_active)
+    {
+        let (btc_pk_hex, fp_state) = item?;
+        let power = fp_state.voting_power();
+        if let Some((cursor_power, cursor_pk)) = &cursor {
+            if (power, &btc_pk_hex) >= (*cursor_power, cursor_pk) {
+                continue;
+            }
+        }
+        active.push(FinalityProviderInfo {
+            btc_pk_hex,
+            power,
+            self_stake: fp_state.self_stake,
+        });
+        if active.len() == limit {
+            break;
+        }
+    }
 
-    Ok(FinalityProvidersByPowerResponse { fps })
+    Ok(FinalityProvidersByPowerResponse { fps: active })
+}
+
+/// Voting power of a finality provider at a height, or zero if it was unknown or jailed
+pub fn voting_power_at_height(
+    deps: Deps,
+    fp: BtcPkHex,
+    height: u64,
+) -> StdResult<VotingPowerResponse> {
+    let power = fps()
+        .may_load_at_height(deps.storage, &fp, height)?
+        .map(|fp_state| fp_state.voting_power())
+        .unwrap_or_default();
+    Ok(VotingPowerResponse { power })
 }
 
 pub fn activated_height(deps: Deps) -> Result<ActivatedHeightResponse, ContractError> {
@@ -185,14 +548,16 @@ mod tests {
     use cosmwasm_std::StdError::NotFound;
     use cosmwasm_std::{from_json, Env, Storage};
 
-    use babylon_apis::btc_staking_api::{FinalityProvider, UnbondedBtcDelegation};
+    use babylon_apis::btc_staking_api::{FinalityProvider, UnbondedBtcDelegation, HASH_SIZE};
     use test_utils::{create_new_finality_provider, get_btc_del_unbonding_sig};
 
     use crate::contract::{execute, instantiate};
     use crate::error::ContractError;
     use crate::msg::{ExecuteMsg, FinalityProviderInfo, InstantiateMsg};
     use crate::staking::tests::staking_tx_hash;
-    use crate::state::staking::{BtcDelegation, FinalityProviderState, FP_STATE_KEY};
+    use crate::state::staking::{
+        BtcDelegation, FinalityProviderState, FP_STATE_KEY, STAKER_DELEGATIONS,
+    };
     use crate::test_utils::staking_params;
 
     const CREATOR: &str = "creator";
@@ -224,6 +589,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -263,9 +629,10 @@ mod tests {
 
         // Query finality providers with start_after
         let fp_pk = fps[0].btc_pk_hex.clone();
-        let fps = crate::queries::finality_providers(deps.as_ref(), Some(fp_pk), None)
-            .unwrap()
-            .fps;
+        let fps =
+            crate::queries::finality_providers(deps.as_ref(), Some(fp_pk.parse().unwrap()), None)
+                .unwrap()
+                .fps;
         assert_eq!(fps.len(), 1);
         assert!(fps[0] == fp1 || fps[0] == fp2);
     }
@@ -283,6 +650,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -341,6 +709,14 @@ mod tests {
 
         assert_eq!(dels.len(), 1);
         assert_eq!(dels[0], sorted_dels[1]);
+
+        // Query a delegation by its staking tx hash, in both byte orders
+        let hash = staking_tx_hash(&sorted_dels[1]);
+        for hash_hex in [hash.to_hex(), hex::encode(hash.as_byte_array())] {
+            let del = crate::queries::delegation(deps.as_ref(), hash_hex).unwrap();
+            assert_eq!(del, sorted_dels[1]);
+        }
+        crate::queries::delegation(deps.as_ref(), "00".repeat(HASH_SIZE)).unwrap_err();
     }
 
     #[test]
@@ -356,6 +732,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -443,6 +820,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -476,19 +854,130 @@ mod tests {
         execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         // Query delegations by finality provider
-        let dels1 = crate::queries::delegations_by_fp(deps.as_ref(), fp1_pk.clone())
-            .unwrap()
-            .hashes;
+        let dels1 =
+            crate::queries::delegations_by_fp(deps.as_ref(), fp1_pk.parse().unwrap(), None, None)
+                .unwrap()
+                .hashes;
         assert_eq!(dels1.len(), 1);
-        let dels2 = crate::queries::delegations_by_fp(deps.as_ref(), fp2_pk.clone())
-            .unwrap()
-            .hashes;
+        let dels2 =
+            crate::queries::delegations_by_fp(deps.as_ref(), fp2_pk.parse().unwrap(), None, None)
+                .unwrap()
+                .hashes;
         assert_eq!(dels2.len(), 1);
         assert_ne!(dels1[0], dels2[0]);
-        let err = crate::queries::delegations_by_fp(deps.as_ref(), "f3".to_string()).unwrap_err();
+        let err = crate::queries::delegations_by_fp(
+            deps.as_ref(),
+            "f3".repeat(32).parse().unwrap(),
+            None,
+            None,
+        )
+        .unwrap_err();
         assert!(matches!(err, ContractError::Std(NotFound { .. })));
     }
 
+    #[test]
+    fn test_delegations_by_staker() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp1 = create_new_finality_provider(1);
+        let fp1_pk = new_fp1.btc_pk_hex.clone();
+        let del1 = test_utils::get_derived_btc_delegation(1, &[1]);
+        let del2 = test_utils::get_derived_btc_delegation(2, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp1],
+            active_del: vec![del1.clone(), del2.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Each delegation is listed under its staker address
+        for del in [&del1, &del2] {
+            let dels = crate::queries::delegations_by_staker(
+                deps.as_ref(),
+                del.staker_addr.clone(),
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+            .delegations;
+            assert!(dels.contains(&del.into()));
+            assert!(dels.iter().all(|d| d.staker_addr == del.staker_addr));
+        }
+        let dels = crate::queries::delegations_by_staker(
+            deps.as_ref(),
+            "unknown".to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(dels.delegations.is_empty());
+
+        // The delegations of an FP are paginated
+        let page1 = crate::queries::delegations_by_fp(
+            deps.as_ref(),
+            fp1_pk.parse().unwrap(),
+            None,
+            Some(1),
+        )
+        .unwrap()
+        .hashes;
+        assert_eq!(page1.len(), 1);
+        let page2 = crate::queries::delegations_by_fp(
+            deps.as_ref(),
+            fp1_pk.parse().unwrap(),
+            Some(page1[0].clone()),
+            None,
+        )
+        .unwrap()
+        .hashes;
+        let all =
+            crate::queries::delegations_by_fp(deps.as_ref(), fp1_pk.parse().unwrap(), None, None)
+                .unwrap()
+                .hashes;
+        assert_eq!(all.len(), 2);
+        assert_eq!([page1, page2].concat(), all);
+
+        // The staker index is backfilled upon migration, in chunks
+        STAKER_DELEGATIONS.clear(deps.as_mut().storage);
+        let (count, cursor) =
+            crate::state::staking::backfill_staker_delegations(deps.as_mut().storage, None, 1)
+                .unwrap();
+        assert_eq!(count, 1);
+        let (count, _) = crate::state::staking::backfill_staker_delegations(
+            deps.as_mut().storage,
+            cursor.as_deref(),
+            10,
+        )
+        .unwrap();
+        assert_eq!(count, 1);
+        let dels = crate::queries::delegations_by_staker(
+            deps.as_ref(),
+            del1.staker_addr.clone(),
+            None,
+            None,
+            Some(true),
+        )
+        .unwrap()
+        .delegations;
+        assert!(dels.contains(&(&del1).into()));
+    }
+
     #[test]
     fn test_active_delegations_by_fp() {
         let mut deps = mock_dependencies();
@@ -502,6 +991,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -594,6 +1084,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -630,52 +1121,72 @@ mod tests {
 
         // Query finality provider info
         let fp =
-            crate::queries::finality_provider_info(deps.as_ref(), fp1_pk.clone(), None).unwrap();
+            crate::queries::finality_provider_info(deps.as_ref(), fp1_pk.parse().unwrap(), None)
+                .unwrap();
         assert_eq!(
             fp,
             FinalityProviderInfo {
                 btc_pk_hex: fp1_pk.clone(),
                 power: 250,
+                self_stake: 0,
             }
         );
 
         // Query finality provider info with same height as execute call
-        let fp = crate::queries::finality_provider_info(deps.as_ref(), fp1_pk.clone(), Some(11))
-            .unwrap();
+        let fp = crate::queries::finality_provider_info(
+            deps.as_ref(),
+            fp1_pk.parse().unwrap(),
+            Some(11),
+        )
+        .unwrap();
         assert_eq!(
             fp,
             FinalityProviderInfo {
                 btc_pk_hex: fp1_pk.clone(),
                 power: 0, // Historical data is not checkpoint yet
+                self_stake: 0,
             }
         );
 
         // Query finality provider info with past height as execute call
-        let fp = crate::queries::finality_provider_info(deps.as_ref(), fp1_pk.clone(), Some(12))
-            .unwrap();
+        let fp = crate::queries::finality_provider_info(
+            deps.as_ref(),
+            fp1_pk.parse().unwrap(),
+            Some(12),
+        )
+        .unwrap();
         assert_eq!(
             fp,
             FinalityProviderInfo {
                 btc_pk_hex: fp1_pk.clone(),
                 power: 250,
+                self_stake: 0,
             }
         );
 
         // Query finality provider info with some larger height
-        let fp = crate::queries::finality_provider_info(deps.as_ref(), fp1_pk.clone(), Some(1000))
-            .unwrap();
+        let fp = crate::queries::finality_provider_info(
+            deps.as_ref(),
+            fp1_pk.parse().unwrap(),
+            Some(1000),
+        )
+        .unwrap();
         assert_eq!(
             fp,
             FinalityProviderInfo {
                 btc_pk_hex: fp1_pk.clone(),
                 power: 250,
+                self_stake: 0,
             }
         );
 
         // Query finality provider info for a non-existent FP
-        let non_existent_fp = "010203040506".to_string();
-        let result =
-            crate::queries::finality_provider_info(deps.as_ref(), non_existent_fp.clone(), None);
+        let non_existent_fp = "01".repeat(32);
+        let result = crate::queries::finality_provider_info(
+            deps.as_ref(),
+            non_existent_fp.parse().unwrap(),
+            None,
+        );
 
         // Assert that the result is a FinalityProviderNotFound error
         assert!(matches!(
@@ -697,6 +1208,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -735,7 +1247,14 @@ mod tests {
         // Deserialize result
         let fp_state: FinalityProviderState = from_json(fp_state_raw).unwrap();
 
-        assert_eq!(fp_state, FinalityProviderState { power: 100 });
+        assert_eq!(
+            fp_state,
+            FinalityProviderState {
+                power: 100,
+                self_stake: 0,
+                jailed: false,
+            }
+        );
     }
 
     #[test]
@@ -751,6 +1270,7 @@ mod tests {
             InstantiateMsg {
                 params: Some(params),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
@@ -800,16 +1320,19 @@ mod tests {
             FinalityProviderInfo {
                 btc_pk_hex: fp2_pk.clone(),
                 power: 225,
+                self_stake: 0,
             }
         });
         // fp1 and fp3 can be in arbitrary order
         let fp1_info = FinalityProviderInfo {
             btc_pk_hex: fp1_pk.clone(),
             power: 100,
+            self_stake: 0,
         };
         let fp3_info = FinalityProviderInfo {
             btc_pk_hex: fp3_pk.clone(),
             power: 100,
+            self_stake: 0,
         };
         assert!(
             (fps[1] == fp1_info && fps[2] == fp3_info)
@@ -825,6 +1348,7 @@ mod tests {
             FinalityProviderInfo {
                 btc_pk_hex: fp2_pk.clone(),
                 power: 225,
+                self_stake: 0,
             }
         });
         assert!(fps[1] == fp1_info || fps[1] == fp3_info);
@@ -837,4 +1361,79 @@ mod tests {
         assert_eq!(fps.len(), 1);
         assert!(fps[0] == fp1_info || fps[0] == fp3_info);
     }
+
+    #[test]
+    fn active_set_and_voting_power_at_height_work() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        // Cap the active set at two finality providers
+        let params = crate::state::config::Params {
+            max_active_finality_providers: 2,
+            ..staking_params()
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fps: Vec<_> = (1..=3).map(create_new_finality_provider).collect();
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: new_fps.clone(),
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let mut del1 = test_utils::get_derived_btc_delegation(1, &[1]);
+        let mut del2 = test_utils::get_derived_btc_delegation(2, &[2]);
+        let mut del3 = test_utils::get_derived_btc_delegation(3, &[2]);
+        del1.total_sat = 100;
+        del2.total_sat = 150;
+        del3.total_sat = 75;
+        let del_env = mock_env();
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![del1, del2, del3],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), del_env.clone(), info, msg).unwrap();
+
+        // Only the top two finality providers are in the active set, whatever the page
+        let fps = crate::queries::finality_providers_by_power(deps.as_ref(), None, None)
+            .unwrap()
+            .fps;
+        assert_eq!(fps.len(), 2);
+        assert_eq!(fps[0].btc_pk_hex, new_fps[1].btc_pk_hex);
+        assert_eq!(fps[0].power, 225);
+        assert_eq!(fps[1].btc_pk_hex, new_fps[0].btc_pk_hex);
+        let fps =
+            crate::queries::finality_providers_by_power(deps.as_ref(), Some(fps[1].clone()), None)
+                .unwrap()
+                .fps;
+        assert!(fps.is_empty());
+
+        // The voting power is snapshotted by height
+        let fp2: babylon_apis::btc_pk::BtcPkHex = new_fps[1].btc_pk_hex.parse().unwrap();
+        let height = del_env.block.height;
+        let power =
+            crate::queries::voting_power_at_height(deps.as_ref(), fp2.clone(), height).unwrap();
+        assert_eq!(power.power, 0);
+        let power = crate::queries::voting_power_at_height(deps.as_ref(), fp2, height + 1).unwrap();
+        assert_eq!(power.power, 225);
+        // Unknown finality providers have no voting power
+        let unknown: babylon_apis::btc_pk::BtcPkHex = "02".repeat(32).parse().unwrap();
+        let power =
+            crate::queries::voting_power_at_height(deps.as_ref(), unknown, height + 1).unwrap();
+        assert_eq!(power.power, 0);
+    }
 }
@@ -0,0 +1,27 @@
+//! schema enumerates the public API of the contract, for the generation of its JSON schema
+//! files, and the snapshot test checking the checked-in ones
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg};
+use cosmwasm_schema::{generate_api, Api};
+
+/// api returns the public API of the contract
+pub fn api() -> Api {
+    generate_api! {
+        instantiate: InstantiateMsg,
+        query: QueryMsg,
+        migrate: MigrateMsg,
+        execute: ExecuteMsg,
+        sudo: SudoMsg,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn schema_files_are_up_to_date() {
+        let schema_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+        babylon_apis::schema::assert_schema_files(api(), &schema_dir);
+    }
+}
@@ -1,35 +1,56 @@
+use std::collections::BTreeMap;
+
 use bitcoin::absolute::LockTime;
 use bitcoin::consensus::deserialize;
 use bitcoin::hashes::Hash;
 use bitcoin::{Transaction, Txid};
-use cosmwasm_std::{DepsMut, Env, Event, MessageInfo, Response, Storage};
+use cosmwasm_std::{
+    coin, coins, from_json, to_json_binary, BankMsg, Binary, DepsMut, Env, Event, MessageInfo,
+    Order, OverflowError, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw_storage_plus::Bound;
+use cw_utils::{must_pay, nonpayable};
 use hex::ToHex;
 
-use std::str::FromStr;
-
 use crate::error::ContractError;
-use crate::state::config::{ADMIN, CONFIG, PARAMS};
+use crate::msg::{BtcStakingResult, Genesis};
+use crate::state::config::{
+    DuplicateFps, Params, ADMIN, BABYLON_CONFIG_CHECKED, CONFIG, EVENT_VERBOSITY, PARAMS,
+};
+use crate::state::migration::{ensure_sections_active, SECTION_REWARDS};
+use crate::state::quarantine::{QuarantinedItem, QuarantinedOp, QUARANTINE};
+use crate::state::rewards::{self, FP_REWARDS};
 use crate::state::staking::{
-    fps, BtcDelegation, FinalityProviderState, ACTIVATED_HEIGHT, DELEGATIONS, DELEGATION_FPS, FPS,
-    FP_DELEGATIONS,
+    can_claim, fps, BtcDelegation, ClaimGrant, DelegationStatus, FinalityProviderState, FpDeposit,
+    FpExit, FpExitStatus, FpJail, FpMetadataRecord, FpRemoval, UnbondingIntent, ACTIVATED_HEIGHT,
+    ADDR_STAKER_BINDINGS, BTC_TIP_HEIGHT, CLAIM_GRANTS, DELEGATIONS, DELEGATION_EXPIRIES,
+    DELEGATION_FPS, FPS, FP_DELEGATIONS, FP_DEPOSITS, FP_EXITS, FP_JAILS, FP_METADATA, FP_REMOVALS,
+    PENDING_UNBONDINGS, STAKER_BINDINGS, STAKER_DELEGATIONS, UNBONDING_INTENTS,
 };
 use crate::validation::{
-    verify_active_delegation, verify_new_fp, verify_slashed_delegation, verify_undelegation,
+    verify_active_delegation, verify_fp_metadata, verify_new_fp, verify_slashed_delegation,
+    verify_staker_binding, verify_staking_tx, verify_undelegation,
 };
+use babylon_apis::btc_pk::BtcPkHex;
 use babylon_apis::btc_staking_api::{
-    ActiveBtcDelegation, FinalityProvider, NewFinalityProvider, SlashedBtcDelegation,
-    UnbondedBtcDelegation,
+    ActiveBtcDelegation, Cw20HookMsg, EventVerbosity, FinalityProvider, FpMetadata,
+    NewFinalityProvider, RewardInfo, SlashedBtcDelegation, TxHash, UnbondedBtcDelegation,
+    HASH_SIZE,
 };
+use babylon_apis::cw20::{cw20_transfer_msg, Cw20ReceiveMsg};
+use babylon_apis::math::add_sat;
 
+use babylon_apis::error::format_error;
 use babylon_apis::Validate;
 use babylon_bindings::BabylonMsg;
 use babylon_contract::msg::btc_header::BtcHeaderResponse;
 
 use babylon_contract::msg::contract::QueryMsg as BabylonQueryMsg;
+use babylon_contract::state::config::Config as BabylonConfig;
 
 /// handle_btc_staking handles the BTC staking operations
 pub fn handle_btc_staking(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: &MessageInfo,
     new_fps: &[NewFinalityProvider],
@@ -42,39 +63,281 @@ pub fn handle_btc_staking(
         return Err(ContractError::Unauthorized);
     }
 
-    let mut res = Response::new();
+    // Ensure the params are consistent with the Babylon contract before processing anything
+    check_babylon_config(&mut deps)?;
+
+    let verbosity = EVENT_VERBOSITY.may_load(deps.storage)?.unwrap_or_default();
+    // Events of state transitions resulting from the operations
+    let mut events = vec![];
+    // Events of the operations themselves, emitted in debug mode only
+    let mut op_events = vec![];
+
+    // Remove the voting power of matured unbonding intents
+    events.extend(process_unbonding_intents(deps.storage, env.block.height)?);
 
+    let mut result = BtcStakingResult::default();
+
+    let params = PARAMS.load(deps.storage)?;
     for fp in new_fps {
-        handle_new_fp(deps.storage, fp, env.block.height)?;
-        // TODO: Add event
+        // Quarantine finality providers failing validation, instead of failing the whole batch
+        let fp = match validate_new_fp(fp) {
+            Ok(fp) => fp,
+            Err(err) => {
+                let item = QuarantinedItem {
+                    id: fp.btc_pk_hex.clone(),
+                    op: QuarantinedOp::NewFp(fp.clone()),
+                    reason: format_error(err.code(), err),
+                    height: env.block.height,
+                };
+                events.push(quarantine(deps.storage, &item)?);
+                result.quarantined.push(item.id);
+                continue;
+            }
+        };
+        if params.duplicate_fps != DuplicateFps::Reject && FPS.has(deps.storage, &fp.btc_pk_hex) {
+            events.push(handle_duplicate_fp(
+                deps.storage,
+                params.duplicate_fps,
+                &fp,
+            )?);
+            continue;
+        }
+        register_new_fp(deps.storage, &fp, env.block.height)?;
+        result.new_fps.push(fp.btc_pk_hex.clone());
+        op_events.push(
+            Event::new("new_finality_provider")
+                .add_attribute("btc_pk_hex", &fp.btc_pk_hex)
+                .add_attribute("consumer_id", &fp.consumer_id),
+        );
     }
 
     // Process active delegations
     for del in active_delegations {
-        handle_active_delegation(deps.storage, env.block.height, del)?;
-        // TODO: Add event
+        let (del, staking_tx) = match validate_active_delegation(&params, del) {
+            Ok(validated) => validated,
+            Err(err) => {
+                let item = QuarantinedItem {
+                    id: active_delegation_id(del),
+                    op: QuarantinedOp::ActiveDel(del.clone()),
+                    reason: format_error(err.code(), err),
+                    height: env.block.height,
+                };
+                events.push(quarantine(deps.storage, &item)?);
+                result.quarantined.push(item.id);
+                continue;
+            }
+        };
+        // Skip already known delegations, so that redelivered packets are processed safely
+        let staking_tx_hash = TxHash::from(staking_tx.txid());
+        if DELEGATIONS.has(deps.storage, staking_tx_hash.as_ref()) {
+            events.push(
+                Event::new("active_delegation")
+                    .add_attribute("staking_tx_hash", staking_tx_hash.to_string())
+                    .add_attribute("duplicate", "true"),
+            );
+            continue;
+        }
+        let (staking_tx_hash, evs) =
+            register_active_delegation(deps.storage, &params, env.block.height, &del, &staking_tx)?;
+        events.extend(evs);
+        op_events.push(
+            Event::new("active_delegation")
+                .add_attribute("staking_tx_hash", staking_tx_hash.to_string())
+                .add_attribute("total_sat", del.total_sat.to_string())
+                .add_attribute("duplicate", "false"),
+        );
+        result.active_delegations.push(staking_tx_hash.to_string());
     }
 
     // Process slashed delegations
     for del in slashed_delegations {
-        let ev = handle_slashed_delegation(deps.storage, env.block.height, del)?;
-        res = res.add_event(ev);
+        events.extend(handle_slashed_delegation(
+            deps.storage,
+            env.block.height,
+            del,
+        )?);
+        let staking_tx_hash = TxHash::from_hex(&del.staking_tx_hash)?.to_hex();
+        op_events.push(
+            Event::new("slashed_delegation").add_attribute("staking_tx_hash", &staking_tx_hash),
+        );
+        result.slashed_delegations.push(staking_tx_hash);
     }
 
     // Process undelegations
     for undel in unbonded_delegations {
-        let ev = handle_undelegation(deps.storage, env.block.height, undel)?;
-        res = res.add_event(ev);
+        events.extend(handle_undelegation(deps.storage, env.block.height, undel)?);
+        let staking_tx_hash = TxHash::from_hex(&undel.staking_tx_hash)?.to_hex();
+        op_events.push(
+            Event::new("unbonded_delegation").add_attribute("staking_tx_hash", &staking_tx_hash),
+        );
+        result.unbonded_delegations.push(staking_tx_hash);
     }
 
-    Ok(res)
+    let res = Response::new();
+    let res = match verbosity {
+        EventVerbosity::Minimal => res.add_event(summary_event(&result)),
+        EventVerbosity::Standard => res.add_events(events),
+        EventVerbosity::Debug => res.add_events(op_events).add_events(events),
+    };
+
+    Ok(res.set_data(to_json_binary(&result)?))
+}
+
+/// summary_event returns the event summarising the accepted BTC staking operations
+fn summary_event(result: &BtcStakingResult) -> Event {
+    Event::new("btc_staking")
+        .add_attribute("new_fps", result.new_fps.len().to_string())
+        .add_attribute(
+            "active_delegations",
+            result.active_delegations.len().to_string(),
+        )
+        .add_attribute(
+            "slashed_delegations",
+            result.slashed_delegations.len().to_string(),
+        )
+        .add_attribute(
+            "unbonded_delegations",
+            result.unbonded_delegations.len().to_string(),
+        )
+        .add_attribute("quarantined", result.quarantined.len().to_string())
+}
+
+/// quarantine keeps aside a BTC staking operation that failed validation
+fn quarantine(storage: &mut dyn Storage, item: &QuarantinedItem) -> StdResult<Event> {
+    QUARANTINE.save(storage, &item.id, item)?;
+    Ok(Event::new("quarantined_btc_staking")
+        .add_attribute("id", &item.id)
+        .add_attribute("reason", &item.reason))
+}
+
+/// active_delegation_id returns the id of an active delegation in the quarantine, i.e. its
+/// staking tx hash, or the hash of the raw staking tx if it cannot be parsed
+fn active_delegation_id(del: &ActiveBtcDelegation) -> String {
+    let txid = match deserialize::<Transaction>(&del.staking_tx) {
+        Ok(staking_tx) => staking_tx.txid(),
+        Err(_) => Txid::hash(&del.staking_tx),
+    };
+    TxHash::from(txid).to_string()
+}
+
+/// handle_reprocess_quarantined processes a quarantined BTC staking operation again, e.g. after
+/// a parameters fix, and removes it from the quarantine if it is accepted
+pub fn handle_reprocess_quarantined(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    id: &str,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let item = QUARANTINE
+        .may_load(deps.storage, id)?
+        .ok_or_else(|| ContractError::QuarantinedItemNotFound(id.to_string()))?;
+
+    let mut events = vec![];
+    match &item.op {
+        QuarantinedOp::NewFp(fp) => handle_new_fp(deps.storage, fp, env.block.height)?,
+        QuarantinedOp::ActiveDel(del) => {
+            let (_, evs) = handle_active_delegation(deps.storage, env.block.height, del)?;
+            events.extend(evs);
+        }
+    }
+    QUARANTINE.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "reprocess_quarantined")
+        .add_attribute("id", id)
+        .add_events(events))
+}
+
+/// handle_discard_quarantined drops a quarantined BTC staking operation
+pub fn handle_discard_quarantined(
+    deps: DepsMut,
+    info: &MessageInfo,
+    id: &str,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    if !QUARANTINE.has(deps.storage, id) {
+        return Err(ContractError::QuarantinedItemNotFound(id.to_string()));
+    }
+    QUARANTINE.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "discard_quarantined")
+        .add_attribute("id", id))
+}
+
+/// import_genesis registers the genesis finality providers and active delegations, validating
+/// them the same as `BtcStaking` operations
+pub fn import_genesis(
+    storage: &mut dyn Storage,
+    height: u64,
+    genesis: &Genesis,
+) -> Result<Vec<Event>, ContractError> {
+    for fp in &genesis.fps {
+        handle_new_fp(storage, fp, height)?;
+    }
+    let mut events = vec![];
+    for del in &genesis.active_delegations {
+        let (_, evs) = handle_active_delegation(storage, height, del)?;
+        events.extend(evs);
+    }
+    Ok(events)
 }
 
-/// handle_bew_fp handles registering a new finality provider
+/// handle_new_fp handles registering a new finality provider
 pub fn handle_new_fp(
     storage: &mut dyn Storage,
     new_fp: &NewFinalityProvider,
     height: u64,
+) -> Result<(), ContractError> {
+    let new_fp = validate_new_fp(new_fp)?;
+    register_new_fp(storage, &new_fp, height)
+}
+
+/// validate_new_fp runs the stateless checks of a new finality provider, and returns it with its
+/// BTC PK in canonical (x-only) encoding
+fn validate_new_fp(new_fp: &NewFinalityProvider) -> Result<NewFinalityProvider, ContractError> {
+    let mut new_fp = new_fp.clone();
+    new_fp.normalize_pks()?;
+
+    // basic validations on the finality provider data
+    new_fp.validate()?;
+
+    // verify the finality provider registration request (full or lite)
+    verify_new_fp(&new_fp)?;
+    Ok(new_fp)
+}
+
+/// handle_duplicate_fp handles a validated registration of an already existing finality
+/// provider, according to the `duplicate_fps` param
+fn handle_duplicate_fp(
+    storage: &mut dyn Storage,
+    policy: DuplicateFps,
+    new_fp: &NewFinalityProvider,
+) -> StdResult<Event> {
+    let action = match policy {
+        DuplicateFps::Reject | DuplicateFps::Skip => "skipped",
+        DuplicateFps::Update => {
+            // Only the mutable fields are updated; the rest of the registration is ignored
+            FPS.update(storage, &new_fp.btc_pk_hex, |fp| -> StdResult<_> {
+                let mut fp = fp.ok_or_else(|| StdError::not_found("FinalityProvider"))?;
+                fp.description = new_fp.description.clone();
+                fp.commission = new_fp.commission;
+                Ok(fp)
+            })?;
+            "updated"
+        }
+    };
+    Ok(Event::new("duplicate_finality_provider")
+        .add_attribute("btc_pk_hex", &new_fp.btc_pk_hex)
+        .add_attribute("action", action))
+}
+
+/// register_new_fp registers a validated new finality provider
+fn register_new_fp(
+    storage: &mut dyn Storage,
+    new_fp: &NewFinalityProvider,
+    height: u64,
 ) -> Result<(), ContractError> {
     // Avoid overwriting existing finality providers
     if FPS.has(storage, &new_fp.btc_pk_hex) {
@@ -82,11 +345,15 @@ pub fn handle_new_fp(
             new_fp.btc_pk_hex.clone(),
         ));
     }
-    // basic validations on the finality provider data
-    new_fp.validate()?;
-
-    // verify the finality provider registration request (full or lite)
-    verify_new_fp(new_fp)?;
+    // Removed finality providers cannot register again
+    if FP_REMOVALS
+        .may_load(storage, &new_fp.btc_pk_hex)?
+        .is_some_and(|removal| removal.removed_height.is_some())
+    {
+        return Err(ContractError::FinalityProviderRemoved(
+            new_fp.btc_pk_hex.clone(),
+        ));
+    }
 
     // get DB object
     let fp = FinalityProvider::from(new_fp);
@@ -104,14 +371,28 @@ pub fn handle_active_delegation(
     storage: &mut dyn Storage,
     height: u64,
     active_delegation: &ActiveBtcDelegation,
-) -> Result<(), ContractError> {
+) -> Result<(TxHash, Vec<Event>), ContractError> {
+    let params = PARAMS.load(storage)?;
+    let (active_delegation, staking_tx) = validate_active_delegation(&params, active_delegation)?;
+    register_active_delegation(storage, &params, height, &active_delegation, &staking_tx)
+}
+
+/// validate_active_delegation runs the checks of an active delegation that do not depend on
+/// the staking state, and returns it with its BTC PKs in canonical (x-only) encoding, along with
+/// its parsed staking tx
+fn validate_active_delegation(
+    params: &Params,
+    active_delegation: &ActiveBtcDelegation,
+) -> Result<(ActiveBtcDelegation, Transaction), ContractError> {
+    let mut normalized = active_delegation.clone();
+    normalized.normalize_pks()?;
+    let active_delegation = &normalized;
+
     // TODO: Get params
     // btc_confirmation_depth
     // checkpoint_finalization_timeout
     // minimum_unbonding_time
 
-    let params = PARAMS.load(storage)?;
-
     // Basic stateless checks
     active_delegation.validate()?;
 
@@ -140,8 +421,27 @@ pub fn handle_active_delegation(
             return Err(ContractError::ErrInvalidLockType);
         }
     }
+
+    // verify the staking output, if enabled, before the (costlier) signature checks
+    if params.verify_staking_tx {
+        verify_staking_tx(params, active_delegation, &staking_tx)?;
+    }
+    // verify the active delegation (full or lite)
+    verify_active_delegation(params, active_delegation, &staking_tx)?;
+
+    Ok((normalized, staking_tx))
+}
+
+/// register_active_delegation registers a validated active delegation
+fn register_active_delegation(
+    storage: &mut dyn Storage,
+    params: &Params,
+    height: u64,
+    active_delegation: &ActiveBtcDelegation,
+    staking_tx: &Transaction,
+) -> Result<(TxHash, Vec<Event>), ContractError> {
     // Get staking tx hash
-    let staking_tx_hash = staking_tx.txid();
+    let staking_tx_hash = TxHash::from(staking_tx.txid());
 
     // Check staking tx is not duplicated
     if DELEGATIONS.has(storage, staking_tx_hash.as_ref()) {
@@ -150,9 +450,6 @@ pub fn handle_active_delegation(
         ));
     }
 
-    // verify the active delegation (full or lite)
-    verify_active_delegation(&params, active_delegation, &staking_tx)?;
-
     // All good, construct BTCDelegation and insert BTC delegation
     // NOTE: the BTC delegation does not have voting power yet.
     // It will have voting power only when
@@ -160,8 +457,9 @@ pub fn handle_active_delegation(
     // 2) It receives a covenant signature.
 
     // Update delegations by registered finality provider
-    let fps = fps();
+    let mut events = vec![];
     let mut registered_fp = false;
+    let stake_tracked = rewards::is_stake_tracked(storage, staking_tx_hash.as_ref())?;
     for fp_btc_pk_hex in &active_delegation.fp_btc_pk_list {
         // Skip if finality provider is not registered, as it can belong to another Consumer, or Babylon
         if !FPS.has(storage, fp_btc_pk_hex) {
@@ -173,6 +471,10 @@ pub fn handle_active_delegation(
         if fp.slashed_height > 0 {
             continue;
         }
+        // Skip deregistered FPs, as they don't accept new delegations
+        if FP_EXITS.has(storage, fp_btc_pk_hex) {
+            continue;
+        }
 
         // Update staking tx hash by finality provider map
         let mut fp_delegations = FP_DELEGATIONS
@@ -188,12 +490,24 @@ pub fn handle_active_delegation(
         delegation_fps.push(fp_btc_pk_hex.clone());
         DELEGATION_FPS.save(storage, staking_tx_hash.as_ref(), &delegation_fps)?;
 
-        // Update aggregated voting power by FP
-        fps.update(storage, fp_btc_pk_hex, height, |fp_state| {
-            let mut fp_state = fp_state.unwrap_or_default();
-            fp_state.power = fp_state.power.saturating_add(active_delegation.total_sat);
-            Ok::<_, ContractError>(fp_state)
+        // Update aggregated voting power and self-stake by FP
+        let is_self_delegation = fp.addr == active_delegation.staker_addr;
+        let ev = update_fp_stake(storage, params, height, fp_btc_pk_hex, |fp_state| {
+            fp_state.power = add_sat(fp_state.power, active_delegation.total_sat)?;
+            if is_self_delegation {
+                fp_state.self_stake = add_sat(fp_state.self_stake, active_delegation.total_sat)?;
+            }
+            Ok(())
         })?;
+        events.extend(ev);
+        if stake_tracked {
+            rewards::add_stake(
+                storage,
+                fp_btc_pk_hex,
+                &active_delegation.staker_addr,
+                active_delegation.total_sat,
+            )?;
+        }
 
         registered_fp = true;
     }
@@ -204,6 +518,16 @@ pub fn handle_active_delegation(
     // Add this BTC delegation
     let delegation = BtcDelegation::from(active_delegation);
     DELEGATIONS.save(storage, staking_tx_hash.as_ref(), &delegation)?;
+    STAKER_DELEGATIONS.save(
+        storage,
+        (&delegation.staker_addr, staking_tx_hash.as_ref()),
+        &(),
+    )?;
+    DELEGATION_EXPIRIES.save(
+        storage,
+        (delegation.end_height, staking_tx_hash.as_ref()),
+        &(),
+    )?;
 
     // Store activated height, if first delegation
     if ACTIVATED_HEIGHT.may_load(storage)?.is_none() {
@@ -212,7 +536,7 @@ pub fn handle_active_delegation(
 
     // TODO: Emit corresponding events
 
-    Ok(())
+    Ok((staking_tx_hash, events))
 }
 
 /// handle_undelegation handles undelegation from an active delegation
@@ -220,13 +544,17 @@ fn handle_undelegation(
     storage: &mut dyn Storage,
     height: u64,
     undelegation: &UnbondedBtcDelegation,
-) -> Result<Event, ContractError> {
+) -> Result<Vec<Event>, ContractError> {
     // Basic stateless checks
     undelegation.validate()?;
 
-    let staking_tx_hash = Txid::from_str(&undelegation.staking_tx_hash)?;
+    let staking_tx_hash = TxHash::from_hex(&undelegation.staking_tx_hash)?;
     let mut btc_del = DELEGATIONS.load(storage, staking_tx_hash.as_ref())?;
 
+    // An expired delegation has already lost its voting power, so there is nothing to unbond
+    if btc_del.status == DelegationStatus::Expired {
+        return Ok(vec![]);
+    }
     // Ensure the BTC delegation is active
     if !btc_del.is_active() {
         return Err(ContractError::DelegationIsNotActive(
@@ -246,23 +574,21 @@ fn handle_undelegation(
         &undelegation.unbonding_tx_sig,
     )?;
 
-    // Discount the voting power from the affected finality providers
-    let affected_fps = DELEGATION_FPS.load(storage, staking_tx_hash.as_ref())?;
-    let fps = fps();
-    for fp in affected_fps {
-        fps.update(storage, &fp, height, |fp_state| {
-            let mut fp_state =
-                fp_state.ok_or(ContractError::FinalityProviderNotFound(fp.clone()))?; // should never happen
-            fp_state.power = fp_state.power.saturating_sub(btc_del.total_sat);
-            Ok::<_, ContractError>(fp_state)
-        })?;
-    }
+    // Discount the voting power and self-stake from the affected finality providers, unless an
+    // unbonding intent has already done so
+    let fp_events = if settle_unbonding_intent(storage, &staking_tx_hash)? {
+        vec![]
+    } else {
+        discount_fp_stake(storage, height, &staking_tx_hash, &btc_del)?
+    };
     // Record event that the BTC delegation becomes unbonded
     let unbonding_event = Event::new("btc_undelegation")
         .add_attribute("staking_tx_hash", staking_tx_hash.to_string())
         .add_attribute("height", height.to_string());
 
-    Ok(unbonding_event)
+    let mut events = vec![unbonding_event];
+    events.extend(fp_events);
+    Ok(events)
 }
 
 /// handle_slashed_delegation handles undelegation due to slashing from an active delegation
@@ -271,13 +597,17 @@ fn handle_slashed_delegation(
     storage: &mut dyn Storage,
     height: u64,
     delegation: &SlashedBtcDelegation,
-) -> Result<Event, ContractError> {
+) -> Result<Vec<Event>, ContractError> {
     // Basic stateless checks
     delegation.validate()?;
 
-    let staking_tx_hash = Txid::from_str(&delegation.staking_tx_hash)?;
+    let staking_tx_hash = TxHash::from_hex(&delegation.staking_tx_hash)?;
     let mut btc_del = DELEGATIONS.load(storage, staking_tx_hash.as_ref())?;
 
+    // An expired delegation has already lost its voting power, so there is nothing to slash
+    if btc_del.status == DelegationStatus::Expired {
+        return Ok(vec![]);
+    }
     // Ensure the BTC delegation is active
     if !btc_del.is_active() {
         return Err(ContractError::DelegationIsNotActive(
@@ -289,20 +619,17 @@ fn handle_slashed_delegation(
     let recovered_fp_sk_hex = delegation.recovered_fp_btc_sk.clone();
     verify_slashed_delegation(&btc_del, &recovered_fp_sk_hex)?;
 
-    // Discount the voting power from the affected finality providers
-    let affected_fps = DELEGATION_FPS.load(storage, staking_tx_hash.as_ref())?;
-    let fps = fps();
-    for fp in affected_fps {
-        fps.update(storage, &fp, height, |fp_state| {
-            let mut fp_state =
-                fp_state.ok_or(ContractError::FinalityProviderNotFound(fp.clone()))?; // should never happen
-            fp_state.power = fp_state.power.saturating_sub(btc_del.total_sat);
-            Ok::<_, ContractError>(fp_state)
-        })?;
-    }
+    // Discount the voting power and self-stake from the affected finality providers, unless an
+    // unbonding intent has already done so
+    let fp_events = if settle_unbonding_intent(storage, &staking_tx_hash)? {
+        vec![]
+    } else {
+        discount_fp_stake(storage, height, &staking_tx_hash, &btc_del)?
+    };
 
     // Mark the delegation as slashed
     btc_del.slashed = true;
+    btc_del.status = DelegationStatus::Slashed;
     DELEGATIONS.save(storage, staking_tx_hash.as_ref(), &btc_del)?;
 
     // Record event that the BTC delegation becomes unbonded due to slashing at this height
@@ -310,449 +637,3791 @@ fn handle_slashed_delegation(
         .add_attribute("staking_tx_hash", staking_tx_hash.to_string())
         .add_attribute("height", height.to_string());
 
-    Ok(slashing_event)
+    let mut events = vec![slashing_event];
+    events.extend(fp_events);
+    Ok(events)
 }
 
-/// handle_slash_fp handles FP slashing at the staking level
-pub fn handle_slash_fp(
+/// handle_unbond handles a delegator-initiated unbonding intent.
+/// The intent is recorded, and the voting power of the delegation is removed after
+/// `unbonding_intent_delay` blocks (immediately if zero)
+pub fn handle_unbond(
     deps: DepsMut,
     env: Env,
     info: &MessageInfo,
-    fp_btc_pk_hex: &str,
+    staking_tx_hash_hex: &str,
 ) -> Result<Response<BabylonMsg>, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.babylon && !ADMIN.is_admin(deps.as_ref(), &info.sender)? {
+    let staking_tx_hash = TxHash::from_hex(staking_tx_hash_hex)?;
+    let btc_del = DELEGATIONS.load(deps.storage, staking_tx_hash.as_ref())?;
+
+    // Only the consumer address bound to the staker's BTC key can unbond its delegation, as the
+    // staker address is a Babylon address
+    let staker = STAKER_BINDINGS
+        .may_load(deps.storage, &btc_del.btc_pk_hex)?
+        .ok_or_else(|| ContractError::StakerNotBound(btc_del.btc_pk_hex.clone()))?;
+    if info.sender != staker {
         return Err(ContractError::Unauthorized);
     }
-    slash_finality_provider(deps, env, fp_btc_pk_hex)
-}
-
-/// btc_undelegate adds the signature of the unbonding tx signed by the staker to the given BTC
-/// delegation
-fn btc_undelegate(
-    storage: &mut dyn Storage,
-    staking_tx_hash: &Txid,
-    btc_del: &mut BtcDelegation,
-    unbonding_tx_sig: &[u8],
-) -> Result<(), ContractError> {
-    btc_del.undelegation_info.delegator_unbonding_sig = unbonding_tx_sig.to_vec();
-
-    // Set BTC delegation back to KV store
-    DELEGATIONS.save(storage, staking_tx_hash.as_ref(), btc_del)?;
+    // Ensure the BTC delegation is active
+    if !btc_del.is_active() {
+        return Err(ContractError::DelegationIsNotActive(
+            staking_tx_hash.to_string(),
+        ));
+    }
+    if UNBONDING_INTENTS.has(deps.storage, staking_tx_hash.as_ref()) {
+        return Err(ContractError::UnbondingIntentAlreadyExists(
+            staking_tx_hash.to_string(),
+        ));
+    }
 
-    // TODO? Notify subscriber about this unbonded BTC delegation
-    //  - Who are subscribers in this context?
-    //  - How to notify them? Emit event?
+    let height = env.block.height;
+    let params = PARAMS.load(deps.storage)?;
+    let intent = UnbondingIntent {
+        staking_tx_hash: staking_tx_hash.to_string(),
+        staker_addr: btc_del.staker_addr,
+        requested_height: height,
+        unbond_height: height + params.unbonding_intent_delay,
+        processed: false,
+    };
+    UNBONDING_INTENTS.save(deps.storage, staking_tx_hash.as_ref(), &intent)?;
+    PENDING_UNBONDINGS.save(
+        deps.storage,
+        (intent.unbond_height, staking_tx_hash.as_ref()),
+        &(),
+    )?;
 
-    // TODO? Record event that the BTC delegation becomes unbonded at this height
+    // Record event for covenant / vigilante tooling
+    let intent_event = Event::new("btc_unbonding_intent")
+        .add_attribute("staking_tx_hash", &intent.staking_tx_hash)
+        .add_attribute("staker_addr", &intent.staker_addr)
+        .add_attribute("height", height.to_string())
+        .add_attribute("unbond_height", intent.unbond_height.to_string());
 
-    Ok(())
+    let evs = process_unbonding_intents(deps.storage, height)?;
+    let intent = UNBONDING_INTENTS.load(deps.storage, staking_tx_hash.as_ref())?;
+    Ok(Response::new()
+        .add_event(intent_event)
+        .add_events(evs)
+        .set_data(to_json_binary(&intent)?))
 }
 
-/// `slash_finality_provider` slashes a finality provider with the given PK.
-/// A slashed finality provider will not have voting power
-pub(crate) fn slash_finality_provider(
+/// handle_bind_staker binds a BTC staker key to the sender's address, given a signature by the
+/// staker key over the binding.
+/// Any previous binding of the key is replaced
+pub fn handle_bind_staker(
     deps: DepsMut,
     env: Env,
-    fp_btc_pk_hex: &str,
+    info: &MessageInfo,
+    btc_pk_hex: &BtcPkHex,
+    sig: &[u8],
 ) -> Result<Response<BabylonMsg>, ContractError> {
-    // Ensure finality provider exists
-    let mut fp = FPS.load(deps.storage, fp_btc_pk_hex)?;
+    verify_staker_binding(&env, info.sender.as_str(), btc_pk_hex, sig)?;
 
-    // Check if the finality provider is already slashed
-    if fp.slashed_height > 0 {
-        return Err(ContractError::FinalityProviderAlreadySlashed(
-            fp_btc_pk_hex.to_string(),
-        ));
+    if let Some(prev_addr) = STAKER_BINDINGS.may_load(deps.storage, btc_pk_hex)? {
+        ADDR_STAKER_BINDINGS.remove(deps.storage, (&prev_addr, btc_pk_hex));
     }
-    // Set the finality provider as slashed
-    fp.slashed_height = env.block.height;
-
-    // Set BTC slashing height (if available from the babylon contract)
-    // FIXME: Turn this into a hard error
-    // return fmt.Errorf("failed to get current BTC tip")
-    let btc_height = get_btc_tip_height(&deps).unwrap_or_default();
-    fp.slashed_btc_height = btc_height;
+    STAKER_BINDINGS.save(deps.storage, btc_pk_hex, &info.sender)?;
+    ADDR_STAKER_BINDINGS.save(deps.storage, (&info.sender, btc_pk_hex), &())?;
 
-    // Record slashed event. The next `BeginBlock` will consume this event for updating the active
-    // FP set.
-    // We simply set the FP voting power to zero from the next *processing* height (See NOTE in
-    // `handle_finality_signature`)
-    fps().update(deps.storage, fp_btc_pk_hex, env.block.height + 1, |fp| {
-        let mut fp = fp.unwrap_or_default();
-        fp.power = 0;
-        Ok::<_, ContractError>(fp)
-    })?;
+    let event = Event::new("btc_staker_bound")
+        .add_attribute("btc_pk_hex", btc_pk_hex.as_str())
+        .add_attribute("staker_addr", info.sender.as_str());
+    Ok(Response::new().add_event(event))
+}
 
-    // Save the finality provider back
-    FPS.save(deps.storage, fp_btc_pk_hex, &fp)?;
+/// handle_unbind_staker removes the binding of a BTC staker key, by the bound address
+pub fn handle_unbind_staker(
+    deps: DepsMut,
+    info: &MessageInfo,
+    btc_pk_hex: &BtcPkHex,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let addr = STAKER_BINDINGS.load(deps.storage, btc_pk_hex)?;
+    if addr != info.sender {
+        return Err(ContractError::Unauthorized);
+    }
+    STAKER_BINDINGS.remove(deps.storage, btc_pk_hex);
+    ADDR_STAKER_BINDINGS.remove(deps.storage, (&addr, btc_pk_hex));
 
-    // TODO: Add events
-    Ok(Response::new())
+    let event = Event::new("btc_staker_unbound")
+        .add_attribute("btc_pk_hex", btc_pk_hex.as_str())
+        .add_attribute("staker_addr", addr.as_str());
+    Ok(Response::new().add_event(event))
 }
 
-/// get_btc_tip_height queries the Babylon contract for the latest BTC tip height
-fn get_btc_tip_height(deps: &DepsMut) -> Result<u64, ContractError> {
-    // Get the BTC tip from the babylon contract through a raw query
-    let babylon_addr = CONFIG.load(deps.storage)?.babylon;
-
-    // Query the Babylon contract
-    // TODO: use a raw query for performance / efficiency
-    let query_msg = BabylonQueryMsg::BtcTipHeader {};
-    let tip: BtcHeaderResponse = deps.querier.query_wasm_smart(babylon_addr, &query_msg)?;
+/// handle_set_fp_metadata publishes the operational metadata of a finality provider, given a
+/// signature by its key over it.
+/// Any previous metadata is replaced
+pub fn handle_set_fp_metadata(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    fp_btc_pk_hex: &BtcPkHex,
+    metadata: FpMetadata,
+    sig: Binary,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let fp = FPS
+        .may_load(deps.storage, fp_btc_pk_hex)?
+        .ok_or_else(|| ContractError::FinalityProviderNotFound(fp_btc_pk_hex.to_string()))?;
 
-    Ok(tip.height)
-}
+    // Only the finality provider, or the address bound to its BTC key, can publish its metadata,
+    // so that a previously signed record cannot be replayed by others
+    if info.sender.as_str() != fp.addr
+        && STAKER_BINDINGS.may_load(deps.storage, fp_btc_pk_hex)? != Some(info.sender.clone())
+    {
+        return Err(ContractError::Unauthorized);
+    }
+    verify_fp_metadata(&env, fp_btc_pk_hex, &metadata, &sig)?;
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use super::*;
+    let record = FpMetadataRecord {
+        metadata,
+        sig,
+        updated_height: env.block.height,
+    };
+    FP_METADATA.save(deps.storage, fp_btc_pk_hex, &record)?;
 
-    use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
+    let event = Event::new("fp_metadata_updated")
+        .add_attribute("fp_btc_pk_hex", fp_btc_pk_hex.as_str())
+        .add_attribute("height", env.block.height.to_string());
+    Ok(Response::new().add_event(event))
+}
 
-    use test_utils::{
-        create_new_finality_provider, create_new_fp_sk, get_active_btc_delegation,
-        get_btc_del_unbonding_sig, get_derived_btc_delegation,
+/// handle_grant_claim records the grant by the sender (staker) to `grantee` to claim its rewards
+/// on its behalf, replacing any previous grant to the same grantee
+pub fn handle_grant_claim(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    grantee: &str,
+    expiry: Option<u64>,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let grantee = deps.api.addr_validate(grantee)?;
+    if grantee == info.sender {
+        return Err(ContractError::InvalidClaimGrant(
+            "a staker cannot grant itself".to_string(),
+        ));
+    }
+    if let Some(expiry) = expiry {
+        if expiry <= env.block.height {
+            return Err(ContractError::InvalidClaimGrant(format!(
+                "expiry height {expiry} is not after the current height {}",
+                env.block.height
+            )));
+        }
+    }
+    let grant = ClaimGrant {
+        grantee: grantee.clone(),
+        expiry,
+        granted_height: env.block.height,
     };
+    CLAIM_GRANTS.save(deps.storage, (&info.sender, &grantee), &grant)?;
 
-    use crate::contract::tests::{CREATOR, INIT_ADMIN};
-    use crate::contract::{execute, instantiate};
-    use crate::msg::{ExecuteMsg, InstantiateMsg};
-    use crate::queries;
-    use crate::state::staking::BtcUndelegationInfo;
-    use crate::test_utils::staking_params;
+    let mut event = Event::new("claim_granted")
+        .add_attribute("staker_addr", info.sender.as_str())
+        .add_attribute("grantee", grantee.as_str());
+    if let Some(expiry) = expiry {
+        event = event.add_attribute("expiry", expiry.to_string());
+    }
+    Ok(Response::new().add_event(event))
+}
 
-    // Compute staking tx hash of a delegation
-    pub(crate) fn staking_tx_hash(del: &BtcDelegation) -> Txid {
-        let staking_tx: Transaction = deserialize(&del.staking_tx).unwrap();
-        staking_tx.txid()
+/// handle_revoke_claim revokes the grant by the sender (staker) to `grantee` to claim its rewards
+pub fn handle_revoke_claim(
+    deps: DepsMut,
+    info: &MessageInfo,
+    grantee: &str,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let grantee = deps.api.addr_validate(grantee)?;
+    if !CLAIM_GRANTS.has(deps.storage, (&info.sender, &grantee)) {
+        return Err(ContractError::ClaimGrantNotFound(
+            info.sender.to_string(),
+            grantee.to_string(),
+        ));
     }
+    CLAIM_GRANTS.remove(deps.storage, (&info.sender, &grantee));
 
-    #[test]
-    fn test_add_fp_unauthorized() {
-        let mut deps = mock_dependencies();
-        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
-        let init_admin = deps.api.addr_make(INIT_ADMIN);
+    let event = Event::new("claim_revoked")
+        .add_attribute("staker_addr", info.sender.as_str())
+        .add_attribute("grantee", grantee.as_str());
+    Ok(Response::new().add_event(event))
+}
 
-        instantiate(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            InstantiateMsg {
-                params: None,
-                admin: Some(init_admin.to_string()), // Admin provided
-            },
+/// handle_distribute_rewards distributes the attached funds as rewards to the given finality
+/// providers. The commission share of each reward goes to the finality provider's address, and
+/// the rest to its delegators, proportionally to their stake
+pub fn handle_distribute_rewards(
+    deps: DepsMut,
+    info: &MessageInfo,
+    fp_distribution: &[RewardInfo],
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let denom = PARAMS.load(deps.storage)?.reward_denom;
+    let amount = must_pay(info, &denom)?;
+    distribute_rewards(deps.storage, fp_distribution, amount, &denom, None)
+}
+
+/// handle_receive_cw20 handles the cw20 receive hook, i.e. the rewards funded in a cw20 token.
+/// The sender is the token contract, which must be registered by the admin
+pub fn handle_receive_cw20(
+    deps: DepsMut,
+    info: &MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    nonpayable(info)?;
+    let token = info.sender.as_str();
+    if !rewards::CW20_REWARD_TOKENS.has(deps.storage, &info.sender) {
+        return Err(ContractError::UnregisteredRewardToken(token.to_string()));
+    }
+    match from_json(&msg.msg)? {
+        Cw20HookMsg::DistributeRewards { fp_distribution } => {
+            let res = distribute_rewards(
+                deps.storage,
+                &fp_distribution,
+                msg.amount,
+                token,
+                Some(token),
+            )?;
+            Ok(res.add_attribute("sender", msg.sender))
+        }
+    }
+}
+
+/// handle_update_reward_tokens registers and unregisters cw20 token contracts as rewards
+pub fn handle_update_reward_tokens(
+    deps: DepsMut,
+    info: &MessageInfo,
+    add: &[String],
+    remove: &[String],
+) -> Result<Response<BabylonMsg>, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let mut res = Response::new().add_attribute("action", "update_reward_tokens");
+    for token in add {
+        let token = deps.api.addr_validate(token)?;
+        rewards::CW20_REWARD_TOKENS.save(deps.storage, &token, &())?;
+        res = res.add_attribute("added", token);
+    }
+    for token in remove {
+        let token = deps.api.addr_validate(token)?;
+        rewards::CW20_REWARD_TOKENS.remove(deps.storage, &token);
+        res = res.add_attribute("removed", token);
+    }
+    Ok(res)
+}
+
+/// distribute_rewards distributes `amount` as rewards to the given finality providers, in the
+/// reward denom, or in the cw20 `token` if any.
+/// `asset` is the reward denom, or the token address, for the events.
+/// Rewards cannot be distributed until the stakes are backfilled
+fn distribute_rewards(
+    storage: &mut dyn Storage,
+    fp_distribution: &[RewardInfo],
+    amount: Uint128,
+    asset: &str,
+    token: Option<&str>,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    ensure_sections_active(storage, &[SECTION_REWARDS])?;
+    let total: Uint128 = fp_distribution.iter().map(|info| info.reward).sum();
+    if total != amount {
+        return Err(ContractError::InvalidRewardDistribution(format!(
+            "rewards add up to {total}{asset}, but {amount}{asset} were sent"
+        )));
+    }
+
+    let mut events = vec![];
+    for reward_info in fp_distribution {
+        let fp_btc_pk_hex = reward_info.fp_btc_pk_hex.as_str();
+        let fp = FPS
+            .may_load(storage, fp_btc_pk_hex)?
+            .ok_or_else(|| ContractError::FinalityProviderNotFound(fp_btc_pk_hex.to_string()))?;
+        if fp.slashed_height > 0 {
+            return Err(ContractError::FinalityProviderAlreadySlashed(
+                fp_btc_pk_hex.to_string(),
+            ));
+        }
+        let staked = FP_REWARDS
+            .may_load(storage, fp_btc_pk_hex)?
+            .is_some_and(|fp_rewards| fp_rewards.total_sat > 0);
+        if !staked {
+            return Err(ContractError::InvalidRewardDistribution(format!(
+                "finality provider {fp_btc_pk_hex} has no stake"
+            )));
+        }
+        let commission = match token {
+            Some(token) => rewards::distribute_cw20(
+                storage,
+                fp_btc_pk_hex,
+                &fp.addr,
+                fp.commission,
+                token,
+                reward_info.reward,
+            )?,
+            None => rewards::distribute(
+                storage,
+                fp_btc_pk_hex,
+                &fp.addr,
+                fp.commission,
+                reward_info.reward,
+            )?,
+        };
+        let mut event = Event::new("rewards_distributed")
+            .add_attribute("fp_btc_pk_hex", fp_btc_pk_hex)
+            .add_attribute("reward", reward_info.reward.to_string())
+            .add_attribute("commission", commission.to_string());
+        if let Some(token) = token {
+            event = event.add_attribute("token", token);
+        }
+        events.push(event);
+    }
+    Ok(Response::new().add_events(events))
+}
+
+/// handle_withdraw_rewards sends the rewards of the staker (the sender if `None`) to the sender,
+/// i.e. the staker itself or a grantee of an unexpired claim grant from it.
+/// With a commission lockup, the commissions of a finality provider address start vesting
+/// instead, and only the commissions vested so far are sent
+pub fn handle_withdraw_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    staker_addr: Option<&str>,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let staker = match staker_addr {
+        Some(staker_addr) => deps.api.addr_validate(staker_addr)?,
+        None => info.sender.clone(),
+    };
+    if !can_claim(deps.storage, &staker, &info.sender, env.block.height)? {
+        return Err(ContractError::Unauthorized);
+    }
+    let params = PARAMS.load(deps.storage)?;
+    let mut amount = rewards::withdraw(deps.storage, staker.as_str())?;
+    let mut cw20_amounts = rewards::withdraw_cw20(deps.storage, staker.as_str())?;
+    let locked = match params.commission_lockup_blocks {
+        0 => BTreeMap::new(),
+        _ => rewards::commissions(deps.storage, staker.as_str())?,
+    };
+    let vested = rewards::withdraw_commissions(
+        deps.storage,
+        staker.as_str(),
+        env.block.height,
+        params.commission_lockup_blocks,
+    )?;
+    for (asset, vested) in vested {
+        match asset.as_str() {
+            rewards::NATIVE_ASSET => amount += vested,
+            _ => *cw20_amounts.entry(asset).or_default() += vested,
+        }
+    }
+    if amount.is_zero() && cw20_amounts.is_empty() && locked.is_empty() {
+        return Err(ContractError::NoPendingRewards(staker.to_string()));
+    }
+
+    let denom = params.reward_denom;
+    let mut res = Response::new();
+    let mut event = Event::new("rewards_withdrawn")
+        .add_attribute("staker_addr", staker.as_str())
+        .add_attribute("recipient", info.sender.as_str());
+    for (asset, locked) in locked {
+        let asset = match asset.as_str() {
+            rewards::NATIVE_ASSET => denom.as_str(),
+            token => token,
+        };
+        event = event.add_attribute("locked_commission", format!("{locked}{asset}"));
+    }
+    if !amount.is_zero() {
+        event = event.add_attribute("amount", coin(amount.u128(), &denom).to_string());
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(amount.u128(), denom),
+        });
+    }
+    for (token, amount) in cw20_amounts {
+        event = event.add_attribute("cw20_amount", format!("{amount}{token}"));
+        res = res.add_message(cw20_transfer_msg(&token, info.sender.as_str(), amount)?);
+    }
+    Ok(res.add_event(event))
+}
+
+/// handle_register_fp registers a finality provider directly on the consumer, on behalf of its
+/// address, holding the registration deposit of the params, if any
+pub fn handle_register_fp(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    new_fp: &NewFinalityProvider,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    if info.sender.as_str() != new_fp.addr {
+        return Err(ContractError::Unauthorized);
+    }
+    let deposit = PARAMS.load(deps.storage)?.fp_registration_deposit;
+    match &deposit {
+        Some(deposit) => {
+            let amount = must_pay(info, &deposit.denom)?;
+            if amount != deposit.amount {
+                return Err(ContractError::InvalidFpDeposit(format!(
+                    "{deposit} is required, but {amount}{} were sent",
+                    deposit.denom
+                )));
+            }
+        }
+        None => nonpayable(info)?,
+    }
+
+    let new_fp = validate_new_fp(new_fp)?;
+    let height = env.block.height;
+    register_new_fp(deps.storage, &new_fp, height)?;
+    let mut event = Event::new("fp_registered")
+        .add_attribute("btc_pk_hex", &new_fp.btc_pk_hex)
+        .add_attribute("addr", &new_fp.addr);
+    if let Some(amount) = deposit {
+        event = event.add_attribute("deposit", amount.to_string());
+        let deposit = FpDeposit {
+            depositor: info.sender.clone(),
+            amount,
+            deposited_height: height,
+        };
+        FP_DEPOSITS.save(deps.storage, &new_fp.btc_pk_hex, &deposit)?;
+    }
+    Ok(Response::new().add_event(event))
+}
+
+/// handle_refund_fp_deposit refunds the registration deposit of a finality provider to its
+/// depositor, once the finality provider reaches the minimum stake, or is deregistered
+pub fn handle_refund_fp_deposit(
+    deps: DepsMut,
+    info: &MessageInfo,
+    fp_btc_pk_hex: &BtcPkHex,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let fp_btc_pk_hex = fp_btc_pk_hex.to_string();
+    let deposit = FP_DEPOSITS.load(deps.storage, &fp_btc_pk_hex)?;
+    if info.sender != deposit.depositor {
+        return Err(ContractError::Unauthorized);
+    }
+    let not_refundable =
+        |reason: &str| ContractError::FpDepositNotRefundable(fp_btc_pk_hex.clone(), reason.into());
+    // Removed finality providers are no longer in the registry
+    let fp = FPS
+        .may_load(deps.storage, &fp_btc_pk_hex)?
+        .ok_or_else(|| not_refundable("removed"))?;
+    if fp.slashed_height > 0 {
+        return Err(not_refundable("slashed"));
+    }
+    if !FP_EXITS.has(deps.storage, &fp_btc_pk_hex) {
+        let min_stake = PARAMS.load(deps.storage)?.fp_deposit_min_stake_sat;
+        let power = fps()
+            .may_load(deps.storage, &fp_btc_pk_hex)?
+            .unwrap_or_default()
+            .power;
+        if power < min_stake {
+            return Err(not_refundable(&format!(
+                "voting power {power} is below the minimum stake {min_stake}"
+            )));
+        }
+    }
+
+    FP_DEPOSITS.remove(deps.storage, &fp_btc_pk_hex);
+    let event = Event::new("fp_deposit_refunded")
+        .add_attribute("fp_btc_pk_hex", &fp_btc_pk_hex)
+        .add_attribute("depositor", deposit.depositor.as_str())
+        .add_attribute("amount", deposit.amount.to_string());
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: deposit.depositor.to_string(),
+            amount: vec![deposit.amount],
+        })
+        .add_event(event))
+}
+
+/// handle_deregister_fp deregisters a finality provider, by its address or the address bound to
+/// its BTC key.
+/// The finality provider stops accepting new delegations, and exits as soon as its voting power
+/// drops to zero, i.e. once all its delegations are unbonded
+pub fn handle_deregister_fp(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    fp_btc_pk_hex: &BtcPkHex,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let fp_btc_pk_hex = fp_btc_pk_hex.to_string();
+    let fp = FPS
+        .may_load(deps.storage, &fp_btc_pk_hex)?
+        .ok_or_else(|| ContractError::FinalityProviderNotFound(fp_btc_pk_hex.clone()))?;
+
+    // Only the finality provider, or the address bound to its BTC key, can deregister it
+    if info.sender.as_str() != fp.addr
+        && STAKER_BINDINGS.may_load(deps.storage, &fp_btc_pk_hex)? != Some(info.sender.clone())
+    {
+        return Err(ContractError::Unauthorized);
+    }
+    if FP_EXITS.has(deps.storage, &fp_btc_pk_hex) {
+        return Err(ContractError::FinalityProviderDeregistered(fp_btc_pk_hex));
+    }
+
+    let height = env.block.height;
+    FP_EXITS.save(
+        deps.storage,
+        &fp_btc_pk_hex,
+        &FpExit {
+            status: FpExitStatus::Exiting,
+            requested_height: height,
+            exited_height: None,
+        },
+    )?;
+    let mut events = vec![Event::new("fp_exit_requested")
+        .add_attribute("fp_btc_pk_hex", &fp_btc_pk_hex)
+        .add_attribute("height", height.to_string())];
+
+    // Exit right away if there's nothing to unbond
+    let power = fps()
+        .may_load(deps.storage, &fp_btc_pk_hex)?
+        .unwrap_or_default()
+        .power;
+    if power == 0 {
+        events.extend(complete_fp_exit(deps.storage, height, &fp_btc_pk_hex)?);
+    }
+
+    let exit = FP_EXITS.load(deps.storage, &fp_btc_pk_hex)?;
+    Ok(Response::new()
+        .add_events(events)
+        .set_data(to_json_binary(&exit)?))
+}
+
+/// complete_fp_exit marks the given finality provider as exited, if it's exiting
+fn complete_fp_exit(
+    storage: &mut dyn Storage,
+    height: u64,
+    fp_btc_pk_hex: &str,
+) -> Result<Option<Event>, ContractError> {
+    let Some(mut exit) = FP_EXITS.may_load(storage, fp_btc_pk_hex)? else {
+        return Ok(None);
+    };
+    if exit.status != FpExitStatus::Exiting {
+        return Ok(None);
+    }
+    exit.status = FpExitStatus::Exited;
+    exit.exited_height = Some(height);
+    FP_EXITS.save(storage, fp_btc_pk_hex, &exit)?;
+
+    Ok(Some(
+        Event::new("fp_exited")
+            .add_attribute("fp_btc_pk_hex", fp_btc_pk_hex)
+            .add_attribute("requested_height", exit.requested_height.to_string())
+            .add_attribute("height", height.to_string()),
+    ))
+}
+
+/// handle_remove_fp removes a finality provider, on behalf of the admin.
+/// The first call schedules the removal, and the removal is executed by calling it again once the
+/// `fp_removal_delay` has elapsed (or right away if there is no delay)
+pub fn handle_remove_fp(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    fp_btc_pk_hex: &BtcPkHex,
+    reason: &str,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let fp_btc_pk_hex = fp_btc_pk_hex.to_string();
+    if !FPS.has(deps.storage, &fp_btc_pk_hex) {
+        return Err(ContractError::FinalityProviderNotFound(fp_btc_pk_hex));
+    }
+
+    let height = env.block.height;
+    let mut res = Response::new();
+    let removal = match FP_REMOVALS.may_load(deps.storage, &fp_btc_pk_hex)? {
+        Some(removal) if removal.removed_height.is_none() => removal,
+        _ => {
+            let params = PARAMS.load(deps.storage)?;
+            let removal = FpRemoval {
+                reason: reason.to_string(),
+                requested_height: height,
+                executable_height: height + params.fp_removal_delay,
+                removed_height: None,
+            };
+            FP_REMOVALS.save(deps.storage, &fp_btc_pk_hex, &removal)?;
+            res = res.add_event(
+                Event::new("fp_removal_scheduled")
+                    .add_attribute("fp_btc_pk_hex", &fp_btc_pk_hex)
+                    .add_attribute("reason", reason)
+                    .add_attribute("executable_height", removal.executable_height.to_string()),
+            );
+            if params.fp_removal_delay > 0 {
+                return Ok(res.set_data(to_json_binary(&removal)?));
+            }
+            removal
+        }
+    };
+    if height < removal.executable_height {
+        return Err(ContractError::FinalityProviderRemovalTimelocked(
+            fp_btc_pk_hex,
+            removal.executable_height,
+        ));
+    }
+
+    let (removal, event) = remove_finality_provider(deps.storage, height, &fp_btc_pk_hex, removal)?;
+    Ok(res.add_event(event).set_data(to_json_binary(&removal)?))
+}
+
+/// remove_finality_provider removes the given finality provider, along with its voting power,
+/// jail and metadata.
+/// Its delegations no longer delegate to it, and the removal is recorded
+fn remove_finality_provider(
+    storage: &mut dyn Storage,
+    height: u64,
+    fp_btc_pk_hex: &str,
+    mut removal: FpRemoval,
+) -> Result<(FpRemoval, Event), ContractError> {
+    let fp_delegations = FP_DELEGATIONS
+        .may_load(storage, fp_btc_pk_hex)?
+        .unwrap_or_default();
+    for staking_tx_hash in &fp_delegations {
+        let staking_tx_hash = TxHash::from_slice(staking_tx_hash)?;
+        let mut delegation_fps = DELEGATION_FPS
+            .may_load(storage, staking_tx_hash.as_ref())?
+            .unwrap_or_default();
+        delegation_fps.retain(|pk| pk != fp_btc_pk_hex);
+        DELEGATION_FPS.save(storage, staking_tx_hash.as_ref(), &delegation_fps)?;
+
+        // Settle the rewards of the delegations still staking in the finality provider
+        let btc_del = DELEGATIONS.load(storage, staking_tx_hash.as_ref())?;
+        let unbonding = UNBONDING_INTENTS
+            .may_load(storage, staking_tx_hash.as_ref())?
+            .is_some_and(|intent| intent.processed);
+        if btc_del.is_active()
+            && !unbonding
+            && rewards::is_stake_tracked(storage, staking_tx_hash.as_ref())?
+        {
+            rewards::remove_stake(
+                storage,
+                fp_btc_pk_hex,
+                &btc_del.staker_addr,
+                btc_del.total_sat,
+            )?;
+        }
+    }
+    FP_DELEGATIONS.remove(storage, fp_btc_pk_hex);
+
+    let power = fps()
+        .may_load(storage, fp_btc_pk_hex)?
+        .unwrap_or_default()
+        .power;
+    fps().remove(storage, fp_btc_pk_hex, height)?;
+    FPS.remove(storage, fp_btc_pk_hex);
+    FP_EXITS.remove(storage, fp_btc_pk_hex);
+    FP_JAILS.remove(storage, fp_btc_pk_hex);
+    FP_METADATA.remove(storage, fp_btc_pk_hex);
+
+    removal.removed_height = Some(height);
+    FP_REMOVALS.save(storage, fp_btc_pk_hex, &removal)?;
+
+    let event = Event::new("fp_removed")
+        .add_attribute("fp_btc_pk_hex", fp_btc_pk_hex)
+        .add_attribute("reason", &removal.reason)
+        .add_attribute("power", power.to_string())
+        .add_attribute("delegations", fp_delegations.len().to_string())
+        .add_attribute("height", height.to_string());
+    Ok((removal, event))
+}
+
+/// handle_begin_block processes the unbonding intents matured at the current height
+pub fn handle_begin_block(deps: DepsMut, env: Env) -> Result<Response<BabylonMsg>, ContractError> {
+    let events = process_unbonding_intents(deps.storage, env.block.height)?;
+    Ok(Response::new().add_events(events))
+}
+
+/// handle_end_block expires the active delegations whose staking time-lock has ended at the BTC
+/// tip reported by the Babylon contract, and removes their voting power
+pub fn handle_end_block(deps: DepsMut, env: Env) -> Result<Response<BabylonMsg>, ContractError> {
+    // The Babylon contract has no BTC tip until it has received its BTC headers
+    let Ok(btc_tip_height) = get_btc_tip_height(&deps) else {
+        return Ok(Response::new());
+    };
+    BTC_TIP_HEIGHT.save(deps.storage, &btc_tip_height)?;
+    let events = process_expired_delegations(deps.storage, env.block.height, btc_tip_height)?;
+    Ok(Response::new().add_events(events))
+}
+
+/// process_expired_delegations expires the active delegations whose end height is below the
+/// given BTC tip height, and removes their voting power (unless an unbonding intent has already
+/// done so)
+fn process_expired_delegations(
+    storage: &mut dyn Storage,
+    height: u64,
+    btc_tip_height: u64,
+) -> Result<Vec<Event>, ContractError> {
+    let expired = DELEGATION_EXPIRIES
+        .keys(
+            storage,
+            None,
+            Some(Bound::exclusive((btc_tip_height, &[0u8; HASH_SIZE]))),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut events = vec![];
+    for (end_height, staking_tx_hash) in expired {
+        DELEGATION_EXPIRIES.remove(storage, (end_height, &staking_tx_hash));
+        let mut btc_del = DELEGATIONS.load(storage, &staking_tx_hash)?;
+        // Unbonded or slashed delegations have already lost their voting power
+        if !btc_del.is_active() {
+            continue;
+        }
+        let staking_tx_hash = TxHash::from_byte_array(staking_tx_hash);
+        let fp_events = if settle_unbonding_intent(storage, &staking_tx_hash)? {
+            vec![]
+        } else {
+            discount_fp_stake(storage, height, &staking_tx_hash, &btc_del)?
+        };
+        btc_del.status = DelegationStatus::Expired;
+        DELEGATIONS.save(storage, staking_tx_hash.as_ref(), &btc_del)?;
+
+        events.push(
+            Event::new("btc_delegation_expired")
+                .add_attribute("staking_tx_hash", staking_tx_hash.to_string())
+                .add_attribute("end_height", end_height.to_string())
+                .add_attribute("btc_tip_height", btc_tip_height.to_string())
+                .add_attribute("height", height.to_string()),
+        );
+        events.extend(fp_events);
+    }
+    Ok(events)
+}
+
+/// process_unbonding_intents removes the voting power of the delegations whose unbonding intent
+/// has matured at the given height
+fn process_unbonding_intents(
+    storage: &mut dyn Storage,
+    height: u64,
+) -> Result<Vec<Event>, ContractError> {
+    let matured = PENDING_UNBONDINGS
+        .keys(
+            storage,
+            None,
+            Some(Bound::exclusive((height + 1, &[0u8; HASH_SIZE]))),
+            Order::Ascending,
+        )
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut events = vec![];
+    for (unbond_height, staking_tx_hash) in matured {
+        PENDING_UNBONDINGS.remove(storage, (unbond_height, &staking_tx_hash));
+        let mut intent = UNBONDING_INTENTS.load(storage, &staking_tx_hash)?;
+        intent.processed = true;
+        UNBONDING_INTENTS.save(storage, &staking_tx_hash, &intent)?;
+
+        let btc_del = DELEGATIONS.load(storage, &staking_tx_hash)?;
+        let fp_events = discount_fp_stake(
+            storage,
+            height,
+            &TxHash::from_byte_array(staking_tx_hash),
+            &btc_del,
+        )?;
+        events.push(
+            Event::new("btc_unbonding_intent_processed")
+                .add_attribute("staking_tx_hash", intent.staking_tx_hash)
+                .add_attribute("height", height.to_string()),
+        );
+        events.extend(fp_events);
+    }
+    Ok(events)
+}
+
+/// settle_unbonding_intent settles the unbonding intent (if any) of a delegation that Babylon
+/// reports as unbonded or slashed.
+/// Returns whether the voting power of the delegation has already been removed by the intent
+fn settle_unbonding_intent(
+    storage: &mut dyn Storage,
+    staking_tx_hash: &TxHash,
+) -> Result<bool, ContractError> {
+    let Some(mut intent) = UNBONDING_INTENTS.may_load(storage, staking_tx_hash.as_ref())? else {
+        return Ok(false);
+    };
+    if intent.processed {
+        return Ok(true);
+    }
+    PENDING_UNBONDINGS.remove(storage, (intent.unbond_height, staking_tx_hash.as_ref()));
+    intent.processed = true;
+    UNBONDING_INTENTS.save(storage, staking_tx_hash.as_ref(), &intent)?;
+    Ok(false)
+}
+
+/// discount_fp_stake discounts the voting power, and the self-stake if the delegation is a
+/// self-delegation, of the given BTC delegation from the finality providers it delegates to
+fn discount_fp_stake(
+    storage: &mut dyn Storage,
+    height: u64,
+    staking_tx_hash: &TxHash,
+    btc_del: &BtcDelegation,
+) -> Result<Vec<Event>, ContractError> {
+    let params = PARAMS.load(storage)?;
+    let affected_fps = DELEGATION_FPS.load(storage, staking_tx_hash.as_ref())?;
+    let stake_tracked = rewards::is_stake_tracked(storage, staking_tx_hash.as_ref())?;
+    let mut events = vec![];
+    for fp_btc_pk_hex in affected_fps {
+        let fp = FPS.load(storage, &fp_btc_pk_hex)?;
+        let is_self_delegation = fp.addr == btc_del.staker_addr;
+        if fps().may_load(storage, &fp_btc_pk_hex)?.is_none() {
+            return Err(ContractError::FinalityProviderNotFound(fp_btc_pk_hex)); // should never happen
+        }
+        let ev = update_fp_stake(storage, &params, height, &fp_btc_pk_hex, |fp_state| {
+            fp_state.power = fp_state.power.saturating_sub(btc_del.total_sat);
+            if is_self_delegation {
+                fp_state.self_stake = fp_state.self_stake.saturating_sub(btc_del.total_sat);
+            }
+            Ok(())
+        })?;
+        events.extend(ev);
+        if stake_tracked {
+            rewards::remove_stake(
+                storage,
+                &fp_btc_pk_hex,
+                &btc_del.staker_addr,
+                btc_del.total_sat,
+            )?;
+        }
+    }
+    Ok(events)
+}
+
+/// update_fp_stake applies `update` to the state of the given finality provider.
+/// Returns an activation / deactivation event if the finality provider's self-stake crosses the
+/// `min_fp_self_stake_sat` threshold, and an exit event if a deregistered finality provider's
+/// voting power drops to zero
+fn update_fp_stake(
+    storage: &mut dyn Storage,
+    params: &Params,
+    height: u64,
+    fp_btc_pk_hex: &str,
+    update: impl FnOnce(&mut FinalityProviderState) -> Result<(), OverflowError>,
+) -> Result<Vec<Event>, ContractError> {
+    let min_self_stake = params.min_fp_self_stake_sat;
+    let mut crossed = None;
+    let mut power = 0;
+    fps().update(storage, fp_btc_pk_hex, height, |fp_state| {
+        let mut fp_state = fp_state.unwrap_or_default();
+        let was_eligible = fp_state.self_stake >= min_self_stake;
+        update(&mut fp_state).map_err(StdError::from)?;
+        let is_eligible = fp_state.self_stake >= min_self_stake;
+        if was_eligible != is_eligible {
+            crossed = Some((is_eligible, fp_state.self_stake));
+        }
+        power = fp_state.power;
+        Ok::<_, ContractError>(fp_state)
+    })?;
+
+    let mut events = vec![];
+    if let Some((activated, self_stake)) = crossed {
+        let ty = if activated {
+            "fp_self_stake_activated"
+        } else {
+            "fp_self_stake_deactivated"
+        };
+        events.push(
+            Event::new(ty)
+                .add_attribute("fp_btc_pk_hex", fp_btc_pk_hex)
+                .add_attribute("self_stake", self_stake.to_string())
+                .add_attribute("min_fp_self_stake_sat", min_self_stake.to_string())
+                .add_attribute("height", height.to_string()),
+        );
+    }
+    if power == 0 {
+        events.extend(complete_fp_exit(storage, height, fp_btc_pk_hex)?);
+    }
+    Ok(events)
+}
+
+/// handle_slash_fp handles FP slashing at the staking level
+pub fn handle_slash_fp(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    fp_btc_pk_hex: &str,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.babylon && !ADMIN.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(ContractError::Unauthorized);
+    }
+    slash_finality_provider(deps, env, fp_btc_pk_hex)
+}
+
+/// btc_undelegate adds the signature of the unbonding tx signed by the staker to the given BTC
+/// delegation
+fn btc_undelegate(
+    storage: &mut dyn Storage,
+    staking_tx_hash: &TxHash,
+    btc_del: &mut BtcDelegation,
+    unbonding_tx_sig: &[u8],
+) -> Result<(), ContractError> {
+    btc_del.undelegation_info.delegator_unbonding_sig = unbonding_tx_sig.to_vec();
+    btc_del.status = DelegationStatus::Unbonding;
+
+    // Set BTC delegation back to KV store
+    DELEGATIONS.save(storage, staking_tx_hash.as_ref(), btc_del)?;
+
+    // TODO? Notify subscriber about this unbonded BTC delegation
+    //  - Who are subscribers in this context?
+    //  - How to notify them? Emit event?
+
+    // TODO? Record event that the BTC delegation becomes unbonded at this height
+
+    Ok(())
+}
+
+/// `slash_finality_provider` slashes a finality provider with the given PK.
+/// A slashed finality provider will not have voting power
+pub(crate) fn slash_finality_provider(
+    deps: DepsMut,
+    env: Env,
+    fp_btc_pk_hex: &str,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    // Ensure finality provider exists
+    let mut fp = FPS.load(deps.storage, fp_btc_pk_hex)?;
+
+    // Check if the finality provider is already slashed
+    if fp.slashed_height > 0 {
+        return Err(ContractError::FinalityProviderAlreadySlashed(
+            fp_btc_pk_hex.to_string(),
+        ));
+    }
+    // Set the finality provider as slashed
+    fp.slashed_height = env.block.height;
+
+    // Set BTC slashing height (if available from the babylon contract)
+    // FIXME: Turn this into a hard error
+    // return fmt.Errorf("failed to get current BTC tip")
+    let btc_height = get_btc_tip_height(&deps).unwrap_or_default();
+    fp.slashed_btc_height = btc_height;
+
+    // Record slashed event. The next `BeginBlock` will consume this event for updating the active
+    // FP set.
+    // We simply set the FP voting power to zero from the next *processing* height (See NOTE in
+    // `handle_finality_signature`)
+    fps().update(deps.storage, fp_btc_pk_hex, env.block.height + 1, |fp| {
+        let mut fp = fp.unwrap_or_default();
+        fp.power = 0;
+        Ok::<_, ContractError>(fp)
+    })?;
+
+    // Save the finality provider back
+    FPS.save(deps.storage, fp_btc_pk_hex, &fp)?;
+
+    // TODO: Add events
+    Ok(Response::new())
+}
+
+/// handle_jail_fp jails a finality provider, on behalf of the Babylon contract (routing it from
+/// the finality contract) or the admin.
+/// Jailing an unknown, slashed or already jailed finality provider is a no-op, so that the
+/// finality contract's block processing is not disrupted
+pub fn handle_jail_fp(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    fp_btc_pk_hex: &BtcPkHex,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.babylon && !ADMIN.is_admin(deps.as_ref(), &info.sender)? {
+        return Err(ContractError::Unauthorized);
+    }
+    let fp_btc_pk_hex = fp_btc_pk_hex.to_string();
+    let Some(fp) = FPS.may_load(deps.storage, &fp_btc_pk_hex)? else {
+        return Ok(Response::new());
+    };
+    if fp.slashed_height > 0 || FP_JAILS.has(deps.storage, &fp_btc_pk_hex) {
+        return Ok(Response::new());
+    }
+
+    let height = env.block.height;
+    let params = PARAMS.load(deps.storage)?;
+    let jail = FpJail {
+        jailed_height: height,
+        unjailable_height: height + params.jail_downtime,
+    };
+    FP_JAILS.save(deps.storage, &fp_btc_pk_hex, &jail)?;
+    fps().update(deps.storage, &fp_btc_pk_hex, height, |fp_state| {
+        let mut fp_state = fp_state.unwrap_or_default();
+        fp_state.jailed = true;
+        Ok::<_, ContractError>(fp_state)
+    })?;
+
+    let event = Event::new("fp_jailed")
+        .add_attribute("fp_btc_pk_hex", &fp_btc_pk_hex)
+        .add_attribute("height", height.to_string())
+        .add_attribute("unjailable_height", jail.unjailable_height.to_string());
+    Ok(Response::new().add_event(event))
+}
+
+/// handle_unjail_fp unjails a finality provider, by its address or the address bound to its BTC
+/// key, once its jail downtime has elapsed. Its voting power is restored
+pub fn handle_unjail_fp(
+    deps: DepsMut,
+    env: Env,
+    info: &MessageInfo,
+    fp_btc_pk_hex: &BtcPkHex,
+) -> Result<Response<BabylonMsg>, ContractError> {
+    let fp_btc_pk_hex = fp_btc_pk_hex.to_string();
+    let fp = FPS
+        .may_load(deps.storage, &fp_btc_pk_hex)?
+        .ok_or_else(|| ContractError::FinalityProviderNotFound(fp_btc_pk_hex.clone()))?;
+
+    // Only the finality provider, or the address bound to its BTC key, can unjail it
+    if info.sender.as_str() != fp.addr
+        && STAKER_BINDINGS.may_load(deps.storage, &fp_btc_pk_hex)? != Some(info.sender.clone())
+    {
+        return Err(ContractError::Unauthorized);
+    }
+    if fp.slashed_height > 0 {
+        return Err(ContractError::FinalityProviderAlreadySlashed(fp_btc_pk_hex));
+    }
+    let jail = FP_JAILS
+        .may_load(deps.storage, &fp_btc_pk_hex)?
+        .ok_or_else(|| ContractError::FinalityProviderNotJailed(fp_btc_pk_hex.clone()))?;
+    let height = env.block.height;
+    if height < jail.unjailable_height {
+        return Err(ContractError::FinalityProviderJailed(
+            fp_btc_pk_hex,
+            jail.unjailable_height,
+        ));
+    }
+
+    FP_JAILS.remove(deps.storage, &fp_btc_pk_hex);
+    let mut power = 0;
+    fps().update(deps.storage, &fp_btc_pk_hex, height, |fp_state| {
+        let mut fp_state = fp_state.unwrap_or_default();
+        fp_state.jailed = false;
+        power = fp_state.power;
+        Ok::<_, ContractError>(fp_state)
+    })?;
+
+    let event = Event::new("fp_unjailed")
+        .add_attribute("fp_btc_pk_hex", &fp_btc_pk_hex)
+        .add_attribute("height", height.to_string())
+        .add_attribute("power", power.to_string());
+    Ok(Response::new().add_event(event))
+}
+
+/// check_babylon_config checks, once, that the BTC network and confirmation depth in the params
+/// match the ones of the Babylon contract, so that misdeployments are surfaced before any
+/// delegation is processed.
+/// The check is skipped if the Babylon address is not a contract, e.g. when the staking contract
+/// is operated directly by an admin
+pub(crate) fn check_babylon_config(deps: &mut DepsMut) -> Result<(), ContractError> {
+    if BABYLON_CONFIG_CHECKED
+        .may_load(deps.storage)?
+        .unwrap_or(false)
+    {
+        return Ok(());
+    }
+
+    let babylon_addr = CONFIG.load(deps.storage)?.babylon;
+    if deps.querier.query_wasm_contract_info(&babylon_addr).is_ok() {
+        let babylon_cfg: BabylonConfig = deps
+            .querier
+            .query_wasm_smart(&babylon_addr, &BabylonQueryMsg::Config {})?;
+        let params = PARAMS.load(deps.storage)?;
+        if babylon_cfg.network != params.btc_network {
+            return Err(ContractError::BabylonConfigMismatch(format!(
+                "BTC network {:?} != {:?}",
+                params.btc_network, babylon_cfg.network
+            )));
+        }
+        if let Some(depth) = params.btc_confirmation_depth {
+            if depth != babylon_cfg.btc_confirmation_depth {
+                return Err(ContractError::BabylonConfigMismatch(format!(
+                    "BTC confirmation depth {depth} != {}",
+                    babylon_cfg.btc_confirmation_depth
+                )));
+            }
+        }
+    }
+
+    BABYLON_CONFIG_CHECKED.save(deps.storage, &true)?;
+    Ok(())
+}
+
+/// get_btc_tip_height queries the Babylon contract for the latest BTC tip height
+fn get_btc_tip_height(deps: &DepsMut) -> Result<u64, ContractError> {
+    // Get the BTC tip from the babylon contract through a raw query
+    let babylon_addr = CONFIG.load(deps.storage)?.babylon;
+
+    // Query the Babylon contract
+    // TODO: use a raw query for performance / efficiency
+    let query_msg = BabylonQueryMsg::BtcTipHeader {};
+    let tip: BtcHeaderResponse = deps.querier.query_wasm_smart(babylon_addr, &query_msg)?;
+
+    Ok(tip.height)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{
+        message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{Attribute, Binary, Decimal, OwnedDeps};
+
+    use test_utils::{
+        create_new_finality_provider, create_new_fp_sk, get_active_btc_delegation,
+        get_btc_del_unbonding_sig, get_derived_btc_delegation,
+    };
+
+    use babylon_apis::cw20::Cw20Coin;
+
+    use crate::contract::tests::{CREATOR, INIT_ADMIN};
+    use crate::contract::{execute, instantiate, migrate, sudo};
+    use crate::msg::{
+        CommissionVestingInfo, ExecuteMsg, FpPendingRewards, InstantiateMsg, MigrateMsg, SudoMsg,
+    };
+    use crate::queries;
+    use crate::state::staking::{BtcUndelegationInfo, DelegationStatus};
+    use crate::test_utils::staking_params;
+
+    /// bind_staker binds the given staker key to the given address, as `ExecuteMsg::BindStaker`
+    /// does, given that the test data has no staker secret key to sign the binding with
+    fn bind_staker(storage: &mut dyn Storage, btc_pk_hex: &str, addr: &cosmwasm_std::Addr) {
+        STAKER_BINDINGS.save(storage, btc_pk_hex, addr).unwrap();
+        ADDR_STAKER_BINDINGS
+            .save(storage, (addr, btc_pk_hex), &())
+            .unwrap();
+    }
+
+    // Compute staking tx hash of a delegation
+    pub(crate) fn staking_tx_hash(del: &BtcDelegation) -> TxHash {
+        let staking_tx: Transaction = deserialize(&del.staking_tx).unwrap();
+        staking_tx.txid().into()
+    }
+
+    #[test]
+    fn test_add_fp_unauthorized() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let init_admin = deps.api.addr_make(INIT_ADMIN);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: None,
+                admin: Some(init_admin.to_string()), // Admin provided
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+
+        // Only the Creator or Admin can call this
+        let other_info = message_info(&deps.api.addr_make("other"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), other_info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+    }
+
+    #[test]
+    fn test_add_fp_admin() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let init_admin = deps.api.addr_make(INIT_ADMIN);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: None,
+                admin: Some(init_admin.to_string()), // Admin provided
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let admin_info = message_info(&init_admin, &[]); // Mock info for the admin
+        let new_fp = create_new_finality_provider(1);
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+
+        // Use admin_info to execute the message
+        let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // Check the finality provider has been stored
+        let query_res =
+            queries::finality_provider(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap()).unwrap();
+        // get DB object
+        let fp = FinalityProvider::from(&new_fp);
+        assert_eq!(query_res, fp);
+
+        // Trying to add the same fp again fails
+        let err = execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FinalityProviderAlreadyExists(new_fp.btc_pk_hex.clone())
+        );
+    }
+
+    #[test]
+    fn duplicate_fp_registrations_work() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let init_admin = deps.api.addr_make(INIT_ADMIN);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            InstantiateMsg {
+                params: Some(Params {
+                    duplicate_fps: DuplicateFps::Skip,
+                    ..staking_params()
+                }),
+                admin: Some(init_admin.to_string()),
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let admin_info = message_info(&init_admin, &[]);
+        let new_fp = create_new_finality_provider(1);
+        let mut resent_fp = new_fp.clone();
+        resent_fp.commission = Decimal::percent(20);
+        resent_fp.description = None;
+        let btc_staking = |fp: &NewFinalityProvider| ExecuteMsg::BtcStaking {
+            new_fp: vec![fp.clone()],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let duplicate_event = |action: &str| {
+            Event::new("duplicate_finality_provider")
+                .add_attribute("btc_pk_hex", &new_fp.btc_pk_hex)
+                .add_attribute("action", action)
+        };
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            btc_staking(&new_fp),
+        )
+        .unwrap();
+
+        // A re-sent registration is skipped, leaving the finality provider untouched
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            btc_staking(&resent_fp),
+        )
+        .unwrap();
+        assert!(res.events.contains(&duplicate_event("skipped")));
+        let fp = FPS.load(&deps.storage, &new_fp.btc_pk_hex).unwrap();
+        assert_eq!(fp, FinalityProvider::from(&new_fp));
+
+        // In update mode, its mutable fields are updated instead
+        PARAMS
+            .update(&mut deps.storage, |mut params| -> StdResult<_> {
+                params.duplicate_fps = DuplicateFps::Update;
+                Ok(params)
+            })
+            .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            btc_staking(&resent_fp),
+        )
+        .unwrap();
+        assert!(res.events.contains(&duplicate_event("updated")));
+        let fp = FPS.load(&deps.storage, &new_fp.btc_pk_hex).unwrap();
+        assert_eq!(fp.commission, Decimal::percent(20));
+        assert_eq!(fp.description, None);
+        assert_eq!(fp.addr, new_fp.addr);
+
+        // Re-sent registrations failing validation are still quarantined
+        let mut invalid_fp = resent_fp.clone();
+        invalid_fp.commission = Decimal::percent(150);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info,
+            btc_staking(&invalid_fp),
+        )
+        .unwrap();
+        let result: BtcStakingResult = cosmwasm_std::from_json(res.data.unwrap()).unwrap();
+        assert_eq!(result.quarantined, vec![new_fp.btc_pk_hex.clone()]);
+        let fp = FPS.load(&deps.storage, &new_fp.btc_pk_hex).unwrap();
+        assert_eq!(fp.commission, Decimal::percent(20));
+    }
+
+    #[test]
+    fn verify_staking_tx_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(Params {
+                    verify_staking_tx: true,
+                    ..staking_params()
+                }),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let staking_tx_hash_hex =
+            staking_tx_hash(&BtcDelegation::from(&active_delegation)).to_string();
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let btc_staking = |active_del: ActiveBtcDelegation| ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![active_del],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+
+        // Delegations inconsistent with their staking output are rejected
+        let inconsistent = [
+            ActiveBtcDelegation {
+                total_sat: active_delegation.total_sat + 1,
+                ..active_delegation.clone()
+            },
+            ActiveBtcDelegation {
+                end_height: active_delegation.end_height + 1,
+                ..active_delegation.clone()
+            },
+            ActiveBtcDelegation {
+                staking_output_idx: 5,
+                ..active_delegation.clone()
+            },
+        ];
+        for del in inconsistent {
+            execute(deps.as_mut(), mock_env(), info.clone(), btc_staking(del)).unwrap();
+            let item =
+                queries::quarantined_item(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+            assert!(item.reason.starts_with("code 827:"), "{}", item.reason);
+            QUARANTINE.remove(deps.as_mut().storage, &staking_tx_hash_hex);
+        }
+        queries::delegation(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap_err();
+
+        // The actual staking output is accepted
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            btc_staking(active_delegation),
+        )
+        .unwrap();
+        queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
+    }
+
+    #[test]
+    fn active_delegation_happy_path() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        let params = staking_params();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        // Build valid active delegation
+        let active_delegation = get_active_btc_delegation();
+
+        // Register one FP first
+        let mut new_fp = create_new_finality_provider(1);
+        new_fp
+            .btc_pk_hex
+            .clone_from(&active_delegation.fp_btc_pk_list[0]);
+
+        // Check that the finality provider has no power yet
+        let res = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        );
+        assert!(matches!(
+            res,
+            Err(ContractError::FinalityProviderNotFound(pk)) if pk == new_fp.btc_pk_hex
+        ));
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Now add the active delegation
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // Check the active delegation is being stored
+        let delegation = BtcDelegation::from(&active_delegation);
+        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
+        // and reported in the response data
+        let result: BtcStakingResult = cosmwasm_std::from_json(res.data.unwrap()).unwrap();
+        assert_eq!(result.active_delegations, vec![staking_tx_hash_hex.clone()]);
+        let query_res = queries::delegation(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        assert_eq!(query_res, delegation);
+
+        // Check that the finality provider power has been updated
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, active_delegation.total_sat);
+
+        // Redelivering the active delegation is a no-op, flagged as a duplicate
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(res.events.contains(
+            &Event::new("active_delegation")
+                .add_attribute("staking_tx_hash", &staking_tx_hash_hex)
+                .add_attribute("duplicate", "true")
+        ));
+        let result: BtcStakingResult = cosmwasm_std::from_json(res.data.unwrap()).unwrap();
+        assert!(result.active_delegations.is_empty());
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, active_delegation.total_sat);
+    }
+
+    #[test]
+    fn undelegation_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        let params = staking_params();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        // Register one FP first
+        let new_fp = create_new_finality_provider(1);
+
+        // Build valid active delegation
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // Check the delegation is active (it has no unbonding or slashing tx signature)
+        let active_delegation_undelegation = active_delegation.undelegation_info.clone();
+        // Compute the staking tx hash
+        let delegation = BtcDelegation::from(&active_delegation);
+        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
+
+        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        let btc_undelegation = btc_del.undelegation_info;
+        assert_eq!(
+            btc_undelegation,
+            BtcUndelegationInfo {
+                unbonding_tx: active_delegation_undelegation.unbonding_tx.to_vec(),
+                slashing_tx: active_delegation_undelegation.slashing_tx.to_vec(),
+                delegator_unbonding_sig: vec![],
+                delegator_slashing_sig: active_delegation_undelegation
+                    .delegator_slashing_sig
+                    .to_vec(),
+                covenant_unbonding_sig_list: vec![],
+                covenant_slashing_sigs: vec![],
+            }
+        );
+
+        let unbonding_sig = get_btc_del_unbonding_sig(1, &[1]);
+
+        // Now send the undelegation message
+        let undelegation = UnbondedBtcDelegation {
+            staking_tx_hash: staking_tx_hash_hex.clone(),
+            unbonding_tx_sig: unbonding_sig.to_bytes().into(),
+        };
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![undelegation.clone()],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // Check the delegation is not active any more (updated with the unbonding tx signature)
+        let active_delegation_undelegation = active_delegation.undelegation_info;
+        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
+        let btc_undelegation = btc_del.undelegation_info;
+        assert_eq!(
+            btc_undelegation,
+            BtcUndelegationInfo {
+                unbonding_tx: active_delegation_undelegation.unbonding_tx.into(),
+                slashing_tx: active_delegation_undelegation.slashing_tx.into(),
+                delegator_unbonding_sig: unbonding_sig.to_bytes().into(),
+                delegator_slashing_sig: active_delegation_undelegation
+                    .delegator_slashing_sig
+                    .into(),
+                covenant_unbonding_sig_list: vec![],
+                covenant_slashing_sigs: vec![],
+            }
+        );
+
+        // Check the finality provider power has been updated
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, 0);
+    }
+
+    #[test]
+    fn slashed_delegation_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        let params = staking_params();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        // Register one FP first
+        let new_fp = create_new_finality_provider(1);
+
+        // Build valid active delegation
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // Check the delegation is active (it has no unbonding sig or is slashed)
+        // Compute the staking tx hash
+        let delegation = BtcDelegation::from(&active_delegation);
+        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
+        // Query the delegation
+        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        assert!(&btc_del.undelegation_info.delegator_unbonding_sig.is_empty());
+        assert!(!btc_del.slashed);
+
+        // Check the finality provider has power
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, btc_del.total_sat);
+
+        // Now send the slashed delegation message
+        let fp_sk = create_new_fp_sk(1);
+        let fp_sk_hex = hex::encode(fp_sk.to_bytes());
+        let slashed = SlashedBtcDelegation {
+            staking_tx_hash: staking_tx_hash_hex.clone(),
+            recovered_fp_btc_sk: fp_sk_hex,
+        };
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            unbonded_del: vec![],
+            slashed_del: vec![slashed.clone()],
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+        // Check events
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty.as_str(), "btc_undelegation_slashed");
+        assert_eq!(res.events[0].attributes.len(), 2);
+        assert_eq!(res.events[0].attributes[0].key.as_str(), "staking_tx_hash");
+        assert_eq!(
+            res.events[0].attributes[0].value.as_str(),
+            staking_tx_hash_hex
+        );
+        assert_eq!(res.events[0].attributes[1].key.as_str(), "height");
+
+        // Check the delegation is not active any more (slashed)
+        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
+        assert!(btc_del.slashed);
+        // Check the unbonding sig is still empty
+        assert!(btc_del.undelegation_info.delegator_unbonding_sig.is_empty());
+
+        // Check the finality provider power has been zeroed (it has only this delegation that was
+        // slashed)
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, 0);
+    }
+
+    #[test]
+    fn fp_self_stake_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        // Build valid active delegation
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+
+        // Require the whole delegation as self-stake
+        let mut params = staking_params();
+        params.min_fp_self_stake_sat = active_delegation.total_sat;
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        // Register one FP
+        let new_fp = create_new_finality_provider(1);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Make the FP operated by the staker of the delegation.
+        // Done directly in storage, as the FP's PoP is over its address
+        let mut fp = FPS.load(&deps.storage, &new_fp.btc_pk_hex).unwrap();
+        fp.addr.clone_from(&active_delegation.staker_addr);
+        FPS.save(&mut deps.storage, &new_fp.btc_pk_hex, &fp)
+            .unwrap();
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // The FP crosses the self-stake threshold
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty.as_str(), "fp_self_stake_activated");
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, active_delegation.total_sat);
+        assert_eq!(fp.self_stake, active_delegation.total_sat);
+
+        // Undelegate
+        let delegation = BtcDelegation::from(&active_delegation);
+        let undelegation = UnbondedBtcDelegation {
+            staking_tx_hash: staking_tx_hash(&delegation).to_string(),
+            unbonding_tx_sig: get_btc_del_unbonding_sig(1, &[1]).to_bytes().into(),
+        };
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![undelegation],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // The FP falls below the self-stake threshold
+        assert_eq!(res.events.len(), 2);
+        assert_eq!(res.events[0].ty.as_str(), "btc_undelegation");
+        assert_eq!(res.events[1].ty.as_str(), "fp_self_stake_deactivated");
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, 0);
+        assert_eq!(fp.self_stake, 0);
+    }
+
+    #[test]
+    fn unbond_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        let mut params = staking_params();
+        params.unbonding_intent_delay = 10;
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let delegation = BtcDelegation::from(&active_delegation);
+        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
+        let unbond_msg = ExecuteMsg::Unbond {
+            staking_tx_hash: staking_tx_hash_hex.clone(),
+        };
+
+        // Unbonding requires the staker key to be bound to a consumer address
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), unbond_msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::StakerNotBound(active_delegation.btc_pk_hex.clone())
+        );
+        let staker = deps.api.addr_make("staker");
+        bind_staker(&mut deps.storage, &active_delegation.btc_pk_hex, &staker);
+
+        // Only the bound address can unbond
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), unbond_msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+
+        let staker_info = message_info(&staker, &[]);
+        let mut env = mock_env();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            staker_info.clone(),
+            unbond_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty.as_str(), "btc_unbonding_intent");
+
+        // The intent is recorded, but the delegation keeps its power until the delay elapses
+        let intent = queries::unbonding_intent(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        assert_eq!(intent.unbond_height, env.block.height + 10);
+        assert!(!intent.processed);
+        let intents = queries::unbonding_intents(deps.as_ref(), None, None, Some(true)).unwrap();
+        assert_eq!(intents.intents, vec![intent]);
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, active_delegation.total_sat);
+
+        // Unbonding twice fails
+        let err = execute(deps.as_mut(), env.clone(), staker_info, unbond_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnbondingIntentAlreadyExists(staking_tx_hash_hex.clone())
+        );
+
+        // The power is removed once the delay elapses
+        env.block.height += 10;
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(res.events[0].ty.as_str(), "btc_unbonding_intent_processed");
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, 0);
+        let intents = queries::unbonding_intents(deps.as_ref(), None, None, Some(true)).unwrap();
+        assert!(intents.intents.is_empty());
+
+        // Babylon reporting the unbonding afterwards is still accepted
+        let undelegation = UnbondedBtcDelegation {
+            staking_tx_hash: staking_tx_hash_hex.clone(),
+            unbonding_tx_sig: get_btc_del_unbonding_sig(1, &[1]).to_bytes().into(),
+        };
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![undelegation],
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
+        assert!(!btc_del.is_active());
+    }
+
+    #[test]
+    fn begin_block_processes_unbonding_intents() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        let mut params = staking_params();
+        params.unbonding_intent_delay = 10;
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let delegation = BtcDelegation::from(&active_delegation);
+        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
+        let staker = deps.api.addr_make("staker");
+        bind_staker(&mut deps.storage, &active_delegation.btc_pk_hex, &staker);
+        let staker_info = message_info(&staker, &[]);
+        let mut env = mock_env();
+        let unbond_msg = ExecuteMsg::Unbond {
+            staking_tx_hash: staking_tx_hash_hex.clone(),
+        };
+        execute(deps.as_mut(), env.clone(), staker_info, unbond_msg).unwrap();
+
+        let begin_block = SudoMsg::BeginBlock {
+            hash_hex: "deadbeef".to_string(),
+            app_hash_hex: "".to_string(),
+        };
+        let fp_power = |deps: cosmwasm_std::Deps| {
+            queries::finality_provider_info(deps, new_fp.btc_pk_hex.parse().unwrap(), None)
+                .unwrap()
+                .power
+        };
+
+        // Nothing matures before the delay elapses
+        env.block.height += 9;
+        let res = sudo(deps.as_mut(), env.clone(), begin_block.clone()).unwrap();
+        assert!(res.events.is_empty());
+        assert_eq!(fp_power(deps.as_ref()), active_delegation.total_sat);
+
+        // The begin block hook removes the power once it does, without any BTC staking operation
+        env.block.height += 1;
+        let res = sudo(deps.as_mut(), env.clone(), begin_block).unwrap();
+        assert_eq!(res.events[0].ty.as_str(), "btc_unbonding_intent_processed");
+        assert_eq!(fp_power(deps.as_ref()), 0);
+        let intent = queries::unbonding_intent(deps.as_ref(), staking_tx_hash_hex).unwrap();
+        assert!(intent.processed);
+
+        // End block is a no-op without a BTC tip
+        let end_block = SudoMsg::EndBlock {
+            hash_hex: "deadbeef".to_string(),
+            app_hash_hex: "".to_string(),
+        };
+        let res = sudo(deps.as_mut(), env, end_block).unwrap();
+        assert!(res.events.is_empty());
+    }
+
+    #[test]
+    fn end_block_expires_delegations() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let delegation = BtcDelegation::from(&active_delegation);
+        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
+        let end_block = SudoMsg::EndBlock {
+            hash_hex: "deadbeef".to_string(),
+            app_hash_hex: "".to_string(),
+        };
+        let fp_power = |deps: cosmwasm_std::Deps| {
+            queries::finality_provider_info(deps, new_fp.btc_pk_hex.parse().unwrap(), None)
+                .unwrap()
+                .power
+        };
+        // The Babylon contract reports the given BTC tip height
+        let set_btc_tip = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, height: u64| {
+            let tip = BtcHeaderResponse {
+                header: babylon_contract::msg::btc_header::BtcHeader {
+                    version: 0,
+                    prev_blockhash: "".to_string(),
+                    merkle_root: "".to_string(),
+                    time: 0,
+                    bits: 0,
+                    nonce: 0,
+                },
+                hash: "".to_string(),
+                height,
+                cum_work: Default::default(),
+            };
+            let tip = to_json_binary(&tip).unwrap();
+            deps.querier.update_wasm(move |_| {
+                cosmwasm_std::SystemResult::Ok(cosmwasm_std::ContractResult::Ok(tip.clone()))
+            });
+        };
+
+        // The delegation is pending until its time-lock begins
+        set_btc_tip(&mut deps, delegation.start_height - 1);
+        let res = sudo(deps.as_mut(), env.clone(), end_block.clone()).unwrap();
+        assert!(res.events.is_empty());
+        let status =
+            queries::delegation_status(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        assert_eq!(status.status, DelegationStatus::Pending);
+        assert_eq!(status.btc_tip_height, Some(delegation.start_height - 1));
+
+        // It is still active at its end height
+        set_btc_tip(&mut deps, delegation.end_height);
+        let res = sudo(deps.as_mut(), env.clone(), end_block.clone()).unwrap();
+        assert!(res.events.is_empty());
+        let status =
+            queries::delegation_status(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        assert_eq!(status.status, DelegationStatus::Active);
+        assert_eq!(fp_power(deps.as_ref()), active_delegation.total_sat);
+
+        // And expires, losing its voting power, once the BTC tip is past it
+        set_btc_tip(&mut deps, delegation.end_height + 1);
+        let res = sudo(deps.as_mut(), env.clone(), end_block.clone()).unwrap();
+        assert_eq!(res.events[0].ty.as_str(), "btc_delegation_expired");
+        assert_eq!(
+            res.events[0].attributes[0],
+            Attribute::new("staking_tx_hash", &staking_tx_hash_hex)
+        );
+        assert_eq!(fp_power(deps.as_ref()), 0);
+        let status =
+            queries::delegation_status(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        assert_eq!(status.status, DelegationStatus::Expired);
+        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
+        assert_eq!(btc_del.status, DelegationStatus::Expired);
+        assert!(!btc_del.is_active());
+
+        // Expiry happens only once
+        let res = sudo(deps.as_mut(), env.clone(), end_block).unwrap();
+        assert!(res.events.is_empty());
+        assert!(DELEGATION_EXPIRIES.is_empty(&deps.storage));
+
+        // Unbonding or slashing an expired delegation is a no-op
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            slashed_del: vec![SlashedBtcDelegation {
+                staking_tx_hash: staking_tx_hash_hex.clone(),
+                recovered_fp_btc_sk: "".to_string(),
+            }],
+            unbonded_del: vec![UnbondedBtcDelegation {
+                staking_tx_hash: staking_tx_hash_hex.clone(),
+                unbonding_tx_sig: get_btc_del_unbonding_sig(1, &[1]).to_bytes().into(),
+            }],
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert!(res.events.is_empty());
+        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
+        assert_eq!(btc_del.status, DelegationStatus::Expired);
+        assert!(!btc_del.slashed);
+        assert!(btc_del.undelegation_info.delegator_unbonding_sig.is_empty());
+        assert_eq!(fp_power(deps.as_ref()), 0);
+    }
+
+    #[test]
+    fn backfill_delegation_statuses_works() {
+        let mut deps = mock_dependencies();
+        let mut slashed = BtcDelegation::from(&get_derived_btc_delegation(1, &[1]));
+        slashed.slashed = true;
+        let mut unbonded = BtcDelegation::from(&get_derived_btc_delegation(2, &[1]));
+        unbonded.undelegation_info.delegator_unbonding_sig = vec![1];
+        let active = BtcDelegation::from(&get_derived_btc_delegation(3, &[1]));
+        for del in [&slashed, &unbonded, &active] {
+            DELEGATIONS
+                .save(deps.as_mut().storage, staking_tx_hash(del).as_ref(), del)
+                .unwrap();
+        }
+
+        // Delegations stored before their status was recorded are seen as active
+        assert!(slashed.is_active() && unbonded.is_active());
+        let (count, cursor) =
+            crate::state::staking::backfill_delegation_statuses(deps.as_mut().storage, None, 10)
+                .unwrap();
+        assert_eq!(count, 3);
+        assert!(cursor.is_some());
+        for (del, status) in [
+            (&slashed, DelegationStatus::Slashed),
+            (&unbonded, DelegationStatus::Unbonding),
+            (&active, DelegationStatus::Active),
+        ] {
+            let stored = DELEGATIONS
+                .load(&deps.storage, staking_tx_hash(del).as_ref())
+                .unwrap();
+            assert_eq!(stored.status, status);
+        }
+
+        // Only the active delegations are indexed for expiry, and backfilling again is a no-op
+        crate::state::staking::backfill_delegation_expiries(deps.as_mut().storage, None, 10)
+            .unwrap();
+        assert_eq!(
+            DELEGATION_EXPIRIES
+                .range(&deps.storage, None, None, Order::Ascending)
+                .count(),
+            1
+        );
+        crate::state::staking::backfill_delegation_statuses(deps.as_mut().storage, None, 10)
+            .unwrap();
+        let stored = DELEGATIONS
+            .load(&deps.storage, staking_tx_hash(&slashed).as_ref())
+            .unwrap();
+        assert_eq!(stored.status, DelegationStatus::Slashed);
+    }
+
+    #[test]
+    fn jail_fp_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let mut env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(Params {
+                    jail_downtime: 10,
+                    ..staking_params()
+                }),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let btc_pk_hex: BtcPkHex = new_fp.btc_pk_hex.parse().unwrap();
+        let fp_info = message_info(&cosmwasm_std::Addr::unchecked(&new_fp.addr), &[]);
+
+        // Only the Babylon contract (or the admin) can jail a finality provider
+        let jail_msg = ExecuteMsg::Jail {
+            fp_btc_pk_hex: btc_pk_hex.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            fp_info.clone(),
+            jail_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+
+        let jailed_height = env.block.height;
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), jail_msg.clone()).unwrap();
+        assert_eq!(res.events[0].ty, "fp_jailed");
+        let jail = queries::finality_provider_jail(deps.as_ref(), btc_pk_hex.clone()).unwrap();
+        assert_eq!(
+            jail,
+            FpJail {
+                jailed_height,
+                unjailable_height: jailed_height + 10,
+            }
+        );
+        // Jailing again is a no-op
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), jail_msg).unwrap();
+        assert!(res.events.is_empty());
+
+        // The jailed finality provider is excluded from the voting power table
+        env.block.height += 1;
+        let fp_power =
+            queries::finality_provider_info(deps.as_ref(), btc_pk_hex.clone(), None).unwrap();
+        assert_eq!(fp_power.power, 0);
+        let by_power = queries::finality_providers_by_power(deps.as_ref(), None, None).unwrap();
+        assert_eq!(by_power.fps[0].power, 0);
+
+        // Its operator can unjail it only after the downtime
+        let unjail_msg = ExecuteMsg::Unjail {
+            fp_btc_pk_hex: btc_pk_hex.clone(),
+        };
+        let err =
+            execute(deps.as_mut(), env.clone(), info.clone(), unjail_msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            fp_info.clone(),
+            unjail_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FinalityProviderJailed(new_fp.btc_pk_hex.clone(), jailed_height + 10)
+        );
+
+        env.block.height = jailed_height + 10;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            fp_info.clone(),
+            unjail_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.events[0],
+            Event::new("fp_unjailed")
+                .add_attribute("fp_btc_pk_hex", &new_fp.btc_pk_hex)
+                .add_attribute("height", env.block.height.to_string())
+                .add_attribute("power", active_delegation.total_sat.to_string())
+        );
+        // Its voting power is restored
+        let fp_power =
+            queries::finality_provider_info(deps.as_ref(), btc_pk_hex.clone(), None).unwrap();
+        assert_eq!(fp_power.power, active_delegation.total_sat);
+        queries::finality_provider_jail(deps.as_ref(), btc_pk_hex).unwrap_err();
+
+        // Unjailing a finality provider that is not jailed fails
+        let err = execute(deps.as_mut(), env, fp_info, unjail_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FinalityProviderNotJailed(new_fp.btc_pk_hex.clone())
+        );
+    }
+
+    #[test]
+    fn deregister_fp_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let deregister_msg = ExecuteMsg::DeregisterFinalityProvider {
+            btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+        };
+
+        // Only the finality provider can deregister itself
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            deregister_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+
+        let fp_info = message_info(&cosmwasm_std::Addr::unchecked(&new_fp.addr), &[]);
+        let mut env = mock_env();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            fp_info.clone(),
+            deregister_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty.as_str(), "fp_exit_requested");
+
+        // The finality provider is exiting, as it still has delegations
+        let exit =
+            queries::finality_provider_exit(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap())
+                .unwrap();
+        assert_eq!(exit.status, FpExitStatus::Exiting);
+        assert_eq!(exit.requested_height, env.block.height);
+        assert_eq!(exit.exited_height, None);
+
+        // Deregistering twice fails
+        let err = execute(deps.as_mut(), env.clone(), fp_info, deregister_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FinalityProviderDeregistered(new_fp.btc_pk_hex.clone())
+        );
+
+        // New delegations are not accepted
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![get_derived_btc_delegation(2, &[1])],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::FinalityProviderNotRegistered);
+
+        // The finality provider exits once its delegations are unbonded
+        env.block.height += 1;
+        let delegation = BtcDelegation::from(&active_delegation);
+        let undelegation = UnbondedBtcDelegation {
+            staking_tx_hash: staking_tx_hash(&delegation).to_string(),
+            unbonding_tx_sig: get_btc_del_unbonding_sig(1, &[1]).to_bytes().into(),
+        };
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![undelegation],
+        };
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res.events.iter().any(|ev| ev.ty == "fp_exited"));
+        let exit =
+            queries::finality_provider_exit(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap())
+                .unwrap();
+        assert_eq!(exit.status, FpExitStatus::Exited);
+        assert_eq!(exit.exited_height, Some(env.block.height));
+    }
+
+    #[test]
+    fn register_fp_deposit_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let env = mock_env();
+
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(Params {
+                    fp_registration_deposit: Some(coin(100, "stake")),
+                    fp_deposit_min_stake_sat: active_delegation.total_sat,
+                    ..staking_params()
+                }),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let fp_addr = cosmwasm_std::Addr::unchecked(&new_fp.addr);
+        let register_msg = ExecuteMsg::RegisterFinalityProvider { fp: new_fp.clone() };
+
+        // Only the finality provider's address can register it
+        let spammer_info = message_info(&deps.api.addr_make("spammer"), &coins(100, "stake"));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            spammer_info,
+            register_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+
+        // The deposit is required
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&fp_addr, &coins(99, "stake")),
+            register_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFpDeposit(_)));
+
+        let fp_info = message_info(&fp_addr, &coins(100, "stake"));
+        let res = execute(deps.as_mut(), env.clone(), fp_info.clone(), register_msg).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("fp_registered")
+                .add_attribute("btc_pk_hex", &new_fp.btc_pk_hex)
+                .add_attribute("addr", &new_fp.addr)
+                .add_attribute("deposit", "100stake")]
+        );
+        let fp =
+            queries::finality_provider(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap()).unwrap();
+        assert_eq!(fp.addr, new_fp.addr);
+        let deposit =
+            queries::finality_provider_deposit(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap())
+                .unwrap();
+        assert_eq!(
+            deposit,
+            FpDeposit {
+                depositor: fp_addr.clone(),
+                amount: coin(100, "stake"),
+                deposited_height: env.block.height,
+            }
+        );
+
+        // The deposit is held until the finality provider reaches the minimum stake
+        let refund_msg = ExecuteMsg::RefundFpDeposit {
+            btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+        };
+        let refund_info = message_info(&fp_addr, &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            refund_info.clone(),
+            refund_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::FpDepositNotRefundable(..)));
+
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![active_delegation],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            refund_info.clone(),
+            refund_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: fp_addr.to_string(),
+                amount: coins(100, "stake"),
+            }
+            .into()
+        );
+
+        // The deposit is refunded once
+        execute(deps.as_mut(), env, refund_info, refund_msg).unwrap_err();
+        assert!(!FP_DEPOSITS.has(&deps.storage, &new_fp.btc_pk_hex));
+    }
+
+    #[test]
+    fn remove_fp_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let admin_info = message_info(&deps.api.addr_make(INIT_ADMIN), &[]);
+
+        let mut params = staking_params();
+        params.fp_removal_delay = 10;
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(params),
+                admin: Some(admin_info.sender.to_string()),
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let remove_msg = ExecuteMsg::RemoveFinalityProvider {
+            btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+            reason: "defunct".to_string(),
+        };
+
+        // Only the admin can remove a finality provider
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), remove_msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Admin(cw_controllers::AdminError::NotAdmin {})
+        );
+
+        // The removal is scheduled first, and timelocked
+        let mut env = mock_env();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin_info.clone(),
+            remove_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "fp_removal_scheduled");
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin_info.clone(),
+            remove_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FinalityProviderRemovalTimelocked(
+                new_fp.btc_pk_hex.clone(),
+                env.block.height + 10
+            )
+        );
+        queries::finality_provider(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap()).unwrap();
+        let jail = FpJail {
+            jailed_height: env.block.height,
+            unjailable_height: env.block.height + 5,
+        };
+        FP_JAILS
+            .save(deps.as_mut().storage, &new_fp.btc_pk_hex, &jail)
+            .unwrap();
+        let record = FpMetadataRecord {
+            metadata: babylon_apis::btc_staking_api::FpMetadata {
+                rpc_endpoint: None,
+                alert_email_hash: None,
+                version: Some("v1".to_string()),
+            },
+            sig: Binary::default(),
+            updated_height: env.block.height,
+        };
+        FP_METADATA
+            .save(deps.as_mut().storage, &new_fp.btc_pk_hex, &record)
+            .unwrap();
+
+        // Once the delay has elapsed, the finality provider is removed along with its power, jail
+        // and metadata
+        env.block.height += 10;
+        let res = execute(deps.as_mut(), env.clone(), admin_info, remove_msg).unwrap();
+        let event = &res.events[0];
+        assert_eq!(event.ty, "fp_removed");
+        assert!(event
+            .attributes
+            .iter()
+            .any(|a| a.key == "power" && a.value == active_delegation.total_sat.to_string()));
+        let removal =
+            queries::finality_provider_removal(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap())
+                .unwrap();
+        assert_eq!(removal.reason, "defunct");
+        assert_eq!(removal.removed_height, Some(env.block.height));
+        queries::finality_provider(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap()).unwrap_err();
+        let fps = queries::finality_providers_by_power(deps.as_ref(), None, None).unwrap();
+        assert!(fps.fps.is_empty());
+        assert!(!FP_JAILS.has(&deps.storage, &new_fp.btc_pk_hex));
+        assert!(!FP_METADATA.has(&deps.storage, &new_fp.btc_pk_hex));
+
+        // Its delegations can still be unbonded
+        let delegation = BtcDelegation::from(&active_delegation);
+        let undelegation = UnbondedBtcDelegation {
+            staking_tx_hash: staking_tx_hash(&delegation).to_string(),
+            unbonding_tx_sig: get_btc_del_unbonding_sig(1, &[1]).to_bytes().into(),
+        };
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![undelegation],
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A removed finality provider cannot register again
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FinalityProviderRemoved(new_fp.btc_pk_hex)
+        );
+    }
+
+    #[test]
+    fn genesis_import_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+
+        // Genesis delegations are validated the same as BTC staking operations
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: Some(Genesis {
+                    fps: vec![],
+                    active_delegations: vec![active_delegation.clone()],
+                }),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::FinalityProviderNotRegistered);
+
+        let mut deps = mock_dependencies();
+        let res = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: Some(Genesis {
+                    fps: vec![new_fp.clone()],
+                    active_delegations: vec![active_delegation.clone()],
+                }),
+            },
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "genesis_active_delegations" && attr.value == "1"));
+
+        // The genesis finality provider has the power of the genesis delegation
+        let fp = queries::finality_provider_info(
+            deps.as_ref(),
+            new_fp.btc_pk_hex.parse().unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fp.power, active_delegation.total_sat);
+        let delegation = BtcDelegation::from(&active_delegation);
+        let btc_del =
+            queries::delegation(deps.as_ref(), staking_tx_hash(&delegation).to_string()).unwrap();
+        assert!(btc_del.is_active());
+    }
+
+    #[test]
+    fn event_verbosity_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let admin_info = message_info(&deps.api.addr_make(INIT_ADMIN), &[]);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: Some(admin_info.sender.to_string()),
+                genesis: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            queries::event_verbosity(deps.as_ref()).unwrap(),
+            EventVerbosity::Standard
+        );
+
+        // Only the admin can set the verbosity
+        let set_msg = |verbosity| ExecuteMsg::SetEventVerbosity { verbosity };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            set_msg(EventVerbosity::Debug),
         )
-        .unwrap();
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Admin(cw_controllers::AdminError::NotAdmin {})
+        );
 
+        // Debug mode emits an event per operation, along with the state transition events
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            set_msg(EventVerbosity::Debug),
+        )
+        .unwrap();
         let new_fp = create_new_finality_provider(1);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![get_derived_btc_delegation(1, &[1])],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let types: Vec<_> = res.events.iter().map(|e| e.ty.as_str()).collect();
+        assert_eq!(types, vec!["new_finality_provider", "active_delegation"]);
+
+        // Minimal mode emits a single summary event
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info,
+            set_msg(EventVerbosity::Minimal),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![get_derived_btc_delegation(2, &[1])],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty.as_str(), "btc_staking");
+        assert_eq!(
+            res.events[0].attributes[1].key.as_str(),
+            "active_delegations"
+        );
+        assert_eq!(res.events[0].attributes[1].value.as_str(), "1");
+    }
 
+    #[test]
+    fn fp_metadata_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+        let new_fp = create_new_finality_provider(1);
         let msg = ExecuteMsg::BtcStaking {
             new_fp: vec![new_fp.clone()],
             active_del: vec![],
             slashed_del: vec![],
             unbonded_del: vec![],
         };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        // Only the Creator or Admin can call this
+        // Sign the metadata with the finality provider key
+        let fp_sk = create_new_fp_sk(1);
+        assert_eq!(
+            hex::encode(fp_sk.verifying_key().to_bytes()),
+            new_fp.btc_pk_hex
+        );
+        let metadata = FpMetadata {
+            rpc_endpoint: Some("https://fp.example.com:26657".to_string()),
+            alert_email_hash: Some(hex::encode([7u8; 32])),
+            version: Some("v1.2.3".to_string()),
+        };
+        let sign = |metadata: &FpMetadata| -> Binary {
+            let msg_hash = babylon_apis::btc_staking_api::fp_metadata_msg_hash(
+                &env.block.chain_id,
+                env.contract.address.as_str(),
+                &new_fp.btc_pk_hex,
+                metadata,
+            )
+            .unwrap();
+            fp_sk
+                .sign_raw(&msg_hash, &[0u8; 32])
+                .unwrap()
+                .to_bytes()
+                .into()
+        };
+        let set_msg = |metadata: FpMetadata, sig: Binary| ExecuteMsg::SetFpMetadata {
+            fp_btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+            metadata,
+            sig,
+        };
+        let fp_info = message_info(&cosmwasm_std::Addr::unchecked(&new_fp.addr), &[]);
+
+        // Only the finality provider can publish its metadata
         let other_info = message_info(&deps.api.addr_make("other"), &[]);
-        let err = execute(deps.as_mut(), mock_env(), other_info, msg.clone()).unwrap_err();
+        let msg = set_msg(metadata.clone(), sign(&metadata));
+        let err = execute(deps.as_mut(), env.clone(), other_info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+
+        // The signature must be over the published metadata
+        let other_metadata = FpMetadata {
+            version: Some("v1.2.4".to_string()),
+            ..metadata.clone()
+        };
+        let msg = set_msg(other_metadata.clone(), sign(&metadata));
+        let err = execute(deps.as_mut(), env.clone(), fp_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFpMetadata(_)));
+
+        // And the fields must be well-formed
+        let bad_metadata = FpMetadata {
+            rpc_endpoint: Some("ftp://fp.example.com".to_string()),
+            ..metadata.clone()
+        };
+        let msg = set_msg(bad_metadata.clone(), sign(&bad_metadata));
+        let err = execute(deps.as_mut(), env.clone(), fp_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFpMetadata(_)));
+
+        let sig = sign(&metadata);
+        let msg = set_msg(metadata.clone(), sig.clone());
+        let res = execute(deps.as_mut(), env.clone(), fp_info.clone(), msg).unwrap();
+        assert_eq!(res.events[0].ty.as_str(), "fp_metadata_updated");
+        let record =
+            queries::finality_provider_metadata(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap())
+                .unwrap();
+        assert_eq!(record.metadata, metadata);
+        assert_eq!(record.sig, sig);
+        assert_eq!(record.updated_height, env.block.height);
+
+        // A new record replaces the previous one
+        let msg = set_msg(other_metadata.clone(), sign(&other_metadata));
+        execute(deps.as_mut(), env, fp_info, msg).unwrap();
+        let record =
+            queries::finality_provider_metadata(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap())
+                .unwrap();
+        assert_eq!(record.metadata, other_metadata);
+    }
+
+    #[test]
+    fn staker_binding_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        // Sign the binding of a staker key to the staker address
+        let staker = deps.api.addr_make("staker");
+        let staker_sk = create_new_fp_sk(1);
+        let btc_pk_hex = hex::encode(staker_sk.verifying_key().to_bytes());
+        let msg_hash = babylon_apis::btc_staking_api::staker_binding_msg_hash(
+            &env.block.chain_id,
+            env.contract.address.as_str(),
+            staker.as_str(),
+        );
+        let sig = staker_sk.sign_raw(&msg_hash, &[0u8; 32]).unwrap();
+        let bind_msg = ExecuteMsg::BindStaker {
+            btc_pk_hex: btc_pk_hex.parse().unwrap(),
+            sig: sig.to_bytes().into(),
+        };
+
+        // The binding is specific to the signed address
+        let other_info = message_info(&deps.api.addr_make("other"), &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            other_info.clone(),
+            bind_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidStakerBinding(_)));
+
+        let staker_info = message_info(&staker, &[]);
+        execute(deps.as_mut(), env.clone(), staker_info.clone(), bind_msg).unwrap();
+
+        // The binding is queryable by either key
+        let binding = queries::staker_binding(deps.as_ref(), btc_pk_hex.parse().unwrap()).unwrap();
+        assert_eq!(binding.addr, staker);
+        let bindings =
+            queries::staker_bindings_by_addr(deps.as_ref(), staker.to_string(), None, None)
+                .unwrap();
+        assert_eq!(bindings.btc_pk_hexes, vec![btc_pk_hex.clone()]);
+
+        // Register a delegation by the bound staker key.
+        // The staker key is set directly in storage, as the delegation's signatures are over it
+        let new_fp = create_new_finality_provider(1);
+        let active_delegation = get_derived_btc_delegation(1, &[1]);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp],
+            active_del: vec![active_delegation.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let staking_tx_hash = staking_tx_hash(&BtcDelegation::from(&active_delegation));
+        let mut btc_del = DELEGATIONS
+            .load(&deps.storage, staking_tx_hash.as_ref())
+            .unwrap();
+        btc_del.btc_pk_hex.clone_from(&btc_pk_hex);
+        DELEGATIONS
+            .save(&mut deps.storage, staking_tx_hash.as_ref(), &btc_del)
+            .unwrap();
+
+        // The bound address can unbond the delegation
+        let unbond_msg = ExecuteMsg::Unbond {
+            staking_tx_hash: staking_tx_hash.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            other_info.clone(),
+            unbond_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), env.clone(), staker_info.clone(), unbond_msg).unwrap();
+
+        // Only the bound address can remove the binding
+        let unbind_msg = ExecuteMsg::UnbindStaker {
+            btc_pk_hex: btc_pk_hex.parse().unwrap(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), other_info, unbind_msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), env, staker_info, unbind_msg).unwrap();
+        queries::staker_binding(deps.as_ref(), btc_pk_hex.parse().unwrap()).unwrap_err();
+        let bindings =
+            queries::staker_bindings_by_addr(deps.as_ref(), staker.to_string(), None, None)
+                .unwrap();
+        assert!(bindings.btc_pk_hexes.is_empty());
+    }
+
+    #[test]
+    fn claim_grant_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let mut env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let staker = deps.api.addr_make("staker");
+        let custodian = deps.api.addr_make("custodian");
+        let staker_info = message_info(&staker, &[]);
+        let height = env.block.height;
+
+        // A staker cannot grant itself, and the expiry has to be in the future
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            staker_info.clone(),
+            ExecuteMsg::GrantClaim {
+                grantee: staker.to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidClaimGrant(_)));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            staker_info.clone(),
+            ExecuteMsg::GrantClaim {
+                grantee: custodian.to_string(),
+                expiry: Some(height),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidClaimGrant(_)));
+
+        // Grant the custodian until 10 blocks from now
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            staker_info.clone(),
+            ExecuteMsg::GrantClaim {
+                grantee: custodian.to_string(),
+                expiry: Some(height + 10),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.events[0],
+            Event::new("claim_granted")
+                .add_attribute("staker_addr", staker.as_str())
+                .add_attribute("grantee", custodian.as_str())
+                .add_attribute("expiry", (height + 10).to_string())
+        );
+        let grants = queries::claim_grants(deps.as_ref(), staker.to_string(), None, None).unwrap();
+        assert_eq!(
+            grants.grants,
+            vec![ClaimGrant {
+                grantee: custodian.clone(),
+                expiry: Some(height + 10),
+                granted_height: height,
+            }]
+        );
+
+        // The custodian can claim on behalf of the staker until the grant expires
+        let other = deps.api.addr_make("other");
+        assert!(can_claim(&deps.storage, &staker, &staker, height).unwrap());
+        assert!(can_claim(&deps.storage, &staker, &custodian, height + 9).unwrap());
+        assert!(!can_claim(&deps.storage, &staker, &custodian, height + 10).unwrap());
+        assert!(!can_claim(&deps.storage, &staker, &other, height).unwrap());
+        // Grants are per staker
+        assert!(!can_claim(&deps.storage, &other, &custodian, height).unwrap());
+
+        // Revoke the grant
+        env.block.height += 1;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            staker_info.clone(),
+            ExecuteMsg::RevokeClaim {
+                grantee: custodian.to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "claim_revoked");
+        assert!(!can_claim(&deps.storage, &staker, &custodian, env.block.height).unwrap());
+        let grants = queries::claim_grants(deps.as_ref(), staker.to_string(), None, None).unwrap();
+        assert!(grants.grants.is_empty());
+
+        // Only existing grants can be revoked
+        let err = execute(
+            deps.as_mut(),
+            env,
+            staker_info,
+            ExecuteMsg::RevokeClaim {
+                grantee: custodian.to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ClaimGrantNotFound(..)));
+    }
+
+    #[test]
+    fn rewards_distribution_works() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: None,
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let operator = deps.api.addr_make("operator");
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+
+        // Register one FP, operated by `operator` with a 10% commission.
+        // Done directly in storage, as the FP's PoP is over its address
+        let new_fp = create_new_finality_provider(1);
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let mut fp = FPS.load(&deps.storage, &new_fp.btc_pk_hex).unwrap();
+        fp.addr = operator.to_string();
+        fp.commission = Decimal::percent(10);
+        FPS.save(&mut deps.storage, &new_fp.btc_pk_hex, &fp)
+            .unwrap();
+
+        // Alice and Bob delegate the same stake to it
+        let mut alice_del = get_derived_btc_delegation(1, &[1]);
+        alice_del.staker_addr = alice.to_string();
+        let mut bob_del = get_derived_btc_delegation(2, &[1]);
+        bob_del.staker_addr = bob.to_string();
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![],
+            active_del: vec![alice_del.clone(), bob_del.clone()],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let distribute_msg = ExecuteMsg::DistributeRewards {
+            fp_distribution: vec![RewardInfo {
+                fp_btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+                reward: Uint128::new(1000),
+            }],
+        };
+
+        // The rewards have to add up to the funds
+        let funder = message_info(&deps.api.addr_make("funder"), &coins(999, "stake"));
+        let err = execute(deps.as_mut(), env.clone(), funder, distribute_msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRewardDistribution(_)));
+
+        let funder = message_info(&deps.api.addr_make("funder"), &coins(1000, "stake"));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            funder.clone(),
+            distribute_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("rewards_distributed")
+                .add_attribute("fp_btc_pk_hex", &new_fp.btc_pk_hex)
+                .add_attribute("reward", "1000")
+                .add_attribute("commission", "100")]
+        );
+
+        // The commission goes to the operator, and the rest is split by stake
+        let pending = queries::pending_rewards(deps.as_ref(), operator.to_string()).unwrap();
+        assert_eq!(pending.rewards, coin(100, "stake"));
+        assert!(pending.fp_rewards.is_empty());
+        let pending = queries::pending_rewards(deps.as_ref(), alice.to_string()).unwrap();
+        assert_eq!(pending.rewards, coin(450, "stake"));
+        assert_eq!(
+            pending.fp_rewards,
+            vec![FpPendingRewards {
+                fp_btc_pk_hex: new_fp.btc_pk_hex.clone(),
+                stake: alice_del.total_sat,
+                pending: Uint128::new(450),
+            }]
+        );
+
+        // Bob unbonds, so that Alice gets all the delegators' share of the next rewards
+        let bob_tx_hash = staking_tx_hash(&BtcDelegation::from(&bob_del)).to_string();
+        bind_staker(&mut deps.storage, &bob_del.btc_pk_hex, &bob);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&bob, &[]),
+            ExecuteMsg::Unbond {
+                staking_tx_hash: bob_tx_hash,
+            },
+        )
+        .unwrap();
+        execute(deps.as_mut(), env.clone(), funder, distribute_msg).unwrap();
+        let pending = queries::pending_rewards(deps.as_ref(), alice.to_string()).unwrap();
+        assert_eq!(pending.rewards, coin(1350, "stake"));
+        let pending = queries::pending_rewards(deps.as_ref(), bob.to_string()).unwrap();
+        assert_eq!(pending.rewards, coin(450, "stake"));
+        assert!(pending.fp_rewards.is_empty());
+
+        let fp_rewards =
+            queries::finality_provider_rewards(deps.as_ref(), new_fp.btc_pk_hex.parse().unwrap())
+                .unwrap();
+        assert_eq!(fp_rewards.total_sat, alice_del.total_sat);
+        assert_eq!(fp_rewards.distributed, Uint128::new(2000));
+
+        // Only the staker, or a grantee of it, can withdraw its rewards
+        let custodian = deps.api.addr_make("custodian");
+        let withdraw_msg = ExecuteMsg::WithdrawRewards {
+            staker_addr: Some(alice.to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&custodian, &[]),
+            withdraw_msg.clone(),
+        )
+        .unwrap_err();
         assert_eq!(err, ContractError::Unauthorized);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&alice, &[]),
+            ExecuteMsg::GrantClaim {
+                grantee: custodian.to_string(),
+                expiry: None,
+            },
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&custodian, &[]),
+            withdraw_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: custodian.to_string(),
+                amount: coins(1350, "stake"),
+            }
+            .into()
+        );
+
+        // Nothing left to withdraw
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&custodian, &[]),
+            withdraw_msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingRewards(alice.to_string()));
+
+        // Bob withdraws for himself
+        let res = execute(
+            deps.as_mut(),
+            env,
+            message_info(&bob, &[]),
+            ExecuteMsg::WithdrawRewards { staker_addr: None },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: bob.to_string(),
+                amount: coins(450, "stake"),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn cw20_rewards_work() {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let admin_info = message_info(&deps.api.addr_make(INIT_ADMIN), &[]);
+        let env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            InstantiateMsg {
+                params: Some(staking_params()),
+                admin: Some(admin_info.sender.to_string()),
+                genesis: None,
+            },
+        )
+        .unwrap();
+
+        let token = deps.api.addr_make("token");
+        let alice = deps.api.addr_make("alice");
+        let new_fp = create_new_finality_provider(1);
+        let mut alice_del = get_derived_btc_delegation(1, &[1]);
+        alice_del.staker_addr = alice.to_string();
+        let msg = ExecuteMsg::BtcStaking {
+            new_fp: vec![new_fp.clone()],
+            active_del: vec![alice_del],
+            slashed_del: vec![],
+            unbonded_del: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let mut fp = FPS.load(&deps.storage, &new_fp.btc_pk_hex).unwrap();
+        fp.commission = Decimal::zero();
+        FPS.save(&mut deps.storage, &new_fp.btc_pk_hex, &fp)
+            .unwrap();
+
+        let receive_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: deps.api.addr_make("funder").to_string(),
+            amount: Uint128::new(1000),
+            msg: to_json_binary(&Cw20HookMsg::DistributeRewards {
+                fp_distribution: vec![RewardInfo {
+                    fp_btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+                    reward: Uint128::new(1000),
+                }],
+            })
+            .unwrap(),
+        });
+
+        // Only tokens registered by the admin can be distributed
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&token, &[]),
+            receive_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnregisteredRewardToken(token.to_string())
+        );
+        let update_msg = ExecuteMsg::UpdateRewardTokens {
+            add: vec![token.to_string()],
+            remove: vec![],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&alice, &[]),
+            update_msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Admin(..)));
+        execute(deps.as_mut(), env.clone(), admin_info, update_msg).unwrap();
+        let tokens = queries::reward_tokens(deps.as_ref()).unwrap();
+        assert_eq!(tokens.tokens, vec![token.to_string()]);
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&token, &[]),
+            receive_msg,
+        )
+        .unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("rewards_distributed")
+                .add_attribute("fp_btc_pk_hex", &new_fp.btc_pk_hex)
+                .add_attribute("reward", "1000")
+                .add_attribute("commission", "0")
+                .add_attribute("token", token.as_str())]
+        );
+        let pending = queries::pending_rewards(deps.as_ref(), alice.to_string()).unwrap();
+        assert_eq!(pending.rewards, coin(0, "stake"));
+        assert_eq!(
+            pending.cw20_rewards,
+            vec![Cw20Coin {
+                address: token.to_string(),
+                amount: Uint128::new(1000),
+            }]
+        );
+
+        // The cw20 rewards are transferred on withdrawal
+        let res = execute(
+            deps.as_mut(),
+            env,
+            message_info(&alice, &[]),
+            ExecuteMsg::WithdrawRewards { staker_addr: None },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            cw20_transfer_msg(token.as_str(), alice.as_str(), Uint128::new(1000)).unwrap()
+        );
+        let pending = queries::pending_rewards(deps.as_ref(), alice.to_string()).unwrap();
+        assert!(pending.cw20_rewards.is_empty());
     }
 
     #[test]
-    fn test_add_fp_admin() {
+    fn commission_lockup_works() {
         let mut deps = mock_dependencies();
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
-        let init_admin = deps.api.addr_make(INIT_ADMIN);
+        let mut env = mock_env();
 
         instantiate(
             deps.as_mut(),
-            mock_env(),
+            env.clone(),
             info.clone(),
             InstantiateMsg {
-                params: None,
-                admin: Some(init_admin.to_string()), // Admin provided
+                params: Some(Params {
+                    commission_lockup_blocks: 10,
+                    ..staking_params()
+                }),
+                admin: None,
+                genesis: None,
             },
         )
         .unwrap();
 
-        let admin_info = message_info(&init_admin, &[]); // Mock info for the admin
+        let operator = deps.api.addr_make("operator");
         let new_fp = create_new_finality_provider(1);
-
         let msg = ExecuteMsg::BtcStaking {
             new_fp: vec![new_fp.clone()],
-            active_del: vec![],
+            active_del: vec![get_derived_btc_delegation(1, &[1])],
             slashed_del: vec![],
             unbonded_del: vec![],
         };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let mut fp = FPS.load(&deps.storage, &new_fp.btc_pk_hex).unwrap();
+        fp.addr = operator.to_string();
+        fp.commission = Decimal::percent(10);
+        FPS.save(&mut deps.storage, &new_fp.btc_pk_hex, &fp)
+            .unwrap();
+        let funder = message_info(&deps.api.addr_make("funder"), &coins(1000, "stake"));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            funder,
+            ExecuteMsg::DistributeRewards {
+                fp_distribution: vec![RewardInfo {
+                    fp_btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+                    reward: Uint128::new(1000),
+                }],
+            },
+        )
+        .unwrap();
 
-        // Use admin_info to execute the message
-        let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg.clone()).unwrap();
-        assert_eq!(0, res.messages.len());
+        // The withdrawn commission starts vesting, and nothing is sent yet
+        let withdraw_msg = ExecuteMsg::WithdrawRewards { staker_addr: None };
+        let operator_info = message_info(&operator, &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator_info.clone(),
+            withdraw_msg.clone(),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            res.events,
+            vec![Event::new("rewards_withdrawn")
+                .add_attribute("staker_addr", operator.as_str())
+                .add_attribute("recipient", operator.as_str())
+                .add_attribute("locked_commission", "100stake")]
+        );
 
-        // Check the finality provider has been stored
-        let query_res =
-            queries::finality_provider(deps.as_ref(), new_fp.btc_pk_hex.clone()).unwrap();
-        // get DB object
-        let fp = FinalityProvider::from(&new_fp);
-        assert_eq!(query_res, fp);
+        // Half of it vests over half of the lockup
+        env.block.height += 5;
+        let vestings =
+            queries::commission_vestings(deps.as_ref(), env.clone(), operator.to_string()).unwrap();
+        assert_eq!(
+            vestings.vestings,
+            vec![CommissionVestingInfo {
+                start_height: env.block.height - 5,
+                end_height: env.block.height + 5,
+                token: None,
+                amount: Uint128::new(100),
+                claimed: Uint128::zero(),
+                vested: Uint128::new(50),
+            }]
+        );
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator_info.clone(),
+            withdraw_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: operator.to_string(),
+                amount: coins(50, "stake"),
+            }
+            .into()
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator_info.clone(),
+            withdraw_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingRewards(operator.to_string()));
 
-        // Trying to add the same fp again fails
-        let err = execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap_err();
+        // The rest vests by the end of the lockup
+        env.block.height += 10;
+        let res = execute(deps.as_mut(), env.clone(), operator_info, withdraw_msg).unwrap();
         assert_eq!(
-            err,
-            ContractError::FinalityProviderAlreadyExists(new_fp.btc_pk_hex.clone())
+            res.messages[0].msg,
+            BankMsg::Send {
+                to_address: operator.to_string(),
+                amount: coins(50, "stake"),
+            }
+            .into()
         );
+        let vestings =
+            queries::commission_vestings(deps.as_ref(), env, operator.to_string()).unwrap();
+        assert!(vestings.vestings.is_empty());
     }
 
     #[test]
-    fn active_delegation_happy_path() {
+    fn migrate_backfills_reward_stakes() {
         let mut deps = mock_dependencies();
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let env = mock_env();
 
-        let params = staking_params();
         instantiate(
             deps.as_mut(),
-            mock_env(),
+            env.clone(),
             info.clone(),
             InstantiateMsg {
-                params: Some(params),
+                params: Some(staking_params()),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
 
-        // Build valid active delegation
-        let active_delegation = get_active_btc_delegation();
-
-        // Register one FP first
-        let mut new_fp = create_new_finality_provider(1);
-        new_fp
-            .btc_pk_hex
-            .clone_from(&active_delegation.fp_btc_pk_list[0]);
-
-        // Check that the finality provider has no power yet
-        let res = queries::finality_provider_info(deps.as_ref(), new_fp.btc_pk_hex.clone(), None);
-        assert!(matches!(
-            res,
-            Err(ContractError::FinalityProviderNotFound(pk)) if pk == new_fp.btc_pk_hex
-        ));
-
+        let new_fp = create_new_finality_provider(1);
+        let dels = [1, 2].map(|i| get_derived_btc_delegation(i, &[1]));
         let msg = ExecuteMsg::BtcStaking {
             new_fp: vec![new_fp.clone()],
-            active_del: vec![],
+            active_del: dels.to_vec(),
             slashed_del: vec![],
             unbonded_del: vec![],
         };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let fp_rewards = rewards::FP_REWARDS
+            .load(&deps.storage, &new_fp.btc_pk_hex)
+            .unwrap();
+        assert_eq!(
+            fp_rewards.total_sat,
+            dels.iter().map(|del| del.total_sat).sum::<u64>()
+        );
 
-        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        // Drop the reward distribution state, as in deployments predating it
+        rewards::FP_REWARDS.remove(&mut deps.storage, &new_fp.btc_pk_hex);
+        for del in &dels {
+            rewards::STAKER_REWARDS
+                .remove(&mut deps.storage, (&del.staker_addr, &new_fp.btc_pk_hex));
+        }
 
-        // Now add the active delegation
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        let scheduled = res
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "scheduled_migrations")
+            .unwrap();
+        assert!(scheduled
+            .value
+            .split(',')
+            .any(|name| name == "reward_stakes"));
+
+        // Rewards cannot be distributed until the stakes are backfilled
+        let funder = message_info(&deps.api.addr_make("funder"), &coins(1000, "stake"));
+        let distribute_msg = ExecuteMsg::DistributeRewards {
+            fp_distribution: vec![RewardInfo {
+                fp_btc_pk_hex: new_fp.btc_pk_hex.parse().unwrap(),
+                reward: Uint128::new(1000),
+            }],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            funder.clone(),
+            distribute_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SectionMigrating {
+                section: "rewards".to_string()
+            }
+        );
+
+        // The stakes are backfilled in chunks, in staking tx hash order
+        let continue_msg = ExecuteMsg::ContinueMigration { limit: 1 };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), continue_msg).unwrap();
+        assert_eq!(
+            res.events,
+            vec![Event::new("migration_progress")
+                .add_attribute("name", "reward_stakes")
+                .add_attribute("section", "rewards")
+                .add_attribute("migrated", "1")
+                .add_attribute("complete", "false")]
+        );
+        let (first, second) =
+            if staking_tx_hash(&(&dels[0]).into()) < staking_tx_hash(&(&dels[1]).into()) {
+                (0, 1)
+            } else {
+                (1, 0)
+            };
+        let backfilled = rewards::FP_REWARDS
+            .load(&deps.storage, &new_fp.btc_pk_hex)
+            .unwrap();
+        assert_eq!(backfilled.total_sat, dels[first].total_sat);
+
+        // Unbonding a delegation the backfill has not gone through yet leaves its stake to it
         let msg = ExecuteMsg::BtcStaking {
             new_fp: vec![],
-            active_del: vec![active_delegation.clone()],
+            active_del: vec![],
             slashed_del: vec![],
-            unbonded_del: vec![],
+            unbonded_del: vec![UnbondedBtcDelegation {
+                staking_tx_hash: staking_tx_hash(&(&dels[second]).into()).to_string(),
+                unbonding_tx_sig: get_btc_del_unbonding_sig(second as i32 + 1, &[1])
+                    .to_bytes()
+                    .into(),
+            }],
         };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // Check the active delegation is being stored
-        let delegation = BtcDelegation::from(&active_delegation);
-        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
-        let query_res = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
-        assert_eq!(query_res, delegation);
-
-        // Check that the finality provider power has been updated
-        let fp = queries::finality_provider_info(deps.as_ref(), new_fp.btc_pk_hex.clone(), None)
+        let continue_msg = ExecuteMsg::ContinueMigration { limit: 10 };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), continue_msg).unwrap();
+        assert!(res.events[0]
+            .attributes
+            .contains(&Attribute::new("complete", "true")));
+        let backfilled = rewards::FP_REWARDS
+            .load(&deps.storage, &new_fp.btc_pk_hex)
             .unwrap();
-        assert_eq!(fp.power, active_delegation.total_sat);
+        assert_eq!(backfilled.total_sat, dels[first].total_sat);
+        let staker_rewards = rewards::STAKER_REWARDS
+            .load(
+                &deps.storage,
+                (&dels[first].staker_addr, &new_fp.btc_pk_hex),
+            )
+            .unwrap();
+        assert_eq!(staker_rewards.stake, dels[first].total_sat);
+        execute(deps.as_mut(), env.clone(), funder, distribute_msg).unwrap();
+
+        // Migrating again doesn't schedule the stakes backfill again
+        let res = migrate(deps.as_mut(), env, MigrateMsg {}).unwrap();
+        assert!(res
+            .attributes
+            .contains(&Attribute::new("scheduled_migrations", "")));
     }
 
     #[test]
-    fn undelegation_works() {
+    fn quarantine_works() {
         let mut deps = mock_dependencies();
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        let admin_info = message_info(&deps.api.addr_make(INIT_ADMIN), &[]);
 
-        let params = staking_params();
         instantiate(
             deps.as_mut(),
             mock_env(),
             info.clone(),
             InstantiateMsg {
-                params: Some(params),
-                admin: None,
+                params: Some(staking_params()),
+                admin: Some(admin_info.sender.to_string()),
+                genesis: None,
             },
         )
         .unwrap();
 
-        // Register one FP first
-        let new_fp = create_new_finality_provider(1);
-
-        // Build valid active delegation
-        let active_delegation = get_derived_btc_delegation(1, &[1]);
-
+        // An invalid finality provider and delegation are quarantined, instead of failing the batch
+        let valid_fp = create_new_finality_provider(1);
+        let mut invalid_fp = create_new_finality_provider(2);
+        invalid_fp.commission = Decimal::percent(150);
+        let mut invalid_del = get_derived_btc_delegation(1, &[1]);
+        invalid_del.staking_tx = Binary::new(vec![0xde, 0xad]);
         let msg = ExecuteMsg::BtcStaking {
-            new_fp: vec![new_fp.clone()],
-            active_del: vec![active_delegation.clone()],
+            new_fp: vec![valid_fp.clone(), invalid_fp.clone()],
+            active_del: vec![invalid_del.clone()],
             slashed_del: vec![],
             unbonded_del: vec![],
         };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let result: BtcStakingResult = cosmwasm_std::from_json(res.data.unwrap()).unwrap();
+        assert_eq!(result.new_fps, vec![valid_fp.btc_pk_hex.clone()]);
+        assert_eq!(result.quarantined.len(), 2);
+        assert_eq!(result.quarantined[0], invalid_fp.btc_pk_hex);
 
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // Check the delegation is active (it has no unbonding or slashing tx signature)
-        let active_delegation_undelegation = active_delegation.undelegation_info.clone();
-        // Compute the staking tx hash
-        let delegation = BtcDelegation::from(&active_delegation);
-        let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
-
-        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
-        let btc_undelegation = btc_del.undelegation_info;
+        let item = queries::quarantined_item(deps.as_ref(), invalid_fp.btc_pk_hex.clone()).unwrap();
+        assert_eq!(item.op, QuarantinedOp::NewFp(invalid_fp.clone()));
+        assert!(item.reason.starts_with("code 802: Invalid commission rate"));
+        let del_id = result.quarantined[1].clone();
+        let item = queries::quarantined_item(deps.as_ref(), del_id.clone()).unwrap();
+        assert_eq!(item.op, QuarantinedOp::ActiveDel(invalid_del));
         assert_eq!(
-            btc_undelegation,
-            BtcUndelegationInfo {
-                unbonding_tx: active_delegation_undelegation.unbonding_tx.to_vec(),
-                slashing_tx: active_delegation_undelegation.slashing_tx.to_vec(),
-                delegator_unbonding_sig: vec![],
-                delegator_slashing_sig: active_delegation_undelegation
-                    .delegator_slashing_sig
-                    .to_vec(),
-                covenant_unbonding_sig_list: vec![],
-                covenant_slashing_sigs: vec![],
-            }
+            queries::quarantine(deps.as_ref(), None, None)
+                .unwrap()
+                .items
+                .len(),
+            2
         );
 
-        let unbonding_sig = get_btc_del_unbonding_sig(1, &[1]);
-
-        // Now send the undelegation message
-        let undelegation = UnbondedBtcDelegation {
-            staking_tx_hash: staking_tx_hash_hex.clone(),
-            unbonding_tx_sig: unbonding_sig.to_bytes().into(),
-        };
-
-        let msg = ExecuteMsg::BtcStaking {
-            new_fp: vec![],
-            active_del: vec![],
-            slashed_del: vec![],
-            unbonded_del: vec![undelegation.clone()],
-        };
-
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // Check the delegation is not active any more (updated with the unbonding tx signature)
-        let active_delegation_undelegation = active_delegation.undelegation_info;
-        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
-        let btc_undelegation = btc_del.undelegation_info;
+        // Only the admin can reprocess or discard quarantined operations
+        let reprocess_msg = |id: &str| ExecuteMsg::ReprocessQuarantined { id: id.to_string() };
+        let discard_msg = |id: &str| ExecuteMsg::DiscardQuarantined { id: id.to_string() };
+        let other_info = message_info(&deps.api.addr_make("other"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), other_info, discard_msg(&del_id)).unwrap_err();
         assert_eq!(
-            btc_undelegation,
-            BtcUndelegationInfo {
-                unbonding_tx: active_delegation_undelegation.unbonding_tx.into(),
-                slashing_tx: active_delegation_undelegation.slashing_tx.into(),
-                delegator_unbonding_sig: unbonding_sig.to_bytes().into(),
-                delegator_slashing_sig: active_delegation_undelegation
-                    .delegator_slashing_sig
-                    .into(),
-                covenant_unbonding_sig_list: vec![],
-                covenant_slashing_sigs: vec![],
-            }
+            err,
+            ContractError::Admin(cw_controllers::AdminError::NotAdmin {})
         );
 
-        // Check the finality provider power has been updated
-        let fp = queries::finality_provider_info(deps.as_ref(), new_fp.btc_pk_hex.clone(), None)
+        // Still invalid operations stay quarantined
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            reprocess_msg(&del_id),
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            discard_msg(&del_id),
+        )
+        .unwrap();
+        let err = queries::quarantined_item(deps.as_ref(), del_id.clone()).unwrap_err();
+        assert_eq!(err, ContractError::QuarantinedItemNotFound(del_id.clone()));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            discard_msg(&del_id),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::QuarantinedItemNotFound(del_id));
+
+        // Once fixed (e.g. after a migration), quarantined operations are accepted on reprocessing
+        let mut item =
+            queries::quarantined_item(deps.as_ref(), invalid_fp.btc_pk_hex.clone()).unwrap();
+        invalid_fp.commission = Decimal::percent(5);
+        item.op = QuarantinedOp::NewFp(invalid_fp.clone());
+        QUARANTINE
+            .save(deps.as_mut().storage, &item.id, &item)
             .unwrap();
-        assert_eq!(fp.power, 0);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info,
+            reprocess_msg(&invalid_fp.btc_pk_hex),
+        )
+        .unwrap();
+        queries::finality_provider(deps.as_ref(), invalid_fp.btc_pk_hex.parse().unwrap()).unwrap();
+        assert!(queries::quarantine(deps.as_ref(), None, None)
+            .unwrap()
+            .items
+            .is_empty());
     }
 
     #[test]
-    fn slashed_delegation_works() {
+    fn compressed_pks_work() {
         let mut deps = mock_dependencies();
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
-
-        let params = staking_params();
         instantiate(
             deps.as_mut(),
             mock_env(),
             info.clone(),
             InstantiateMsg {
-                params: Some(params),
+                params: Some(staking_params()),
                 admin: None,
+                genesis: None,
             },
         )
         .unwrap();
 
-        // Register one FP first
-        let new_fp = create_new_finality_provider(1);
-
-        // Build valid active delegation
-        let active_delegation = get_derived_btc_delegation(1, &[1]);
-
+        // Register a FP, and a delegation to it, with PKs in compressed encoding
+        let active_delegation = get_active_btc_delegation();
+        let fp_pk_hex = active_delegation.fp_btc_pk_list[0].clone();
+        let mut new_fp = create_new_finality_provider(1);
+        new_fp.btc_pk_hex = format!("02{}", fp_pk_hex.to_ascii_uppercase());
+        let mut compressed_del = active_delegation.clone();
+        compressed_del.btc_pk_hex = format!("03{}", active_delegation.btc_pk_hex);
+        compressed_del.fp_btc_pk_list = vec![format!("03{fp_pk_hex}")];
+        for cov_sig in compressed_del.covenant_sigs.iter_mut() {
+            cov_sig.cov_pk = [&[0x02], cov_sig.cov_pk.as_slice()].concat().into();
+        }
         let msg = ExecuteMsg::BtcStaking {
-            new_fp: vec![new_fp.clone()],
-            active_del: vec![active_delegation.clone()],
+            new_fp: vec![new_fp],
+            active_del: vec![compressed_del],
             slashed_del: vec![],
             unbonded_del: vec![],
         };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let result: BtcStakingResult = cosmwasm_std::from_json(res.data.unwrap()).unwrap();
+        assert!(result.quarantined.is_empty());
 
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // Check the delegation is active (it has no unbonding sig or is slashed)
-        // Compute the staking tx hash
+        // They are stored under their x-only encoding
+        assert_eq!(result.new_fps, vec![fp_pk_hex.clone()]);
         let delegation = BtcDelegation::from(&active_delegation);
         let staking_tx_hash_hex = staking_tx_hash(&delegation).to_string();
-        // Query the delegation
-        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex.clone()).unwrap();
-        assert!(&btc_del.undelegation_info.delegator_unbonding_sig.is_empty());
-        assert!(!btc_del.slashed);
-
-        // Check the finality provider has power
-        let fp = queries::finality_provider_info(deps.as_ref(), new_fp.btc_pk_hex.clone(), None)
-            .unwrap();
-        assert_eq!(fp.power, btc_del.total_sat);
-
-        // Now send the slashed delegation message
-        let fp_sk = create_new_fp_sk(1);
-        let fp_sk_hex = hex::encode(fp_sk.to_bytes());
-        let slashed = SlashedBtcDelegation {
-            staking_tx_hash: staking_tx_hash_hex.clone(),
-            recovered_fp_btc_sk: fp_sk_hex,
-        };
-
-        let msg = ExecuteMsg::BtcStaking {
-            new_fp: vec![],
-            active_del: vec![],
-            unbonded_del: vec![],
-            slashed_del: vec![slashed.clone()],
-        };
-
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        assert_eq!(0, res.messages.len());
-        // Check events
-        assert_eq!(res.events.len(), 1);
-        assert_eq!(res.events[0].ty.as_str(), "btc_undelegation_slashed");
-        assert_eq!(res.events[0].attributes.len(), 2);
-        assert_eq!(res.events[0].attributes[0].key.as_str(), "staking_tx_hash");
-        assert_eq!(
-            res.events[0].attributes[0].value.as_str(),
-            staking_tx_hash_hex
-        );
-        assert_eq!(res.events[0].attributes[1].key.as_str(), "height");
-
-        // Check the delegation is not active any more (slashed)
-        let btc_del = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
-        assert!(btc_del.slashed);
-        // Check the unbonding sig is still empty
-        assert!(btc_del.undelegation_info.delegator_unbonding_sig.is_empty());
-
-        // Check the finality provider power has been zeroed (it has only this delegation that was
-        // slashed)
-        let fp = queries::finality_provider_info(deps.as_ref(), new_fp.btc_pk_hex.clone(), None)
+        let stored = queries::delegation(deps.as_ref(), staking_tx_hash_hex).unwrap();
+        assert_eq!(stored, delegation);
+        let fp = queries::finality_provider_info(deps.as_ref(), fp_pk_hex.parse().unwrap(), None)
             .unwrap();
-        assert_eq!(fp.power, 0);
+        assert_eq!(fp.power, active_delegation.total_sat);
     }
 }
@@ -1,6 +1,9 @@
+use babylon_apis::btc_pk::canonical_btc_pk_hex;
+use babylon_apis::btc_staking_api::EventVerbosity;
+use babylon_apis::error::StakingApiError;
 use babylon_bitcoin::chain_params::Network;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Coin};
 
 use cw_controllers::Admin;
 use cw_storage_plus::Item;
@@ -8,8 +11,12 @@ use derivative::Derivative;
 
 pub(crate) const CONFIG: Item<Config> = Item::new("config");
 pub(crate) const PARAMS: Item<Params> = Item::new("params");
+/// Whether the params have been checked against the Babylon contract's config
+pub(crate) const BABYLON_CONFIG_CHECKED: Item<bool> = Item::new("babylon_config_checked");
 /// Storage for admin
 pub(crate) const ADMIN: Admin = Admin::new("admin");
+/// Verbosity of the events emitted while processing BTC staking operations. Set by the admin
+pub(crate) const EVENT_VERBOSITY: Item<EventVerbosity> = Item::new("event_verbosity");
 
 /// Config are Babylon-selectable BTC staking configuration
 // TODO: Add / enable config entries as needed
@@ -25,7 +32,7 @@ pub struct Config {
 #[derivative(Default)]
 pub struct Params {
     // covenant_pks is the list of public keys held by the covenant committee each PK
-    // follows encoding in BIP-340 spec on Bitcoin
+    // follows encoding in BIP-340 spec on Bitcoin (compressed PKs are converted upon instantiation)
     pub covenant_pks: Vec<String>,
     // covenant_quorum is the minimum number of signatures needed for the covenant multi-signature
     pub covenant_quorum: u32,
@@ -47,4 +54,91 @@ pub struct Params {
     /// expressed as a decimal (e.g. 0.5 for 50%).
     #[derivative(Default(value = "String::from(\"0.1\")"))]
     pub slashing_rate: String,
+    /// `btc_confirmation_depth` is the BTC confirmation depth the BTC staking protocol expects.
+    /// If set, it must match the one of the Babylon contract
+    #[serde(default)]
+    pub btc_confirmation_depth: Option<u64>,
+    /// `min_fp_self_stake_sat` is the minimum self-stake (quantified in Satoshi) a finality
+    /// provider needs in order to be part of the active set. Zero disables the requirement
+    #[serde(default)]
+    pub min_fp_self_stake_sat: u64,
+    /// `unbonding_intent_delay` is the number of blocks after a delegator-initiated unbonding
+    /// intent before the delegation loses its voting power. Zero removes it immediately
+    #[serde(default)]
+    pub unbonding_intent_delay: u64,
+    /// `fp_removal_delay` is the number of blocks after the admin schedules the removal of a
+    /// finality provider before it can be removed. Zero removes it immediately
+    #[serde(default)]
+    pub fp_removal_delay: u64,
+    /// `jail_downtime` is the number of blocks a jailed finality provider has to wait before its
+    /// operator can unjail it. Zero allows unjailing right away
+    #[serde(default)]
+    pub jail_downtime: u64,
+    /// `max_active_finality_providers` is the maximum number of finality providers of the active
+    /// set, i.e. the top finality providers by voting power listed by `FinalityProvidersByPower`
+    #[derivative(Default(value = "DEFAULT_MAX_ACTIVE_FINALITY_PROVIDERS"))]
+    #[serde(default = "default_max_active_finality_providers")]
+    pub max_active_finality_providers: u32,
+    /// `reward_denom` is the denom of the rewards distributed to the finality providers and their
+    /// delegators
+    #[derivative(Default(value = "default_reward_denom()"))]
+    #[serde(default = "default_reward_denom")]
+    pub reward_denom: String,
+    /// `duplicate_fps` is how `BtcStaking` registrations of already existing finality providers
+    /// are handled. Defaults to rejecting the whole packet
+    #[serde(default)]
+    pub duplicate_fps: DuplicateFps,
+    /// `verify_staking_tx` is whether the staking output of active delegations is checked against
+    /// their keys, staking time and amount, instead of trusting Babylon for it
+    #[serde(default)]
+    pub verify_staking_tx: bool,
+    /// `commission_lockup_blocks` is the number of blocks the finality provider commissions vest
+    /// over once withdrawn, linearly. Zero pays them out right away
+    #[serde(default)]
+    pub commission_lockup_blocks: u64,
+    /// `fp_registration_deposit` is the refundable deposit required to register a finality
+    /// provider directly on the consumer, if any
+    #[serde(default)]
+    pub fp_registration_deposit: Option<Coin>,
+    /// `fp_deposit_min_stake_sat` is the voting power a finality provider registered directly on
+    /// the consumer has to attract before its deposit can be refunded, in satoshi
+    #[serde(default)]
+    pub fp_deposit_min_stake_sat: u64,
+}
+
+/// DuplicateFps is how the registrations of already existing finality providers are handled,
+/// e.g. when Babylon re-sends them after a channel reset
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum DuplicateFps {
+    /// `Reject` fails the whole packet with `ContractError::FinalityProviderAlreadyExists`
+    #[default]
+    Reject,
+    /// `Skip` ignores the registration, emitting a `duplicate_finality_provider` event
+    Skip,
+    /// `Update` updates the mutable fields of the finality provider (description and
+    /// commission) from the registration, emitting a `duplicate_finality_provider` event
+    Update,
+}
+
+const DEFAULT_MAX_ACTIVE_FINALITY_PROVIDERS: u32 = 100;
+
+fn default_max_active_finality_providers() -> u32 {
+    DEFAULT_MAX_ACTIVE_FINALITY_PROVIDERS
+}
+
+fn default_reward_denom() -> String {
+    "stake".to_string()
+}
+
+impl Params {
+    /// `normalize_pks` converts the covenant PKs to their canonical (x-only) encoding
+    pub fn normalize_pks(&mut self) -> Result<(), StakingApiError> {
+        self.covenant_pks = self
+            .covenant_pks
+            .iter()
+            .map(|pk| canonical_btc_pk_hex(pk))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,81 @@
+//! migration defines the storage migrations of the contract, run in chunks (see
+//! `babylon_apis::migration`) and advanced by anyone through `ContinueMigration`.
+//! The migrations backfill the state introduced by newer versions of the contract
+use babylon_apis::migration::Migration;
+use cosmwasm_std::{StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::state::rewards::{self, FP_REWARDS};
+use crate::state::staking::{self, DELEGATIONS, DELEGATION_EXPIRIES, STAKER_DELEGATIONS};
+use crate::state::state_hash::SECTION_HASHES_REBUILD;
+
+pub const SECTION_DELEGATIONS: &str = "delegations";
+pub const SECTION_REWARDS: &str = "rewards";
+
+/// The migration setting the reward distribution stake of the active delegations.
+/// Until it is complete, the stakes of the delegations it has not gone through yet are left to
+/// it (see `rewards::is_stake_tracked`), and rewards cannot be distributed
+pub const REWARD_STAKES_MIGRATION: &str = "reward_stakes";
+
+const REWARD_STAKES: Migration = Migration {
+    name: REWARD_STAKES_MIGRATION,
+    section: SECTION_REWARDS,
+    step: rewards::backfill_stakes,
+};
+const STAKER_DELEGATIONS_INDEX: Migration = Migration {
+    name: "staker_delegations",
+    section: SECTION_DELEGATIONS,
+    step: staking::backfill_staker_delegations,
+};
+/// The lifecycle state of the stored delegations is recorded before indexing their expiries
+const DELEGATION_STATUSES: Migration = Migration {
+    name: "delegation_statuses",
+    section: SECTION_DELEGATIONS,
+    step: staking::backfill_delegation_statuses,
+};
+const DELEGATION_EXPIRIES_INDEX: Migration = Migration {
+    name: "delegation_expiries",
+    section: SECTION_DELEGATIONS,
+    step: staking::backfill_delegation_expiries,
+};
+
+/// The migrations of the current contract version, in order
+pub const MIGRATIONS: &[Migration] = &[
+    REWARD_STAKES,
+    STAKER_DELEGATIONS_INDEX,
+    DELEGATION_STATUSES,
+    DELEGATION_EXPIRIES_INDEX,
+    SECTION_HASHES_REBUILD,
+];
+
+/// needed_migrations returns the migrations the stored state needs, i.e. the backfills of the
+/// state missing from the older contract versions. The delegation state needs none without any
+/// delegation
+pub fn needed_migrations(storage: &dyn Storage) -> StdResult<Vec<Migration>> {
+    let mut migrations = vec![];
+    if !DELEGATIONS.is_empty(storage) {
+        if FP_REWARDS.is_empty(storage) {
+            migrations.push(REWARD_STAKES);
+        }
+        if STAKER_DELEGATIONS.is_empty(storage) {
+            migrations.push(STAKER_DELEGATIONS_INDEX);
+        }
+        migrations.push(DELEGATION_STATUSES);
+        if DELEGATION_EXPIRIES.is_empty(storage) {
+            migrations.push(DELEGATION_EXPIRIES_INDEX);
+        }
+    }
+    migrations.push(SECTION_HASHES_REBUILD);
+    Ok(migrations)
+}
+
+/// ensure_sections_active ensures none of the given state sections is being migrated
+pub fn ensure_sections_active(
+    storage: &dyn Storage,
+    sections: &[&str],
+) -> Result<(), ContractError> {
+    match babylon_apis::migration::migrating_section(storage, sections)? {
+        Some(section) => Err(ContractError::SectionMigrating { section }),
+        None => Ok(()),
+    }
+}
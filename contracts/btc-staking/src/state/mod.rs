@@ -1,4 +1,8 @@
 pub mod config;
+pub mod migration;
+pub mod quarantine;
+pub mod rewards;
 pub mod staking;
+pub mod state_hash;
 
 mod fp_index;
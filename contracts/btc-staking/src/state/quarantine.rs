@@ -0,0 +1,27 @@
+use cosmwasm_schema::cw_serde;
+use cw_storage_plus::Map;
+
+use babylon_apis::btc_staking_api::{ActiveBtcDelegation, NewFinalityProvider};
+
+/// A BTC staking operation that failed validation, kept aside until it is reprocessed (e.g. after
+/// a parameters fix) or discarded by the admin
+#[cw_serde]
+pub enum QuarantinedOp {
+    NewFp(NewFinalityProvider),
+    ActiveDel(ActiveBtcDelegation),
+}
+
+#[cw_serde]
+pub struct QuarantinedItem {
+    /// id is the BTC public key (in hex format) of a new finality provider, or the staking tx
+    /// hash (in hex format) of an active delegation
+    pub id: String,
+    pub op: QuarantinedOp,
+    /// reason is the validation error of the operation, prefixed by its error code
+    pub reason: String,
+    /// height is the height at which the operation was quarantined
+    pub height: u64,
+}
+
+/// Quarantined BTC staking operations, by id
+pub(crate) const QUARANTINE: Map<&str, QuarantinedItem> = Map::new("quarantine");
@@ -0,0 +1,644 @@
+use cosmwasm_schema::cw_serde;
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Decimal, Decimal256, Order, StdResult, Storage, Uint128, Uint256};
+use cw_storage_plus::{Bound, Map};
+
+use babylon_apis::math::{add_sat, split_commission};
+use babylon_apis::migration::is_migrated;
+
+use crate::state::migration::REWARD_STAKES_MIGRATION;
+use crate::state::staking::{DELEGATIONS, DELEGATION_FPS, FPS, FP_REMOVALS, UNBONDING_INTENTS};
+
+/// Reward distribution state of the finality providers, by BTC public key
+pub const FP_REWARDS: Map<&str, FpRewards> = Map::new("fp_rewards");
+/// Reward distribution state of the stakers, by staker address and finality provider BTC public key
+pub const STAKER_REWARDS: Map<(&str, &str), StakerRewards> = Map::new("staker_rewards");
+/// Settled rewards not withdrawn yet, by address
+pub const REWARD_BALANCES: Map<&str, Uint128> = Map::new("reward_balances");
+
+/// The cw20 tokens registered by the admin as rewards
+pub const CW20_REWARD_TOKENS: Map<&Addr, ()> = Map::new("cw20_reward_tokens");
+/// cw20 reward distribution state of the finality providers, by BTC public key and token
+pub const FP_CW20_REWARDS: Map<(&str, &str), Cw20FpRewards> = Map::new("fp_cw20_rewards");
+/// cw20 reward per satoshi of the finality providers the rewards of the stakers were last settled
+/// at, by staker address, finality provider BTC public key and token.
+/// Missing if no reward was distributed in the token since, i.e. zero
+pub const STAKER_CW20_REWARDS: Map<(&str, &str, &str), Decimal256> =
+    Map::new("staker_cw20_rewards");
+/// Settled cw20 rewards not withdrawn yet, by address and token
+pub const CW20_REWARD_BALANCES: Map<(&str, &str), Uint128> = Map::new("cw20_reward_balances");
+/// Finality provider commissions not withdrawn yet, by address and asset (`NATIVE_ASSET` or cw20
+/// token)
+pub const COMMISSION_BALANCES: Map<(&str, &str), Uint128> = Map::new("commission_balances");
+/// Withdrawn commissions vesting, by address, start height and asset (`NATIVE_ASSET` or cw20
+/// token)
+pub const COMMISSION_VESTINGS: Map<(&str, u64, &str), CommissionVesting> =
+    Map::new("commission_vestings");
+
+/// The asset key of the reward denom, in the maps keyed by asset
+pub const NATIVE_ASSET: &str = "";
+
+/// `FpRewards` is the reward distribution state of a finality provider
+#[cw_serde]
+#[derive(Default)]
+pub struct FpRewards {
+    /// `total_sat` is the stake of the active delegations to the finality provider, in satoshi
+    pub total_sat: u64,
+    /// `reward_per_sat` is the cumulative reward per satoshi of stake distributed to the
+    /// delegators of the finality provider
+    pub reward_per_sat: Decimal256,
+    /// `distributed` is the total reward distributed to the finality provider, commission
+    /// included
+    pub distributed: Uint128,
+}
+
+/// `Cw20FpRewards` is the reward distribution state of a finality provider in a cw20 token.
+/// The stake is the one of `FpRewards`
+#[cw_serde]
+#[derive(Default)]
+pub struct Cw20FpRewards {
+    /// `reward_per_sat` is the cumulative reward per satoshi of stake distributed to the
+    /// delegators of the finality provider, in the token
+    pub reward_per_sat: Decimal256,
+    /// `distributed` is the total reward distributed to the finality provider in the token,
+    /// commission included
+    pub distributed: Uint128,
+}
+
+/// `CommissionVesting` is a withdrawn commission vesting linearly until `end_height`
+#[cw_serde]
+pub struct CommissionVesting {
+    pub end_height: u64,
+    pub amount: Uint128,
+    /// `claimed` is the part of the amount paid out so far
+    pub claimed: Uint128,
+}
+
+impl CommissionVesting {
+    /// `vested` is the part of the amount vested at `height`, claimed included
+    pub fn vested(&self, start_height: u64, height: u64) -> Uint128 {
+        if height >= self.end_height {
+            return self.amount;
+        }
+        let elapsed = height.saturating_sub(start_height);
+        self.amount
+            .multiply_ratio(elapsed, self.end_height - start_height)
+    }
+}
+
+/// `StakerRewards` is the reward distribution state of a staker in a finality provider
+#[cw_serde]
+#[derive(Default)]
+pub struct StakerRewards {
+    /// `stake` is the stake of the active delegations of the staker to the finality provider, in
+    /// satoshi
+    pub stake: u64,
+    /// `reward_per_sat` is the cumulative reward per satoshi of the finality provider the rewards
+    /// of the staker were last settled at
+    pub reward_per_sat: Decimal256,
+}
+
+impl StakerRewards {
+    /// `pending` is the reward accrued since the last settlement
+    pub fn pending(&self, fp_rewards: &FpRewards) -> StdResult<Uint128> {
+        let accrued =
+            Uint256::from(self.stake).mul_floor(fp_rewards.reward_per_sat - self.reward_per_sat);
+        Ok(Uint128::try_from(accrued)?)
+    }
+}
+
+/// `distribute` distributes `amount` to the finality provider, the `commission` share to its
+/// address `fp_addr` and the rest to its delegators, proportionally to their stake.
+/// Returns the commission.
+/// The finality provider must have stake
+pub fn distribute(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    fp_addr: &str,
+    commission: Decimal,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let mut fp_rewards = FP_REWARDS.load(storage, fp_btc_pk_hex)?;
+    let (commission, delegators_reward) = split_commission(amount, commission);
+    credit_commission(storage, fp_addr, NATIVE_ASSET, commission)?;
+    fp_rewards.reward_per_sat += Decimal256::from_ratio(delegators_reward, fp_rewards.total_sat);
+    fp_rewards.distributed += amount;
+    FP_REWARDS.save(storage, fp_btc_pk_hex, &fp_rewards)?;
+    Ok(commission)
+}
+
+/// `distribute_cw20` distributes `amount` of the cw20 `token` to the finality provider, as
+/// `distribute` does for the reward denom.
+/// Returns the commission.
+/// The finality provider must have stake
+pub fn distribute_cw20(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    fp_addr: &str,
+    commission: Decimal,
+    token: &str,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let total_sat = FP_REWARDS.load(storage, fp_btc_pk_hex)?.total_sat;
+    let mut fp_rewards = FP_CW20_REWARDS
+        .may_load(storage, (fp_btc_pk_hex, token))?
+        .unwrap_or_default();
+    let (commission, delegators_reward) = split_commission(amount, commission);
+    credit_commission(storage, fp_addr, token, commission)?;
+    fp_rewards.reward_per_sat += Decimal256::from_ratio(delegators_reward, total_sat);
+    fp_rewards.distributed += amount;
+    FP_CW20_REWARDS.save(storage, (fp_btc_pk_hex, token), &fp_rewards)?;
+    Ok(commission)
+}
+
+/// `add_stake` adds `sat` to the stake of the staker in the finality provider, settling its
+/// rewards accrued so far
+pub fn add_stake(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    staker_addr: &str,
+    sat: u64,
+) -> StdResult<()> {
+    update_stake(storage, fp_btc_pk_hex, staker_addr, |stake| {
+        stake.saturating_add(sat)
+    })
+}
+
+/// `remove_stake` removes `sat` from the stake of the staker in the finality provider, settling
+/// its rewards accrued so far
+pub fn remove_stake(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    staker_addr: &str,
+    sat: u64,
+) -> StdResult<()> {
+    update_stake(storage, fp_btc_pk_hex, staker_addr, |stake| {
+        stake.saturating_sub(sat)
+    })
+}
+
+fn update_stake(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    staker_addr: &str,
+    update: impl FnOnce(u64) -> u64,
+) -> StdResult<()> {
+    let mut fp_rewards = FP_REWARDS
+        .may_load(storage, fp_btc_pk_hex)?
+        .unwrap_or_default();
+    let mut rewards = STAKER_REWARDS
+        .may_load(storage, (staker_addr, fp_btc_pk_hex))?
+        .unwrap_or_default();
+    credit(storage, staker_addr, rewards.pending(&fp_rewards)?)?;
+
+    let stake = update(rewards.stake);
+    settle_cw20(storage, fp_btc_pk_hex, staker_addr, rewards.stake, stake)?;
+    fp_rewards.total_sat = add_sat(fp_rewards.total_sat, stake)?.saturating_sub(rewards.stake);
+    FP_REWARDS.save(storage, fp_btc_pk_hex, &fp_rewards)?;
+    if stake == 0 {
+        STAKER_REWARDS.remove(storage, (staker_addr, fp_btc_pk_hex));
+    } else {
+        rewards.stake = stake;
+        rewards.reward_per_sat = fp_rewards.reward_per_sat;
+        STAKER_REWARDS.save(storage, (staker_addr, fp_btc_pk_hex), &rewards)?;
+    }
+    Ok(())
+}
+
+/// `pending_rewards` returns the rewards of the staker pending in each finality provider it
+/// stakes in, by finality provider BTC public key
+pub fn pending_rewards(
+    storage: &dyn Storage,
+    staker_addr: &str,
+) -> StdResult<Vec<(String, StakerRewards, Uint128)>> {
+    STAKER_REWARDS
+        .prefix(staker_addr)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (fp_btc_pk_hex, rewards) = item?;
+            let fp_rewards = FP_REWARDS.load(storage, &fp_btc_pk_hex)?;
+            let pending = rewards.pending(&fp_rewards)?;
+            Ok((fp_btc_pk_hex, rewards, pending))
+        })
+        .collect()
+}
+
+/// `settle_cw20` credits the cw20 rewards accrued by the `stake` of the staker in the finality
+/// provider, and resets the settlement points for its `new_stake`
+fn settle_cw20(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    staker_addr: &str,
+    stake: u64,
+    new_stake: u64,
+) -> StdResult<()> {
+    for (token, pending, fp_rewards) in pending_cw20_in(storage, fp_btc_pk_hex, staker_addr, stake)?
+    {
+        credit_cw20(storage, staker_addr, &token, pending)?;
+        let key = (staker_addr, fp_btc_pk_hex, token.as_str());
+        if new_stake == 0 {
+            STAKER_CW20_REWARDS.remove(storage, key);
+        } else {
+            STAKER_CW20_REWARDS.save(storage, key, &fp_rewards.reward_per_sat)?;
+        }
+    }
+    Ok(())
+}
+
+/// `pending_cw20_in` returns the cw20 rewards accrued by the `stake` of the staker in the finality
+/// provider since the last settlement, along with the reward state of the finality provider, by
+/// token
+fn pending_cw20_in(
+    storage: &dyn Storage,
+    fp_btc_pk_hex: &str,
+    staker_addr: &str,
+    stake: u64,
+) -> StdResult<Vec<(String, Uint128, Cw20FpRewards)>> {
+    FP_CW20_REWARDS
+        .prefix(fp_btc_pk_hex)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (token, fp_rewards) = item?;
+            let settled = STAKER_CW20_REWARDS
+                .may_load(storage, (staker_addr, fp_btc_pk_hex, &token))?
+                .unwrap_or_default();
+            let accrued = Uint256::from(stake).mul_floor(fp_rewards.reward_per_sat - settled);
+            Ok((token, Uint128::try_from(accrued)?, fp_rewards))
+        })
+        .collect()
+}
+
+/// `pending_cw20` returns the cw20 rewards the staker can withdraw, i.e. its settled rewards plus
+/// the rewards pending in each finality provider it stakes in, by token
+pub fn pending_cw20(
+    storage: &dyn Storage,
+    staker_addr: &str,
+) -> StdResult<BTreeMap<String, Uint128>> {
+    let mut amounts = BTreeMap::<String, Uint128>::new();
+    for item in
+        CW20_REWARD_BALANCES
+            .prefix(staker_addr)
+            .range(storage, None, None, Order::Ascending)
+    {
+        let (token, balance) = item?;
+        *amounts.entry(token).or_default() += balance;
+    }
+    for (fp_btc_pk_hex, rewards) in STAKER_REWARDS
+        .prefix(staker_addr)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+    {
+        for (token, pending, _) in
+            pending_cw20_in(storage, &fp_btc_pk_hex, staker_addr, rewards.stake)?
+        {
+            *amounts.entry(token).or_default() += pending;
+        }
+    }
+    amounts.retain(|_, amount| !amount.is_zero());
+    Ok(amounts)
+}
+
+/// `withdraw_cw20` settles the cw20 rewards of the staker in all the finality providers it stakes
+/// in, and takes its whole cw20 balances out, by token
+pub fn withdraw_cw20(
+    storage: &mut dyn Storage,
+    staker_addr: &str,
+) -> StdResult<BTreeMap<String, Uint128>> {
+    let amounts = pending_cw20(storage, staker_addr)?;
+    for (fp_btc_pk_hex, rewards) in STAKER_REWARDS
+        .prefix(staker_addr)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+    {
+        for (token, _, fp_rewards) in
+            pending_cw20_in(storage, &fp_btc_pk_hex, staker_addr, rewards.stake)?
+        {
+            let key = (staker_addr, fp_btc_pk_hex.as_str(), token.as_str());
+            STAKER_CW20_REWARDS.save(storage, key, &fp_rewards.reward_per_sat)?;
+        }
+    }
+    CW20_REWARD_BALANCES
+        .prefix(staker_addr)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+        .iter()
+        .for_each(|token| CW20_REWARD_BALANCES.remove(storage, (staker_addr, token)));
+    Ok(amounts)
+}
+
+/// `commissions` returns the commissions of the address not withdrawn yet, by asset
+pub fn commissions(storage: &dyn Storage, addr: &str) -> StdResult<BTreeMap<String, Uint128>> {
+    COMMISSION_BALANCES
+        .prefix(addr)
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+/// `withdraw_commissions` takes the commissions of the address out, by asset.
+/// With a lockup, i.e. `lockup_blocks` > 0, they start vesting at `height` instead, and only
+/// the commissions vested so far are taken out
+pub fn withdraw_commissions(
+    storage: &mut dyn Storage,
+    addr: &str,
+    height: u64,
+    lockup_blocks: u64,
+) -> StdResult<BTreeMap<String, Uint128>> {
+    let amounts = commissions(storage, addr)?;
+    for asset in amounts.keys() {
+        COMMISSION_BALANCES.remove(storage, (addr, asset));
+    }
+    if lockup_blocks == 0 {
+        return Ok(amounts);
+    }
+
+    for (asset, amount) in amounts {
+        COMMISSION_VESTINGS.update(storage, (addr, height, &asset), |vesting| -> StdResult<_> {
+            let mut vesting = vesting.unwrap_or(CommissionVesting {
+                end_height: height + lockup_blocks,
+                amount: Uint128::zero(),
+                claimed: Uint128::zero(),
+            });
+            vesting.amount += amount;
+            Ok(vesting)
+        })?;
+    }
+    let mut vested = BTreeMap::<String, Uint128>::new();
+    for ((start_height, asset), mut vesting) in commission_vestings(storage, addr)? {
+        let claimable = vesting.vested(start_height, height) - vesting.claimed;
+        if claimable.is_zero() {
+            continue;
+        }
+        *vested.entry(asset.clone()).or_default() += claimable;
+        vesting.claimed += claimable;
+        if vesting.claimed == vesting.amount {
+            COMMISSION_VESTINGS.remove(storage, (addr, start_height, &asset));
+        } else {
+            COMMISSION_VESTINGS.save(storage, (addr, start_height, &asset), &vesting)?;
+        }
+    }
+    Ok(vested)
+}
+
+/// `commission_vestings` returns the withdrawn commissions of the address vesting, by start
+/// height and asset
+pub fn commission_vestings(
+    storage: &dyn Storage,
+    addr: &str,
+) -> StdResult<Vec<((u64, String), CommissionVesting)>> {
+    COMMISSION_VESTINGS
+        .sub_prefix(addr)
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+/// `withdraw` settles the rewards of the staker in all the finality providers it stakes in, and
+/// takes its whole balance out
+pub fn withdraw(storage: &mut dyn Storage, staker_addr: &str) -> StdResult<Uint128> {
+    let mut amount = REWARD_BALANCES
+        .may_load(storage, staker_addr)?
+        .unwrap_or_default();
+    for (fp_btc_pk_hex, mut rewards, pending) in pending_rewards(storage, staker_addr)? {
+        amount += pending;
+        rewards.reward_per_sat = FP_REWARDS.load(storage, &fp_btc_pk_hex)?.reward_per_sat;
+        STAKER_REWARDS.save(storage, (staker_addr, &fp_btc_pk_hex), &rewards)?;
+    }
+    REWARD_BALANCES.remove(storage, staker_addr);
+    Ok(amount)
+}
+
+/// `is_stake_tracked` returns whether the stake of the given delegation is tracked, i.e. unless
+/// the stakes backfill has not gone through the delegation yet, leaving its stake to the backfill
+pub fn is_stake_tracked(storage: &dyn Storage, staking_tx_hash: &[u8]) -> StdResult<bool> {
+    is_migrated(storage, REWARD_STAKES_MIGRATION, staking_tx_hash)
+}
+
+/// `backfill_stakes` sets the stake of the active delegations, for deployments predating the
+/// rewards distribution, i.e. without reward distribution state yet. Delegations to slashed or
+/// removed finality providers are skipped, as they are no longer eligible for rewards.
+/// It is a chunked migration step (see `babylon_apis::migration::MigrationStep`), going through
+/// up to `limit` delegations after the cursor
+pub fn backfill_stakes(
+    storage: &mut dyn Storage,
+    cursor: Option<&[u8]>,
+    limit: usize,
+) -> StdResult<(usize, Option<Vec<u8>>)> {
+    let start = cursor.map(|cursor| Bound::ExclusiveRaw(cursor.to_vec()));
+    let delegation_fps = DELEGATION_FPS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let (count, last) = (
+        delegation_fps.len(),
+        delegation_fps.last().map(|(key, _)| key.to_vec()),
+    );
+    for (staking_tx_hash, fp_btc_pk_hexs) in delegation_fps {
+        let btc_del = DELEGATIONS.load(storage, &staking_tx_hash)?;
+        let unbonding = UNBONDING_INTENTS
+            .may_load(storage, &staking_tx_hash)?
+            .is_some_and(|intent| intent.processed);
+        if !btc_del.is_active() || unbonding {
+            continue;
+        }
+        for fp_btc_pk_hex in fp_btc_pk_hexs {
+            let slashed = FPS
+                .may_load(storage, &fp_btc_pk_hex)?
+                .is_none_or(|fp| fp.slashed_height > 0);
+            if slashed || FP_REMOVALS.has(storage, &fp_btc_pk_hex) {
+                continue;
+            }
+            add_stake(
+                storage,
+                &fp_btc_pk_hex,
+                &btc_del.staker_addr,
+                btc_del.total_sat,
+            )?;
+        }
+    }
+    Ok((count, last))
+}
+
+fn credit(storage: &mut dyn Storage, addr: &str, amount: Uint128) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    REWARD_BALANCES.update(storage, addr, |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+fn credit_commission(
+    storage: &mut dyn Storage,
+    addr: &str,
+    asset: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    COMMISSION_BALANCES.update(storage, (addr, asset), |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+fn credit_cw20(
+    storage: &mut dyn Storage,
+    addr: &str,
+    token: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    CW20_REWARD_BALANCES.update(storage, (addr, token), |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn rewards_are_split_by_stake() {
+        let mut storage = MockStorage::new();
+        add_stake(&mut storage, "fp", "alice", 100).unwrap();
+        add_stake(&mut storage, "fp", "bob", 300).unwrap();
+
+        // 10% commission to the finality provider, the rest split 1:3
+        let commission = distribute(
+            &mut storage,
+            "fp",
+            "operator",
+            Decimal::percent(10),
+            Uint128::new(1000),
+        )
+        .unwrap();
+        assert_eq!(commission, Uint128::new(100));
+        let pending = pending_rewards(&storage, "alice").unwrap();
+        assert_eq!(pending[0].2, Uint128::new(225));
+        let pending = pending_rewards(&storage, "bob").unwrap();
+        assert_eq!(pending[0].2, Uint128::new(675));
+
+        // Stake changes only affect the rewards distributed afterwards
+        remove_stake(&mut storage, "fp", "bob", 300).unwrap();
+        assert!(pending_rewards(&storage, "bob").unwrap().is_empty());
+        distribute(
+            &mut storage,
+            "fp",
+            "operator",
+            Decimal::zero(),
+            Uint128::new(500),
+        )
+        .unwrap();
+
+        assert_eq!(withdraw(&mut storage, "alice").unwrap(), Uint128::new(725));
+        assert_eq!(withdraw(&mut storage, "alice").unwrap(), Uint128::zero());
+        assert_eq!(withdraw(&mut storage, "bob").unwrap(), Uint128::new(675));
+        assert_eq!(withdraw(&mut storage, "operator").unwrap(), Uint128::zero());
+        assert_eq!(
+            withdraw_commissions(&mut storage, "operator", 1, 0).unwrap()[NATIVE_ASSET],
+            Uint128::new(100)
+        );
+        assert_eq!(FP_REWARDS.load(&storage, "fp").unwrap().total_sat, 100);
+    }
+
+    #[test]
+    fn cw20_rewards_are_split_by_stake() {
+        let mut storage = MockStorage::new();
+        add_stake(&mut storage, "fp", "alice", 100).unwrap();
+        distribute_cw20(
+            &mut storage,
+            "fp",
+            "operator",
+            Decimal::percent(10),
+            "token",
+            Uint128::new(1000),
+        )
+        .unwrap();
+
+        // Bob only gets the rewards distributed after he stakes
+        add_stake(&mut storage, "fp", "bob", 300).unwrap();
+        distribute_cw20(
+            &mut storage,
+            "fp",
+            "operator",
+            Decimal::zero(),
+            "token",
+            Uint128::new(400),
+        )
+        .unwrap();
+        let pending = pending_cw20(&storage, "alice").unwrap();
+        assert_eq!(pending["token"], Uint128::new(1000));
+        let pending = pending_cw20(&storage, "bob").unwrap();
+        assert_eq!(pending["token"], Uint128::new(300));
+
+        // Stake changes settle the cw20 rewards accrued so far
+        remove_stake(&mut storage, "fp", "bob", 300).unwrap();
+        assert!(!STAKER_CW20_REWARDS.has(&storage, ("bob", "fp", "token")));
+        assert_eq!(
+            withdraw_cw20(&mut storage, "bob").unwrap()["token"],
+            Uint128::new(300)
+        );
+        assert!(withdraw_cw20(&mut storage, "bob").unwrap().is_empty());
+        assert_eq!(
+            withdraw_cw20(&mut storage, "alice").unwrap()["token"],
+            Uint128::new(1000)
+        );
+        assert!(pending_cw20(&storage, "alice").unwrap().is_empty());
+        assert_eq!(
+            withdraw_commissions(&mut storage, "operator", 1, 0).unwrap()["token"],
+            Uint128::new(100)
+        );
+        // Native rewards are not affected
+        assert_eq!(withdraw(&mut storage, "alice").unwrap(), Uint128::zero());
+    }
+
+    #[test]
+    fn commissions_vest_linearly() {
+        let mut storage = MockStorage::new();
+        add_stake(&mut storage, "fp", "alice", 100).unwrap();
+        distribute(
+            &mut storage,
+            "fp",
+            "operator",
+            Decimal::percent(50),
+            Uint128::new(2000),
+        )
+        .unwrap();
+        assert_eq!(
+            commissions(&storage, "operator").unwrap()[NATIVE_ASSET],
+            Uint128::new(1000)
+        );
+
+        // Withdrawn commissions start vesting over 10 blocks
+        assert!(withdraw_commissions(&mut storage, "operator", 100, 10)
+            .unwrap()
+            .is_empty());
+        assert!(commissions(&storage, "operator").unwrap().is_empty());
+        let vested = withdraw_commissions(&mut storage, "operator", 104, 10).unwrap();
+        assert_eq!(vested[NATIVE_ASSET], Uint128::new(400));
+
+        // New commissions vest on their own schedule
+        distribute(
+            &mut storage,
+            "fp",
+            "operator",
+            Decimal::percent(50),
+            Uint128::new(200),
+        )
+        .unwrap();
+        let vested = withdraw_commissions(&mut storage, "operator", 105, 10).unwrap();
+        assert_eq!(vested[NATIVE_ASSET], Uint128::new(100));
+        assert_eq!(commission_vestings(&storage, "operator").unwrap().len(), 2);
+        let vested = withdraw_commissions(&mut storage, "operator", 115, 10).unwrap();
+        assert_eq!(vested[NATIVE_ASSET], Uint128::new(600));
+        assert!(commission_vestings(&storage, "operator")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,8 +1,10 @@
 use cosmwasm_schema::cw_serde;
-use cw_storage_plus::{IndexedSnapshotMap, Item, Map, MultiIndex, Strategy};
+use cosmwasm_std::{Addr, Binary, Coin, Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, IndexedSnapshotMap, Item, Map, MultiIndex, Strategy};
 
 use crate::state::fp_index::FinalityProviderIndexes;
-use babylon_apis::btc_staking_api::{BTCDelegationStatus, FinalityProvider, HASH_SIZE};
+use babylon_apis::btc_staking_api::{BTCDelegationStatus, FinalityProvider, FpMetadata, HASH_SIZE};
+use babylon_apis::compression::CompressedMap;
 use babylon_apis::{btc_staking_api, Bytes};
 
 #[cw_serde]
@@ -46,31 +48,65 @@ pub struct BtcDelegation {
     pub params_version: u32,
     /// slashed is used to indicate whether a given delegation is related to a slashed FP
     pub slashed: bool,
+    /// status is the recorded lifecycle state of the delegation. It is `Active` until the
+    /// delegation is unbonded early, slashed or expired. `Pending` is only derived at query time
+    #[serde(default)]
+    pub status: DelegationStatus,
+}
+
+/// Lifecycle state of a BTC delegation
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum DelegationStatus {
+    /// The staking time-lock has not begun yet at the last known BTC tip
+    Pending,
+    /// The delegation has voting power
+    #[default]
+    Active,
+    /// The delegation is unbonding early, either as reported by Babylon or through a processed
+    /// unbonding intent
+    Unbonding,
+    /// The staking time-lock of the delegation has ended
+    Expired,
+    /// The delegation is related to a slashed finality provider
+    Slashed,
 }
 
 impl BtcDelegation {
     pub fn is_active(&self) -> bool {
-        // TODO: Implement full delegation status checks (needs BTC height)
-        // self.get_status(btc_height, w) == BTCDelegationStatus::ACTIVE
-        !self.is_unbonded_early() && !self.is_slashed()
+        self.status == DelegationStatus::Active
     }
 
-    fn is_unbonded_early(&self) -> bool {
-        !self.undelegation_info.delegator_unbonding_sig.is_empty()
+    /// status returns the lifecycle state of the delegation, given the last known BTC tip height
+    /// (if any) and whether an unbonding intent has already removed its voting power
+    pub fn status(&self, btc_tip_height: Option<u64>, unbonding: bool) -> DelegationStatus {
+        match self.status {
+            DelegationStatus::Active if unbonding => DelegationStatus::Unbonding,
+            DelegationStatus::Active
+                if btc_tip_height.is_some_and(|tip| tip < self.start_height) =>
+            {
+                DelegationStatus::Pending
+            }
+            status => status,
+        }
     }
 
-    fn is_slashed(&self) -> bool {
-        self.slashed
+    /// recorded_status returns the lifecycle state implied by the unbonding signature and slashing
+    /// of the delegation, for delegations stored before their status was recorded
+    fn recorded_status(&self) -> DelegationStatus {
+        if self.slashed {
+            DelegationStatus::Slashed
+        } else if !self.undelegation_info.delegator_unbonding_sig.is_empty() {
+            DelegationStatus::Unbonding
+        } else {
+            self.status
+        }
     }
 
     pub fn get_status(&self, btc_height: u64, w: u64) -> BTCDelegationStatus {
-        // Manually unbonded, staking tx time-lock has not begun, is less than w BTC blocks left, or
-        // has expired
-        if self.is_unbonded_early()
-            || btc_height < self.start_height
-            || btc_height + w > self.end_height
-            || self.is_slashed()
-        {
+        // Manually unbonded, slashed, staking tx time-lock has not begun, is less than w BTC blocks
+        // left, or has expired
+        if !self.is_active() || btc_height < self.start_height || btc_height + w > self.end_height {
             BTCDelegationStatus::UNBONDED
         } else {
             // At this point, the BTC delegation has an active time-lock, and Babylon is not aware of
@@ -102,6 +138,7 @@ impl From<btc_staking_api::ActiveBtcDelegation> for BtcDelegation {
             undelegation_info: active_delegation.undelegation_info.into(),
             params_version: active_delegation.params_version,
             slashed: false,
+            status: DelegationStatus::Active,
         }
     }
 }
@@ -112,6 +149,23 @@ impl From<&btc_staking_api::ActiveBtcDelegation> for BtcDelegation {
     }
 }
 
+/// A delegator-initiated unbonding intent.
+/// It is recorded before Babylon reports the delegation as unbonded, so that covenant / vigilante
+/// tooling can pick it up
+#[cw_serde]
+pub struct UnbondingIntent {
+    /// staking_tx_hash is the (reversed) staking tx hash of the delegation, in hex
+    pub staking_tx_hash: String,
+    /// staker_addr is the address that requested the unbonding
+    pub staker_addr: String,
+    /// requested_height is the height at which the unbonding was requested
+    pub requested_height: u64,
+    /// unbond_height is the height at which the delegation loses its voting power
+    pub unbond_height: u64,
+    /// processed is whether the voting power of the delegation has already been removed
+    pub processed: bool,
+}
+
 #[cw_serde]
 pub struct CovenantAdaptorSignatures {
     /// cov_pk is the public key of the covenant emulator, used as the public key of the adaptor signature
@@ -199,13 +253,332 @@ impl From<btc_staking_api::SignatureInfo> for SignatureInfo {
 /// Finality providers by their BTC public key
 pub(crate) const FPS: Map<&str, FinalityProvider> = Map::new("fps");
 
-/// Delegations by staking tx hash
+/// Delegations by staking tx hash.
+/// With the `compression` feature, their proofs are stored compressed (see `DelegationMap`)
 /// TODO: create a new DB object for BTC delegation
-pub(crate) const DELEGATIONS: Map<&[u8; HASH_SIZE], BtcDelegation> = Map::new("delegations");
+pub(crate) const DELEGATIONS: DelegationMap =
+    DelegationMap::new("delegations", "delegation_proofs");
+
+/// `DelegationProofs` are the covenant signature arrays of a delegation, which dominate its size
+#[cw_serde]
+#[derive(Default)]
+pub struct DelegationProofs {
+    pub covenant_sigs: Vec<CovenantAdaptorSignatures>,
+    pub covenant_unbonding_sig_list: Vec<SignatureInfo>,
+    pub covenant_slashing_sigs: Vec<CovenantAdaptorSignatures>,
+}
+
+impl DelegationProofs {
+    /// `split` takes the proofs out of the delegation
+    pub fn split(del: &mut BtcDelegation) -> Self {
+        DelegationProofs {
+            covenant_sigs: std::mem::take(&mut del.covenant_sigs),
+            covenant_unbonding_sig_list: std::mem::take(
+                &mut del.undelegation_info.covenant_unbonding_sig_list,
+            ),
+            covenant_slashing_sigs: std::mem::take(
+                &mut del.undelegation_info.covenant_slashing_sigs,
+            ),
+        }
+    }
+
+    /// `join` puts the proofs back into the delegation
+    pub fn join(self, del: &mut BtcDelegation) {
+        del.covenant_sigs = self.covenant_sigs;
+        del.undelegation_info.covenant_unbonding_sig_list = self.covenant_unbonding_sig_list;
+        del.undelegation_info.covenant_slashing_sigs = self.covenant_slashing_sigs;
+    }
+}
+
+/// `DelegationMap` is a map of delegations, with the same interface as `Map`.
+/// With the `compression` feature, the proofs of the delegations are split off, and stored
+/// compressed (see `babylon_apis::compression`) in a map of their own, keyed the same. The rest of
+/// the delegations stays plain, as it's mostly incompressible keys and txs.
+/// Delegations stored whole, e.g. without the feature, are read as is, so that the feature can be
+/// switched without migrating the existing delegations
+pub(crate) struct DelegationMap {
+    namespace: &'static str,
+    proofs_namespace: &'static str,
+}
+
+impl DelegationMap {
+    pub const fn new(namespace: &'static str, proofs_namespace: &'static str) -> Self {
+        DelegationMap {
+            namespace,
+            proofs_namespace,
+        }
+    }
+
+    fn delegations<'a>(&self) -> Map<&'a [u8; HASH_SIZE], BtcDelegation> {
+        Map::new(self.namespace)
+    }
+
+    fn proofs<'a>(&self) -> CompressedMap<&'a [u8; HASH_SIZE], DelegationProofs> {
+        CompressedMap::new(self.proofs_namespace)
+    }
+
+    pub fn save(
+        &self,
+        storage: &mut dyn Storage,
+        key: &[u8; HASH_SIZE],
+        del: &BtcDelegation,
+    ) -> StdResult<()> {
+        if cfg!(feature = "compression") {
+            let mut del = del.clone();
+            let proofs = DelegationProofs::split(&mut del);
+            self.delegations().save(storage, key, &del)?;
+            self.proofs().save(storage, key, &proofs)
+        } else {
+            self.delegations().save(storage, key, del)?;
+            self.proofs().remove(storage, key);
+            Ok(())
+        }
+    }
+
+    pub fn load(&self, storage: &dyn Storage, key: &[u8; HASH_SIZE]) -> StdResult<BtcDelegation> {
+        let del = self.delegations().load(storage, key)?;
+        self.with_proofs(storage, key, del)
+    }
+
+    pub fn may_load(
+        &self,
+        storage: &dyn Storage,
+        key: &[u8; HASH_SIZE],
+    ) -> StdResult<Option<BtcDelegation>> {
+        self.delegations()
+            .may_load(storage, key)?
+            .map(|del| self.with_proofs(storage, key, del))
+            .transpose()
+    }
+
+    pub fn has(&self, storage: &dyn Storage, key: &[u8; HASH_SIZE]) -> bool {
+        self.delegations().has(storage, key)
+    }
+
+    pub fn is_empty(&self, storage: &dyn Storage) -> bool {
+        self.delegations().is_empty(storage)
+    }
+
+    pub fn range_raw<'a, 'c>(
+        &'a self,
+        storage: &'c dyn Storage,
+        min: Option<Bound<'a, &'a [u8; HASH_SIZE]>>,
+        max: Option<Bound<'a, &'a [u8; HASH_SIZE]>>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(Vec<u8>, BtcDelegation)>> + 'c>
+    where
+        'a: 'c,
+    {
+        Box::new(
+            self.delegations()
+                .range_raw(storage, min, max, order)
+                .map(move |item| {
+                    let (key, del) = item?;
+                    let hash: &[u8; HASH_SIZE] = key
+                        .as_slice()
+                        .try_into()
+                        .map_err(|_| StdError::invalid_data_size(HASH_SIZE, key.len()))?;
+                    let del = self.with_proofs(storage, hash, del)?;
+                    Ok((key, del))
+                }),
+        )
+    }
+
+    /// with_proofs joins the proofs stored apart, if any, into the delegation
+    fn with_proofs(
+        &self,
+        storage: &dyn Storage,
+        key: &[u8; HASH_SIZE],
+        mut del: BtcDelegation,
+    ) -> StdResult<BtcDelegation> {
+        if let Some(proofs) = self.proofs().may_load(storage, key)? {
+            proofs.join(&mut del);
+        }
+        Ok(del)
+    }
+}
 /// Map of staking hashes by finality provider
 pub(crate) const FP_DELEGATIONS: Map<&str, Vec<Vec<u8>>> = Map::new("fp_delegations");
+/// Index of delegations by (staker address, staking tx hash)
+pub(crate) const STAKER_DELEGATIONS: Map<(&str, &[u8; HASH_SIZE]), ()> =
+    Map::new("staker_delegations");
+
+/// delegations_after returns up to `limit` delegations after the given staking tx hash (if any),
+/// in staking tx hash order, for the backfills resuming from it
+fn delegations_after(
+    storage: &dyn Storage,
+    cursor: Option<&[u8]>,
+    limit: usize,
+) -> StdResult<Vec<([u8; HASH_SIZE], BtcDelegation)>> {
+    let start = cursor.map(|cursor| Bound::ExclusiveRaw(cursor.to_vec()));
+    DELEGATIONS
+        .range_raw(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, del) = item?;
+            let len = key.len();
+            let staking_tx_hash = key
+                .try_into()
+                .map_err(|_| StdError::invalid_data_size(HASH_SIZE, len))?;
+            Ok((staking_tx_hash, del))
+        })
+        .collect()
+}
+
+/// backfill_staker_delegations indexes the delegations by staker address, for contracts migrating
+/// from a version without the index. It is a chunked migration step (see
+/// `babylon_apis::migration::MigrationStep`), indexing up to `limit` delegations after the cursor
+pub fn backfill_staker_delegations(
+    storage: &mut dyn Storage,
+    cursor: Option<&[u8]>,
+    limit: usize,
+) -> StdResult<(usize, Option<Vec<u8>>)> {
+    let delegations = delegations_after(storage, cursor, limit)?;
+    for (staking_tx_hash, del) in &delegations {
+        STAKER_DELEGATIONS.save(storage, (&del.staker_addr, staking_tx_hash), &())?;
+    }
+    Ok((
+        delegations.len(),
+        delegations.last().map(|(key, _)| key.to_vec()),
+    ))
+}
+
+/// backfill_delegation_statuses records the lifecycle state of the unbonded or slashed
+/// delegations, for contracts migrating from a version without it. It is a chunked migration
+/// step, going through up to `limit` delegations after the cursor
+pub fn backfill_delegation_statuses(
+    storage: &mut dyn Storage,
+    cursor: Option<&[u8]>,
+    limit: usize,
+) -> StdResult<(usize, Option<Vec<u8>>)> {
+    let delegations = delegations_after(storage, cursor, limit)?;
+    let (count, last) = (
+        delegations.len(),
+        delegations.last().map(|(key, _)| key.to_vec()),
+    );
+    for (staking_tx_hash, mut del) in delegations {
+        if del.recorded_status() != del.status {
+            del.status = del.recorded_status();
+            DELEGATIONS.save(storage, &staking_tx_hash, &del)?;
+        }
+    }
+    Ok((count, last))
+}
+
+/// backfill_delegation_expiries indexes the active delegations by end height, for contracts
+/// migrating from a version without the index. It is a chunked migration step, going through up
+/// to `limit` delegations after the cursor
+pub fn backfill_delegation_expiries(
+    storage: &mut dyn Storage,
+    cursor: Option<&[u8]>,
+    limit: usize,
+) -> StdResult<(usize, Option<Vec<u8>>)> {
+    let delegations = delegations_after(storage, cursor, limit)?;
+    for (staking_tx_hash, del) in &delegations {
+        if del.is_active() {
+            DELEGATION_EXPIRIES.save(storage, (del.end_height, staking_tx_hash), &())?;
+        }
+    }
+    Ok((
+        delegations.len(),
+        delegations.last().map(|(key, _)| key.to_vec()),
+    ))
+}
+/// Exit status of a finality provider
+#[cw_serde]
+#[derive(Copy)]
+pub enum FpExitStatus {
+    /// The finality provider is deregistered, and waits for its delegations to unbond
+    Exiting,
+    /// All the delegations of the finality provider are unbonded
+    Exited,
+}
+
+/// The graceful exit of a deregistered finality provider
+#[cw_serde]
+pub struct FpExit {
+    pub status: FpExitStatus,
+    /// requested_height is the height at which the finality provider deregistered
+    pub requested_height: u64,
+    /// exited_height is the height at which the finality provider exited, if it did
+    pub exited_height: Option<u64>,
+}
+
+/// The removal of a finality provider by the admin, e.g. of a defunct or fraudulent finality
+/// provider whose removal never arrives from Babylon
+#[cw_serde]
+pub struct FpRemoval {
+    pub reason: String,
+    /// requested_height is the height at which the removal was scheduled
+    pub requested_height: u64,
+    /// executable_height is the height from which the removal can be executed
+    pub executable_height: u64,
+    /// removed_height is the height at which the finality provider was removed, if it was
+    pub removed_height: Option<u64>,
+}
+
+/// The operational metadata published by a finality provider, along with its signature, so that
+/// it can be verified off-chain as well
+#[cw_serde]
+pub struct FpMetadataRecord {
+    pub metadata: FpMetadata,
+    /// sig is the BIP-340 signature by the finality provider key over `fp_metadata_msg_hash`
+    pub sig: Binary,
+    /// updated_height is the height at which the metadata was published
+    pub updated_height: u64,
+}
+
+/// The jailing of a finality provider for missing too many consecutive blocks
+#[cw_serde]
+pub struct FpJail {
+    /// jailed_height is the height at which the finality provider was jailed
+    pub jailed_height: u64,
+    /// unjailable_height is the height from which the finality provider can be unjailed by its
+    /// operator
+    pub unjailable_height: u64,
+}
+
+/// The deposit of a finality provider registered directly on the consumer
+#[cw_serde]
+pub struct FpDeposit {
+    /// depositor is the address that registered the finality provider, the deposit is refunded to
+    pub depositor: Addr,
+    pub amount: Coin,
+    /// deposited_height is the height at which the finality provider was registered
+    pub deposited_height: u64,
+}
+
 /// Reverse map of finality providers by staking hash
 pub(crate) const DELEGATION_FPS: Map<&[u8; HASH_SIZE], Vec<String>> = Map::new("delegation_fps");
+/// Delegator-initiated unbonding intents by staking tx hash
+pub(crate) const UNBONDING_INTENTS: Map<&[u8; HASH_SIZE], UnbondingIntent> =
+    Map::new("unbonding_intents");
+/// Consumer address bound to each BTC staker public key (hex)
+pub(crate) const STAKER_BINDINGS: Map<&str, Addr> = Map::new("staker_bindings");
+/// Reverse map of BTC staker public keys (hex) by bound consumer address
+pub(crate) const ADDR_STAKER_BINDINGS: Map<(&Addr, &str), ()> = Map::new("addr_staker_bindings");
+/// Reward claim grants, by (staker address, grantee address)
+pub(crate) const CLAIM_GRANTS: Map<(&Addr, &Addr), ClaimGrant> = Map::new("claim_grants");
+/// Exits of deregistered finality providers, by BTC public key (hex)
+pub(crate) const FP_EXITS: Map<&str, FpExit> = Map::new("fp_exits");
+/// Operational metadata of finality providers, by BTC public key (hex)
+pub(crate) const FP_METADATA: Map<&str, FpMetadataRecord> = Map::new("fp_metadata");
+/// Jailings of finality providers, by BTC public key (hex)
+pub(crate) const FP_JAILS: Map<&str, FpJail> = Map::new("fp_jails");
+/// Removals of finality providers by the admin, by BTC public key (hex)
+pub(crate) const FP_REMOVALS: Map<&str, FpRemoval> = Map::new("fp_removals");
+/// Deposits of finality providers registered directly on the consumer, not refunded yet, by BTC
+/// public key (hex)
+pub(crate) const FP_DEPOSITS: Map<&str, FpDeposit> = Map::new("fp_deposits");
+/// Unbonding intents whose voting power is still to be removed, by (unbond height, staking tx hash)
+pub(crate) const PENDING_UNBONDINGS: Map<(u64, &[u8; HASH_SIZE]), ()> =
+    Map::new("pending_unbondings");
+/// Active delegations to be expired, by (end height, staking tx hash)
+pub(crate) const DELEGATION_EXPIRIES: Map<(u64, &[u8; HASH_SIZE]), ()> =
+    Map::new("delegation_expiries");
+
+/// The last BTC tip height reported by the Babylon contract, as seen at end block
+pub const BTC_TIP_HEIGHT: Item<u64> = Item::new("btc_tip_height");
 
 pub const FP_STATE_KEY: &str = "fp_state";
 const FP_STATE_CHECKPOINTS: &str = "fp_state__checkpoints";
@@ -224,7 +597,11 @@ pub const ACTIVATED_HEIGHT: Item<u64> = Item::new("activated_height");
 pub fn fps<'a>() -> IndexedSnapshotMap<&'a str, FinalityProviderState, FinalityProviderIndexes<'a>>
 {
     let indexes = FinalityProviderIndexes {
-        power: MultiIndex::new(|_, fp_state| fp_state.power, FP_STATE_KEY, FP_POWER_KEY),
+        power: MultiIndex::new(
+            |_, fp_state| fp_state.voting_power(),
+            FP_STATE_KEY,
+            FP_POWER_KEY,
+        ),
     };
     IndexedSnapshotMap::new(
         FP_STATE_KEY,
@@ -240,4 +617,103 @@ pub fn fps<'a>() -> IndexedSnapshotMap<&'a str, FinalityProviderState, FinalityP
 pub struct FinalityProviderState {
     /// Finality provider power, in satoshis
     pub power: u64,
+    /// Finality provider self-stake, i.e. the power delegated by the finality provider's own
+    /// address, in satoshis
+    #[serde(default)]
+    pub self_stake: u64,
+    /// Whether the finality provider is jailed, i.e. excluded from the voting power table until
+    /// it is unjailed. Its delegations keep counting towards `power` meanwhile
+    #[serde(default)]
+    pub jailed: bool,
+}
+
+impl FinalityProviderState {
+    /// voting_power is the power of the finality provider, or zero if it is jailed
+    pub fn voting_power(&self) -> u64 {
+        if self.jailed {
+            0
+        } else {
+            self.power
+        }
+    }
+}
+
+/// `ClaimGrant` is a revocable allowance, recorded by a staker, for a third party (e.g. a
+/// custodial service) to claim the staker's rewards on its behalf
+#[cw_serde]
+pub struct ClaimGrant {
+    /// grantee is the address allowed to claim the rewards
+    pub grantee: Addr,
+    /// expiry is the height from which the grant is no longer valid, or `None` if it is valid
+    /// until revoked
+    pub expiry: Option<u64>,
+    /// granted_height is the height at which the grant was recorded
+    pub granted_height: u64,
+}
+
+impl ClaimGrant {
+    pub fn is_expired(&self, height: u64) -> bool {
+        self.expiry.is_some_and(|expiry| height >= expiry)
+    }
+}
+
+/// can_claim returns whether `claimer` can claim the rewards of `staker` at `height`, i.e.
+/// whether it is the staker itself or holds an unexpired grant from it
+pub fn can_claim(
+    storage: &dyn Storage,
+    staker: &Addr,
+    claimer: &Addr,
+    height: u64,
+) -> StdResult<bool> {
+    if staker == claimer {
+        return Ok(true);
+    }
+    let grant = CLAIM_GRANTS.may_load(storage, (staker, claimer))?;
+    Ok(grant.is_some_and(|grant| !grant.is_expired(height)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use test_utils::get_active_btc_delegation;
+
+    const LEGACY: Map<&[u8; HASH_SIZE], BtcDelegation> = Map::new("delegations");
+
+    #[test]
+    fn delegation_map_works() {
+        let mut storage = MockStorage::new();
+        let del = BtcDelegation::from(get_active_btc_delegation());
+        assert!(!del.covenant_sigs.is_empty());
+
+        // Delegations stored whole are read as is
+        LEGACY.save(&mut storage, &[1; HASH_SIZE], &del).unwrap();
+        assert_eq!(DELEGATIONS.load(&storage, &[1; HASH_SIZE]).unwrap(), del);
+
+        DELEGATIONS
+            .save(&mut storage, &[2; HASH_SIZE], &del)
+            .unwrap();
+        assert_eq!(DELEGATIONS.load(&storage, &[2; HASH_SIZE]).unwrap(), del);
+        assert!(DELEGATIONS.has(&storage, &[2; HASH_SIZE]));
+        assert!(DELEGATIONS
+            .may_load(&storage, &[3; HASH_SIZE])
+            .unwrap()
+            .is_none());
+        let dels = DELEGATIONS
+            .range_raw(&storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, del)| del))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(dels, vec![del.clone(), del.clone()]);
+
+        // With compression, the proofs are stored apart
+        let stored = LEGACY.load(&storage, &[2; HASH_SIZE]).unwrap();
+        if cfg!(feature = "compression") {
+            let mut stripped = del.clone();
+            DelegationProofs::split(&mut stripped);
+            assert_eq!(stored, stripped);
+        } else {
+            assert_eq!(stored, del);
+        }
+    }
 }
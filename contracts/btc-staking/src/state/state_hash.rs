@@ -0,0 +1,74 @@
+//! state_hash defines the state sections of the contract, which the state hash is computed over
+//! (see `babylon_apis::state_hash`)
+use babylon_apis::migration::Migration;
+use babylon_apis::state_hash::{rebuild_section_hashes, StateSection, STATE_HASH_REBUILD};
+
+/// All state sections, in the order they are folded into the state hash
+pub const SECTIONS: &[StateSection] = &[
+    StateSection {
+        name: "config",
+        namespaces: &[
+            "config",
+            "params",
+            "admin",
+            "babylon_config_checked",
+            "event_verbosity",
+            "contract_info",
+        ],
+    },
+    StateSection {
+        name: "finality_providers",
+        namespaces: &[
+            "fps",
+            "fp_state",
+            "fp_state__checkpoints",
+            "fp_state__changelog",
+            "fp_state__power",
+            "fp_exits",
+            "fp_metadata",
+            "fp_jails",
+            "fp_removals",
+            "fp_deposits",
+            "activated_height",
+        ],
+    },
+    StateSection {
+        name: "delegations",
+        namespaces: &[
+            "delegations",
+            "delegation_proofs",
+            "fp_delegations",
+            "staker_delegations",
+            "delegation_fps",
+            "unbonding_intents",
+            "pending_unbondings",
+            "delegation_expiries",
+            "btc_tip_height",
+            "staker_bindings",
+            "addr_staker_bindings",
+        ],
+    },
+    StateSection {
+        name: "rewards",
+        namespaces: &[
+            "fp_rewards",
+            "staker_rewards",
+            "reward_balances",
+            "claim_grants",
+            "cw20_reward_tokens",
+            "fp_cw20_rewards",
+            "staker_cw20_rewards",
+            "cw20_reward_balances",
+            "commission_balances",
+            "commission_vestings",
+        ],
+    },
+];
+
+/// The migration rebuilding the section hashes, for contracts migrating from a version not keeping
+/// them
+pub const SECTION_HASHES_REBUILD: Migration = Migration {
+    name: STATE_HASH_REBUILD,
+    section: STATE_HASH_REBUILD,
+    step: |storage, cursor, limit| rebuild_section_hashes(storage, SECTIONS, cursor, limit),
+};
@@ -1,7 +1,7 @@
 use babylon_bitcoin::chain_params::Network;
 use test_utils::get_params;
 
-use crate::state::config::Params;
+use crate::state::config::{DuplicateFps, Params};
 
 pub fn staking_params() -> Params {
     let proto_params = get_params();
@@ -12,5 +12,17 @@ pub fn staking_params() -> Params {
         slashing_address: proto_params.slashing_address,
         min_slashing_tx_fee_sat: proto_params.min_slashing_tx_fee_sat as u64,
         slashing_rate: "0.01".to_string(), // TODO: fix this
+        btc_confirmation_depth: None,
+        min_fp_self_stake_sat: 0,
+        unbonding_intent_delay: 0,
+        fp_removal_delay: 0,
+        jail_downtime: 0,
+        max_active_finality_providers: 100,
+        reward_denom: "stake".to_string(),
+        duplicate_fps: DuplicateFps::Reject,
+        verify_staking_tx: false,
+        commission_lockup_blocks: 0,
+        fp_registration_deposit: None,
+        fp_deposit_min_stake_sat: 0,
     }
 }
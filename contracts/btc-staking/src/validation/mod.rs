@@ -1,8 +1,15 @@
 use crate::state::config::Params;
 use crate::{error::ContractError, state::staking::BtcDelegation};
-use babylon_apis::btc_staking_api::{ActiveBtcDelegation, NewFinalityProvider};
+use babylon_apis::btc_pk::{to_x_only, BtcPkHex};
+use babylon_apis::btc_staking_api::{
+    fp_metadata_msg_hash, staker_binding_msg_hash, ActiveBtcDelegation, FpMetadata,
+    NewFinalityProvider,
+};
+use babylon_bitcoin::schnorr::verify_digest as verify_schnorr_digest;
+use babylon_btcstaking::scripts_utils::BabylonScriptPaths;
 use bitcoin::Transaction;
-use cosmwasm_std::Binary;
+use cosmwasm_std::{Binary, Env};
+use k256::schnorr::VerifyingKey;
 
 #[cfg(feature = "full-validation")]
 use {
@@ -13,7 +20,7 @@ use {
     bitcoin::{consensus::deserialize, Address},
     cosmwasm_std::CanonicalAddr,
     hex::ToHex,
-    k256::schnorr::{Signature, SigningKey, VerifyingKey},
+    k256::schnorr::{Signature, SigningKey},
     k256::sha2::{Digest, Sha256},
     std::str::FromStr,
 };
@@ -52,43 +59,167 @@ fn verify_pop(
     Ok(())
 }
 
-#[cfg(feature = "full-validation")]
+/// decode_pk decodes a BTC public key in hex format, in either x-only or compressed encoding
+fn decode_pk(pk_hex: &str) -> Result<VerifyingKey, ContractError> {
+    let pk_bytes = hex::decode(pk_hex).map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
+    let pk_bytes = to_x_only(&pk_bytes)?;
+    VerifyingKey::from_bytes(&pk_bytes).map_err(|e| ContractError::SecP256K1Error(e.to_string()))
+}
+
 fn decode_pks(
     staker_pk_hex: &str,
     fp_pk_hex_list: &[String],
     cov_pk_hex_list: &[String],
 ) -> Result<(VerifyingKey, Vec<VerifyingKey>, Vec<VerifyingKey>), ContractError> {
     // get staker's public key
-    let staker_pk_bytes =
-        hex::decode(&staker_pk_hex).map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
-    let staker_pk = VerifyingKey::from_bytes(&staker_pk_bytes)
-        .map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
+    let staker_pk = decode_pk(staker_pk_hex)?;
 
     // get all FP's public keys
     let fp_pks: Vec<VerifyingKey> = fp_pk_hex_list
         .iter()
-        .map(|pk_hex| {
-            let pk_bytes =
-                hex::decode(pk_hex).map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
-            VerifyingKey::from_bytes(&pk_bytes)
-                .map_err(|e| ContractError::SecP256K1Error(e.to_string()))
-        })
+        .map(|pk_hex| decode_pk(pk_hex))
         .collect::<Result<Vec<VerifyingKey>, ContractError>>()?;
     // get all covenant members' public keys
     let cov_pks: Vec<VerifyingKey> = cov_pk_hex_list
         .iter()
-        .map(|pk_hex| {
-            let pk_bytes =
-                hex::decode(pk_hex).map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
-            VerifyingKey::from_bytes(&pk_bytes)
-                .map_err(|e| ContractError::SecP256K1Error(e.to_string()))
-        })
+        .map(|pk_hex| decode_pk(pk_hex))
         .collect::<Result<Vec<VerifyingKey>, ContractError>>()?;
 
     Ok((staker_pk, fp_pks, cov_pks))
 }
 
 /// verify_new_fp verifies the new finality provider data (full validation version)
+/// verify_staker_binding verifies the BIP-340 signature by the given BTC staker key over its
+/// binding to `staker_addr`
+pub fn verify_staker_binding(
+    env: &Env,
+    staker_addr: &str,
+    btc_pk: &BtcPkHex,
+    sig: &[u8],
+) -> Result<(), ContractError> {
+    let pk = k256::schnorr::VerifyingKey::from_bytes(&btc_pk.to_bytes())
+        .map_err(|e| ContractError::InvalidStakerBinding(e.to_string()))?;
+    let sig = k256::schnorr::Signature::try_from(sig)
+        .map_err(|e| ContractError::InvalidStakerBinding(e.to_string()))?;
+
+    let msg_hash = staker_binding_msg_hash(
+        &env.block.chain_id,
+        env.contract.address.as_str(),
+        staker_addr,
+    );
+    verify_schnorr_digest(&pk, &msg_hash, &sig)
+        .map_err(|e| ContractError::InvalidStakerBinding(e.to_string()))
+}
+
+/// verify_staking_tx checks the staking output of the staking tx against the delegation, i.e. that
+/// the output at `staking_output_idx` holds `total_sat`, and pays to the taproot script of the
+/// staker, finality provider and covenant keys with the delegation's staking time.
+/// Enabled by the `verify_staking_tx` param, as the delegations are otherwise trusted from Babylon
+pub fn verify_staking_tx(
+    params: &Params,
+    active_delegation: &ActiveBtcDelegation,
+    staking_tx: &Transaction,
+) -> Result<(), ContractError> {
+    let idx = active_delegation.staking_output_idx;
+    let staking_output = staking_tx.output.get(idx as usize).ok_or_else(|| {
+        ContractError::StakingTxMismatch(format!(
+            "staking output index {idx}, tx has {} outputs",
+            staking_tx.output.len()
+        ))
+    })?;
+    let value = staking_output.value.to_sat();
+    if value != active_delegation.total_sat {
+        return Err(ContractError::StakingTxMismatch(format!(
+            "staking output value {value} != total sat {}",
+            active_delegation.total_sat
+        )));
+    }
+
+    let staking_time = active_delegation
+        .end_height
+        .checked_sub(active_delegation.start_height)
+        .and_then(|time| u16::try_from(time).ok())
+        .ok_or_else(|| {
+            ContractError::StakingTxMismatch(format!(
+                "invalid staking time from {} to {}",
+                active_delegation.start_height, active_delegation.end_height
+            ))
+        })?;
+    let (staker_pk, fp_pks, cov_pks) = decode_pks(
+        &active_delegation.btc_pk_hex,
+        &active_delegation.fp_btc_pk_list,
+        &params.covenant_pks,
+    )?;
+    let script_paths = BabylonScriptPaths::new(
+        &staker_pk,
+        &fp_pks,
+        &cov_pks,
+        params.covenant_quorum as usize,
+        staking_time,
+    )?;
+    if script_paths.pk_script() != staking_output.script_pubkey {
+        return Err(ContractError::StakingTxMismatch(
+            "staking output script does not commit to the delegation keys and staking time"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Maximum length of the string fields of a finality provider metadata
+pub const MAX_FP_METADATA_FIELD_LEN: usize = 256;
+
+/// verify_fp_metadata checks the fields of a finality provider metadata, and verifies the BIP-340
+/// signature by the finality provider key over it
+pub fn verify_fp_metadata(
+    env: &Env,
+    fp_btc_pk: &BtcPkHex,
+    metadata: &FpMetadata,
+    sig: &[u8],
+) -> Result<(), ContractError> {
+    let invalid = |msg: &str| ContractError::InvalidFpMetadata(msg.to_string());
+    for field in [&metadata.rpc_endpoint, &metadata.version]
+        .into_iter()
+        .flatten()
+    {
+        if field.is_empty() || field.len() > MAX_FP_METADATA_FIELD_LEN {
+            return Err(invalid("empty or too long field"));
+        }
+    }
+    if let Some(rpc_endpoint) = &metadata.rpc_endpoint {
+        if !["http://", "https://", "ws://", "wss://"]
+            .iter()
+            .any(|scheme| rpc_endpoint.starts_with(scheme))
+        {
+            return Err(invalid("RPC endpoint is not an http(s) or ws(s) URL"));
+        }
+    }
+    if let Some(alert_email_hash) = &metadata.alert_email_hash {
+        if alert_email_hash.len() != 64
+            || !alert_email_hash
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        {
+            return Err(invalid(
+                "alert email hash is not a lowercase hex SHA-256 hash",
+            ));
+        }
+    }
+
+    let pk = k256::schnorr::VerifyingKey::from_bytes(&fp_btc_pk.to_bytes())
+        .map_err(|e| ContractError::InvalidFpMetadata(e.to_string()))?;
+    let sig = k256::schnorr::Signature::try_from(sig)
+        .map_err(|e| ContractError::InvalidFpMetadata(e.to_string()))?;
+    let msg_hash = fp_metadata_msg_hash(
+        &env.block.chain_id,
+        env.contract.address.as_str(),
+        fp_btc_pk.as_str(),
+        metadata,
+    )?;
+    verify_schnorr_digest(&pk, &msg_hash, &sig)
+        .map_err(|e| ContractError::InvalidFpMetadata(e.to_string()))
+}
+
 pub fn verify_new_fp(new_fp: &NewFinalityProvider) -> Result<(), ContractError> {
     // The following code is marked with `#[cfg(feature = "full-validation")]`
     // so that it is included in the build if the `full-validation` feature is
@@ -98,10 +229,7 @@ pub fn verify_new_fp(new_fp: &NewFinalityProvider) -> Result<(), ContractError>
     {
         // get FP's PK
         use babylon_apis::new_canonical_addr;
-        let fp_pk_bytes = hex::decode(&new_fp.btc_pk_hex)
-            .map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
-        let fp_pk = VerifyingKey::from_bytes(&fp_pk_bytes)
-            .map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
+        let fp_pk = decode_pk(&new_fp.btc_pk_hex)?;
 
         // get canonicalised FP address
         // TODO: parameterise `bbn` prefix
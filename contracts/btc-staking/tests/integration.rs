@@ -36,6 +36,7 @@ fn instantiate_works() {
     let msg = InstantiateMsg {
         params: None,
         admin: None,
+        genesis: None,
     };
     let info = mock_info(CREATOR, &[]);
     let res: ContractResult<Response> = instantiate(&mut deps, mock_env(), info, msg);
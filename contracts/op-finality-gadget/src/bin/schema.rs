@@ -1,12 +1,11 @@
-use cosmwasm_schema::write_api;
-use cosmwasm_std::Empty;
-use op_finality_gadget::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use std::env::current_dir;
+
+use babylon_apis::schema::write_schema_files;
 
 fn main() {
-    write_api! {
-        instantiate: InstantiateMsg,
-        query: QueryMsg,
-        migrate: Empty,
-        execute: ExecuteMsg,
-    }
+    // Clear & write standard API
+    write_schema_files(
+        op_finality_gadget::schema::api(),
+        &current_dir().unwrap().join("schema"),
+    );
 }
@@ -1,15 +1,33 @@
-use crate::error::ContractError;
-use crate::exec::admin::set_enabled;
+use crate::error::{ContractError, ERROR_CODES};
+use crate::exec::admin::{
+    accept_admin, set_enabled, set_min_vote_payload_version, set_mode, update_admin,
+};
 use crate::exec::finality::{handle_finality_signature, handle_public_randomness_commit};
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::exec::mirror::{handle_mirror_finality_signature, handle_mirror_public_randomness};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::queries::{
-    query_block_voters, query_config, query_first_pub_rand_commit, query_last_pub_rand_commit,
+    query_block_attestation, query_block_voters, query_config, query_evidence,
+    query_first_pub_rand_commit, query_last_pub_rand_commit, query_pending_admin,
+    query_pub_rand_commit_epoch,
 };
-use crate::state::config::{Config, ADMIN, CONFIG, IS_ENABLED};
+use crate::state::config::{get_mode, Config, ADMIN, CONFIG, IS_ENABLED, MODE};
+use crate::state::state_hash::{SECTIONS, SECTION_HASHES_REBUILD};
+use babylon_apis::contract_version::ensure_upgrade;
+use babylon_apis::error::ErrorCodesResponse;
+use babylon_apis::finality_api::{PubRandCommit, SigningContext};
+use babylon_apis::migration::{self, Migration, MigrationProgressResponse};
+use babylon_apis::state_hash::{get_state_hash, init_section_hashes};
 use cosmwasm_std::{
-    to_json_binary, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response, StdResult,
+    to_json_binary, Deps, DepsMut, Env, Event, MessageInfo, QueryResponse, Response, StdError,
+    StdResult,
 };
-use cw_controllers::AdminError;
+use cw2::set_contract_version;
+
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The storage migrations of the current contract version, in order
+const MIGRATIONS: &[Migration] = &[SECTION_HASHES_REBUILD];
 
 pub fn instantiate(
     mut deps: DepsMut,
@@ -20,19 +38,44 @@ pub fn instantiate(
     let api = deps.api;
     ADMIN.set(deps.branch(), Some(api.addr_validate(&msg.admin)?))?;
     IS_ENABLED.save(deps.storage, &msg.is_enabled)?;
+    MODE.save(deps.storage, &msg.mode.unwrap_or_default())?;
 
+    let signing_context_version = msg.signing_context_version.unwrap_or_default();
+    SigningContext::validate_version(signing_context_version).map_err(StdError::generic_err)?;
     let config = Config {
         consumer_id: msg.consumer_id,
+        signing_context_version,
+        min_vote_payload_version: msg.min_vote_payload_version.unwrap_or_default(),
     };
     CONFIG.save(deps.storage, &config)?;
 
+    init_section_hashes(deps.storage)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new().add_attribute("action", "instantiate"))
 }
 
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
+/// migrate migrates the contract to this version of its code, refusing downgrades.
+/// Deployments predating contract versioning are versioned. State added since (e.g. the operating
+/// mode, or the epochs of the public randomness commitments) is optional, so only the section
+/// hashes are rebuilt, in chunks through `ContinueMigration`
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = ensure_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let scheduled = migration::schedule_migrations(deps.storage, MIGRATIONS)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version.unwrap_or_default())
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("scheduled_migrations", scheduled.join(",")))
+}
+
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<QueryResponse, ContractError> {
     match msg {
+        QueryMsg::BlockAttestation { height, hash } => Ok(to_json_binary(
+            &query_block_attestation(deps, &env, height, hash)?,
+        )?),
         QueryMsg::Config {} => Ok(to_json_binary(&query_config(deps)?)?),
         QueryMsg::Admin {} => Ok(to_json_binary(&ADMIN.query_admin(deps)?)?),
+        QueryMsg::PendingAdmin {} => Ok(to_json_binary(&query_pending_admin(deps)?)?),
         QueryMsg::BlockVoters { height, hash } => {
             Ok(to_json_binary(&query_block_voters(deps, height, hash)?)?)
         }
@@ -42,7 +85,29 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<QueryResponse, Cont
         QueryMsg::LastPubRandCommit { btc_pk_hex } => Ok(to_json_binary(
             &query_last_pub_rand_commit(deps.storage, &btc_pk_hex)?,
         )?),
+        QueryMsg::PubRandCommitEpoch {
+            btc_pk_hex,
+            start_height,
+        } => Ok(to_json_binary(&query_pub_rand_commit_epoch(
+            deps.storage,
+            &btc_pk_hex,
+            start_height,
+        )?)?),
+        QueryMsg::Evidence {
+            fp_pubkey_hex,
+            height,
+        } => Ok(to_json_binary(&query_evidence(
+            deps.storage,
+            &fp_pubkey_hex,
+            height,
+        )?)?),
         QueryMsg::IsEnabled {} => Ok(to_json_binary(&IS_ENABLED.load(deps.storage)?)?),
+        QueryMsg::Mode {} => Ok(to_json_binary(&get_mode(deps.storage)?)?),
+        QueryMsg::ErrorCodes {} => Ok(to_json_binary(&ErrorCodesResponse::from(ERROR_CODES))?),
+        QueryMsg::StateHash {} => Ok(to_json_binary(&get_state_hash(deps.storage, SECTIONS)?)?),
+        QueryMsg::MigrationProgress {} => Ok(to_json_binary(&MigrationProgressResponse {
+            migrations: migration::get_migration_progress(deps.storage)?,
+        })?),
     }
 }
 
@@ -52,8 +117,6 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    let api = deps.api;
-
     match msg {
         ExecuteMsg::CommitPublicRandomness {
             fp_pubkey_hex,
@@ -63,6 +126,7 @@ pub fn execute(
             signature,
         } => handle_public_randomness_commit(
             deps,
+            &env,
             &fp_pubkey_hex,
             start_height,
             num_pub_rand,
@@ -76,6 +140,7 @@ pub fn execute(
             proof,
             block_hash,
             signature,
+            payload_version,
         } => handle_finality_signature(
             deps,
             env,
@@ -85,14 +150,58 @@ pub fn execute(
             &proof,
             &block_hash,
             &signature,
+            payload_version.unwrap_or_default(),
         ),
         ExecuteMsg::SetEnabled { enabled } => set_enabled(deps, info, enabled),
-        ExecuteMsg::UpdateAdmin { admin } => ADMIN
-            .execute_update_admin(deps, info, Some(api.addr_validate(&admin)?))
-            .map_err(|err| match err {
-                AdminError::Std(e) => ContractError::StdError(e),
-                AdminError::NotAdmin {} => ContractError::Unauthorized,
-            }),
+        ExecuteMsg::SetMode { mode } => set_mode(deps, info, mode),
+        ExecuteMsg::SetMinVotePayloadVersion { version } => {
+            set_min_vote_payload_version(deps, info, version)
+        }
+        ExecuteMsg::MirrorPublicRandomness {
+            fp_pubkey_hex,
+            start_height,
+            num_pub_rand,
+            commitment,
+        } => handle_mirror_public_randomness(
+            deps,
+            info,
+            &fp_pubkey_hex,
+            PubRandCommit {
+                start_height,
+                num_pub_rand,
+                commitment: commitment.to_vec(),
+            },
+        ),
+        ExecuteMsg::MirrorFinalitySignature {
+            fp_pubkey_hex,
+            height,
+            pub_rand,
+            block_hash,
+            signature,
+        } => handle_mirror_finality_signature(
+            deps,
+            info,
+            &fp_pubkey_hex,
+            height,
+            &pub_rand,
+            &block_hash,
+            &signature,
+        ),
+        ExecuteMsg::UpdateAdmin { admin } => update_admin(deps, info, admin),
+        ExecuteMsg::AcceptAdmin {} => accept_admin(deps, info),
+        ExecuteMsg::ContinueMigration { limit } => {
+            let progress = migration::continue_migration(deps.storage, MIGRATIONS, limit as usize)?;
+            let mut event = Event::new("migration_progress");
+            event = match progress {
+                Some(progress) => event
+                    .add_attribute("name", progress.name)
+                    .add_attribute("section", progress.section)
+                    .add_attribute("migrated", progress.migrated.to_string())
+                    .add_attribute("complete", progress.complete.to_string()),
+                None => event.add_attribute("pending", "false"),
+            };
+            Ok(Response::new().add_event(event))
+        }
     }
 }
 
@@ -101,6 +210,8 @@ pub fn execute(
 pub(crate) mod tests {
     use super::*;
 
+    use crate::state::config::Mode;
+    use babylon_apis::btc_pk::BtcPkHex;
     use cosmwasm_std::{
         from_json,
         testing::{message_info, mock_dependencies, mock_env},
@@ -122,6 +233,9 @@ pub(crate) mod tests {
             admin: init_admin.to_string(),
             consumer_id,
             is_enabled: true,
+            signing_context_version: None,
+            min_vote_payload_version: None,
+            mode: None,
         };
 
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
@@ -151,6 +265,9 @@ pub(crate) mod tests {
             admin: init_admin.to_string(), // Admin provided
             consumer_id: "op-stack-l2-11155420".to_string(),
             is_enabled: true,
+            signing_context_version: None,
+            min_vote_payload_version: None,
+            mode: None,
         };
 
         let info = message_info(&deps.api.addr_make(CREATOR), &[]);
@@ -164,9 +281,9 @@ pub(crate) mod tests {
         // Use assert_admin to verify that the admin was set correctly
         ADMIN.assert_admin(deps.as_ref(), &init_admin).unwrap();
 
-        // Update the admin to new_admin
+        // Propose new_admin as the admin
         let update_admin_msg = ExecuteMsg::UpdateAdmin {
-            admin: new_admin.to_string(),
+            admin: Some(new_admin.to_string()),
         };
 
         // Execute the UpdateAdmin message with non-admin info
@@ -174,7 +291,7 @@ pub(crate) mod tests {
         let err = execute(
             deps.as_mut(),
             mock_env(),
-            non_admin_info,
+            non_admin_info.clone(),
             update_admin_msg.clone(),
         )
         .unwrap_err();
@@ -187,7 +304,253 @@ pub(crate) mod tests {
         // Assert that no messages were sent
         assert_eq!(0, res.messages.len());
 
+        // The admin is only updated once new_admin accepts the transfer
+        ADMIN.assert_admin(deps.as_ref(), &init_admin).unwrap();
+        let pending: AdminResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::PendingAdmin {}).unwrap())
+                .unwrap();
+        assert_eq!(pending.admin.unwrap(), new_admin.as_str());
+
+        // Only the pending admin can accept the transfer
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            non_admin_info,
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotPendingAdmin);
+
+        let new_admin_info = message_info(&new_admin, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            new_admin_info.clone(),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap();
+
         // Use assert_admin to verify that the admin was updated correctly
         ADMIN.assert_admin(deps.as_ref(), &new_admin).unwrap();
+        let pending: AdminResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::PendingAdmin {}).unwrap())
+                .unwrap();
+        assert_eq!(pending.admin, None);
+
+        // A pending proposal can be cancelled
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            new_admin_info,
+            ExecuteMsg::UpdateAdmin {
+                admin: Some(init_admin.to_string()),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&new_admin, &[]),
+            ExecuteMsg::UpdateAdmin { admin: None },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&init_admin, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotPendingAdmin);
+        ADMIN.assert_admin(deps.as_ref(), &new_admin).unwrap();
+    }
+
+    #[test]
+    fn passive_mirror_mode_works() {
+        let mut deps = mock_dependencies();
+        let admin = deps.api.addr_make(INIT_ADMIN);
+        let admin_info = message_info(&admin, &[]);
+        let non_admin_info = message_info(&deps.api.addr_make("non_admin"), &[]);
+
+        let msg = InstantiateMsg {
+            admin: admin.to_string(),
+            consumer_id: "op-stack-l2-11155420".to_string(),
+            is_enabled: true,
+            signing_context_version: None,
+            min_vote_payload_version: None,
+            mode: Some(Mode::Passive),
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let mode: Mode =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Mode {}).unwrap()).unwrap();
+        assert_eq!(mode, Mode::Passive);
+
+        // Finality providers cannot submit to a mirror
+        let fp_pubkey_hex: BtcPkHex = "02".repeat(32).parse().unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            non_admin_info.clone(),
+            ExecuteMsg::CommitPublicRandomness {
+                fp_pubkey_hex: fp_pubkey_hex.clone(),
+                start_height: 1,
+                num_pub_rand: 10,
+                commitment: vec![1u8; 32].into(),
+                signature: vec![1u8; 64].into(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::PassiveMode);
+
+        // Only the admin can relay data
+        let mirror_commit = ExecuteMsg::MirrorPublicRandomness {
+            fp_pubkey_hex: fp_pubkey_hex.clone(),
+            start_height: 1,
+            num_pub_rand: 10,
+            commitment: vec![1u8; 32].into(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            non_admin_info.clone(),
+            mirror_commit.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), mock_env(), admin_info.clone(), mirror_commit).unwrap();
+
+        let block_hash = vec![3u8; 32];
+        let mirror_sig = ExecuteMsg::MirrorFinalitySignature {
+            fp_pubkey_hex: fp_pubkey_hex.clone(),
+            height: 5,
+            pub_rand: vec![2u8; 32].into(),
+            block_hash: block_hash.clone().into(),
+            signature: vec![4u8; 32].into(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin_info.clone(),
+            mirror_sig.clone(),
+        )
+        .unwrap();
+
+        // The relayed data is served by queries
+        let commit: Option<PubRandCommit> = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::LastPubRandCommit {
+                    btc_pk_hex: fp_pubkey_hex.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(commit.unwrap().num_pub_rand, 10);
+        let voters: Option<std::collections::HashSet<String>> = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::BlockVoters {
+                    height: 5,
+                    hash: hex::encode(&block_hash),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(voters.unwrap().contains(fp_pubkey_hex.as_str()));
+
+        // Only the admin can switch modes
+        let set_active = ExecuteMsg::SetMode { mode: Mode::Active };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            non_admin_info,
+            set_active.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), mock_env(), admin_info.clone(), set_active).unwrap();
+
+        // Active deployments do not accept relayed data
+        let err = execute(deps.as_mut(), mock_env(), admin_info, mirror_sig).unwrap_err();
+        assert_eq!(err, ContractError::NotPassiveMode);
+    }
+
+    #[test]
+    fn min_vote_payload_version_works() {
+        let mut deps = mock_dependencies();
+        let admin = deps.api.addr_make(INIT_ADMIN);
+        let admin_info = message_info(&admin, &[]);
+        let non_admin_info = message_info(&deps.api.addr_make("non_admin"), &[]);
+
+        let msg = InstantiateMsg {
+            admin: admin.to_string(),
+            consumer_id: "op-stack-l2-11155420".to_string(),
+            is_enabled: true,
+            signing_context_version: None,
+            min_vote_payload_version: Some(1),
+            mode: None,
+        };
+        let info = message_info(&deps.api.addr_make(CREATOR), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Only the admin can raise the minimum version
+        let set_min_version = ExecuteMsg::SetMinVotePayloadVersion { version: 2 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            non_admin_info.clone(),
+            set_min_version.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        execute(deps.as_mut(), mock_env(), admin_info, set_min_version).unwrap();
+        let config: Config =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.min_vote_payload_version, 2);
+
+        let submit = |payload_version| ExecuteMsg::SubmitFinalitySignature {
+            fp_pubkey_hex: "02".repeat(32).parse().unwrap(),
+            height: 1,
+            pub_rand: vec![1u8; 32].into(),
+            proof: babylon_merkle::Proof {
+                total: 1,
+                index: 0,
+                leaf_hash: vec![2u8; 32].into(),
+                aunts: vec![],
+            },
+            block_hash: vec![3u8; 32].into(),
+            signature: vec![4u8; 32].into(),
+            payload_version,
+        };
+
+        // Daemons predating the handshake, or on an outdated version, are told what to upgrade to
+        for (payload_version, version) in [(None, 0), (Some(1), 1)] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                non_admin_info.clone(),
+                submit(payload_version),
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::OutdatedVotePayloadVersion {
+                    version,
+                    min_version: 2
+                }
+            );
+        }
+
+        // Up-to-date submissions go through to the regular processing
+        let err = execute(deps.as_mut(), mock_env(), non_admin_info, submit(Some(2))).unwrap_err();
+        assert!(!matches!(
+            err,
+            ContractError::OutdatedVotePayloadVersion { .. }
+        ));
     }
 }
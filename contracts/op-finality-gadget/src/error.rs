@@ -1,3 +1,4 @@
+use babylon_apis::error::{ErrorCategory, ErrorCode};
 use babylon_merkle::error::MerkleError;
 use cosmwasm_std::StdError;
 use hex::FromHexError;
@@ -40,8 +41,188 @@ pub enum ContractError {
     FailedFetchVotingPower(String),
     #[error("Caller is not the admin")]
     Unauthorized,
+    #[error("Caller is not the pending admin")]
+    NotPendingAdmin,
     #[error("Finality gadget is already enabled")]
     AlreadyEnabled,
     #[error("Finality gadget is already disabled")]
     AlreadyDisabled,
+    #[error("Finality gadget is in passive mirror mode")]
+    PassiveMode,
+    #[error("Finality gadget is not in passive mirror mode")]
+    NotPassiveMode,
+    #[error("The public randomness commitment of finality provider {0} at height {1} was made in epoch {2}, which is not BTC-finalised yet")]
+    PubRandCommitNotTimestamped(String, u64, u64),
+    #[error("Vote payload version {version} is no longer supported: upgrade the finality provider to vote payload version {min_version} or later")]
+    OutdatedVotePayloadVersion { version: u32, min_version: u32 },
+}
+
+impl ContractError {
+    /// code returns the stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            ContractError::EmptySignature => 1001,
+            ContractError::EotsError(..) => 1002,
+            ContractError::FailedSignatureVerification(..) => 1003,
+            ContractError::HeightTooHigh => 901,
+            ContractError::HexError(..) => 101,
+            ContractError::InvalidFinalitySigHeight(..) => 902,
+            ContractError::InvalidFinalitySigAmount(..) => 903,
+            ContractError::InvalidPubRandHeight(..) => 904,
+            ContractError::InvalidSignature(..) => 1004,
+            ContractError::MerkleError(..) => 1005,
+            ContractError::MissingPubRandCommit(..) => 905,
+            ContractError::SecP256K1Error(..) => 1006,
+            ContractError::StdError(..) => 102,
+            ContractError::QueryBlockVoterError(..) => 906,
+            ContractError::NotFoundFinalityProvider(..) => 801,
+            ContractError::FailedFetchVotingPower(..) => 907,
+            ContractError::Unauthorized => 201,
+            ContractError::NotPendingAdmin => 202,
+            ContractError::AlreadyEnabled => 401,
+            ContractError::AlreadyDisabled => 402,
+            ContractError::PassiveMode => 403,
+            ContractError::NotPassiveMode => 404,
+            ContractError::PubRandCommitNotTimestamped(..) => 908,
+            ContractError::OutdatedVotePayloadVersion { .. } => 909,
+        }
+    }
+}
+
+/// ERROR_CODES is the table of error codes of the contract
+pub const ERROR_CODES: &[ErrorCode] = &[
+        ErrorCode {
+            code: 101,
+            category: ErrorCategory::Std,
+            description: "The hex cannot be decoded",
+        },
+        ErrorCode {
+            code: 102,
+            category: ErrorCategory::Std,
+            description: "Generic error",
+        },
+        ErrorCode {
+            code: 201,
+            category: ErrorCategory::Auth,
+            description: "Caller is not the admin",
+        },
+        ErrorCode {
+            code: 202,
+            category: ErrorCategory::Auth,
+            description: "Caller is not the pending admin",
+        },
+        ErrorCode {
+            code: 401,
+            category: ErrorCategory::Config,
+            description: "Finality gadget is already enabled",
+        },
+        ErrorCode {
+            code: 402,
+            category: ErrorCategory::Config,
+            description: "Finality gadget is already disabled",
+        },
+        ErrorCode {
+            code: 403,
+            category: ErrorCategory::Config,
+            description: "Finality gadget is in passive mirror mode",
+        },
+        ErrorCode {
+            code: 404,
+            category: ErrorCategory::Config,
+            description: "Finality gadget is not in passive mirror mode",
+        },
+        ErrorCode {
+            code: 801,
+            category: ErrorCategory::Staking,
+            description: "Finality provider not found for consumer",
+        },
+        ErrorCode {
+            code: 901,
+            category: ErrorCategory::Finality,
+            description: "The chain has not reached the given height yet",
+        },
+        ErrorCode {
+            code: 902,
+            category: ErrorCategory::Finality,
+            description: "The inclusion proof for height does not correspond to the given height",
+        },
+        ErrorCode {
+            code: 903,
+            category: ErrorCategory::Finality,
+            description: "The total amount of public randomnesses in the proof does not match the amount of public committed randomness",
+        },
+        ErrorCode {
+            code: 904,
+            category: ErrorCategory::Finality,
+            description: "The start height has overlap with the height of the highest public randomness committed",
+        },
+        ErrorCode {
+            code: 905,
+            category: ErrorCategory::Finality,
+            description: "Public randomness not found for finality provider at the height",
+        },
+        ErrorCode {
+            code: 906,
+            category: ErrorCategory::Finality,
+            description: "Failed to query block voters for block",
+        },
+        ErrorCode {
+            code: 907,
+            category: ErrorCategory::Finality,
+            description: "Failed to query the voting power of the finality provider",
+        },
+        ErrorCode {
+            code: 908,
+            category: ErrorCategory::Finality,
+            description: "The public randomness commitment of finality provider is not BTC-finalised yet",
+        },
+        ErrorCode {
+            code: 909,
+            category: ErrorCategory::Finality,
+            description: "Vote payload version is no longer supported",
+        },
+        ErrorCode {
+            code: 1001,
+            category: ErrorCategory::Crypto,
+            description: "Empty signature from the delegator",
+        },
+        ErrorCode {
+            code: 1002,
+            category: ErrorCategory::Crypto,
+            description: "EOTS error",
+        },
+        ErrorCode {
+            code: 1003,
+            category: ErrorCategory::Crypto,
+            description: "Failed to verify signature",
+        },
+        ErrorCode {
+            code: 1004,
+            category: ErrorCategory::Crypto,
+            description: "Invalid finality signature",
+        },
+        ErrorCode {
+            code: 1005,
+            category: ErrorCategory::Crypto,
+            description: "Merkle proof error",
+        },
+        ErrorCode {
+            code: 1006,
+            category: ErrorCategory::Crypto,
+            description: "Invalid secp256k1 key or signature",
+        },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use babylon_apis::error::{check_error_codes, validate_error_codes};
+
+    #[test]
+    fn error_codes_are_valid() {
+        validate_error_codes(ERROR_CODES).unwrap();
+
+        // Every variant has a code in the table, described after its message
+        check_error_codes(include_str!("error.rs"), &["ContractError"], ERROR_CODES).unwrap();
+    }
 }
@@ -1,8 +1,8 @@
-use cosmwasm_std::{DepsMut, MessageInfo, Response};
+use cosmwasm_std::{DepsMut, Event, MessageInfo, Response};
 
 use crate::{
     error::ContractError,
-    state::config::{ADMIN, IS_ENABLED},
+    state::config::{Mode, ADMIN, CONFIG, IS_ENABLED, MODE, PENDING_ADMIN},
 };
 
 // Enable or disable the finality gadget.
@@ -30,8 +30,77 @@ pub fn set_enabled(
     Result::Ok(Response::default())
 }
 
+// Switch the finality gadget between active and passive mirror mode.
+// Only callable by contract admin.
+// In passive mode, the finality gadget only records the data relayed by the admin from a primary
+// deployment, without validating it. Switching to active mode makes it enforce validation again.
+pub fn set_mode(deps: DepsMut, info: MessageInfo, mode: Mode) -> Result<Response, ContractError> {
+    check_admin(&deps, info)?;
+    MODE.save(deps.storage, &mode)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_mode")
+        .add_attribute("mode", format!("{mode:?}").to_lowercase()))
+}
+
+// Set the minimum vote payload version finality signature submissions must declare.
+// Only callable by contract admin.
+// Finality providers still on an older version are rejected, and told which version to upgrade to.
+pub fn set_min_vote_payload_version(
+    deps: DepsMut,
+    info: MessageInfo,
+    version: u32,
+) -> Result<Response, ContractError> {
+    check_admin(&deps, info)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_version = config.min_vote_payload_version;
+    config.min_vote_payload_version = version;
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_min_vote_payload_version")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("version", version.to_string()))
+}
+
+// Propose a new contract admin, or cancel the pending proposal if `admin` is not set.
+// Only callable by contract admin.
+// The proposed admin only takes over once it accepts the transfer, so that control cannot be
+// handed over to a wrong or unusable address.
+pub fn update_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: Option<String>,
+) -> Result<Response, ContractError> {
+    check_admin(&deps, info)?;
+    let mut event = Event::new("update_admin");
+    match admin {
+        Some(admin) => {
+            let admin = deps.api.addr_validate(&admin)?;
+            PENDING_ADMIN.save(deps.storage, &admin)?;
+            event = event.add_attribute("pending_admin", admin);
+        }
+        None => PENDING_ADMIN.remove(deps.storage),
+    }
+    Ok(Response::new().add_event(event))
+}
+
+// Accept the admin transfer proposed by the current contract admin.
+// Only callable by the pending admin.
+pub fn accept_admin(mut deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if PENDING_ADMIN.may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::NotPendingAdmin);
+    }
+    let previous_admin = ADMIN.get(deps.as_ref())?;
+    ADMIN.set(deps.branch(), Some(info.sender.clone()))?;
+    PENDING_ADMIN.remove(deps.storage);
+    let mut event = Event::new("accept_admin").add_attribute("admin", info.sender);
+    if let Some(previous_admin) = previous_admin {
+        event = event.add_attribute("previous_admin", previous_admin);
+    }
+    Ok(Response::new().add_event(event))
+}
+
 // Helper function to check caller is contract admin
-fn check_admin(deps: &DepsMut, info: MessageInfo) -> Result<(), ContractError> {
+pub(crate) fn check_admin(deps: &DepsMut, info: MessageInfo) -> Result<(), ContractError> {
     // Check caller is admin
     if !ADMIN.is_admin(deps.as_ref(), &info.sender)? {
         return Err(ContractError::Unauthorized {});
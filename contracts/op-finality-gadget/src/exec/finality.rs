@@ -2,16 +2,16 @@ use std::collections::HashSet;
 
 use crate::error::ContractError;
 use crate::queries::query_last_pub_rand_commit;
-use crate::state::config::CONFIG;
-use crate::state::finality::{BLOCK_VOTES, SIGNATURES};
+use crate::state::config::{get_mode, Mode, CONFIG};
+use crate::state::finality::{BLOCK_VOTES, EVIDENCES, SIGNATURES};
 use crate::state::public_randomness::{
-    get_pub_rand_commit_for_height, PUB_RAND_COMMITS, PUB_RAND_VALUES,
+    get_pub_rand_commit_for_height, PUB_RAND_COMMITS, PUB_RAND_COMMIT_EPOCHS, PUB_RAND_VALUES,
 };
-use crate::utils::query_finality_provider;
+use crate::utils::{query_current_epoch, query_finality_provider, query_last_finalized_epoch};
 
-use babylon_apis::finality_api::PubRandCommit;
+use babylon_apis::finality_api::{Evidence, PubRandCommit, SigningContext};
 use babylon_merkle::Proof;
-use cosmwasm_std::{Deps, DepsMut, Env, Event, Response};
+use cosmwasm_std::{Deps, DepsMut, Env, Event, Order, Response, StdError, StdResult, Storage};
 use k256::ecdsa::signature::Verifier;
 use k256::schnorr::{Signature, VerifyingKey};
 use k256::sha2::{Digest, Sha256};
@@ -19,19 +19,24 @@ use k256::sha2::{Digest, Sha256};
 // Most logic copied from contracts/btc-staking/src/finality.rs
 pub fn handle_public_randomness_commit(
     deps: DepsMut,
+    env: &Env,
     fp_pubkey_hex: &str,
     start_height: u64,
     num_pub_rand: u64,
     commitment: &[u8],
     signature: &[u8],
 ) -> Result<Response, ContractError> {
+    ensure_active(deps.storage)?;
+
     // Ensure the finality provider is registered
     check_fp_exist(deps.as_ref(), fp_pubkey_hex)?;
 
     // TODO: ensure log_2(num_pub_rand) is an integer?
 
     // Verify signature over the list
+    let ctx = signing_context(deps.storage, env)?;
     verify_commitment_signature(
+        &ctx,
         fp_pubkey_hex,
         start_height,
         num_pub_rand,
@@ -66,17 +71,70 @@ pub fn handle_public_randomness_commit(
         (fp_pubkey_hex, pr_commit.start_height),
         &pr_commit,
     )?;
+    // Record the epoch it's made in, so that it's only used once BTC-timestamped
+    let epoch = query_current_epoch(deps.as_ref())?;
+    PUB_RAND_COMMIT_EPOCHS.save(deps.storage, (fp_pubkey_hex, start_height), &epoch)?;
 
     let event = Event::new("public_randomness_commit")
         .add_attribute("fp_pubkey_hex", fp_pubkey_hex)
         .add_attribute("pr_commit.start_height", pr_commit.start_height.to_string())
-        .add_attribute("pr_commit.num_pub_rand", pr_commit.num_pub_rand.to_string());
+        .add_attribute("pr_commit.num_pub_rand", pr_commit.num_pub_rand.to_string())
+        .add_attribute("pr_commit.epoch", epoch.to_string());
 
     Ok(Response::new().add_event(event))
 }
 
+/// `ensure_pub_rand_timestamped` rejects public randomness commitments made in an epoch that is
+/// not BTC-finalised yet, i.e. after `last_finalized_epoch`.
+/// Commitments whose epoch wasn't recorded predate the check, and are accepted
+pub(crate) fn ensure_pub_rand_timestamped(
+    deps: Deps,
+    fp_btc_pk_hex: &str,
+    pr_commit: &PubRandCommit,
+    last_finalized_epoch: impl FnOnce() -> StdResult<u64>,
+) -> Result<(), ContractError> {
+    let Some(epoch) =
+        PUB_RAND_COMMIT_EPOCHS.may_load(deps.storage, (fp_btc_pk_hex, pr_commit.start_height))?
+    else {
+        return Ok(());
+    };
+    if epoch > last_finalized_epoch()? {
+        return Err(ContractError::PubRandCommitNotTimestamped(
+            fp_btc_pk_hex.to_string(),
+            pr_commit.start_height,
+            epoch,
+        ));
+    }
+    Ok(())
+}
+
+/// `ensure_active` rejects finality provider submissions while in passive mirror mode, as the
+/// data of a mirror only comes from its primary deployment
+fn ensure_active(storage: &dyn Storage) -> Result<(), ContractError> {
+    match get_mode(storage)? {
+        Mode::Active => Ok(()),
+        Mode::Passive => Err(ContractError::PassiveMode),
+    }
+}
+
+/// `signing_context` returns the signing context of finality provider signatures submitted to
+/// this contract
+pub(crate) fn signing_context(
+    storage: &dyn Storage,
+    env: &Env,
+) -> Result<SigningContext, ContractError> {
+    let config = CONFIG.load(storage)?;
+    Ok(SigningContext {
+        version: config.signing_context_version,
+        chain_id: env.block.chain_id.clone(),
+        contract_address: env.contract.address.to_string(),
+        consumer_id: config.consumer_id,
+    })
+}
+
 // Copied from contracts/btc-staking/src/finality.rs
 pub(crate) fn verify_commitment_signature(
+    ctx: &SigningContext,
     fp_btc_pk_hex: &str,
     start_height: u64,
     num_pub_rand: u64,
@@ -96,10 +154,7 @@ pub(crate) fn verify_commitment_signature(
         Signature::try_from(signature).map_err(|e| ContractError::SecP256K1Error(e.to_string()))?;
 
     // get signed message
-    let mut msg: Vec<u8> = vec![];
-    msg.extend_from_slice(&start_height.to_be_bytes());
-    msg.extend_from_slice(&num_pub_rand.to_be_bytes());
-    msg.extend_from_slice(commitment);
+    let msg = ctx.commit_pub_rand_msg(start_height, num_pub_rand, commitment);
 
     // Verify the signature
     btc_pk
@@ -111,14 +166,26 @@ pub(crate) fn verify_commitment_signature(
 #[allow(clippy::too_many_arguments)]
 pub fn handle_finality_signature(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     fp_btc_pk_hex: &str,
     height: u64,
     pub_rand: &[u8],
     proof: &Proof,
     block_hash: &[u8],
     signature: &[u8],
+    payload_version: u32,
 ) -> Result<Response, ContractError> {
+    ensure_active(deps.storage)?;
+
+    // Ensure the finality provider daemon speaks a supported vote payload version
+    let min_version = CONFIG.load(deps.storage)?.min_vote_payload_version;
+    if payload_version < min_version {
+        return Err(ContractError::OutdatedVotePayloadVersion {
+            version: payload_version,
+            min_version,
+        });
+    }
+
     // Ensure the finality provider exists
     check_fp_exist(deps.as_ref(), fp_btc_pk_hex)?;
 
@@ -151,7 +218,7 @@ pub fn handle_finality_signature(
 
     // Ensure the finality provider has not cast the same vote yet
     let existing_sig = SIGNATURES.may_load(deps.storage, (height, fp_btc_pk_hex))?;
-    match existing_sig {
+    match &existing_sig {
         Some(existing_sig) if existing_sig == signature => {
             deps.api.debug(&format!("Received duplicated finality vote. Height: {height}, Finality Provider: {fp_btc_pk_hex}"));
             // Exactly the same vote already exists, return success to the provider
@@ -162,9 +229,15 @@ pub fn handle_finality_signature(
 
     // Find the public randomness commitment for this height from this finality provider
     let pr_commit = get_pub_rand_commit_for_height(deps.storage, fp_btc_pk_hex, height)?;
+    // Ensure the commitment is BTC-timestamped
+    ensure_pub_rand_timestamped(deps.as_ref(), fp_btc_pk_hex, &pr_commit, || {
+        query_last_finalized_epoch(deps.as_ref())
+    })?;
 
     // Verify the finality signature message
+    let ctx = signing_context(deps.storage, &env)?;
     verify_finality_signature(
+        &ctx,
         fp_btc_pk_hex,
         height,
         pub_rand,
@@ -178,6 +251,24 @@ pub fn handle_finality_signature(
     // TODO?: Don't save public randomness values, to save storage space
     PUB_RAND_VALUES.save(deps.storage, (fp_btc_pk_hex, height), &pub_rand.to_vec())?;
 
+    // A different signature at the same height is a vote for another block, i.e. an equivocation
+    if let Some(existing_sig) = existing_sig {
+        let res = handle_equivocation(
+            deps.storage,
+            &ctx,
+            fp_btc_pk_hex,
+            height,
+            pub_rand,
+            &existing_sig,
+            block_hash,
+            signature,
+        )?
+        .map_or_else(Response::new, |event| Response::new().add_event(event));
+        // NOTE: We should NOT return error here, otherwise the state change triggered in this tx
+        // (including the evidence) will be rolled back
+        return Ok(res);
+    }
+
     // TODO: Verify whether the voted block is a fork or not
     /*
     indexedBlock, err := ms.GetBlock(ctx, req.BlockHeight)
@@ -218,18 +309,7 @@ pub fn handle_finality_signature(
     */
 
     // This signature is good, save the vote to the store
-    SIGNATURES.save(deps.storage, (height, fp_btc_pk_hex), &signature.to_vec())?;
-
-    // Check if the key (height, block_hash) exists
-    let mut block_votes_fp_set = BLOCK_VOTES
-        .may_load(deps.storage, (height, block_hash))?
-        .unwrap_or_else(HashSet::new);
-
-    // Add the fp_btc_pk_hex to the set
-    block_votes_fp_set.insert(fp_btc_pk_hex.to_string());
-
-    // Save the updated set back to storage
-    BLOCK_VOTES.save(deps.storage, (height, block_hash), &block_votes_fp_set)?;
+    save_vote(deps.storage, fp_btc_pk_hex, height, block_hash, signature)?;
 
     // TODO: If this finality provider has signed the canonical block before, slash it via
     // extracting its secret key, and emit an event
@@ -262,10 +342,113 @@ pub fn handle_finality_signature(
     Ok(Response::new().add_event(event))
 }
 
+/// `save_vote` records the finality signature of the finality provider, and its vote for the
+/// (height, block hash) pair
+pub(crate) fn save_vote(
+    storage: &mut dyn Storage,
+    fp_btc_pk_hex: &str,
+    height: u64,
+    block_hash: &[u8],
+    signature: &[u8],
+) -> Result<(), ContractError> {
+    SIGNATURES.save(storage, (height, fp_btc_pk_hex), &signature.to_vec())?;
+
+    // Check if the key (height, block_hash) exists
+    let mut block_votes_fp_set = BLOCK_VOTES
+        .may_load(storage, (height, block_hash))?
+        .unwrap_or_else(HashSet::new);
+
+    // Add the fp_btc_pk_hex to the set
+    block_votes_fp_set.insert(fp_btc_pk_hex.to_string());
+
+    // Save the updated set back to storage
+    BLOCK_VOTES.save(storage, (height, block_hash), &block_votes_fp_set)?;
+    Ok(())
+}
+
+/// `handle_equivocation` records the evidence of a finality provider having signed two different
+/// blocks at the same height. The block it voted for first is taken as the canonical one, and the
+/// other one as the fork. Its BTC secret key is extracted from the two EOTS signatures, and a
+/// slashing event is returned, so that the consumer chain or Babylon can act on it.
+/// Further conflicting signatures at the same height are ignored
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_equivocation(
+    storage: &mut dyn Storage,
+    ctx: &SigningContext,
+    fp_btc_pk_hex: &str,
+    height: u64,
+    pub_rand: &[u8],
+    canonical_sig: &[u8],
+    fork_hash: &[u8],
+    fork_sig: &[u8],
+) -> Result<Option<Event>, ContractError> {
+    if EVIDENCES.has(storage, (fp_btc_pk_hex, height)) {
+        return Ok(None);
+    }
+
+    // Find the block the finality provider voted for first
+    let canonical_hash = BLOCK_VOTES
+        .prefix(height)
+        .range(storage, None, None, Order::Ascending)
+        .find_map(|item| match item {
+            Ok((hash, fps)) if fps.contains(fp_btc_pk_hex) => Some(Ok(hash)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .transpose()?
+        .ok_or_else(|| {
+            StdError::not_found(format!("vote of {fp_btc_pk_hex} at height {height}"))
+        })?;
+
+    // Extract the BTC secret key through the EOTS relationship of the two signatures
+    let pk = eots::PublicKey::from_hex(fp_btc_pk_hex)?;
+    let canonical_msg_hash = Sha256::digest(ctx.finality_sig_msg(height, &canonical_hash));
+    let fork_msg_hash = Sha256::digest(ctx.finality_sig_msg(height, fork_hash));
+    let btc_sk = pk.extract_secret_key(
+        pub_rand,
+        &canonical_msg_hash,
+        canonical_sig,
+        &fork_msg_hash,
+        fork_sig,
+    )?;
+
+    let evidence = Evidence {
+        fp_btc_pk: hex::decode(fp_btc_pk_hex)?,
+        block_height: height,
+        pub_rand: pub_rand.to_vec(),
+        canonical_app_hash: canonical_hash,
+        fork_app_hash: fork_hash.to_vec(),
+        canonical_finality_sig: canonical_sig.to_vec(),
+        fork_finality_sig: fork_sig.to_vec(),
+    };
+    EVIDENCES.save(storage, (fp_btc_pk_hex, height), &evidence)?;
+
+    let event = Event::new("slashed_finality_provider")
+        .add_attribute("fp_pubkey_hex", fp_btc_pk_hex)
+        .add_attribute("block_height", height.to_string())
+        .add_attribute(
+            "canonical_app_hash",
+            hex::encode(&evidence.canonical_app_hash),
+        )
+        .add_attribute(
+            "canonical_finality_sig",
+            hex::encode(&evidence.canonical_finality_sig),
+        )
+        .add_attribute("fork_app_hash", hex::encode(&evidence.fork_app_hash))
+        .add_attribute(
+            "fork_finality_sig",
+            hex::encode(&evidence.fork_finality_sig),
+        )
+        .add_attribute("secret_key", hex::encode(btc_sk.to_bytes()));
+    Ok(Some(event))
+}
+
 /// Verifies the finality signature message w.r.t. the public randomness commitment:
 /// - Public randomness inclusion proof.
 /// - Finality signature
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn verify_finality_signature(
+    ctx: &SigningContext,
     fp_btc_pk_hex: &str,
     block_height: u64,
     pub_rand: &[u8],
@@ -294,7 +477,7 @@ pub(crate) fn verify_finality_signature(
 
     // Public randomness is good, verify finality signature
     let pubkey = eots::PublicKey::from_hex(fp_btc_pk_hex)?;
-    let msg = msg_to_sign(block_height, app_hash);
+    let msg = ctx.finality_sig_msg(block_height, app_hash);
     let msg_hash = Sha256::digest(msg);
 
     if !pubkey.verify(pub_rand, &msg_hash, signature)? {
@@ -303,15 +486,6 @@ pub(crate) fn verify_finality_signature(
     Ok(())
 }
 
-/// `msg_to_sign` returns the message for an EOTS signature.
-///
-/// The EOTS signature on a block will be (block_height || block_hash)
-fn msg_to_sign(height: u64, block_hash: &[u8]) -> Vec<u8> {
-    let mut msg: Vec<u8> = height.to_be_bytes().to_vec();
-    msg.extend_from_slice(block_hash);
-    msg
-}
-
 fn check_fp_exist(deps: Deps, fp_pubkey_hex: &str) -> Result<(), ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let fp = query_finality_provider(deps, config.consumer_id.clone(), fp_pubkey_hex.to_string());
@@ -332,9 +506,24 @@ fn check_fp_exist(deps: Deps, fp_pubkey_hex: &str) -> Result<(), ContractError>
 pub(crate) mod tests {
     use super::*;
 
-    use babylon_apis::finality_api::PubRandCommit;
+    use babylon_apis::finality_api::{PubRandCommit, SIGNING_CONTEXT_V0, SIGNING_CONTEXT_V1};
+    use cosmwasm_std::testing::mock_dependencies;
     use hex::ToHex;
-    use test_utils::{get_add_finality_sig, get_pub_rand_commit, get_pub_rand_value};
+    use k256::ecdsa::signature::Signer;
+    use k256::schnorr::SigningKey;
+    use test_utils::{
+        create_new_fp_sk, get_add_finality_sig, get_add_finality_sig_2, get_pub_rand_commit,
+        get_pub_rand_value,
+    };
+
+    fn signing_ctx(version: u32) -> SigningContext {
+        SigningContext {
+            version,
+            chain_id: "babylon".to_string(),
+            contract_address: "op-finality-gadget".to_string(),
+            consumer_id: "op-stack-l2-11155420".to_string(),
+        }
+    }
 
     /// Get public randomness public key, commitment, and signature information
     ///
@@ -359,6 +548,7 @@ pub(crate) mod tests {
 
         // Verify commitment signature
         let res = verify_commitment_signature(
+            &signing_ctx(SIGNING_CONTEXT_V0),
             &fp_btc_pk_hex,
             pr_commit.start_height,
             pr_commit.num_pub_rand,
@@ -383,6 +573,7 @@ pub(crate) mod tests {
             panic!("Proof index should be non-negative");
         }
         let res = verify_finality_signature(
+            &signing_ctx(SIGNING_CONTEXT_V0),
             &pk_hex,
             initial_height + proof.index.unsigned_abs(),
             &pub_rand_one,
@@ -395,4 +586,134 @@ pub(crate) mod tests {
         );
         assert!(res.is_ok());
     }
+
+    #[test]
+    fn domain_separated_commitment_signature_works() {
+        let fp_sk = create_new_fp_sk(1);
+        let fp_btc_pk_hex = hex::encode(fp_sk.verifying_key().to_bytes());
+        let (_, pr_commit, legacy_sig) = get_public_randomness_commitment();
+
+        let ctx = signing_ctx(SIGNING_CONTEXT_V1);
+        let msg = ctx.commit_pub_rand_msg(
+            pr_commit.start_height,
+            pr_commit.num_pub_rand,
+            &pr_commit.commitment,
+        );
+        let sig = fp_sk.sign(&msg).to_bytes().to_vec();
+        let verify = |ctx: &SigningContext, sig: &[u8]| {
+            verify_commitment_signature(
+                ctx,
+                &fp_btc_pk_hex,
+                pr_commit.start_height,
+                pr_commit.num_pub_rand,
+                &pr_commit.commitment,
+                sig,
+            )
+        };
+        verify(&ctx, &sig).unwrap();
+
+        // The signature cannot be replayed on another chain, contract, or consumer
+        let mut other = ctx.clone();
+        other.chain_id = "other-chain".to_string();
+        verify(&other, &sig).unwrap_err();
+        let mut other = ctx.clone();
+        other.contract_address = "other-contract".to_string();
+        verify(&other, &sig).unwrap_err();
+        let mut other = ctx.clone();
+        other.consumer_id = "other-consumer".to_string();
+        verify(&other, &sig).unwrap_err();
+
+        // Nor under the legacy scheme, and legacy signatures are rejected under the new one
+        verify(&signing_ctx(SIGNING_CONTEXT_V0), &sig).unwrap_err();
+        verify(&ctx, &legacy_sig).unwrap_err();
+    }
+
+    #[test]
+    fn equivocation_evidence_works() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        let ctx = signing_ctx(SIGNING_CONTEXT_V0);
+        let (pk_hex, _, _) = get_public_randomness_commitment();
+        let pub_rand = get_pub_rand_value();
+        // Two valid signatures of the same finality provider, for two different blocks at the
+        // same height
+        let canonical = get_add_finality_sig();
+        let fork = get_add_finality_sig_2();
+        let height = canonical.block_height;
+        assert_eq!(fork.block_height, height);
+        assert_ne!(fork.block_app_hash, canonical.block_app_hash);
+
+        save_vote(
+            storage,
+            &pk_hex,
+            height,
+            &canonical.block_app_hash,
+            &canonical.finality_sig,
+        )
+        .unwrap();
+        let equivocate = |storage: &mut dyn Storage| {
+            handle_equivocation(
+                storage,
+                &ctx,
+                &pk_hex,
+                height,
+                &pub_rand,
+                &canonical.finality_sig,
+                &fork.block_app_hash,
+                &fork.finality_sig,
+            )
+            .unwrap()
+        };
+        let event = equivocate(storage).unwrap();
+
+        // The BTC secret key of the finality provider is extracted
+        let secret_key = event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "secret_key")
+            .unwrap();
+        let btc_sk = SigningKey::from_bytes(&hex::decode(&secret_key.value).unwrap()).unwrap();
+        assert_eq!(hex::encode(btc_sk.verifying_key().to_bytes()), pk_hex);
+
+        // The evidence is recorded, with the first voted block as the canonical one
+        let evidence = EVIDENCES.load(storage, (&pk_hex, height)).unwrap();
+        assert_eq!(
+            evidence,
+            Evidence {
+                fp_btc_pk: hex::decode(&pk_hex).unwrap(),
+                block_height: height,
+                pub_rand: pub_rand.clone(),
+                canonical_app_hash: canonical.block_app_hash.to_vec(),
+                fork_app_hash: fork.block_app_hash.to_vec(),
+                canonical_finality_sig: canonical.finality_sig.to_vec(),
+                fork_finality_sig: fork.finality_sig.to_vec(),
+            }
+        );
+
+        // Further conflicting signatures at the same height are ignored
+        assert_eq!(equivocate(storage), None);
+    }
+
+    #[test]
+    fn pub_rand_timestamping_works() {
+        let mut deps = mock_dependencies();
+        let (pk_hex, pr_commit, _) = get_public_randomness_commitment();
+        let ensure = |deps: Deps, last_finalized_epoch: u64| {
+            ensure_pub_rand_timestamped(deps, &pk_hex, &pr_commit, || Ok(last_finalized_epoch))
+        };
+
+        // Commitments predating the check are accepted
+        ensure(deps.as_ref(), 0).unwrap();
+
+        // Commitments are accepted once their epoch is BTC-finalised
+        PUB_RAND_COMMIT_EPOCHS
+            .save(deps.as_mut().storage, (&pk_hex, pr_commit.start_height), &5)
+            .unwrap();
+        assert_eq!(
+            ensure(deps.as_ref(), 4).unwrap_err(),
+            ContractError::PubRandCommitNotTimestamped(pk_hex.clone(), pr_commit.start_height, 5)
+        );
+        ensure(deps.as_ref(), 5).unwrap();
+        ensure(deps.as_ref(), 6).unwrap();
+    }
 }
@@ -0,0 +1,72 @@
+use cosmwasm_std::{DepsMut, Event, MessageInfo, Response, Storage};
+
+use babylon_apis::finality_api::PubRandCommit;
+
+use crate::error::ContractError;
+use crate::exec::admin::check_admin;
+use crate::exec::finality::save_vote;
+use crate::state::config::{get_mode, Mode};
+use crate::state::public_randomness::{PUB_RAND_COMMITS, PUB_RAND_VALUES};
+
+// Record a public randomness commitment relayed from the primary deployment.
+// Only callable by contract admin, in passive mirror mode.
+// The commitment was validated by the primary deployment, so it is stored as is.
+pub fn handle_mirror_public_randomness(
+    deps: DepsMut,
+    info: MessageInfo,
+    fp_pubkey_hex: &str,
+    pr_commit: PubRandCommit,
+) -> Result<Response, ContractError> {
+    check_mirror(&deps, info)?;
+
+    PUB_RAND_COMMITS.save(
+        deps.storage,
+        (fp_pubkey_hex, pr_commit.start_height),
+        &pr_commit,
+    )?;
+
+    let event = Event::new("mirror_public_randomness_commit")
+        .add_attribute("fp_pubkey_hex", fp_pubkey_hex)
+        .add_attribute("pr_commit.start_height", pr_commit.start_height.to_string())
+        .add_attribute("pr_commit.num_pub_rand", pr_commit.num_pub_rand.to_string());
+
+    Ok(Response::new().add_event(event))
+}
+
+// Record a finality signature relayed from the primary deployment.
+// Only callable by contract admin, in passive mirror mode.
+// The signature was validated by the primary deployment, so it is stored as is.
+pub fn handle_mirror_finality_signature(
+    deps: DepsMut,
+    info: MessageInfo,
+    fp_pubkey_hex: &str,
+    height: u64,
+    pub_rand: &[u8],
+    block_hash: &[u8],
+    signature: &[u8],
+) -> Result<Response, ContractError> {
+    check_mirror(&deps, info)?;
+
+    PUB_RAND_VALUES.save(deps.storage, (fp_pubkey_hex, height), &pub_rand.to_vec())?;
+    save_vote(deps.storage, fp_pubkey_hex, height, block_hash, signature)?;
+
+    let event = Event::new("mirror_finality_signature")
+        .add_attribute("fp_pubkey_hex", fp_pubkey_hex)
+        .add_attribute("block_height", height.to_string())
+        .add_attribute("block_hash", hex::encode(block_hash));
+
+    Ok(Response::new().add_event(event))
+}
+
+// Helper function to check the caller is the contract admin, and the contract is a mirror
+fn check_mirror(deps: &DepsMut, info: MessageInfo) -> Result<(), ContractError> {
+    check_admin(deps, info)?;
+    ensure_passive(deps.storage)
+}
+
+fn ensure_passive(storage: &dyn Storage) -> Result<(), ContractError> {
+    match get_mode(storage)? {
+        Mode::Passive => Ok(()),
+        Mode::Active => Err(ContractError::NotPassiveMode),
+    }
+}
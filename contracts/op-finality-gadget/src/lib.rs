@@ -1,14 +1,18 @@
+use babylon_apis::state_hash::with_state_hash;
 use cosmwasm_std::{
     entry_point, Deps, DepsMut, Env, MessageInfo, QueryResponse, Response, StdResult,
 };
 use error::ContractError;
-use msg::{ExecuteMsg, InstantiateMsg};
+use msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
+use state::state_hash::SECTIONS;
 
 pub mod contract;
 pub mod error;
 pub mod exec;
 pub mod msg;
 pub mod queries;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
 pub mod state;
 pub mod utils;
 
@@ -19,7 +23,9 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
-    contract::instantiate(deps, env, info, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        contract::instantiate(deps, env, info, msg)
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -27,6 +33,11 @@ pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> Result<QueryResponse,
     contract::query(deps, env, msg)
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    with_state_hash(deps, SECTIONS, |deps| contract::migrate(deps, env, msg))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -34,5 +45,7 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    contract::execute(deps, env, info, msg)
+    with_state_hash(deps, SECTIONS, |deps| {
+        contract::execute(deps, env, info, msg)
+    })
 }
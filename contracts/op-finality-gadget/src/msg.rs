@@ -1,19 +1,43 @@
 #[cfg(not(target_arch = "wasm32"))]
 use {
-    crate::state::config::Config, babylon_apis::finality_api::PubRandCommit,
-    cw_controllers::AdminResponse, std::collections::HashSet,
+    crate::state::config::Config,
+    babylon_apis::error::ErrorCodesResponse,
+    babylon_apis::finality_api::{Evidence, PubRandCommit},
+    babylon_apis::migration::MigrationProgressResponse,
+    babylon_apis::state_hash::StateHashResponse,
+    cw_controllers::AdminResponse,
+    std::collections::HashSet,
 };
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::Binary;
 
+use babylon_apis::btc_pk::BtcPkHex;
 use babylon_merkle::Proof;
 
+use crate::state::config::Mode;
+
+/// MigrateMsg is the message of a migration to a newer version of the contract
+#[cw_serde]
+#[derive(Default)]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admin: String,
     pub consumer_id: String,
     pub is_enabled: bool,
+    /// `signing_context_version` is the version of the signing context finality providers bind
+    /// their signatures to. Defaults to the legacy version 0, i.e. no domain separation
+    pub signing_context_version: Option<u32>,
+    /// `min_vote_payload_version` is the minimum vote payload version finality signature
+    /// submissions must declare. Defaults to 0, i.e. accepting the daemons predating the version
+    /// handshake
+    #[serde(default)]
+    pub min_vote_payload_version: Option<u32>,
+    /// `mode` is the operating mode of the finality gadget. Defaults to active
+    #[serde(default)]
+    pub mode: Option<Mode>,
 }
 
 #[cw_serde]
@@ -21,8 +45,18 @@ pub struct InstantiateMsg {
 pub enum QueryMsg {
     #[returns(AdminResponse)]
     Admin {},
+    /// `PendingAdmin` returns the admin proposed by the current admin (if any), pending acceptance
+    #[returns(AdminResponse)]
+    PendingAdmin {},
     #[returns(Option<HashSet<String>>)]
     BlockVoters { height: u64, hash: String },
+    /// `BlockAttestation` returns the finality votes (if any) for a given block, tightly encoded
+    /// for cheap verification by an EVM verifier contract on L1.
+    ///
+    /// `hash` is the 32-byte block hash, in hex format.
+    /// See `queries::encode_block_attestation` for the encoding
+    #[returns(Option<Binary>)]
+    BlockAttestation { height: u64, hash: String },
     /// `Config` returns the configuration of the op-finality-gadget contract
     #[returns(Config)]
     Config {},
@@ -30,14 +64,47 @@ pub enum QueryMsg {
     ///
     /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
     #[returns(Option<PubRandCommit>)]
-    FirstPubRandCommit { btc_pk_hex: String },
+    FirstPubRandCommit { btc_pk_hex: BtcPkHex },
     /// `LastPubRandCommit` returns the last public random commitment (if any) for a given FP.
     ///
     /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
     #[returns(Option<PubRandCommit>)]
-    LastPubRandCommit { btc_pk_hex: String },
+    LastPubRandCommit { btc_pk_hex: BtcPkHex },
+    /// `PubRandCommitEpoch` returns the Babylon epoch (if recorded) in which the public random
+    /// commitment of a given FP starting at `start_height` was made.
+    /// The commitment can only be used once that epoch is BTC-finalised.
+    ///
+    /// `btc_pk_hex` is the BTC public key of the finality provider, in hex format.
+    #[returns(Option<u64>)]
+    PubRandCommitEpoch {
+        btc_pk_hex: BtcPkHex,
+        start_height: u64,
+    },
+    /// `Evidence` returns the equivocation evidence (if any) of a given FP at a given height,
+    /// i.e. its finality signatures for two different blocks at that height.
+    ///
+    /// `fp_pubkey_hex` is the BTC public key of the finality provider, in hex format.
+    #[returns(Option<Evidence>)]
+    Evidence {
+        fp_pubkey_hex: BtcPkHex,
+        height: u64,
+    },
     #[returns(bool)]
     IsEnabled {},
+    /// `Mode` returns the operating mode of the finality gadget
+    #[returns(Mode)]
+    Mode {},
+    /// `ErrorCodes` returns the table of stable error codes of the contract
+    #[returns(ErrorCodesResponse)]
+    ErrorCodes {},
+    /// `StateHash` returns a deterministic digest over the contract's state, by state section
+    /// (see `babylon_apis::state_hash`), e.g. to compare the state of two deployments after a
+    /// migration
+    #[returns(StateHashResponse)]
+    StateHash {},
+    /// `MigrationProgress` returns the progress of the scheduled storage migrations
+    #[returns(MigrationProgressResponse)]
+    MigrationProgress {},
 }
 
 // Note: copied from packages/apis/src/btc_staking_api.rs
@@ -45,7 +112,7 @@ pub enum QueryMsg {
 pub enum ExecuteMsg {
     CommitPublicRandomness {
         /// `fp_pubkey_hex` is the BTC PK of the finality provider that commits the public randomness
-        fp_pubkey_hex: String,
+        fp_pubkey_hex: BtcPkHex,
         /// `start_height` is the start block height of the list of public randomness
         start_height: u64,
         /// `num_pub_rand` is the amount of public randomness committed
@@ -54,7 +121,8 @@ pub enum ExecuteMsg {
         /// Currently, it's the root of the Merkle tree that includes the public randomness
         commitment: Binary,
         /// `signature` is the signature on (start_height || num_pub_rand || commitment) signed by
-        /// the SK corresponding to `fp_pubkey_hex`.
+        /// the SK corresponding to `fp_pubkey_hex`, prefixed by the signing context (see
+        /// `babylon_apis::finality_api::SigningContext`).
         /// This prevents others committing public randomness on behalf of `fp_pubkey_hex`
         signature: Binary,
     },
@@ -68,12 +136,17 @@ pub enum ExecuteMsg {
     /// defs.
     // TODO: Move to its own module / contract
     SubmitFinalitySignature {
-        fp_pubkey_hex: String,
+        fp_pubkey_hex: BtcPkHex,
         height: u64,
         pub_rand: Binary,
         proof: Proof,
         block_hash: Binary,
         signature: Binary,
+        /// `payload_version` is the vote payload version the finality provider daemon speaks.
+        /// Absent for daemons predating the version handshake, which are on version 0.
+        /// Submissions below the minimum version of the contract config are rejected
+        #[serde(default)]
+        payload_version: Option<u32>,
     },
     /// Enable or disable finality gadget.
     ///
@@ -81,13 +154,49 @@ pub enum ExecuteMsg {
     /// If disabled, the verifier should bypass the EOTS verification logic, allowing the OP derivation
     /// derivation pipeline to pass through. Note this should be implemented in the verifier and is not
     /// enforced by the contract itself.
-    SetEnabled {
-        enabled: bool,
+    SetEnabled { enabled: bool },
+    /// Switch the finality gadget between active and passive mirror mode.
+    ///
+    /// This message can be called by the admin only.
+    SetMode { mode: Mode },
+    /// Set the minimum vote payload version finality signature submissions must declare, so that
+    /// the finality provider fleet can be upgraded to a new protocol version in a coordinated way.
+    ///
+    /// This message can be called by the admin only.
+    SetMinVotePayloadVersion { version: u32 },
+    /// Record a public randomness commitment relayed from the primary deployment, without
+    /// validating it.
+    ///
+    /// This message can be called by the admin only, in passive mirror mode.
+    MirrorPublicRandomness {
+        fp_pubkey_hex: BtcPkHex,
+        start_height: u64,
+        num_pub_rand: u64,
+        commitment: Binary,
     },
-    // Update the admin address.
-    //
-    // This message can be called by the admin only.
-    UpdateAdmin {
-        admin: String,
+    /// Record a finality signature relayed from the primary deployment, without validating it.
+    ///
+    /// This message can be called by the admin only, in passive mirror mode.
+    MirrorFinalitySignature {
+        fp_pubkey_hex: BtcPkHex,
+        height: u64,
+        pub_rand: Binary,
+        block_hash: Binary,
+        signature: Binary,
     },
+    /// Propose a new admin address, or cancel the pending proposal if `admin` is not set.
+    ///
+    /// This message can be called by the admin only.
+    /// The proposed admin takes over control once it accepts the transfer (see `AcceptAdmin`)
+    UpdateAdmin { admin: Option<String> },
+    /// Accept the admin transfer proposed by the current admin.
+    ///
+    /// This message can be called by the pending admin only.
+    AcceptAdmin {},
+    /// `ContinueMigration` migrates up to `limit` entries of the first pending storage migration
+    /// (see the `MigrationProgress` query). The state section of a migration is paused until the
+    /// migration is complete.
+    ///
+    /// Anyone can continue a migration
+    ContinueMigration { limit: u32 },
 }
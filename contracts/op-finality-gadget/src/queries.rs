@@ -1,10 +1,15 @@
 use crate::error::ContractError;
-use crate::state::config::{Config, ADMIN, CONFIG, IS_ENABLED};
-use crate::state::finality::BLOCK_VOTES;
-use crate::state::public_randomness::get_pub_rand_commit;
-use babylon_apis::finality_api::PubRandCommit;
-use cosmwasm_std::{Deps, StdResult, Storage};
+use crate::exec::finality::signing_context;
+use crate::state::config::{Config, ADMIN, CONFIG, IS_ENABLED, PENDING_ADMIN};
+use crate::state::finality::{BLOCK_VOTES, EVIDENCES, SIGNATURES};
+use crate::state::public_randomness::{
+    get_pub_rand_commit, PUB_RAND_COMMIT_EPOCHS, PUB_RAND_VALUES,
+};
+use crate::utils::query_finality_provider;
+use babylon_apis::finality_api::{Evidence, PubRandCommit, SigningContext};
+use cosmwasm_std::{Binary, Deps, Env, StdError, StdResult, Storage};
 use cw_controllers::AdminResponse;
+use k256::sha2::{Digest, Sha256};
 use std::collections::HashSet;
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
@@ -30,6 +35,84 @@ pub fn query_block_voters(
     Ok(fp_pubkey_hex_list)
 }
 
+/// `ATTESTATION_FORMAT_VERSION` is the version of the block attestation encoding
+pub const ATTESTATION_FORMAT_VERSION: u8 = 1;
+
+pub fn query_block_attestation(
+    deps: Deps,
+    env: &Env,
+    height: u64,
+    hash: String,
+) -> Result<Option<Binary>, ContractError> {
+    let block_hash = hex::decode(&hash)?;
+    let ctx = signing_context(deps.storage, env)?;
+    let consumer_id = ctx.consumer_id.clone();
+    encode_block_attestation(deps.storage, &ctx, height, &block_hash, |fp_btc_pk_hex| {
+        Ok(
+            query_finality_provider(deps, consumer_id.clone(), fp_btc_pk_hex.to_string())?
+                .voting_power,
+        )
+    })
+}
+
+/// `encode_block_attestation` packages the finality votes for the block at `height` with
+/// `block_hash`, if any, for verification by an EVM verifier contract.
+/// All integers are big-endian, and fields are tightly packed (as in `abi.encodePacked`):
+///
+/// version (u8) || height (u64) || block_hash (bytes32) || msg_hash (bytes32) || count (u16) ||
+/// count * (btc_pk (bytes32) || power (u64) || pub_rand (bytes32) || signature (bytes32))
+///
+/// `msg_hash` is the SHA-256 of the message signed by the finality providers (see
+/// `SigningContext::finality_sig_msg`), and `btc_pk` and `pub_rand` are x-only public keys.
+/// The votes are sorted by BTC public key, so that duplicates are trivial to reject.
+/// `power` is the voting power of the finality provider as given by `voting_power`. The verifier
+/// compares the summed power to its own view of the total power for the quorum check
+pub(crate) fn encode_block_attestation(
+    storage: &dyn Storage,
+    ctx: &SigningContext,
+    height: u64,
+    block_hash: &[u8],
+    voting_power: impl Fn(&str) -> StdResult<u64>,
+) -> Result<Option<Binary>, ContractError> {
+    let block_hash: [u8; 32] = block_hash
+        .try_into()
+        .map_err(|_| StdError::generic_err("Block hash must be 32 bytes"))?;
+    let Some(voters) = BLOCK_VOTES.may_load(storage, (height, &block_hash))? else {
+        return Ok(None);
+    };
+    let mut voters: Vec<_> = voters.into_iter().collect();
+    voters.sort();
+    let count = u16::try_from(voters.len())
+        .map_err(|_| StdError::generic_err("Too many votes for the block"))?;
+
+    let mut res = vec![ATTESTATION_FORMAT_VERSION];
+    res.extend_from_slice(&height.to_be_bytes());
+    res.extend_from_slice(&block_hash);
+    res.extend_from_slice(&Sha256::digest(ctx.finality_sig_msg(height, &block_hash)));
+    res.extend_from_slice(&count.to_be_bytes());
+    for fp_btc_pk_hex in voters {
+        let pub_rand = PUB_RAND_VALUES.load(storage, (&fp_btc_pk_hex, height))?;
+        let signature = SIGNATURES.load(storage, (height, &fp_btc_pk_hex))?;
+        for (name, value) in [
+            ("BTC public key", &hex::decode(&fp_btc_pk_hex)?),
+            ("public randomness", &pub_rand),
+            ("signature", &signature),
+        ] {
+            if value.len() != 32 {
+                return Err(StdError::generic_err(format!(
+                    "Invalid {name} length for finality provider {fp_btc_pk_hex}"
+                ))
+                .into());
+            }
+        }
+        res.extend_from_slice(&hex::decode(&fp_btc_pk_hex)?);
+        res.extend_from_slice(&voting_power(&fp_btc_pk_hex)?.to_be_bytes());
+        res.extend_from_slice(&pub_rand);
+        res.extend_from_slice(&signature);
+    }
+    Ok(Some(res.into()))
+}
+
 pub fn query_first_pub_rand_commit(
     storage: &dyn Storage,
     fp_btc_pk_hex: &str,
@@ -46,6 +129,22 @@ pub fn query_last_pub_rand_commit(
     Ok(res.into_iter().next())
 }
 
+pub fn query_pub_rand_commit_epoch(
+    storage: &dyn Storage,
+    fp_btc_pk_hex: &str,
+    start_height: u64,
+) -> StdResult<Option<u64>> {
+    PUB_RAND_COMMIT_EPOCHS.may_load(storage, (fp_btc_pk_hex, start_height))
+}
+
+pub fn query_evidence(
+    storage: &dyn Storage,
+    fp_btc_pk_hex: &str,
+    height: u64,
+) -> StdResult<Option<Evidence>> {
+    EVIDENCES.may_load(storage, (fp_btc_pk_hex, height))
+}
+
 pub fn query_is_enabled(deps: Deps) -> StdResult<bool> {
     IS_ENABLED.load(deps.storage)
 }
@@ -53,3 +152,81 @@ pub fn query_is_enabled(deps: Deps) -> StdResult<bool> {
 pub fn query_admin(deps: Deps) -> StdResult<AdminResponse> {
     ADMIN.query_admin(deps)
 }
+
+pub fn query_pending_admin(deps: Deps) -> StdResult<AdminResponse> {
+    let admin = PENDING_ADMIN.may_load(deps.storage)?;
+    Ok(AdminResponse {
+        admin: admin.map(String::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use babylon_apis::finality_api::SIGNING_CONTEXT_V1;
+    use cosmwasm_std::testing::mock_dependencies;
+    use std::collections::HashSet;
+
+    #[test]
+    fn block_attestation_encoding_works() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        let ctx = SigningContext {
+            version: SIGNING_CONTEXT_V1,
+            chain_id: "babylon".to_string(),
+            contract_address: "op-finality-gadget".to_string(),
+            consumer_id: "op-stack-l2-11155420".to_string(),
+        };
+        let height = 42;
+        let block_hash = [0xabu8; 32];
+        let power = |fp_btc_pk_hex: &str| {
+            Ok(if fp_btc_pk_hex == "02".repeat(32) {
+                20
+            } else {
+                10
+            })
+        };
+
+        // No votes, no attestation
+        let res = encode_block_attestation(storage, &ctx, height, &block_hash, power).unwrap();
+        assert_eq!(res, None);
+        encode_block_attestation(storage, &ctx, height, &block_hash[1..], power).unwrap_err();
+
+        let fps = ["02".repeat(32), "01".repeat(32)];
+        for (i, fp) in fps.iter().enumerate() {
+            PUB_RAND_VALUES
+                .save(storage, (fp, height), &vec![i as u8; 32])
+                .unwrap();
+            SIGNATURES
+                .save(storage, (height, fp), &vec![0xf0 + i as u8; 32])
+                .unwrap();
+        }
+        BLOCK_VOTES
+            .save(storage, (height, &block_hash), &HashSet::from(fps.clone()))
+            .unwrap();
+
+        let res = encode_block_attestation(storage, &ctx, height, &block_hash, power)
+            .unwrap()
+            .unwrap();
+        let expected = [
+            vec![ATTESTATION_FORMAT_VERSION],
+            height.to_be_bytes().to_vec(),
+            block_hash.to_vec(),
+            Sha256::digest(ctx.finality_sig_msg(height, &block_hash)).to_vec(),
+            2u16.to_be_bytes().to_vec(),
+            // Votes sorted by BTC public key
+            vec![0x01; 32],
+            10u64.to_be_bytes().to_vec(),
+            vec![1; 32],
+            vec![0xf1; 32],
+            vec![0x02; 32],
+            20u64.to_be_bytes().to_vec(),
+            vec![0; 32],
+            vec![0xf0; 32],
+        ]
+        .concat();
+        assert_eq!(res.to_vec(), expected);
+        assert_eq!(res.len(), 1 + 8 + 32 + 32 + 2 + 2 * 104);
+    }
+}
@@ -1,14 +1,48 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, StdResult, Storage};
 use cw_controllers::Admin;
 use cw_storage_plus::Item;
 
 pub const ADMIN: Admin = Admin::new("admin");
+// the admin proposed by the current admin, until it accepts the transfer
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
 pub const CONFIG: Item<Config> = Item::new("config");
 // if the finality gadget is disabled, it will always return true for the is finalized query
 pub const IS_ENABLED: Item<bool> = Item::new("is_enabled");
+// the operating mode of the finality gadget. Absent means active, for deployments predating it
+pub const MODE: Item<Mode> = Item::new("mode");
 
 /// Config are OP finality gadget's configuration
 #[cw_serde]
 pub struct Config {
     pub consumer_id: String,
+    /// `signing_context_version` is the version of the signing context finality providers bind
+    /// their signatures to (see `babylon_apis::finality_api::SigningContext`).
+    /// Defaults to the legacy version 0, i.e. no domain separation
+    #[serde(default)]
+    pub signing_context_version: u32,
+    /// `min_vote_payload_version` is the minimum vote payload version finality signature
+    /// submissions must declare. Defaults to 0, i.e. accepting the daemons predating the version
+    /// handshake
+    #[serde(default)]
+    pub min_vote_payload_version: u32,
+}
+
+/// Mode is the operating mode of the OP finality gadget
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum Mode {
+    /// `Active` validates and records public randomness commitments and finality signatures
+    /// submitted by finality providers
+    #[default]
+    Active,
+    /// `Passive` only records the public randomness commitments and finality signatures relayed
+    /// by the admin from a primary deployment, without validating them, and serves queries.
+    /// Useful as a hot standby / read replica, e.g. during migrations
+    Passive,
+}
+
+/// get_mode returns the operating mode of the finality gadget
+pub fn get_mode(storage: &dyn Storage) -> StdResult<Mode> {
+    Ok(MODE.may_load(storage)?.unwrap_or_default())
 }
@@ -1,8 +1,13 @@
 use cw_storage_plus::Map;
 use std::collections::HashSet;
 
+use babylon_apis::finality_api::Evidence;
+
 /// Map of signatures by block height and fp
 pub(crate) const SIGNATURES: Map<(u64, &str), Vec<u8>> = Map::new("fp_sigs");
 
 /// Map of (block height, block hash) tuples to the list of fps that voted for this combination
 pub(crate) const BLOCK_VOTES: Map<(u64, &[u8]), HashSet<String>> = Map::new("block_hashes");
+
+/// Map of equivocation evidence by fp and block height
+pub(crate) const EVIDENCES: Map<(&str, u64), Evidence> = Map::new("evidences");
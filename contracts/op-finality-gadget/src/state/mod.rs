@@ -1,3 +1,4 @@
 pub mod config;
 pub mod finality;
 pub mod public_randomness;
+pub mod state_hash;
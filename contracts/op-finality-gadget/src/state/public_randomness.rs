@@ -1,11 +1,17 @@
 use crate::error::ContractError;
 use babylon_apis::finality_api::PubRandCommit;
-use cosmwasm_std::Order::{Ascending, Descending};
+use babylon_apis::pagination::{page_limit, page_range};
+use cosmwasm_std::Order::Descending;
 use cosmwasm_std::{StdResult, Storage};
 use cw_storage_plus::{Bound, Map};
 
 /// Map of public randomness commitments by fp and block height
 pub(crate) const PUB_RAND_COMMITS: Map<(&str, u64), PubRandCommit> = Map::new("fp_pub_rand_commit");
+/// Map of the Babylon epoch each public randomness commitment was made in, by fp and start height.
+/// Finality signatures are only accepted once the epoch of their commitment is BTC-finalised.
+/// Commitments predating it, or relayed to a passive mirror, have no entry
+pub(crate) const PUB_RAND_COMMIT_EPOCHS: Map<(&str, u64), u64> =
+    Map::new("fp_pub_rand_commit_epoch");
 /// Map of public randomness values by fp and block height
 pub(crate) const PUB_RAND_VALUES: Map<(&str, u64), Vec<u8>> = Map::new("fp_pub_rand");
 
@@ -41,10 +47,6 @@ pub fn get_pub_rand_commit_for_height(
     }
 }
 
-// Copied from contracts/btc-staking/src/state/public_randomness.rs
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
-
 pub fn get_pub_rand_commit(
     storage: &dyn Storage,
     fp_btc_pk_hex: &str,
@@ -52,13 +54,8 @@ pub fn get_pub_rand_commit(
     limit: Option<u32>,
     reverse: Option<bool>,
 ) -> Result<Vec<PubRandCommit>, ContractError> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start_after = start_after.map(Bound::exclusive);
-    let (start, end, order) = if reverse.unwrap_or(false) {
-        (None, start_after, Descending)
-    } else {
-        (start_after, None, Ascending)
-    };
+    let limit = page_limit(limit);
+    let (start, end, order) = page_range(start_after, reverse);
     let res = PUB_RAND_COMMITS
         .prefix(fp_btc_pk_hex)
         .range_raw(storage, start, end, order)
@@ -0,0 +1,39 @@
+//! state_hash defines the state sections of the contract, which the state hash is computed over
+//! (see `babylon_apis::state_hash`)
+use babylon_apis::migration::Migration;
+use babylon_apis::state_hash::{rebuild_section_hashes, StateSection, STATE_HASH_REBUILD};
+
+/// All state sections, in the order they are folded into the state hash
+pub const SECTIONS: &[StateSection] = &[
+    StateSection {
+        name: "config",
+        namespaces: &[
+            "config",
+            "admin",
+            "pending_admin",
+            "is_enabled",
+            "mode",
+            "contract_info",
+        ],
+    },
+    StateSection {
+        name: "finality",
+        namespaces: &["fp_sigs", "block_hashes", "evidences"],
+    },
+    StateSection {
+        name: "public_randomness",
+        namespaces: &[
+            "fp_pub_rand_commit",
+            "fp_pub_rand_commit_epoch",
+            "fp_pub_rand",
+        ],
+    },
+];
+
+/// The migration rebuilding the section hashes, for contracts migrating from a version not keeping
+/// them
+pub const SECTION_HASHES_REBUILD: Migration = Migration {
+    name: STATE_HASH_REBUILD,
+    section: STATE_HASH_REBUILD,
+    step: |storage, cursor, limit| rebuild_section_hashes(storage, SECTIONS, cursor, limit),
+};
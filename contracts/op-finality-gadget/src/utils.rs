@@ -54,6 +54,49 @@ pub fn query_finality_provider(
     Ok(res)
 }
 
+/// `CKPT_STATUS_FINALIZED` is the status of the checkpoints of the BTC-finalised epochs, see
+/// https://github.com/babylonlabs-io/babylon/blob/main/proto/babylon/checkpointing/v1/checkpoint.proto
+const CKPT_STATUS_FINALIZED: i32 = 4;
+
+/// query_current_epoch returns the current epoch number of the Babylon chain
+pub fn query_current_epoch(deps: Deps) -> StdResult<u64> {
+    let res_data = query_grpc(
+        deps,
+        "/babylon.epoching.v1.Query/CurrentEpoch".to_string(),
+        Binary::new(Anybuf::new().into_vec()),
+    )?;
+    let res_decoded = Bufany::deserialize(&res_data)
+        .map_err(|e| StdError::generic_err(format!("Decoding CurrentEpoch response: {e:?}")))?;
+    // see https://github.com/babylonlabs-io/babylon/blob/main/proto/babylon/epoching/v1/query.proto
+    // `current_epoch` is field 1 of `QueryCurrentEpochResponse`
+    res_decoded
+        .uint64(1)
+        .ok_or_else(|| StdError::generic_err("Invalid CurrentEpoch response"))
+}
+
+/// query_last_finalized_epoch returns the number of the last BTC-finalised (i.e. BTC-timestamped
+/// and k-deep) epoch of the Babylon chain
+pub fn query_last_finalized_epoch(deps: Deps) -> StdResult<u64> {
+    let query_data = Anybuf::new()
+        .append_int32(1, CKPT_STATUS_FINALIZED)
+        .into_vec();
+    let res_data = query_grpc(
+        deps,
+        "/babylon.checkpointing.v1.Query/LastCheckpointWithStatus".to_string(),
+        Binary::new(query_data),
+    )?;
+    let res_decoded = Bufany::deserialize(&res_data).map_err(|e| {
+        StdError::generic_err(format!("Decoding LastCheckpointWithStatus response: {e:?}"))
+    })?;
+    // see https://github.com/babylonlabs-io/babylon/blob/main/proto/babylon/checkpointing/v1/query.proto
+    // `raw_checkpoint` is field 1 of `QueryLastCheckpointWithStatusResponse`, and `epoch_num`
+    // field 1 of `RawCheckpointResponse`
+    res_decoded
+        .message(1)
+        .and_then(|ckpt| ckpt.uint64(1))
+        .ok_or_else(|| StdError::generic_err("Invalid LastCheckpointWithStatus response"))
+}
+
 /// TODO: query_grpc need to be replaced with cosmwasm::std::QuerierWrapper.query_grpc
 /// copy from the cosmwasm
 /// See [`GrpcQuery`](crate::GrpcQuery) for more information.
@@ -19,6 +19,9 @@ fn instantiate_works() {
         admin: mock_api.addr_make(CREATOR),
         consumer_id: "op-stack-l2-11155420".to_string(),
         is_enabled: false,
+        signing_context_version: None,
+        min_vote_payload_version: None,
+        mode: None,
     };
     let info = mock_info(CREATOR, &[]);
     let res: ContractResult<Response> = instantiate(&mut deps, mock_env(), info, msg.clone());
@@ -50,6 +53,9 @@ fn disable_and_reenable_works() {
         admin: mock_api.addr_make(CREATOR),
         consumer_id: "op-stack-l2-11155420".to_string(),
         is_enabled: false,
+        signing_context_version: None,
+        min_vote_payload_version: None,
+        mode: None,
     };
     let info = mock_info(CREATOR, &[]);
     let mut res: ContractResult<Response> =
@@ -109,6 +115,9 @@ fn instantiate_enabled() {
         admin: mock_api.addr_make(CREATOR),
         consumer_id: "op-stack-l2-11155420".to_string(),
         is_enabled: true,
+        signing_context_version: None,
+        min_vote_payload_version: None,
+        mode: None,
     };
     let info = mock_info(CREATOR, &[]);
     let res: ContractResult<Response> = instantiate(&mut instance, mock_env(), info, msg.clone());
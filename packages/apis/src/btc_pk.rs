@@ -0,0 +1,217 @@
+//! btc_pk validates the BTC public keys given in hex format in messages.
+//! `BtcPkHex` is parsed when a message is deserialized, so that malformed keys are rejected
+//! upfront rather than deep inside the handlers, and keys differing only in case are the same key.
+//!
+//! Keys are accepted both in BIP-340 (x-only, 32 bytes) and in SEC1 compressed (33 bytes)
+//! encoding, as different toolchains emit different encodings. They are always converted to their
+//! canonical x-only encoding, so that the same key in either encoding is the same key
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use cosmwasm_schema::schemars::gen::SchemaGenerator;
+use cosmwasm_schema::schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+use cosmwasm_schema::schemars::JsonSchema;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::StakingApiError;
+
+/// Length of a BIP-340 (x-only) public key, in bytes
+pub const BTC_PK_SIZE: usize = 32;
+/// Length of a SEC1 compressed public key, in bytes
+pub const COMPRESSED_BTC_PK_SIZE: usize = 33;
+
+/// to_x_only returns the canonical (BIP-340 x-only) encoding of the given BTC public key, in either
+/// x-only or SEC1 compressed encoding. The parity of a compressed key is dropped, as BIP-340 keys
+/// always have an even Y coordinate
+pub fn to_x_only(pk: &[u8]) -> Result<[u8; BTC_PK_SIZE], StakingApiError> {
+    match pk.len() {
+        BTC_PK_SIZE => Ok(pk.try_into().expect("x-only BTC PK size")),
+        COMPRESSED_BTC_PK_SIZE if matches!(pk[0], 0x02 | 0x03) => {
+            Ok(pk[1..].try_into().expect("x-only BTC PK size"))
+        }
+        _ => Err(StakingApiError::InvalidBtcPkHex(hex::encode(pk))),
+    }
+}
+
+/// canonical_btc_pk_hex returns the canonical (lowercase, x-only) hex encoding of the given BTC
+/// public key in hex format, in either x-only or SEC1 compressed encoding
+pub fn canonical_btc_pk_hex(pk_hex: &str) -> Result<String, StakingApiError> {
+    pk_hex.parse::<BtcPkHex>().map(String::from)
+}
+
+/// BtcPkHex is a BIP-340 (x-only) BTC public key in hex format.
+/// It is always 64 lowercase hex chars long; parsing accepts any case, and SEC1 compressed keys
+/// (66 hex chars), and normalizes them
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BtcPkHex(String);
+
+impl BtcPkHex {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// to_bytes returns the raw public key
+    pub fn to_bytes(&self) -> [u8; BTC_PK_SIZE] {
+        let mut pk = [0u8; BTC_PK_SIZE];
+        // The key is validated upon parsing
+        hex::decode_to_slice(&self.0, &mut pk).expect("valid BTC PK hex");
+        pk
+    }
+}
+
+impl FromStr for BtcPkHex {
+    type Err = StakingApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || StakingApiError::InvalidBtcPkHex(s.to_string());
+        match s.len() {
+            len if len == 2 * BTC_PK_SIZE && s.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                Ok(BtcPkHex(s.to_ascii_lowercase()))
+            }
+            len if len == 2 * COMPRESSED_BTC_PK_SIZE => {
+                let pk = hex::decode(s).map_err(|_| invalid())?;
+                Ok(to_x_only(&pk).map_err(|_| invalid())?.into())
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for BtcPkHex {
+    type Error = StakingApiError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<&str> for BtcPkHex {
+    type Error = StakingApiError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<[u8; BTC_PK_SIZE]> for BtcPkHex {
+    fn from(pk: [u8; BTC_PK_SIZE]) -> Self {
+        BtcPkHex(hex::encode(pk))
+    }
+}
+
+impl From<BtcPkHex> for String {
+    fn from(pk: BtcPkHex) -> Self {
+        pk.0
+    }
+}
+
+impl Deref for BtcPkHex {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for BtcPkHex {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for BtcPkHex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for BtcPkHex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for BtcPkHex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+impl JsonSchema for BtcPkHex {
+    fn schema_name() -> String {
+        "BtcPkHex".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        let mut schema = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                max_length: Some(2 * COMPRESSED_BTC_PK_SIZE as u32),
+                min_length: Some(2 * BTC_PK_SIZE as u32),
+                pattern: Some("^(0[23])?[0-9a-fA-F]{64}$".to_string()),
+            })),
+            ..Default::default()
+        };
+        schema.metadata().description = Some(
+            "A BIP-340 (x-only) BTC public key, as 64 hex chars, or a SEC1 compressed one, as 66 \
+             hex chars. It is normalized to lowercase x-only"
+                .to_string(),
+        );
+        schema.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{from_json, to_json_string};
+
+    const PK_HEX: &str = "3b9c8b4bd1ff5b1c37e1bfd6e13a7fa4f1e0bcb29dbd2db6b1e0a1d3c1e5f4a2";
+
+    #[test]
+    fn btc_pk_hex_parsing_works() {
+        let pk: BtcPkHex = PK_HEX.to_ascii_uppercase().parse().unwrap();
+        assert_eq!(pk.as_str(), PK_HEX);
+        assert_eq!(BtcPkHex::from(pk.to_bytes()), pk);
+
+        for invalid in [
+            "",
+            &PK_HEX[2..],
+            &format!("{PK_HEX}00"),
+            &format!("04{PK_HEX}"),
+            &PK_HEX.replace('3', "g"),
+        ] {
+            assert_eq!(
+                invalid.parse::<BtcPkHex>().unwrap_err(),
+                StakingApiError::InvalidBtcPkHex(invalid.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_btc_pk_hex_parsing_works() {
+        // Both parities map to the same x-only key
+        for prefix in ["02", "03"] {
+            let pk: BtcPkHex = format!("{prefix}{PK_HEX}").parse().unwrap();
+            assert_eq!(pk.as_str(), PK_HEX);
+            let raw = hex::decode(format!("{prefix}{PK_HEX}")).unwrap();
+            assert_eq!(to_x_only(&raw).unwrap(), pk.to_bytes());
+        }
+        assert_eq!(
+            canonical_btc_pk_hex(&format!("02{PK_HEX}")).unwrap(),
+            PK_HEX
+        );
+        to_x_only(&[0x04; COMPRESSED_BTC_PK_SIZE]).unwrap_err();
+        to_x_only(&[0x02; 31]).unwrap_err();
+    }
+
+    #[test]
+    fn btc_pk_hex_serde_works() {
+        let pk: BtcPkHex = from_json(format!("\"{}\"", PK_HEX.to_ascii_uppercase())).unwrap();
+        assert_eq!(to_json_string(&pk).unwrap(), format!("\"{PK_HEX}\""));
+        from_json::<BtcPkHex>("\"deadbeef\"").unwrap_err();
+    }
+}
@@ -1,12 +1,161 @@
 /// BTC staking messages / API
 /// The definitions here follow the same structure as the equivalent IBC protobuf message types,
 /// defined in `packages/proto/src/gen/babylon.btcstaking.v1.rs`
+use std::fmt;
+use std::str::FromStr;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Binary, Decimal};
+use cosmwasm_std::{Binary, Decimal, StdResult, Uint128};
+use sha2::{Digest, Sha256};
+
+use babylon_bitcoin::hash_types::Txid;
+
+use crate::btc_pk::{canonical_btc_pk_hex, to_x_only, BtcPkHex};
+use crate::canonical_json::to_canonical_json_vec;
+use crate::cw20::Cw20ReceiveMsg;
+use crate::error::StakingApiError;
 
 /// Hash size in bytes
 pub const HASH_SIZE: usize = 32;
 
+/// Operation tag of staker binding signatures
+pub const STAKER_BINDING_CONTEXT_TAG: &str = "staker_bind";
+
+/// `staker_binding_msg_hash` returns the hash signed (BIP-340) by a BTC staker key to bind it to
+/// the given consumer address, i.e.
+/// `sha256("btcstaking/{operation}/{chain_id}/{contract_address}/{staker_addr}")`
+pub fn staker_binding_msg_hash(
+    chain_id: &str,
+    contract_address: &str,
+    staker_addr: &str,
+) -> [u8; HASH_SIZE] {
+    let msg = format!(
+        "btcstaking/{STAKER_BINDING_CONTEXT_TAG}/{chain_id}/{contract_address}/{staker_addr}"
+    );
+    Sha256::digest(msg.as_bytes()).into()
+}
+
+/// Operation tag of finality provider metadata signatures
+pub const FP_METADATA_CONTEXT_TAG: &str = "fp_metadata";
+
+/// FpMetadata is the operational record a finality provider publishes, so that consumer operators
+/// can reach it, e.g. when it is blocking finalization
+#[cw_serde]
+#[derive(Default)]
+pub struct FpMetadata {
+    /// rpc_endpoint is the URL of the finality provider's RPC endpoint
+    pub rpc_endpoint: Option<String>,
+    /// alert_email_hash is the SHA-256 hash (in hex) of the finality provider's alert email
+    /// address, so that it can be checked without being published
+    pub alert_email_hash: Option<String>,
+    /// version is the version string of the finality provider's software
+    pub version: Option<String>,
+}
+
+/// `fp_metadata_msg_hash` returns the hash signed (BIP-340) by a finality provider key over its
+/// metadata, i.e.
+/// `sha256("btcstaking/{operation}/{chain_id}/{contract_address}/{fp_btc_pk_hex}/{metadata}")`,
+/// with the metadata in canonical JSON
+pub fn fp_metadata_msg_hash(
+    chain_id: &str,
+    contract_address: &str,
+    fp_btc_pk_hex: &str,
+    metadata: &FpMetadata,
+) -> StdResult<[u8; HASH_SIZE]> {
+    let mut msg = format!(
+        "btcstaking/{FP_METADATA_CONTEXT_TAG}/{chain_id}/{contract_address}/{fp_btc_pk_hex}/"
+    )
+    .into_bytes();
+    msg.extend(to_canonical_json_vec(metadata)?);
+    Ok(Sha256::digest(msg).into())
+}
+
+/// TxHash is the hash of a BTC tx, e.g. the staking tx hash that identifies a BTC delegation.
+/// The hash is kept in internal byte order, which is the order used for storage keys.
+/// Its canonical hex form is byte-reversed (as displayed by Bitcoin tools), lowercase, and
+/// `HASH_SIZE * 2` chars long
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TxHash([u8; HASH_SIZE]);
+
+impl TxHash {
+    pub const fn from_byte_array(bytes: [u8; HASH_SIZE]) -> Self {
+        TxHash(bytes)
+    }
+
+    /// from_slice builds a tx hash from its bytes, in internal byte order
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, StakingApiError> {
+        let bytes: [u8; HASH_SIZE] = bytes
+            .try_into()
+            .map_err(|_| StakingApiError::InvalidStakingTxHashLength(HASH_SIZE))?;
+        Ok(TxHash(bytes))
+    }
+
+    /// from_hex parses a tx hash from its (byte-reversed) hex form.
+    /// Upper case hex is accepted, but `to_hex` always returns lower case
+    pub fn from_hex(hex: &str) -> Result<Self, StakingApiError> {
+        if hex.len() != HASH_SIZE * 2 {
+            return Err(StakingApiError::InvalidStakingTxHash(HASH_SIZE * 2));
+        }
+        let mut bytes = [0u8; HASH_SIZE];
+        hex::decode_to_slice(hex, &mut bytes)?;
+        bytes.reverse();
+        Ok(TxHash(bytes))
+    }
+
+    /// to_hex returns the canonical (byte-reversed, lower case) hex form of the tx hash
+    pub fn to_hex(&self) -> String {
+        let mut bytes = self.0;
+        bytes.reverse();
+        hex::encode(bytes)
+    }
+
+    pub fn as_byte_array(&self) -> &[u8; HASH_SIZE] {
+        &self.0
+    }
+
+    /// reversed returns the tx hash with its bytes in reverse order, i.e. the hash that a hex
+    /// string in the other byte order than the canonical one parses to
+    pub fn reversed(&self) -> Self {
+        let mut bytes = self.0;
+        bytes.reverse();
+        TxHash(bytes)
+    }
+}
+
+impl AsRef<[u8]> for TxHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8; HASH_SIZE]> for TxHash {
+    fn as_ref(&self) -> &[u8; HASH_SIZE] {
+        &self.0
+    }
+}
+
+impl From<Txid> for TxHash {
+    fn from(txid: Txid) -> Self {
+        let mut bytes = [0u8; HASH_SIZE];
+        bytes.copy_from_slice(txid.as_ref());
+        TxHash(bytes)
+    }
+}
+
+impl FromStr for TxHash {
+    type Err = StakingApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TxHash::from_hex(s)
+    }
+}
+
+impl fmt::Display for TxHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
 #[cw_serde]
 /// btc_staking execution handlers
 pub enum ExecuteMsg {
@@ -23,7 +172,150 @@ pub enum ExecuteMsg {
     /// Used by the babylon-contract only.
     /// The Babylon contract will call this message to set the finality provider's staking power to
     /// zero when the finality provider is found to be malicious by the finality contract.
-    Slash { fp_btc_pk_hex: String },
+    Slash { fp_btc_pk_hex: BtcPkHex },
+    /// Jail a finality provider, e.g. for missing too many consecutive blocks.
+    /// Used by the babylon-contract only.
+    /// The Babylon contract routes it from the finality contract. A jailed finality provider is
+    /// excluded from the voting power table until its operator unjails it
+    Jail { fp_btc_pk_hex: BtcPkHex },
+    /// Unjail a finality provider, once the `jail_downtime` has elapsed since it was jailed.
+    /// Callable by the finality provider's address, or the address bound to its BTC key
+    Unjail { fp_btc_pk_hex: BtcPkHex },
+    /// Delegator-initiated unbonding intent.
+    /// Callable by the consumer address bound to the staker key of the delegation (see
+    /// `BindStaker`).
+    /// The delegation loses its voting power (immediately, or after the configured delay), before
+    /// Babylon reports it as unbonded
+    Unbond { staking_tx_hash: String },
+    /// Bind a BTC staker public key to the sender's (consumer) address.
+    /// `sig` is the BIP-340 signature by the staker key over `staker_binding_msg_hash`.
+    /// A new binding of the same key replaces the previous one
+    BindStaker { btc_pk_hex: BtcPkHex, sig: Binary },
+    /// Remove the binding of a BTC staker public key.
+    /// Callable by the bound address only
+    UnbindStaker { btc_pk_hex: BtcPkHex },
+    /// Register a finality provider directly on the consumer, instead of through Babylon.
+    /// The sender must be the finality provider's address, and attach the registration deposit
+    /// of the params, if any
+    RegisterFinalityProvider { fp: NewFinalityProvider },
+    /// Refund the registration deposit of a finality provider registered directly on the
+    /// consumer, once its voting power reaches the `fp_deposit_min_stake_sat` of the params, or
+    /// once it's deregistered. Slashed or removed finality providers forfeit their deposit.
+    /// Callable by the depositor only
+    RefundFpDeposit { btc_pk_hex: BtcPkHex },
+    /// Deregister a finality provider.
+    /// Callable by the finality provider's address, or the address bound to its BTC key.
+    /// The finality provider stops accepting new delegations, and exits once all its existing
+    /// delegations are unbonded
+    DeregisterFinalityProvider { btc_pk_hex: BtcPkHex },
+    /// Publish the operational metadata of a finality provider, replacing any previous one.
+    /// `sig` is the BIP-340 signature by the finality provider key over `fp_metadata_msg_hash`.
+    /// Callable by the finality provider's address, or the address bound to its BTC key
+    SetFpMetadata {
+        fp_btc_pk_hex: BtcPkHex,
+        metadata: FpMetadata,
+        sig: Binary,
+    },
+    /// Set the verbosity of the events emitted while processing BTC staking operations.
+    /// Callable by the admin only
+    SetEventVerbosity { verbosity: EventVerbosity },
+    /// Process a quarantined BTC staking operation again, e.g. after a parameters fix.
+    /// It is removed from the quarantine if it is accepted.
+    /// Callable by the admin only
+    ReprocessQuarantined { id: String },
+    /// Drop a quarantined BTC staking operation.
+    /// Callable by the admin only
+    DiscardQuarantined { id: String },
+    /// Remove a finality provider, e.g. a defunct or fraudulent one whose removal never arrives
+    /// from Babylon. Its voting power is removed, and its delegations no longer delegate to it.
+    /// The first call schedules the removal, which is executed by calling it again once
+    /// `fp_removal_delay` blocks have elapsed (right away if zero).
+    /// Callable by the admin only
+    RemoveFinalityProvider {
+        btc_pk_hex: BtcPkHex,
+        reason: String,
+    },
+    /// Allow `grantee` (e.g. a custodial service) to claim the sender's staking rewards on its
+    /// behalf.
+    /// `expiry` is the height from which the grant is no longer valid, or `None` for a grant valid
+    /// until revoked. A new grant to the same grantee replaces the previous one
+    GrantClaim {
+        grantee: String,
+        expiry: Option<u64>,
+    },
+    /// Revoke the reward claim grant of the sender to `grantee`
+    RevokeClaim { grantee: String },
+    /// Distribute the attached funds as rewards to the given finality providers.
+    /// The rewards must add up to the funds, in the `reward_denom` of the params. The commission
+    /// share of each reward goes to the finality provider's address, and the rest to its
+    /// delegators, proportionally to their stake
+    DistributeRewards { fp_distribution: Vec<RewardInfo> },
+    /// Withdraw the rewards of `staker_addr` (the sender's if `None`) to the sender.
+    /// Callable by the staker, or a grantee of an unexpired claim grant from it
+    WithdrawRewards { staker_addr: Option<String> },
+    /// `Receive` is the cw20 receive hook, for funding rewards in a cw20 token registered by the
+    /// admin. The hook message is a `Cw20HookMsg`
+    Receive(Cw20ReceiveMsg),
+    /// Register (`add`) or unregister (`remove`) cw20 token contracts as rewards.
+    /// Unregistered tokens can no longer be distributed, but the rewards distributed in them so
+    /// far remain withdrawable.
+    /// Only the admin can update the reward tokens
+    UpdateRewardTokens {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// `ContinueMigration` migrates up to `limit` entries of the first pending storage migration
+    /// (see the `MigrationProgress` query). The state section of a migration is paused until the
+    /// migration is complete.
+    ///
+    /// Anyone can continue a migration
+    ContinueMigration { limit: u32 },
+}
+
+/// `Cw20HookMsg` are the messages of the cw20 receive hook (`ExecuteMsg::Receive`)
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Distribute the received tokens as rewards to the given finality providers, as
+    /// `ExecuteMsg::DistributeRewards` does. The rewards must add up to the received amount
+    DistributeRewards { fp_distribution: Vec<RewardInfo> },
+}
+
+/// `RewardInfo` is the reward of a finality provider in a rewards distribution
+#[cw_serde]
+pub struct RewardInfo {
+    pub fp_btc_pk_hex: BtcPkHex,
+    pub reward: Uint128,
+}
+
+/// `EventVerbosity` controls how many events are emitted while processing BTC staking operations,
+/// to balance indexer needs against the block event size limits
+#[cw_serde]
+#[derive(Copy, Default)]
+pub enum EventVerbosity {
+    /// `Minimal` emits a single event per batch, with the number of operations of each kind
+    Minimal,
+    /// `Standard` emits the events of state transitions resulting from the operations, e.g.
+    /// voting power changes
+    #[default]
+    Standard,
+    /// `Debug` additionally emits an event per processed operation
+    Debug,
+}
+
+/// `BtcStakingResult` is the result of the accepted BTC staking operations, set as the response
+/// data of `BtcStaking`. Delegations are identified by their staking tx hash, in hex format
+#[cw_serde]
+#[derive(Default)]
+pub struct BtcStakingResult {
+    /// `new_fps` are the BTC public keys (in hex format) of the registered finality providers
+    pub new_fps: Vec<String>,
+    pub active_delegations: Vec<String>,
+    pub slashed_delegations: Vec<String>,
+    pub unbonded_delegations: Vec<String>,
+    /// `quarantined` are the ids of the new finality providers and active delegations that
+    /// failed validation, and were quarantined instead (see the `Quarantine` query)
+    #[serde(default)]
+    pub quarantined: Vec<String>,
 }
 
 #[cw_serde]
@@ -64,6 +356,29 @@ pub struct FinalityProvider {
     pub consumer_id: String,
 }
 
+impl NewFinalityProvider {
+    /// `normalize_pks` converts the BTC PK of the finality provider to its canonical (x-only)
+    /// encoding. An empty PK is left as is, for validation to reject it
+    pub fn normalize_pks(&mut self) -> Result<(), StakingApiError> {
+        normalize_pk_hex(&mut self.btc_pk_hex)
+    }
+}
+
+/// `normalize_pk_hex` converts the given BTC PK in hex format to its canonical encoding, unless
+/// it is empty
+fn normalize_pk_hex(pk_hex: &mut String) -> Result<(), StakingApiError> {
+    if !pk_hex.is_empty() {
+        *pk_hex = canonical_btc_pk_hex(pk_hex)?;
+    }
+    Ok(())
+}
+
+/// `normalize_pk` converts the given raw BTC PK to its canonical encoding
+fn normalize_pk(pk: &mut Binary) -> Result<(), StakingApiError> {
+    *pk = to_x_only(pk)?.to_vec().into();
+    Ok(())
+}
+
 impl From<&NewFinalityProvider> for FinalityProvider {
     fn from(new_fp: &NewFinalityProvider) -> Self {
         FinalityProvider {
@@ -201,6 +516,29 @@ pub struct ActiveBtcDelegation {
     pub params_version: u32,
 }
 
+impl ActiveBtcDelegation {
+    /// `normalize_pks` converts the BTC PKs of the staker, finality providers and covenant members
+    /// of the delegation to their canonical (x-only) encoding. Empty PKs are left as is, for
+    /// validation to reject them
+    pub fn normalize_pks(&mut self) -> Result<(), StakingApiError> {
+        normalize_pk_hex(&mut self.btc_pk_hex)?;
+        for fp_btc_pk in self.fp_btc_pk_list.iter_mut() {
+            normalize_pk_hex(fp_btc_pk)?;
+        }
+        for cov_sig in self.covenant_sigs.iter_mut() {
+            normalize_pk(&mut cov_sig.cov_pk)?;
+        }
+        let undelegation_info = &mut self.undelegation_info;
+        for cov_sig in undelegation_info.covenant_unbonding_sig_list.iter_mut() {
+            normalize_pk(&mut cov_sig.pk)?;
+        }
+        for cov_sig in undelegation_info.covenant_slashing_sigs.iter_mut() {
+            normalize_pk(&mut cov_sig.cov_pk)?;
+        }
+        Ok(())
+    }
+}
+
 /// CovenantAdaptorSignatures is a list adaptor signatures signed by the
 /// covenant with different finality provider's public keys as encryption keys
 #[cw_serde]
@@ -266,3 +604,43 @@ pub struct UnbondedBtcDelegation {
     /// It proves that the BTC delegator wants to unbond
     pub unbonding_tx_sig: Binary,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_hash_works() {
+        let hex = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let hash = TxHash::from_hex(hex).unwrap();
+        assert_eq!(hash.to_hex(), hex);
+        assert_eq!(hash.to_string(), hex);
+
+        // Same byte order as the BTC txid, in both forms
+        let txid = Txid::from_str(hex).unwrap();
+        assert_eq!(TxHash::from(txid), hash);
+        assert_eq!(hash.as_ref() as &[u8], txid.as_ref() as &[u8]);
+        assert_eq!(TxHash::from_slice(hash.as_ref()).unwrap(), hash);
+        assert_eq!(hash.reversed().to_hex(), hex::encode(hash.as_byte_array()));
+        assert_eq!(hash.reversed().reversed(), hash);
+
+        // Upper case hex is canonicalized
+        let upper = TxHash::from_hex(&hex.to_uppercase()).unwrap();
+        assert_eq!(upper, hash);
+        assert_eq!(upper.to_hex(), hex);
+
+        // Invalid hashes
+        assert_eq!(
+            TxHash::from_hex(&hex[2..]).unwrap_err(),
+            StakingApiError::InvalidStakingTxHash(HASH_SIZE * 2)
+        );
+        assert!(matches!(
+            TxHash::from_hex(&hex.replace('a', "z")).unwrap_err(),
+            StakingApiError::HexError(..)
+        ));
+        assert_eq!(
+            TxHash::from_slice(&[0u8; HASH_SIZE - 1]).unwrap_err(),
+            StakingApiError::InvalidStakingTxHashLength(HASH_SIZE)
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! canonical_json serializes values to a canonical JSON form, for payloads covered by a signature
+//! (or a hash), so that the signer and the verifier encode the same bytes whatever their JSON
+//! serializer. The form follows RFC 8785 (JCS), restricted to integer numbers:
+//! - no insignificant whitespace
+//! - object keys sorted by their UTF-16 code units, and unique
+//! - strings with the minimal escaping, i.e. only `"`, `\` and control characters are escaped
+//! - numbers are integers, without fraction, exponent, leading zeros or negative zero. Floats
+//!   are rejected, as their encoding is ambiguous (and they are not valid in contract messages)
+use cosmwasm_std::{to_json_vec, StdError, StdResult};
+use serde::Serialize;
+
+/// Maximum nesting depth of arrays and objects, bounding the recursion of the parser
+pub const MAX_DEPTH: usize = 128;
+
+/// to_canonical_json_vec serializes the given value to canonical JSON
+pub fn to_canonical_json_vec<T: Serialize + ?Sized>(value: &T) -> StdResult<Vec<u8>> {
+    canonicalize_json(&to_json_vec(value)?)
+}
+
+/// canonicalize_json re-encodes the given JSON document to canonical JSON
+pub fn canonicalize_json(json: &[u8]) -> StdResult<Vec<u8>> {
+    let mut parser = Parser { json, pos: 0 };
+    let value = parser.parse_document()?;
+    let mut out = Vec::with_capacity(json.len());
+    value.write(&mut out);
+    Ok(out)
+}
+
+fn invalid_json(msg: impl std::fmt::Display) -> StdError {
+    StdError::generic_err(format!("invalid canonical JSON: {msg}"))
+}
+
+enum Value {
+    Null,
+    Bool(bool),
+    /// An integer, validated against the canonical number grammar
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    /// Object members, sorted by key
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Null => out.extend_from_slice(b"null"),
+            Value::Bool(true) => out.extend_from_slice(b"true"),
+            Value::Bool(false) => out.extend_from_slice(b"false"),
+            Value::Number(n) => out.extend_from_slice(n.as_bytes()),
+            Value::String(s) => write_string(s, out),
+            Value::Array(items) => {
+                out.push(b'[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(b',');
+                    }
+                    item.write(out);
+                }
+                out.push(b']');
+            }
+            Value::Object(members) => {
+                out.push(b'{');
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        out.push(b',');
+                    }
+                    write_string(key, out);
+                    out.push(b':');
+                    value.write(out);
+                }
+                out.push(b'}');
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut Vec<u8>) {
+    out.push(b'"');
+    for c in s.chars() {
+        match c {
+            '"' => out.extend_from_slice(b"\\\""),
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\u{08}' => out.extend_from_slice(b"\\b"),
+            '\u{0c}' => out.extend_from_slice(b"\\f"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\t' => out.extend_from_slice(b"\\t"),
+            c if c < ' ' => out.extend_from_slice(format!("\\u{:04x}", c as u32).as_bytes()),
+            c => {
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    out.push(b'"');
+}
+
+struct Parser<'a> {
+    json: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn parse_document(&mut self) -> StdResult<Value> {
+        let value = self.parse_value(0)?;
+        self.skip_whitespace();
+        if self.pos != self.json.len() {
+            return Err(invalid_json(format!("trailing data at {}", self.pos)));
+        }
+        Ok(value)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> StdResult<u8> {
+        let b = self
+            .peek()
+            .ok_or_else(|| invalid_json("unexpected end of input"))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn expect(&mut self, expected: u8) -> StdResult<()> {
+        let pos = self.pos;
+        if self.next()? != expected {
+            return Err(invalid_json(format!(
+                "expected '{}' at {pos}",
+                expected as char
+            )));
+        }
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &[u8], value: Value) -> StdResult<Value> {
+        if !self.json[self.pos..].starts_with(literal) {
+            return Err(invalid_json(format!("unexpected token at {}", self.pos)));
+        }
+        self.pos += literal.len();
+        Ok(value)
+    }
+
+    fn parse_value(&mut self, depth: usize) -> StdResult<Value> {
+        if depth > MAX_DEPTH {
+            return Err(invalid_json(format!("nesting deeper than {MAX_DEPTH}")));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.parse_literal(b"null", Value::Null),
+            Some(b't') => self.parse_literal(b"true", Value::Bool(true)),
+            Some(b'f') => self.parse_literal(b"false", Value::Bool(false)),
+            Some(b'"') => self.parse_string().map(Value::String),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'{') => self.parse_object(depth),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(invalid_json(format!("unexpected token at {}", self.pos))),
+            None => Err(invalid_json("unexpected end of input")),
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> StdResult<Value> {
+        self.expect(b'[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => continue,
+                b']' => return Ok(Value::Array(items)),
+                _ => {
+                    return Err(invalid_json(format!(
+                        "expected ',' or ']' at {}",
+                        self.pos - 1
+                    )))
+                }
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> StdResult<Value> {
+        self.expect(b'{')?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                let key = self.parse_string()?;
+                self.skip_whitespace();
+                self.expect(b':')?;
+                let value = self.parse_value(depth + 1)?;
+                members.push((key, value));
+                self.skip_whitespace();
+                match self.next()? {
+                    b',' => continue,
+                    b'}' => break,
+                    _ => {
+                        return Err(invalid_json(format!(
+                            "expected ',' or '}}' at {}",
+                            self.pos - 1
+                        )))
+                    }
+                }
+            }
+        }
+
+        members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+        if let Some(w) = members.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(invalid_json(format!("duplicate key \"{}\"", w[0].0)));
+        }
+        Ok(Value::Object(members))
+    }
+
+    fn parse_number(&mut self) -> StdResult<Value> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let digits = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some(b'.' | b'e' | b'E')) {
+            return Err(invalid_json(format!("non-integer number at {start}")));
+        }
+        let number = std::str::from_utf8(&self.json[start..self.pos])
+            .map_err(|_| invalid_json(format!("invalid number at {start}")))?;
+        match &self.json[digits..self.pos] {
+            [] => Err(invalid_json(format!("invalid number at {start}"))),
+            [b'0', _, ..] => Err(invalid_json(format!("leading zero in number at {start}"))),
+            [b'0'] if digits > start => Err(invalid_json(format!("negative zero at {start}"))),
+            _ => Ok(Value::Number(number.to_string())),
+        }
+    }
+
+    fn parse_string(&mut self) -> StdResult<String> {
+        self.expect(b'"')?;
+        let mut s = Vec::new();
+        loop {
+            match self.next()? {
+                b'"' => break,
+                b'\\' => {
+                    let c = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{08}',
+                        b'f' => '\u{0c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.parse_unicode_escape()?,
+                        _ => {
+                            return Err(invalid_json(format!("invalid escape at {}", self.pos - 1)))
+                        }
+                    };
+                    let mut buf = [0u8; 4];
+                    s.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                b if b < b' ' => {
+                    return Err(invalid_json(format!(
+                        "unescaped control character at {}",
+                        self.pos - 1
+                    )))
+                }
+                b => s.push(b),
+            }
+        }
+        String::from_utf8(s).map_err(|e| invalid_json(e.to_string()))
+    }
+
+    /// parse_unicode_escape parses the hex digits of a `\u` escape, combining surrogate pairs
+    fn parse_unicode_escape(&mut self) -> StdResult<char> {
+        let high = self.parse_hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            self.expect(b'\\')?;
+            self.expect(b'u')?;
+            let low = self.parse_hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(invalid_json(format!(
+                    "invalid surrogate pair at {}",
+                    self.pos
+                )));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code)
+            .ok_or_else(|| invalid_json(format!("invalid code point at {}", self.pos)))
+    }
+
+    fn parse_hex4(&mut self) -> StdResult<u32> {
+        let hex = self
+            .json
+            .get(self.pos..self.pos + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .ok_or_else(|| invalid_json(format!("invalid unicode escape at {}", self.pos)))?;
+        let code = u32::from_str_radix(hex, 16)
+            .map_err(|_| invalid_json(format!("invalid unicode escape at {}", self.pos)))?;
+        self.pos += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(json: &str) -> String {
+        String::from_utf8(canonicalize_json(json.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn canonicalize_json_sorts_keys_and_strips_whitespace() {
+        assert_eq!(
+            canonical(r#" { "b" : [ 1 , { "d": null, "c": true } ], "a": "x" } "#),
+            r#"{"a":"x","b":[1,{"c":true,"d":null}]}"#
+        );
+        // Any key order gives the same encoding
+        assert_eq!(
+            canonical(r#"{"a":"x","b":[1,{"c":true,"d":null}]}"#),
+            canonical(r#"{"b":[1,{"d":null,"c":true}],"a":"x"}"#)
+        );
+        assert_eq!(canonical("[]"), "[]");
+        assert_eq!(canonical("{ }"), "{}");
+        assert_eq!(canonical("-12"), "-12");
+    }
+
+    #[test]
+    fn canonicalize_json_sorts_keys_by_utf16() {
+        // U+1F600 sorts before U+E000 in UTF-16 (surrogate 0xD83D), unlike in UTF-8
+        assert_eq!(
+            canonical("{\"\u{e000}\":1,\"\u{1f600}\":2,\"a\":3}"),
+            "{\"a\":3,\"\u{1f600}\":2,\"\u{e000}\":1}"
+        );
+    }
+
+    #[test]
+    fn canonicalize_json_normalizes_escapes() {
+        assert_eq!(
+            canonical(r#""A\/é😀\"\\\n\u001f""#),
+            "\"A/\u{e9}\u{1f600}\\\"\\\\\\n\\u001f\""
+        );
+    }
+
+    #[test]
+    fn canonicalize_json_rejects_ambiguous_input() {
+        for json in [
+            "1.0",
+            "1e3",
+            "-0",
+            "01",
+            "-",
+            r#"{"a":1,"a":2}"#,
+            r#"{"a":1} 2"#,
+            r#""\ud83d""#,
+            "\"\n\"",
+            "[1,]",
+            "nul",
+            "",
+        ] {
+            assert!(canonicalize_json(json.as_bytes()).is_err(), "{json}");
+        }
+        let deep = "[".repeat(MAX_DEPTH + 2) + &"]".repeat(MAX_DEPTH + 2);
+        assert!(canonicalize_json(deep.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn to_canonical_json_vec_works() {
+        #[derive(Serialize)]
+        struct Payload {
+            staker_addr: String,
+            amount: cosmwasm_std::Uint128,
+            height: u64,
+            memo: Option<String>,
+        }
+        let payload = Payload {
+            staker_addr: "bbn1staker".to_string(),
+            amount: 1_000u128.into(),
+            height: 42,
+            memo: None,
+        };
+        assert_eq!(
+            to_canonical_json_vec(&payload).unwrap(),
+            br#"{"amount":"1000","height":42,"memo":null,"staker_addr":"bbn1staker"}"#
+        );
+    }
+}
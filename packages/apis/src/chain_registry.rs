@@ -0,0 +1,135 @@
+//! chain_registry is a registry of the known Babylon networks, with the parameters a consumer
+//! deployment connecting to them is expected to use.
+//! Entries must be kept in sync with the parameters of the Babylon networks; new networks are
+//! only appended
+use babylon_bitcoin::chain_params::Network;
+use cosmwasm_std::{StdError, StdResult};
+
+/// KnownNetwork is an entry of the registry of known Babylon networks
+#[derive(Clone, Debug, PartialEq)]
+pub struct KnownNetwork {
+    /// name identifies the network in the registry, e.g. `mainnet`
+    pub name: &'static str,
+    /// chain_id is the chain id of the Babylon chain
+    pub chain_id: &'static str,
+    /// btc_network is the BTC network Babylon checkpoints to
+    pub btc_network: Network,
+    /// babylon_tag is the hex-encoded tag of the Babylon checkpoints
+    pub babylon_tag: &'static str,
+    /// btc_confirmation_depth is the depth after which BTC checkpoints are confirmed
+    pub btc_confirmation_depth: u64,
+    /// checkpoint_finalization_timeout is the depth after which BTC checkpoints are finalised
+    pub checkpoint_finalization_timeout: u64,
+    /// epoch_interval is the number of Babylon blocks per epoch, starting from the genesis epoch
+    pub epoch_interval: u64,
+}
+
+/// The known Babylon networks
+pub const KNOWN_NETWORKS: &[KnownNetwork] = &[
+    KnownNetwork {
+        name: "mainnet",
+        chain_id: "bbn-1",
+        btc_network: Network::Mainnet,
+        babylon_tag: "62627931",
+        btc_confirmation_depth: 10,
+        checkpoint_finalization_timeout: 300,
+        epoch_interval: 360,
+    },
+    KnownNetwork {
+        name: "testnet",
+        chain_id: "bbn-test-5",
+        btc_network: Network::Signet,
+        babylon_tag: "62627434",
+        btc_confirmation_depth: 10,
+        checkpoint_finalization_timeout: 100,
+        epoch_interval: 360,
+    },
+];
+
+/// known_network returns the known Babylon network with the given name or chain id
+pub fn known_network(name_or_chain_id: &str) -> StdResult<&'static KnownNetwork> {
+    KNOWN_NETWORKS
+        .iter()
+        .find(|n| n.name == name_or_chain_id || n.chain_id == name_or_chain_id)
+        .ok_or_else(|| {
+            StdError::generic_err(format!("unknown Babylon network: {name_or_chain_id}"))
+        })
+}
+
+impl KnownNetwork {
+    /// validate checks that the given deployment parameters are the ones of the network
+    pub fn validate(
+        &self,
+        btc_network: &Network,
+        babylon_tag: &str,
+        btc_confirmation_depth: u64,
+        checkpoint_finalization_timeout: u64,
+    ) -> StdResult<()> {
+        let mismatch = |param: &str, expected: &dyn std::fmt::Debug, got: &dyn std::fmt::Debug| {
+            StdError::generic_err(format!(
+                "{param} mismatch for Babylon {}: expected {expected:?}, got {got:?}",
+                self.chain_id
+            ))
+        };
+        if btc_network != &self.btc_network {
+            return Err(mismatch("network", &self.btc_network, btc_network));
+        }
+        if !babylon_tag.eq_ignore_ascii_case(self.babylon_tag) {
+            return Err(mismatch("babylon_tag", &self.babylon_tag, &babylon_tag));
+        }
+        if btc_confirmation_depth != self.btc_confirmation_depth {
+            return Err(mismatch(
+                "btc_confirmation_depth",
+                &self.btc_confirmation_depth,
+                &btc_confirmation_depth,
+            ));
+        }
+        if checkpoint_finalization_timeout != self.checkpoint_finalization_timeout {
+            return Err(mismatch(
+                "checkpoint_finalization_timeout",
+                &self.checkpoint_finalization_timeout,
+                &checkpoint_finalization_timeout,
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_networks_are_unique() {
+        for (i, network) in KNOWN_NETWORKS.iter().enumerate() {
+            assert_eq!(network.babylon_tag.len(), 8);
+            hex::decode(network.babylon_tag).unwrap();
+            assert!(KNOWN_NETWORKS[i + 1..]
+                .iter()
+                .all(|n| n.name != network.name && n.chain_id != network.chain_id));
+        }
+    }
+
+    #[test]
+    fn known_network_validation_works() {
+        let mainnet = known_network("mainnet").unwrap();
+        assert_eq!(known_network("bbn-1").unwrap(), mainnet);
+        known_network("bbn-devnet").unwrap_err();
+
+        mainnet
+            .validate(&Network::Mainnet, "62627931", 10, 300)
+            .unwrap();
+        mainnet
+            .validate(&Network::Signet, "62627931", 10, 300)
+            .unwrap_err();
+        mainnet
+            .validate(&Network::Mainnet, "01020304", 10, 300)
+            .unwrap_err();
+        mainnet
+            .validate(&Network::Mainnet, "62627931", 6, 300)
+            .unwrap_err();
+        mainnet
+            .validate(&Network::Mainnet, "62627931", 10, 100)
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,212 @@
+//! compression stores large state values (e.g. the covenant signature arrays proving delegations)
+//! deflated, trading some gas for decompression against storage gas and state size.
+//! Compression of written values is enabled by the `compression` feature. Values are compressed
+//! only when that makes them smaller, and reading accepts both compressed and plain values,
+//! so that maps can switch to compression without migrating their existing entries
+use cosmwasm_std::{from_json, to_json_vec, Binary, Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, Map, PrimaryKey};
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Maximum size of a decompressed value, bounding the memory used to read a value
+pub const MAX_DECOMPRESSED_SIZE: usize = 1024 * 1024;
+
+/// Deflate compression level (0-10). Values are written once and read many times, so the best
+/// compression is favoured over compression speed
+#[cfg(feature = "compression")]
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// Deflated is the stored form of a compressed value
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Deflated {
+    /// deflate is the deflated JSON encoding of the value
+    deflate: Binary,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored<T> {
+    Deflated(Deflated),
+    Plain(T),
+}
+
+/// Compressed is a value stored compressed if that makes it smaller.
+/// It is (de)serialized as a `{"deflate": <base64>}` object if compressed, or as the plain value
+/// otherwise
+#[derive(Clone, Debug, PartialEq)]
+pub struct Compressed<T>(pub T);
+
+impl<T: Serialize> Serialize for Compressed<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[cfg(feature = "compression")]
+        if let Some(deflated) = deflate(&self.0).map_err(serde::ser::Error::custom)? {
+            return deflated.serialize(serializer);
+        }
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Compressed<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Stored::<T>::deserialize(deserializer)? {
+            Stored::Plain(value) => Ok(Compressed(value)),
+            Stored::Deflated(deflated) => {
+                inflate(&deflated).map(Compressed).map_err(D::Error::custom)
+            }
+        }
+    }
+}
+
+/// deflate returns the compressed form of the given value, or `None` if it is not smaller than
+/// the plain value
+#[cfg(feature = "compression")]
+fn deflate<T: Serialize>(value: &T) -> StdResult<Option<Deflated>> {
+    let json = to_json_vec(value)?;
+    let deflated = miniz_oxide::deflate::compress_to_vec(&json, COMPRESSION_LEVEL);
+    // `{"deflate":"<base64>"}`
+    let stored_len = 14 + deflated.len().div_ceil(3) * 4;
+    if stored_len >= json.len() {
+        return Ok(None);
+    }
+    Ok(Some(Deflated {
+        deflate: Binary::new(deflated),
+    }))
+}
+
+fn inflate<T: DeserializeOwned>(deflated: &Deflated) -> StdResult<T> {
+    let json = miniz_oxide::inflate::decompress_to_vec_with_limit(
+        deflated.deflate.as_slice(),
+        MAX_DECOMPRESSED_SIZE,
+    )
+    .map_err(|e| StdError::generic_err(format!("Failed to decompress value: {e}")))?;
+    from_json(json)
+}
+
+/// CompressedMap is a map storing its values compressed (see `Compressed`), with the same
+/// interface as `Map`
+pub struct CompressedMap<K, T> {
+    map: Map<K, Compressed<T>>,
+}
+
+impl<K, T> CompressedMap<K, T> {
+    pub const fn new(namespace: &'static str) -> Self {
+        CompressedMap {
+            map: Map::new(namespace),
+        }
+    }
+}
+
+impl<'a, K, T> CompressedMap<K, T>
+where
+    K: PrimaryKey<'a>,
+    T: Serialize + DeserializeOwned,
+{
+    pub fn save(&self, storage: &mut dyn Storage, key: K, value: &T) -> StdResult<()> {
+        storage.set(&self.map.key(key), &to_json_vec(&Compressed(value))?);
+        Ok(())
+    }
+
+    pub fn remove(&self, storage: &mut dyn Storage, key: K) {
+        self.map.remove(storage, key)
+    }
+
+    pub fn load(&self, storage: &dyn Storage, key: K) -> StdResult<T> {
+        self.map.load(storage, key).map(|value| value.0)
+    }
+
+    pub fn may_load(&self, storage: &dyn Storage, key: K) -> StdResult<Option<T>> {
+        Ok(self.map.may_load(storage, key)?.map(|value| value.0))
+    }
+
+    pub fn has(&self, storage: &dyn Storage, key: K) -> bool {
+        self.map.has(storage, key)
+    }
+
+    pub fn range_raw<'c>(
+        &self,
+        storage: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(Vec<u8>, T)>> + 'c>
+    where
+        T: 'c,
+    {
+        Box::new(
+            self.map
+                .range_raw(storage, min, max, order)
+                .map(|item| item.map(|(key, value)| (key, value.0))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const LEGACY: Map<u64, Vec<Vec<u8>>> = Map::new("sigs");
+    const SIGS: CompressedMap<u64, Vec<Vec<u8>>> = CompressedMap::new("sigs");
+
+    fn sigs(n: usize) -> Vec<Vec<u8>> {
+        (0..n).map(|i| vec![i as u8; 64]).collect()
+    }
+
+    #[test]
+    fn compressed_map_works() {
+        let mut storage = MockStorage::new();
+
+        // Plain values are read as is
+        LEGACY.save(&mut storage, 1, &sigs(9)).unwrap();
+        assert_eq!(SIGS.load(&storage, 1).unwrap(), sigs(9));
+
+        SIGS.save(&mut storage, 2, &sigs(9)).unwrap();
+        SIGS.save(&mut storage, 3, &sigs(1)).unwrap();
+        assert_eq!(SIGS.load(&storage, 2).unwrap(), sigs(9));
+        assert!(SIGS.has(&storage, 3));
+        assert!(SIGS.may_load(&storage, 4).unwrap().is_none());
+        SIGS.load(&storage, 4).unwrap_err();
+        let values = SIGS
+            .range_raw(
+                &storage,
+                Some(Bound::exclusive(1u64)),
+                None,
+                Order::Ascending,
+            )
+            .map(|item| item.map(|(_, value)| value.len()))
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(values, vec![9, 1]);
+
+        SIGS.remove(&mut storage, 3);
+        assert!(!SIGS.has(&storage, 3));
+    }
+
+    #[test]
+    fn compression_works() {
+        let mut storage = MockStorage::new();
+        SIGS.save(&mut storage, 1, &sigs(9)).unwrap();
+        SIGS.save(&mut storage, 2, &vec![]).unwrap();
+        let plain = to_json_vec(&sigs(9)).unwrap();
+        let stored = storage.get(&SIGS.map.key(1)).unwrap();
+        if cfg!(feature = "compression") {
+            assert!(stored.starts_with(b"{\"deflate\":"));
+            assert!(stored.len() < plain.len());
+        } else {
+            assert_eq!(stored, plain);
+        }
+        // Values that do not shrink are stored plain
+        assert_eq!(storage.get(&SIGS.map.key(2)).unwrap(), b"[]");
+
+        // Compressed values are bounded in size
+        let bomb = Deflated {
+            deflate: Binary::new(miniz_oxide::deflate::compress_to_vec(
+                &vec![b' '; MAX_DECOMPRESSED_SIZE + 1],
+                1,
+            )),
+        };
+        storage.set(&SIGS.map.key(3), &to_json_vec(&bomb).unwrap());
+        SIGS.load(&storage, 3).unwrap_err();
+    }
+}
@@ -0,0 +1,78 @@
+//! contract_version gates the migrations of the contracts on their stored `cw2` contract version,
+//! so that a contract can only be migrated to a newer (or the same) version of its own code
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cw2::{get_contract_version, set_contract_version, CONTRACT};
+
+/// ensure_upgrade checks that the contract stored as `contract_name` can be migrated to the code
+/// at `code_version`, i.e. that it is not migrated to another contract or downgraded, and stores
+/// the new version.
+/// Returns the stored version before the migration, or `None` if the contract predates versioning
+pub fn ensure_upgrade(
+    storage: &mut dyn Storage,
+    contract_name: &str,
+    code_version: &str,
+) -> StdResult<Option<String>> {
+    let stored = if CONTRACT.exists(storage) {
+        Some(get_contract_version(storage)?)
+    } else {
+        None
+    };
+    if let Some(stored) = &stored {
+        if stored.contract != contract_name {
+            return Err(StdError::generic_err(format!(
+                "Cannot migrate contract {} to {contract_name}",
+                stored.contract
+            )));
+        }
+        if parse_version(&stored.version)? > parse_version(code_version)? {
+            return Err(StdError::generic_err(format!(
+                "Cannot downgrade {contract_name} from version {} to {code_version}",
+                stored.version
+            )));
+        }
+    }
+    set_contract_version(storage, contract_name, code_version)?;
+    Ok(stored.map(|v| v.version))
+}
+
+/// parse_version parses the numeric components of a `major.minor.patch` version, ignoring any
+/// pre-release or build suffix
+fn parse_version(version: &str) -> StdResult<Vec<u64>> {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    core.split('.')
+        .map(|n| {
+            n.parse()
+                .map_err(|_| StdError::generic_err(format!("Invalid contract version {version}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn ensure_upgrade_works() {
+        let mut storage = MockStorage::new();
+
+        // Contracts predating versioning are versioned upon migration
+        assert_eq!(ensure_upgrade(&mut storage, "c", "0.9.0").unwrap(), None);
+        assert_eq!(get_contract_version(&storage).unwrap().version, "0.9.0");
+
+        // Upgrades and re-migrations to the same version are allowed
+        let prev = ensure_upgrade(&mut storage, "c", "0.10.0").unwrap();
+        assert_eq!(prev.as_deref(), Some("0.9.0"));
+        let prev = ensure_upgrade(&mut storage, "c", "0.10.0-rc.1").unwrap();
+        assert_eq!(prev.as_deref(), Some("0.10.0"));
+
+        // Downgrades and migrations to another contract are not
+        ensure_upgrade(&mut storage, "c", "0.9.9").unwrap_err();
+        ensure_upgrade(&mut storage, "other", "0.10.0").unwrap_err();
+        ensure_upgrade(&mut storage, "c", "latest").unwrap_err();
+        assert_eq!(
+            get_contract_version(&storage).unwrap().version,
+            "0.10.0-rc.1"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! cw20 messages exchanged with cw20 token contracts.
+//! They follow the wire format of the `cw20` specification, for the subset used by the contracts
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_binary, Binary, CosmosMsg, StdResult, Uint128, WasmMsg};
+
+/// `Cw20ReceiveMsg` is sent by a cw20 token contract to the recipient of a `Send`, i.e. the
+/// `Receive` hook of the recipient contract. The sender of the hook is the token contract
+#[cw_serde]
+pub struct Cw20ReceiveMsg {
+    /// `sender` is the address that sent the tokens
+    pub sender: String,
+    pub amount: Uint128,
+    /// `msg` is the JSON message of the recipient contract to execute with the tokens
+    pub msg: Binary,
+}
+
+/// `Cw20Coin` is an amount of a cw20 token, by token contract address
+#[cw_serde]
+pub struct Cw20Coin {
+    pub address: String,
+    pub amount: Uint128,
+}
+
+/// `Cw20ExecuteMsg` are the execute messages of a cw20 token contract the contracts send
+#[cw_serde]
+pub enum Cw20ExecuteMsg {
+    /// `Transfer` moves `amount` tokens from the sender to `recipient`
+    Transfer { recipient: String, amount: Uint128 },
+}
+
+/// `cw20_transfer_msg` returns the message transferring `amount` of the given cw20 token to
+/// `recipient`
+pub fn cw20_transfer_msg<T>(
+    token: &str,
+    recipient: &str,
+    amount: Uint128,
+) -> StdResult<CosmosMsg<T>> {
+    Ok(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
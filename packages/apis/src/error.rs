@@ -1,6 +1,7 @@
 use hex::FromHexError;
 use thiserror::Error;
 
+use cosmwasm_schema::cw_serde;
 use cosmwasm_std::StdError;
 
 #[derive(Error, Debug, PartialEq)]
@@ -13,10 +14,14 @@ pub enum StakingApiError {
     HexError(#[from] FromHexError),
     #[error("Staking tx hash hex string is not {0} chars long")]
     InvalidStakingTxHash(usize),
+    #[error("Staking tx hash is not {0} bytes long")]
+    InvalidStakingTxHashLength(usize),
     #[error("Invalid Btc tx: {0}")]
     InvalidBtcTx(String),
     #[error("Empty Btc public key")]
     EmptyBtcPk,
+    #[error("Invalid Btc public key hex (expected {} or {} hex chars): {0}", 2 * crate::btc_pk::BTC_PK_SIZE, 2 * crate::btc_pk::COMPRESSED_BTC_PK_SIZE)]
+    InvalidBtcPkHex(String),
     #[error("Empty Btc private key")]
     EmptyBtcSk,
     #[error("Empty proof of possession")]
@@ -37,6 +42,8 @@ pub enum StakingApiError {
     ErrInvalidUnbondingTime(u32, u32),
     #[error("Empty signature from the delegator")]
     EmptySignature,
+    #[error("Invalid commission rate: {0}, max: 1")]
+    InvalidCommission(String),
     #[error("Description error: {0}")]
     DescriptionErr(String),
 }
@@ -46,3 +53,259 @@ impl StakingApiError {
         StakingApiError::DescriptionErr(msg.into())
     }
 }
+
+/// ErrorCategory is the category of a contract error.
+/// Each category owns a range of 100 error codes, i.e. codes of category `c` are in
+/// `[c * 100 + 1, c * 100 + 99]`
+#[cw_serde]
+#[derive(Copy)]
+pub enum ErrorCategory {
+    /// Generic, encoding and decoding errors
+    Std = 1,
+    /// Authorization errors
+    Auth = 2,
+    /// IBC errors
+    Ibc = 3,
+    /// Configuration errors
+    Config = 4,
+    /// BTC light client errors
+    BtcLightClient = 5,
+    /// Babylon epoch chain errors
+    BabylonEpochChain = 6,
+    /// CZ header chain errors
+    CzHeaderChain = 7,
+    /// BTC staking errors
+    Staking = 8,
+    /// Finality errors
+    Finality = 9,
+    /// Signature and proof verification errors
+    Crypto = 10,
+}
+
+/// ErrorCode is an entry of a contract's error code table.
+/// Codes are stable, i.e. they are never reused or renumbered; new codes are only appended
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErrorCode {
+    pub code: u32,
+    pub category: ErrorCategory,
+    pub description: &'static str,
+}
+
+#[cw_serde]
+pub struct ErrorCodeResponse {
+    pub code: u32,
+    pub category: ErrorCategory,
+    pub description: String,
+}
+
+impl From<&ErrorCode> for ErrorCodeResponse {
+    fn from(error_code: &ErrorCode) -> Self {
+        ErrorCodeResponse {
+            code: error_code.code,
+            category: error_code.category,
+            description: error_code.description.to_string(),
+        }
+    }
+}
+
+/// ErrorCodesResponse is the table of error codes of a contract
+#[cw_serde]
+pub struct ErrorCodesResponse {
+    pub codes: Vec<ErrorCodeResponse>,
+}
+
+impl From<&[ErrorCode]> for ErrorCodesResponse {
+    fn from(error_codes: &[ErrorCode]) -> Self {
+        ErrorCodesResponse {
+            codes: error_codes.iter().map(ErrorCodeResponse::from).collect(),
+        }
+    }
+}
+
+/// format_error formats an error message prefixed by its error code, e.g. for acknowledgements,
+/// so that clients can branch on the code without parsing the message
+pub fn format_error(code: u32, msg: impl std::fmt::Display) -> String {
+    format!("code {code}: {msg}")
+}
+
+/// parse_error splits an error message formatted by `format_error` into its error code and
+/// message. Returns `None` if the message is not prefixed by an error code
+pub fn parse_error(err: &str) -> Option<(u32, &str)> {
+    let (code, msg) = err.strip_prefix("code ")?.split_once(": ")?;
+    Some((code.parse().ok()?, msg))
+}
+
+/// validate_error_codes checks that the codes of an error code table are unique, and are in
+/// the range of their category
+pub fn validate_error_codes(error_codes: &[ErrorCode]) -> Result<(), String> {
+    let mut codes = std::collections::HashSet::new();
+    for error_code in error_codes {
+        if !codes.insert(error_code.code) {
+            return Err(format!("duplicate error code {}", error_code.code));
+        }
+        if error_code.code / 100 != error_code.category as u32 || error_code.code % 100 == 0 {
+            return Err(format!(
+                "error code {} is out of the range of its category",
+                error_code.code
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// check_error_codes checks an error code table against the given error enums, parsed from the
+/// source of their module: every variant must have a code in the table, every code of the table
+/// must belong to a variant, and the descriptions must match the `#[error]` messages of their
+/// variants. Variants forwarding the code of a wrapped error are checked through the wrapped
+/// error's enum, if listed
+pub fn check_error_codes(
+    source: &str,
+    enum_names: &[&str],
+    error_codes: &[ErrorCode],
+) -> Result<(), String> {
+    let mut covered = std::collections::HashSet::new();
+    for enum_name in enum_names {
+        let codes = parse_error_code_arms(source, enum_name)?;
+        for (variant, msg) in parse_error_variants(source, enum_name)? {
+            let code = match codes.get(&variant) {
+                Some(Some(code)) => *code,
+                Some(None) => continue,
+                None => return Err(format!("{enum_name}::{variant} has no error code")),
+            };
+            let error_code = error_codes
+                .iter()
+                .find(|error_code| error_code.code == code)
+                .ok_or_else(|| {
+                    format!("error code {code} of {enum_name}::{variant} is not in the table")
+                })?;
+            if !describes(error_code.description, &msg) {
+                return Err(format!(
+                    "description {:?} of error code {code} does not match the message {msg:?} of {enum_name}::{variant}",
+                    error_code.description
+                ));
+            }
+            covered.insert(code);
+        }
+    }
+    match error_codes
+        .iter()
+        .find(|error_code| !covered.contains(&error_code.code))
+    {
+        Some(error_code) => Err(format!("error code {} has no variant", error_code.code)),
+        None => Ok(()),
+    }
+}
+
+/// describes returns whether the given description matches the given `#[error]` message: it is
+/// the message itself if the message has no arguments, or starts with the message's leading text
+/// otherwise. Messages made of a wrapped error only can be described freely
+fn describes(description: &str, msg: &str) -> bool {
+    if msg == "{0}" {
+        return true;
+    }
+    if !msg.contains('{') {
+        return description == msg;
+    }
+    let head = msg
+        .split([':', '{', '(', '`', '.'])
+        .next()
+        .unwrap_or_default()
+        .trim_end();
+    description
+        .get(..head.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(head))
+}
+
+/// parse_error_variants returns the variants of the given error enum, along with their
+/// `#[error]` message, from the source of its module
+fn parse_error_variants(source: &str, enum_name: &str) -> Result<Vec<(String, String)>, String> {
+    let start = source
+        .find(&format!("pub enum {enum_name} {{"))
+        .ok_or_else(|| format!("enum {enum_name} not found"))?;
+    let mut variants = vec![];
+    let mut attr: Option<String> = None;
+    let mut msg: Option<String> = None;
+    for line in source[start..].lines().skip(1) {
+        if line == "}" {
+            break;
+        }
+        let line = line.trim();
+        if let Some(attr_text) = attr.as_mut() {
+            attr_text.push_str(line);
+        } else if line.starts_with("#[error(") {
+            attr = Some(line.to_string());
+        } else if line.starts_with("#[") || line.starts_with("//") {
+            continue;
+        } else if let Some(msg) = msg.take() {
+            let variant: String = line
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                .collect();
+            variants.push((variant, msg));
+            continue;
+        } else {
+            continue;
+        }
+        if attr
+            .as_ref()
+            .is_some_and(|attr_text| attr_text.ends_with(")]"))
+        {
+            let attr_text = attr.take().unwrap_or_default();
+            msg = Some(parse_string_literal(&attr_text).ok_or_else(|| {
+                format!("invalid #[error] attribute in {enum_name}: {attr_text}")
+            })?);
+        }
+    }
+    Ok(variants)
+}
+
+/// parse_string_literal returns the content of the first string literal of the given text
+fn parse_string_literal(text: &str) -> Option<String> {
+    let mut chars = text[text.find('"')? + 1..].chars();
+    let mut literal = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => literal.push(chars.next()?),
+            '"' => return Some(literal),
+            c => literal.push(c),
+        }
+    }
+    None
+}
+
+/// parse_error_code_arms returns the codes of the variants of the given error enum, from the
+/// arms of its `code` method in the source of its module. Variants forwarding the code of a
+/// wrapped error have no code of their own
+fn parse_error_code_arms(
+    source: &str,
+    enum_name: &str,
+) -> Result<std::collections::HashMap<String, Option<u32>>, String> {
+    let impl_start = source
+        .find(&format!("impl {enum_name} {{"))
+        .ok_or_else(|| format!("impl {enum_name} not found"))?;
+    let code_start = source[impl_start..]
+        .find("fn code(&self) -> u32")
+        .ok_or_else(|| format!("{enum_name}::code not found"))?;
+    let prefix = format!("{enum_name}::");
+    let mut codes = std::collections::HashMap::new();
+    for line in source[impl_start + code_start..].lines().skip(1) {
+        if line == "    }" {
+            break;
+        }
+        let Some((patterns, code)) = line.split_once("=>") else {
+            continue;
+        };
+        let code = code.trim().trim_end_matches(',').parse().ok();
+        for pattern in patterns.split('|') {
+            let Some(variant) = pattern.trim().strip_prefix(&prefix) else {
+                continue;
+            };
+            let variant: String = variant
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                .collect();
+            codes.insert(variant, code);
+        }
+    }
+    Ok(codes)
+}
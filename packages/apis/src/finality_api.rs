@@ -5,8 +5,9 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::Binary;
 
 use babylon_merkle::Proof;
+use sha2::{Digest, Sha256};
 
-use crate::Bytes;
+use crate::{sudo_api, Bytes};
 
 #[cw_serde]
 /// babylon_finality execution handlers
@@ -28,10 +29,18 @@ pub enum ExecuteMsg {
         /// Currently, it's the root of the Merkle tree that includes the public randomness
         commitment: Binary,
         /// `signature` is the signature on (start_height || num_pub_rand || commitment) signed by
-        /// the SK corresponding to `fp_pubkey_hex`.
+        /// the SK corresponding to `fp_pubkey_hex`, prefixed by the signing context (see
+        /// `SigningContext`).
         /// This prevents others committing public randomness on behalf of `fp_pubkey_hex`
         signature: Binary,
     },
+    /// Committing sequences of public randomness for many finality providers at once, e.g. by
+    /// an operator running many finality providers.
+    /// Every commitment is verified and stored independently: an invalid commitment does not
+    /// fail the others, but is reported in the `commit_public_randomness_batch` event instead
+    CommitPublicRandomnessBatch {
+        commits: Vec<PublicRandomnessCommit>,
+    },
     /// Submit Finality Signature.
     ///
     /// This is a message that can be called by a finality provider to submit their finality
@@ -47,7 +56,30 @@ pub enum ExecuteMsg {
         proof: Proof,
         block_hash: Binary,
         signature: Binary,
+        /// `payload_version` is the vote payload version the finality provider daemon speaks.
+        /// Absent for daemons predating the version handshake, which are on version 0.
+        /// Submissions below the minimum version the contract supports are rejected
+        #[serde(default)]
+        payload_version: Option<u32>,
     },
+    /// `ContinueMigration` migrates up to `limit` entries of the first pending storage migration
+    /// (see the `MigrationProgress` query). The state section of a migration is paused until the
+    /// migration is complete.
+    ///
+    /// Anyone can continue a migration
+    ContinueMigration { limit: u32 },
+}
+
+/// `PublicRandomnessCommit` is a public randomness commitment of a batch (see
+/// `ExecuteMsg::CommitPublicRandomnessBatch`). Its fields are the ones of
+/// `ExecuteMsg::CommitPublicRandomness`
+#[cw_serde]
+pub struct PublicRandomnessCommit {
+    pub fp_pubkey_hex: String,
+    pub start_height: u64,
+    pub num_pub_rand: u64,
+    pub commitment: Binary,
+    pub signature: Binary,
 }
 
 /// `IndexedBlock` is the necessary metadata and finalization status of a block
@@ -115,19 +147,288 @@ pub struct Evidence {
     pub fork_finality_sig: Bytes,
 }
 
+/// `SudoMsg` are the privileged calls of the consumer chain to the finality contract, i.e. the
+/// per-block hooks shared by all the contracts, and the calls specific to the finality contract.
+/// Both are encoded as is, e.g. `{"begin_block": {..}}` or `{"native_votes": {..}}`.
+/// They are published in the contract's schema, so that the consumer chain wiring the sudo calls
+/// can be generated from it
 #[cw_serde]
+#[serde(untagged)]
 pub enum SudoMsg {
-    /// The SDK should call SudoMsg::BeginBlock{} once per block (in BeginBlock).
-    /// It allows the staking module to index the BTC height, and update the power
-    /// distribution of the active Finality Providers.
-    BeginBlock {
-        hash_hex: String,
-        app_hash_hex: String,
-    },
-    /// The SDK should call SudoMsg::EndBlock{} once per block (in EndBlock).
-    /// It allows the finality module to index blocks and tally the finality provider votes
-    EndBlock {
-        hash_hex: String,
-        app_hash_hex: String,
+    Block(sudo_api::SudoMsg),
+    Finality(FinalitySudoMsg),
+}
+
+impl From<sudo_api::SudoMsg> for SudoMsg {
+    fn from(msg: sudo_api::SudoMsg) -> Self {
+        SudoMsg::Block(msg)
+    }
+}
+
+impl From<FinalitySudoMsg> for SudoMsg {
+    fn from(msg: FinalitySudoMsg) -> Self {
+        SudoMsg::Finality(msg)
+    }
+}
+
+/// `FinalitySudoMsg` are the privileged calls of the consumer chain specific to the finality
+/// contract
+#[cw_serde]
+pub enum FinalitySudoMsg {
+    /// The SDK should call SudoMsg::NativeVotes{} to report the native-staked validators of the
+    /// consumer chain at the given height, along with whether they voted for the block.
+    /// It allows the finality module to require a native stake quorum for finalization as well
+    NativeVotes {
+        height: u64,
+        validators: Vec<NativeValidatorVote>,
     },
+    /// Governance can call SudoMsg::InvalidateVotes{} to strike the votes of the given finality
+    /// providers (BTC PKs in hex) at the given height, e.g. votes produced by a compromised key
+    /// before it was tombstoned. The struck votes cannot be cast again, and the block is tallied
+    /// again without them. The intervention is recorded for auditing
+    InvalidateVotes { height: u64, fp_pks: Vec<String> },
+    /// Governance can call SudoMsg::SetTallyStartHeight{} to skip tallying the heights below
+    /// `height`, e.g. when the finality contract joins an already-running consumer chain.
+    /// The height cannot go below the next height to be tallied
+    SetTallyStartHeight { height: u64 },
+    /// Governance can call SudoMsg::SetMinVotePayloadVersion{} to set the minimum vote payload
+    /// version finality signature submissions must declare, so that the finality provider fleet
+    /// can be upgraded to a new protocol version in a coordinated way
+    SetMinVotePayloadVersion { version: u32 },
+}
+
+/// `NativeValidatorVote` is the vote of a native-staked validator of the consumer chain on a block
+#[cw_serde]
+pub struct NativeValidatorVote {
+    /// `address` is the operator address of the validator
+    pub address: String,
+    /// `power` is the voting power of the validator at the block height
+    pub power: u64,
+    /// `voted` is whether the validator voted for (signed) the block
+    pub voted: bool,
+}
+
+/// Legacy signing context version, i.e. signed payloads carry no domain separation tag
+pub const SIGNING_CONTEXT_V0: u32 = 0;
+/// Signing context version binding signed payloads to the chain id, contract address and
+/// consumer id
+pub const SIGNING_CONTEXT_V1: u32 = 1;
+/// Signing context version of structured payloads, i.e. V1 payloads with an EIP-191-like header,
+/// and length-prefixed variable-size fields (see `SigningContext::finality_sig_msg`)
+pub const SIGNING_CONTEXT_V2: u32 = 2;
+/// Latest supported signing context version
+pub const SIGNING_CONTEXT_LATEST: u32 = SIGNING_CONTEXT_V2;
+
+/// First byte of structured signed payloads. As in EIP-191, it is not a valid first byte of a
+/// Bitcoin transaction or of an RLP / protobuf encoded message, so that structured payloads
+/// cannot be confused with them
+pub const STRUCTURED_PAYLOAD_HEADER: u8 = 0x19;
+
+const SIGNING_CONTEXT_PROTOCOL: &str = "btcstaking";
+/// Signing context operation tag of public randomness commitments
+pub const COMMIT_PUB_RAND_CONTEXT_TAG: &str = "fp_rand_commit";
+/// Signing context operation tag of finality signatures
+pub const FINALITY_SIG_CONTEXT_TAG: &str = "fp_fin_vote";
+
+/// `SigningContext` is the domain finality provider signatures are bound to.
+/// Binding signatures to a chain, contract and consumer prevents a signature from being replayed
+/// against another deployment accepting the same finality provider key
+#[derive(Clone, Debug, PartialEq)]
+pub struct SigningContext {
+    /// `version` is the signing context version. Version 0 is the legacy scheme, without domain
+    /// separation
+    pub version: u32,
+    pub chain_id: String,
+    pub contract_address: String,
+    pub consumer_id: String,
+}
+
+impl SigningContext {
+    /// `validate_version` checks the given signing context version is supported
+    pub fn validate_version(version: u32) -> Result<(), String> {
+        if version > SIGNING_CONTEXT_LATEST {
+            return Err(format!("unsupported signing context version {version}"));
+        }
+        Ok(())
+    }
+
+    /// `prefix` returns the domain separation tag of the given operation, prepended to the
+    /// signed payloads. It is empty for the legacy version, and
+    /// `sha256("btcstaking/{version}/{operation}/{chain_id}/{contract_address}/{consumer_id}")`
+    /// otherwise
+    pub fn prefix(&self, operation: &str) -> Vec<u8> {
+        if self.version == SIGNING_CONTEXT_V0 {
+            return vec![];
+        }
+        let ctx = format!(
+            "{SIGNING_CONTEXT_PROTOCOL}/{}/{operation}/{}/{}/{}",
+            self.version, self.chain_id, self.contract_address, self.consumer_id
+        );
+        Sha256::digest(ctx.as_bytes()).to_vec()
+    }
+
+    /// `header` returns the header of the signed payloads of the given operation, i.e. the prefix
+    /// for versions up to V1, and (0x19 || version || prefix) for structured payloads, with
+    /// `version` as a single byte
+    fn header(&self, operation: &str) -> Vec<u8> {
+        let prefix = self.prefix(operation);
+        if self.version < SIGNING_CONTEXT_V2 {
+            return prefix;
+        }
+        let mut header = Vec::with_capacity(2 + prefix.len());
+        header.push(STRUCTURED_PAYLOAD_HEADER);
+        header.push(self.version as u8);
+        header.extend_from_slice(&prefix);
+        header
+    }
+
+    /// `extend_bytes` appends a variable-size field to the given payload, prefixed by its length
+    /// (as a big-endian u32) for structured payloads
+    fn extend_bytes(&self, msg: &mut Vec<u8>, field: &[u8]) {
+        if self.version >= SIGNING_CONTEXT_V2 {
+            msg.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        }
+        msg.extend_from_slice(field);
+    }
+
+    /// `commit_pub_rand_msg` returns the payload signed by a public randomness commitment, i.e.
+    /// (header || start_height || num_pub_rand || commitment), with the commitment length
+    /// prefixed for structured payloads (see `finality_sig_msg`)
+    pub fn commit_pub_rand_msg(
+        &self,
+        start_height: u64,
+        num_pub_rand: u64,
+        commitment: &[u8],
+    ) -> Vec<u8> {
+        let mut msg = self.header(COMMIT_PUB_RAND_CONTEXT_TAG);
+        msg.extend_from_slice(&start_height.to_be_bytes());
+        msg.extend_from_slice(&num_pub_rand.to_be_bytes());
+        self.extend_bytes(&mut msg, commitment);
+        msg
+    }
+
+    /// `finality_sig_msg` returns the payload signed by a finality signature (vote).
+    ///
+    /// The byte layout is, by version:
+    /// - V0: `block_height || block_hash`
+    /// - V1: `prefix || block_height || block_hash`
+    /// - V2: `0x19 || 0x02 || prefix || block_height || len(block_hash) || block_hash`
+    ///
+    /// where `prefix` is the 32-byte domain separation tag (see `prefix`), `block_height` is a
+    /// big-endian u64 and `len(block_hash)` a big-endian u32.
+    /// Finality providers must build the exact same payload for their votes to be accepted (see
+    /// the test vectors of this module)
+    pub fn finality_sig_msg(&self, height: u64, block_hash: &[u8]) -> Vec<u8> {
+        let mut msg = self.header(FINALITY_SIG_CONTEXT_TAG);
+        msg.extend_from_slice(&height.to_be_bytes());
+        self.extend_bytes(&mut msg, block_hash);
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(version: u32) -> SigningContext {
+        SigningContext {
+            version,
+            chain_id: "chain-1".to_string(),
+            contract_address: "contract".to_string(),
+            consumer_id: "consumer-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn signing_context_works() {
+        // Legacy version has no prefix
+        assert_eq!(
+            ctx(SIGNING_CONTEXT_V0).finality_sig_msg(1, b"hash"),
+            [1u64.to_be_bytes().as_slice(), b"hash"].concat()
+        );
+
+        // Prefixes are bound to the version, operation, chain, contract and consumer
+        let prefix = ctx(SIGNING_CONTEXT_V1).prefix(FINALITY_SIG_CONTEXT_TAG);
+        assert_eq!(prefix.len(), 32);
+        assert_ne!(
+            prefix,
+            ctx(SIGNING_CONTEXT_V1).prefix(COMMIT_PUB_RAND_CONTEXT_TAG)
+        );
+        let mut other = ctx(SIGNING_CONTEXT_V1);
+        other.chain_id = "chain-2".to_string();
+        assert_ne!(prefix, other.prefix(FINALITY_SIG_CONTEXT_TAG));
+        let mut other = ctx(SIGNING_CONTEXT_V1);
+        other.contract_address = "other".to_string();
+        assert_ne!(prefix, other.prefix(FINALITY_SIG_CONTEXT_TAG));
+        let mut other = ctx(SIGNING_CONTEXT_V1);
+        other.consumer_id = "consumer-2".to_string();
+        assert_ne!(prefix, other.prefix(FINALITY_SIG_CONTEXT_TAG));
+
+        // Structured payloads carry the header, and length-prefix variable-size fields
+        let msg = ctx(SIGNING_CONTEXT_V2).finality_sig_msg(1, b"hash");
+        assert_eq!(
+            msg[..2],
+            [STRUCTURED_PAYLOAD_HEADER, SIGNING_CONTEXT_V2 as u8]
+        );
+        assert_eq!(msg[34..42], 1u64.to_be_bytes());
+        assert_eq!(msg[42..46], 4u32.to_be_bytes());
+        assert_eq!(&msg[46..], b"hash");
+
+        SigningContext::validate_version(SIGNING_CONTEXT_LATEST).unwrap();
+        SigningContext::validate_version(SIGNING_CONTEXT_LATEST + 1).unwrap_err();
+    }
+
+    /// Finality vote payloads of block height 42 and block hash `[0xab; 32]`, under the test
+    /// signing context (see `ctx`), by version.
+    /// Finality provider implementations can check their payloads against these
+    const FINALITY_SIG_MSG_TEST_VECTORS: [(u32, &str); 3] = [
+        (
+            SIGNING_CONTEXT_V0,
+            "000000000000002aabababababababababababababababababababababababababababababababab",
+        ),
+        (
+            SIGNING_CONTEXT_V1,
+            "a6f2eb50d80bf5c6c609f801baf9043b0fb3be1f00872eff18e69cc4bd318679\
+             000000000000002a\
+             abababababababababababababababababababababababababababababababab",
+        ),
+        (
+            SIGNING_CONTEXT_V2,
+            "1902\
+             0c6bb03ecebc8f32bd73a29227c4c931cdb758154e3db7f13c7bd95f44575a90\
+             000000000000002a\
+             00000020\
+             abababababababababababababababababababababababababababababababab",
+        ),
+    ];
+
+    #[test]
+    fn finality_sig_msg_test_vectors() {
+        for (version, expected) in FINALITY_SIG_MSG_TEST_VECTORS {
+            let msg = ctx(version).finality_sig_msg(42, &[0xab; 32]);
+            assert_eq!(hex::encode(msg), expected, "version {version}");
+        }
+    }
+
+    #[test]
+    fn sudo_msgs_are_encoded_as_is() {
+        let begin_block = r#"{"begin_block":{"hash_hex":"aa","app_hash_hex":"bb"}}"#;
+        let msg: SudoMsg = cosmwasm_std::from_json(begin_block).unwrap();
+        assert_eq!(
+            msg,
+            SudoMsg::Block(sudo_api::SudoMsg::BeginBlock {
+                hash_hex: "aa".to_string(),
+                app_hash_hex: "bb".to_string(),
+            })
+        );
+        assert_eq!(cosmwasm_std::to_json_string(&msg).unwrap(), begin_block);
+
+        let set_height = r#"{"set_tally_start_height":{"height":10}}"#;
+        let msg: SudoMsg = cosmwasm_std::from_json(set_height).unwrap();
+        assert_eq!(
+            msg,
+            FinalitySudoMsg::SetTallyStartHeight { height: 10 }.into()
+        );
+        assert_eq!(cosmwasm_std::to_json_string(&msg).unwrap(), set_height);
+    }
 }
@@ -1,10 +1,27 @@
+pub mod btc_pk;
 pub mod btc_staking_api;
+pub mod canonical_json;
+pub mod chain_registry;
+pub mod compression;
+pub mod contract_version;
+pub mod cw20;
 pub mod error;
 pub mod finality_api;
+pub mod math;
+pub mod migration;
+pub mod pagination;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schema;
+pub mod state_hash;
+pub mod storage_layout;
+pub mod sudo_api;
 mod validate;
+pub mod zoneconcierge_api;
 
 use bech32::{FromBase32, Variant};
-use cosmwasm_std::{Addr, Binary, CanonicalAddr, CustomQuery, QueryRequest, WasmQuery};
+use cosmwasm_std::{
+    Addr, Binary, CanonicalAddr, CustomQuery, Decimal, QueryRequest, StdError, StdResult, WasmQuery,
+};
 
 pub fn encode_raw_query<T: Into<Binary>, Q: CustomQuery>(addr: &Addr, key: T) -> QueryRequest<Q> {
     WasmQuery::Raw {
@@ -47,6 +64,15 @@ pub fn new_canonical_addr(addr: &str, prefix: &str) -> Result<CanonicalAddr, Sta
     Ok(bytes.into())
 }
 
+/// legacy_dec_from_str parses a Cosmos SDK `LegacyDec` as encoded in protobuf messages, i.e. as
+/// the integer string of its atomics, with 18 decimal places
+pub fn legacy_dec_from_str(s: &str) -> StdResult<Decimal> {
+    let atomics = s
+        .parse::<u128>()
+        .map_err(|e| StdError::generic_err(format!("invalid LegacyDec {s}: {e}")))?;
+    Ok(Decimal::raw(atomics))
+}
+
 pub type Bytes = Vec<u8>;
 
 use error::StakingApiError;
@@ -0,0 +1,139 @@
+//! math provides the satoshi and voting power arithmetic shared by the contracts, checked against
+//! overflows with large stake amounts, along with their rounding rules
+use cosmwasm_std::{Decimal, OverflowError, OverflowOperation, Uint128, Uint256};
+
+/// add_sat adds two satoshi amounts (or voting powers), failing on overflow
+pub fn add_sat(a: u64, b: u64) -> Result<u64, OverflowError> {
+    a.checked_add(b)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add))
+}
+
+/// sub_sat subtracts two satoshi amounts (or voting powers), failing on underflow
+pub fn sub_sat(a: u64, b: u64) -> Result<u64, OverflowError> {
+    a.checked_sub(b)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Sub))
+}
+
+/// sum_sat sums satoshi amounts (or voting powers), failing on overflow
+pub fn sum_sat(amounts: impl IntoIterator<Item = u64>) -> Result<u64, OverflowError> {
+    amounts.into_iter().try_fold(0, add_sat)
+}
+
+/// power_share returns the share `part / total` of the total power, rounded down.
+/// It is zero if the total power is zero
+pub fn power_share(part: u64, total: u64) -> Decimal {
+    if total == 0 {
+        return Decimal::zero();
+    }
+    Decimal::from_ratio(part, total)
+}
+
+/// exceeds_share checks whether `part` is more than the `threshold` share of `total`, exactly
+/// (i.e. without rounding the share). It is false if the total is zero
+pub fn exceeds_share(part: u64, total: u64, threshold: Decimal) -> bool {
+    total > 0
+        && Uint256::from(part) * Uint256::from(Decimal::one().atomics())
+            > Uint256::from(threshold.atomics()) * Uint256::from(total)
+}
+
+/// exceeds_fraction checks whether `part` is more than `numerator / denominator` of `total`,
+/// exactly. E.g. `exceeds_fraction(voted, total, 2, 3)` is the 2/3 supermajority check
+pub fn exceeds_fraction(part: u64, total: u64, numerator: u64, denominator: u64) -> bool {
+    u128::from(part) * u128::from(denominator) > u128::from(total) * u128::from(numerator)
+}
+
+/// split_commission splits `amount` into the `commission` share and the rest.
+/// The commission is rounded down, so that rounding is in favour of the delegators
+pub fn split_commission(amount: Uint128, commission: Decimal) -> (Uint128, Uint128) {
+    let commission = amount.mul_floor(commission.min(Decimal::one()));
+    (commission, amount - commission)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const ROUNDS: usize = 10_000;
+
+    /// amount returns a random amount, biased towards the edges of the u64 range
+    fn amount(rng: &mut StdRng) -> u64 {
+        match rng.gen_range(0..4) {
+            0 => rng.gen_range(0..1000),
+            1 => u64::MAX - rng.gen_range(0..1000),
+            _ => rng.gen(),
+        }
+    }
+
+    #[test]
+    fn sat_arithmetic_properties() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..ROUNDS {
+            let (a, b) = (amount(&mut rng), amount(&mut rng));
+            let exact = u128::from(a) + u128::from(b);
+            match add_sat(a, b) {
+                Ok(sum) => assert_eq!(u128::from(sum), exact),
+                Err(_) => assert!(exact > u128::from(u64::MAX)),
+            }
+            assert_eq!(sub_sat(a, b).is_ok(), a >= b);
+            if let Ok(sum) = add_sat(a, b) {
+                assert_eq!(sub_sat(sum, b).unwrap(), a);
+                assert_eq!(sum_sat([a, b]).unwrap(), sum);
+            }
+        }
+        assert_eq!(sum_sat([]).unwrap(), 0);
+        sum_sat([u64::MAX, 1]).unwrap_err();
+    }
+
+    #[test]
+    fn share_properties() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..ROUNDS {
+            let total = amount(&mut rng);
+            let part = if total == 0 {
+                0
+            } else {
+                rng.gen_range(0..=total)
+            };
+            let share = power_share(part, total);
+            assert!(share <= Decimal::one());
+
+            // The exact checks agree with the exact rational comparison
+            let (num, den) = (rng.gen_range(0..=100u64), 100u64);
+            assert_eq!(
+                exceeds_fraction(part, total, num, den),
+                u128::from(part) * 100 > u128::from(total) * u128::from(num)
+            );
+            assert_eq!(
+                exceeds_share(part, total, Decimal::percent(num)),
+                total > 0 && exceeds_fraction(part, total, num, den)
+            );
+        }
+        assert_eq!(power_share(1, 0), Decimal::zero());
+        assert!(!exceeds_share(0, 0, Decimal::zero()));
+        // Exactly 2/3 is not a supermajority
+        assert!(!exceeds_fraction(2, 3, 2, 3));
+        assert!(exceeds_fraction(u64::MAX, u64::MAX, 2, 3));
+    }
+
+    #[test]
+    fn split_commission_properties() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..ROUNDS {
+            let amount = Uint128::new(rng.gen());
+            let permille = rng.gen_range(0..=1000u64);
+            let (fee, rest) = split_commission(amount, Decimal::permille(permille));
+            assert_eq!(fee + rest, amount);
+            // Rounded down, i.e. less than a unit below the exact commission
+            let exact = Uint256::from(amount) * Uint256::from(permille);
+            let fee = Uint256::from(fee) * Uint256::from(1000u64);
+            assert!(fee <= exact && exact < fee + Uint256::from(1000u64));
+        }
+        let (fee, rest) = split_commission(Uint128::new(999), Decimal::percent(10));
+        assert_eq!((fee, rest), (Uint128::new(99), Uint128::new(900)));
+        // Commissions are capped to the whole amount
+        let (fee, rest) = split_commission(Uint128::new(5), Decimal::percent(200));
+        assert_eq!((fee, rest), (Uint128::new(5), Uint128::zero()));
+    }
+}
@@ -0,0 +1,200 @@
+//! migration runs the storage migrations of a contract in chunks, so that migrations of large
+//! state sections can span many transactions. Migrations are scheduled upon contract migration,
+//! and advanced by anyone through the `ContinueMigration` message of the contract.
+//! The state section of a migration is paused until the migration is complete
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Binary, Order, StdResult, Storage};
+use cw_storage_plus::Map;
+
+/// MigrationStep migrates up to the given number of entries after the given cursor, i.e. the key
+/// of the last entry migrated so far, if any. It returns the number of entries migrated, and the
+/// key of the last one
+pub type MigrationStep =
+    fn(&mut dyn Storage, Option<&[u8]>, usize) -> StdResult<(usize, Option<Vec<u8>>)>;
+
+/// Migration is a storage migration of a state section, e.g. the move of its entries to a new
+/// layout (see `storage_layout`), or the backfill of an index
+#[derive(Clone, Copy)]
+pub struct Migration {
+    /// name uniquely identifies the migration
+    pub name: &'static str,
+    /// section is the state section paused until the migration is complete
+    pub section: &'static str,
+    /// step migrates the next entries of the migration.
+    /// The migration is complete once it migrates fewer entries than asked for
+    pub step: MigrationStep,
+}
+
+/// MigrationProgress is the progress of a scheduled migration
+#[cw_serde]
+pub struct MigrationProgress {
+    pub name: String,
+    pub section: String,
+    /// migrated is the number of entries migrated so far
+    pub migrated: u64,
+    pub complete: bool,
+    /// cursor is the key of the last entry migrated so far, for the migrations resuming from it
+    #[serde(default)]
+    pub cursor: Option<Binary>,
+}
+
+/// MigrationProgressResponse is the progress of the scheduled storage migrations, in name order
+#[cw_serde]
+pub struct MigrationProgressResponse {
+    pub migrations: Vec<MigrationProgress>,
+}
+
+/// Progress of the scheduled migrations, by name
+pub const MIGRATION_PROGRESS: Map<&str, MigrationProgress> = Map::new("migration_progress");
+
+/// schedule_migrations schedules the given migrations, unless they have been scheduled already.
+/// It returns the names of the newly scheduled migrations
+pub fn schedule_migrations(
+    storage: &mut dyn Storage,
+    migrations: &[Migration],
+) -> StdResult<Vec<&'static str>> {
+    let mut scheduled = vec![];
+    for migration in migrations {
+        if MIGRATION_PROGRESS.has(storage, migration.name) {
+            continue;
+        }
+        let progress = MigrationProgress {
+            name: migration.name.to_string(),
+            section: migration.section.to_string(),
+            migrated: 0,
+            complete: false,
+            cursor: None,
+        };
+        MIGRATION_PROGRESS.save(storage, migration.name, &progress)?;
+        scheduled.push(migration.name);
+    }
+    Ok(scheduled)
+}
+
+/// continue_migration migrates up to `limit` entries of the first pending migration.
+/// It returns the progress of that migration, or `None` if no migration is pending
+pub fn continue_migration(
+    storage: &mut dyn Storage,
+    migrations: &[Migration],
+    limit: usize,
+) -> StdResult<Option<MigrationProgress>> {
+    for migration in migrations {
+        let Some(mut progress) = MIGRATION_PROGRESS.may_load(storage, migration.name)? else {
+            continue;
+        };
+        if progress.complete {
+            continue;
+        }
+        let cursor = progress.cursor.as_ref().map(Binary::as_slice);
+        let (migrated, cursor) = (migration.step)(storage, cursor, limit)?;
+        progress.migrated += migrated as u64;
+        progress.complete = migrated < limit;
+        progress.cursor = cursor.map(Binary::new).or(progress.cursor);
+        MIGRATION_PROGRESS.save(storage, migration.name, &progress)?;
+        return Ok(Some(progress));
+    }
+    Ok(None)
+}
+
+/// get_migration_progress returns the progress of all the scheduled migrations
+pub fn get_migration_progress(storage: &dyn Storage) -> StdResult<Vec<MigrationProgress>> {
+    MIGRATION_PROGRESS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, progress)| progress))
+        .collect()
+}
+
+/// migrating_section returns the first of the given state sections being migrated, if any
+pub fn migrating_section(storage: &dyn Storage, sections: &[&str]) -> StdResult<Option<String>> {
+    Ok(get_migration_progress(storage)?
+        .into_iter()
+        .find(|progress| !progress.complete && sections.contains(&progress.section.as_str()))
+        .map(|progress| progress.section))
+}
+
+/// is_migrated returns whether the entry with the given key has been migrated by the given
+/// migration, i.e. whether the migration is not pending, or has moved past the key already
+pub fn is_migrated(storage: &dyn Storage, name: &str, key: &[u8]) -> StdResult<bool> {
+    Ok(match MIGRATION_PROGRESS.may_load(storage, name)? {
+        Some(progress) if !progress.complete => progress
+            .cursor
+            .is_some_and(|cursor| key <= cursor.as_slice()),
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cw_storage_plus::Bound;
+
+    const VALUES: Map<u64, u32> = Map::new("test_values");
+    const DOUBLED: Map<u64, u32> = Map::new("test_doubled");
+
+    /// Backfills `DOUBLED` from `VALUES`, resuming after the cursor
+    fn backfill(
+        storage: &mut dyn Storage,
+        cursor: Option<&[u8]>,
+        limit: usize,
+    ) -> StdResult<(usize, Option<Vec<u8>>)> {
+        let start = cursor.map(|cursor| Bound::ExclusiveRaw(cursor.to_vec()));
+        let values = VALUES
+            .range_raw(storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut last = None;
+        for (key, value) in &values {
+            let i = u64::from_be_bytes(key.as_slice().try_into().unwrap());
+            DOUBLED.save(storage, i, &(value * 2))?;
+            last = Some(key.clone());
+        }
+        Ok((values.len(), last))
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[Migration {
+        name: "test_doubled",
+        section: "values",
+        step: backfill,
+    }];
+
+    #[test]
+    fn resumable_migration_works() {
+        let mut storage = MockStorage::new();
+        for i in 0..5 {
+            VALUES.save(&mut storage, i, &(i as u32)).unwrap();
+        }
+
+        // Entries are migrated unless the migration is pending
+        assert!(is_migrated(&storage, "test_doubled", &4u64.to_be_bytes()).unwrap());
+        schedule_migrations(&mut storage, TEST_MIGRATIONS).unwrap();
+        assert!(!is_migrated(&storage, "test_doubled", &0u64.to_be_bytes()).unwrap());
+        assert_eq!(
+            migrating_section(&storage, &["values"]).unwrap(),
+            Some("values".to_string())
+        );
+        assert_eq!(migrating_section(&storage, &["other"]).unwrap(), None);
+
+        // Each step resumes after the last migrated entry
+        for (migrated, complete) in [(2, false), (4, false), (5, true)] {
+            let progress = continue_migration(&mut storage, TEST_MIGRATIONS, 2)
+                .unwrap()
+                .unwrap();
+            assert_eq!(progress.migrated, migrated);
+            assert_eq!(progress.complete, complete);
+            let last = migrated - 1;
+            assert!(is_migrated(&storage, "test_doubled", &last.to_be_bytes()).unwrap());
+            assert_eq!(
+                is_migrated(&storage, "test_doubled", &(last + 1).to_be_bytes()).unwrap(),
+                complete
+            );
+        }
+        for i in 0..5 {
+            assert_eq!(DOUBLED.load(&storage, i).unwrap(), 2 * i as u32);
+        }
+        assert_eq!(migrating_section(&storage, &["values"]).unwrap(), None);
+        assert!(continue_migration(&mut storage, TEST_MIGRATIONS, 2)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,66 @@
+//! pagination standardises the limits and cursors of the paginated queries of the contracts.
+//! Pages resume strictly after the cursor (usually the key of the last item of the previous
+//! page), and are at most `MAX_LIMIT` items long, whatever the requested limit
+use cosmwasm_std::Order;
+use cw_storage_plus::{Bound, PrimaryKey};
+
+/// Number of items of a page, if no limit is requested
+pub const DEFAULT_LIMIT: u32 = 10;
+/// Maximum number of items of a page
+pub const MAX_LIMIT: u32 = 30;
+
+/// page_limit returns the number of items of a page, for the requested limit
+pub fn page_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+/// start_after returns the bound resuming a page strictly after the given cursor, if any
+pub fn start_after<'a, K: PrimaryKey<'a>>(cursor: Option<K>) -> Option<Bound<'a, K>> {
+    cursor.map(Bound::exclusive)
+}
+
+/// Range bounds and order of a page, i.e. `(min, max, order)`
+pub type PageRange<'a, K> = (Option<Bound<'a, K>>, Option<Bound<'a, K>>, Order);
+
+/// page_range returns the range bounds and order of a page starting strictly after the given
+/// cursor, in descending order if `reverse` is set, or ascending order otherwise
+pub fn page_range<'a, K: PrimaryKey<'a>>(
+    cursor: Option<K>,
+    reverse: Option<bool>,
+) -> PageRange<'a, K> {
+    let start_after = start_after(cursor);
+    if reverse.unwrap_or(false) {
+        (None, start_after, Order::Descending)
+    } else {
+        (start_after, None, Order::Ascending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_limit_works() {
+        assert_eq!(page_limit(None), DEFAULT_LIMIT as usize);
+        assert_eq!(page_limit(Some(5)), 5);
+        assert_eq!(page_limit(Some(MAX_LIMIT + 1)), MAX_LIMIT as usize);
+    }
+
+    #[test]
+    fn page_range_works() {
+        let (min, max, order) = page_range::<u64>(None, None);
+        assert!(min.is_none() && max.is_none());
+        assert_eq!(order, Order::Ascending);
+
+        let (min, max, order) = page_range(Some(5u64), None);
+        assert!(matches!(min, Some(Bound::Exclusive((5, _)))));
+        assert!(max.is_none());
+        assert_eq!(order, Order::Ascending);
+
+        let (min, max, order) = page_range(Some(5u64), Some(true));
+        assert!(min.is_none());
+        assert!(matches!(max, Some(Bound::Exclusive((5, _)))));
+        assert_eq!(order, Order::Descending);
+    }
+}
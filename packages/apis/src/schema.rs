@@ -0,0 +1,64 @@
+//! schema writes the JSON schema files of a contract's API, and checks the checked-in (golden)
+//! schema files against it, so that API changes cannot reach relayers and SDKs unnoticed.
+//! The files are laid out as by `cosmwasm_schema::write_api!`, i.e. the full API in
+//! `<contract>.json`, and each message / response in `raw/`
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cosmwasm_schema::{remove_schemas, Api};
+
+/// schema_files returns the (path relative to the schema dir, content) of the schema files of the
+/// given API
+pub fn schema_files(api: Api) -> Vec<(PathBuf, String)> {
+    let full_api_file = PathBuf::from(format!("{}.json", api.contract_name));
+    let api = api.render();
+    let mut files = vec![(full_api_file, api.to_string().unwrap() + "\n")];
+    for (filename, json) in api.to_schema_files().unwrap() {
+        files.push((Path::new("raw").join(filename), json + "\n"));
+    }
+    files
+}
+
+/// write_schema_files replaces the schema files in `schema_dir` with the ones of the given API
+pub fn write_schema_files(api: Api, schema_dir: &Path) {
+    let raw_dir = schema_dir.join("raw");
+    fs::create_dir_all(&raw_dir).unwrap();
+    remove_schemas(schema_dir).unwrap();
+    remove_schemas(&raw_dir).unwrap();
+    for (path, json) in schema_files(api) {
+        let path = schema_dir.join(path);
+        fs::write(&path, json).unwrap();
+        println!("Exported {}", path.display());
+    }
+}
+
+/// assert_schema_files panics if the schema files in `schema_dir` are not exactly the ones of the
+/// given API, i.e. if the API changed without the (golden) schema files being regenerated
+pub fn assert_schema_files(api: Api, schema_dir: &Path) {
+    let files = schema_files(api);
+    let mut outdated: Vec<PathBuf> = files
+        .iter()
+        .filter(|(path, json)| {
+            fs::read_to_string(schema_dir.join(path)).ok().as_ref() != Some(json)
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    // Schema files of removed messages / responses are outdated as well
+    for dir in [PathBuf::new(), PathBuf::from("raw")] {
+        let Ok(entries) = fs::read_dir(schema_dir.join(&dir)) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = dir.join(entry.file_name());
+            let is_json = path.extension().is_some_and(|ext| ext == "json");
+            if is_json && !files.iter().any(|(p, _)| *p == path) {
+                outdated.push(path);
+            }
+        }
+    }
+    assert!(
+        outdated.is_empty(),
+        "outdated schema files in {}: {outdated:?}\nRun `cargo schema` in the contract's directory to regenerate them",
+        schema_dir.display()
+    );
+}
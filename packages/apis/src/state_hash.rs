@@ -0,0 +1,387 @@
+//! state_hash keeps a deterministic digest over the state of a contract, by state section.
+//! A section is a named group of storage namespaces (see `StateSection`). Its hash is a multiset
+//! hash of its current entries, i.e. the sum of the hashes of the entries, updated upon every
+//! write and removal (see `HashedStorage`). Two contracts holding the same state report the same
+//! hash, whatever writes, removals or pruning got them there.
+//! Entries outside of all sections are hashed under the `other` section, so that the overall state
+//! hash covers the whole state of the contract
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{CustomQuery, DepsMut, Order, Record, StdError, StdResult, Storage};
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+use crate::migration::{migrating_section, MigrationProgress, MIGRATION_PROGRESS};
+
+/// The section of the entries outside of all the sections of a contract
+pub const SECTION_OTHER: &str = "other";
+
+/// The name of the migration rebuilding the section hashes from the stored entries (see
+/// `rebuild_section_hashes`), for contracts migrating from a version not keeping them.
+/// It is also the name of its section, as the state hash is unavailable until it is complete
+pub const STATE_HASH_REBUILD: &str = "state_hash";
+
+/// The namespaces of the state hash bookkeeping, which is not hashed: the section hashes, by
+/// section name, and the key of the last entry hashed by a pending rebuild
+const BOOKKEEPING: StateSection = StateSection {
+    name: "",
+    namespaces: &[SECTION_HASHES_NAMESPACE, REBUILD_CURSOR_KEY],
+};
+const SECTION_HASHES_NAMESPACE: &str = "state_hashes";
+const REBUILD_CURSOR_KEY: &str = "state_hash_cursor";
+
+/// `StateSection` is a named group of storage namespaces, i.e. the namespaces of `Item`s, and the
+/// namespaces of `Map`s (and of their indexes) holding their entries
+pub struct StateSection {
+    pub name: &'static str,
+    pub namespaces: &'static [&'static str],
+}
+
+impl StateSection {
+    /// contains returns whether the raw storage `key` is in one of the namespaces of the section
+    fn contains(&self, key: &[u8]) -> bool {
+        self.namespaces.iter().any(|ns| {
+            let ns = ns.as_bytes();
+            key == ns
+                || (key.len() > ns.len() + 2
+                    && key[..2] == (ns.len() as u16).to_be_bytes()
+                    && &key[2..ns.len() + 2] == ns)
+        })
+    }
+}
+
+/// section_of returns the name of the section of the raw storage `key`
+fn section_of<'a>(sections: &'a [StateSection], key: &[u8]) -> &'a str {
+    sections
+        .iter()
+        .find(|section| section.contains(key))
+        .map_or(SECTION_OTHER, |section| section.name)
+}
+
+/// section_hash_key returns the raw storage key of the hash of the given section, i.e. its key in
+/// the section hashes namespace
+fn section_hash_key(section: &str) -> Vec<u8> {
+    let ns = SECTION_HASHES_NAMESPACE.as_bytes();
+    [&(ns.len() as u16).to_be_bytes(), ns, section.as_bytes()].concat()
+}
+
+fn load_section_hash(storage: &dyn Storage, section: &str) -> [u8; 32] {
+    storage
+        .get(&section_hash_key(section))
+        .and_then(|hash| hash.try_into().ok())
+        .unwrap_or_default()
+}
+
+/// entry_hash is the hash of a single entry, i.e. `sha256(len(key) || key || len(value) || value)`
+fn entry_hash(key: &[u8], value: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update((key.len() as u64).to_be_bytes())
+        .chain_update(key)
+        .chain_update((value.len() as u64).to_be_bytes())
+        .chain_update(value)
+        .finalize()
+        .into()
+}
+
+/// add_entry adds the hash of the given entry to the hash of its section, or subtracts it upon
+/// `remove`, modulo 2^256
+fn add_entry(
+    storage: &mut dyn Storage,
+    sections: &[StateSection],
+    key: &[u8],
+    value: &[u8],
+    remove: bool,
+) {
+    let section = section_of(sections, key);
+    let mut sum = load_section_hash(storage, section);
+    let hash = entry_hash(key, value);
+    let mut carry = 0i16;
+    for i in (0..32).rev() {
+        let digit = if remove {
+            sum[i] as i16 - hash[i] as i16 + carry
+        } else {
+            sum[i] as i16 + hash[i] as i16 + carry
+        };
+        sum[i] = digit.rem_euclid(256) as u8;
+        carry = digit.div_euclid(256);
+    }
+    storage.set(&section_hash_key(section), &sum);
+}
+
+/// HashedStorage keeps the section hashes of a contract up to date with the writes to its
+/// storage. Contracts wrap their storage into it at every entry point writing to it.
+///
+/// While the section hashes are being rebuilt, the writes to the entries the rebuild has not gone
+/// through yet are left to it
+pub struct HashedStorage<'a> {
+    storage: &'a mut dyn Storage,
+    sections: &'a [StateSection],
+}
+
+impl<'a> HashedStorage<'a> {
+    pub fn new(storage: &'a mut dyn Storage, sections: &'a [StateSection]) -> Self {
+        HashedStorage { storage, sections }
+    }
+
+    /// is_hashed returns whether the writes to the given key are reflected in the section hashes
+    fn is_hashed(&self, key: &[u8]) -> bool {
+        if BOOKKEEPING.contains(key) {
+            return false;
+        }
+        match self.storage.get(REBUILD_CURSOR_KEY.as_bytes()) {
+            Some(cursor) => key <= cursor.as_slice(),
+            None => true,
+        }
+    }
+
+    fn update(&mut self, key: &[u8], value: Option<&[u8]>) {
+        if !self.is_hashed(key) {
+            return;
+        }
+        if let Some(prev) = self.storage.get(key) {
+            add_entry(self.storage, self.sections, key, &prev, true);
+        }
+        if let Some(value) = value {
+            add_entry(self.storage, self.sections, key, value, false);
+        }
+    }
+}
+
+impl Storage for HashedStorage<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(key)
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.storage.range(start, end, order)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.update(key, Some(value));
+        self.storage.set(key, value);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.update(key, None);
+        self.storage.remove(key);
+    }
+}
+
+/// with_state_hash runs `f` with the storage of `deps` wrapped into a `HashedStorage`, i.e.
+/// keeping the section hashes up to date with the writes of `f`
+pub fn with_state_hash<C: CustomQuery, T>(
+    deps: DepsMut<C>,
+    sections: &[StateSection],
+    f: impl FnOnce(DepsMut<C>) -> T,
+) -> T {
+    let mut storage = HashedStorage::new(deps.storage, sections);
+    f(DepsMut {
+        storage: &mut storage,
+        api: deps.api,
+        querier: deps.querier,
+    })
+}
+
+/// init_section_hashes records the rebuild of the section hashes as complete, for contracts
+/// instantiated with a version keeping them up to date from the start
+pub fn init_section_hashes(storage: &mut dyn Storage) -> StdResult<()> {
+    let progress = MigrationProgress {
+        name: STATE_HASH_REBUILD.to_string(),
+        section: STATE_HASH_REBUILD.to_string(),
+        migrated: 0,
+        complete: true,
+        cursor: None,
+    };
+    MIGRATION_PROGRESS.save(storage, STATE_HASH_REBUILD, &progress)
+}
+
+/// rebuild_section_hashes is the step of the `STATE_HASH_REBUILD` migration (see
+/// `migration::MigrationStep`). It hashes up to `limit` entries after the cursor
+/// into the section hashes, starting over from empty section hashes
+pub fn rebuild_section_hashes(
+    storage: &mut dyn Storage,
+    sections: &[StateSection],
+    cursor: Option<&[u8]>,
+    limit: usize,
+) -> StdResult<(usize, Option<Vec<u8>>)> {
+    if cursor.is_none() {
+        for section in sections
+            .iter()
+            .map(|section| section.name)
+            .chain([SECTION_OTHER])
+        {
+            storage.remove(&section_hash_key(section));
+        }
+    }
+    let start = cursor.map(|cursor| [cursor, &[0]].concat());
+    let entries = storage
+        .range(start.as_deref(), None, Order::Ascending)
+        .filter(|(key, _)| !BOOKKEEPING.contains(key))
+        .take(limit)
+        .collect::<Vec<_>>();
+    for (key, value) in &entries {
+        add_entry(storage, sections, key, value, false);
+    }
+    let last = entries.last().map(|(key, _)| key.clone());
+    match &last {
+        Some(last) if entries.len() == limit => storage.set(REBUILD_CURSOR_KEY.as_bytes(), last),
+        _ => storage.remove(REBUILD_CURSOR_KEY.as_bytes()),
+    }
+    Ok((entries.len(), last))
+}
+
+/// SectionHashResponse is the hash of a single state section
+#[cw_serde]
+pub struct SectionHashResponse {
+    /// section is the name of the state section
+    pub section: String,
+    /// hash is the hex-encoded hash of the section
+    pub hash: String,
+}
+
+/// StateHashResponse is a deterministic digest over the contract's state.
+/// Two contracts with the same state hash hold the same state
+#[cw_serde]
+pub struct StateHashResponse {
+    /// sections are the per-section hashes the state hash is computed from, followed by the
+    /// `other` section
+    pub sections: Vec<SectionHashResponse>,
+    /// hash is the hex-encoded hash over all the section hashes
+    pub hash: String,
+}
+
+/// get_state_hash returns the hashes of all sections, together with the overall state hash.
+/// The hash of a section is the sum, modulo 2^256, of
+/// `sha256(len(key) || key || len(value) || value)` over its entries. The state hash is
+/// `sha256(name || hash || ...)` over the sections, in the given order, followed by the `other`
+/// section.
+/// It fails while the section hashes are being rebuilt
+pub fn get_state_hash(
+    storage: &dyn Storage,
+    sections: &[StateSection],
+) -> StdResult<StateHashResponse> {
+    if migrating_section(storage, &[STATE_HASH_REBUILD])?.is_some() {
+        return Err(StdError::generic_err(
+            "The state hash is unavailable until its rebuild is complete",
+        ));
+    }
+    let names = sections
+        .iter()
+        .map(|section| section.name)
+        .chain([SECTION_OTHER]);
+    let mut state_hasher = Sha256::new();
+    let mut section_hashes = Vec::with_capacity(sections.len() + 1);
+    for name in names {
+        let hash = load_section_hash(storage, name);
+        state_hasher.update(name.as_bytes());
+        state_hasher.update(hash);
+        section_hashes.push(SectionHashResponse {
+            section: name.to_string(),
+            hash: hash.encode_hex(),
+        });
+    }
+    Ok(StateHashResponse {
+        sections: section_hashes,
+        hash: state_hasher.finalize().encode_hex(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration::{continue_migration, schedule_migrations, Migration};
+    use cosmwasm_std::testing::MockStorage;
+    use cw_storage_plus::{Item, Map};
+
+    const SECTIONS: &[StateSection] = &[StateSection {
+        name: "values",
+        namespaces: &["values", "values_last"],
+    }];
+    const VALUES: Map<u64, String> = Map::new("values");
+    const VALUES_LAST: Item<u64> = Item::new("values_last");
+    const OTHER: Item<u64> = Item::new("values_other");
+
+    const MIGRATIONS: &[Migration] = &[Migration {
+        name: STATE_HASH_REBUILD,
+        section: STATE_HASH_REBUILD,
+        step: |storage, cursor, limit| rebuild_section_hashes(storage, SECTIONS, cursor, limit),
+    }];
+
+    #[test]
+    fn state_hash_depends_on_contents_only() {
+        let mut storage = MockStorage::new();
+        let mut storage = HashedStorage::new(&mut storage, SECTIONS);
+        let empty = get_state_hash(&storage, SECTIONS).unwrap();
+
+        // Entries are hashed under their section
+        VALUES.save(&mut storage, 1, &"a".to_string()).unwrap();
+        VALUES_LAST.save(&mut storage, &1).unwrap();
+        let written = get_state_hash(&storage, SECTIONS).unwrap();
+        assert_ne!(written.sections[0], empty.sections[0]);
+        assert_eq!(written.sections[1], empty.sections[1]);
+
+        // Entries outside of all sections are hashed under the `other` section
+        OTHER.save(&mut storage, &1).unwrap();
+        let other = get_state_hash(&storage, SECTIONS).unwrap();
+        assert_eq!(other.sections[0], written.sections[0]);
+        assert_ne!(other.sections[1], written.sections[1]);
+        assert_ne!(other.hash, written.hash);
+
+        // Overwrites and removals are reflected, so that the same contents hash the same
+        VALUES.save(&mut storage, 2, &"b".to_string()).unwrap();
+        VALUES.save(&mut storage, 1, &"b".to_string()).unwrap();
+        assert_ne!(get_state_hash(&storage, SECTIONS).unwrap(), other);
+        VALUES.remove(&mut storage, 2);
+        VALUES.save(&mut storage, 1, &"a".to_string()).unwrap();
+        assert_eq!(get_state_hash(&storage, SECTIONS).unwrap(), other);
+        OTHER.remove(&mut storage);
+        assert_eq!(get_state_hash(&storage, SECTIONS).unwrap(), written);
+        VALUES.remove(&mut storage, 1);
+        VALUES_LAST.remove(&mut storage);
+        assert_eq!(get_state_hash(&storage, SECTIONS).unwrap(), empty);
+    }
+
+    #[test]
+    fn rebuild_section_hashes_works() {
+        // The same contents, written with and without keeping the section hashes
+        let mut hashed = MockStorage::new();
+        let mut hashed = HashedStorage::new(&mut hashed, SECTIONS);
+        init_section_hashes(&mut hashed).unwrap();
+        let mut storage = MockStorage::new();
+        for i in 0..5 {
+            VALUES.save(&mut hashed, i, &i.to_string()).unwrap();
+            VALUES.save(&mut storage, i, &i.to_string()).unwrap();
+        }
+        OTHER.save(&mut storage, &1).unwrap();
+        let mut storage = HashedStorage::new(&mut storage, SECTIONS);
+        schedule_migrations(&mut storage, MIGRATIONS).unwrap();
+        get_state_hash(&storage, SECTIONS).unwrap_err();
+
+        // Writes during the rebuild are reflected, whether the rebuild has gone through them yet
+        continue_migration(&mut storage, MIGRATIONS, 3).unwrap();
+        for (i, value) in [(0, "a"), (4, "b")] {
+            VALUES.save(&mut hashed, i, &value.to_string()).unwrap();
+            VALUES.save(&mut storage, i, &value.to_string()).unwrap();
+        }
+        OTHER.remove(&mut storage);
+        continue_migration(&mut storage, MIGRATIONS, 3).unwrap();
+        let progress = continue_migration(&mut storage, MIGRATIONS, 3)
+            .unwrap()
+            .unwrap();
+        assert!(progress.complete);
+
+        // The migration progress is part of the state, so it is aligned before comparing
+        init_section_hashes(&mut hashed).unwrap();
+        MIGRATION_PROGRESS
+            .save(&mut hashed, STATE_HASH_REBUILD, &progress)
+            .unwrap();
+        assert_eq!(
+            get_state_hash(&storage, SECTIONS).unwrap(),
+            get_state_hash(&hashed, SECTIONS).unwrap()
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! storage_layout versions the storage namespaces of the contracts, so that the layout of large
+//! maps (e.g. BTC headers, delegations) can change without rewriting all their entries in a
+//! single migration transaction.
+//! The namespace of a map carries its layout version, e.g. `btc_lc_headers@v2`. A `VersionedMap`
+//! reads its entries from the namespace of the current layout, falling back to (and upgrading)
+//! the entries still under the previous layout. Entries move to the current layout lazily, when
+//! they are written, or in bounded batches through `VersionedMap::migrate`
+use cosmwasm_std::storage_keys::namespace_with_key;
+use cosmwasm_std::{to_json_vec, Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Map, PrimaryKey};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// versioned_namespace! returns the storage namespace of the given layout version of a map.
+/// The first layout version is the plain namespace, so that existing maps are at version 1
+#[macro_export]
+macro_rules! versioned_namespace {
+    ($namespace:literal, 1) => {
+        $namespace
+    };
+    ($namespace:literal, $version:literal) => {
+        concat!($namespace, "@v", $version)
+    };
+}
+
+/// VersionedMap is a map under migration from a previous layout `L` to the current layout `T`
+pub struct VersionedMap<K, T, L> {
+    previous: Map<K, L>,
+    current: Map<K, T>,
+    upgrade: fn(L) -> StdResult<T>,
+}
+
+impl<'a, K, T, L> VersionedMap<K, T, L>
+where
+    K: PrimaryKey<'a>,
+    T: Serialize + DeserializeOwned,
+    L: Serialize + DeserializeOwned,
+{
+    /// new creates a map migrating from the `previous` namespace to the `current` one, upgrading
+    /// the entries with `upgrade`
+    pub const fn new(
+        previous: &'static str,
+        current: &'static str,
+        upgrade: fn(L) -> StdResult<T>,
+    ) -> Self {
+        VersionedMap {
+            previous: Map::new(previous),
+            current: Map::new(current),
+            upgrade,
+        }
+    }
+
+    /// current returns the map of the current layout, e.g. for range queries over the entries
+    /// migrated so far
+    pub fn current(&self) -> &Map<K, T> {
+        &self.current
+    }
+
+    pub fn may_load(&self, storage: &dyn Storage, key: K) -> StdResult<Option<T>> {
+        if let Some(value) = self.current.may_load(storage, key.clone())? {
+            return Ok(Some(value));
+        }
+        self.previous
+            .may_load(storage, key)?
+            .map(self.upgrade)
+            .transpose()
+    }
+
+    pub fn load(&self, storage: &dyn Storage, key: K) -> StdResult<T> {
+        self.may_load(storage, key)?
+            .ok_or_else(|| StdError::not_found(std::any::type_name::<T>()))
+    }
+
+    pub fn has(&self, storage: &dyn Storage, key: K) -> bool {
+        self.current.has(storage, key.clone()) || self.previous.has(storage, key)
+    }
+
+    /// save saves the entry under the current layout, dropping its previous layout entry if any
+    pub fn save(&self, storage: &mut dyn Storage, key: K, value: &T) -> StdResult<()> {
+        self.previous.remove(storage, key.clone());
+        self.current.save(storage, key, value)
+    }
+
+    pub fn remove(&self, storage: &mut dyn Storage, key: K) {
+        self.previous.remove(storage, key.clone());
+        self.current.remove(storage, key);
+    }
+
+    /// migrate moves up to `limit` entries from the previous layout to the current one, in key
+    /// order. Entries already saved under the current layout are kept as they are.
+    /// Returns the number of entries moved, i.e. the migration is complete once it is lower than
+    /// `limit`
+    pub fn migrate(&self, storage: &mut dyn Storage, limit: usize) -> StdResult<usize> {
+        let entries = self
+            .previous
+            .range_raw(storage, None, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let moved = entries.len();
+        for (key, value) in entries {
+            let current_key = namespace_with_key(&[self.current.namespace_bytes()], &key);
+            if storage.get(&current_key).is_none() {
+                let value = (self.upgrade)(value)?;
+                storage.set(&current_key, &to_json_vec(&value)?);
+            }
+            storage.remove(&namespace_with_key(
+                &[self.previous.namespace_bytes()],
+                &key,
+            ));
+        }
+        Ok(moved)
+    }
+
+    /// is_migrated returns whether all entries are under the current layout
+    pub fn is_migrated(&self, storage: &dyn Storage) -> bool {
+        self.previous.is_empty(storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    const LEGACY: Map<(&str, u64), u32> = Map::new("values");
+    const VALUES: VersionedMap<(&str, u64), String, u32> = VersionedMap::new(
+        versioned_namespace!("values", 1),
+        versioned_namespace!("values", 2),
+        |v| Ok(v.to_string()),
+    );
+
+    #[test]
+    fn versioned_namespace_works() {
+        assert_eq!(versioned_namespace!("values", 1), "values");
+        assert_eq!(versioned_namespace!("values", 2), "values@v2");
+    }
+
+    #[test]
+    fn versioned_map_works() {
+        let mut storage = MockStorage::new();
+        for i in 0..5 {
+            LEGACY.save(&mut storage, ("a", i), &(i as u32)).unwrap();
+        }
+
+        // Previous layout entries are read upgraded
+        assert_eq!(VALUES.load(&storage, ("a", 3)).unwrap(), "3");
+        assert!(VALUES.has(&storage, ("a", 3)));
+        assert!(VALUES.may_load(&storage, ("b", 3)).unwrap().is_none());
+        VALUES.load(&storage, ("b", 3)).unwrap_err();
+
+        // Saved entries move to the current layout
+        VALUES
+            .save(&mut storage, ("a", 1), &"one".to_string())
+            .unwrap();
+        assert!(!LEGACY.has(&storage, ("a", 1)));
+        assert_eq!(VALUES.load(&storage, ("a", 1)).unwrap(), "one");
+
+        // Entries migrate in batches, keeping the ones already under the current layout
+        assert_eq!(VALUES.migrate(&mut storage, 2).unwrap(), 2);
+        assert!(!VALUES.is_migrated(&storage));
+        assert_eq!(VALUES.migrate(&mut storage, 10).unwrap(), 2);
+        assert!(VALUES.is_migrated(&storage));
+        assert_eq!(VALUES.migrate(&mut storage, 10).unwrap(), 0);
+        let values = VALUES
+            .current()
+            .prefix("a")
+            .range(&storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (0, "0".to_string()),
+                (1, "one".to_string()),
+                (2, "2".to_string()),
+                (3, "3".to_string()),
+                (4, "4".to_string()),
+            ]
+        );
+
+        VALUES.remove(&mut storage, ("a", 0));
+        assert!(!VALUES.has(&storage, ("a", 0)));
+    }
+}
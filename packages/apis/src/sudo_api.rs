@@ -0,0 +1,23 @@
+//! sudo_api defines the privileged calls of the consumer chain shared by all the contracts
+use cosmwasm_schema::cw_serde;
+
+/// `SudoMsg` are the per-block hooks the consumer chain calls the contracts with.
+/// Contracts with privileged calls of their own accept these along with them (see
+/// `finality_api::SudoMsg`)
+#[cw_serde]
+pub enum SudoMsg {
+    /// The SDK should call SudoMsg::BeginBlock{} once per block (in BeginBlock).
+    /// It allows the staking module to remove the voting power of the delegations whose unbonding
+    /// intent has matured, and the finality module to update the power distribution of the
+    /// active finality providers
+    BeginBlock {
+        hash_hex: String,
+        app_hash_hex: String,
+    },
+    /// The SDK should call SudoMsg::EndBlock{} once per block (in EndBlock).
+    /// It allows the finality module to index blocks and tally the finality provider votes
+    EndBlock {
+        hash_hex: String,
+        app_hash_hex: String,
+    },
+}
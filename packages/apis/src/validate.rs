@@ -1,10 +1,10 @@
 use babylon_bitcoin::{deserialize, Transaction};
 
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError};
 
 use crate::btc_staking_api::{
     ActiveBtcDelegation, FinalityProviderDescription, NewFinalityProvider, ProofOfPossessionBtc,
-    SlashedBtcDelegation, UnbondedBtcDelegation, HASH_SIZE,
+    SlashedBtcDelegation, TxHash, UnbondedBtcDelegation,
 };
 use crate::error::StakingApiError;
 
@@ -20,6 +20,12 @@ impl Validate for NewFinalityProvider {
             .map(FinalityProviderDescription::validate)
             .transpose()?;
 
+        if self.commission > Decimal::one() {
+            return Err(StakingApiError::InvalidCommission(
+                self.commission.to_string(),
+            ));
+        }
+
         if self.btc_pk_hex.is_empty() {
             return Err(StakingApiError::EmptyBtcPk);
         }
@@ -165,9 +171,7 @@ impl Validate for ActiveBtcDelegation {
 
 impl Validate for UnbondedBtcDelegation {
     fn validate(&self) -> Result<(), StakingApiError> {
-        if self.staking_tx_hash.len() != HASH_SIZE * 2 {
-            return Err(StakingApiError::InvalidStakingTxHash(HASH_SIZE * 2));
-        }
+        TxHash::from_hex(&self.staking_tx_hash)?;
 
         if self.unbonding_tx_sig.is_empty() {
             return Err(StakingApiError::EmptySignature);
@@ -181,9 +185,7 @@ impl Validate for UnbondedBtcDelegation {
 
 impl Validate for SlashedBtcDelegation {
     fn validate(&self) -> Result<(), StakingApiError> {
-        if self.staking_tx_hash.len() != HASH_SIZE * 2 {
-            return Err(StakingApiError::InvalidStakingTxHash(HASH_SIZE * 2));
-        }
+        TxHash::from_hex(&self.staking_tx_hash)?;
 
         // if self.recovered_fp_btc_sk.is_empty() {
         //     return Err(StakingApiError::EmptyBtcSk);
@@ -0,0 +1,168 @@
+//! zoneconcierge_api defines the acknowledgements of the zoneconcierge packets received by the
+//! babylon contract.
+//! Acknowledgements keep the standard `StdAck` envelope, so that they remain compatible with
+//! relayers. Success acknowledgements carry a JSON-encoded `ZoneconciergeAck` with a payload
+//! specific to the packet type, and error acknowledgements carry the error code of the failure
+//! (see `crate::error::format_error`), from the contract's stable error codes table
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{from_json, to_json_binary, StdAck, StdError, StdResult, Uint256};
+
+use crate::btc_staking_api::BtcStakingResult;
+use crate::error::{format_error, parse_error};
+
+/// ZoneconciergeAck is the acknowledgement of a zoneconcierge packet
+#[cw_serde]
+pub enum ZoneconciergeAck {
+    /// BtcTimestamp acknowledges a BTC timestamp packet
+    BtcTimestamp(BtcTimestampResponse),
+    /// BtcStaking acknowledges a BTC staking packet
+    BtcStaking(BtcStakingResponse),
+    /// Error acknowledges a packet that failed processing.
+    /// `code` is the error code of the failure, from the contract's error codes table
+    Error { code: u32, message: String },
+}
+
+/// BtcTimestampResponse is the success acknowledgement of a BTC timestamp packet.
+///
+/// It carries the BTC light client tip after processing the timestamp, so that Babylon can
+/// detect a divergence of the consumer's BTC light client without further queries
+#[cw_serde]
+pub struct BtcTimestampResponse {
+    /// `btc_tip_height` is the height of the BTC tip
+    pub btc_tip_height: u64,
+    /// `btc_tip_hash` is the hash of the BTC tip.
+    /// Encoded as a (byte-reversed) hex string.
+    pub btc_tip_hash: String,
+    /// `btc_tip_work` is the cumulative total work of the BTC tip
+    pub btc_tip_work: Uint256,
+}
+
+/// BtcStakingResponse is the success acknowledgement of a BTC staking packet.
+///
+/// It summarises the operations of the packet accepted by the btc-staking contracts, so that
+/// Babylon can reconcile what the consumer accepted against what it sent.
+/// The operations routed to a btc-staking contract are accepted or rejected together
+#[cw_serde]
+#[derive(Default)]
+pub struct BtcStakingResponse {
+    pub fps_added: u64,
+    pub dels_activated: u64,
+    pub dels_slashed: u64,
+    pub dels_unbonded: u64,
+    /// rejected are the operations rejected by the btc-staking contracts, with the reasons
+    pub rejected: Vec<RejectedBtcStaking>,
+    /// quarantined are the ids of the new finality providers and active delegations that
+    /// failed validation, and were quarantined by the btc-staking contracts
+    #[serde(default)]
+    pub quarantined: Vec<String>,
+}
+
+/// RejectedBtcStaking are the operations of a BTC staking packet rejected by a btc-staking
+/// contract
+#[cw_serde]
+pub struct RejectedBtcStaking {
+    /// contract is the address of the btc-staking contract
+    pub contract: String,
+    /// fps are the BTC public keys (in hex format) of the rejected new finality providers
+    pub fps: Vec<String>,
+    /// dels are the staking tx hashes (in hex format) of the rejected delegation operations
+    pub dels: Vec<String>,
+    /// reason is the error of the btc-staking contract
+    pub reason: String,
+}
+
+impl BtcStakingResponse {
+    /// add_accepted accounts for the operations accepted by a btc-staking contract
+    pub fn add_accepted(&mut self, result: &BtcStakingResult) {
+        self.fps_added += result.new_fps.len() as u64;
+        self.dels_activated += result.active_delegations.len() as u64;
+        self.dels_slashed += result.slashed_delegations.len() as u64;
+        self.dels_unbonded += result.unbonded_delegations.len() as u64;
+        self.quarantined.extend(result.quarantined.iter().cloned());
+    }
+}
+
+impl ZoneconciergeAck {
+    pub fn error(code: u32, message: impl Into<String>) -> Self {
+        ZoneconciergeAck::Error {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        !matches!(self, ZoneconciergeAck::Error { .. })
+    }
+
+    /// to_std_ack encodes the acknowledgement into its `StdAck` envelope
+    pub fn to_std_ack(&self) -> StdResult<StdAck> {
+        match self {
+            ZoneconciergeAck::Error { code, message } => Ok(error_ack(*code, message)),
+            _ => Ok(StdAck::success(to_json_binary(self)?)),
+        }
+    }
+
+    /// from_std_ack decodes the acknowledgement from its `StdAck` envelope.
+    /// It fails on error acknowledgements without an error code, i.e. not issued by the contract
+    pub fn from_std_ack(ack: &StdAck) -> StdResult<Self> {
+        match ack {
+            StdAck::Success(data) => from_json(data),
+            StdAck::Error(err) => {
+                let (code, message) = parse_error(err).ok_or_else(|| {
+                    StdError::generic_err(format!("error acknowledgement without code: {err}"))
+                })?;
+                Ok(ZoneconciergeAck::error(code, message))
+            }
+        }
+    }
+}
+
+/// error_ack returns the `StdAck` envelope of the error acknowledgement with the given code and
+/// message. Unlike `ZoneconciergeAck::to_std_ack`, it cannot fail
+pub fn error_ack(code: u32, message: impl std::fmt::Display) -> StdAck {
+    StdAck::error(format_error(code, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoneconcierge_ack_encoding_works() {
+        let acks = [
+            ZoneconciergeAck::BtcTimestamp(BtcTimestampResponse {
+                btc_tip_height: 10,
+                btc_tip_hash: "00".repeat(32),
+                btc_tip_work: Uint256::from(100u32),
+            }),
+            ZoneconciergeAck::BtcStaking(BtcStakingResponse {
+                fps_added: 1,
+                dels_activated: 2,
+                dels_slashed: 0,
+                dels_unbonded: 3,
+                rejected: vec![RejectedBtcStaking {
+                    contract: "btc_staking".to_string(),
+                    fps: vec![],
+                    dels: vec!["00".repeat(32)],
+                    reason: "code 801: invalid delegation".to_string(),
+                }],
+                quarantined: vec!["11".repeat(32)],
+            }),
+            ZoneconciergeAck::error(304, "invalid packet: unsupported method"),
+        ];
+        for ack in acks {
+            let std_ack = ack.to_std_ack().unwrap();
+            assert_eq!(std_ack.is_success(), ack.is_success());
+            assert_eq!(ZoneconciergeAck::from_std_ack(&std_ack).unwrap(), ack);
+        }
+        assert_eq!(
+            ZoneconciergeAck::error(401, "BTC staking contract address is not set")
+                .to_std_ack()
+                .unwrap(),
+            StdAck::error("code 401: BTC staking contract address is not set")
+        );
+
+        ZoneconciergeAck::from_std_ack(&StdAck::error("no code")).unwrap_err();
+        ZoneconciergeAck::from_std_ack(&StdAck::success(b"\x01")).unwrap_err();
+    }
+}
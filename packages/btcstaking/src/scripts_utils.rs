@@ -169,6 +169,12 @@ pub fn build_relative_time_lock_pk_script(pk: &VerifyingKey, lock_time: u16) ->
     // NOTE: avoid using TaprootBuilder as this bloats the binary size
     let merkle_root = TapNodeHash::from_script(&script, LeafVersion::TapScript);
 
+    Ok(build_taproot_pk_script(merkle_root))
+}
+
+/// build_taproot_pk_script builds the taproot output script committing to the given script tree,
+/// with an unspendable key path
+fn build_taproot_pk_script(merkle_root: TapNodeHash) -> ScriptBuf {
     // compute the tweaked key in bytes
     let tweaked_key_bytes = compute_tweaked_key_bytes(merkle_root);
     // construct the Taproot output script
@@ -176,8 +182,7 @@ pub fn build_relative_time_lock_pk_script(pk: &VerifyingKey, lock_time: u16) ->
     builder = builder
         .push_opcode(OP_PUSHNUM_1)
         .push_slice(tweaked_key_bytes);
-    let taproot_pk_script = builder.into_script();
-    Ok(taproot_pk_script)
+    builder.into_script()
 }
 
 fn aggregate_scripts(scripts: &[ScriptBuf]) -> ScriptBuf {
@@ -237,7 +242,23 @@ impl BabylonScriptPaths {
         })
     }
 
-    // TODO: implement a function for aggregating all scripts to a single ScriptBuf
+    /// merkle_root returns the Merkle root of the taproot script tree of the paths, assembled as
+    /// Babylon does (with `txscript.AssembleTaprootScriptTree`) from the time-lock, unbonding and
+    /// slashing leaves, i.e. `branch(branch(time_lock, unbonding), slashing)`
+    pub fn merkle_root(&self) -> TapNodeHash {
+        let leaf = |script: &ScriptBuf| TapNodeHash::from_script(script, LeafVersion::TapScript);
+        let branch = TapNodeHash::from_node_hashes(
+            leaf(&self.time_lock_path_script),
+            leaf(&self.unbonding_path_script),
+        );
+        TapNodeHash::from_node_hashes(branch, leaf(&self.slashing_path_script))
+    }
+
+    /// pk_script returns the taproot output script committing to the paths, i.e. the script of
+    /// the staking output (or of the unbonding output, with the unbonding time as lock time)
+    pub fn pk_script(&self) -> ScriptBuf {
+        build_taproot_pk_script(self.merkle_root())
+    }
 }
 
 #[cfg(test)]
@@ -235,6 +235,51 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_staking_output_pk_script() {
+        let btc_del = get_btc_delegation(1, vec![1]);
+        let params = get_params();
+
+        let staking_tx: Transaction = deserialize(&btc_del.staking_tx).unwrap();
+        let staker_pk: VerifyingKey = VerifyingKey::from_bytes(&btc_del.btc_pk).unwrap();
+        let fp_pks: Vec<VerifyingKey> = btc_del
+            .fp_btc_pk_list
+            .iter()
+            .map(|bytes| VerifyingKey::from_bytes(bytes).expect("Invalid public key bytes"))
+            .collect();
+        let cov_pks: Vec<VerifyingKey> = params
+            .covenant_pks
+            .iter()
+            .map(|bytes| VerifyingKey::from_bytes(bytes).expect("Invalid public key bytes"))
+            .collect();
+        let staking_time = (btc_del.end_height - btc_del.start_height) as u16;
+
+        let babylon_script_paths = BabylonScriptPaths::new(
+            &staker_pk,
+            &fp_pks,
+            &cov_pks,
+            params.covenant_quorum as usize,
+            staking_time,
+        )
+        .unwrap();
+
+        // The staking output commits to the script paths
+        let staking_out = &staking_tx.output[btc_del.staking_output_idx as usize];
+        assert_eq!(babylon_script_paths.pk_script(), staking_out.script_pubkey);
+        assert_eq!(staking_out.value.to_sat(), btc_del.total_sat);
+
+        // And to the staking time
+        let babylon_script_paths = BabylonScriptPaths::new(
+            &staker_pk,
+            &fp_pks,
+            &cov_pks,
+            params.covenant_quorum as usize,
+            staking_time + 1,
+        )
+        .unwrap();
+        assert_ne!(babylon_script_paths.pk_script(), staking_out.script_pubkey);
+    }
+
     #[test]
     fn test_verify_unbonding_tx_schnorr_sig() {
         let btc_del = get_btc_delegation(1, vec![1]);
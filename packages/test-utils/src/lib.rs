@@ -5,22 +5,24 @@ use prost::{bytes::Bytes, Message};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::str::FromStr;
 use std::{env, fs};
 
-use cosmwasm_std::{Binary, Decimal};
+use cosmwasm_std::Binary;
 
 use babylon_apis::btc_staking_api::{
     ActiveBtcDelegation, BtcUndelegationInfo, CovenantAdaptorSignatures,
     FinalityProviderDescription, NewFinalityProvider, ProofOfPossessionBtc,
 };
 use babylon_apis::finality_api::PubRandCommit;
+use babylon_apis::legacy_dec_from_str;
 use babylon_bitcoin::{deserialize, BlockHash, BlockHeader};
 use babylon_proto::babylon::btclightclient::v1::{BtcHeaderInfo, QueryMainChainResponse};
 use babylon_proto::babylon::btcstaking::v1::{BtcDelegation, FinalityProvider, Params};
 use babylon_proto::babylon::finality::v1::{MsgAddFinalitySig, MsgCommitPubRandList};
 use babylon_proto::babylon::zoneconcierge::v1::BtcTimestamp;
 
+pub mod scenario;
+
 const BTC_LC_MAIN: &str = "btc_light_client.dat";
 const BTC_LC_FORK: &str = "btc_light_client_fork.dat";
 const BTC_LC_FORK_MSG: &str = "btc_light_client_fork_msg.json";
@@ -244,7 +246,7 @@ pub fn new_finality_provider(fp: FinalityProvider) -> NewFinalityProvider {
             security_contact: desc.security_contact,
             details: desc.details,
         }),
-        commission: Decimal::from_str(&fp.commission).unwrap(),
+        commission: legacy_dec_from_str(&fp.commission).unwrap(),
         btc_pk_hex: fp.btc_pk.encode_hex(),
         pop: match fp.pop {
             Some(pop) => Some(ProofOfPossessionBtc {
@@ -0,0 +1,76 @@
+//! Finalization scenarios.
+//!
+//! A scenario describes a sequence of protocol events (BTC headers arriving, Babylon epochs
+//! finalising, finality providers committing randomness, votes arriving, blocks being produced)
+//! together with expectations on the resulting state. Scenarios are plain JSON files under
+//! `testdata/scenarios/`, so that they double as protocol documentation, and they are executed
+//! against the real contracts by the multi-test scenario runner of the `btc-finality` contract.
+//!
+//! Test data is referenced by index, e.g. `"fps": [1]` refers to `finality_provider_1.dat`.
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::find_testdata_path;
+
+const SCENARIOS_DIR: &str = "scenarios";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// name is the name of the scenario
+    pub name: String,
+    /// description is a human-readable description of what the scenario exercises
+    #[serde(default)]
+    pub description: String,
+    /// height is the Consumer chain height at which the contracts are instantiated
+    pub height: u64,
+    /// steps are executed in order
+    pub steps: Vec<Step>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Step {
+    #[serde(flatten)]
+    pub action: Action,
+    /// expect_error is a substring of the error the step is expected to fail with.
+    /// If not set, the step is expected to succeed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Action {
+    /// BTC headers `start..end` of the BTC light client test data arrive from Babylon
+    BtcHeaders { start: usize, end: usize },
+    /// Babylon epoch `epoch` is finalised, BTC-finalising the Consumer header at `height`
+    FinalizeEpoch { epoch: u64, height: u64 },
+    /// The finality providers of the given test data indexes are registered
+    RegisterFinalityProviders { fps: Vec<i32> },
+    /// The BTC delegation of the given test data index, to the given finality providers, becomes
+    /// active
+    AddDelegation { del: i32, fps: Vec<i32> },
+    /// The finality provider of the public randomness test data commits its public randomness
+    CommitPublicRandomness {},
+    /// A Consumer block is produced at `height`, i.e. the begin and end blockers are called.
+    /// Its app hash is the one signed by the finality signature test data
+    Block { height: u64 },
+    /// The finality signature of the given test data index (i.e.
+    /// `add_finality_sig_{sig}_msg.dat`) is submitted
+    SubmitFinalitySignature { sig: i32 },
+    /// The BTC light client tip is at `height`
+    ExpectBtcTip { height: u64 },
+    /// The finality provider of the given test data index has `power` voting power
+    ExpectPower { fp: i32, power: u64 },
+    /// The block at `height` is indexed, and its finalisation status is `finalized`
+    ExpectBlock { height: u64, finalized: bool },
+}
+
+/// Loads the scenario `testdata/scenarios/{name}.json`
+pub fn get_scenario(name: &str) -> Scenario {
+    let file_path = find_testdata_path()
+        .join(SCENARIOS_DIR)
+        .join(format!("{name}.json"));
+    let testdata: &[u8] = &fs::read(file_path).unwrap();
+    serde_json::from_slice(testdata).unwrap()
+}